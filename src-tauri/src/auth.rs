@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tauri::command;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Manager, State};
 use anyhow::Result;
 use oauth2::{
     AuthUrl, ClientId, CsrfToken, PkceCodeChallenge, RedirectUrl, Scope,
@@ -9,15 +12,19 @@ use oauth2::{
 use oauth2::basic::BasicClient;
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
-use crate::storage::StorageManager;
-use tokio::sync::oneshot;
+use tokio::net::TcpListener;
+use tokio::sync::{oneshot, watch};
 use warp::Filter;
 
+use crate::storage::StorageState;
+
 // Microsoft OAuth2 endpoints
 const MICROSOFT_AUTH_URL: &str = "https://login.microsoftonline.com/consumers/oauth2/v2.0/authorize";
 const MICROSOFT_TOKEN_URL: &str = "https://login.microsoftonline.com/consumers/oauth2/v2.0/token";
+const MICROSOFT_DEVICE_CODE_URL: &str = "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode";
 const MINECRAFT_AUTH_URL: &str = "https://api.minecraftservices.com/authentication/login_with_xbox";
 const MINECRAFT_PROFILE_URL: &str = "https://api.minecraftservices.com/minecraft/profile";
+const MINECRAFT_SKINS_URL: &str = "https://api.minecraftservices.com/minecraft/profile/skins";
 const XBOX_LIVE_AUTH_URL: &str = "https://user.auth.xboxlive.com/user/authenticate";
 const XBOX_XSTS_AUTH_URL: &str = "https://xsts.auth.xboxlive.com/xsts/authorize";
 
@@ -34,6 +41,134 @@ const XBOX_XSTS_AUTH_URL: &str = "https://xsts.auth.xboxlive.com/xsts/authorize"
 pub const CLIENT_ID: &str = "cbd5ce66-bb68-4a36-bb3a-6c489107e8e5"; // Replace with your Azure app client ID
 const REDIRECT_URI: &str = "http://localhost:7931/auth/callback";
 
+/// Ports tried for the sign-in callback server, Microsoft accepts any port on a localhost redirect
+const CALLBACK_PORTS: RangeInclusive<u16> = 7931..=7940;
+
+/// Time the callback server gets to finish the browser's response before it is stopped
+const CALLBACK_SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Added to the polling interval each time the token endpoint answers `slow_down`
+const DEVICE_CODE_SLOW_DOWN: Duration = Duration::from_secs(5);
+
+/// How often stored tokens are checked for expiry in the background
+const TOKEN_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Tokens expiring within this are refreshed by the background check
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(30 * 60);
+
+/// Held while a token is refreshed, so a launch and the background check never spend one refresh token twice
+static REFRESH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Progress of `start_oauth_with_server`, sent with `oauth_flow_state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthFlowState {
+    WaitingForBrowser,
+    CodeReceived,
+    Exchanging,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OAuthFlowEvent {
+    pub state: OAuthFlowState,
+    pub message: Option<String>,
+}
+
+/// Error from the browser sign-in, sent to the UI tagged by `kind`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OAuthError {
+    /// The browser never came back within `oauth_timeout_secs`
+    Timeout { seconds: u64, message: String },
+    /// Every port in the callback range is taken by another program
+    PortsInUse { message: String },
+    Failed { message: String },
+}
+
+impl OAuthError {
+    pub fn message(&self) -> &str {
+        match self {
+            OAuthError::Timeout { message, .. }
+            | OAuthError::PortsInUse { message }
+            | OAuthError::Failed { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl From<String> for OAuthError {
+    fn from(message: String) -> Self {
+        OAuthError::Failed { message }
+    }
+}
+
+/// Code the player enters at `verification_uri` on any device, from `start_device_code_auth`
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceCode {
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Seconds until the code stops working
+    pub expires_in: u64,
+}
+
+/// Progress of the device code sign-in, sent with `auth_progress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceAuthState {
+    WaitingForUser,
+    Authenticating,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthProgressEvent {
+    pub state: DeviceAuthState,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+/// A device code waiting for the player, only the newest one is polled
+#[derive(Debug, Clone)]
+struct DeviceCodeSession {
+    device_code: String,
+    code: DeviceCode,
+    interval: Duration,
+    expires_at: SystemTime,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// What to do after the token endpoint answered a device code poll with an error
+#[derive(Debug, PartialEq)]
+enum DevicePoll {
+    Pending,
+    SlowDown,
+    Expired,
+    Fail(String),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MinecraftAccount {
     pub id: String,
@@ -44,6 +179,9 @@ pub struct MinecraftAccount {
     pub expires_at: u64,
     pub skin_url: Option<String>,
     pub cape_url: Option<String>,
+    /// The token couldn't be refreshed, signing in again clears it
+    #[serde(default)]
+    pub needs_relogin: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -158,6 +296,7 @@ struct MinecraftCape {
 use lazy_static::lazy_static;
 lazy_static! {
     static ref OAUTH_SESSIONS: Mutex<HashMap<String, OAuthSession>> = Mutex::new(HashMap::new());
+    static ref DEVICE_CODE_SESSION: Mutex<Option<DeviceCodeSession>> = Mutex::new(None);
 }
 
 #[command]
@@ -201,15 +340,39 @@ pub async fn start_microsoft_oauth() -> Result<String, String> {
     Ok(auth_url.to_string())
 }
 
+/// Sign in through the browser with a temporary local server receiving the redirect
+///
+/// Emits `oauth_flow_state` as the flow progresses. The server is stopped whether
+/// the flow succeeds, fails or times out.
 #[command]
-pub async fn start_oauth_with_server() -> Result<MinecraftAccount, String> {
+pub async fn start_oauth_with_server(
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<MinecraftAccount, OAuthError> {
+    let timeout = Duration::from_secs(storage.read().await.get_settings().oauth_timeout_secs.max(1));
+
+    let result = run_oauth_with_server(&app_handle, timeout).await;
+    match &result {
+        Ok(_) => emit_flow_state(&app_handle, OAuthFlowState::Done, None),
+        Err(e) => {
+            println!("❌ Microsoft sign-in failed: {}", e);
+            emit_flow_state(&app_handle, OAuthFlowState::Failed, Some(e.to_string()));
+        }
+    }
+    result
+}
+
+async fn run_oauth_with_server(app_handle: &AppHandle, timeout: Duration) -> Result<MinecraftAccount, OAuthError> {
+    let server = CallbackServer::start(CALLBACK_PORTS).await?;
+    let redirect_uri = server.redirect_uri.clone();
+
     let client = BasicClient::new(
         ClientId::new(CLIENT_ID.to_string()),
         None, // ClientSecret
         AuthUrl::new(MICROSOFT_AUTH_URL.to_string()).unwrap(),
         Some(TokenUrl::new(MICROSOFT_TOKEN_URL.to_string()).unwrap())
     )
-    .set_redirect_uri(RedirectUrl::new(REDIRECT_URI.to_string()).unwrap());
+    .set_redirect_uri(RedirectUrl::new(redirect_uri.clone()).unwrap());
 
     // Generate PKCE challenge
     let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
@@ -226,28 +389,179 @@ pub async fn start_oauth_with_server() -> Result<MinecraftAccount, String> {
     // This prevents the issue where users get stuck with the wrong MS account
     auth_url.query_pairs_mut().append_pair("prompt", "select_account");
 
-    // Start local server
-    let server_future = start_callback_server();
-    
     // Open auth URL in browser
+    emit_flow_state(app_handle, OAuthFlowState::WaitingForBrowser, None);
     let _ = open::that(auth_url.to_string());
-    
-    // Wait for callback
-    let (code, state) = server_future.await
-        .map_err(|e| format!("Failed to get callback: {}", e))?;
+
+    // Wait for callback, the server stops as soon as it has answered
+    let (code, state) = server.wait(timeout).await?;
+    emit_flow_state(app_handle, OAuthFlowState::CodeReceived, None);
 
     // Verify CSRF token
     if csrf_token.secret() != &state {
-        return Err("Invalid CSRF token".to_string());
+        return Err("Invalid CSRF token".to_string().into());
     }
 
     // Exchange authorization code for tokens
-    let microsoft_token = exchange_code_for_token(&code, pkce_verifier.secret())
+    emit_flow_state(app_handle, OAuthFlowState::Exchanging, None);
+    let microsoft_token = exchange_code_for_token(&code, pkce_verifier.secret(), &redirect_uri)
         .await
         .map_err(|e| format!("Failed to exchange code: {}", e))?;
 
     // Complete authentication flow
-    complete_authentication_flow(microsoft_token).await
+    Ok(complete_authentication_flow(microsoft_token).await?)
+}
+
+fn emit_flow_state(app_handle: &AppHandle, state: OAuthFlowState, message: Option<String>) {
+    let _ = app_handle.emit("oauth_flow_state", OAuthFlowEvent { state, message });
+}
+
+/// Sign in by entering a code on any device, for when the local callback server can't work
+///
+/// Replaces a device code that is still pending. Finish with `poll_device_code_auth`.
+#[command]
+pub async fn start_device_code_auth() -> Result<DeviceCode, String> {
+    let response = crate::http::client()
+        .post(MICROSOFT_DEVICE_CODE_URL)
+        .form(&[("client_id", CLIENT_ID), ("scope", "XboxLive.signin offline_access")])
+        .send()
+        .await
+        .map_err(|e| format!("Failed to request a device code: {}", e))?;
+    if !response.status().is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("Failed to request a device code: {}", text));
+    }
+    let response: DeviceCodeResponse = response.json().await
+        .map_err(|e| format!("Invalid device code response: {}", e))?;
+
+    let code = DeviceCode {
+        user_code: response.user_code,
+        verification_uri: response.verification_uri,
+        expires_in: response.expires_in,
+    };
+    *DEVICE_CODE_SESSION.lock().unwrap() = Some(DeviceCodeSession {
+        device_code: response.device_code,
+        code: code.clone(),
+        interval: Duration::from_secs(response.interval.max(1)),
+        expires_at: SystemTime::now() + Duration::from_secs(response.expires_in),
+    });
+
+    println!("🔑 Device code {} issued, enter it at {}", code.user_code, code.verification_uri);
+    Ok(code)
+}
+
+/// Wait for the player to enter the device code, then sign in and store the account
+///
+/// Emits `auth_progress` while waiting. Gives up with a timeout once the code expires.
+#[command]
+pub async fn poll_device_code_auth(app_handle: AppHandle) -> Result<MinecraftAccount, OAuthError> {
+    let session = DEVICE_CODE_SESSION.lock().unwrap().clone()
+        .ok_or_else(|| OAuthError::from("No device code sign-in was started".to_string()))?;
+    let emit = |state, message: Option<String>| {
+        let _ = app_handle.emit("auth_progress", AuthProgressEvent {
+            state,
+            user_code: session.code.user_code.clone(),
+            verification_uri: session.code.verification_uri.clone(),
+            message,
+        });
+    };
+
+    emit(DeviceAuthState::WaitingForUser, None);
+    let result = match poll_device_code(&session).await {
+        Ok(microsoft_token) => {
+            emit(DeviceAuthState::Authenticating, None);
+            complete_authentication_flow(microsoft_token).await.map_err(OAuthError::from)
+        }
+        Err(e) => Err(e),
+    };
+
+    let mut pending = DEVICE_CODE_SESSION.lock().unwrap();
+    if pending.as_ref().is_some_and(|pending| pending.device_code == session.device_code) {
+        *pending = None;
+    }
+    drop(pending);
+
+    match &result {
+        Ok(account) => {
+            println!("✅ Signed in as {} with a device code", account.username);
+            emit(DeviceAuthState::Done, None);
+        }
+        Err(e) => {
+            println!("❌ Device code sign-in failed: {}", e);
+            emit(DeviceAuthState::Failed, Some(e.to_string()));
+        }
+    }
+    result
+}
+
+/// Poll the token endpoint at the interval Microsoft asks for until the code is used or expires
+async fn poll_device_code(session: &DeviceCodeSession) -> Result<MicrosoftTokenResponse, OAuthError> {
+    let client = crate::http::client();
+    let mut interval = session.interval;
+    loop {
+        let remaining = session.expires_at.duration_since(SystemTime::now()).unwrap_or_default();
+        if remaining.is_zero() {
+            return Err(device_code_expired(session));
+        }
+        tokio::time::sleep(interval.min(remaining)).await;
+
+        // A newer start_device_code_auth replaced this code
+        let current = DEVICE_CODE_SESSION.lock().unwrap().as_ref().map(|s| s.device_code.clone());
+        if current.as_deref() != Some(session.device_code.as_str()) {
+            return Err("The sign-in code was replaced by a new one".to_string().into());
+        }
+
+        let response = client
+            .post(MICROSOFT_TOKEN_URL)
+            .form(&[
+                ("client_id", CLIENT_ID),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                ("device_code", session.device_code.as_str()),
+            ])
+            .send()
+            .await;
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                // A dropped connection is retried at the next interval
+                println!("⚠️  Device code poll failed: {}", e);
+                continue;
+            }
+        };
+        if response.status().is_success() {
+            return response.json().await
+                .map_err(|e| format!("Invalid token response: {}", e).into());
+        }
+
+        let error: TokenErrorResponse = response.json().await
+            .map_err(|e| format!("Invalid token error response: {}", e))?;
+        match device_poll_step(&error) {
+            DevicePoll::Pending => {}
+            DevicePoll::SlowDown => interval += DEVICE_CODE_SLOW_DOWN,
+            DevicePoll::Expired => return Err(device_code_expired(session)),
+            DevicePoll::Fail(message) => return Err(message.into()),
+        }
+    }
+}
+
+fn device_poll_step(error: &TokenErrorResponse) -> DevicePoll {
+    match error.error.as_str() {
+        "authorization_pending" => DevicePoll::Pending,
+        "slow_down" => DevicePoll::SlowDown,
+        "authorization_declined" => DevicePoll::Fail("Sign-in was declined".to_string()),
+        "expired_token" => DevicePoll::Expired,
+        other => DevicePoll::Fail(format!(
+            "Sign-in failed: {}",
+            error.error_description.as_deref().unwrap_or(other)
+        )),
+    }
+}
+
+fn device_code_expired(session: &DeviceCodeSession) -> OAuthError {
+    OAuthError::Timeout {
+        seconds: session.code.expires_in,
+        message: format!("The code {} expired before it was entered, start the sign-in again", session.code.user_code),
+    }
 }
 
 #[command]
@@ -270,7 +584,7 @@ pub async fn complete_microsoft_oauth(
     }
 
     // Exchange authorization code for tokens
-    let microsoft_token = exchange_code_for_token(&authorization_code, &session.pkce_verifier)
+    let microsoft_token = exchange_code_for_token(&authorization_code, &session.pkce_verifier, REDIRECT_URI)
         .await
         .map_err(|e| format!("Failed to exchange code: {}", e))?;
 
@@ -307,6 +621,7 @@ pub async fn complete_microsoft_oauth(
         cape_url: profile.capes.and_then(|capes| {
             capes.into_iter().find(|c| c.state == "ACTIVE").map(|c| c.url)
         }),
+        needs_relogin: false,
     };
 
     // Clean up session
@@ -322,26 +637,20 @@ pub async fn complete_microsoft_oauth(
 
 #[command]
 pub async fn get_stored_accounts() -> Result<Vec<MinecraftAccount>, String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-
     // Load accounts from storage
-    load_minecraft_accounts(&storage)
+    load_minecraft_accounts()
         .await
         .map_err(|e| format!("Failed to load accounts: {}", e))
 }
 
 #[command]
 pub async fn refresh_minecraft_token(account_id: String) -> Result<MinecraftAccount, String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-
-    let mut accounts = load_minecraft_accounts(&storage)
+    let accounts = load_minecraft_accounts()
         .await
         .map_err(|e| format!("Failed to load accounts: {}", e))?;
 
     let account = accounts
-        .iter_mut()
+        .iter()
         .find(|a| a.id == account_id)
         .ok_or("Account not found")?;
 
@@ -351,70 +660,317 @@ pub async fn refresh_minecraft_token(account_id: String) -> Result<MinecraftAcco
         return Ok(account.clone());
     }
 
-    // Refresh Microsoft token
-    let microsoft_token = refresh_microsoft_token(&account.refresh_token)
+    refresh_account_token(account)
         .await
-        .map_err(|e| format!("Failed to refresh Microsoft token: {}", e))?;
+        .map_err(|e| format!("Failed to refresh Microsoft token: {}", e))
+}
 
-    // Re-authenticate with Xbox Live and Minecraft
-    let xbox_token = authenticate_xbox_live(&microsoft_token.access_token)
+/// Remove a stored account, the next one becomes active if it was the active one
+#[command]
+pub async fn remove_minecraft_account(
+    account_id: String,
+    storage: State<'_, StorageState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let mut accounts = load_minecraft_accounts()
         .await
-        .map_err(|e| format!("Xbox Live auth failed: {}", e))?;
+        .map_err(|e| format!("Failed to load accounts: {}", e))?;
 
-    let (xsts_token, user_hash) = get_xsts_token(&xbox_token)
-        .await
-        .map_err(|e| format!("XSTS auth failed: {}", e))?;
+    let active = select_account(&accounts, storage.read().await.get_settings().active_account_uuid.as_deref())
+        .map(|account| account.id.clone());
+    let next_active = if active.as_deref() == Some(account_id.as_str()) {
+        Some(next_account_after(&accounts, &account_id).map(|account| account.uuid.clone()))
+    } else {
+        None
+    };
 
-    let minecraft_token = authenticate_minecraft(&xsts_token, &user_hash)
+    let removed: Vec<String> = accounts.iter()
+        .filter(|a| a.id == account_id)
+        .map(|a| a.uuid.clone())
+        .collect();
+    accounts.retain(|a| a.id != account_id);
+
+    save_minecraft_accounts(&accounts)
         .await
-        .map_err(|e| format!("Minecraft auth failed: {}", e))?;
+        .map_err(|e| format!("Failed to save accounts: {}", e))?;
+    for uuid in removed {
+        if let Err(e) = crate::secrets::delete(&crate::secrets::account_key(&uuid)).await {
+            println!("⚠️  Failed to delete the stored tokens of {}: {}", uuid, e);
+        }
+    }
 
-    // Update account
-    account.access_token = minecraft_token.access_token;
-    account.refresh_token = microsoft_token.refresh_token;
-    account.expires_at = current_timestamp() + minecraft_token.expires_in;
+    if let Some(next_active) = next_active {
+        match &next_active {
+            Some(uuid) => println!("👤 Removed the active account, switching to {}", uuid),
+            None => println!("👤 Removed the last account"),
+        }
+        set_active_uuid(&storage, next_active).await?;
+    }
+    emit_accounts_changed(&app_handle, &storage).await;
 
-    // Clone account before saving to avoid borrow issues
-    let updated_account = account.clone();
+    Ok(())
+}
 
-    // Save updated accounts
-    save_minecraft_accounts(&storage, &accounts)
+/// The account launches use, None when no account is stored
+#[command]
+pub async fn get_active_account(storage: State<'_, StorageState>) -> Result<Option<MinecraftAccount>, String> {
+    let accounts = load_minecraft_accounts()
         .await
-        .map_err(|e| format!("Failed to save accounts: {}", e))?;
+        .map_err(|e| format!("Failed to load accounts: {}", e))?;
+    let active_uuid = storage.read().await.get_settings().active_account_uuid.clone();
+    Ok(select_account(&accounts, active_uuid.as_deref()).cloned())
+}
+
+/// Make a stored account the one launches use
+#[command]
+pub async fn set_active_account(
+    uuid: String,
+    storage: State<'_, StorageState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let accounts = load_minecraft_accounts()
+        .await
+        .map_err(|e| format!("Failed to load accounts: {}", e))?;
+    let account = accounts.iter()
+        .find(|account| same_uuid(&account.uuid, &uuid))
+        .ok_or_else(|| format!("No stored account with UUID {}", uuid))?;
 
-    Ok(updated_account)
+    println!("👤 Active account is now {} ({})", account.username, account.uuid);
+    set_active_uuid(&storage, Some(account.uuid.clone())).await?;
+    emit_accounts_changed(&app_handle, &storage).await;
+    Ok(())
 }
 
+/// Skin and head avatar of a player, cached for a day unless `refresh` is set
+///
+/// Never fails: unknown players and network errors give the default Steve or Alex.
 #[command]
-pub async fn remove_minecraft_account(account_id: String) -> Result<(), String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+pub async fn get_player_skin(uuid: String, refresh: Option<bool>) -> Result<crate::skins::PlayerSkin, String> {
+    Ok(crate::skins::player_skin(&uuid, refresh.unwrap_or(false)).await)
+}
 
-    let mut accounts = load_minecraft_accounts(&storage)
+/// Change an account's skin to a 64x64 or 64x32 PNG, returning the new skin
+#[command]
+pub async fn upload_skin(
+    account_uuid: String,
+    file_path: String,
+    variant: crate::skins::SkinModel,
+    storage: State<'_, StorageState>,
+    app_handle: AppHandle,
+) -> Result<crate::skins::PlayerSkin, String> {
+    let path = std::path::PathBuf::from(&file_path);
+    let png = tokio::fs::read(&path)
         .await
-        .map_err(|e| format!("Failed to load accounts: {}", e))?;
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    crate::skins::validate_skin(&png)?;
+
+    let file_name = path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "skin.png".to_string());
+    let variant_name = match variant {
+        crate::skins::SkinModel::Classic => "classic",
+        crate::skins::SkinModel::Slim => "slim",
+    };
 
-    accounts.retain(|a| a.id != account_id);
+    let profile = profile_request(&account_uuid, |client, token| {
+        let file = reqwest::multipart::Part::bytes(png.clone())
+            .file_name(file_name.clone())
+            .mime_str("image/png")
+            .expect("image/png is a valid MIME type");
+        let form = reqwest::multipart::Form::new()
+            .text("variant", variant_name)
+            .part("file", file);
+        client.post(MINECRAFT_SKINS_URL).bearer_auth(token).multipart(form)
+    }).await?;
+
+    println!("👕 Uploaded a new {} skin for {}", variant_name, profile.name);
+    Ok(apply_profile_skin(&account_uuid, profile, &storage, &app_handle).await)
+}
 
-    save_minecraft_accounts(&storage, &accounts)
+/// Put an account back on its default skin, returning it
+#[command]
+pub async fn reset_skin(
+    account_uuid: String,
+    storage: State<'_, StorageState>,
+    app_handle: AppHandle,
+) -> Result<crate::skins::PlayerSkin, String> {
+    let profile = profile_request(&account_uuid, |client, token| {
+        client.delete(format!("{}/active", MINECRAFT_SKINS_URL)).bearer_auth(token)
+    }).await?;
+
+    println!("👕 Reset the skin of {}", profile.name);
+    Ok(apply_profile_skin(&account_uuid, profile, &storage, &app_handle).await)
+}
+
+/// Send a profile change as a stored account, refreshing its token once on a 401
+async fn profile_request<F>(account_uuid: &str, build: F) -> Result<MinecraftProfile, String>
+where
+    F: Fn(&reqwest::Client, &str) -> reqwest::RequestBuilder,
+{
+    let (account, token) = resolve_account(Some(account_uuid))
         .await
-        .map_err(|e| format!("Failed to save accounts: {}", e))?;
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No stored account with UUID {}", account_uuid))?;
+    let mut token = token.ok_or("The account's sign-in has expired, sign in again")?;
+    let client = crate::http::client();
+    let mut refreshed = false;
+
+    loop {
+        let response = build(&client, &token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Minecraft services: {}", e))?;
+        let status = response.status();
+
+        if status == reqwest::StatusCode::UNAUTHORIZED && !refreshed {
+            println!("⚠️  Minecraft services rejected the token of {}, refreshing it", account.username);
+            token = refresh_account_token(&account)
+                .await
+                .map_err(|e| format!("Failed to refresh the account's sign-in: {}", e))?
+                .access_token;
+            refreshed = true;
+            continue;
+        }
+
+        let retry_after = response.headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(profile_error(status, retry_after, &body));
+        }
+        return serde_json::from_str(&body)
+            .map_err(|e| format!("Failed to parse the Minecraft profile: {}", e));
+    }
+}
+
+/// Readable message for a refused profile change
+fn profile_error(status: reqwest::StatusCode, retry_after: Option<u64>, body: &str) -> String {
+    match status {
+        reqwest::StatusCode::TOO_MANY_REQUESTS => match retry_after {
+            Some(seconds) => format!("Minecraft limits how often skins can change, try again in {}s", seconds),
+            None => "Minecraft limits how often skins can change, try again in a minute".to_string(),
+        },
+        reqwest::StatusCode::UNAUTHORIZED => "Minecraft rejected the account's sign-in, sign in again".to_string(),
+        _ => {
+            let message = serde_json::from_str::<serde_json::Value>(body)
+                .ok()
+                .and_then(|json| {
+                    json.get("errorMessage")
+                        .or_else(|| json.get("error"))
+                        .and_then(|message| message.as_str())
+                        .map(String::from)
+                });
+            match message {
+                Some(message) => format!("Minecraft services refused the change ({}): {}", status, message),
+                None => format!("Minecraft services refused the change ({})", status),
+            }
+        }
+    }
+}
+
+/// Cache the skin a changed profile reports and store its URL on the account
+async fn apply_profile_skin(
+    account_uuid: &str,
+    profile: MinecraftProfile,
+    storage: &StorageState,
+    app_handle: &AppHandle,
+) -> crate::skins::PlayerSkin {
+    let active = profile.skins
+        .into_iter()
+        .flatten()
+        .find(|skin| skin.state == "ACTIVE");
+    match &active {
+        Some(skin) => {
+            let model = if skin.variant.eq_ignore_ascii_case("slim") {
+                crate::skins::SkinModel::Slim
+            } else {
+                crate::skins::SkinModel::Classic
+            };
+            crate::skins::cache_active_skin(account_uuid, &skin.url, model).await;
+        }
+        None => crate::skins::forget_skin(account_uuid).await,
+    }
+
+    match load_minecraft_accounts().await {
+        Ok(mut accounts) => {
+            if let Some(account) = accounts.iter_mut().find(|account| same_uuid(&account.uuid, account_uuid)) {
+                account.skin_url = active.map(|skin| skin.url);
+                if let Err(e) = save_minecraft_accounts(&accounts).await {
+                    println!("⚠️  Failed to store the new skin URL: {}", e);
+                }
+            }
+        }
+        Err(e) => println!("⚠️  Failed to load accounts: {}", e),
+    }
+    emit_accounts_changed(app_handle, storage).await;
+
+    crate::skins::player_skin(account_uuid, false).await
+}
+
+/// Account launches use without an override: the active one, else the first stored
+pub fn select_account<'a>(accounts: &'a [MinecraftAccount], active_uuid: Option<&str>) -> Option<&'a MinecraftAccount> {
+    active_uuid
+        .and_then(|uuid| accounts.iter().find(|account| same_uuid(&account.uuid, uuid)))
+        .or_else(|| accounts.first())
+}
 
+/// Account after `account_id` in storage order, wrapping around, None if it is the only one
+fn next_account_after<'a>(accounts: &'a [MinecraftAccount], account_id: &str) -> Option<&'a MinecraftAccount> {
+    let index = accounts.iter().position(|account| account.id == account_id)?;
+    accounts.iter().cycle().skip(index + 1).take(accounts.len() - 1).next()
+}
+
+/// Check a name for offline play, 3 to 16 letters, digits or underscores like a real profile name
+pub fn validate_offline_username(name: &str) -> Result<(), String> {
+    if !(3..=16).contains(&name.len()) {
+        return Err(format!("Offline username '{}' must be 3 to 16 characters long", name));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Offline username '{}' may only contain letters, digits and underscores", name));
+    }
     Ok(())
 }
 
+/// UUID the game itself gives an offline player: `OfflinePlayer:<name>` hashed into a version 3 UUID
+pub fn offline_uuid(name: &str) -> String {
+    use md5::{Digest, Md5};
+    let hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{}", name)).into();
+    uuid::Builder::from_md5_bytes(hash).into_uuid().to_string()
+}
+
+/// Profile UUIDs come with and without dashes depending on the API
+fn same_uuid(a: &str, b: &str) -> bool {
+    a.replace('-', "").eq_ignore_ascii_case(&b.replace('-', ""))
+}
+
+async fn set_active_uuid(storage: &StorageState, uuid: Option<String>) -> Result<(), String> {
+    let mut storage = storage.write().await;
+    let mut settings = storage.get_settings().clone();
+    settings.active_account_uuid = uuid;
+    storage.update_settings(settings).await
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+async fn emit_accounts_changed(app_handle: &AppHandle, storage: &StorageState) {
+    let active_uuid = storage.read().await.get_settings().active_account_uuid.clone();
+    let _ = app_handle.emit("accounts_changed", serde_json::json!({ "active_account_uuid": active_uuid }));
+}
+
 // Helper functions
 
 async fn exchange_code_for_token(
     authorization_code: &str,
     pkce_verifier: &str,
+    redirect_uri: &str,
 ) -> Result<MicrosoftTokenResponse> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client();
     
     let params = [
         ("client_id", CLIENT_ID),
         ("code", authorization_code),
-        ("redirect_uri", REDIRECT_URI),
+        ("redirect_uri", redirect_uri),
         ("grant_type", "authorization_code"),
         ("code_verifier", pkce_verifier),
     ];
@@ -434,8 +990,36 @@ async fn exchange_code_for_token(
     Ok(token_response)
 }
 
+/// Step name of the Microsoft token refresh, the only refusal that ends a sign-in
+const TOKEN_REFRESH_STEP: &str = "Token refresh";
+
+/// A sign-in endpoint answered with an error status
+#[derive(Debug)]
+struct AuthStatusError {
+    step: &'static str,
+    status: reqwest::StatusCode,
+    body: String,
+}
+
+impl fmt::Display for AuthStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed ({}): {}", self.step, self.status, self.body)
+    }
+}
+
+impl std::error::Error for AuthStatusError {}
+
+async fn check_status(step: &'static str, response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(AuthStatusError { step, status, body }.into())
+}
+
 async fn refresh_microsoft_token(refresh_token: &str) -> Result<MicrosoftTokenResponse> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client();
     
     let params = [
         ("client_id", CLIENT_ID),
@@ -450,17 +1034,13 @@ async fn refresh_microsoft_token(refresh_token: &str) -> Result<MicrosoftTokenRe
         .send()
         .await?;
 
-    if !response.status().is_success() {
-        let text = response.text().await?;
-        return Err(anyhow::anyhow!("Token refresh failed: {}", text));
-    }
-
+    let response = check_status(TOKEN_REFRESH_STEP, response).await?;
     let token_response: MicrosoftTokenResponse = response.json().await?;
     Ok(token_response)
 }
 
 async fn authenticate_xbox_live(microsoft_token: &str) -> Result<String> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client();
 
     let auth_request = XboxLiveAuthRequest {
         properties: XboxLiveAuthProperties {
@@ -480,17 +1060,13 @@ async fn authenticate_xbox_live(microsoft_token: &str) -> Result<String> {
         .send()
         .await?;
 
-    if !response.status().is_success() {
-        let text = response.text().await?;
-        return Err(anyhow::anyhow!("Xbox Live auth failed: {}", text));
-    }
-
+    let response = check_status("Xbox Live auth", response).await?;
     let auth_response: XboxLiveAuthResponse = response.json().await?;
     Ok(auth_response.token)
 }
 
 async fn get_xsts_token(xbox_token: &str) -> Result<(String, String)> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client();
 
     let auth_request = XSTSAuthRequest {
         properties: XSTSAuthProperties {
@@ -509,11 +1085,7 @@ async fn get_xsts_token(xbox_token: &str) -> Result<(String, String)> {
         .send()
         .await?;
 
-    if !response.status().is_success() {
-        let text = response.text().await?;
-        return Err(anyhow::anyhow!("XSTS auth failed: {}", text));
-    }
-
+    let response = check_status("XSTS auth", response).await?;
     let auth_response: XboxLiveAuthResponse = response.json().await?;
     let user_hash = auth_response
         .display_claims
@@ -527,7 +1099,7 @@ async fn get_xsts_token(xbox_token: &str) -> Result<(String, String)> {
 }
 
 async fn authenticate_minecraft(xsts_token: &str, user_hash: &str) -> Result<MinecraftAuthResponse> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client();
 
     let auth_request = MinecraftAuthRequest {
         identity_token: format!("XBL3.0 x={};{}", user_hash, xsts_token),
@@ -541,17 +1113,13 @@ async fn authenticate_minecraft(xsts_token: &str, user_hash: &str) -> Result<Min
         .send()
         .await?;
 
-    if !response.status().is_success() {
-        let text = response.text().await?;
-        return Err(anyhow::anyhow!("Minecraft auth failed: {}", text));
-    }
-
+    let response = check_status("Minecraft auth", response).await?;
     let auth_response: MinecraftAuthResponse = response.json().await?;
     Ok(auth_response)
 }
 
 async fn get_minecraft_profile(minecraft_token: &str) -> Result<MinecraftProfile> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client();
 
     let response = client
         .get(MINECRAFT_PROFILE_URL)
@@ -569,8 +1137,7 @@ async fn get_minecraft_profile(minecraft_token: &str) -> Result<MinecraftProfile
 }
 
 async fn store_minecraft_account(account: &MinecraftAccount) -> Result<()> {
-    let storage = StorageManager::new().await?;
-    let mut accounts = load_minecraft_accounts(&storage).await.unwrap_or_default();
+    let mut accounts = load_minecraft_accounts().await.unwrap_or_default();
     
     // Remove existing account with same ID
     accounts.retain(|a| a.id != account.id);
@@ -579,10 +1146,21 @@ async fn store_minecraft_account(account: &MinecraftAccount) -> Result<()> {
     let account_to_save = account.clone();
     accounts.push(account_to_save);
     
-    save_minecraft_accounts(&storage, &accounts).await
+    save_minecraft_accounts(&accounts).await
 }
 
-async fn load_minecraft_accounts(_storage: &StorageManager) -> Result<Vec<MinecraftAccount>> {
+/// Tokens of one account, kept in the credential store rather than accounts.json
+#[derive(Debug, Serialize, Deserialize)]
+struct AccountTokens {
+    access_token: String,
+    refresh_token: String,
+}
+
+/// Stored accounts with their tokens filled in from the credential store
+///
+/// Tokens still in accounts.json from older versions are moved to the store and
+/// removed from the file on the way.
+async fn load_minecraft_accounts() -> Result<Vec<MinecraftAccount>> {
     let accounts_path = crate::storage::get_launcher_dir().join("accounts.json");
     
     if !accounts_path.exists() {
@@ -590,22 +1168,57 @@ async fn load_minecraft_accounts(_storage: &StorageManager) -> Result<Vec<Minecr
     }
     
     let content = tokio::fs::read_to_string(&accounts_path).await?;
-    let accounts: Vec<MinecraftAccount> = serde_json::from_str(&content)?;
+    let mut accounts: Vec<MinecraftAccount> = serde_json::from_str(&content)?;
+
+    if accounts.iter().any(|account| !account.refresh_token.is_empty()) {
+        match save_minecraft_accounts(&accounts).await {
+            Ok(()) => println!("🔐 Moved account tokens out of accounts.json"),
+            Err(e) => eprintln!("❌ Failed to move account tokens out of accounts.json: {}", e),
+        }
+        return Ok(accounts);
+    }
+
+    for account in &mut accounts {
+        match crate::secrets::get(&crate::secrets::account_key(&account.uuid)).await {
+            Ok(Some(stored)) => {
+                let tokens: AccountTokens = serde_json::from_str(&stored)?;
+                account.access_token = tokens.access_token;
+                account.refresh_token = tokens.refresh_token;
+            }
+            Ok(None) => println!("⚠️  No stored tokens for {}", account.username),
+            Err(e) => println!("⚠️  Failed to read the tokens of {}: {}", account.username, e),
+        }
+    }
     Ok(accounts)
 }
 
-async fn save_minecraft_accounts(
-    _storage: &StorageManager,
-    accounts: &[MinecraftAccount],
-) -> Result<()> {
+/// Save accounts with their tokens in the credential store and the rest in accounts.json
+async fn save_minecraft_accounts(accounts: &[MinecraftAccount]) -> Result<()> {
     let accounts_path = crate::storage::get_launcher_dir().join("accounts.json");
     
     // Ensure parent directory exists
     if let Some(parent) = accounts_path.parent() {
         tokio::fs::create_dir_all(parent).await?;
     }
+
+    let mut stored = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        // Tokens that failed to load stay as they are in the store
+        if !account.refresh_token.is_empty() {
+            let tokens = AccountTokens {
+                access_token: account.access_token.clone(),
+                refresh_token: account.refresh_token.clone(),
+            };
+            crate::secrets::set(&crate::secrets::account_key(&account.uuid), &serde_json::to_string(&tokens)?).await?;
+        }
+        stored.push(MinecraftAccount {
+            access_token: String::new(),
+            refresh_token: String::new(),
+            ..account.clone()
+        });
+    }
     
-    let content = serde_json::to_string_pretty(accounts)?;
+    let content = serde_json::to_string_pretty(&stored)?;
     tokio::fs::write(&accounts_path, content).await?;
     Ok(())
 }
@@ -617,41 +1230,90 @@ fn current_timestamp() -> u64 {
         .as_secs()
 }
 
-pub async fn get_active_account_token() -> Result<Option<String>> {
-    let storage = StorageManager::new().await?;
-    let accounts = load_minecraft_accounts(&storage).await?;
-    
-    if let Some(account) = accounts.first() {
-        let current_time = current_timestamp();
-        let time_until_expiry = account.expires_at.saturating_sub(current_time);
-        
-        println!("🔐 Microsoft account found: {} (expires in {}s)", account.username, time_until_expiry);
-        
-        // Check if token is still valid (at least 5 minutes remaining)
-        if account.expires_at > current_time + 300 {
-            println!("✅ Microsoft account token is valid");
-            Ok(Some(account.access_token.clone()))
-        } else {
-            println!("⚠️  Microsoft account token expired or expiring soon, attempting refresh...");
-            // Token expired, try to refresh
-            match refresh_account_token(account).await {
-                Ok(refreshed_account) => {
-                    println!("✅ Microsoft account token refreshed successfully");
-                    Ok(Some(refreshed_account.access_token))
-                }
-                Err(e) => {
-                    println!("❌ Failed to refresh Microsoft account token: {}", e);
-                    Ok(None)
+/// The account to launch with and its token, refreshed if it is about to expire
+///
+/// `account_uuid` picks a stored account for one launch and is an error when it isn't
+/// stored. Without it the active account is used. The token is None when it expired
+/// and couldn't be refreshed.
+pub async fn resolve_account(account_uuid: Option<&str>) -> Result<Option<(MinecraftAccount, Option<String>)>> {
+    let accounts = load_minecraft_accounts().await?;
+    let account = match account_uuid {
+        Some(uuid) => accounts.iter()
+            .find(|account| same_uuid(&account.uuid, uuid))
+            .ok_or_else(|| anyhow::anyhow!("No stored account with UUID {}", uuid))?,
+        None => {
+            let active_uuid = match crate::storage::shared().await {
+                Ok(storage) => storage.read().await.get_settings().active_account_uuid.clone(),
+                Err(_) => None,
+            };
+            match select_account(&accounts, active_uuid.as_deref()) {
+                Some(account) => account,
+                None => {
+                    println!("⚠️  No Microsoft account found in storage");
+                    return Ok(None);
                 }
             }
         }
+    };
+    let token = account_token(account).await;
+    Ok(Some((account.clone(), token)))
+}
+
+async fn account_token(account: &MinecraftAccount) -> Option<String> {
+    let current_time = current_timestamp();
+    let time_until_expiry = account.expires_at.saturating_sub(current_time);
+    
+    println!("🔐 Microsoft account found: {} (expires in {}s)", account.username, time_until_expiry);
+    
+    // Check if token is still valid (at least 5 minutes remaining)
+    if account.expires_at > current_time + 300 {
+        println!("✅ Microsoft account token is valid");
+        Some(account.access_token.clone())
     } else {
-        println!("⚠️  No Microsoft account found in storage");
-        Ok(None)
+        println!("⚠️  Microsoft account token expired or expiring soon, attempting refresh...");
+        // Token expired, try to refresh
+        match refresh_account_token(account).await {
+            Ok(refreshed_account) => {
+                println!("✅ Microsoft account token refreshed successfully");
+                Some(refreshed_account.access_token)
+            }
+            Err(e) => {
+                println!("❌ Failed to refresh Microsoft account token: {}", e);
+                None
+            }
+        }
     }
 }
 
+/// Refresh an account's token and store it
+///
+/// When Microsoft refuses the refresh the account is marked as needing a new
+/// sign-in. Network errors leave it alone, the next attempt may work.
 async fn refresh_account_token(account: &MinecraftAccount) -> Result<MinecraftAccount> {
+    let _refreshing = REFRESH_LOCK.lock().await;
+
+    // Another refresh may have stored a new token while this one waited
+    let stored = load_minecraft_accounts().await?
+        .into_iter()
+        .find(|stored| stored.id == account.id);
+    if let Some(stored) = stored {
+        if stored.refresh_token != account.refresh_token && stored.expires_at > current_timestamp() + 300 {
+            return Ok(stored);
+        }
+    }
+
+    match exchange_refresh_token(account).await {
+        Ok(refreshed) => Ok(refreshed),
+        Err(e) => {
+            if needs_new_sign_in(&e) {
+                mark_needs_relogin(&account.id).await;
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn exchange_refresh_token(account: &MinecraftAccount) -> Result<MinecraftAccount> {
     // Refresh Microsoft token
     let microsoft_token = refresh_microsoft_token(&account.refresh_token).await?;
 
@@ -669,63 +1331,232 @@ async fn refresh_account_token(account: &MinecraftAccount) -> Result<MinecraftAc
         expires_at: current_timestamp() + minecraft_token.expires_in,
         skin_url: account.skin_url.clone(),
         cape_url: account.cape_url.clone(),
+        needs_relogin: false,
     };
 
     // Update stored account
-    let storage = StorageManager::new().await?;
-    let mut accounts = load_minecraft_accounts(&storage).await?;
+    let mut accounts = load_minecraft_accounts().await?;
     if let Some(stored_account) = accounts.iter_mut().find(|a| a.id == account.id) {
         *stored_account = refreshed_account.clone();
-        save_minecraft_accounts(&storage, &accounts).await?;
+        save_minecraft_accounts(&accounts).await?;
         Ok(refreshed_account)
     } else {
         Err(anyhow::anyhow!("Account not found in storage"))
     }
 }
 
-async fn start_callback_server() -> Result<(String, String)> {
-    let (tx, rx) = oneshot::channel();
-    let tx = Arc::new(Mutex::new(Some(tx)));
+/// Whether Microsoft refused the refresh token itself, so only a new sign-in helps
+///
+/// Network errors, rate limits and server errors may clear up on the next attempt.
+fn needs_new_sign_in(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<AuthStatusError>().is_some_and(|e| {
+            e.step == TOKEN_REFRESH_STEP
+                && e.status.is_client_error()
+                && !matches!(e.status, reqwest::StatusCode::TOO_MANY_REQUESTS | reqwest::StatusCode::REQUEST_TIMEOUT)
+        })
+    })
+}
 
-    let callback = warp::path!("auth" / "callback")
-        .and(warp::query::<HashMap<String, String>>())
-        .map(move |params: HashMap<String, String>| {
-            let code = params.get("code").cloned().unwrap_or_default();
-            let state = params.get("state").cloned().unwrap_or_default();
-            
-            // Send the result through the channel
-            if let Some(sender) = tx.lock().unwrap().take() {
-                let _ = sender.send((code.clone(), state.clone()));
+async fn mark_needs_relogin(account_id: &str) {
+    let mut accounts = match load_minecraft_accounts().await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            println!("⚠️  Failed to load accounts: {}", e);
+            return;
+        }
+    };
+    let Some(account) = accounts.iter_mut().find(|account| account.id == account_id) else {
+        return;
+    };
+    println!("🔑 {} needs to sign in again", account.username);
+    account.needs_relogin = true;
+    if let Err(e) = save_minecraft_accounts(&accounts).await {
+        println!("⚠️  Failed to save accounts: {}", e);
+    }
+}
+
+/// Check stored tokens every `TOKEN_CHECK_INTERVAL`, starting now
+///
+/// Tokens expiring within `TOKEN_REFRESH_MARGIN` are refreshed ahead of time, so a
+/// launch after a day away doesn't wait on Microsoft. Each refresh is announced
+/// with `account_token_refreshed`. Accounts needing a new sign-in are skipped
+/// until the user signs in again or a launch manages to refresh them.
+pub fn start_token_refresh(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TOKEN_CHECK_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            refresh_expiring_tokens(&app_handle).await;
+        }
+    });
+}
+
+async fn refresh_expiring_tokens(app_handle: &AppHandle) {
+    let accounts = match load_minecraft_accounts().await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            println!("⚠️  Failed to load accounts for the token check: {}", e);
+            return;
+        }
+    };
+
+    let due = current_timestamp() + TOKEN_REFRESH_MARGIN.as_secs();
+    for account in accounts.iter().filter(|account| !account.needs_relogin && account.expires_at <= due) {
+        println!("🔄 Refreshing the token of {} before it expires", account.username);
+        match refresh_account_token(account).await {
+            Ok(refreshed) => {
+                println!("✅ Token of {} refreshed", refreshed.username);
+                let _ = app_handle.emit("account_token_refreshed", serde_json::json!({
+                    "account_id": refreshed.id,
+                    "uuid": refreshed.uuid,
+                    "expires_at": refreshed.expires_at,
+                }));
+            }
+            Err(e) => {
+                println!("❌ Failed to refresh the token of {}: {}", account.username, e);
+                let storage = app_handle.state::<StorageState>();
+                emit_accounts_changed(app_handle, &storage).await;
             }
+        }
+    }
+}
+
+/// Local server receiving the OAuth redirect
+///
+/// Dropping it stops the server, so a failed or abandoned sign-in never leaves
+/// the port bound.
+struct CallbackServer {
+    redirect_uri: String,
+    port: u16,
+    params: oneshot::Receiver<HashMap<String, String>>,
+    _shutdown: watch::Sender<bool>,
+}
+
+impl CallbackServer {
+    /// Bind the first free port in `ports` and start serving the callback route
+    async fn start(ports: RangeInclusive<u16>) -> Result<Self, OAuthError> {
+        let listener = bind_callback_listener(ports).await?;
+        let port = listener.local_addr()
+            .map_err(|e| format!("Failed to read callback server address: {}", e))?
+            .port();
+
+        let (tx, params) = oneshot::channel();
+        let tx = Arc::new(Mutex::new(Some(tx)));
+
+        let callback = warp::path!("auth" / "callback")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(move |params: HashMap<String, String>| {
+                let failed = params.contains_key("error");
+
+                // Send the result through the channel
+                if let Some(sender) = tx.lock().unwrap().take() {
+                    let _ = sender.send(params);
+                }
 
-            warp::reply::html(r#"
+                let (title, text) = if failed {
+                    ("Authentication Failed", "Sign-in did not complete. You can close this window and try again from ChaiLauncher.")
+                } else {
+                    ("Authentication Successful!", "You can now close this browser window and return to ChaiLauncher.")
+                };
+                warp::reply::html(format!(r#"
                 <html>
-                <head><title>Authentication Complete</title></head>
+                <head><title>{title}</title></head>
                 <body>
-                    <h1>Authentication Successful!</h1>
-                    <p>You can now close this browser window and return to ChaiLauncher.</p>
+                    <h1>{title}</h1>
+                    <p>{text}</p>
                     <script>
                         setTimeout(() => window.close(), 3000);
                     </script>
                 </body>
                 </html>
-            "#)
+            "#))
+            });
+
+        let (shutdown, mut stop) = watch::channel(false);
+        let mut stop_after_grace = stop.clone();
+        let server = warp::serve(callback)
+            .incoming(listener)
+            .graceful(async move {
+                // A closed channel means the CallbackServer was dropped
+                let _ = stop.wait_for(|stop| *stop).await;
+            })
+            .run();
+
+        // Graceful shutdown waits for open connections, which browsers keep alive
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = server => {}
+                _ = async {
+                    let _ = stop_after_grace.wait_for(|stop| *stop).await;
+                    tokio::time::sleep(CALLBACK_SHUTDOWN_GRACE).await;
+                } => {}
+            }
+            println!("🔌 OAuth callback server on port {} stopped", port);
         });
 
-    let routes = callback;
-    
-    // Start server on port 7931
-    let server = warp::serve(routes).run(([127, 0, 0, 1], 7931));
-    
-    // Run server in background
-    tokio::spawn(server);
-    
-    // Wait for callback
-    match tokio::time::timeout(std::time::Duration::from_secs(300), rx).await {
-        Ok(Ok((code, state))) => Ok((code, state)),
-        Ok(Err(_)) => Err(anyhow::anyhow!("Channel error")),
-        Err(_) => Err(anyhow::anyhow!("OAuth timeout")),
+        println!("🔐 OAuth callback server listening on port {}", port);
+        Ok(Self {
+            redirect_uri: format!("http://localhost:{}/auth/callback", port),
+            port,
+            params,
+            _shutdown: shutdown,
+        })
     }
+
+    /// Wait for the redirect and return its `(code, state)`, stopping the server either way
+    async fn wait(self, timeout: Duration) -> Result<(String, String), OAuthError> {
+        let params = match tokio::time::timeout(timeout, self.params).await {
+            Ok(Ok(params)) => params,
+            Ok(Err(_)) => return Err("OAuth callback server stopped unexpectedly".to_string().into()),
+            Err(_) => {
+                return Err(OAuthError::Timeout {
+                    seconds: timeout.as_secs(),
+                    message: format!(
+                        "Sign-in timed out after {}s waiting for the browser. Try again and finish signing in within that time.",
+                        timeout.as_secs()
+                    ),
+                });
+            }
+        };
+
+        if let Some(error) = params.get("error") {
+            let description = params.get("error_description").map(String::as_str).unwrap_or(error);
+            return Err(format!("Microsoft sign-in was not completed: {}", description).into());
+        }
+
+        let code = params.get("code").cloned().unwrap_or_default();
+        let state = params.get("state").cloned().unwrap_or_default();
+        if code.is_empty() {
+            return Err(format!("Sign-in callback on port {} had no authorization code", self.port).into());
+        }
+        Ok((code, state))
+    }
+}
+
+/// Bind the first free port in `ports` on the loopback interface
+async fn bind_callback_listener(ports: RangeInclusive<u16>) -> Result<TcpListener, OAuthError> {
+    let mut last_error = None;
+
+    for port in ports.clone() {
+        match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) => {
+                println!("⚠️  OAuth callback port {} is unavailable: {}", port, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(OAuthError::PortsInUse {
+        message: format!(
+            "Could not start the sign-in callback server: ports {}-{} are all in use ({}). Close the program using them, such as a development server, and try again.",
+            ports.start(),
+            ports.end(),
+            last_error.map(|e| e.to_string()).unwrap_or_else(|| "no ports to try".to_string())
+        ),
+    })
 }
 
 async fn complete_authentication_flow(microsoft_token: MicrosoftTokenResponse) -> Result<MinecraftAccount, String> {
@@ -762,6 +1593,7 @@ async fn complete_authentication_flow(microsoft_token: MicrosoftTokenResponse) -
         cape_url: profile.capes.and_then(|capes| {
             capes.into_iter().find(|c| c.state == "ACTIVE").map(|c| c.url)
         }),
+        needs_relogin: false,
     };
 
     // Store account
@@ -770,4 +1602,135 @@ async fn complete_authentication_flow(microsoft_token: MicrosoftTokenResponse) -
         .map_err(|e| format!("Failed to store account: {}", e))?;
 
     Ok(account)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn only_refused_refreshes_need_a_new_sign_in() {
+        let unreachable = reqwest::get("http://127.0.0.1:1").await.unwrap_err();
+        assert!(!needs_new_sign_in(&anyhow::Error::from(unreachable).context("Token refresh failed")));
+
+        let refused = |step, status| anyhow::Error::from(AuthStatusError {
+            step,
+            status,
+            body: r#"{"error":"invalid_grant"}"#.to_string(),
+        });
+        assert!(needs_new_sign_in(&refused(TOKEN_REFRESH_STEP, reqwest::StatusCode::BAD_REQUEST)));
+        for status in [
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+            reqwest::StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            assert!(!needs_new_sign_in(&refused(TOKEN_REFRESH_STEP, status)));
+        }
+        assert!(!needs_new_sign_in(&refused("Xbox Live auth", reqwest::StatusCode::UNAUTHORIZED)));
+    }
+
+    #[test]
+    fn explains_refused_profile_changes() {
+        let limited = profile_error(reqwest::StatusCode::TOO_MANY_REQUESTS, Some(30), "");
+        assert!(limited.contains("try again in 30s"));
+        let limited = profile_error(reqwest::StatusCode::TOO_MANY_REQUESTS, None, "");
+        assert!(limited.contains("try again in a minute"));
+
+        let invalid = profile_error(
+            reqwest::StatusCode::BAD_REQUEST,
+            None,
+            r#"{"path":"/minecraft/profile/skins","errorMessage":"Could not validate image data"}"#,
+        );
+        assert!(invalid.ends_with("Could not validate image data"));
+        assert!(invalid.contains("400"));
+
+        let unknown = profile_error(reqwest::StatusCode::BAD_GATEWAY, None, "<html>");
+        assert_eq!(unknown, "Minecraft services refused the change (502 Bad Gateway)");
+    }
+
+    #[tokio::test]
+    async fn reports_ports_in_use() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let error = bind_callback_listener(port..=port).await.unwrap_err();
+        assert!(matches!(error, OAuthError::PortsInUse { .. }));
+        assert!(error.message().contains(&port.to_string()));
+    }
+
+    #[tokio::test]
+    async fn timed_out_server_releases_its_port() {
+        let free = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = free.local_addr().unwrap().port();
+        drop(free);
+
+        let server = CallbackServer::start(port..=port).await.unwrap();
+        assert_eq!(server.redirect_uri, format!("http://localhost:{}/auth/callback", port));
+
+        let error = server.wait(Duration::from_millis(50)).await.unwrap_err();
+        assert!(matches!(error, OAuthError::Timeout { .. }));
+
+        tokio::time::sleep(CALLBACK_SHUTDOWN_GRACE + Duration::from_millis(500)).await;
+        assert!(TcpListener::bind(("127.0.0.1", port)).await.is_ok());
+    }
+    #[test]
+    fn device_code_polling_waits_slows_down_and_fails() {
+        let error = |code: &str| TokenErrorResponse { error: code.to_string(), error_description: None };
+        assert_eq!(device_poll_step(&error("authorization_pending")), DevicePoll::Pending);
+        assert_eq!(device_poll_step(&error("slow_down")), DevicePoll::SlowDown);
+        assert_eq!(device_poll_step(&error("expired_token")), DevicePoll::Expired);
+        assert_eq!(device_poll_step(&error("authorization_declined")), DevicePoll::Fail("Sign-in was declined".to_string()));
+        assert_eq!(device_poll_step(&error("bad_verification_code")), DevicePoll::Fail("Sign-in failed: bad_verification_code".to_string()));
+    }
+
+    #[tokio::test]
+    async fn expired_device_codes_time_out_without_polling() {
+        let session = DeviceCodeSession {
+            device_code: "device".to_string(),
+            code: DeviceCode { user_code: "ABCD-EFGH".to_string(), verification_uri: "https://microsoft.com/link".to_string(), expires_in: 900 },
+            interval: Duration::from_secs(5),
+            expires_at: SystemTime::now() - Duration::from_secs(1),
+        };
+        let error = poll_device_code(&session).await.unwrap_err();
+        assert!(matches!(error, OAuthError::Timeout { seconds: 900, .. }));
+    }
+
+    #[test]
+    fn offline_players_get_the_vanilla_uuid() {
+        assert_eq!(offline_uuid("Notch"), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+        assert!(validate_offline_username("Steve_42").is_ok());
+        for bad in ["ab", "seventeen_chars_x", "bad name", "Ünicode"] {
+            assert!(validate_offline_username(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    fn account(id: &str, uuid: &str) -> MinecraftAccount {
+        MinecraftAccount {
+            id: id.to_string(),
+            username: id.to_string(),
+            uuid: uuid.to_string(),
+            access_token: String::new(),
+            refresh_token: String::new(),
+            expires_at: 0,
+            skin_url: None,
+            cape_url: None,
+            needs_relogin: false,
+        }
+    }
+
+    #[test]
+    fn active_account_falls_back_to_the_first_and_then_the_next() {
+        let accounts = vec![
+            account("a", "069a79f4-44e9-4726-a5be-fca90e38aaf5"),
+            account("b", "853c80ef3c3749fdaa49938b674adae6"),
+            account("c", "61699b2ed3274a019f1e0ea8c3f06bc6"),
+        ];
+        assert_eq!(select_account(&accounts, None).unwrap().id, "a");
+        assert_eq!(select_account(&accounts, Some("853C80EF-3C37-49FD-AA49-938B674ADAE6")).unwrap().id, "b");
+        assert_eq!(select_account(&accounts, Some("removed")).unwrap().id, "a");
+        assert!(select_account(&[], Some("anything")).is_none());
+
+        assert_eq!(next_account_after(&accounts, "b").unwrap().id, "c");
+        assert_eq!(next_account_after(&accounts, "c").unwrap().id, "a");
+        assert!(next_account_after(&accounts[..1], "a").is_none());
+    }
+}
@@ -1,6 +1,6 @@
-use tauri::command;
+use tauri::{command, State};
 use std::process::Command;
-use crate::storage::StorageManager;
+use crate::storage::StorageState;
 
 #[command]
 pub async fn open_folder(path: String) -> Result<(), String> {
@@ -32,14 +32,10 @@ pub async fn open_folder(path: String) -> Result<(), String> {
 }
 
 #[command]
-pub async fn open_instance_folder(instance_id: String) -> Result<(), String> {
-    let instance_id = instance_id;
-    use crate::storage::StorageManager;
-    
+pub async fn open_instance_folder(instance_id: String, storage: State<'_, StorageState>) -> Result<(), String> {
     println!("🔍 Looking for instance with ID: {}", instance_id);
     
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+    let storage = storage.read().await;
     
     // Debug: List all available instances
     println!("📋 Available instances:");
@@ -66,37 +62,36 @@ pub async fn open_instance_folder(instance_id: String) -> Result<(), String> {
     }
 }
 
+/// Save the manual auth token in the credential store
 #[command]
-pub async fn set_auth_token(token: String) -> Result<(), String> {
-    let mut storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
-    let mut settings = storage.get_settings().clone();
-    settings.auth_token = Some(token);
-    
-    storage.update_settings(settings).await
+pub async fn set_auth_token(token: String, storage: State<'_, StorageState>) -> Result<(), String> {
+    crate::secrets::migrate_auth_token(&storage).await;
+    crate::secrets::set(crate::secrets::AUTH_TOKEN_KEY, &token).await
         .map_err(|e| format!("Failed to save auth token: {}", e))?;
     
     Ok(())
 }
 
 #[command]
-pub async fn get_auth_token() -> Result<Option<String>, String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
-    Ok(storage.get_settings().auth_token.clone())
+pub async fn get_auth_token(storage: State<'_, StorageState>) -> Result<Option<String>, String> {
+    crate::secrets::migrate_auth_token(&storage).await;
+    crate::secrets::get(crate::secrets::AUTH_TOKEN_KEY).await
+        .map_err(|e| format!("Failed to read auth token: {}", e))
 }
 
 #[command]
-pub async fn clear_auth_token() -> Result<(), String> {
-    let mut storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
-    let mut settings = storage.get_settings().clone();
-    settings.auth_token = None;
-    
-    storage.update_settings(settings).await
+pub async fn clear_auth_token(storage: State<'_, StorageState>) -> Result<(), String> {
+    let mut storage = storage.write().await;
+    
+    if storage.get_settings().auth_token.is_some() {
+        let mut settings = storage.get_settings().clone();
+        settings.auth_token = None;
+        storage.update_settings(settings).await
+            .map_err(|e| format!("Failed to clear auth token: {}", e))?;
+    }
+    drop(storage);
+
+    crate::secrets::delete(crate::secrets::AUTH_TOKEN_KEY).await
         .map_err(|e| format!("Failed to clear auth token: {}", e))?;
     
     Ok(())
@@ -104,14 +99,15 @@ pub async fn clear_auth_token() -> Result<(), String> {
 
 /// Get current authentication status for debugging
 #[command]
-pub async fn get_auth_status() -> Result<serde_json::Value, String> {
+pub async fn get_auth_status(storage: State<'_, StorageState>) -> Result<serde_json::Value, String> {
     let mut status = serde_json::Map::new();
     
     // Check Microsoft accounts
     match crate::auth::get_stored_accounts().await {
         Ok(accounts) => {
             status.insert("microsoft_accounts_count".to_string(), serde_json::Value::from(accounts.len()));
-            if let Some(account) = accounts.first() {
+            let active_uuid = storage.read().await.get_settings().active_account_uuid.clone();
+            if let Some(account) = crate::auth::select_account(&accounts, active_uuid.as_deref()) {
                 status.insert("microsoft_username".to_string(), serde_json::Value::from(account.username.clone()));
                 status.insert("microsoft_uuid".to_string(), serde_json::Value::from(account.uuid.clone()));
                 
@@ -130,7 +126,7 @@ pub async fn get_auth_status() -> Result<serde_json::Value, String> {
     }
     
     // Check manual auth token
-    match get_auth_token().await {
+    match get_auth_token(storage).await {
         Ok(token) => {
             status.insert("manual_token_set".to_string(), serde_json::Value::from(token.is_some()));
             if let Some(token) = token {
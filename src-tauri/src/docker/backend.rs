@@ -0,0 +1,297 @@
+use super::error::DockerError;
+use super::manager::{DockerManager, PlayerProbe, PreparedDeployment, ServerContext};
+use super::mock::MockDockerBackend;
+use super::resources::ServerResourcesUpdate;
+use super::upgrade::{ServerUpgradeProgress, ServerUpgradeResult};
+use super::world_upload::{WorldUploadProgress, WorldUploadResult};
+use super::types::*;
+use crate::minecraft::MinecraftInstance;
+
+/// Environment variable that enables the mock Docker backend
+pub const DOCKER_MOCK_ENV: &str = "ChaiLauncher_DOCKER_MOCK";
+
+/// Docker backend used by the commands, either a real daemon or the in-memory mock
+pub enum DockerBackend {
+    Docker(DockerManager),
+    Mock(MockDockerBackend),
+}
+
+impl DockerBackend {
+    /// Create the backend selected by `ChaiLauncher_DOCKER_MOCK` or the `docker_mock` setting
+    pub async fn new() -> Result<Self, String> {
+        if Self::mock_enabled().await {
+            println!("🧪 Using mock Docker backend");
+            return Ok(DockerBackend::Mock(MockDockerBackend::new()));
+        }
+
+        Ok(DockerBackend::Docker(DockerManager::new().await?))
+    }
+
+    async fn mock_enabled() -> bool {
+        if let Ok(value) = std::env::var(DOCKER_MOCK_ENV) {
+            return matches!(value.to_lowercase().as_str(), "1" | "true" | "yes");
+        }
+
+        match crate::storage::shared().await {
+            Ok(storage) => storage.read().await.get_settings().docker_mock,
+            Err(_) => false,
+        }
+    }
+
+    pub fn is_mock(&self) -> bool {
+        matches!(self, DockerBackend::Mock(_))
+    }
+
+    pub async fn test_connection(&self, connection: &DockerConnection) -> Result<bool, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.test_connection(connection).await,
+            DockerBackend::Mock(mock) => mock.test_connection(connection).await.map_err(DockerError::from),
+        }
+    }
+
+    /// Stop what outlives the launcher otherwise, i.e. SSH tunnels
+    pub async fn shutdown(&mut self) {
+        if let DockerBackend::Docker(manager) = self {
+            manager.shutdown().await;
+        }
+    }
+
+    pub async fn add_connection(&mut self, connection: DockerConnection) -> Result<(), DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.add_connection(connection).await,
+            DockerBackend::Mock(mock) => mock.add_connection(connection).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn deploy_server(
+        &mut self,
+        request: ServerDeploymentRequest,
+        minecraft_instance: &MinecraftInstance,
+        on_progress: &(dyn Fn(PullProgress) + Send + Sync),
+    ) -> Result<ServerInstance, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.deploy_server(request, minecraft_instance, on_progress).await,
+            DockerBackend::Mock(mock) => mock.deploy_server(request, minecraft_instance).await.map_err(DockerError::from),
+        }
+    }
+
+    /// Plan a deployment to download without the lock, None for the mock which deploys at once
+    pub async fn prepare_deployment(
+        &self,
+        request: &ServerDeploymentRequest,
+        minecraft_instance: &MinecraftInstance,
+    ) -> Result<Option<PreparedDeployment>, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.prepare_deployment(request.clone(), minecraft_instance).await.map(Some),
+            DockerBackend::Mock(_) => Ok(None),
+        }
+    }
+
+    pub async fn create_deployment(
+        &mut self,
+        prepared: PreparedDeployment,
+        minecraft_instance: &MinecraftInstance,
+    ) -> Result<ServerInstance, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.create_deployment(prepared, minecraft_instance).await,
+            DockerBackend::Mock(_) => Err("Mock servers are deployed with deploy_server".into()),
+        }
+    }
+
+    pub async fn start_server(&mut self, server_id: &str) -> Result<(), DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.start_server(server_id).await,
+            DockerBackend::Mock(mock) => mock.start_server(server_id).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn stop_server(&mut self, server_id: &str) -> Result<(), DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.stop_server(server_id).await,
+            DockerBackend::Mock(mock) => mock.stop_server(server_id).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn get_server_status(&mut self, server_id: &str) -> Result<ServerStatus, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.get_server_status(server_id).await,
+            DockerBackend::Mock(mock) => mock.get_server_status(server_id).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn remove_server(&mut self, server_id: &str) -> Result<(), DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.remove_server(server_id).await,
+            DockerBackend::Mock(mock) => mock.remove_server(server_id).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn get_servers(&self) -> Vec<ServerInstance> {
+        match self {
+            DockerBackend::Docker(manager) => manager.get_servers().await,
+            DockerBackend::Mock(mock) => mock.get_servers().into_iter().cloned().collect(),
+        }
+    }
+
+    pub async fn get_servers_for_instance(&self, instance_id: &str) -> Vec<ServerInstance> {
+        match self {
+            DockerBackend::Docker(manager) => manager.get_servers_for_instance(instance_id).await,
+            DockerBackend::Mock(mock) => mock.get_servers_for_instance(instance_id).into_iter().cloned().collect(),
+        }
+    }
+
+    pub async fn get_docker_connections(&self) -> Vec<DockerConnection> {
+        match self {
+            DockerBackend::Docker(manager) => manager.get_docker_connections().await,
+            DockerBackend::Mock(mock) => mock.get_docker_connections().into_iter().cloned().collect(),
+        }
+    }
+
+    pub async fn get_server_logs(&self, server_id: &str, lines: Option<u32>) -> Result<Vec<LogEntry>, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.get_server_logs(server_id, lines).await,
+            DockerBackend::Mock(mock) => mock.get_server_logs(server_id, lines).await.map_err(DockerError::from),
+        }
+    }
+
+    /// Where to follow a server's log from, None for mock servers which have no container
+    pub async fn log_source(&self, server_id: &str) -> Result<Option<(bollard::Docker, String)>, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.log_source(server_id).await.map(Some),
+            DockerBackend::Mock(mock) => match mock.get_servers().iter().any(|server| server.id == server_id) {
+                true => Ok(None),
+                false => Err("Server not found".into()),
+            },
+        }
+    }
+
+    pub async fn exec_command(&self, server_id: &str, minecraft_command: String) -> Result<String, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.exec_command(server_id, minecraft_command).await,
+            DockerBackend::Mock(mock) => mock.exec_command(server_id, minecraft_command).await.map_err(DockerError::from),
+        }
+    }
+
+    /// Read a file in the server's data volume, missing files read as empty
+    pub async fn read_server_file(&self, server_id: &str, path: &str) -> Result<Vec<u8>, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.read_server_file(server_id, path).await,
+            DockerBackend::Mock(mock) => mock.read_server_file(server_id, path).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn write_server_file(&mut self, server_id: &str, path: &str, contents: &str) -> Result<(), DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.write_server_file(server_id, path, contents).await,
+            DockerBackend::Mock(mock) => mock.write_server_file(server_id, path, contents).await.map_err(DockerError::from),
+        }
+    }
+
+    /// A server that hasn't started yet has no server.properties and reads as empty
+    pub async fn read_server_properties(&self, server_id: &str) -> Result<String, DockerError> {
+        let bytes = self.read_server_file(server_id, super::properties::SERVER_PROPERTIES_PATH).await?;
+        Ok(super::properties::decode(&bytes))
+    }
+
+    pub async fn write_server_properties(&mut self, server_id: &str, contents: &str) -> Result<(), DockerError> {
+        self.write_server_file(server_id, super::properties::SERVER_PROPERTIES_PATH, contents).await
+    }
+
+    pub async fn send_rcon_command(&self, server_id: &str, command: &str) -> Result<String, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.send_rcon_command(server_id, command).await,
+            // The mock answers commands the same way over every transport
+            DockerBackend::Mock(mock) => mock.exec_command(server_id, command.to_string()).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn update_server_resources(&mut self, server_id: &str, limits: ServerResourceLimits) -> Result<ServerResourcesUpdate, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.update_server_resources(server_id, limits).await,
+            DockerBackend::Mock(mock) => mock.update_server_resources(server_id, limits).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn upload_world(
+        &mut self,
+        server_id: &str,
+        world_dir: &std::path::Path,
+        backup: bool,
+        on_progress: &(dyn Fn(WorldUploadProgress) + Send + Sync),
+    ) -> Result<WorldUploadResult, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.upload_world(server_id, world_dir, backup, on_progress).await,
+            DockerBackend::Mock(mock) => mock.upload_world(server_id, world_dir, backup, on_progress).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn upgrade_server(
+        &mut self,
+        server_id: &str,
+        target_version: &str,
+        force: bool,
+        on_progress: &(dyn Fn(ServerUpgradeProgress) + Send + Sync),
+    ) -> Result<ServerUpgradeResult, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.upgrade_server(server_id, target_version, force, on_progress).await,
+            DockerBackend::Mock(mock) => mock.upgrade_server(server_id, target_version, force, on_progress).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn export_server_compose(&self, server_id: &str, output_path: &std::path::Path) -> Result<std::path::PathBuf, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.export_server_compose(server_id, output_path).await,
+            DockerBackend::Mock(mock) => mock.export_server_compose(server_id, output_path).await.map_err(DockerError::from),
+        }
+    }
+
+    pub async fn get_server_stats(&self, server_id: &str) -> Result<ServerStats, String> {
+        match self {
+            DockerBackend::Docker(manager) => manager.get_server_stats(server_id).await,
+            DockerBackend::Mock(mock) => mock.get_server_stats(server_id),
+        }
+    }
+
+    pub async fn sample_players(&mut self, server_id: &str) -> Result<PlayerSample, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.sample_players(server_id).await,
+            DockerBackend::Mock(mock) => mock.sample_players(server_id).await.map_err(DockerError::from),
+        }
+    }
+
+    /// A server's Docker context for calls made without the manager lock, None for mock servers
+    pub async fn server_context(&self, server_id: &str) -> Result<Option<ServerContext>, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.context(server_id).await.map(Some),
+            DockerBackend::Mock(_) => Ok(None),
+        }
+    }
+
+    pub fn query_enabled(&self, server_id: &str) -> bool {
+        match self {
+            DockerBackend::Docker(manager) => manager.query_enabled(server_id),
+            DockerBackend::Mock(_) => false,
+        }
+    }
+
+    /// Probe a recreated server's query support again
+    pub fn reset_query_support(&mut self, server_id: &str) {
+        if let DockerBackend::Docker(manager) = self {
+            manager.reset_query_support(server_id);
+        }
+    }
+
+    pub fn record_players(&mut self, server_id: &str, probe: PlayerProbe) -> Result<PlayerSample, DockerError> {
+        match self {
+            DockerBackend::Docker(manager) => manager.record_players(server_id, probe),
+            DockerBackend::Mock(_) => Err("Mock servers are sampled with sample_players".into()),
+        }
+    }
+
+    pub fn get_player_history(&self, server_id: &str, hours: u32) -> Vec<PlayerSample> {
+        match self {
+            DockerBackend::Docker(manager) => manager.get_player_history(server_id, hours),
+            DockerBackend::Mock(mock) => mock.get_player_history(server_id, hours),
+        }
+    }
+}
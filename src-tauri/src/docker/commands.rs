@@ -1,16 +1,45 @@
-use super::{DockerManager, DockerConnection, ServerDeploymentRequest, ServerInstance};
+use super::{DockerBackend, DockerConnection, DockerError, PullProgress, ServerDeploymentRequest, ServerInstance, ServerResourceLimits};
+use super::properties::{PropertiesFile, ServerPropertiesUpdate};
+use super::resources::ServerResourcesUpdate;
+use super::templates::{self, ServerTemplate};
+use super::players::{self, OpRef, PlayerRef, ServerPlayersConfig, ServerPlayersUpdate};
+use super::log_stream::{LogStreamsState, OutputStream, ServerLogLine};
+use super::upgrade::{ServerUpgradeProgress, ServerUpgradeResult, ServerUpgradeStage};
+use super::world_upload::{WorldUploadProgress, WorldUploadResult, WorldUploadStage};
 use crate::minecraft::MinecraftInstance;
+use crate::notifications::NotificationTarget;
+use crate::tasks::{TaskContext, TaskHandle, TaskManagerState};
+use std::collections::BTreeMap;
+use std::future::Future;
 use tokio::sync::Mutex;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 // Global Docker manager state
-pub type DockerManagerState = Mutex<DockerManager>;
+pub type DockerManagerState = Mutex<DockerBackend>;
+
+/// Run a Docker operation until it finishes or its task is cancelled
+///
+/// Dropping the operation aborts any request still in flight and releases the manager lock.
+async fn cancellable<T>(
+    ctx: &TaskContext,
+    operation: &str,
+    run: impl Future<Output = Result<T, DockerError>>,
+) -> Result<T, DockerError> {
+    tokio::select! {
+        result = run => result,
+        _ = ctx.cancelled() => Err(DockerError::cancelled(operation)),
+    }
+}
+
+fn server_queue(server_id: &str) -> String {
+    format!("docker:{}", server_id)
+}
 
 #[tauri::command]
-pub async fn test_docker_connection(connection: DockerConnection) -> Result<bool, String> {
-    match DockerManager::new().await {
+pub async fn test_docker_connection(connection: DockerConnection) -> Result<bool, DockerError> {
+    match DockerBackend::new().await {
         Ok(manager) => manager.test_connection(&connection).await,
-        Err(e) => Err(format!("Failed to create Docker manager: {}", e))
+        Err(e) => Err(format!("Failed to create Docker manager: {}", e).into())
     }
 }
 
@@ -18,46 +47,105 @@ pub async fn test_docker_connection(connection: DockerConnection) -> Result<bool
 pub async fn add_docker_connection(
     state: State<'_, DockerManagerState>,
     connection: DockerConnection,
-) -> Result<(), String> {
+) -> Result<(), DockerError> {
     let mut manager = state.lock().await;
     manager.add_connection(connection).await
 }
 
+/// Server templates the deploy dialog can offer
+#[tauri::command]
+pub async fn get_server_templates() -> Result<Vec<ServerTemplate>, DockerError> {
+    Ok(templates::server_templates())
+}
+
+/// Deploy a server as a cancellable `docker_deploy` task, emitting `docker_pull_progress` while the image downloads
 #[tauri::command]
 pub async fn deploy_minecraft_server(
-    state: State<'_, DockerManagerState>,
+    app_handle: AppHandle,
+    tasks: State<'_, TaskManagerState>,
     deployment_request: ServerDeploymentRequest,
     minecraft_instance: MinecraftInstance,
-) -> Result<ServerInstance, String> {
-    let mut manager = state.lock().await;
-    manager.deploy_server(deployment_request, &minecraft_instance).await
+) -> Result<ServerInstance, DockerError> {
+    let queue_key = server_queue(&deployment_request.name);
+    let app = app_handle.clone();
+
+    let task: TaskHandle<ServerInstance, DockerError> = tasks.spawn(app_handle, "docker_deploy", &queue_key, move |ctx| async move {
+        ctx.set_subject(&deployment_request.name, Some(NotificationTarget::View { view: "servers".to_string() }));
+        let on_progress = |progress: PullProgress| {
+            ctx.set_progress(progress.percent, Some(format!("{}: {}", progress.layer, progress.status)));
+            let _ = app.emit("docker_pull_progress", serde_json::json!({
+                "task_id": ctx.id,
+                "progress": progress,
+            }));
+        };
+
+        // The manager is only locked to plan and to create the container, the image download can take minutes
+        let state = app.state::<DockerManagerState>();
+        cancellable(&ctx, "deploy server", async {
+            let prepared = state.lock().await.prepare_deployment(&deployment_request, &minecraft_instance).await?;
+            match prepared {
+                Some(prepared) => {
+                    prepared.download(&on_progress).await?;
+                    state.lock().await.create_deployment(prepared, &minecraft_instance).await
+                }
+                None => state.lock().await.deploy_server(deployment_request, &minecraft_instance, &on_progress).await,
+            }
+        }).await
+    });
+
+    task.wait().await
 }
 
 #[tauri::command]
 pub async fn start_server(
     state: State<'_, DockerManagerState>,
     server_id: String,
-) -> Result<(), String> {
+) -> Result<(), DockerError> {
     let mut manager = state.lock().await;
     manager.start_server(&server_id).await
 }
 
+/// Stop a server as a cancellable `docker_stop` task
 #[tauri::command]
 pub async fn stop_server(
-    state: State<'_, DockerManagerState>,
+    app_handle: AppHandle,
+    tasks: State<'_, TaskManagerState>,
     server_id: String,
-) -> Result<(), String> {
-    let mut manager = state.lock().await;
-    manager.stop_server(&server_id).await
+) -> Result<(), DockerError> {
+    let queue_key = server_queue(&server_id);
+    let app = app_handle.clone();
+
+    let task: TaskHandle<(), DockerError> = tasks.spawn(app_handle, "docker_stop", &queue_key, move |ctx| async move {
+        let state = app.state::<DockerManagerState>();
+        cancellable(&ctx, "stop server", async {
+            let mut manager = state.lock().await;
+            manager.stop_server(&server_id).await
+        }).await
+    });
+
+    task.wait().await
 }
 
+/// Remove a server as a cancellable `docker_remove` task
 #[tauri::command]
 pub async fn remove_server(
-    state: State<'_, DockerManagerState>,
+    app_handle: AppHandle,
+    tasks: State<'_, TaskManagerState>,
     server_id: String,
-) -> Result<(), String> {
-    let mut manager = state.lock().await;
-    manager.remove_server(&server_id).await
+) -> Result<(), DockerError> {
+    let queue_key = server_queue(&server_id);
+    let app = app_handle.clone();
+
+    let task: TaskHandle<(), DockerError> = tasks.spawn(app_handle, "docker_remove", &queue_key, move |ctx| async move {
+        app.state::<LogStreamsState>().forget(&server_id);
+        let state = app.state::<DockerManagerState>();
+        cancellable(&ctx, "remove server", async {
+            let mut manager = state.lock().await;
+            manager.remove_server(&server_id).await
+        }).await
+    });
+
+    task.wait().await
 }
 
 #[tauri::command]
@@ -65,7 +153,7 @@ pub async fn get_servers(
     state: State<'_, DockerManagerState>,
 ) -> Result<Vec<ServerInstance>, String> {
     let manager = state.lock().await;
-    Ok(manager.get_servers().into_iter().cloned().collect())
+    Ok(manager.get_servers().await)
 }
 
 #[tauri::command]
@@ -74,7 +162,7 @@ pub async fn get_servers_for_instance(
     instance_id: String,
 ) -> Result<Vec<ServerInstance>, String> {
     let manager = state.lock().await;
-    Ok(manager.get_servers_for_instance(&instance_id).into_iter().cloned().collect())
+    Ok(manager.get_servers_for_instance(&instance_id).await)
 }
 
 #[tauri::command]
@@ -82,7 +170,7 @@ pub async fn get_docker_connections(
     state: State<'_, DockerManagerState>,
 ) -> Result<Vec<DockerConnection>, String> {
     let manager = state.lock().await;
-    Ok(manager.get_docker_connections().into_iter().cloned().collect())
+    Ok(manager.get_docker_connections().await)
 }
 
 #[tauri::command]
@@ -90,53 +178,197 @@ pub async fn get_server_logs(
     state: State<'_, DockerManagerState>,
     server_id: String,
     lines: Option<u32>,
-) -> Result<Vec<super::LogEntry>, String> {
+) -> Result<Vec<super::LogEntry>, DockerError> {
     let manager = state.lock().await;
     manager.get_server_logs(&server_id, lines).await
 }
 
+/// Follow a server's console, emitting `server_log` events until it stops or the stream is stopped
+///
+/// Mock servers have nothing to follow, their log is sent once.
+#[tauri::command]
+pub async fn start_server_log_stream(
+    app_handle: AppHandle,
+    state: State<'_, DockerManagerState>,
+    streams: State<'_, LogStreamsState>,
+    server_id: String,
+) -> Result<(), DockerError> {
+    let manager = state.lock().await;
+    match manager.log_source(&server_id).await? {
+        Some((docker, container_id)) => {
+            streams.start(app_handle, &server_id, docker, container_id);
+        }
+        None => {
+            for entry in manager.get_server_logs(&server_id, None).await? {
+                let _ = app_handle.emit("server_log", ServerLogLine {
+                    server_id: server_id.clone(),
+                    line: entry.message,
+                    timestamp: entry.timestamp,
+                    stream: OutputStream::Stdout,
+                    level: entry.level,
+                    dropped: 0,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_server_log_stream(
+    streams: State<'_, LogStreamsState>,
+    server_id: String,
+) -> Result<(), DockerError> {
+    streams.stop(&server_id);
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn execute_server_command(
     state: State<'_, DockerManagerState>,
     server_id: String,
     command: String,
-) -> Result<String, String> {
+) -> Result<String, DockerError> {
     let manager = state.lock().await;
     manager.exec_command(&server_id, command).await
 }
 
+/// Run a command over RCON and return the server's response
 #[tauri::command]
-pub async fn get_server_status(
+pub async fn send_rcon_command(
+    state: State<'_, DockerManagerState>,
+    server_id: String,
+    command: String,
+) -> Result<String, DockerError> {
+    let manager = state.lock().await;
+    manager.send_rcon_command(&server_id, &command).await
+}
+
+/// Properties from the server's server.properties
+#[tauri::command]
+pub async fn get_server_properties(
     state: State<'_, DockerManagerState>,
     server_id: String,
-) -> Result<super::ServerStatus, String> {
+) -> Result<BTreeMap<String, String>, DockerError> {
+    let manager = state.lock().await;
+    let contents = manager.read_server_properties(&server_id).await?;
+    Ok(PropertiesFile::parse(&contents).to_map())
+}
+
+/// Change properties in server.properties, keeping comments and unknown keys
+#[tauri::command]
+pub async fn update_server_properties(
+    state: State<'_, DockerManagerState>,
+    server_id: String,
+    properties: BTreeMap<String, String>,
+) -> Result<ServerPropertiesUpdate, DockerError> {
     let mut manager = state.lock().await;
-    manager.get_server_status(&server_id).await
+    let mut file = PropertiesFile::parse(&manager.read_server_properties(&server_id).await?);
+    let update = file.update(&properties);
+    if !update.changed.is_empty() {
+        manager.write_server_properties(&server_id, &file.to_string()).await?;
+    }
+    Ok(update)
 }
 
+async fn read_players_config(manager: &DockerBackend, server_id: &str) -> Result<ServerPlayersConfig, DockerError> {
+    let whitelist = manager.read_server_file(server_id, players::WHITELIST_PATH).await?;
+    let ops = manager.read_server_file(server_id, players::OPS_PATH).await?;
+    Ok(ServerPlayersConfig::parse(&String::from_utf8_lossy(&whitelist), &String::from_utf8_lossy(&ops))?)
+}
 
+/// Whitelisted players and operators of a server
+#[tauri::command]
+pub async fn get_server_players_config(
+    state: State<'_, DockerManagerState>,
+    server_id: String,
+) -> Result<ServerPlayersConfig, DockerError> {
+    let manager = state.lock().await;
+    read_players_config(&manager, &server_id).await
+}
 
+/// Replace a server's whitelist and operators, applying the changes live over RCON when it can
 #[tauri::command]
-pub async fn restart_server(
+pub async fn update_server_players_config(
     state: State<'_, DockerManagerState>,
     server_id: String,
-) -> Result<(), String> {
-    // Stop the server first
-    {
-        let mut manager = state.lock().await;
-        manager.stop_server(&server_id).await?;
+    whitelist: Vec<PlayerRef>,
+    ops: Vec<OpRef>,
+) -> Result<ServerPlayersUpdate, DockerError> {
+    let current = read_players_config(&*state.lock().await, &server_id).await?;
+    // Names are looked up without holding the manager
+    let config = players::resolve(&current, whitelist, ops).await?;
+
+    let mut manager = state.lock().await;
+    let mut warnings = Vec::new();
+    let mut applied_live = true;
+    for command in players::live_commands(&current, &config) {
+        if let Err(e) = manager.send_rcon_command(&server_id, &command).await {
+            warnings.push(format!("Couldn't apply the changes over RCON, they take effect when the server restarts: {}", e));
+            applied_live = false;
+            break;
+        }
     }
-    
-    // Wait a moment for the server to fully stop
-    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-    
-    // Start the server again
-    {
-        let mut manager = state.lock().await;
-        manager.start_server(&server_id).await?;
+    manager.write_server_file(&server_id, players::WHITELIST_PATH, &config.whitelist_json()).await?;
+    manager.write_server_file(&server_id, players::OPS_PATH, &config.ops_json()).await?;
+    if applied_live {
+        // Picks up what the commands can't express exactly, such as renamed entries
+        if let Err(e) = manager.send_rcon_command(&server_id, "whitelist reload").await {
+            println!("⚠️  Failed to reload the whitelist of server {}: {}", server_id, e);
+        }
     }
-    
-    Ok(())
+    if applied_live && players::op_settings_changed(&current, &config) {
+        warnings.push("Operator levels and player limit bypasses take effect when the server restarts".to_string());
+    }
+    Ok(ServerPlayersUpdate { config, applied_live, warnings })
+}
+
+#[tauri::command]
+pub async fn get_server_status(
+    state: State<'_, DockerManagerState>,
+    server_id: String,
+) -> Result<super::ServerStatus, DockerError> {
+    let mut manager = state.lock().await;
+    manager.get_server_status(&server_id).await
+}
+
+
+
+/// Restart a server as a cancellable `docker_restart` task
+#[tauri::command]
+pub async fn restart_server(
+    app_handle: AppHandle,
+    tasks: State<'_, TaskManagerState>,
+    server_id: String,
+) -> Result<(), DockerError> {
+    let queue_key = server_queue(&server_id);
+    let app = app_handle.clone();
+
+    let task: TaskHandle<(), DockerError> = tasks.spawn(app_handle, "docker_restart", &queue_key, move |ctx| async move {
+        let state = app.state::<DockerManagerState>();
+        let name = state.lock().await.get_servers().await
+            .into_iter()
+            .find(|server| server.id == server_id)
+            .map_or_else(|| server_id.clone(), |server| server.name);
+        ctx.set_subject(&name, Some(NotificationTarget::Server { id: server_id.clone() }));
+
+        cancellable(&ctx, "restart server", async {
+            // Stop the server first
+            {
+                let mut manager = state.lock().await;
+                manager.stop_server(&server_id).await?;
+            }
+            
+            // Wait a moment for the server to fully stop
+            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            
+            // Start the server again
+            let mut manager = state.lock().await;
+            manager.start_server(&server_id).await
+        }).await
+    });
+
+    task.wait().await
 }
 
 #[tauri::command]
@@ -147,9 +379,9 @@ pub async fn backup_server(
 ) -> Result<String, String> {
     let manager = state.lock().await;
     
-    let server = manager.get_servers().iter()
+    let server = manager.get_servers().await
+        .into_iter()
         .find(|s| s.id == server_id)
-        .cloned()
         .ok_or("Server not found")?;
 
     let backup_id = format!("backup-{}-{}", server.name, chrono::Utc::now().timestamp());
@@ -174,19 +406,242 @@ pub async fn get_server_stats(
     server_id: String,
 ) -> Result<super::ServerStats, String> {
     let manager = state.lock().await;
-    
-    let _server = manager.get_servers().iter()
-        .find(|s| s.id == server_id)
-        .ok_or("Server not found")?;
+    manager.get_server_stats(&server_id).await
+}
 
-    // For a full implementation, we would get actual stats from Docker
-    // For now, return mock stats
-    Ok(super::ServerStats {
-        cpu_usage: 15.5,
-        memory_usage: 1024 * 1024 * 1500, // 1.5 GB in bytes
-        memory_limit: 1024 * 1024 * 2048,  // 2 GB in bytes
-        network_in: 1024 * 512,  // 512 KB
-        network_out: 1024 * 256, // 256 KB
-        uptime: 3600, // 1 hour in seconds
-    })
+/// Change the CPU and memory limits of a server's container
+#[tauri::command]
+pub async fn update_server_resources(
+    state: State<'_, DockerManagerState>,
+    server_id: String,
+    limits: ServerResourceLimits,
+) -> Result<ServerResourcesUpdate, DockerError> {
+    let mut manager = state.lock().await;
+    manager.update_server_resources(&server_id, limits).await
+}
+
+/// Replace a server's world with a world from an instance's saves, as a `docker_world_upload` task
+///
+/// Emits `world_upload_progress` through compression, transfer and extraction. The
+/// replaced world is backed up on the server unless `backup` is false.
+#[tauri::command]
+pub async fn upload_world(
+    app_handle: AppHandle,
+    tasks: State<'_, TaskManagerState>,
+    server_id: String,
+    instance_id: String,
+    world_name: String,
+    backup: Option<bool>,
+) -> Result<WorldUploadResult, DockerError> {
+    let storage = crate::storage::shared().await
+        .map_err(|e| format!("Failed to open launcher storage: {}", e))?;
+    let game_dir = storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.game_dir.clone())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    let world_dir = crate::minecraft::worlds::world_path(&game_dir, &world_name)?;
+
+    let queue_key = server_queue(&server_id);
+    let app = app_handle.clone();
+
+    let task: TaskHandle<WorldUploadResult, DockerError> = tasks.spawn(app_handle, "docker_world_upload", &queue_key, move |ctx| async move {
+        ctx.set_subject(&world_name, Some(NotificationTarget::View { view: "servers".to_string() }));
+        let on_progress = |progress: WorldUploadProgress| {
+            let stage = match progress.stage {
+                WorldUploadStage::Compressing => "Compressing world",
+                WorldUploadStage::Transferring => "Sending world to the server",
+                WorldUploadStage::Extracting => "Replacing the server's world",
+            };
+            ctx.set_progress(progress.percent, Some(stage.to_string()));
+            let _ = app.emit("world_upload_progress", serde_json::json!({
+                "task_id": ctx.id,
+                "server_id": server_id,
+                "progress": progress,
+            }));
+        };
+
+        // The manager is only locked to copy the server out, the transfer can take many minutes
+        let state = app.state::<DockerManagerState>();
+        cancellable(&ctx, "upload world", async {
+            let context = state.lock().await.server_context(&server_id).await?;
+            match context {
+                Some(context) => {
+                    let result = context.upload_world(&world_dir, backup.unwrap_or(true), &on_progress).await;
+                    state.lock().await.reset_query_support(&server_id);
+                    result
+                }
+                None => state.lock().await.upload_world(&server_id, &world_dir, backup.unwrap_or(true), &on_progress).await,
+            }
+        }).await
+    });
+
+    task.wait().await
+}
+
+/// Move a server to another Minecraft version as a `docker_upgrade` task
+///
+/// Emits `server_upgrade_progress` through the backup, image pull, recreate and
+/// verify stages, and `roll_back` when the upgraded server fails to start. Moving
+/// to an older version is refused unless `force` is true.
+#[tauri::command]
+pub async fn upgrade_server(
+    app_handle: AppHandle,
+    tasks: State<'_, TaskManagerState>,
+    server_id: String,
+    new_version: String,
+    force: Option<bool>,
+) -> Result<ServerUpgradeResult, DockerError> {
+    let queue_key = server_queue(&server_id);
+    let app = app_handle.clone();
+
+    let task: TaskHandle<ServerUpgradeResult, DockerError> = tasks.spawn(app_handle, "docker_upgrade", &queue_key, move |ctx| async move {
+        let state = app.state::<DockerManagerState>();
+        let name = state.lock().await.get_servers().await
+            .into_iter()
+            .find(|server| server.id == server_id)
+            .map_or_else(|| server_id.clone(), |server| server.name);
+        ctx.set_subject(&name, Some(NotificationTarget::Server { id: server_id.clone() }));
+        let on_progress = |progress: ServerUpgradeProgress| {
+            let stage = match progress.stage {
+                ServerUpgradeStage::Backup => "Backing up the server",
+                ServerUpgradeStage::Pull => "Downloading the server image",
+                ServerUpgradeStage::Recreate => "Recreating the container",
+                ServerUpgradeStage::Verify => "Waiting for the server to start",
+                ServerUpgradeStage::RollBack => "Restoring the previous version",
+            };
+            ctx.set_progress(progress.percent, Some(stage.to_string()));
+            let _ = app.emit("server_upgrade_progress", serde_json::json!({
+                "task_id": ctx.id,
+                "server_id": server_id,
+                "progress": progress,
+            }));
+        };
+
+        // Not cancellable, stopping halfway could leave the server without a container.
+        // The manager is only locked to copy the server out, the upgrade can take many minutes.
+        let context = state.lock().await.server_context(&server_id).await?;
+        match context {
+            Some(context) => {
+                let result = context.upgrade(&new_version, force.unwrap_or(false), &on_progress).await;
+                state.lock().await.reset_query_support(&server_id);
+                result
+            }
+            None => state.lock().await.upgrade_server(&server_id, &new_version, force.unwrap_or(false), &on_progress).await,
+        }
+    });
+
+    task.wait().await
+}
+
+/// Export a server as a Compose file at `output_path` so it can run without the launcher
+///
+/// Passwords and tokens are written as `${...}` placeholders, with their values in
+/// the `.env` file next to it. Returns the path written.
+#[tauri::command]
+pub async fn export_server_compose(
+    state: State<'_, DockerManagerState>,
+    server_id: String,
+    output_path: String,
+) -> Result<String, DockerError> {
+    let manager = state.lock().await;
+    let path = manager.export_server_compose(&server_id, std::path::Path::new(&output_path)).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Whether the mock Docker backend is active
+#[tauri::command]
+pub async fn is_docker_mock(
+    state: State<'_, DockerManagerState>,
+) -> Result<bool, String> {
+    let manager = state.lock().await;
+    Ok(manager.is_mock())
+}
+
+#[tauri::command]
+pub async fn get_server_players(
+    state: State<'_, DockerManagerState>,
+    server_id: String,
+) -> Result<super::PlayerSample, DockerError> {
+    sample_players(&state, &server_id, false).await?
+        .ok_or_else(|| "Server is not running".into())
+}
+
+/// Sample a server's players, holding the manager lock only to copy the server out and record the result
+///
+/// With `only_running`, servers that aren't running or whose status can't be read give None.
+async fn sample_players(
+    state: &DockerManagerState,
+    server_id: &str,
+    only_running: bool,
+) -> Result<Option<super::PlayerSample>, DockerError> {
+    let (context, try_query) = {
+        let mut manager = state.lock().await;
+        match manager.server_context(server_id).await? {
+            Some(context) => (context, manager.query_enabled(server_id)),
+            // The mock answers from memory, so it is sampled under the lock
+            None => {
+                if only_running && !matches!(manager.get_server_status(server_id).await, Ok(super::ServerStatus::Running)) {
+                    return Ok(None);
+                }
+                return manager.sample_players(server_id).await.map(Some);
+            }
+        }
+    };
+    if only_running && !matches!(context.status().await, Ok(super::ServerStatus::Running)) {
+        return Ok(None);
+    }
+    let probe = context.probe_players(try_query).await;
+    state.lock().await.record_players(server_id, probe).map(Some)
+}
+
+#[tauri::command]
+pub async fn get_server_player_history(
+    state: State<'_, DockerManagerState>,
+    server_id: String,
+    hours: u32,
+) -> Result<Vec<super::PlayerSample>, String> {
+    let manager = state.lock().await;
+    Ok(manager.get_player_history(&server_id, hours))
+}
+
+/// Periodically refresh running servers and emit `server_stats` events with their players
+pub fn start_stats_poller(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(
+            tokio::time::Duration::from_secs(super::manager::STATS_POLL_INTERVAL_SECS)
+        );
+
+        loop {
+            interval.tick().await;
+            if crate::shutdown::is_shutting_down() {
+                break;
+            }
+
+            let state = app_handle.state::<DockerManagerState>();
+            let server_ids: Vec<String> = {
+                let manager = state.lock().await;
+                manager.get_servers().await.into_iter().map(|s| s.id).collect()
+            };
+
+            for server_id in server_ids {
+                // The status check and probes run without the manager lock
+                match sample_players(&state, &server_id, true).await {
+                    Ok(None) => {}
+                    Ok(Some(sample)) => {
+                        let _ = app_handle.emit("server_stats", serde_json::json!({
+                            "server_id": server_id,
+                            "online_players": sample.online_players,
+                            "max_players": sample.max_players,
+                            "players": sample.players,
+                            "timestamp": sample.timestamp,
+                            "mock": sample.mock,
+                        }));
+                    }
+                    Err(e) => {
+                        println!("Failed to sample players for server {}: {}", server_id, e);
+                    }
+                }
+            }
+        }
+    });
 }
\ No newline at end of file
@@ -0,0 +1,290 @@
+//! Exporting a server container as a Docker Compose file
+//!
+//! The running container is the source of truth: its image, environment, published
+//! ports, volumes, restart policy and limits are rendered into a Compose service so
+//! the server can be run without the launcher. Variables holding secrets are left
+//! as `${...}` placeholders and their values go into the `.env` file next to the
+//! Compose file, which Compose reads on its own. Named volumes are declared
+//! external, so the exported service keeps using the data the launcher created.
+
+use bollard::models::{ContainerInspectResponse, RestartPolicyNameEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A Compose file with the services and volumes an export needs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComposeFile {
+    pub services: BTreeMap<String, ComposeService>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub volumes: BTreeMap<String, ComposeVolume>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComposeService {
+    pub image: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stdin_open: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tty: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mem_limit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mem_reservation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memswap_limit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComposeVolume {
+    #[serde(default)]
+    pub external: bool,
+}
+
+/// A rendered service and the secret values its placeholders stand for
+#[derive(Debug, Clone)]
+pub struct ComposeExport {
+    pub file: ComposeFile,
+    /// `.env` entries, by placeholder name
+    pub secrets: BTreeMap<String, String>,
+}
+
+/// Variables whose values stay out of the Compose file
+fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    ["PASSWORD", "SECRET", "TOKEN", "API_KEY"].iter().any(|marker| key.contains(marker))
+}
+
+/// A Compose service name for a server name
+pub fn service_name(server_name: &str) -> String {
+    let name: String = server_name.trim().to_lowercase().chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    let name = name.trim_matches('-');
+    if name.is_empty() { "minecraft".to_string() } else { name.to_string() }
+}
+
+/// Compose size string for a byte count
+fn size(bytes: i64) -> String {
+    const MB: i64 = 1024 * 1024;
+    if bytes % MB == 0 { format!("{}m", bytes / MB) } else { format!("{}b", bytes) }
+}
+
+/// Render a server container as the Compose service `service`
+///
+/// `image_env` is the image's own environment; variables the container only has
+/// because the image sets them are left out.
+pub fn from_container(service: &str, inspect: &ContainerInspectResponse, image_env: &[String]) -> Result<ComposeExport, String> {
+    let config = inspect.config.clone().unwrap_or_default();
+    let host_config = inspect.host_config.clone().unwrap_or_default();
+    let placeholder_prefix = service.to_ascii_uppercase().replace('-', "_");
+
+    let mut secrets = BTreeMap::new();
+    let mut environment = BTreeMap::new();
+    for var in config.env.unwrap_or_default() {
+        if image_env.contains(&var) {
+            continue;
+        }
+        let (key, value) = var.split_once('=').unwrap_or((var.as_str(), ""));
+        let value = if is_secret(key) {
+            let placeholder = format!("{}_{}", placeholder_prefix, key);
+            secrets.insert(placeholder.clone(), value.to_string());
+            format!("${{{}}}", placeholder)
+        } else {
+            // Compose would read a literal `$` as the start of a variable
+            value.replace('$', "$$")
+        };
+        environment.insert(key.to_string(), value);
+    }
+
+    let mut ports = Vec::new();
+    for (container_port, bindings) in host_config.port_bindings.unwrap_or_default() {
+        for binding in bindings.unwrap_or_default() {
+            let host_port = binding.host_port.filter(|port| !port.is_empty());
+            let Some(host_port) = host_port else { continue };
+            ports.push(match binding.host_ip.filter(|ip| !ip.is_empty()) {
+                Some(ip) => format!("{}:{}:{}", ip, host_port, container_port),
+                None => format!("{}:{}", host_port, container_port),
+            });
+        }
+    }
+    ports.sort();
+
+    let mut volumes = BTreeMap::new();
+    let binds = host_config.binds.unwrap_or_default();
+    for bind in &binds {
+        let source = bind.split(':').next().unwrap_or_default();
+        // Anything not a path is a named volume the launcher created
+        if !source.is_empty() && !source.contains('/') && !source.contains('\\') {
+            volumes.insert(source.to_string(), ComposeVolume { external: true });
+        }
+    }
+
+    let restart = host_config.restart_policy.and_then(|policy| match policy.name? {
+        RestartPolicyNameEnum::EMPTY | RestartPolicyNameEnum::NO => None,
+        RestartPolicyNameEnum::ON_FAILURE => Some(match policy.maximum_retry_count.filter(|count| *count > 0) {
+            Some(count) => format!("on-failure:{}", count),
+            None => "on-failure".to_string(),
+        }),
+        name => Some(name.to_string()),
+    });
+
+    let service_config = ComposeService {
+        image: config.image.ok_or("Container has no image")?,
+        container_name: inspect.name.as_deref().map(|name| name.trim_start_matches('/').to_string()),
+        restart,
+        stdin_open: config.open_stdin.unwrap_or(false),
+        tty: config.tty.unwrap_or(false),
+        environment,
+        ports,
+        volumes: binds,
+        mem_limit: host_config.memory.filter(|bytes| *bytes > 0).map(size),
+        mem_reservation: host_config.memory_reservation.filter(|bytes| *bytes > 0).map(size),
+        memswap_limit: host_config.memory_swap.filter(|bytes| *bytes > 0).map(size),
+        cpus: host_config.nano_cpus.filter(|nanos| *nanos > 0).map(|nanos| nanos as f64 / 1e9),
+    };
+
+    Ok(ComposeExport {
+        file: ComposeFile { services: BTreeMap::from([(service.to_string(), service_config)]), volumes },
+        secrets,
+    })
+}
+
+pub fn to_yaml(file: &ComposeFile) -> Result<String, String> {
+    serde_yaml::to_string(file).map_err(|e| format!("Failed to render the Compose file: {}", e))
+}
+
+/// `existing` .env content with `secrets` set, other variables kept as they were
+pub fn merge_env_file(existing: &str, secrets: &BTreeMap<String, String>) -> String {
+    let mut lines: Vec<String> = existing.lines()
+        .filter(|line| {
+            let key = line.split_once('=').map(|(key, _)| key.trim());
+            !key.is_some_and(|key| secrets.contains_key(key))
+        })
+        .map(str::to_string)
+        .collect();
+    for (key, value) in secrets {
+        lines.push(format!("{}={}", key, value));
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+/// Write the Compose file to `path` and its secrets into the `.env` next to it
+pub async fn write(path: &Path, export: &ComposeExport) -> Result<(), String> {
+    let yaml = to_yaml(&export.file)?;
+    tokio::fs::write(path, yaml).await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    if export.secrets.is_empty() {
+        return Ok(());
+    }
+
+    let env_path = path.parent().unwrap_or(Path::new(".")).join(".env");
+    let existing = match tokio::fs::read_to_string(&env_path).await {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", env_path.display(), e)),
+    };
+    tokio::fs::write(&env_path, merge_env_file(&existing, &export.secrets)).await
+        .map_err(|e| format!("Failed to write {}: {}", env_path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&env_path, std::fs::Permissions::from_mode(0o600)).await
+            .map_err(|e| format!("Failed to restrict {}: {}", env_path.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::models::{ContainerConfig, HostConfig, PortBinding, RestartPolicy};
+    use std::collections::HashMap;
+
+    fn container() -> ContainerInspectResponse {
+        let binding = |ip: &str, port: &str| Some(vec![PortBinding { host_ip: Some(ip.to_string()), host_port: Some(port.to_string()) }]);
+        ContainerInspectResponse {
+            name: Some("/chai-server-Survival".to_string()),
+            config: Some(ContainerConfig {
+                image: Some("itzg/minecraft-server:java21".to_string()),
+                env: Some(vec![
+                    "EULA=TRUE".to_string(),
+                    "VERSION=1.21".to_string(),
+                    "RCON_PASSWORD=hunter2".to_string(),
+                    "MOTD=Costs $5".to_string(),
+                    "JAVA_HOME=/opt/java/openjdk".to_string(),
+                ]),
+                open_stdin: Some(true),
+                ..Default::default()
+            }),
+            host_config: Some(HostConfig {
+                binds: Some(vec![
+                    "/home/steve/.chai/survival:/data".to_string(),
+                    "chai-server-logs-Survival:/data/logs".to_string(),
+                ]),
+                port_bindings: Some(HashMap::from([
+                    ("25565/tcp".to_string(), binding("0.0.0.0", "25570")),
+                    ("25565/udp".to_string(), binding("0.0.0.0", "25570")),
+                    ("25575/tcp".to_string(), binding("127.0.0.1", "25580")),
+                ])),
+                restart_policy: Some(RestartPolicy { name: Some(RestartPolicyNameEnum::UNLESS_STOPPED), maximum_retry_count: None }),
+                memory: Some(3072 * 1024 * 1024),
+                memory_swap: Some(3072 * 1024 * 1024),
+                nano_cpus: Some(1_500_000_000),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_through_yaml() {
+        let service = service_name("Survival World!");
+        assert_eq!(service, "survival-world");
+        let export = from_container(&service, &container(), &["JAVA_HOME=/opt/java/openjdk".to_string()]).unwrap();
+        let yaml = to_yaml(&export.file).unwrap();
+
+        let parsed: ComposeFile = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, export.file);
+        let server = &parsed.services["survival-world"];
+        assert_eq!(server.image, "itzg/minecraft-server:java21");
+        assert_eq!(server.container_name.as_deref(), Some("chai-server-Survival"));
+        assert_eq!(server.restart.as_deref(), Some("unless-stopped"));
+        assert!(server.stdin_open);
+        assert_eq!(server.ports, vec!["0.0.0.0:25570:25565/tcp", "0.0.0.0:25570:25565/udp", "127.0.0.1:25580:25575/tcp"]);
+        assert_eq!(server.volumes.len(), 2);
+        assert_eq!(server.mem_limit.as_deref(), Some("3072m"));
+        assert_eq!(server.cpus, Some(1.5));
+        assert!(parsed.volumes["chai-server-logs-Survival"].external);
+        assert_eq!(parsed.volumes.len(), 1);
+
+        // Secrets only appear as placeholders, image variables not at all
+        assert_eq!(server.environment["RCON_PASSWORD"], "${SURVIVAL_WORLD_RCON_PASSWORD}");
+        assert_eq!(server.environment["MOTD"], "Costs $$5");
+        assert!(!server.environment.contains_key("JAVA_HOME"));
+        assert!(!yaml.contains("hunter2"));
+        assert_eq!(export.secrets["SURVIVAL_WORLD_RCON_PASSWORD"], "hunter2");
+    }
+
+    #[test]
+    fn env_file_keeps_unrelated_variables() {
+        let secrets = BTreeMap::from([("SURVIVAL_RCON_PASSWORD".to_string(), "new".to_string())]);
+        let merged = merge_env_file("# mine\nOTHER=1\nSURVIVAL_RCON_PASSWORD=old\n", &secrets);
+        assert_eq!(merged, "# mine\nOTHER=1\nSURVIVAL_RCON_PASSWORD=new\n");
+        assert_eq!(merge_env_file("", &secrets), "SURVIVAL_RCON_PASSWORD=new\n");
+    }
+}
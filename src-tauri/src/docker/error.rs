@@ -0,0 +1,78 @@
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+/// Error from a Docker operation, sent to the UI tagged by `kind`
+///
+/// Every variant carries a readable `message` so callers that only show text keep working.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DockerError {
+    /// The daemon stopped answering pings, nothing will work until it is reachable again
+    HostUnreachable { operation: String, seconds: u64, message: String },
+    /// The daemon is reachable but the operation ran past its time limit
+    Timeout { operation: String, seconds: u64, message: String },
+    /// The task running the operation was cancelled
+    Cancelled { operation: String, message: String },
+    /// TLS certificates are missing, unreadable or rejected by either side
+    Certificate { message: String },
+    /// The daemon couldn't be reached at all, e.g. a refused connection or an unknown host
+    Network { message: String },
+    Other { message: String },
+}
+
+impl DockerError {
+    pub fn host_unreachable(operation: &str, after: Duration) -> Self {
+        DockerError::HostUnreachable {
+            operation: operation.to_string(),
+            seconds: after.as_secs(),
+            message: format!("Docker host is unreachable: {} got no response within {}s", operation, after.as_secs()),
+        }
+    }
+
+    pub fn timeout(operation: &str, after: Duration) -> Self {
+        DockerError::Timeout {
+            operation: operation.to_string(),
+            seconds: after.as_secs(),
+            message: format!("Docker host is reachable but {} did not finish within {}s", operation, after.as_secs()),
+        }
+    }
+
+    pub fn cancelled(operation: &str) -> Self {
+        DockerError::Cancelled {
+            operation: operation.to_string(),
+            message: format!("Cancelled {}", operation),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            DockerError::HostUnreachable { message, .. }
+            | DockerError::Timeout { message, .. }
+            | DockerError::Cancelled { message, .. }
+            | DockerError::Certificate { message }
+            | DockerError::Network { message }
+            | DockerError::Other { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for DockerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for DockerError {}
+
+impl From<String> for DockerError {
+    fn from(message: String) -> Self {
+        DockerError::Other { message }
+    }
+}
+
+impl From<&str> for DockerError {
+    fn from(message: &str) -> Self {
+        DockerError::Other { message: message.to_string() }
+    }
+}
@@ -0,0 +1,283 @@
+//! Live console output of servers
+//!
+//! A stream follows the container's log with timestamps and emits every line as a
+//! `server_log` event. Lines go through a bounded queue to the emitter; when the
+//! frontend falls behind, lines are dropped and the next event says how many. The
+//! stream ends on its own when the container stops or the connection drops, and is
+//! announced with `server_log_stream_ended`. The last timestamp seen is kept, so a
+//! new stream for the same server picks up where the previous one stopped.
+
+use bollard::container::{LogOutput, LogsOptions};
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use futures::stream::StreamExt;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+use super::types::LogLevel;
+
+pub type LogStreamsState = Arc<LogStreams>;
+
+/// Lines waiting for the frontend before new ones are dropped
+const QUEUE_LINES: usize = 1000;
+
+/// Lines of history sent when a server's log is followed for the first time
+const INITIAL_TAIL: &str = "200";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Payload of `server_log`
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerLogLine {
+    pub server_id: String,
+    pub line: String,
+    /// RFC 3339, from Docker when it sent one
+    pub timestamp: String,
+    pub stream: OutputStream,
+    pub level: LogLevel,
+    /// Lines dropped since the previous event because the frontend fell behind
+    pub dropped: u64,
+}
+
+struct RunningStream {
+    generation: u64,
+    task: tokio::task::JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct Streams {
+    running: HashMap<String, RunningStream>,
+    last_seen: HashMap<String, DateTime<Utc>>,
+    next_generation: u64,
+}
+
+#[derive(Default)]
+pub struct LogStreams {
+    streams: Mutex<Streams>,
+}
+
+impl LogStreams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follow a container's log for `server_id`, false when a stream is already running
+    pub fn start(self: &Arc<Self>, app: AppHandle, server_id: &str, docker: Docker, container_id: String) -> bool {
+        let mut streams = self.streams.lock().unwrap();
+        if streams.running.get(server_id).is_some_and(|stream| !stream.task.is_finished()) {
+            return false;
+        }
+        streams.next_generation += 1;
+        let generation = streams.next_generation;
+        let since = streams.last_seen.get(server_id).copied();
+        let task = tokio::spawn(self.clone().follow(app, server_id.to_string(), docker, container_id, generation, since));
+        streams.running.insert(server_id.to_string(), RunningStream { generation, task });
+        true
+    }
+
+    /// Stop following a server's log, false when it wasn't followed
+    pub fn stop(&self, server_id: &str) -> bool {
+        match self.streams.lock().unwrap().running.remove(server_id) {
+            Some(stream) => {
+                stream.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget a removed server, including where its log was read up to
+    pub fn forget(&self, server_id: &str) {
+        self.stop(server_id);
+        self.streams.lock().unwrap().last_seen.remove(server_id);
+    }
+
+    fn seen(&self, server_id: &str, timestamp: DateTime<Utc>) {
+        let mut streams = self.streams.lock().unwrap();
+        let last = streams.last_seen.entry(server_id.to_string()).or_insert(timestamp);
+        *last = (*last).max(timestamp);
+    }
+
+    async fn follow(
+        self: Arc<Self>,
+        app: AppHandle,
+        server_id: String,
+        docker: Docker,
+        container_id: String,
+        generation: u64,
+        since: Option<DateTime<Utc>>,
+    ) {
+        let (sender, mut receiver) = mpsc::channel::<ServerLogLine>(QUEUE_LINES);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let emitter = tokio::spawn({
+            let (app, dropped) = (app.clone(), dropped.clone());
+            async move {
+                while let Some(mut line) = receiver.recv().await {
+                    line.dropped = dropped.swap(0, Ordering::Relaxed);
+                    let _ = app.emit("server_log", &line);
+                }
+            }
+        });
+
+        // Docker's `since` is in whole seconds, lines up to the last one seen are skipped below
+        let options = LogsOptions::<String> {
+            follow: true,
+            stdout: true,
+            stderr: true,
+            timestamps: true,
+            since: since.map(|since| since.timestamp()).unwrap_or(0),
+            tail: if since.is_some() { "all".to_string() } else { INITIAL_TAIL.to_string() },
+            ..Default::default()
+        };
+        println!("📜 Following the log of server {}", server_id);
+
+        let mut logs = docker.logs(&container_id, Some(options));
+        let mut splitter = LineSplitter::default();
+        let mut error = None;
+        let send = |raw: String, stream: OutputStream| -> bool {
+            let (timestamp, line) = parse_line(&raw);
+            if let Some(timestamp) = timestamp {
+                if since.is_some_and(|since| timestamp <= since) {
+                    return true;
+                }
+                self.seen(&server_id, timestamp);
+            }
+            let line = ServerLogLine {
+                server_id: server_id.clone(),
+                level: LogLevel::of(&line),
+                line,
+                timestamp: timestamp.unwrap_or_else(Utc::now).to_rfc3339(),
+                stream,
+                dropped: 0,
+            };
+            match sender.try_send(line) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    dropped.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            }
+        };
+
+        while let Some(chunk) = logs.next().await {
+            let (stream, message) = match chunk {
+                Ok(LogOutput::StdOut { message } | LogOutput::Console { message }) => (OutputStream::Stdout, message),
+                Ok(LogOutput::StdErr { message }) => (OutputStream::Stderr, message),
+                Ok(LogOutput::StdIn { .. }) => continue,
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break;
+                }
+            };
+            let lines = splitter.push(stream, &message);
+            if !lines.into_iter().all(|line| send(line, stream)) {
+                break;
+            }
+        }
+        for (stream, line) in splitter.finish() {
+            send(line, stream);
+        }
+        drop(sender);
+        let _ = emitter.await;
+
+        match &error {
+            Some(error) => println!("🔌 Log stream of server {} ended: {}", server_id, error),
+            None => println!("📜 Log stream of server {} ended", server_id),
+        }
+        {
+            let mut streams = self.streams.lock().unwrap();
+            if streams.running.get(&server_id).is_some_and(|stream| stream.generation == generation) {
+                streams.running.remove(&server_id);
+            }
+        }
+        let _ = app.emit("server_log_stream_ended", serde_json::json!({
+            "server_id": server_id,
+            "error": error,
+        }));
+    }
+}
+
+/// Split a timestamped log line into its timestamp and the line the server printed
+fn parse_line(raw: &str) -> (Option<DateTime<Utc>>, String) {
+    let raw = raw.trim_end_matches('\r');
+    if let Some((timestamp, line)) = raw.split_once(' ') {
+        if let Ok(timestamp) = DateTime::parse_from_rfc3339(timestamp) {
+            return (Some(timestamp.with_timezone(&Utc)), line.to_string());
+        }
+    }
+    (None, raw.to_string())
+}
+
+/// Reassembles lines from log frames, which may hold several lines or part of one
+#[derive(Default)]
+pub(super) struct LineSplitter {
+    stdout: String,
+    stderr: String,
+}
+
+impl LineSplitter {
+    fn buffer(&mut self, stream: OutputStream) -> &mut String {
+        match stream {
+            OutputStream::Stdout => &mut self.stdout,
+            OutputStream::Stderr => &mut self.stderr,
+        }
+    }
+
+    /// Complete lines of `stream` after adding `bytes` to it
+    pub(super) fn push(&mut self, stream: OutputStream, bytes: &[u8]) -> Vec<String> {
+        let buffer = self.buffer(stream);
+        buffer.push_str(&String::from_utf8_lossy(bytes));
+        let Some(end) = buffer.rfind('\n') else {
+            return Vec::new();
+        };
+        let rest = buffer.split_off(end + 1);
+        let complete = std::mem::replace(buffer, rest);
+        complete.lines().map(str::to_string).collect()
+    }
+
+    /// Unterminated lines left when the log ends
+    fn finish(self) -> Vec<(OutputStream, String)> {
+        [(OutputStream::Stdout, self.stdout), (OutputStream::Stderr, self.stderr)]
+            .into_iter()
+            .filter(|(_, line)| !line.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassembles_lines_per_stream() {
+        let mut splitter = LineSplitter::default();
+        assert!(splitter.push(OutputStream::Stdout, b"2024-05-01T10:00:00.5Z [Server thread/INFO]: Don").is_empty());
+        assert_eq!(splitter.push(OutputStream::Stderr, b"2024-05-01T10:00:01Z oops\n"), vec!["2024-05-01T10:00:01Z oops"]);
+        assert_eq!(
+            splitter.push(OutputStream::Stdout, b"e!\r\n2024-05-01T10:00:02Z a\n2024-05-01T10:00:03Z b"),
+            vec!["2024-05-01T10:00:00.5Z [Server thread/INFO]: Done!", "2024-05-01T10:00:02Z a"]
+        );
+        assert_eq!(splitter.finish(), vec![(OutputStream::Stdout, "2024-05-01T10:00:03Z b".to_string())]);
+    }
+
+    #[test]
+    fn separates_docker_timestamps() {
+        let (timestamp, line) = parse_line("2024-05-01T10:00:00.123456789Z [Server thread/INFO]: Done!\r");
+        assert_eq!(timestamp.unwrap().timestamp_millis(), 1714557600123);
+        assert_eq!(line, "[Server thread/INFO]: Done!");
+
+        let (timestamp, line) = parse_line("no timestamp here");
+        assert!(timestamp.is_none());
+        assert_eq!(line, "no timestamp here");
+    }
+}
@@ -1,29 +1,91 @@
+use super::compose;
+use super::error::DockerError;
+use super::properties::{self, PropertiesFile};
+use super::resources::{self, ServerResourcesUpdate};
+use super::ports::{self, PortsInUse};
+use super::templates::ServerType;
+use super::upgrade::{self, ServerUpgradeProgress, ServerUpgradeResult, ServerUpgradeStage};
+use super::query::{self, QueryResponse, QuerySupport};
+use super::rcon::{self, RconPool, RconRoute};
+use super::ssh::SshTunnel;
+use super::world_upload::{self, WorldUploadProgress, WorldUploadResult, WorldUploadStage};
+use super::timeouts::{classify_timeout, ping, with_timeout};
 use super::types::*;
 use crate::minecraft::MinecraftInstance;
-use crate::storage::StorageManager;
+use crate::storage::{DockerTimeouts, StorageState};
 use bollard::{Docker, API_DEFAULT_VERSION};
 use bollard::container::{
-    Config, CreateContainerOptions, RemoveContainerOptions, StartContainerOptions, 
-    StopContainerOptions, ListContainersOptions
+    Config, CreateContainerOptions, InspectContainerOptions, RemoveContainerOptions, StartContainerOptions,
+    StatsOptions, StopContainerOptions, ListContainersOptions, WaitContainerOptions
 };
 use bollard::models::{HostConfig, PortBinding, RestartPolicy, RestartPolicyNameEnum};
 use bollard::image::ListImagesOptions;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
 use uuid;
 
+/// Seconds Docker waits for a server to shut down before killing it
+const STOP_GRACE_SECS: i64 = 30;
+
+/// Interval between periodic `server_stats` samples
+pub const STATS_POLL_INTERVAL_SECS: u64 = 30;
+
+/// How long console output is collected after writing a command to it
+const CONSOLE_OUTPUT_WINDOW: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Number of player samples kept per server (24 hours at the polling interval)
+const PLAYER_HISTORY_CAPACITY: usize = (24 * 60 * 60 / STATS_POLL_INTERVAL_SECS) as usize;
+
+fn command_response(output: String) -> String {
+    if output.trim().is_empty() {
+        "Command executed successfully".to_string()
+    } else {
+        output
+    }
+}
+
+/// RCON password from the secret store, older servers kept theirs in the environment
+async fn rcon_password(server: &ServerInstance) -> String {
+    match crate::secrets::get(&rcon::password_key(&server.id)).await {
+        Ok(Some(password)) => return password,
+        Ok(None) => {}
+        Err(e) => eprintln!("⚠️  Failed to read the RCON password of server {}: {}", server.id, e),
+    }
+    server.environment_vars.get("RCON_PASSWORD")
+        .cloned()
+        .unwrap_or_else(|| rcon::LEGACY_PASSWORD.to_string())
+}
+
 pub struct DockerManager {
     connections: HashMap<String, Docker>,
-    storage: StorageManager,
+    /// SSH tunnels of `Ssh` connections, by connection id
+    tunnels: HashMap<String, SshTunnel>,
+    storage: StorageState,
+    player_history: HashMap<String, VecDeque<PlayerSample>>,
+    query_support: QuerySupport,
+    timeouts: DockerTimeouts,
+    rcon: Arc<tokio::sync::Mutex<RconPool>>,
+}
+
+/// Stopping waits for the server to save, so it gets the grace period on top of the request timeout
+fn stop_budget(timeouts: &DockerTimeouts) -> std::time::Duration {
+    timeouts.request() + std::time::Duration::from_secs(STOP_GRACE_SECS as u64)
 }
 
 impl DockerManager {
     pub async fn new() -> Result<Self, String> {
-        let storage = StorageManager::new().await
+        let storage = crate::storage::shared().await
             .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+        let timeouts = storage.read().await.get_settings().docker_timeouts.clone();
         
         let mut manager = Self {
             connections: HashMap::new(),
+            tunnels: HashMap::new(),
             storage,
+            player_history: HashMap::new(),
+            query_support: QuerySupport::default(),
+            timeouts,
+            rcon: Arc::new(tokio::sync::Mutex::new(RconPool::default())),
         };
         
         // Load and reconnect to saved Docker connections
@@ -32,24 +94,55 @@ impl DockerManager {
         Ok(manager)
     }
 
+    /// Copy of a stored server, so the storage lock isn't held across Docker calls
+    async fn server(&self, server_id: &str) -> Result<ServerInstance, DockerError> {
+        self.storage.read().await
+            .get_server(server_id)
+            .cloned()
+            .ok_or_else(|| "Server not found".into())
+    }
+
+    /// Copy out what Docker calls for a server need, so they can run without the manager lock
+    pub async fn context(&self, server_id: &str) -> Result<ServerContext, DockerError> {
+        let server = self.server(server_id).await?;
+        let docker = self.connections.get(&server.docker_connection_id)
+            .ok_or("Docker connection not found")?
+            .clone();
+        let host = self.server_host(&server).await?;
+        let remote = self.is_remote(&server.docker_connection_id).await;
+        Ok(ServerContext {
+            server,
+            docker,
+            host,
+            remote,
+            timeouts: self.timeouts.clone(),
+            storage: self.storage.clone(),
+            rcon: self.rcon.clone(),
+        })
+    }
+
     /// Reconnect to all saved Docker connections
     async fn reconnect_saved_connections(&mut self) -> Result<(), String> {
-        let connections = self.storage.get_docker_connections().iter().cloned().cloned().collect::<Vec<_>>();
+        let connections = self.get_docker_connections().await;
         
         for connection in connections {
-            if let Ok(docker) = self.create_docker_connection(&connection).await {
+            if let Ok((docker, tunnel)) = self.create_docker_connection(&connection).await {
                 self.connections.insert(connection.id.clone(), docker);
+                if let Some(tunnel) = tunnel {
+                    self.tunnels.insert(connection.id.clone(), tunnel);
+                }
                 // Update connection status to connected in storage
                 let mut updated_connection = connection;
                 updated_connection.is_connected = true;
-                if let Err(e) = self.storage.update_docker_connection(updated_connection).await {
+                if let Err(e) = self.storage.write().await.update_docker_connection(updated_connection).await {
                     eprintln!("Failed to update connection status: {}", e);
                 }
             } else {
+                super::tls::remove_files(&connection.id).await;
                 // Mark connection as disconnected if we can't connect
                 let mut updated_connection = connection;
                 updated_connection.is_connected = false;
-                if let Err(e) = self.storage.update_docker_connection(updated_connection).await {
+                if let Err(e) = self.storage.write().await.update_docker_connection(updated_connection).await {
                     eprintln!("Failed to update connection status: {}", e);
                 }
             }
@@ -59,64 +152,80 @@ impl DockerManager {
     }
 
     /// Create a Docker connection based on the connection type
-    async fn create_docker_connection(&self, connection: &DockerConnection) -> Result<Docker, String> {
-        match connection.connection_type {
+    ///
+    /// Connecting is lazy, the client only talks to the daemon on the first request.
+    /// SSH connections also return the tunnel the client goes through.
+    async fn create_docker_connection(&self, connection: &DockerConnection) -> Result<(Docker, Option<SshTunnel>), DockerError> {
+        let mut tunnel = None;
+        let docker = match connection.connection_type {
             DockerConnectionType::Local => {
                 self.connect_local_docker()
-                    .map_err(|e| format!("Failed to connect to local Docker: {}", e))
+                    .map_err(|e| format!("Failed to connect to local Docker: {}", e).into())
             },
             DockerConnectionType::WindowsNamedPipe => {
                 #[cfg(target_os = "windows")]
                 {
                     Docker::connect_with_named_pipe_defaults()
-                        .map_err(|e| format!("Failed to connect via Windows named pipe: {}", e))
+                        .map_err(|e| format!("Failed to connect via Windows named pipe: {}", e).into())
                 }
                 #[cfg(not(target_os = "windows"))]
                 {
-                    Err("Windows named pipe connection is only available on Windows".to_string())
+                    Err("Windows named pipe connection is only available on Windows".into())
                 }
             },
             DockerConnectionType::UnixSocket => {
                 #[cfg(not(target_os = "windows"))]
                 {
                     Docker::connect_with_socket_defaults()
-                        .map_err(|e| format!("Failed to connect via Unix socket: {}", e))
+                        .map_err(|e| format!("Failed to connect via Unix socket: {}", e).into())
                 }
                 #[cfg(target_os = "windows")]
                 {
-                    Err("Unix socket connection is not available on Windows".to_string())
+                    Err("Unix socket connection is not available on Windows".into())
                 }
             },
-            DockerConnectionType::Remote => {
-                let host = if let Some(port) = connection.port {
-                    format!("tcp://{}:{}", connection.host, port)
-                } else {
-                    format!("tcp://{}:2376", connection.host)
-                };
-                Docker::connect_with_http(&host, 120, API_DEFAULT_VERSION)
-                    .map_err(|e| format!("Failed to connect to remote Docker: {}", e))
-            },
-            DockerConnectionType::Swarm => {
-                let host = if let Some(port) = connection.port {
-                    format!("tcp://{}:{}", connection.host, port)
-                } else {
-                    format!("tcp://{}:2376", connection.host)
-                };
-                Docker::connect_with_http(&host, 120, API_DEFAULT_VERSION)
-                    .map_err(|e| format!("Failed to connect to Docker Swarm: {}", e))
+            DockerConnectionType::Remote | DockerConnectionType::Swarm => {
+                let kind = if matches!(connection.connection_type, DockerConnectionType::Swarm) { "Docker Swarm" } else { "remote Docker" };
+                let endpoint = connection.endpoint();
+                match &connection.tls {
+                    Some(tls) => {
+                        let files = super::tls::files(connection, tls).await?;
+                        Docker::connect_with_ssl(&endpoint, &files.key, &files.cert, &files.ca, self.timeouts.request_secs, API_DEFAULT_VERSION)
+                            .map_err(|e| super::tls::classify(&format!("Failed to connect to {} over TLS", kind), &e))
+                    }
+                    None => Docker::connect_with_http(&endpoint, self.timeouts.request_secs, API_DEFAULT_VERSION)
+                        .map_err(|e| format!("Failed to connect to {}: {}", kind, e).into()),
+                }
             }
-        }
+            DockerConnectionType::Ssh => {
+                let opened = SshTunnel::open(connection).await?;
+                let docker = Docker::connect_with_http(&opened.endpoint(), self.timeouts.request_secs, API_DEFAULT_VERSION)
+                    .map_err(|e| format!("Failed to connect through the SSH tunnel: {}", e).into());
+                tunnel = Some(opened);
+                docker
+            }
+        }?;
+
+        Ok((docker.with_timeout(self.timeouts.request()), tunnel))
     }
 
     /// Test connection to Docker
-    pub async fn test_connection(&self, connection: &DockerConnection) -> Result<bool, String> {
-        let docker = self.create_docker_connection(connection).await?;
+    ///
+    /// Certificate problems fail as `DockerError::Certificate` and unreachable hosts
+    /// as `DockerError::Network`.
+    pub async fn test_connection(&self, connection: &DockerConnection) -> Result<bool, DockerError> {
+        let (docker, tunnel) = self.create_docker_connection(connection).await?;
 
         // Test the connection by pinging
-        match docker.ping().await {
-            Ok(_) => Ok(true),
-            Err(e) => Err(format!("Docker ping failed: {}", e)),
+        let pinged = ping(&docker, &self.timeouts).await;
+        if let Some(tunnel) = tunnel {
+            tunnel.close().await;
         }
+        if !self.connections.contains_key(&connection.id) {
+            super::tls::remove_files(&connection.id).await;
+        }
+        pinged?;
+        Ok(true)
     }
 
     /// Connect to local Docker with platform-specific socket handling
@@ -143,51 +252,120 @@ impl DockerManager {
     }
 
     /// Add a Docker connection
-    pub async fn add_connection(&mut self, mut connection: DockerConnection) -> Result<(), String> {
-        // Test connection first
-        if !self.test_connection(&connection).await? {
-            return Err("Failed to establish Docker connection".to_string());
+    pub async fn add_connection(&mut self, mut connection: DockerConnection) -> Result<(), DockerError> {
+        // Create the client and make sure the daemon answers before saving
+        let (docker, tunnel) = self.create_docker_connection(&connection).await?;
+        if let Err(e) = ping(&docker, &self.timeouts).await {
+            if let Some(tunnel) = tunnel {
+                tunnel.close().await;
+            }
+            super::tls::remove_files(&connection.id).await;
+            return Err(e);
         }
 
-        // Create Docker client
-        let docker = self.create_docker_connection(&connection).await?;
+        // PEM text goes to the secret store, the saved connection only records that
+        super::tls::store_pem(&mut connection).await?;
 
         // Mark as connected and save to storage
         connection.is_connected = true;
-        self.storage.add_docker_connection(connection.clone()).await
+        self.storage.write().await.add_docker_connection(connection.clone()).await
             .map_err(|e| format!("Failed to save Docker connection: {}", e))?;
 
         // Store active connection
+        if let Some(tunnel) = tunnel {
+            self.tunnels.insert(connection.id.clone(), tunnel);
+        }
         self.connections.insert(connection.id.clone(), docker);
         Ok(())
     }
 
-    /// Deploy a server for a Minecraft instance
+    /// Close SSH tunnels and delete written out certificates before the launcher exits
+    pub async fn shutdown(&mut self) {
+        for (_, tunnel) in self.tunnels.drain() {
+            tunnel.close().await;
+        }
+        super::tls::remove_all_files().await;
+    }
+
+    /// Deploy a server for a Minecraft instance, reporting image pull progress to `on_progress`
     pub async fn deploy_server(
         &mut self,
         request: ServerDeploymentRequest,
         minecraft_instance: &MinecraftInstance,
-    ) -> Result<ServerInstance, String> {
+        on_progress: &(dyn Fn(PullProgress) + Send + Sync),
+    ) -> Result<ServerInstance, DockerError> {
+        let prepared = self.prepare_deployment(request, minecraft_instance).await?;
+        prepared.download(on_progress).await?;
+        self.create_deployment(prepared, minecraft_instance).await
+    }
+
+    /// Check and plan a deployment, the first step of `deploy_server`
+    ///
+    /// The returned deployment downloads its image without the manager, then
+    /// `create_deployment` finishes it.
+    pub async fn prepare_deployment(
+        &self,
+        request: ServerDeploymentRequest,
+        minecraft_instance: &MinecraftInstance,
+    ) -> Result<PreparedDeployment, DockerError> {
+        let docker = self.connections.get(&request.docker_connection_id)
+            .ok_or("Docker connection not found")?
+            .clone();
+
+        let plan = super::templates::plan_deployment(&request, &minecraft_instance.version)?;
+        for warning in resources::validate(&request.resources, request.memory_limit)? {
+            println!("⚠️  {}: {}", request.name, warning);
+        }
+        // Fail before the download when the port can't be had
+        self.allocate_port(&request, &docker).await?;
+
+        Ok(PreparedDeployment { request, plan, docker, timeouts: self.timeouts.clone() })
+    }
+
+    /// Create, start and save the container of a downloaded deployment
+    pub async fn create_deployment(
+        &mut self,
+        prepared: PreparedDeployment,
+        minecraft_instance: &MinecraftInstance,
+    ) -> Result<ServerInstance, DockerError> {
+        let PreparedDeployment { request, plan, .. } = prepared;
         let docker = self.connections.get(&request.docker_connection_id)
             .ok_or("Docker connection not found")?;
 
-        // Use itzg's minecraft-server image instead of building our own
-        let image_name = "itzg/minecraft-server:latest".to_string();
-        self.ensure_itzg_image(docker, &image_name).await?;
+        // Another deployment may have taken the port during the download
+        let port = self.allocate_port(&request, docker).await?;
+        if port != request.port {
+            println!("🔌 Port {} is taken, {} gets port {}", request.port, request.name, port);
+        }
+        println!("🐳 Deploying {} as a {} {} server", request.name, plan.template.name, plan.minecraft_version);
 
         // Prepare unique volume mount for this server
         use std::fs;
         use std::path::Path;
-        let server_dir = minecraft_instance.game_dir.join(&request.name);
-        if !server_dir.exists() {
-            if let Err(e) = fs::create_dir_all(&server_dir) {
-                return Err(format!("Failed to create server directory: {}", e));
+        let mut binds = if self.is_remote(&request.docker_connection_id).await {
+            // A remote daemon can't see the launcher's folders, so the data lives in a volume there
+            vec![format!("chai-server-data-{}:/data", request.name)]
+        } else {
+            let server_dir = minecraft_instance.game_dir.join(&request.name);
+            if !server_dir.exists() {
+                if let Err(e) = fs::create_dir_all(&server_dir) {
+                    return Err(format!("Failed to create server directory: {}", e).into());
+                }
             }
-        }
-        let server_dir_str = server_dir.to_string_lossy().to_string();
-        let mut binds = vec![
-            format!("{}:/data", server_dir_str),
-        ];
+
+            // Custom entrypoints ignore the RCON_* variables, so enable it in the file as well
+            let properties_path = server_dir.join("server.properties");
+            let existing = fs::read(&properties_path).map(|bytes| properties::decode(&bytes)).unwrap_or_default();
+            let mut server_properties = PropertiesFile::parse(&existing);
+            server_properties.set("enable-rcon", "true");
+            server_properties.set("rcon.port", &rcon::CONTAINER_RCON_PORT.to_string());
+            server_properties.set("rcon.password", &plan.rcon_password);
+            fs::write(&properties_path, server_properties.to_string())
+                .map_err(|e| format!("Failed to write server.properties: {}", e))?;
+
+            let server_dir_str = server_dir.to_string_lossy().to_string();
+            vec![format!("{}:/data", server_dir_str)]
+        };
 
         // Add logs volume (optional for itzg image)
         binds.push(format!("chai-server-logs-{}:/data/logs", request.name));
@@ -198,7 +376,15 @@ impl DockerManager {
             "25565/tcp".to_string(),
             Some(vec![PortBinding {
                 host_ip: Some("0.0.0.0".to_string()),
-                host_port: Some(request.port.to_string()),
+                host_port: Some(port.to_string()),
+            }]),
+        );
+        // Query port shares the game port number over UDP
+        port_bindings.insert(
+            "25565/udp".to_string(),
+            Some(vec![PortBinding {
+                host_ip: Some("0.0.0.0".to_string()),
+                host_port: Some(port.to_string()),
             }]),
         );
         // RCON port (25575 is default for itzg's image)
@@ -206,49 +392,33 @@ impl DockerManager {
             "25575/tcp".to_string(),
             Some(vec![PortBinding {
                 host_ip: Some("127.0.0.1".to_string()), // Only localhost for security
-                host_port: Some((request.port + 10).to_string()), // Offset RCON port
+                host_port: Some(rcon::host_port(port).to_string()),
             }]),
         );
 
         // Configure container
-        let host_config = Some(HostConfig {
+        let mut host_config = HostConfig {
             binds: Some(binds),
             port_bindings: Some(port_bindings),
-            memory: Some((request.memory_limit * 1024 * 1024).try_into().unwrap()), // Convert MB to bytes
             restart_policy: Some(RestartPolicy {
                 name: Some(RestartPolicyNameEnum::UNLESS_STOPPED),
                 maximum_retry_count: None,
             }),
             ..Default::default()
-        });
-
-        // Environment variables for itzg's minecraft-server
-        let mut env_vars = vec![
-            "EULA=TRUE".to_string(),
-            format!("MAX_PLAYERS={}", request.max_players),
-            format!("MOTD={}", request.name),
-            format!("VERSION={}", minecraft_instance.version),
-            format!("MEMORY={}M", request.memory_limit),
-            "TYPE=VANILLA".to_string(),
-            "ONLINE_MODE=TRUE".to_string(),
-            // Enable RCON for command execution
-            "ENABLE_RCON=true".to_string(),
-            "RCON_PASSWORD=minecraft".to_string(), // Default password
-            "RCON_PORT=25575".to_string(),
-        ];
-        
-        // Add custom environment variables
-        for (key, value) in &request.environment_vars {
-            env_vars.push(format!("{}={}", key, value));
-        }
+        };
+        resources::apply(&request.resources, &mut host_config);
 
         let config = Config {
-            image: Some(image_name),
-            env: Some(env_vars),
-            host_config,
+            image: Some(plan.image),
+            env: Some(plan.env),
+            labels: Some(plan.labels),
+            host_config: Some(host_config),
+            // Lets commands fall back to the console when RCON is unreachable
+            open_stdin: Some(true),
             exposed_ports: Some({
                 let mut ports = HashMap::new();
                 ports.insert("25565/tcp".to_string(), HashMap::new()); // Minecraft server port
+                ports.insert("25565/udp".to_string(), HashMap::new()); // Query port
                 ports.insert("25575/tcp".to_string(), HashMap::new()); // RCON port
                 ports
             }),
@@ -262,140 +432,68 @@ impl DockerManager {
             platform: None,
         };
 
-        let container = docker.create_container(Some(options), config)
-            .await
-            .map_err(|e| format!("Failed to create container: {}", e))?;
+        let container = with_timeout(docker, &self.timeouts, "create container", self.timeouts.request(),
+            docker.create_container(Some(options), config)).await?;
 
         // Start container if auto_start is enabled
         if request.auto_start {
-            docker.start_container(&container.id, None::<StartContainerOptions<String>>)
-                .await
-                .map_err(|e| format!("Failed to start container: {}", e))?;
+            with_timeout(docker, &self.timeouts, "start container", self.timeouts.request(),
+                docker.start_container(&container.id, None::<StartContainerOptions<String>>)).await?;
         }
 
         // Create server instance
-        let server = ServerInstance {
+        let mut server = ServerInstance {
             id: uuid::Uuid::new_v4().to_string(),
             name: request.name,
             minecraft_instance_id: request.minecraft_instance_id,
             docker_connection_id: request.docker_connection_id,
             container_id: Some(container.id),
             status: if request.auto_start { ServerStatus::Starting } else { ServerStatus::Stopped },
-            port: request.port,
+            port,
             max_players: request.max_players,
             memory_limit: request.memory_limit,
             created_at: chrono::Utc::now(),
             last_started: if request.auto_start { Some(chrono::Utc::now()) } else { None },
             environment_vars: request.environment_vars,
+            rcon_port: Some(rcon::host_port(port)),
+            resources: request.resources,
+            last_upgrade: None,
+            mock: false,
         };
 
+        if let Err(e) = crate::secrets::set(&rcon::password_key(&server.id), &plan.rcon_password).await {
+            eprintln!("⚠️  Failed to store the RCON password securely, keeping it with the server: {}", e);
+            server.environment_vars.insert("RCON_PASSWORD".to_string(), plan.rcon_password.clone());
+        }
+
         // Save server to persistent storage
-        self.storage.add_server(server.clone()).await
+        self.storage.write().await.add_server(server.clone()).await
             .map_err(|e| format!("Failed to save server: {}", e))?;
 
         Ok(server)
     }
 
     /// Start a server
-    pub async fn start_server(&mut self, server_id: &str) -> Result<(), String> {
-        let mut server = self.storage.get_server(server_id)
-            .ok_or("Server not found")?.clone();
-
-        let docker = self.connections.get(&server.docker_connection_id)
-            .ok_or("Docker connection not found")?;
-
-        if let Some(container_id) = &server.container_id {
-            docker.start_container(container_id, None::<StartContainerOptions<String>>)
-                .await
-                .map_err(|e| format!("Failed to start container: {}", e))?;
-
-            server.status = ServerStatus::Starting;
-            server.last_started = Some(chrono::Utc::now());
-            
-            // Save updated server status
-            self.storage.update_server(server).await
-                .map_err(|e| format!("Failed to update server status: {}", e))?;
-        }
-
+    pub async fn start_server(&mut self, server_id: &str) -> Result<(), DockerError> {
+        self.context(server_id).await?.start().await?;
+        // Configuration may have changed while stopped, so probe query again
+        self.query_support.reset(server_id);
         Ok(())
     }
 
     /// Stop a server
-    pub async fn stop_server(&mut self, server_id: &str) -> Result<(), String> {
-        let mut server = self.storage.get_server(server_id)
-            .ok_or("Server not found")?.clone();
-
-        let docker = self.connections.get(&server.docker_connection_id)
-            .ok_or("Docker connection not found")?;
-
-        if let Some(container_id) = &server.container_id {
-            let options = StopContainerOptions { t: 30 }; // 30 second timeout
-            docker.stop_container(container_id, Some(options))
-                .await
-                .map_err(|e| format!("Failed to stop container: {}", e))?;
-
-            server.status = ServerStatus::Stopping;
-            
-            // Save updated server status
-            self.storage.update_server(server).await
-                .map_err(|e| format!("Failed to update server status: {}", e))?;
-        }
-
-        Ok(())
+    pub async fn stop_server(&mut self, server_id: &str) -> Result<(), DockerError> {
+        self.context(server_id).await?.stop().await
     }
 
     /// Get server status
-    pub async fn get_server_status(&mut self, server_id: &str) -> Result<ServerStatus, String> {
-        let server = self.storage.get_server(server_id)
-            .ok_or("Server not found")?;
-
-        let docker = self.connections.get(&server.docker_connection_id)
-            .ok_or("Docker connection not found")?;
-
-        if let Some(container_id) = &server.container_id {
-            let containers = docker.list_containers(Some(ListContainersOptions::<String> {
-                all: true,
-                filters: {
-                    let mut filters = HashMap::new();
-                    filters.insert("id".to_string(), vec![container_id.clone()]);
-                    filters
-                },
-                ..Default::default()
-            }))
-            .await
-            .map_err(|e| format!("Failed to list containers: {}", e))?;
-
-            if let Some(container) = containers.first() {
-                let status = match container.state.as_deref() {
-                    Some("running") => ServerStatus::Running,
-                    Some("exited") => ServerStatus::Stopped,
-                    Some("created") => ServerStatus::Stopped,
-                    Some("restarting") => ServerStatus::Starting,
-                    _ => ServerStatus::Unknown,
-                };
-
-                // Update status in storage if it's different
-                if status != server.status {
-                    let mut updated_server = server.clone();
-                    updated_server.status = status.clone();
-                    if let Err(e) = self.storage.update_server(updated_server).await {
-                        eprintln!("Failed to update server status in storage: {}", e);
-                    }
-                }
-
-                Ok(status)
-            } else {
-                Ok(ServerStatus::Unknown)
-            }
-        } else {
-            Ok(ServerStatus::Unknown)
-        }
+    pub async fn get_server_status(&mut self, server_id: &str) -> Result<ServerStatus, DockerError> {
+        self.context(server_id).await?.status().await
     }
 
     /// Remove a server
-    pub async fn remove_server(&mut self, server_id: &str) -> Result<(), String> {
-        let server = self.storage.get_server(server_id)
-            .ok_or("Server not found")?.clone();
+    pub async fn remove_server(&mut self, server_id: &str) -> Result<(), DockerError> {
+        let server = self.server(server_id).await?;
 
         let container_id = server.container_id.clone();
         let docker_connection_id = server.docker_connection_id.clone();
@@ -404,8 +502,9 @@ impl DockerManager {
         if let Some(container_id) = container_id {
             if let Some(docker) = self.connections.get(&docker_connection_id) {
                 // Stop the server first
-                let stop_options = StopContainerOptions { t: 30 };
-                let _ = docker.stop_container(&container_id, Some(stop_options)).await;
+                let stop_options = StopContainerOptions { t: STOP_GRACE_SECS };
+                let _ = with_timeout(docker, &self.timeouts, "stop container", stop_budget(&self.timeouts),
+                    docker.stop_container(&container_id, Some(stop_options))).await;
                 
                 // Wait a moment for container to stop
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
@@ -417,38 +516,52 @@ impl DockerManager {
                     ..Default::default()
                 };
 
-                docker.remove_container(&container_id, Some(options))
-                    .await
-                    .map_err(|e| format!("Failed to remove container: {}", e))?;
+                with_timeout(docker, &self.timeouts, "remove container", self.timeouts.request(),
+                    docker.remove_container(&container_id, Some(options))).await?;
             }
         }
 
         // Remove from storage
-        self.storage.remove_server(server_id).await
+        self.storage.write().await.remove_server(server_id).await
             .map_err(|e| format!("Failed to remove server from storage: {}", e))?;
 
+        self.player_history.remove(server_id);
+        self.query_support.reset(server_id);
+        self.rcon.lock().await.disconnect(server_id);
+        if let Err(e) = crate::secrets::delete(&rcon::password_key(server_id)).await {
+            eprintln!("⚠️  Failed to remove the RCON password of server {}: {}", server_id, e);
+        }
+
         Ok(())
     }
 
     /// Get all servers
-    pub fn get_servers(&self) -> Vec<&ServerInstance> {
-        self.storage.get_servers()
+    pub async fn get_servers(&self) -> Vec<ServerInstance> {
+        self.storage.read().await.get_servers().into_iter().cloned().collect()
     }
 
     /// Get servers for a specific Minecraft instance
-    pub fn get_servers_for_instance(&self, instance_id: &str) -> Vec<&ServerInstance> {
-        self.storage.get_servers_for_instance(instance_id)
+    pub async fn get_servers_for_instance(&self, instance_id: &str) -> Vec<ServerInstance> {
+        self.storage.read().await.get_servers_for_instance(instance_id).into_iter().cloned().collect()
     }
 
     /// Get Docker connections
-    pub fn get_docker_connections(&self) -> Vec<&DockerConnection> {
-        self.storage.get_docker_connections()
+    pub async fn get_docker_connections(&self) -> Vec<DockerConnection> {
+        let mut connections: Vec<DockerConnection> = self.storage.read().await.get_docker_connections().into_iter().cloned().collect();
+        for connection in &mut connections {
+            if matches!(connection.connection_type, DockerConnectionType::Ssh) {
+                connection.tunnel = Some(match self.tunnels.get(&connection.id) {
+                    Some(tunnel) => tunnel.status(),
+                    None => TunnelStatus { state: TunnelState::Failed, error: None },
+                });
+            }
+        }
+        connections
     }
 
     /// Get server logs
-    pub async fn get_server_logs(&self, server_id: &str, lines: Option<u32>) -> Result<Vec<LogEntry>, String> {
-        let server = self.storage.get_server(server_id)
-            .ok_or("Server not found")?;
+    pub async fn get_server_logs(&self, server_id: &str, lines: Option<u32>) -> Result<Vec<LogEntry>, DockerError> {
+        let server = self.server(server_id).await?;
 
         let docker = self.connections.get(&server.docker_connection_id)
             .ok_or("Docker connection not found")?;
@@ -465,29 +578,35 @@ impl DockerManager {
                 ..Default::default()
             });
 
-            let mut logs_stream = docker.logs(container_id, options);
-            let mut log_entries = Vec::new();
+            let read_logs = async {
+                let mut logs_stream = docker.logs(container_id, options);
+                let mut log_entries = Vec::new();
 
-            while let Some(log) = logs_stream.next().await {
-                match log {
-                    Ok(output) => {
-                        let log_line = output.to_string();
-                        if let Some(parsed_log) = self.parse_log_line(&log_line) {
-                            log_entries.push(parsed_log);
-                        }
-                    }
-                    Err(e) => {
-                        return Err(format!("Failed to read logs: {}", e));
+                while let Some(log) = logs_stream.next().await {
+                    let output = log?;
+                    if let Some(parsed_log) = self.parse_log_line(&output.to_string()) {
+                        log_entries.push(parsed_log);
                     }
                 }
-            }
 
-            Ok(log_entries)
+                Ok::<_, bollard::errors::Error>(log_entries)
+            };
+
+            with_timeout(docker, &self.timeouts, "read logs", self.timeouts.request(), read_logs).await
         } else {
-            Err("Server has no container".to_string())
+            Err("Server has no container".into())
         }
     }
 
+    /// Docker client and container a server's log is read from, for following it without the manager
+    pub async fn log_source(&self, server_id: &str) -> Result<(Docker, String), DockerError> {
+        let server = self.server(server_id).await?;
+        let docker = self.connections.get(&server.docker_connection_id)
+            .ok_or("Docker connection not found")?;
+        let container_id = server.container_id.ok_or("Server has no container")?;
+        Ok((docker.clone(), container_id))
+    }
+
     /// Parse a Docker log line into a LogEntry
     fn parse_log_line(&self, log_line: &str) -> Option<LogEntry> {
         // Docker log format with timestamps: "timestamp message"
@@ -512,132 +631,1171 @@ impl DockerManager {
             (chrono::Utc::now().to_rfc3339(), cleaned_line.to_string())
         };
 
-        // Determine log level from message content
-        let level = if message.contains("[ERROR]") || message.contains("ERROR") {
-            LogLevel::Error
-        } else if message.contains("[WARN]") || message.contains("WARN") || message.contains("WARNING") {
-            LogLevel::Warn
-        } else if message.contains("[DEBUG]") || message.contains("DEBUG") {
-            LogLevel::Debug
-        } else {
-            LogLevel::Info
-        };
-
         Some(LogEntry {
             timestamp,
-            level,
+            level: LogLevel::of(&message),
             message: message.trim().to_string(),
         })
     }
 
-    /// Execute RCON command in server container using mc-rcon
-    pub async fn exec_command(&self, server_id: &str, minecraft_command: String) -> Result<String, String> {
-        let server = self.storage.get_server(server_id)
-            .ok_or("Server not found")?;
+    /// Run a console command on a server and return its response, see `ServerContext::exec_command`
+    pub async fn exec_command(&self, server_id: &str, minecraft_command: String) -> Result<String, DockerError> {
+        self.context(server_id).await?.exec_command(minecraft_command).await
+    }
 
-        let docker = self.connections.get(&server.docker_connection_id)
+    /// Run a command over RCON from the launcher and return the server's response
+    pub async fn send_rcon_command(&self, server_id: &str, command: &str) -> Result<String, DockerError> {
+        self.context(server_id).await?.send_rcon_command(command).await
+    }
+
+    /// Host ports held on a connection by managed servers and running containers
+    async fn ports_in_use(&self, connection_id: &str, docker: &Docker) -> Result<PortsInUse, DockerError> {
+        let mut in_use = PortsInUse::default();
+        for server in self.storage.read().await.get_servers() {
+            if server.docker_connection_id == connection_id {
+                let owner = format!("server \"{}\"", server.name);
+                in_use.insert(server.port, &owner);
+                in_use.insert(server.rcon_port.unwrap_or_else(|| rcon::host_port(server.port)), &owner);
+            }
+        }
+
+        let containers = with_timeout(docker, &self.timeouts, "list containers", self.timeouts.request(),
+            docker.list_containers(None::<ListContainersOptions<String>>)).await?;
+        for container in containers {
+            let name = container.names.as_ref()
+                .and_then(|names| names.first())
+                .map(|name| name.trim_start_matches('/').to_string())
+                .or(container.id)
+                .unwrap_or_default();
+            let owner = format!("container \"{}\"", name);
+            for port in container.ports.unwrap_or_default() {
+                if let Some(public_port) = port.public_port {
+                    in_use.insert(public_port, &owner);
+                }
+            }
+        }
+        Ok(in_use)
+    }
+
+    /// Host port for a new server, see `ports::allocate`
+    async fn allocate_port(&self, request: &ServerDeploymentRequest, docker: &Docker) -> Result<u16, DockerError> {
+        let in_use = self.ports_in_use(&request.docker_connection_id, docker).await?;
+        let range = self.storage.read().await.get_settings().server_port_range;
+        let port = if self.is_remote(&request.docker_connection_id).await {
+            // Only the daemon knows what else runs on a remote host
+            ports::allocate(request.port, request.auto_assign_port, range, &in_use, |_| true)?
+        } else {
+            ports::allocate(request.port, request.auto_assign_port, range, &in_use, ports::free_on_this_host)?
+        };
+        Ok(port)
+    }
+
+    async fn is_remote(&self, connection_id: &str) -> bool {
+        self.storage.read().await
+            .get_docker_connection(connection_id)
+            .is_some_and(|connection| matches!(connection.connection_type, DockerConnectionType::Remote | DockerConnectionType::Swarm | DockerConnectionType::Ssh))
+    }
+
+    /// Address the server's published ports are reachable on
+    async fn server_host(&self, server: &ServerInstance) -> Result<String, DockerError> {
+        let connection = self.storage.read().await
+            .get_docker_connection(&server.docker_connection_id)
+            .cloned()
             .ok_or("Docker connection not found")?;
+        Ok(match connection.connection_type {
+            DockerConnectionType::Remote | DockerConnectionType::Swarm | DockerConnectionType::Ssh => connection.hostname().to_string(),
+            _ => "127.0.0.1".to_string(),
+        })
+    }
 
-        if let Some(container_id) = &server.container_id {
-            use bollard::exec::{CreateExecOptions, StartExecOptions, StartExecResults};
-            use futures::stream::StreamExt;
+    /// Read a file in the server container, missing files read as empty
+    pub async fn read_server_file(&self, server_id: &str, path: &str) -> Result<Vec<u8>, DockerError> {
+        let script = "[ ! -f \"$1\" ] || cat \"$1\"".to_string();
+        self.context(server_id).await?
+            .exec_output(vec!["sh".to_string(), "-c".to_string(), script, "sh".to_string(), path.to_string()]).await
+    }
 
-            // Use rcon-cli command built into itzg's minecraft-server image
-            let exec_options = CreateExecOptions {
-                cmd: Some(vec!["rcon-cli".to_string(), minecraft_command]),
-                attach_stdout: Some(true),
-                attach_stderr: Some(true),
-                ..Default::default()
-            };
+    /// Replace a file in the server container
+    ///
+    /// The contents travel base64 encoded as an argument, so no quoting can break the
+    /// command, and go through a temporary file so a failed write leaves the old one.
+    pub async fn write_server_file(&self, server_id: &str, path: &str, contents: &str) -> Result<(), DockerError> {
+        use base64::Engine;
+
+        let script = "printf '%s' \"$2\" | base64 -d > \"$1.tmp\" && mv \"$1.tmp\" \"$1\"".to_string();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(contents);
+        self.context(server_id).await?
+            .exec_output(vec!["sh".to_string(), "-c".to_string(), script, "sh".to_string(), path.to_string(), encoded]).await?;
+        println!("📝 Updated {} of server {}", path, server_id);
+        Ok(())
+    }
 
-            let exec = docker.create_exec(container_id, exec_options)
-                .await
-                .map_err(|e| format!("Failed to create exec: {}", e))?;
+    /// Fetch the current players of a running server and record them in its history
+    ///
+    /// Holds the manager for the whole probe. The stats poller instead copies out a
+    /// `ServerContext`, probes without the lock and records the result here.
+    pub async fn sample_players(&mut self, server_id: &str) -> Result<PlayerSample, DockerError> {
+        let probe = self.context(server_id).await?.probe_players(self.query_enabled(server_id)).await;
+        self.record_players(server_id, probe)
+    }
 
-            // Start exec
-            let start_options = StartExecOptions { 
-                detach: false,
-                tty: false,
-                output_capacity: None,
-            };
+    /// Whether a server's players are read with the query protocol before RCON
+    pub fn query_enabled(&self, server_id: &str) -> bool {
+        self.query_support.should_query(server_id)
+    }
 
-            match docker.start_exec(&exec.id, Some(start_options)).await {
-                Ok(StartExecResults::Attached { mut output, .. }) => {
-                    let mut result = Vec::new();
-                    
-                    while let Some(chunk) = output.next().await {
-                        match chunk {
-                            Ok(output) => {
-                                result.push(output.to_string());
-                            }
-                            Err(e) => {
-                                return Err(format!("Failed to read exec output: {}", e));
-                            }
-                        }
-                    }
+    pub fn reset_query_support(&mut self, server_id: &str) {
+        self.query_support.reset(server_id);
+    }
 
-                    let output = result.join("");
-                    Ok(if output.trim().is_empty() { 
-                        "Command executed successfully".to_string() 
-                    } else { 
-                        output 
-                    })
-                }
-                Ok(StartExecResults::Detached) => {
-                    Ok("Command executed in detached mode".to_string())
-                }
-                Err(e) => {
-                    Err(format!("Failed to start exec: {}", e))
-                }
+    /// Record what `ServerContext::probe_players` found in the server's history
+    pub fn record_players(&mut self, server_id: &str, probe: PlayerProbe) -> Result<PlayerSample, DockerError> {
+        match (probe.query_answered, &probe.result) {
+            (Some(true), _) => self.query_support.answered(server_id),
+            (Some(false), Ok(_)) if self.query_support.failed_while_started(server_id) => {
+                println!("Server '{}' doesn't answer queries, reading its players over RCON from now on", probe.server_name);
             }
+            _ => {}
+        }
+        let (response, source) = probe.result?;
+
+        let sample = PlayerSample {
+            timestamp: chrono::Utc::now(),
+            online_players: response.online_players,
+            max_players: response.max_players,
+            players: response.players,
+            source,
+            mock: false,
+        };
+
+        let history = self.player_history.entry(server_id.to_string()).or_default();
+        if history.len() >= PLAYER_HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(sample.clone());
+
+        Ok(sample)
+    }
+
+    /// Get recorded player samples for a server within the last `hours` hours
+    pub fn get_player_history(&self, server_id: &str, hours: u32) -> Vec<PlayerSample> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::hours(hours as i64);
+        self.player_history.get(server_id)
+            .map(|history| history.iter()
+                .filter(|sample| sample.timestamp >= cutoff)
+                .cloned()
+                .collect())
+            .unwrap_or_default()
+    }
+
+    /// Get resource usage stats for a server
+    ///
+    /// Stopped servers report no usage, only their configured limits.
+    pub async fn get_server_stats(&self, server_id: &str) -> Result<ServerStats, String> {
+        use futures::stream::StreamExt;
+
+        let server = self.server(server_id).await
+            .map_err(|e| e.to_string())?;
+
+        let online_players = self.get_latest_players(server_id)
+            .map(|sample| sample.players.clone())
+            .unwrap_or_default();
+
+        let mut stats = ServerStats {
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            memory_limit: server.resources.memory_limit_mb.unwrap_or(0) * 1024 * 1024,
+            network_in: 0,
+            network_out: 0,
+            uptime: 0,
+            online_players,
+            resources: server.resources.clone(),
+            cpu_cores: server.resources.cpus.unwrap_or(0.0),
+            heap_mb: server.memory_limit,
+            warnings: resources::validate(&server.resources, server.memory_limit).unwrap_or_else(|e| vec![e]),
+            mock: false,
+        };
+
+        let (Some(container_id), Some(docker)) = (&server.container_id, self.connections.get(&server.docker_connection_id)) else {
+            return Ok(stats);
+        };
+        let inspect = with_timeout(docker, &self.timeouts, "inspect container", self.timeouts.request(),
+            docker.inspect_container(container_id, None::<InspectContainerOptions>)).await
+            .map_err(|e| e.to_string())?;
+        let Some(state) = inspect.state.filter(|state| state.running == Some(true)) else {
+            return Ok(stats);
+        };
+
+        // Without streaming Docker samples twice, so the CPU delta covers about a second
+        let sample = with_timeout(docker, &self.timeouts, "read container stats", self.timeouts.request(), async {
+            docker.stats(container_id, Some(StatsOptions { stream: false, one_shot: false })).next().await.transpose()
+        }).await.map_err(|e| e.to_string())?;
+        if let Some(sample) = sample {
+            let usage = resources::usage(&sample);
+            stats.cpu_usage = usage.cpu_percent;
+            stats.memory_usage = usage.memory_usage;
+            stats.memory_limit = usage.memory_limit;
+            stats.network_in = usage.network_in;
+            stats.network_out = usage.network_out;
+            stats.cpu_cores = server.resources.cpus.unwrap_or(usage.online_cpus as f64);
+        }
+        stats.uptime = state.started_at.as_deref()
+            .and_then(|started| chrono::DateTime::parse_from_rfc3339(started).ok())
+            .map(|started| (chrono::Utc::now() - started.with_timezone(&chrono::Utc)).num_seconds().max(0) as u64)
+            .unwrap_or(0);
+        Ok(stats)
+    }
+
+    /// Change the CPU and memory limits of a server's container
+    ///
+    /// Limits are updated in place; removing one recreates the container with the
+    /// same configuration, restarting the server if it was running.
+    pub async fn update_server_resources(&mut self, server_id: &str, limits: ServerResourceLimits) -> Result<ServerResourcesUpdate, DockerError> {
+        let mut server = self.server(server_id).await?;
+        let warnings = resources::validate(&limits, server.memory_limit)?;
+
+        let docker = self.connections.get(&server.docker_connection_id)
+            .ok_or("Docker connection not found")?;
+        let container_id = server.container_id.clone()
+            .ok_or("Server has no container")?;
+
+        let recreated = resources::needs_recreate(&server.resources, &limits);
+        if recreated {
+            let recreated_id = recreate_container(docker, &self.timeouts, &container_id, |_, host_config| resources::apply(&limits, host_config)).await?;
+            server.container_id = Some(recreated_id);
         } else {
-            Err("Server has no container".to_string())
+            with_timeout(docker, &self.timeouts, "update container", self.timeouts.request(),
+                docker.update_container(&container_id, resources::update_options(&limits))).await?;
         }
+
+        server.resources = limits.clone();
+        self.storage.write().await.update_server(server).await
+            .map_err(|e| format!("Failed to save server: {}", e))?;
+        Ok(ServerResourcesUpdate { resources: limits, recreated, warnings })
+    }
+
+    /// Replace a server's world with a singleplayer world, see `ServerContext::upload_world`
+    pub async fn upload_world(
+        &mut self,
+        server_id: &str,
+        world_dir: &std::path::Path,
+        backup: bool,
+        on_progress: &(dyn Fn(WorldUploadProgress) + Send + Sync),
+    ) -> Result<WorldUploadResult, DockerError> {
+        let result = self.context(server_id).await?.upload_world(world_dir, backup, on_progress).await;
+        self.reset_query_support(server_id);
+        result
     }
 
-    /// Ensure itzg's Minecraft server image is available
-    async fn ensure_itzg_image(&self, docker: &Docker, image_name: &str) -> Result<(), String> {
-        // Check if image already exists locally
-        let images = docker.list_images(Some(ListImagesOptions::<String> {
+    /// Write a server's container as a Compose file to `output_path`, secrets going to the `.env` beside it
+    pub async fn export_server_compose(&self, server_id: &str, output_path: &std::path::Path) -> Result<std::path::PathBuf, DockerError> {
+        let server = self.server(server_id).await?;
+        let docker = self.connections.get(&server.docker_connection_id)
+            .ok_or("Docker connection not found")?;
+        let container_id = server.container_id.as_ref()
+            .ok_or("Server has no container")?;
+
+        let inspect = with_timeout(docker, &self.timeouts, "inspect container", self.timeouts.request(),
+            docker.inspect_container(container_id, None::<InspectContainerOptions>)).await?;
+        let image = inspect.config.as_ref().and_then(|config| config.image.clone())
+            .ok_or("Container has no image")?;
+        let image_env = match docker.inspect_image(&image).await {
+            Ok(image) => image.config.and_then(|config| config.env).unwrap_or_default(),
+            Err(e) => {
+                // The export still works, it just repeats what the image sets
+                eprintln!("⚠️  Couldn't read the environment of image {}: {}", image, e);
+                Vec::new()
+            }
+        };
+
+        let export = compose::from_container(&compose::service_name(&server.name), &inspect, &image_env)?;
+        compose::write(output_path, &export).await?;
+        println!("📄 Exported server '{}' as {}", server.name, output_path.display());
+        Ok(output_path.to_path_buf())
+    }
+
+    /// Move a server to another Minecraft version, see `ServerContext::upgrade`
+    pub async fn upgrade_server(
+        &mut self,
+        server_id: &str,
+        target_version: &str,
+        force: bool,
+        on_progress: &(dyn Fn(ServerUpgradeProgress) + Send + Sync),
+    ) -> Result<ServerUpgradeResult, DockerError> {
+        let result = self.context(server_id).await?.upgrade(target_version, force, on_progress).await;
+        self.reset_query_support(server_id);
+        result
+    }
+
+    /// Get the most recent player sample for a server, if any
+    pub fn get_latest_players(&self, server_id: &str) -> Option<&PlayerSample> {
+        self.player_history.get(server_id).and_then(|history| history.back())
+    }
+}
+
+/// Replace a container by one with the same configuration after `change`, returning the new id
+async fn recreate_container(
+    docker: &Docker,
+    timeouts: &DockerTimeouts,
+    container_id: &str,
+    change: impl FnOnce(&mut Config<String>, &mut HostConfig),
+) -> Result<String, DockerError> {
+    let inspect = with_timeout(docker, timeouts, "inspect container", timeouts.request(),
+        docker.inspect_container(container_id, None::<InspectContainerOptions>)).await?;
+    let running = inspect.state.as_ref().and_then(|state| state.running).unwrap_or(false);
+    let name = inspect.name.as_deref().unwrap_or_default().trim_start_matches('/').to_string();
+    let mut config: Config<String> = inspect.config.ok_or("Container has no configuration")?.into();
+    let mut host_config = inspect.host_config.unwrap_or_default();
+    change(&mut config, &mut host_config);
+    config.host_config = Some(host_config);
+
+    if running {
+        with_timeout(docker, timeouts, "stop container", stop_budget(timeouts),
+            docker.stop_container(container_id, Some(StopContainerOptions { t: STOP_GRACE_SECS }))).await?;
+    }
+    with_timeout(docker, timeouts, "remove container", timeouts.request(),
+        docker.remove_container(container_id, None::<RemoveContainerOptions>)).await?;
+    let container = with_timeout(docker, timeouts, "create container", timeouts.request(),
+        docker.create_container(Some(CreateContainerOptions { name, platform: None }), config)).await?;
+    if running {
+        with_timeout(docker, timeouts, "start container", timeouts.request(),
+            docker.start_container(&container.id, None::<StartContainerOptions<String>>)).await?;
+    }
+    Ok(container.id)
+}
+
+/// Run `script` with sh in a short lived root container of `image` and collect its output
+///
+/// `host_config` gives the helper the server's volumes. Fails with the output when
+/// the script exits non-zero; the helper is removed either way.
+async fn run_helper(
+    docker: &Docker,
+    timeouts: &DockerTimeouts,
+    image: &str,
+    host_config: HostConfig,
+    script: &str,
+    env: Vec<String>,
+    operation: &str,
+) -> Result<String, DockerError> {
+    use bollard::container::{LogOutput, LogsOptions};
+    use futures::stream::StreamExt;
+
+    let config = Config {
+        image: Some(image.to_string()),
+        entrypoint: Some(vec!["sh".to_string(), "-c".to_string(), script.to_string()]),
+        env: Some(env),
+        user: Some("0:0".to_string()),
+        working_dir: Some("/data".to_string()),
+        host_config: Some(host_config),
+        ..Default::default()
+    };
+    let helper = with_timeout(docker, timeouts, "create container", timeouts.request(),
+        docker.create_container(None::<CreateContainerOptions<String>>, config)).await?;
+
+    let run_helper = async {
+        docker.start_container(&helper.id, None::<StartContainerOptions<String>>).await?;
+        // Non-zero exits surface as errors of the wait stream, the logs say why
+        let mut wait = docker.wait_container(&helper.id, None::<WaitContainerOptions<String>>);
+        let failed = match wait.next().await {
+            Some(Ok(_)) | None => false,
+            Some(Err(_)) => true,
+        };
+        let mut output = String::new();
+        let mut logs = docker.logs(&helper.id, Some(LogsOptions::<String> { stdout: true, stderr: true, ..Default::default() }));
+        while let Some(chunk) = logs.next().await {
+            if let LogOutput::StdOut { message } | LogOutput::StdErr { message } = chunk? {
+                output.push_str(&String::from_utf8_lossy(&message));
+            }
+        }
+        Ok::<_, bollard::errors::Error>((failed, output))
+    };
+    let ran = with_timeout(docker, timeouts, operation, timeouts.pull(), run_helper).await;
+    let removed = docker.remove_container(&helper.id, Some(RemoveContainerOptions { force: true, ..Default::default() })).await;
+    if let Err(e) = removed {
+        eprintln!("⚠️  Failed to remove helper container {}: {}", helper.id, e);
+    }
+
+    let (failed, output) = ran?;
+    if failed {
+        return Err(format!("Failed to {}: {}", operation, output.trim()).into());
+    }
+    Ok(output)
+}
+
+/// Ensure itzg's Minecraft server image is available
+///
+/// Pulls get the long pull budget but still fail after the request timeout without any progress.
+async fn ensure_itzg_image(
+    docker: &Docker,
+    timeouts: &DockerTimeouts,
+    image_name: &str,
+    on_progress: &(dyn Fn(PullProgress) + Send + Sync),
+) -> Result<(), DockerError> {
+    // Check if image already exists locally
+    let images = with_timeout(docker, timeouts, "list images", timeouts.request(),
+        docker.list_images(Some(ListImagesOptions::<String> {
             filters: {
                 let mut filters = HashMap::new();
                 filters.insert("reference".to_string(), vec![image_name.to_string()]);
                 filters
             },
             ..Default::default()
-        }))
-        .await
-        .map_err(|e| format!("Failed to list images: {}", e))?;
+        }))).await?;
+
+    if !images.is_empty() {
+        return Ok(()); // Image already exists
+    }
 
-        if !images.is_empty() {
-            return Ok(()); // Image already exists
+    // Pull itzg's image if it doesn't exist
+    println!("Pulling {} image...", image_name);
+    
+    use bollard::image::CreateImageOptions;
+    use futures::stream::StreamExt;
+    
+    let options = Some(CreateImageOptions {
+        from_image: image_name,
+        ..Default::default()
+    });
+
+    let mut stream = docker.create_image(options, None, None);
+    let mut layers = PullLayers::new(image_name);
+    let deadline = tokio::time::Instant::now() + timeouts.pull();
+    
+    loop {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        let (wait, budget) = if remaining < timeouts.request() {
+            (remaining, timeouts.pull())
+        } else {
+            (timeouts.request(), timeouts.request())
+        };
+
+        let output = match tokio::time::timeout(wait, stream.next()).await {
+            Ok(Some(Ok(output))) => output,
+            Ok(Some(Err(e))) => return Err(format!("Failed to pull image: {}", e).into()),
+            Ok(None) => break,
+            Err(_) => return Err(classify_timeout(docker, timeouts, "pull image", budget).await),
+        };
+
+        if let Some(error) = output.error {
+            return Err(format!("Failed to pull image: {}", error).into());
+        }
+        if let Some(progress) = layers.update(&output) {
+            on_progress(progress);
         }
+    }
 
-        // Pull itzg's image if it doesn't exist
-        println!("Pulling itzg/minecraft-server:latest image...");
-        
-        use bollard::image::CreateImageOptions;
+    println!("Successfully pulled itzg/minecraft-server:latest");
+    Ok(())
+}
+
+/// What one look at a server's players found, recorded by `DockerManager::record_players`
+pub struct PlayerProbe {
+    server_name: String,
+    /// None when the query protocol wasn't tried
+    query_answered: Option<bool>,
+    result: Result<(QueryResponse, PlayerQuerySource), DockerError>,
+}
+
+/// A deployment planned under the manager lock, see `DockerManager::prepare_deployment`
+pub struct PreparedDeployment {
+    request: ServerDeploymentRequest,
+    plan: super::templates::ServerPlan,
+    docker: Docker,
+    timeouts: DockerTimeouts,
+}
+
+impl PreparedDeployment {
+    /// Check the server software has the version and pull the image, without the manager lock
+    pub async fn download(&self, on_progress: &(dyn Fn(PullProgress) + Send + Sync)) -> Result<(), DockerError> {
+        super::templates::validate_version(self.plan.template.server_type, &self.plan.minecraft_version).await?;
+        ensure_itzg_image(&self.docker, &self.timeouts, &self.plan.image, on_progress).await
+    }
+}
+
+/// A server with the Docker client and shared state its calls need
+///
+/// Taken from `DockerManager::context` under the manager lock, then used without it
+/// so slow container and network calls don't hold up other Docker commands.
+#[derive(Clone)]
+pub struct ServerContext {
+    pub server: ServerInstance,
+    docker: Docker,
+    /// Address the server's published ports are reachable on
+    host: String,
+    remote: bool,
+    timeouts: DockerTimeouts,
+    storage: StorageState,
+    rcon: Arc<tokio::sync::Mutex<RconPool>>,
+}
+
+impl ServerContext {
+    /// The container's state, saved to the server record when it changed
+    pub async fn status(&self) -> Result<ServerStatus, DockerError> {
+        let server = &self.server;
+        let docker = &self.docker;
+
+        if let Some(container_id) = &server.container_id {
+            let containers = with_timeout(docker, &self.timeouts, "list containers", self.timeouts.request(),
+                docker.list_containers(Some(ListContainersOptions::<String> {
+                    all: true,
+                    filters: {
+                        let mut filters = HashMap::new();
+                        filters.insert("id".to_string(), vec![container_id.clone()]);
+                        filters
+                    },
+                    ..Default::default()
+                }))).await?;
+
+            if let Some(container) = containers.first() {
+                let status = match container.state.as_deref() {
+                    Some("running") => ServerStatus::Running,
+                    Some("exited") => ServerStatus::Stopped,
+                    Some("created") => ServerStatus::Stopped,
+                    Some("restarting") => ServerStatus::Starting,
+                    _ => ServerStatus::Unknown,
+                };
+
+                // Update status in storage if it's different, on the stored record as it may have changed since
+                if status != server.status {
+                    let mut storage = self.storage.write().await;
+                    if let Some(mut updated_server) = storage.get_server(&server.id).cloned() {
+                        updated_server.status = status.clone();
+                        if let Err(e) = storage.update_server(updated_server).await {
+                            eprintln!("Failed to update server status in storage: {}", e);
+                        }
+                    }
+                }
+
+                Ok(status)
+            } else {
+                Ok(ServerStatus::Unknown)
+            }
+        } else {
+            Ok(ServerStatus::Unknown)
+        }
+    }
+
+    /// Run a console command on a server and return its response
+    ///
+    /// Goes over RCON first, see `send_rcon_command`. When that fails a local
+    /// server gets the command through `rcon-cli` in the container, and containers
+    /// without it get the command written to their console.
+    pub async fn exec_command(&self, minecraft_command: String) -> Result<String, DockerError> {
+        let server = &self.server;
+
+        let rcon_error = match self.send_rcon_command(&minecraft_command).await {
+            Ok(response) => return Ok(command_response(response)),
+            Err(e) => e,
+        };
+        if self.rcon_route() == RconRoute::ContainerCli {
+            println!("⚠️  rcon-cli failed for server '{}', writing to the console: {}", server.name, rcon_error);
+            return self.console_command(&minecraft_command).await;
+        }
+        println!("⚠️  RCON unavailable for server '{}', running the command in the container: {}", server.name, rcon_error);
+
+        match self.rcon_cli(&minecraft_command).await {
+            Ok(output) => Ok(command_response(output)),
+            Err(e) => {
+                println!("⚠️  rcon-cli failed for server '{}', writing to the console: {}", server.name, e);
+                self.console_command(&minecraft_command).await
+            }
+        }
+    }
+
+    fn rcon_route(&self) -> RconRoute {
+        rcon::route(self.remote, &self.host, self.server.rcon_port, self.server.port)
+    }
+
+    /// Run a command over RCON and return the server's response
+    ///
+    /// Local servers are reached from the launcher. Remote hosts publish RCON only on
+    /// their own loopback, so there the command runs through `rcon-cli` in the container.
+    pub async fn send_rcon_command(&self, command: &str) -> Result<String, DockerError> {
+        let server = &self.server;
+        match self.rcon_route() {
+            RconRoute::Direct { host, port } => {
+                let password = rcon_password(server).await;
+                Ok(self.rcon.lock().await.command(&server.id, &host, port, &password, command).await?)
+            }
+            RconRoute::ContainerCli => self.rcon_cli(command).await,
+        }
+    }
+
+    /// Run a command through the `rcon-cli` the itzg image ships
+    async fn rcon_cli(&self, command: &str) -> Result<String, DockerError> {
+        let output = self.exec_output(vec!["rcon-cli".to_string(), command.to_string()]).await?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /// Write a command to the server console and collect what it prints shortly after
+    ///
+    /// Console output isn't tied to the command, so this is a best effort response.
+    async fn console_command(&self, command: &str) -> Result<String, DockerError> {
+        use bollard::container::{AttachContainerOptions, AttachContainerResults};
         use futures::stream::StreamExt;
-        
-        let options = Some(CreateImageOptions {
-            from_image: image_name,
+        use tokio::io::AsyncWriteExt;
+
+        let docker = &self.docker;
+        let container_id = self.server.container_id.as_ref().ok_or("Server has no container")?;
+
+        let options = AttachContainerOptions::<String> {
+            stdin: Some(true),
+            stdout: Some(true),
+            stderr: Some(true),
+            stream: Some(true),
             ..Default::default()
-        });
+        };
+        let AttachContainerResults { mut output, mut input } = with_timeout(docker, &self.timeouts, "attach to console", self.timeouts.request(),
+            docker.attach_container(container_id, Some(options))).await?;
+
+        input.write_all(format!("{}\n", command).as_bytes()).await
+            .map_err(|e| format!("Failed to write to the server console: {}", e))?;
+        input.flush().await
+            .map_err(|e| format!("Failed to write to the server console: {}", e))?;
+
+        let mut printed = String::new();
+        let window = tokio::time::sleep(CONSOLE_OUTPUT_WINDOW);
+        tokio::pin!(window);
+        loop {
+            tokio::select! {
+                _ = &mut window => break,
+                chunk = output.next() => match chunk {
+                    Some(Ok(chunk)) => printed.push_str(&chunk.to_string()),
+                    _ => break,
+                },
+            }
+        }
+        Ok(if printed.trim().is_empty() { "Command sent to the server console".to_string() } else { printed })
+    }
 
-        let mut stream = docker.create_image(options, None, None);
-        
-        while let Some(result) = stream.next().await {
-            match result {
-                Ok(output) => {
-                    if let Some(status) = output.status {
-                        println!("Docker pull: {}", status);
+    /// Run a command in the server container and collect its stdout
+    ///
+    /// Fails with the command's stderr when it exits non-zero.
+    async fn exec_output(&self, cmd: Vec<String>) -> Result<Vec<u8>, DockerError> {
+        use bollard::container::LogOutput;
+        use bollard::exec::{CreateExecOptions, StartExecResults};
+        use futures::stream::StreamExt;
+
+        let docker = &self.docker;
+        let container_id = self.server.container_id.as_ref().ok_or("Server has no container")?;
+
+        let exec_options = CreateExecOptions {
+            cmd: Some(cmd),
+            attach_stdout: Some(true),
+            attach_stderr: Some(true),
+            ..Default::default()
+        };
+        let exec = with_timeout(docker, &self.timeouts, "create exec", self.timeouts.request(),
+            docker.create_exec(container_id, exec_options)).await?;
+
+        let run_exec = async {
+            let mut stdout = Vec::new();
+            let mut stderr = Vec::new();
+            if let StartExecResults::Attached { mut output, .. } = docker.start_exec(&exec.id, None).await? {
+                while let Some(chunk) = output.next().await {
+                    match chunk? {
+                        LogOutput::StdOut { message } => stdout.extend_from_slice(&message),
+                        LogOutput::StdErr { message } => stderr.extend_from_slice(&message),
+                        _ => {}
                     }
-                },
-                Err(e) => return Err(format!("Failed to pull image: {}", e)),
+                }
+            }
+            let exit_code = docker.inspect_exec(&exec.id).await?.exit_code;
+            Ok::<_, bollard::errors::Error>((stdout, stderr, exit_code))
+        };
+
+        let (stdout, stderr, exit_code) = with_timeout(docker, &self.timeouts, "run command", self.timeouts.request(), run_exec).await?;
+        match exit_code {
+            Some(0) | None => Ok(stdout),
+            Some(code) => Err(format!("Command exited with {}: {}", code, String::from_utf8_lossy(&stderr).trim()).into()),
+        }
+    }
+
+    /// The server as stored now, which an upgrade may have given a new container
+    async fn stored(&self) -> Result<ServerInstance, DockerError> {
+        self.storage.read().await
+            .get_server(&self.server.id)
+            .cloned()
+            .ok_or_else(|| "Server not found".into())
+    }
+
+    /// Start the server's container
+    pub async fn start(&self) -> Result<(), DockerError> {
+        let mut server = self.stored().await?;
+        let docker = &self.docker;
+
+        if let Some(container_id) = &server.container_id {
+            with_timeout(docker, &self.timeouts, "start container", self.timeouts.request(),
+                docker.start_container(container_id, None::<StartContainerOptions<String>>)).await?;
+
+            server.status = ServerStatus::Starting;
+            server.last_started = Some(chrono::Utc::now());
+
+            // Save updated server status
+            self.storage.write().await.update_server(server).await
+                .map_err(|e| format!("Failed to update server status: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Stop the server's container, giving it time to save
+    pub async fn stop(&self) -> Result<(), DockerError> {
+        let mut server = self.stored().await?;
+        let docker = &self.docker;
+
+        if let Some(container_id) = &server.container_id {
+            let options = StopContainerOptions { t: STOP_GRACE_SECS };
+            with_timeout(docker, &self.timeouts, "stop container", stop_budget(&self.timeouts),
+                docker.stop_container(container_id, Some(options))).await?;
+
+            server.status = ServerStatus::Stopping;
+            self.rcon.lock().await.disconnect(&server.id);
+            
+            // Save updated server status
+            self.storage.write().await.update_server(server).await
+                .map_err(|e| format!("Failed to update server status: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Move a server to another Minecraft version
+    ///
+    /// The data folder is backed up on the server, then the container is recreated
+    /// with the new version and image and started until its log shows it loaded.
+    /// When it doesn't, the backup and the previous container are put back. A server
+    /// that wasn't running is stopped again once the upgrade is verified.
+    ///
+    /// Takes up to the verify timeout, so it runs on a context rather than the manager.
+    pub async fn upgrade(
+        &self,
+        target_version: &str,
+        force: bool,
+        on_progress: &(dyn Fn(ServerUpgradeProgress) + Send + Sync),
+    ) -> Result<ServerUpgradeResult, DockerError> {
+        let server = self.stored().await?;
+        let docker = self.docker.clone();
+        let container_id = server.container_id.clone()
+            .ok_or("Server has no container")?;
+
+        let inspect = with_timeout(&docker, &self.timeouts, "inspect container", self.timeouts.request(),
+            docker.inspect_container(&container_id, None::<InspectContainerOptions>)).await?;
+        let running = inspect.state.as_ref().and_then(|state| state.running).unwrap_or(false);
+        let name = inspect.name.as_deref().unwrap_or_default().trim_start_matches('/').to_string();
+        let config = inspect.config.ok_or("Container has no configuration")?;
+        let env = config.env.clone().unwrap_or_default();
+        let env_value = |key: &str| env.iter().find_map(|var| var.strip_prefix(key)?.strip_prefix('=').map(str::to_string));
+        let label = |key: &str| config.labels.as_ref().and_then(|labels| labels.get(key).cloned());
+        let previous_version = label("chailauncher.minecraft_version")
+            .or_else(|| env_value("VERSION"))
+            .unwrap_or_else(|| "LATEST".to_string());
+        let server_type = label("chailauncher.server_type")
+            .or_else(|| env_value("TYPE"))
+            .and_then(|server_type| ServerType::from_itzg(&server_type));
+        let previous_image = config.image.clone().ok_or("Container has no image")?;
+        let target_version = target_version.trim().to_string();
+
+        upgrade::check_versions(&previous_version, &target_version, force)?;
+        if let Some(server_type) = server_type {
+            super::templates::validate_version(server_type, &target_version).await?;
+        }
+        let image = upgrade::image_for(&previous_image, &target_version);
+
+        // Enough to put the old container back even after it was removed
+        let host_config = inspect.host_config.unwrap_or_default();
+        let volumes = HostConfig {
+            binds: host_config.binds.clone(),
+            mounts: host_config.mounts.clone(),
+            ..Default::default()
+        };
+        let mut previous_config: Config<String> = config.into();
+        previous_config.host_config = Some(host_config);
+        println!("⬆️  Upgrading server '{}' from {} to {}", server.name, previous_version, target_version);
+
+        on_progress(ServerUpgradeProgress::new(ServerUpgradeStage::Backup, 0));
+        if running {
+            self.stop().await?;
+        }
+        let backup_name = upgrade::backup_name(&previous_version, &chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string());
+        let prepared = async {
+            let output = run_helper(&docker, &self.timeouts, &previous_image, volumes.clone(), upgrade::BACKUP_SCRIPT,
+                vec![format!("NAME={}", backup_name)], "back up the server").await?;
+            let backup = output.lines()
+                .find_map(|line| line.strip_prefix("backup="))
+                .map(|backup| backup.trim().to_string())
+                .ok_or("The backup didn't report where it was saved")?;
+            on_progress(ServerUpgradeProgress::new(ServerUpgradeStage::Backup, 100));
+            ensure_itzg_image(&docker, &self.timeouts, &image, &|progress| on_progress(ServerUpgradeProgress::pulling(progress))).await?;
+            Ok::<_, DockerError>(backup)
+        }.await;
+        let backup = match prepared {
+            Ok(backup) => backup,
+            Err(e) if running => {
+                // Nothing was changed yet, so the old server comes back as it was
+                return Err(match self.start().await {
+                    Ok(()) => e,
+                    Err(start) => format!("{}. The server failed to start again: {}", e.message(), start.message()).into(),
+                });
             }
+            Err(e) => return Err(e),
+        };
+        println!("💾 Backed up server '{}' to {}", server.name, backup);
+
+        on_progress(ServerUpgradeProgress::new(ServerUpgradeStage::Pull, 100));
+        let recreated = recreate_container(&docker, &self.timeouts, &container_id, |config, _| upgrade::retarget(config, &image, &target_version)).await;
+        let verified = match recreated {
+            Ok(new_id) => {
+                self.save_container_id(&new_id).await?;
+                on_progress(ServerUpgradeProgress::new(ServerUpgradeStage::Recreate, 100));
+                self.verify_startup(&docker, &new_id).await
+            }
+            Err(e) => Err(e.message().to_string()),
+        };
+
+        if let Err(reason) = verified {
+            eprintln!("❌ Upgrade of server '{}' failed, rolling back: {}", server.name, reason);
+            on_progress(ServerUpgradeProgress::new(ServerUpgradeStage::RollBack, 0));
+            let rolled_back = self.roll_back_upgrade(&docker, &name, previous_config, &previous_image, volumes, &backup, running).await;
+            on_progress(ServerUpgradeProgress::new(ServerUpgradeStage::RollBack, 100));
+            return Err(match rolled_back {
+                Ok(()) => format!("Upgrading to Minecraft {} failed: {}. The server was restored to {} from its backup",
+                    target_version, reason, previous_version),
+                Err(e) => format!("Upgrading to Minecraft {} failed: {}. Rolling back failed too: {}. The backup is at /data/{}",
+                    target_version, reason, e.message(), backup),
+            }.into());
         }
+        on_progress(ServerUpgradeProgress::new(ServerUpgradeStage::Verify, 100));
+
+        if !running {
+            self.stop().await?;
+        }
+        let mut server = self.stored().await?;
+        if running {
+            server.status = ServerStatus::Running;
+        }
+        if let Some(version) = server.environment_vars.get_mut("VERSION") {
+            *version = target_version.clone();
+        }
+        server.last_upgrade = Some(ServerUpgradeRecord {
+            previous_version: previous_version.clone(),
+            previous_image,
+            minecraft_version: target_version.clone(),
+            backup: backup.clone(),
+            upgraded_at: chrono::Utc::now(),
+        });
+        self.storage.write().await.update_server(server).await
+            .map_err(|e| format!("Failed to save server: {}", e))?;
+        println!("✅ Upgraded server '{}' to {}", name, target_version);
+        Ok(ServerUpgradeResult { previous_version, minecraft_version: target_version, image, backup })
+    }
+
+    async fn save_container_id(&self, container_id: &str) -> Result<(), DockerError> {
+        let mut server = self.stored().await?;
+        server.container_id = Some(container_id.to_string());
+        self.storage.write().await.update_server(server).await
+            .map_err(|e| format!("Failed to save server: {}", e))?;
+        Ok(())
+    }
+
+    /// Start a recreated server and wait for its log to show it loaded, or why it didn't
+    async fn verify_startup(&self, docker: &Docker, container_id: &str) -> Result<(), String> {
+        use super::log_stream::{LineSplitter, OutputStream};
+        use bollard::container::{LogOutput, LogsOptions};
+        use futures::stream::StreamExt;
+
+        let since = chrono::Utc::now().timestamp();
+        self.start().await.map_err(|e| e.message().to_string())?;
+
+        let options = LogsOptions::<String> { follow: true, stdout: true, stderr: true, since, ..Default::default() };
+        let mut logs = docker.logs(container_id, Some(options));
+        let watch = async {
+            let mut splitter = LineSplitter::default();
+            while let Some(chunk) = logs.next().await {
+                let (stream, message) = match chunk {
+                    Ok(LogOutput::StdOut { message } | LogOutput::Console { message }) => (OutputStream::Stdout, message),
+                    Ok(LogOutput::StdErr { message }) => (OutputStream::Stderr, message),
+                    Ok(LogOutput::StdIn { .. }) => continue,
+                    Err(e) => return Err(format!("Lost the server's log: {}", e)),
+                };
+                for line in splitter.push(stream, &message) {
+                    if let Some(outcome) = upgrade::startup_outcome(&line) {
+                        return outcome;
+                    }
+                }
+            }
+            Err("The server stopped before it finished starting".to_string())
+        };
+        let budget = std::time::Duration::from_secs(upgrade::VERIFY_TIMEOUT_SECS);
+        tokio::time::timeout(budget, watch).await
+            .unwrap_or_else(|_| Err(format!("The server didn't finish starting within {} minutes", budget.as_secs() / 60)))
+    }
 
-        println!("Successfully pulled itzg/minecraft-server:latest");
+    /// Put a server's data and container back the way they were before an upgrade
+    #[allow(clippy::too_many_arguments)]
+    async fn roll_back_upgrade(
+        &self,
+        docker: &Docker,
+        name: &str,
+        previous_config: Config<String>,
+        previous_image: &str,
+        volumes: HostConfig,
+        backup: &str,
+        start: bool,
+    ) -> Result<(), DockerError> {
+        // Whichever container holds the name now, the upgraded one or none at all
+        match docker.remove_container(name, Some(RemoveContainerOptions { force: true, ..Default::default() })).await {
+            Ok(()) | Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => {}
+            Err(e) => return Err(format!("Failed to remove the upgraded container: {}", e).into()),
+        }
+        run_helper(docker, &self.timeouts, previous_image, volumes, upgrade::RESTORE_SCRIPT,
+            vec![format!("BACKUP={}", backup)], "restore the backup").await?;
+
+        let container = with_timeout(docker, &self.timeouts, "create container", self.timeouts.request(),
+            docker.create_container(Some(CreateContainerOptions { name: name.to_string(), platform: None }), previous_config)).await?;
+        self.save_container_id(&container.id).await?;
+        self.rcon.lock().await.disconnect(&self.server.id);
+        if start {
+            self.start().await?;
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Replace a server's world with a singleplayer world from `world_dir`
+    ///
+    /// The server is stopped for the swap and started again if it was running. When
+    /// anything fails after stopping it, the server is left stopped with its old world
+    /// unless the swap itself got that far.
+    pub async fn upload_world(
+        &self,
+        world_dir: &std::path::Path,
+        backup: bool,
+        on_progress: &(dyn Fn(WorldUploadProgress) + Send + Sync),
+    ) -> Result<WorldUploadResult, DockerError> {
+        let server = self.stored().await?;
+        let docker = &self.docker;
+        let container_id = server.container_id.clone()
+            .ok_or("Server has no container")?;
+
+        let inspect = with_timeout(docker, &self.timeouts, "inspect container", self.timeouts.request(),
+            docker.inspect_container(&container_id, None::<InspectContainerOptions>)).await?;
+        let running = inspect.state.as_ref().and_then(|state| state.running).unwrap_or(false);
+        let config = inspect.config.unwrap_or_default();
+        let env = config.env.unwrap_or_default();
+        let server_version = config.labels.as_ref()
+            .and_then(|labels| labels.get("chailauncher.minecraft_version").cloned())
+            .or_else(|| env.iter().find_map(|var| var.strip_prefix("VERSION=").map(str::to_string)))
+            .unwrap_or_default();
+        let image = config.image.ok_or("Container has no image")?;
+
+        let dir = world_dir.to_path_buf();
+        let world_version = tokio::task::spawn_blocking(move || crate::minecraft::worlds::read_world_version(&dir)).await
+            .map_err(|e| format!("Failed to read the world: {}", e))??;
+        world_upload::check_compatible(&world_version, &server_version)?;
+
+        // Compress into a temporary file so the transfer size is known and retries aren't needed
+        let folder = world_upload::staging_folder();
+        let archive = TempFile(std::env::temp_dir().join(format!("chai-world-{}.tar.gz", uuid::Uuid::new_v4())));
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+        let pack = tokio::task::spawn_blocking({
+            let (world_dir, folder, path) = (world_dir.to_path_buf(), folder.clone(), archive.0.clone());
+            move || world_upload::pack(&world_dir, &folder, &path, |done, total| {
+                let _ = progress_tx.send((done, total));
+            })
+        });
+        let mut percent = None;
+        while let Some((done, total)) = progress_rx.recv().await {
+            let progress = WorldUploadProgress::new(WorldUploadStage::Compressing, done, total);
+            if percent != Some(progress.percent) {
+                percent = Some(progress.percent);
+                on_progress(progress);
+            }
+        }
+        pack.await.map_err(|e| format!("Failed to compress the world: {}", e))??;
+        let size = std::fs::metadata(&archive.0).map_err(|e| format!("Failed to read the world archive: {}", e))?.len();
+        println!("📦 Compressed world {} to {} bytes for server '{}'", world_dir.display(), size, server.name);
+
+        if running {
+            self.stop().await?;
+        }
+        let swapped = self.swap_world(&container_id, &image, env, &archive.0, size, &folder, backup, on_progress).await;
+
+        let (level_name, backup) = match swapped {
+            Ok(swapped) => swapped,
+            Err(e) if running => return Err(format!("{}. The server was left stopped", e.message()).into()),
+            Err(e) => return Err(e),
+        };
+        if running {
+            self.start().await
+                .map_err(|e| format!("The world was uploaded but the server failed to start: {}", e.message()))?;
+        }
+        println!("🌍 Uploaded world {} to server '{}' as {}", world_dir.display(), server.name, level_name);
+        Ok(WorldUploadResult { level_name, backup, restarted: running })
+    }
+
+    /// Send the archive into the stopped container and swap it in with a helper container
+    #[allow(clippy::too_many_arguments)]
+    async fn swap_world(
+        &self,
+        container_id: &str,
+        image: &str,
+        mut env: Vec<String>,
+        archive: &std::path::Path,
+        size: u64,
+        folder: &str,
+        backup: bool,
+        on_progress: &(dyn Fn(WorldUploadProgress) + Send + Sync),
+    ) -> Result<(String, Option<String>), DockerError> {
+        use bollard::container::UploadToContainerOptions;
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use tokio::io::AsyncReadExt;
+
+        let docker = &self.docker;
+
+        on_progress(WorldUploadProgress::new(WorldUploadStage::Transferring, 0, size));
+        let file = tokio::fs::File::open(archive).await
+            .map_err(|e| format!("Failed to read the world archive: {}", e))?;
+        let sent = Arc::new(AtomicU64::new(0));
+        let body = futures::stream::unfold((file, sent.clone()), |(mut file, sent)| async move {
+            let mut chunk = vec![0u8; 256 * 1024];
+            match file.read(&mut chunk).await {
+                Ok(0) | Err(_) => None,
+                Ok(read) => {
+                    chunk.truncate(read);
+                    sent.fetch_add(read as u64, Ordering::Relaxed);
+                    Some((chunk.into(), (file, sent)))
+                }
+            }
+        });
+        let options = UploadToContainerOptions { path: "/data", ..Default::default() };
+        let upload = with_timeout(docker, &self.timeouts, "upload world", self.timeouts.pull(),
+            docker.upload_to_container_streaming(container_id, Some(options), body));
+        tokio::pin!(upload);
+        let mut ticker = tokio::time::interval(std::time::Duration::from_millis(500));
+        loop {
+            tokio::select! {
+                result = &mut upload => {
+                    result?;
+                    break;
+                }
+                _ = ticker.tick() => on_progress(WorldUploadProgress::new(WorldUploadStage::Transferring, sent.load(Ordering::Relaxed), size)),
+            }
+        }
+        on_progress(WorldUploadProgress::new(WorldUploadStage::Transferring, size, size));
+
+        // The helper gets the server's environment so LEVEL set on the server applies
+        on_progress(WorldUploadProgress::new(WorldUploadStage::Extracting, 0, 1));
+        env.extend([
+            format!("UPLOAD={}", folder),
+            format!("BACKUP={}", if backup { 1 } else { 0 }),
+            format!("STAMP={}", chrono::Utc::now().format("%Y%m%d-%H%M%S")),
+        ]);
+        let volumes = HostConfig {
+            volumes_from: Some(vec![container_id.to_string()]),
+            ..Default::default()
+        };
+        let output = run_helper(docker, &self.timeouts, image, volumes, world_upload::SWAP_SCRIPT, env, "replace the world").await?;
+        let value = |key: &str| output.lines().find_map(|line| line.strip_prefix(key).map(|value| value.trim().to_string()));
+        on_progress(WorldUploadProgress::new(WorldUploadStage::Extracting, 1, 1));
+        Ok((value("level=").unwrap_or_else(|| "world".to_string()), value("backup=")))
+    }
+
+    /// Read the server's players over query, or RCON `list` when query is off or fails
+    pub async fn probe_players(&self, try_query: bool) -> PlayerProbe {
+        let server = &self.server;
+        let mut query_answered = None;
+        if try_query {
+            match query::query_full_stat(&self.host, server.port).await {
+                Ok(response) => {
+                    return PlayerProbe {
+                        server_name: server.name.clone(),
+                        query_answered: Some(true),
+                        result: Ok((response, PlayerQuerySource::Query)),
+                    };
+                }
+                Err(e) => {
+                    println!("Query unavailable for server '{}', using RCON instead: {}", server.name, e);
+                    query_answered = Some(false);
+                }
+            }
+        }
+
+        let result = match self.exec_command("list".to_string()).await {
+            Ok(output) => query::parse_rcon_list(&output)
+                .map(|response| (response, PlayerQuerySource::Rcon))
+                .ok_or_else(|| format!("Unexpected RCON list output: {}", output.trim()).into()),
+            Err(e) => Err(e),
+        };
+        PlayerProbe { server_name: server.name.clone(), query_answered, result }
+    }
+}
+
+/// Temporary file removed when dropped
+struct TempFile(std::path::PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Per-layer state of an image pull
+struct PullLayers {
+    image: String,
+    /// Layer id -> (current bytes, total bytes, finished)
+    layers: HashMap<String, (u64, u64, bool)>,
+}
+
+impl PullLayers {
+    fn new(image: &str) -> Self {
+        Self {
+            image: image.to_string(),
+            layers: HashMap::new(),
+        }
+    }
+
+    /// Record a pull message, returning progress when it concerned a layer
+    fn update(&mut self, output: &bollard::models::CreateImageInfo) -> Option<PullProgress> {
+        let status = output.status.clone().unwrap_or_default();
+        let Some(layer) = output.id.clone() else {
+            if !status.is_empty() {
+                println!("Docker pull: {}", status);
+            }
+            return None;
+        };
+        let detail = output.progress_detail.as_ref();
+        let current = detail.and_then(|d| d.current).map(|v| v.max(0) as u64);
+        let total = detail.and_then(|d| d.total).map(|v| v.max(0) as u64);
+
+        let entry = self.layers.entry(layer.clone()).or_insert((0, 0, false));
+        match status.as_str() {
+            "Pull complete" | "Already exists" => entry.2 = true,
+            // Extraction reports its own byte counts, only downloads are tracked
+            "Downloading" => {
+                entry.0 = current.unwrap_or(entry.0);
+                entry.1 = total.unwrap_or(entry.1);
+            }
+            "Download complete" => entry.0 = entry.1,
+            _ => {}
+        }
+
+        let layers_total = self.layers.len();
+        let layers_done = self.layers.values().filter(|(_, _, done)| *done).count();
+        let partial: f64 = self.layers.values()
+            .filter(|(_, total, done)| !done && *total > 0)
+            .map(|(current, total, _)| *current as f64 / *total as f64)
+            .sum();
+        let percent = ((layers_done as f64 + partial) / layers_total as f64 * 100.0) as u32;
+
+        Some(PullProgress {
+            image: self.image.clone(),
+            layer,
+            status,
+            current,
+            total,
+            layers_done,
+            layers_total,
+            percent: percent.min(100),
+        })
+    }
+}
@@ -0,0 +1,568 @@
+use super::resources::ServerResourcesUpdate;
+use super::upgrade::{ServerUpgradeProgress, ServerUpgradeResult, ServerUpgradeStage};
+use super::world_upload::{WorldUploadProgress, WorldUploadResult, WorldUploadStage};
+use super::types::*;
+use crate::minecraft::MinecraftInstance;
+use crate::storage::ServerPortRange;
+use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+
+const MOCK_CONNECTION_ID: &str = "mock-local";
+const MOCK_SERVER_ID: &str = "mock-server";
+const MOCK_PLAYERS: &[&str] = &["Steve", "Alex"];
+/// Minecraft version of mock servers that don't set `VERSION`
+const MOCK_VERSION: &str = "1.20.1";
+
+/// Canned server output returned by `get_server_logs`
+const MOCK_LOG_LINES: &[(LogLevel, &str)] = &[
+    (LogLevel::Info, "Starting minecraft server version 1.20.1"),
+    (LogLevel::Info, "Loading properties"),
+    (LogLevel::Info, "Default game type: SURVIVAL"),
+    (LogLevel::Info, "Preparing level \"world\""),
+    (LogLevel::Info, "Preparing start region for dimension minecraft:overworld"),
+    (LogLevel::Warn, "Can't keep up! Is the server overloaded? Running 2041ms or 40 ticks behind"),
+    (LogLevel::Info, "Done (4.213s)! For help, type \"help\""),
+    (LogLevel::Info, "Steve joined the game"),
+    (LogLevel::Info, "Alex joined the game"),
+    (LogLevel::Info, "<Steve> hello!"),
+];
+
+/// In-memory Docker backend for developing the UI without a daemon
+///
+/// Every method mirrors `DockerManager` and returns deterministic fake data.
+pub struct MockDockerBackend {
+    connections: HashMap<String, DockerConnection>,
+    servers: HashMap<String, ServerInstance>,
+    player_history: HashMap<String, Vec<PlayerSample>>,
+    /// Files written through `write_server_file`, by server id and path
+    files: HashMap<(String, String), String>,
+}
+
+impl MockDockerBackend {
+    pub fn new() -> Self {
+        let mut connections = HashMap::new();
+        connections.insert(MOCK_CONNECTION_ID.to_string(), DockerConnection {
+            id: MOCK_CONNECTION_ID.to_string(),
+            name: "Mock Docker".to_string(),
+            host: "localhost".to_string(),
+            port: None,
+            connection_type: DockerConnectionType::Local,
+            is_connected: true,
+            tls: None,
+            ssh: None,
+            tunnel: None,
+            mock: true,
+        });
+
+        let mut servers = HashMap::new();
+        servers.insert(MOCK_SERVER_ID.to_string(), ServerInstance {
+            id: MOCK_SERVER_ID.to_string(),
+            name: "Mock Survival".to_string(),
+            minecraft_instance_id: "mock-instance".to_string(),
+            docker_connection_id: MOCK_CONNECTION_ID.to_string(),
+            container_id: Some(format!("{}-container", MOCK_SERVER_ID)),
+            status: ServerStatus::Running,
+            port: 25565,
+            max_players: 20,
+            memory_limit: 2048,
+            created_at: Self::base_time(),
+            last_started: Some(Self::base_time()),
+            environment_vars: HashMap::new(),
+            rcon_port: None,
+            resources: ServerResourceLimits::default(),
+            last_upgrade: None,
+            mock: true,
+        });
+
+        Self {
+            connections,
+            servers,
+            player_history: HashMap::new(),
+            files: HashMap::new(),
+        }
+    }
+
+    fn base_time() -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+    }
+
+    fn server(&self, server_id: &str) -> Result<&ServerInstance, String> {
+        self.servers.get(server_id).ok_or_else(|| "Server not found".to_string())
+    }
+
+    fn server_mut(&mut self, server_id: &str) -> Result<&mut ServerInstance, String> {
+        self.servers.get_mut(server_id).ok_or_else(|| "Server not found".to_string())
+    }
+
+    pub async fn test_connection(&self, _connection: &DockerConnection) -> Result<bool, String> {
+        Ok(true)
+    }
+
+    pub async fn add_connection(&mut self, mut connection: DockerConnection) -> Result<(), String> {
+        connection.is_connected = true;
+        connection.mock = true;
+        self.connections.insert(connection.id.clone(), connection);
+        Ok(())
+    }
+
+    pub async fn deploy_server(
+        &mut self,
+        request: ServerDeploymentRequest,
+        minecraft_instance: &MinecraftInstance,
+    ) -> Result<ServerInstance, String> {
+        if !self.connections.contains_key(&request.docker_connection_id) {
+            return Err("Docker connection not found".to_string());
+        }
+        // Unknown templates fail here too, version lists aren't checked offline
+        super::templates::plan_deployment(&request, &minecraft_instance.version)?;
+        super::resources::validate(&request.resources, request.memory_limit)?;
+
+        let mut in_use = super::ports::PortsInUse::default();
+        for server in self.servers.values().filter(|server| server.docker_connection_id == request.docker_connection_id) {
+            let owner = format!("server \"{}\"", server.name);
+            in_use.insert(server.port, &owner);
+            in_use.insert(super::rcon::host_port(server.port), &owner);
+        }
+        let port = super::ports::allocate(request.port, request.auto_assign_port, ServerPortRange::default(), &in_use, |_| true)?;
+
+        let id = format!("mock-server-{}", self.servers.len() + 1);
+        let server = ServerInstance {
+            id: id.clone(),
+            name: request.name,
+            minecraft_instance_id: request.minecraft_instance_id,
+            docker_connection_id: request.docker_connection_id,
+            container_id: Some(format!("{}-container", id)),
+            status: if request.auto_start { ServerStatus::Running } else { ServerStatus::Stopped },
+            port,
+            max_players: request.max_players,
+            memory_limit: request.memory_limit,
+            created_at: Self::base_time(),
+            last_started: if request.auto_start { Some(Self::base_time()) } else { None },
+            environment_vars: request.environment_vars,
+            rcon_port: None,
+            resources: request.resources,
+            last_upgrade: None,
+            mock: true,
+        };
+
+        self.servers.insert(id, server.clone());
+        Ok(server)
+    }
+
+    pub async fn start_server(&mut self, server_id: &str) -> Result<(), String> {
+        let server = self.server_mut(server_id)?;
+        server.status = ServerStatus::Running;
+        server.last_started = Some(Self::base_time());
+        Ok(())
+    }
+
+    pub async fn stop_server(&mut self, server_id: &str) -> Result<(), String> {
+        self.server_mut(server_id)?.status = ServerStatus::Stopped;
+        Ok(())
+    }
+
+    pub async fn get_server_status(&mut self, server_id: &str) -> Result<ServerStatus, String> {
+        Ok(self.server(server_id)?.status.clone())
+    }
+
+    pub async fn remove_server(&mut self, server_id: &str) -> Result<(), String> {
+        self.servers.remove(server_id).ok_or("Server not found")?;
+        self.player_history.remove(server_id);
+        self.files.retain(|(id, _), _| id != server_id);
+        Ok(())
+    }
+
+    pub fn get_servers(&self) -> Vec<&ServerInstance> {
+        let mut servers: Vec<&ServerInstance> = self.servers.values().collect();
+        servers.sort_by(|a, b| a.id.cmp(&b.id));
+        servers
+    }
+
+    pub fn get_servers_for_instance(&self, instance_id: &str) -> Vec<&ServerInstance> {
+        self.get_servers().into_iter()
+            .filter(|s| s.minecraft_instance_id == instance_id)
+            .collect()
+    }
+
+    pub fn get_docker_connections(&self) -> Vec<&DockerConnection> {
+        let mut connections: Vec<&DockerConnection> = self.connections.values().collect();
+        connections.sort_by(|a, b| a.id.cmp(&b.id));
+        connections
+    }
+
+    pub async fn get_server_logs(&self, server_id: &str, lines: Option<u32>) -> Result<Vec<LogEntry>, String> {
+        self.server(server_id)?;
+
+        let entries: Vec<LogEntry> = MOCK_LOG_LINES.iter().enumerate()
+            .map(|(i, (level, message))| LogEntry {
+                timestamp: (Self::base_time() + chrono::Duration::seconds(i as i64)).to_rfc3339(),
+                level: level.clone(),
+                message: format!("[mock] {}", message),
+            })
+            .collect();
+
+        let lines = lines.unwrap_or(100) as usize;
+        Ok(entries[entries.len().saturating_sub(lines)..].to_vec())
+    }
+
+    pub async fn exec_command(&self, server_id: &str, minecraft_command: String) -> Result<String, String> {
+        let server = self.server(server_id)?;
+        if server.status != ServerStatus::Running {
+            return Err("Server is not running".to_string());
+        }
+
+        match minecraft_command.trim() {
+            "list" => Ok(format!(
+                "There are {} of a max of {} players online: {}",
+                MOCK_PLAYERS.len(),
+                server.max_players,
+                MOCK_PLAYERS.join(", ")
+            )),
+            command => Ok(format!("[mock] Executed: {}", command)),
+        }
+    }
+
+    pub async fn read_server_file(&self, server_id: &str, path: &str) -> Result<Vec<u8>, String> {
+        let server = self.server(server_id)?;
+        if server.status != ServerStatus::Running {
+            return Err("Server is not running".to_string());
+        }
+
+        let written = self.files.get(&(server_id.to_string(), path.to_string()));
+        Ok(match (written, path) {
+            (Some(contents), _) => contents.clone(),
+            (None, super::properties::SERVER_PROPERTIES_PATH) => format!(
+                "#Minecraft server properties\n#Mon Jan 01 12:00:00 UTC 2024\nserver-port=25565\nlevel-name=world\nmotd={}\nmax-players={}\ndifficulty=easy\npvp=true\n",
+                server.name, server.max_players,
+            ),
+            (None, _) => String::new(),
+        }.into_bytes())
+    }
+
+    pub async fn write_server_file(&mut self, server_id: &str, path: &str, contents: &str) -> Result<(), String> {
+        let server = self.server(server_id)?;
+        if server.status != ServerStatus::Running {
+            return Err("Server is not running".to_string());
+        }
+
+        self.files.insert((server_id.to_string(), path.to_string()), contents.to_string());
+        Ok(())
+    }
+
+    pub fn get_server_stats(&self, server_id: &str) -> Result<ServerStats, String> {
+        let server = self.server(server_id)?;
+        let running = server.status == ServerStatus::Running;
+        let memory_limit = server.resources.memory_limit_mb.unwrap_or(server.memory_limit) * 1024 * 1024;
+
+        Ok(ServerStats {
+            cpu_usage: if running { 12.5 } else { 0.0 },
+            memory_usage: if running { memory_limit * 3 / 5 } else { 0 },
+            memory_limit,
+            network_in: if running { 1024 * 512 } else { 0 },
+            network_out: if running { 1024 * 256 } else { 0 },
+            uptime: if running { 3600 } else { 0 },
+            online_players: if running { MOCK_PLAYERS.iter().map(|p| p.to_string()).collect() } else { vec![] },
+            resources: server.resources.clone(),
+            cpu_cores: server.resources.cpus.unwrap_or(4.0),
+            heap_mb: server.memory_limit,
+            warnings: super::resources::validate(&server.resources, server.memory_limit).unwrap_or_else(|e| vec![e]),
+            mock: true,
+        })
+    }
+
+    pub async fn update_server_resources(&mut self, server_id: &str, limits: ServerResourceLimits) -> Result<ServerResourcesUpdate, String> {
+        let server = self.server_mut(server_id)?;
+        let warnings = super::resources::validate(&limits, server.memory_limit)?;
+        let recreated = super::resources::needs_recreate(&server.resources, &limits);
+        server.resources = limits.clone();
+        Ok(ServerResourcesUpdate { resources: limits, recreated, warnings })
+    }
+
+    pub async fn upload_world(
+        &mut self,
+        server_id: &str,
+        world_dir: &std::path::Path,
+        backup: bool,
+        on_progress: &(dyn Fn(WorldUploadProgress) + Send + Sync),
+    ) -> Result<WorldUploadResult, String> {
+        let server = self.server(server_id)?.clone();
+        let world_version = crate::minecraft::worlds::read_world_version(world_dir)?;
+        let server_version = server.environment_vars.get("VERSION").cloned().unwrap_or_default();
+        super::world_upload::check_compatible(&world_version, &server_version)?;
+
+        for stage in [WorldUploadStage::Compressing, WorldUploadStage::Transferring, WorldUploadStage::Extracting] {
+            on_progress(WorldUploadProgress::new(stage, 1, 1));
+        }
+        let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+        Ok(WorldUploadResult {
+            level_name: "world".to_string(),
+            backup: backup.then(|| format!("world-backups/world-{}.tar.gz", stamp)),
+            restarted: matches!(server.status, ServerStatus::Running),
+        })
+    }
+
+    pub async fn upgrade_server(
+        &mut self,
+        server_id: &str,
+        target_version: &str,
+        force: bool,
+        on_progress: &(dyn Fn(ServerUpgradeProgress) + Send + Sync),
+    ) -> Result<ServerUpgradeResult, String> {
+        let server = self.server_mut(server_id)?;
+        let previous_version = server.last_upgrade.as_ref()
+            .map(|upgrade| upgrade.minecraft_version.clone())
+            .or_else(|| server.environment_vars.get("VERSION").cloned())
+            .unwrap_or_else(|| MOCK_VERSION.to_string());
+        let target_version = target_version.trim().to_string();
+        super::upgrade::check_versions(&previous_version, &target_version, force)?;
+
+        for stage in [ServerUpgradeStage::Backup, ServerUpgradeStage::Pull, ServerUpgradeStage::Recreate, ServerUpgradeStage::Verify] {
+            on_progress(ServerUpgradeProgress::new(stage, 100));
+        }
+        let previous_image = "itzg/minecraft-server:java17".to_string();
+        let image = super::upgrade::image_for(&previous_image, &target_version);
+        let backup = format!("upgrade-backups/{}", super::upgrade::backup_name(&previous_version, "20240101-120000"));
+        server.last_upgrade = Some(ServerUpgradeRecord {
+            previous_version: previous_version.clone(),
+            previous_image,
+            minecraft_version: target_version.clone(),
+            backup: backup.clone(),
+            upgraded_at: Self::base_time(),
+        });
+        Ok(ServerUpgradeResult { previous_version, minecraft_version: target_version, image, backup })
+    }
+
+    pub async fn export_server_compose(&self, server_id: &str, output_path: &std::path::Path) -> Result<std::path::PathBuf, String> {
+        use bollard::models::{ContainerConfig, ContainerInspectResponse, HostConfig, PortBinding};
+
+        let server = self.server(server_id)?;
+        let mut env = vec!["EULA=TRUE".to_string(), format!("VERSION={}", MOCK_VERSION), "RCON_PASSWORD=mock".to_string()];
+        env.extend(server.environment_vars.iter().map(|(key, value)| format!("{}={}", key, value)));
+        let binding = |ip: &str, port: u16| Some(vec![PortBinding { host_ip: Some(ip.to_string()), host_port: Some(port.to_string()) }]);
+        let inspect = ContainerInspectResponse {
+            name: Some(format!("/chai-server-{}", server.name)),
+            config: Some(ContainerConfig {
+                image: Some("itzg/minecraft-server:java17".to_string()),
+                env: Some(env),
+                open_stdin: Some(true),
+                ..Default::default()
+            }),
+            host_config: Some(HostConfig {
+                binds: Some(vec![format!("chai-server-data-{}:/data", server.name)]),
+                port_bindings: Some(HashMap::from([
+                    ("25565/tcp".to_string(), binding("0.0.0.0", server.port)),
+                    ("25575/tcp".to_string(), binding("127.0.0.1", super::rcon::host_port(server.port))),
+                ])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let export = super::compose::from_container(&super::compose::service_name(&server.name), &inspect, &[])?;
+        super::compose::write(output_path, &export).await?;
+        Ok(output_path.to_path_buf())
+    }
+
+    pub async fn sample_players(&mut self, server_id: &str) -> Result<PlayerSample, String> {
+        let output = self.exec_command(server_id, "list".to_string()).await?;
+        let response = super::query::parse_rcon_list(&output)
+            .ok_or_else(|| format!("Unexpected RCON list output: {}", output))?;
+
+        let history = self.player_history.entry(server_id.to_string()).or_default();
+        let sample = PlayerSample {
+            timestamp: Self::base_time() + chrono::Duration::seconds(
+                (history.len() as u64 * super::manager::STATS_POLL_INTERVAL_SECS) as i64
+            ),
+            online_players: response.online_players,
+            max_players: response.max_players,
+            players: response.players,
+            source: PlayerQuerySource::Rcon,
+            mock: true,
+        };
+        history.push(sample.clone());
+
+        Ok(sample)
+    }
+
+    pub fn get_player_history(&self, server_id: &str, _hours: u32) -> Vec<PlayerSample> {
+        self.player_history.get(server_id).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::DockerBackend;
+
+    fn backend() -> DockerBackend {
+        DockerBackend::Mock(MockDockerBackend::new())
+    }
+
+    fn minecraft_instance() -> MinecraftInstance {
+        MinecraftInstance {
+            id: "mock-instance".to_string(),
+            name: "Mock Instance".to_string(),
+            version: "1.20.1".to_string(),
+            modpack: None,
+            modpack_version: None,
+            game_dir: std::env::temp_dir().join("mock-instance"),
+            java_path: None,
+            jvm_args: None,
+            last_played: None,
+            total_play_time: 0,
+            icon: None,
+            is_modded: false,
+            mods_count: 0,
+            is_external: None,
+            external_launcher: None,
+            resolved_java_version: None,
+            java_analysis_date: None,
+            log_cleanup: None,
+            game_args: None,
+            pre_launch_command: None,
+            post_exit_command: None,
+            env_vars: None,
+            clear_inherited_env: false,
+            window_width: None,
+            window_height: None,
+            fullscreen: None,
+            jvm_args_preset: None,
+            created_at: None,
+            size_mb: None,
+            ready: true,
+            not_ready_reason: None,
+            unstable: false,
+            health: None,
+        }
+    }
+
+    fn deployment_request(auto_start: bool) -> ServerDeploymentRequest {
+        ServerDeploymentRequest {
+            name: "Test Server".to_string(),
+            minecraft_instance_id: "mock-instance".to_string(),
+            docker_connection_id: MOCK_CONNECTION_ID.to_string(),
+            port: 25570,
+            auto_assign_port: false,
+            max_players: 10,
+            memory_limit: 1024,
+            environment_vars: HashMap::new(),
+            auto_start,
+            template_id: None,
+            minecraft_version: None,
+            resources: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn seeded_data_is_marked_mock() {
+        let backend = backend();
+        assert!(backend.is_mock());
+
+        let connections = backend.get_docker_connections().await;
+        assert_eq!(connections.len(), 1);
+        assert!(connections.iter().all(|c| c.mock && c.is_connected));
+
+        let servers = backend.get_servers().await;
+        assert_eq!(servers.len(), 1);
+        assert!(servers[0].mock);
+        assert_eq!(backend.get_servers_for_instance("mock-instance").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn server_lifecycle() {
+        let mut backend = backend();
+
+        let server = backend.deploy_server(deployment_request(false), &minecraft_instance(), &|_| {}).await.unwrap();
+        assert!(server.mock);
+        assert_eq!(backend.get_server_status(&server.id).await.unwrap(), ServerStatus::Stopped);
+
+        backend.start_server(&server.id).await.unwrap();
+        assert_eq!(backend.get_server_status(&server.id).await.unwrap(), ServerStatus::Running);
+
+        backend.stop_server(&server.id).await.unwrap();
+        assert_eq!(backend.get_server_status(&server.id).await.unwrap(), ServerStatus::Stopped);
+
+        backend.remove_server(&server.id).await.unwrap();
+        assert!(backend.get_server_status(&server.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn deploy_requires_known_connection() {
+        let mut backend = backend();
+        let mut request = deployment_request(true);
+        request.docker_connection_id = "missing".to_string();
+
+        assert!(backend.deploy_server(request, &minecraft_instance(), &|_| {}).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn upgrades_refuse_downgrades_unless_forced() {
+        let mut backend = backend();
+
+        let upgraded = backend.upgrade_server(MOCK_SERVER_ID, "1.20.4", false, &|_| {}).await.unwrap();
+        assert_eq!(upgraded.previous_version, MOCK_VERSION);
+        assert!(backend.upgrade_server(MOCK_SERVER_ID, "1.20.1", false, &|_| {}).await.is_err());
+        backend.upgrade_server(MOCK_SERVER_ID, "1.20.1", true, &|_| {}).await.unwrap();
+
+        let server = backend.get_servers().await.into_iter().find(|server| server.id == MOCK_SERVER_ID).unwrap();
+        let last_upgrade = server.last_upgrade.unwrap();
+        assert_eq!(last_upgrade.previous_version, "1.20.4");
+        assert_eq!(last_upgrade.minecraft_version, "1.20.1");
+    }
+
+    #[tokio::test]
+    async fn deploy_moves_off_taken_ports_when_asked() {
+        let mut backend = backend();
+        let mut request = deployment_request(false);
+        request.port = 25565;
+        assert!(backend.deploy_server(request.clone(), &minecraft_instance(), &|_| {}).await.is_err());
+
+        request.auto_assign_port = true;
+        let server = backend.deploy_server(request, &minecraft_instance(), &|_| {}).await.unwrap();
+        assert_eq!(server.port, 25566);
+        assert!(backend.get_servers().await.iter().any(|listed| listed.id == server.id && listed.port == 25566));
+    }
+
+    #[tokio::test]
+    async fn logs_are_deterministic_and_limited() {
+        let backend = backend();
+
+        let all = backend.get_server_logs(MOCK_SERVER_ID, None).await.unwrap();
+        assert_eq!(all.len(), MOCK_LOG_LINES.len());
+
+        let tail = backend.get_server_logs(MOCK_SERVER_ID, Some(3)).await.unwrap();
+        assert_eq!(tail.len(), 3);
+        assert_eq!(tail[2].message, all[all.len() - 1].message);
+        assert_eq!(tail[2].timestamp, all[all.len() - 1].timestamp);
+    }
+
+    #[tokio::test]
+    async fn commands_require_running_server() {
+        let mut backend = backend();
+
+        let output = backend.exec_command(MOCK_SERVER_ID, "say hi".to_string()).await.unwrap();
+        assert_eq!(output, "[mock] Executed: say hi");
+
+        backend.stop_server(MOCK_SERVER_ID).await.unwrap();
+        assert!(backend.exec_command(MOCK_SERVER_ID, "list".to_string()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn players_and_stats() {
+        let mut backend = backend();
+
+        let sample = backend.sample_players(MOCK_SERVER_ID).await.unwrap();
+        assert!(sample.mock);
+        assert_eq!(sample.online_players, 2);
+        assert_eq!(sample.max_players, 20);
+        assert_eq!(sample.players, vec!["Steve", "Alex"]);
+
+        backend.sample_players(MOCK_SERVER_ID).await.unwrap();
+        assert_eq!(backend.get_player_history(MOCK_SERVER_ID, 24).len(), 2);
+
+        let stats = backend.get_server_stats(MOCK_SERVER_ID).await.unwrap();
+        assert!(stats.mock);
+        assert_eq!(stats.memory_limit, 2048 * 1024 * 1024);
+        assert_eq!(stats.online_players, vec!["Steve", "Alex"]);
+    }
+}
@@ -2,6 +2,23 @@ pub mod commands;
 pub mod manager;
 pub mod types;
 pub mod templates;
+pub mod properties;
+pub mod query;
+pub mod rcon;
+pub mod mock;
+pub mod backend;
+pub mod error;
+pub mod timeouts;
+pub mod tls;
+pub mod ssh;
+pub mod resources;
+pub mod world_upload;
+pub mod log_stream;
+pub mod players;
+pub mod ports;
+pub mod upgrade;
+pub mod compose;
 
-pub use manager::DockerManager;
+pub use backend::DockerBackend;
+pub use error::DockerError;
 pub use types::*;
\ No newline at end of file
@@ -0,0 +1,353 @@
+//! Whitelist and operators of a server
+//!
+//! whitelist.json and ops.json in the server's data folder are the lists the server
+//! loads on start. Players given by name are looked up with the Mojang API, names
+//! already on one of the lists reuse the UUID stored there. A running server is
+//! told about the changes over RCON; ops.json is written after those commands, since
+//! the server saves its own lists whenever they change and `op` can't set a level.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const WHITELIST_PATH: &str = "/data/whitelist.json";
+pub const OPS_PATH: &str = "/data/ops.json";
+
+const PROFILE_URL: &str = "https://api.mojang.com/users/profiles/minecraft";
+
+/// Level `op` gives without a server.properties override
+const DEFAULT_OP_LEVEL: u8 = 4;
+
+/// A player as the frontend names them, the UUID is looked up when missing
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlayerRef {
+    pub name: String,
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpRef {
+    #[serde(flatten)]
+    pub player: PlayerRef,
+    /// 1 to 4, 4 when missing
+    #[serde(default)]
+    pub level: Option<u8>,
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpEntry {
+    pub uuid: String,
+    pub name: String,
+    pub level: u8,
+    pub bypasses_player_limit: bool,
+}
+
+/// An entry of ops.json, which spells its keys in camel case
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpsFileEntry {
+    uuid: String,
+    name: String,
+    level: u8,
+    #[serde(default)]
+    bypasses_player_limit: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ServerPlayersConfig {
+    pub whitelist: Vec<WhitelistEntry>,
+    pub ops: Vec<OpEntry>,
+}
+
+/// Outcome of `update_server_players_config`
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerPlayersUpdate {
+    pub config: ServerPlayersConfig,
+    /// The running server took the changes over RCON
+    pub applied_live: bool,
+    pub warnings: Vec<String>,
+}
+
+impl ServerPlayersConfig {
+    /// Parse whitelist.json and ops.json, missing files read as empty lists
+    pub fn parse(whitelist: &str, ops: &str) -> Result<Self, String> {
+        let whitelist = if whitelist.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_str(whitelist).map_err(|e| format!("Invalid whitelist.json: {}", e))?
+        };
+        let ops: Vec<OpsFileEntry> = if ops.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_str(ops).map_err(|e| format!("Invalid ops.json: {}", e))?
+        };
+        let ops = ops.into_iter()
+            .map(|op| OpEntry { uuid: op.uuid, name: op.name, level: op.level, bypasses_player_limit: op.bypasses_player_limit })
+            .collect();
+        Ok(Self { whitelist, ops })
+    }
+
+    /// whitelist.json, formatted like the server writes it
+    pub fn whitelist_json(&self) -> String {
+        serde_json::to_string_pretty(&self.whitelist).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    pub fn ops_json(&self) -> String {
+        let ops: Vec<OpsFileEntry> = self.ops.iter()
+            .map(|op| OpsFileEntry { uuid: op.uuid.clone(), name: op.name.clone(), level: op.level, bypasses_player_limit: op.bypasses_player_limit })
+            .collect();
+        serde_json::to_string_pretty(&ops).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// UUID stored for a name on either list
+    fn known_uuid(&self, name: &str) -> Option<String> {
+        self.whitelist.iter().map(|entry| (&entry.name, &entry.uuid))
+            .chain(self.ops.iter().map(|op| (&op.name, &op.uuid)))
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|(_, uuid)| uuid.clone())
+    }
+}
+
+/// Minecraft names are letters, digits and underscores, which keeps them safe in commands
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 16
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid player name", name))
+    }
+}
+
+/// UUID in the dashed lowercase form the server files use
+fn normalize_uuid(uuid: &str) -> Result<String, String> {
+    uuid::Uuid::parse_str(uuid.trim())
+        .map(|uuid| uuid.hyphenated().to_string())
+        .map_err(|_| format!("'{}' is not a valid UUID", uuid))
+}
+
+/// Reject names listed twice and ones that can't be players, before anything is looked up
+fn check_refs<'a>(list: &str, names: impl Iterator<Item = &'a str>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for name in names {
+        validate_name(name)?;
+        if !seen.insert(name.to_lowercase()) {
+            return Err(format!("{} is on the {} more than once", name, list));
+        }
+    }
+    Ok(())
+}
+
+/// Look up a player's UUID and the spelling of their name, None for unknown names
+async fn lookup(client: &reqwest::Client, name: &str) -> Result<Option<WhitelistEntry>, String> {
+    #[derive(Deserialize)]
+    struct Profile {
+        id: String,
+        name: String,
+    }
+
+    let response = client.get(format!("{}/{}", PROFILE_URL, name)).send().await
+        .map_err(|e| format!("Failed to reach the Mojang API: {}", e))?;
+    match response.status() {
+        reqwest::StatusCode::OK => {
+            let profile: Profile = response.json().await
+                .map_err(|e| format!("Invalid profile for {}: {}", name, e))?;
+            Ok(Some(WhitelistEntry { uuid: normalize_uuid(&profile.id)?, name: profile.name }))
+        }
+        reqwest::StatusCode::NO_CONTENT | reqwest::StatusCode::NOT_FOUND => Ok(None),
+        reqwest::StatusCode::TOO_MANY_REQUESTS => Err("The Mojang API is rate limiting lookups, try again in a minute".to_string()),
+        status => Err(format!("The Mojang API answered {} for {}", status, name)),
+    }
+}
+
+/// Finds the UUIDs of requested players, looking each name up at most once
+struct Resolver<'a> {
+    current: &'a ServerPlayersConfig,
+    /// Created on the first lookup, most updates only touch players already listed
+    client: Option<reqwest::Client>,
+    looked_up: Vec<WhitelistEntry>,
+    unknown: Vec<String>,
+}
+
+impl Resolver<'_> {
+    /// None for names without an account, which are collected in `unknown`
+    async fn entry(&mut self, player: &PlayerRef) -> Result<Option<WhitelistEntry>, String> {
+        let name = player.name.trim();
+        if let Some(uuid) = player.uuid.as_deref().filter(|uuid| !uuid.trim().is_empty()) {
+            return Ok(Some(WhitelistEntry { uuid: normalize_uuid(uuid)?, name: name.to_string() }));
+        }
+        if let Some(uuid) = self.current.known_uuid(name) {
+            return Ok(Some(WhitelistEntry { uuid, name: name.to_string() }));
+        }
+        if let Some(found) = self.looked_up.iter().find(|found| found.name.eq_ignore_ascii_case(name)) {
+            return Ok(Some(found.clone()));
+        }
+
+        let client = self.client.get_or_insert_with(crate::http::client);
+        let found = lookup(client, name).await?;
+        match &found {
+            Some(found) => self.looked_up.push(found.clone()),
+            None => self.unknown.push(name.to_string()),
+        }
+        Ok(found)
+    }
+}
+
+/// Turn the requested lists into file entries, looking up players given only by name
+pub async fn resolve(current: &ServerPlayersConfig, whitelist: Vec<PlayerRef>, ops: Vec<OpRef>) -> Result<ServerPlayersConfig, String> {
+    check_refs("whitelist", whitelist.iter().map(|player| player.name.trim()))?;
+    check_refs("operator list", ops.iter().map(|op| op.player.name.trim()))?;
+    if let Some(op) = ops.iter().find(|op| op.level.is_some_and(|level| !(1..=4).contains(&level))) {
+        return Err(format!("The operator level of {} must be between 1 and 4", op.player.name));
+    }
+
+    let mut resolver = Resolver {
+        current,
+        client: None,
+        looked_up: Vec::new(),
+        unknown: Vec::new(),
+    };
+    let mut config = ServerPlayersConfig::default();
+    for player in &whitelist {
+        if let Some(found) = resolver.entry(player).await? {
+            config.whitelist.push(found);
+        }
+    }
+    for op in &ops {
+        if let Some(found) = resolver.entry(&op.player).await? {
+            config.ops.push(OpEntry {
+                uuid: found.uuid,
+                name: found.name,
+                level: op.level.unwrap_or(DEFAULT_OP_LEVEL),
+                bypasses_player_limit: op.bypasses_player_limit,
+            });
+        }
+    }
+    if !resolver.unknown.is_empty() {
+        return Err(format!("No Minecraft account is named {}", resolver.unknown.join(", ")));
+    }
+    check_uuids(&config)?;
+    Ok(config)
+}
+
+/// Two names resolving to the same account, e.g. an old and a new name of one player
+fn check_uuids(config: &ServerPlayersConfig) -> Result<(), String> {
+    for (list, players) in [
+        ("whitelist", config.whitelist.iter().map(|entry| (&entry.uuid, &entry.name)).collect::<Vec<_>>()),
+        ("operator list", config.ops.iter().map(|op| (&op.uuid, &op.name)).collect()),
+    ] {
+        let mut seen = HashSet::new();
+        for (uuid, name) in players {
+            if !seen.insert(uuid) {
+                return Err(format!("{} is on the {} more than once", name, list));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// RCON commands bringing a running server from `current` to `next`
+pub fn live_commands(current: &ServerPlayersConfig, next: &ServerPlayersConfig) -> Vec<String> {
+    let whitelisted: HashSet<&str> = current.whitelist.iter().map(|entry| entry.uuid.as_str()).collect();
+    let whitelisting: HashSet<&str> = next.whitelist.iter().map(|entry| entry.uuid.as_str()).collect();
+    let opped: HashSet<&str> = current.ops.iter().map(|op| op.uuid.as_str()).collect();
+    let opping: HashSet<&str> = next.ops.iter().map(|op| op.uuid.as_str()).collect();
+
+    let mut commands = Vec::new();
+    commands.extend(current.whitelist.iter().filter(|entry| !whitelisting.contains(entry.uuid.as_str()))
+        .map(|entry| format!("whitelist remove {}", entry.name)));
+    commands.extend(next.whitelist.iter().filter(|entry| !whitelisted.contains(entry.uuid.as_str()))
+        .map(|entry| format!("whitelist add {}", entry.name)));
+    commands.extend(current.ops.iter().filter(|op| !opping.contains(op.uuid.as_str()))
+        .map(|op| format!("deop {}", op.name)));
+    commands.extend(next.ops.iter().filter(|op| !opped.contains(op.uuid.as_str()))
+        .map(|op| format!("op {}", op.name)));
+    commands
+}
+
+/// Levels and limit bypasses can't be set over RCON, they load from ops.json on start
+pub fn op_settings_changed(current: &ServerPlayersConfig, next: &ServerPlayersConfig) -> bool {
+    next.ops.iter().any(|op| match current.ops.iter().find(|old| old.uuid == op.uuid) {
+        Some(old) => old.level != op.level || old.bypasses_player_limit != op.bypasses_player_limit,
+        None => op.level != DEFAULT_OP_LEVEL || op.bypasses_player_limit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEVE: &str = "8667ba71-b85a-4004-af54-457a9734eed7";
+    const ALEX: &str = "ec561538-f3fd-461d-aff5-086b22154bce";
+
+    fn player(name: &str, uuid: Option<&str>) -> PlayerRef {
+        PlayerRef { name: name.to_string(), uuid: uuid.map(str::to_string) }
+    }
+
+    #[test]
+    fn reads_and_writes_the_server_files() {
+        let config = ServerPlayersConfig::parse(
+            &format!(r#"[{{"uuid": "{}", "name": "Steve"}}]"#, STEVE),
+            &format!(r#"[{{"uuid": "{}", "name": "Alex", "level": 2, "bypassesPlayerLimit": true}}]"#, ALEX),
+        ).unwrap();
+        assert_eq!(config.whitelist[0].name, "Steve");
+        assert_eq!(config.ops[0].level, 2);
+        assert!(config.ops[0].bypasses_player_limit);
+        assert!(config.ops_json().contains("\"bypassesPlayerLimit\": true"));
+        assert_eq!(ServerPlayersConfig::parse("", "\n").unwrap(), ServerPlayersConfig::default());
+        assert!(ServerPlayersConfig::parse("{", "").is_err());
+    }
+
+    #[tokio::test]
+    async fn resolves_known_players_without_lookups() {
+        let current = ServerPlayersConfig {
+            whitelist: vec![WhitelistEntry { uuid: STEVE.to_string(), name: "Steve".to_string() }],
+            ops: Vec::new(),
+        };
+        let ops = vec![OpRef { player: player("steve", None), level: Some(3), bypasses_player_limit: false }];
+        let config = resolve(&current, vec![player("Alex", Some(&ALEX.replace('-', "")))], ops).await.unwrap();
+        assert_eq!(config.whitelist[0].uuid, ALEX);
+        assert_eq!(config.ops[0].uuid, STEVE);
+        assert_eq!(config.ops[0].level, 3);
+    }
+
+    #[tokio::test]
+    async fn rejects_duplicates_and_invalid_entries() {
+        let current = ServerPlayersConfig::default();
+        assert!(resolve(&current, vec![player("Steve", Some(STEVE)), player("STEVE", Some(STEVE))], Vec::new()).await.is_err());
+        assert!(resolve(&current, vec![player("Steve", Some(STEVE)), player("Steve2", Some(STEVE))], Vec::new()).await.is_err());
+        assert!(resolve(&current, vec![player("Steve; stop", Some(STEVE))], Vec::new()).await.is_err());
+        let ops = vec![OpRef { player: player("Steve", Some(STEVE)), level: Some(5), bypasses_player_limit: false }];
+        assert!(resolve(&current, Vec::new(), ops).await.is_err());
+    }
+
+    #[test]
+    fn diffs_into_rcon_commands() {
+        let steve = WhitelistEntry { uuid: STEVE.to_string(), name: "Steve".to_string() };
+        let alex = WhitelistEntry { uuid: ALEX.to_string(), name: "Alex".to_string() };
+        let current = ServerPlayersConfig {
+            whitelist: vec![steve.clone()],
+            ops: vec![OpEntry { uuid: STEVE.to_string(), name: "Steve".to_string(), level: 4, bypasses_player_limit: false }],
+        };
+        let next = ServerPlayersConfig {
+            whitelist: vec![steve, alex],
+            ops: vec![OpEntry { uuid: ALEX.to_string(), name: "Alex".to_string(), level: 4, bypasses_player_limit: false }],
+        };
+        assert_eq!(live_commands(&current, &next), vec!["whitelist add Alex", "deop Steve", "op Alex"]);
+        assert!(live_commands(&next, &next).is_empty());
+        assert!(!op_settings_changed(&current, &next));
+
+        let mut promoted = next.clone();
+        promoted.ops[0].level = 2;
+        assert!(op_settings_changed(&next, &promoted));
+    }
+}
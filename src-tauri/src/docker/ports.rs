@@ -0,0 +1,137 @@
+//! Host ports of servers
+//!
+//! A server publishes its game port over TCP and UDP and its RCON port next to it.
+//! Both are checked before the container is created, against the ports of managed
+//! servers, ports other containers publish and, for local daemons, ports something
+//! on this machine has bound. Docker would only fail the start with a bind error.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, TcpListener, UdpSocket};
+
+use super::rcon;
+use crate::storage::ServerPortRange;
+
+/// Host ports already taken on a Docker host, with who holds them
+#[derive(Debug, Default)]
+pub struct PortsInUse {
+    owners: HashMap<u16, String>,
+}
+
+impl PortsInUse {
+    /// Record `port` as held by `owner`, the first owner recorded is kept
+    pub fn insert(&mut self, port: u16, owner: &str) {
+        self.owners.entry(port).or_insert_with(|| owner.to_string());
+    }
+}
+
+/// Whether `port` can be bound on this machine over TCP and UDP
+pub fn free_on_this_host(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok()
+        && UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok()
+}
+
+/// Pick the host port of a new server
+///
+/// The requested port is used when it and its RCON port are free. Otherwise the
+/// deployment is refused, or with `auto_assign` the first free port of `range` is
+/// taken. `is_free` probes ports the managed servers and containers don't account for.
+pub fn allocate(
+    requested: u16,
+    auto_assign: bool,
+    range: ServerPortRange,
+    in_use: &PortsInUse,
+    is_free: impl Fn(u16) -> bool,
+) -> Result<u16, String> {
+    let Some(reason) = conflict(requested, in_use, &is_free) else {
+        return Ok(requested);
+    };
+    if !auto_assign {
+        return Err(format!("{}, choose another port or let one be assigned", reason));
+    }
+    if range.start > range.end {
+        return Err(format!("The server port range {}-{} is empty", range.start, range.end));
+    }
+    (range.start..=range.end)
+        .find(|&port| conflict(port, in_use, &is_free).is_none())
+        .ok_or_else(|| format!("{}, and no port between {} and {} is free", reason, range.start, range.end))
+}
+
+/// Why a server can't be published on `port`, None when it can
+fn conflict(port: u16, in_use: &PortsInUse, is_free: &impl Fn(u16) -> bool) -> Option<String> {
+    if port == 0 {
+        return Some("Port 0 can't be published".to_string());
+    }
+    let Some(rcon_port) = port.checked_add(rcon::HOST_PORT_OFFSET) else {
+        return Some(format!("Port {} leaves no room for its RCON port", port));
+    };
+    let labels = [format!("Port {}", port), format!("RCON port {} of port {}", rcon_port, port)];
+    for (candidate, label) in [port, rcon_port].into_iter().zip(labels) {
+        if let Some(owner) = in_use.owners.get(&candidate) {
+            return Some(format!("{} is already used by {}", label, owner));
+        }
+        if !is_free(candidate) {
+            return Some(format!("{} is already in use on the host", label));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: ServerPortRange = ServerPortRange { start: 25565, end: 25600 };
+
+    fn servers(ports: &[u16]) -> PortsInUse {
+        let mut in_use = PortsInUse::default();
+        for (i, &port) in ports.iter().enumerate() {
+            let owner = format!("server \"s{}\"", i);
+            in_use.insert(port, &owner);
+            in_use.insert(rcon::host_port(port), &owner);
+        }
+        in_use
+    }
+
+    #[test]
+    fn keeps_a_free_requested_port() {
+        assert_eq!(allocate(25565, false, RANGE, &servers(&[]), |_| true), Ok(25565));
+        // Outside the range is fine when nothing holds it
+        assert_eq!(allocate(30000, true, RANGE, &servers(&[25565]), |_| true), Ok(30000));
+    }
+
+    #[test]
+    fn refuses_taken_ports_without_auto_assign() {
+        let error = allocate(25565, false, RANGE, &servers(&[25565]), |_| true).unwrap_err();
+        assert!(error.starts_with("Port 25565 is already used by server \"s0\""), "{}", error);
+
+        // The RCON port of 25555 is 25565's game port
+        let error = allocate(25555, false, RANGE, &servers(&[25565]), |_| true).unwrap_err();
+        assert!(error.starts_with("RCON port 25565 of port 25555 is already used"), "{}", error);
+
+        let error = allocate(25566, false, RANGE, &servers(&[]), |port| port != 25566).unwrap_err();
+        assert!(error.starts_with("Port 25566 is already in use on the host"), "{}", error);
+    }
+
+    #[test]
+    fn assigns_the_next_port_clear_of_game_and_rcon_ports() {
+        // 25565 and its RCON port 25575 are taken, 25566's RCON port 25576 is bound on the host
+        let in_use = servers(&[25565]);
+        assert_eq!(allocate(25565, true, RANGE, &in_use, |port| port != 25576), Ok(25567));
+
+        // Ports whose RCON port lands on a taken game port are skipped too
+        let in_use = servers(&[25565, 25570]);
+        let range = ServerPortRange { start: 25560, end: 25600 };
+        assert_eq!(allocate(25565, true, range, &in_use, |_| true), Ok(25561));
+    }
+
+    #[test]
+    fn reports_an_exhausted_or_empty_range() {
+        let range = ServerPortRange { start: 25565, end: 25566 };
+        let error = allocate(25565, true, range, &servers(&[25565]), |port| port != 25566).unwrap_err();
+        assert!(error.ends_with("no port between 25565 and 25566 is free"), "{}", error);
+
+        let range = ServerPortRange { start: 25600, end: 25500 };
+        assert!(allocate(25565, true, range, &servers(&[25565]), |_| true).is_err());
+        assert!(allocate(65530, false, RANGE, &servers(&[]), |_| true).is_err());
+    }
+}
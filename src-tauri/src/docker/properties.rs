@@ -0,0 +1,365 @@
+//! Reading and editing a server's server.properties
+//!
+//! The file follows the Java properties format: `#` and `!` comments, `=`, `:` or
+//! whitespace between key and value, backslash escapes and continuation lines.
+//! Edits rewrite only the entries whose value changed, so comments, ordering and
+//! keys the launcher doesn't know about stay as the server wrote them.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Path of the file inside the itzg container
+pub const SERVER_PROPERTIES_PATH: &str = "/data/server.properties";
+
+/// Keys the running server can't pick up without a restart
+const RESTART_KEYS: &[&str] = &[
+    "server-port",
+    "server-ip",
+    "level-name",
+    "level-seed",
+    "level-type",
+    "online-mode",
+    "enable-rcon",
+    "rcon.port",
+    "enable-query",
+    "query.port",
+];
+
+/// Keys the itzg image rewrites from the container environment on every start
+const ENV_MANAGED_KEYS: &[(&str, &str)] = &[
+    ("max-players", "MAX_PLAYERS"),
+    ("motd", "MOTD"),
+    ("online-mode", "ONLINE_MODE"),
+    ("enable-rcon", "ENABLE_RCON"),
+    ("rcon.password", "RCON_PASSWORD"),
+    ("rcon.port", "RCON_PORT"),
+    ("enable-query", "ENABLE_QUERY"),
+];
+
+/// Outcome of `update_server_properties`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServerPropertiesUpdate {
+    /// Every property after the update
+    pub properties: BTreeMap<String, String>,
+    /// Keys whose value changed
+    pub changed: Vec<String>,
+    /// A changed key only takes effect after the server restarts
+    pub needs_restart: bool,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    /// Comment, blank line or anything else kept as written
+    Raw(String),
+    Entry { key: String, value: String, raw: String },
+}
+
+/// A parsed server.properties that can be written back unchanged
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropertiesFile {
+    lines: Vec<Line>,
+}
+
+impl PropertiesFile {
+    pub fn parse(text: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut physical = text.lines();
+        while let Some(line) = physical.next() {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                lines.push(Line::Raw(line.to_string()));
+                continue;
+            }
+
+            // A line ending in an odd number of backslashes continues on the next one
+            let mut raw = line.to_string();
+            let mut logical = trimmed.to_string();
+            while ends_with_continuation(&logical) {
+                logical.pop();
+                match physical.next() {
+                    Some(next) => {
+                        raw.push('\n');
+                        raw.push_str(next);
+                        logical.push_str(next.trim_start());
+                    }
+                    None => break,
+                }
+            }
+
+            let (key, value) = split_entry(&logical);
+            lines.push(Line::Entry { key: unescape(key), value: unescape(value), raw });
+        }
+        Self { lines }
+    }
+
+    /// Value of `key`, the last one wins when it appears twice like in Java
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().rev().find_map(|line| match line {
+            Line::Entry { key: k, value, .. } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn to_map(&self) -> BTreeMap<String, String> {
+        let mut map = BTreeMap::new();
+        for line in &self.lines {
+            if let Line::Entry { key, value, .. } = line {
+                map.insert(key.clone(), value.clone());
+            }
+        }
+        map
+    }
+
+    /// Set `key`, returning whether its value changed
+    ///
+    /// An existing entry is rewritten in place, a new key goes at the end.
+    pub fn set(&mut self, key: &str, value: &str) -> bool {
+        if self.get(key) == Some(value) {
+            return false;
+        }
+
+        let raw = format!("{}={}", escape(key, true), escape(value, false));
+        let existing = self.lines.iter_mut().rev().find_map(|line| match line {
+            Line::Entry { key: k, value, raw } if k == key => Some((value, raw)),
+            _ => None,
+        });
+        match existing {
+            Some((old_value, old_raw)) => {
+                *old_value = value.to_string();
+                *old_raw = raw;
+            }
+            None => self.lines.push(Line::Entry { key: key.to_string(), value: value.to_string(), raw }),
+        }
+        true
+    }
+
+    /// Apply `properties` and report what the change means for the server
+    pub fn update(&mut self, properties: &BTreeMap<String, String>) -> ServerPropertiesUpdate {
+        let mut update = ServerPropertiesUpdate::default();
+        for (key, value) in properties {
+            let key = key.trim();
+            if key.is_empty() || !self.set(key, value) {
+                continue;
+            }
+
+            update.needs_restart |= RESTART_KEYS.contains(&key);
+            if let Some((_, env)) = ENV_MANAGED_KEYS.iter().find(|(managed, _)| *managed == key) {
+                update.warnings.push(format!("{} is set from {} when the container starts and may be overwritten", key, env));
+            }
+            update.changed.push(key.to_string());
+        }
+        update.properties = self.to_map();
+        update
+    }
+}
+
+impl fmt::Display for PropertiesFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Raw(raw) | Line::Entry { raw, .. } => writeln!(f, "{}", raw)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decode file contents, older servers write Latin-1 rather than UTF-8
+pub fn decode(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+/// Split a logical line into its still escaped key and value
+fn split_entry(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    let mut key_end = line.len();
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || c.is_whitespace() {
+            key_end = i;
+            break;
+        }
+    }
+
+    let key = &line[..key_end];
+    let mut rest = line[key_end..].trim_start_matches([' ', '\t', '\u{c}']);
+    if let Some(stripped) = rest.strip_prefix(['=', ':']) {
+        rest = stripped.trim_start_matches([' ', '\t', '\u{c}']);
+    }
+    (key, rest)
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut pending_high: Option<u16> = None;
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            flush_surrogate(&mut out, &mut pending_high);
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                match u16::from_str_radix(&hex, 16) {
+                    Ok(unit) => push_utf16(&mut out, &mut pending_high, unit),
+                    Err(_) => {
+                        flush_surrogate(&mut out, &mut pending_high);
+                        out.push_str(&hex);
+                    }
+                }
+            }
+            Some(other) => {
+                flush_surrogate(&mut out, &mut pending_high);
+                out.push(match other {
+                    't' => '\t',
+                    'n' => '\n',
+                    'r' => '\r',
+                    'f' => '\u{c}',
+                    other => other,
+                });
+            }
+            None => flush_surrogate(&mut out, &mut pending_high),
+        }
+    }
+    flush_surrogate(&mut out, &mut pending_high);
+    out
+}
+
+/// Java escapes characters outside the BMP as surrogate pairs
+fn push_utf16(out: &mut String, pending_high: &mut Option<u16>, unit: u16) {
+    match (pending_high.take(), unit) {
+        (Some(high), 0xDC00..=0xDFFF) => {
+            out.extend(char::decode_utf16([high, unit]).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)));
+        }
+        (high, 0xD800..=0xDBFF) => {
+            if high.is_some() {
+                out.push(char::REPLACEMENT_CHARACTER);
+            }
+            *pending_high = Some(unit);
+        }
+        (high, _) => {
+            if high.is_some() {
+                out.push(char::REPLACEMENT_CHARACTER);
+            }
+            out.push(char::from_u32(unit as u32).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+    }
+}
+
+fn flush_surrogate(out: &mut String, pending_high: &mut Option<u16>) {
+    if pending_high.take().is_some() {
+        out.push(char::REPLACEMENT_CHARACTER);
+    }
+}
+
+/// Escape like `Properties.store`, non-ASCII becomes `\uXXXX` so any server reads it
+fn escape(text: &str, is_key: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{c}' => out.push_str("\\f"),
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            '=' | ':' | '#' | '!' if is_key => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_escapes_and_separators() {
+        let file = PropertiesFile::parse(concat!(
+            "#Minecraft server properties\n",
+            "! old style comment\n",
+            "level-name=my\\ world\n",
+            "key\\=with\\:separators = value\n",
+            "spaced   value with spaces\n",
+            "colon:1\n",
+            "path=C\\:\\\\servers\\\\data\\ttab\n",
+            "long=first \\\n",
+            "    second\n",
+            "empty=\n",
+        ));
+
+        assert_eq!(file.get("level-name"), Some("my world"));
+        assert_eq!(file.get("key=with:separators"), Some("value"));
+        assert_eq!(file.get("spaced"), Some("value with spaces"));
+        assert_eq!(file.get("colon"), Some("1"));
+        assert_eq!(file.get("path"), Some("C:\\servers\\data\ttab"));
+        assert_eq!(file.get("long"), Some("first second"));
+        assert_eq!(file.get("empty"), Some(""));
+        assert_eq!(file.to_map().len(), 7);
+    }
+
+    #[test]
+    fn reads_and_writes_unicode_motds() {
+        let mut file = PropertiesFile::parse("motd=\\u00A7aCaf\\u00E9 \\uD83C\\uDF75 server\nmax-players=20\n");
+        assert_eq!(file.get("motd"), Some("§aCafé 🍵 server"));
+
+        // Raw UTF-8 as written by newer servers reads the same
+        assert_eq!(PropertiesFile::parse("motd=§aCafé 🍵 server").get("motd"), Some("§aCafé 🍵 server"));
+
+        assert!(file.set("motd", "日本語 🍵 #1"));
+        let written = file.to_string();
+        assert!(written.contains("motd=\\u65E5\\u672C\\u8A9E \\uD83C\\uDF75 #1\n"));
+        assert_eq!(PropertiesFile::parse(&written).get("motd"), Some("日本語 🍵 #1"));
+
+        assert_eq!(decode(&[b'm', b'=', 0xE9]), "m=é");
+    }
+
+    #[test]
+    fn update_keeps_comments_and_unknown_keys() {
+        let original = "#Minecraft server properties\n#Sat Jan 01 00:00:00 UTC 2024\nserver-port = 25565\ncustom-plugin-key:keep me\npvp=true\n";
+        let mut file = PropertiesFile::parse(original);
+        assert_eq!(file.to_string(), original);
+
+        let update = file.update(&BTreeMap::from([
+            ("pvp".to_string(), "true".to_string()),
+            ("difficulty".to_string(), "hard".to_string()),
+        ]));
+        assert_eq!(update.changed, vec!["difficulty".to_string()]);
+        assert!(!update.needs_restart);
+        assert_eq!(file.to_string(), format!("{}difficulty=hard\n", original));
+
+        let update = file.update(&BTreeMap::from([
+            ("server-port".to_string(), "25570".to_string()),
+            ("motd".to_string(), " leading space".to_string()),
+        ]));
+        assert!(update.needs_restart);
+        assert_eq!(update.warnings.len(), 1);
+        assert_eq!(update.properties.get("custom-plugin-key").map(String::as_str), Some("keep me"));
+        assert!(file.to_string().contains("server-port=25570\ncustom-plugin-key:keep me\n"));
+        assert!(file.to_string().ends_with("motd=\\ leading space\n"));
+    }
+}
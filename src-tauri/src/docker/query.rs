@@ -0,0 +1,213 @@
+//! Minecraft server query protocol (UT3/GameSpy4)
+//!
+//! Used to read the online player list from servers that have `enable-query` turned on.
+//! Servers without query support fall back to RCON `list` in the Docker manager.
+//! `QuerySupport` remembers which servers those are.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+const QUERY_MAGIC: [u8; 2] = [0xFE, 0xFD];
+const QUERY_TYPE_HANDSHAKE: u8 = 0x09;
+const QUERY_TYPE_STAT: u8 = 0x00;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Failed queries in a row on a started server before only RCON is used for it
+pub const QUERY_FAILURE_LIMIT: u32 = 3;
+
+/// Result of a full stat query
+#[derive(Debug, Clone)]
+pub struct QueryResponse {
+    pub online_players: u32,
+    pub max_players: u32,
+    pub players: Vec<String>,
+}
+
+/// Run a full stat query against a server and return its player list
+pub async fn query_full_stat(host: &str, port: u16) -> Result<QueryResponse, String> {
+    let addr = tokio::net::lookup_host((host, port)).await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("No address found for {}", host))?;
+
+    let bind_addr = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(bind_addr).await
+        .map_err(|e| format!("Failed to bind query socket: {}", e))?;
+    socket.connect(addr).await
+        .map_err(|e| format!("Failed to connect query socket: {}", e))?;
+
+    // Session IDs only use the lower 4 bits of each byte
+    let session_id = (uuid::Uuid::new_v4().as_u128() as i32) & 0x0F0F0F0F;
+    let mut buffer = vec![0u8; 65535];
+
+    // Handshake to obtain a challenge token
+    let mut handshake = Vec::with_capacity(7);
+    handshake.extend_from_slice(&QUERY_MAGIC);
+    handshake.push(QUERY_TYPE_HANDSHAKE);
+    handshake.extend_from_slice(&session_id.to_be_bytes());
+
+    let len = send_and_receive(&socket, &handshake, &mut buffer).await?;
+    if len < 5 || buffer[0] != QUERY_TYPE_HANDSHAKE {
+        return Err("Invalid query handshake response".to_string());
+    }
+    let token = read_cstring(&buffer[5..len]);
+    let challenge: i32 = token.trim().parse()
+        .map_err(|_| format!("Invalid query challenge token: {}", token))?;
+
+    // Full stat request (padding to 4 bytes selects the full response)
+    let mut request = Vec::with_capacity(15);
+    request.extend_from_slice(&QUERY_MAGIC);
+    request.push(QUERY_TYPE_STAT);
+    request.extend_from_slice(&session_id.to_be_bytes());
+    request.extend_from_slice(&challenge.to_be_bytes());
+    request.extend_from_slice(&[0u8; 4]);
+
+    let len = send_and_receive(&socket, &request, &mut buffer).await?;
+    if len < 5 || buffer[0] != QUERY_TYPE_STAT {
+        return Err("Invalid query stat response".to_string());
+    }
+
+    parse_full_stat(&buffer[5..len])
+}
+
+async fn send_and_receive(socket: &UdpSocket, packet: &[u8], buffer: &mut [u8]) -> Result<usize, String> {
+    socket.send(packet).await
+        .map_err(|e| format!("Failed to send query packet: {}", e))?;
+
+    match tokio::time::timeout(QUERY_TIMEOUT, socket.recv(buffer)).await {
+        Ok(Ok(len)) => Ok(len),
+        Ok(Err(e)) => Err(format!("Failed to read query response: {}", e)),
+        Err(_) => Err("Query timed out (is enable-query set?)".to_string()),
+    }
+}
+
+/// Parse the payload of a full stat response (after type and session ID)
+fn parse_full_stat(payload: &[u8]) -> Result<QueryResponse, String> {
+    // Payload starts with the constant "splitnum\0\x80\0" padding
+    let body = payload.get(11..).ok_or("Query response too short")?;
+    let mut fields = body.split(|b| *b == 0);
+
+    let mut values = HashMap::new();
+    while let Some(key) = fields.next() {
+        if key.is_empty() {
+            break;
+        }
+        let value = fields.next().unwrap_or(&[]);
+        values.insert(
+            String::from_utf8_lossy(key).to_string(),
+            String::from_utf8_lossy(value).to_string(),
+        );
+    }
+
+    // Skip the "\x01player_\0\0" padding before the player section
+    let _ = fields.next();
+    let _ = fields.next();
+
+    let players: Vec<String> = fields
+        .take_while(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).to_string())
+        .collect();
+
+    let online_players = values.get("numplayers")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(players.len() as u32);
+    let max_players = values.get("maxplayers")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    Ok(QueryResponse {
+        online_players,
+        max_players,
+        players,
+    })
+}
+
+/// Parse the output of the RCON `list` command
+///
+/// Handles both "There are 1 of a max of 20 players online: Steve" and the
+/// pre-1.13 "There are 1/20 players online:\nSteve" formats.
+pub fn parse_rcon_list(output: &str) -> Option<QueryResponse> {
+    let (header, names) = output.split_once(':')?;
+
+    let numbers: Vec<u32> = header
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect();
+    if numbers.len() < 2 {
+        return None;
+    }
+
+    let players: Vec<String> = names
+        .split([',', '\n'])
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    Some(QueryResponse {
+        online_players: numbers[0],
+        max_players: numbers[1],
+        players,
+    })
+}
+
+/// Servers that don't answer queries, learned while sampling their players
+///
+/// A failed query only counts when RCON answered right after, since a server that
+/// is still starting answers neither. Queries are tried again after a restart.
+#[derive(Debug, Default)]
+pub struct QuerySupport {
+    failures: HashMap<String, u32>,
+}
+
+impl QuerySupport {
+    pub fn should_query(&self, server_id: &str) -> bool {
+        self.failures.get(server_id).is_none_or(|failures| *failures < QUERY_FAILURE_LIMIT)
+    }
+
+    pub fn answered(&mut self, server_id: &str) {
+        self.failures.remove(server_id);
+    }
+
+    /// Count a failed query of a server that answered RCON, true once it is given up on
+    pub fn failed_while_started(&mut self, server_id: &str) -> bool {
+        let failures = self.failures.entry(server_id.to_string()).or_default();
+        *failures += 1;
+        *failures == QUERY_FAILURE_LIMIT
+    }
+
+    /// Probe the server again, e.g. after it was restarted with a new configuration
+    pub fn reset(&mut self, server_id: &str) {
+        self.failures.remove(server_id);
+    }
+}
+
+fn read_cstring(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_is_given_up_only_after_repeated_failures() {
+        let mut support = QuerySupport::default();
+        assert!(support.should_query("a"));
+
+        assert!(!support.failed_while_started("a"));
+        assert!(!support.failed_while_started("a"));
+        assert!(support.should_query("a"));
+        // One answer in between starts the count over
+        support.answered("a");
+        assert!(!support.failed_while_started("a"));
+        assert!(!support.failed_while_started("a"));
+        assert!(support.failed_while_started("a"));
+        assert!(!support.should_query("a"));
+        assert!(support.should_query("b"));
+
+        support.reset("a");
+        assert!(support.should_query("a"));
+    }
+}
@@ -0,0 +1,320 @@
+//! Minecraft RCON client
+//!
+//! RCON is the Source RCON protocol over TCP: little endian length, request id and
+//! type, then a null terminated body. A login packet comes first; a rejected
+//! password is answered with id -1. Long responses are split over several packets
+//! with no end marker, so every command is followed by an empty packet of an
+//! unknown type and everything before its answer belongs to the command.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::error::DockerError;
+
+const PACKET_LOGIN: i32 = 3;
+const PACKET_COMMAND: i32 = 2;
+const PACKET_RESPONSE: i32 = 0;
+
+/// RCON port inside the itzg container
+pub const CONTAINER_RCON_PORT: u16 = 25575;
+
+/// Password of servers deployed before passwords were generated
+pub const LEGACY_PASSWORD: &str = "minecraft";
+
+/// Distance of the published RCON port from the game port
+pub const HOST_PORT_OFFSET: u16 = 10;
+
+/// Host port RCON is published on, next to the game port
+pub fn host_port(game_port: u16) -> u16 {
+    game_port + HOST_PORT_OFFSET
+}
+
+/// How commands reach a server's RCON
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RconRoute {
+    /// Straight to the published port
+    Direct { host: String, port: u16 },
+    /// Through `rcon-cli` in the container, since remote hosts only publish RCON on their own loopback
+    ContainerCli,
+}
+
+/// Route to a server whose ports are published on `host`, `remote` when that is another machine
+pub fn route(remote: bool, host: &str, rcon_port: Option<u16>, game_port: u16) -> RconRoute {
+    if remote {
+        return RconRoute::ContainerCli;
+    }
+    RconRoute::Direct { host: host.to_string(), port: rcon_port.unwrap_or_else(|| host_port(game_port)) }
+}
+
+/// Secret name of a server's RCON password
+pub fn password_key(server_id: &str) -> String {
+    format!("rcon:{}", server_id)
+}
+
+/// Largest body a server accepts from a client
+pub const MAX_COMMAND_LEN: usize = 1446;
+
+/// Largest packet a server sends, responses longer than this are split
+const MAX_PACKET_LEN: i32 = 4096 + 10;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum RconError {
+    #[error("RCON connection failed: {0}")]
+    Connection(#[from] std::io::Error),
+    #[error("RCON password was rejected")]
+    AuthFailed,
+    #[error("RCON server didn't answer within {0}s")]
+    Timeout(u64),
+    #[error("Invalid RCON packet: {0}")]
+    Protocol(String),
+    #[error("Command is longer than {} bytes", MAX_COMMAND_LEN)]
+    CommandTooLong,
+}
+
+impl From<RconError> for DockerError {
+    fn from(error: RconError) -> Self {
+        DockerError::from(error.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Packet {
+    id: i32,
+    kind: i32,
+    body: String,
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        let body = self.body.as_bytes();
+        let mut bytes = Vec::with_capacity(14 + body.len());
+        bytes.extend_from_slice(&(10 + body.len() as i32).to_le_bytes());
+        bytes.extend_from_slice(&self.id.to_le_bytes());
+        bytes.extend_from_slice(&self.kind.to_le_bytes());
+        bytes.extend_from_slice(body);
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+
+    /// Decode the part after the length prefix
+    fn decode(bytes: &[u8]) -> Result<Self, RconError> {
+        if bytes.len() < 10 {
+            return Err(RconError::Protocol(format!("{} byte packet is too short", bytes.len())));
+        }
+        let id = i32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let kind = i32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        let body = &bytes[8..bytes.len() - 2];
+        Ok(Packet { id, kind, body: String::from_utf8_lossy(body).into_owned() })
+    }
+}
+
+/// An authenticated RCON connection
+pub struct RconClient {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl RconClient {
+    pub async fn connect(host: &str, port: u16, password: &str) -> Result<Self, RconError> {
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host, port))).await
+            .map_err(|_| RconError::Timeout(CONNECT_TIMEOUT.as_secs()))??;
+        let mut client = Self { stream, next_id: 1 };
+
+        let login = tokio::time::timeout(RESPONSE_TIMEOUT, client.login(password)).await
+            .map_err(|_| RconError::Timeout(RESPONSE_TIMEOUT.as_secs()))?;
+        login?;
+        Ok(client)
+    }
+
+    async fn login(&mut self, password: &str) -> Result<(), RconError> {
+        let id = self.send(PACKET_LOGIN, password).await?;
+        // Some servers send an empty response before the login result
+        loop {
+            let packet = self.receive().await?;
+            if packet.id == -1 {
+                return Err(RconError::AuthFailed);
+            }
+            if packet.id == id && packet.kind == PACKET_COMMAND {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Run a command and return everything the server answered
+    pub async fn command(&mut self, command: &str) -> Result<String, RconError> {
+        if command.len() > MAX_COMMAND_LEN {
+            return Err(RconError::CommandTooLong);
+        }
+        tokio::time::timeout(RESPONSE_TIMEOUT, self.exchange(command)).await
+            .map_err(|_| RconError::Timeout(RESPONSE_TIMEOUT.as_secs()))?
+    }
+
+    async fn exchange(&mut self, command: &str) -> Result<String, RconError> {
+        let id = self.send(PACKET_COMMAND, command).await?;
+        let sentinel = self.send(PACKET_RESPONSE, "").await?;
+
+        let mut response = String::new();
+        loop {
+            let packet = self.receive().await?;
+            if packet.id == sentinel {
+                return Ok(response);
+            }
+            if packet.id == id {
+                response.push_str(&packet.body);
+            }
+        }
+    }
+
+    async fn send(&mut self, kind: i32, body: &str) -> Result<i32, RconError> {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        self.stream.write_all(&Packet { id, kind, body: body.to_string() }.encode()).await?;
+        Ok(id)
+    }
+
+    async fn receive(&mut self) -> Result<Packet, RconError> {
+        let mut length = [0u8; 4];
+        self.stream.read_exact(&mut length).await?;
+        let length = i32::from_le_bytes(length);
+        if !(10..=MAX_PACKET_LEN).contains(&length) {
+            return Err(RconError::Protocol(format!("length {}", length)));
+        }
+
+        let mut bytes = vec![0u8; length as usize];
+        self.stream.read_exact(&mut bytes).await?;
+        Packet::decode(&bytes)
+    }
+}
+
+/// Open RCON connections, one per server
+#[derive(Default)]
+pub struct RconPool {
+    clients: HashMap<String, RconClient>,
+}
+
+impl RconPool {
+    /// Run a command on `server_id`, connecting on first use
+    ///
+    /// A kept connection the server dropped, e.g. after a restart, is replaced and
+    /// the command sent once more. After a timeout the connection is dropped but the
+    /// command isn't repeated, as it may have run.
+    pub async fn command(&mut self, server_id: &str, host: &str, port: u16, password: &str, command: &str) -> Result<String, RconError> {
+        if let Some(client) = self.clients.get_mut(server_id) {
+            match client.command(command).await {
+                Ok(response) => return Ok(response),
+                Err(RconError::CommandTooLong) => return Err(RconError::CommandTooLong),
+                Err(RconError::Connection(e)) => {
+                    println!("🔌 RCON connection to server {} was lost, reconnecting: {}", server_id, e);
+                    self.clients.remove(server_id);
+                }
+                Err(e) => {
+                    self.clients.remove(server_id);
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut client = RconClient::connect(host, port, password).await?;
+        let response = client.command(command).await?;
+        self.clients.insert(server_id.to_string(), client);
+        Ok(response)
+    }
+
+    /// Close the connection to a stopped or removed server
+    pub fn disconnect(&mut self, server_id: &str) {
+        self.clients.remove(server_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    async fn read_packet(stream: &mut TcpStream) -> Option<Packet> {
+        let mut length = [0u8; 4];
+        stream.read_exact(&mut length).await.ok()?;
+        let mut bytes = vec![0u8; i32::from_le_bytes(length) as usize];
+        stream.read_exact(&mut bytes).await.ok()?;
+        Packet::decode(&bytes).ok()
+    }
+
+    async fn write_packet(stream: &mut TcpStream, id: i32, kind: i32, body: &str) {
+        stream.write_all(&Packet { id, kind, body: body.to_string() }.encode()).await.unwrap();
+    }
+
+    /// Accepts `connections` clients, splits responses in two and hangs up after
+    /// `commands_per_connection` commands
+    async fn fake_server(connections: usize, commands_per_connection: usize) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for _ in 0..connections {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let login = read_packet(&mut stream).await.unwrap();
+                let accepted = login.body == "secret";
+                write_packet(&mut stream, login.id, PACKET_RESPONSE, "").await;
+                write_packet(&mut stream, if accepted { login.id } else { -1 }, PACKET_COMMAND, "").await;
+
+                let mut commands = 0;
+                while let Some(packet) = read_packet(&mut stream).await {
+                    if packet.kind == PACKET_COMMAND {
+                        let response = format!("ran {} ✓", packet.body);
+                        let (first, second) = response.split_at(4);
+                        write_packet(&mut stream, packet.id, PACKET_RESPONSE, first).await;
+                        write_packet(&mut stream, packet.id, PACKET_RESPONSE, second).await;
+                        commands += 1;
+                    } else {
+                        write_packet(&mut stream, packet.id, PACKET_RESPONSE, "Unknown request 0").await;
+                        if commands == commands_per_connection {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn packets_round_trip() {
+        let packet = Packet { id: 7, kind: PACKET_COMMAND, body: "say héllo".to_string() };
+        let bytes = packet.encode();
+        assert_eq!(i32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize, bytes.len() - 4);
+        assert_eq!(&bytes[bytes.len() - 2..], &[0, 0]);
+        assert_eq!(Packet::decode(&bytes[4..]).unwrap(), packet);
+        assert!(Packet::decode(&[0; 6]).is_err());
+    }
+
+    #[test]
+    fn remote_servers_are_reached_through_the_container() {
+        assert_eq!(route(true, "docker.example.com", Some(25600), 25565), RconRoute::ContainerCli);
+        assert_eq!(route(false, "127.0.0.1", None, 25565), RconRoute::Direct { host: "127.0.0.1".to_string(), port: 25575 });
+        assert_eq!(route(false, "127.0.0.1", Some(25600), 25565), RconRoute::Direct { host: "127.0.0.1".to_string(), port: 25600 });
+    }
+
+    #[tokio::test]
+    async fn joins_split_responses_and_rejects_bad_passwords() {
+        let port = fake_server(2, 2).await;
+        assert!(matches!(RconClient::connect("127.0.0.1", port, "wrong").await, Err(RconError::AuthFailed)));
+
+        let mut client = RconClient::connect("127.0.0.1", port, "secret").await.unwrap();
+        assert_eq!(client.command("list").await.unwrap(), "ran list ✓");
+        assert_eq!(client.command("say hi").await.unwrap(), "ran say hi ✓");
+        assert!(matches!(client.command(&"x".repeat(MAX_COMMAND_LEN + 1)).await, Err(RconError::CommandTooLong)));
+    }
+
+    #[tokio::test]
+    async fn pool_reconnects_after_the_server_hangs_up() {
+        let port = fake_server(2, 1).await;
+        let mut pool = RconPool::default();
+        assert_eq!(pool.command("a", "127.0.0.1", port, "secret", "list").await.unwrap(), "ran list ✓");
+        // The first connection is closed now, the pool opens the second one
+        assert_eq!(pool.command("a", "127.0.0.1", port, "secret", "time").await.unwrap(), "ran time ✓");
+    }
+}
@@ -0,0 +1,174 @@
+//! CPU and memory limits of server containers
+//!
+//! Limits are set on the container's HostConfig at deploy and changed in place
+//! with a container update afterwards. Docker can't lift a limit on a running
+//! container, so removing one means recreating the container with the same
+//! configuration. Swap is turned off below the memory limit, a JVM heap in swap
+//! stalls the server rather than slowing it down.
+
+use bollard::container::{MemoryStatsStats, Stats, UpdateContainerOptions};
+use bollard::models::HostConfig;
+use serde::Serialize;
+
+use super::types::ServerResourceLimits;
+
+/// Smallest memory limit Docker accepts
+const MIN_MEMORY_MB: u64 = 6;
+
+const MB: i64 = 1024 * 1024;
+
+/// Outcome of `update_server_resources`
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerResourcesUpdate {
+    pub resources: ServerResourceLimits,
+    /// The container was replaced because a limit was removed
+    pub recreated: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Live usage read from one `docker stats` sample
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Usage {
+    /// Percent of one core, above 100 when several cores are busy
+    pub cpu_percent: f64,
+    pub online_cpus: u64,
+    /// Bytes, without page cache the kernel can drop
+    pub memory_usage: u64,
+    /// Bytes, the host's memory when the container has no limit
+    pub memory_limit: u64,
+    pub network_in: u64,
+    pub network_out: u64,
+}
+
+/// Reject limits Docker would refuse and warn about ones the server won't fit in
+pub fn validate(limits: &ServerResourceLimits, heap_mb: u64) -> Result<Vec<String>, String> {
+    if let Some(cpus) = limits.cpus {
+        if !cpus.is_finite() || cpus <= 0.0 {
+            return Err("The CPU limit must be above 0".to_string());
+        }
+    }
+    for (name, value) in [("memory limit", limits.memory_limit_mb), ("memory reservation", limits.memory_reservation_mb)] {
+        if value.is_some_and(|mb| mb < MIN_MEMORY_MB) {
+            return Err(format!("The {} must be at least {} MB", name, MIN_MEMORY_MB));
+        }
+    }
+    if let (Some(limit), Some(reservation)) = (limits.memory_limit_mb, limits.memory_reservation_mb) {
+        if reservation > limit {
+            return Err("The memory reservation can't be above the memory limit".to_string());
+        }
+    }
+
+    let mut warnings = Vec::new();
+    if let Some(limit) = limits.memory_limit_mb.filter(|limit| *limit < heap_mb) {
+        warnings.push(format!(
+            "The memory limit of {} MB is below the {} MB Java heap, the server will be killed once the heap fills up",
+            limit, heap_mb));
+    }
+    Ok(warnings)
+}
+
+/// Whether going from `current` to `limits` removes a limit, which needs a new container
+pub fn needs_recreate(current: &ServerResourceLimits, limits: &ServerResourceLimits) -> bool {
+    (current.memory_limit_mb.is_some() && limits.memory_limit_mb.is_none())
+        || (current.memory_reservation_mb.is_some() && limits.memory_reservation_mb.is_none())
+        || (current.cpus.is_some() && limits.cpus.is_none())
+}
+
+fn nano_cpus(cpus: f64) -> i64 {
+    (cpus * 1_000_000_000.0).round() as i64
+}
+
+/// Set `limits` on a container's host config, replacing any it had
+pub fn apply(limits: &ServerResourceLimits, host_config: &mut HostConfig) {
+    host_config.memory = limits.memory_limit_mb.map(|mb| mb as i64 * MB);
+    host_config.memory_swap = host_config.memory;
+    host_config.memory_reservation = limits.memory_reservation_mb.map(|mb| mb as i64 * MB);
+    host_config.nano_cpus = limits.cpus.map(nano_cpus);
+    host_config.cpu_quota = None;
+    host_config.cpu_period = None;
+}
+
+/// Update call setting `limits` on a running or stopped container
+pub fn update_options(limits: &ServerResourceLimits) -> UpdateContainerOptions<String> {
+    let memory = limits.memory_limit_mb.map(|mb| mb as i64 * MB);
+    UpdateContainerOptions {
+        memory,
+        memory_swap: memory,
+        memory_reservation: limits.memory_reservation_mb.map(|mb| mb as i64 * MB),
+        nano_cpus: limits.cpus.map(nano_cpus),
+        ..Default::default()
+    }
+}
+
+/// Usage the way `docker stats` shows it
+pub fn usage(stats: &Stats) -> Usage {
+    let online_cpus = stats.cpu_stats.online_cpus
+        .or_else(|| stats.cpu_stats.cpu_usage.percpu_usage.as_ref().map(|cores| cores.len() as u64))
+        .unwrap_or(1);
+    let cpu_delta = stats.cpu_stats.cpu_usage.total_usage.saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+    let system_delta = stats.cpu_stats.system_cpu_usage.unwrap_or(0)
+        .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or(0));
+    let cpu_percent = if system_delta > 0 {
+        cpu_delta as f64 / system_delta as f64 * online_cpus as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    let cache = match &stats.memory_stats.stats {
+        Some(MemoryStatsStats::V1(v1)) => v1.total_inactive_file,
+        Some(MemoryStatsStats::V2(v2)) => v2.inactive_file,
+        None => 0,
+    };
+    let (network_in, network_out) = stats.networks.iter().flatten()
+        .fold((0, 0), |(rx, tx), (_, network)| (rx + network.rx_bytes, tx + network.tx_bytes));
+
+    Usage {
+        cpu_percent,
+        online_cpus,
+        memory_usage: stats.memory_stats.usage.unwrap_or(0).saturating_sub(cache),
+        memory_limit: stats.memory_stats.limit.unwrap_or(0),
+        network_in,
+        network_out,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(memory: Option<u64>, reservation: Option<u64>, cpus: Option<f64>) -> ServerResourceLimits {
+        ServerResourceLimits { memory_limit_mb: memory, memory_reservation_mb: reservation, cpus }
+    }
+
+    #[test]
+    fn validates_limits_against_the_heap() {
+        assert_eq!(validate(&limits(Some(5120), Some(4096), Some(2.0)), 4096).unwrap(), Vec::<String>::new());
+        assert_eq!(validate(&limits(Some(2048), None, None), 4096).unwrap().len(), 1);
+        assert!(validate(&limits(Some(2048), Some(3072), None), 1024).is_err());
+        assert!(validate(&limits(None, None, Some(0.0)), 1024).is_err());
+        assert!(validate(&limits(Some(4), None, None), 1024).is_err());
+    }
+
+    #[test]
+    fn removing_a_limit_needs_a_new_container() {
+        let current = limits(Some(4096), None, Some(2.0));
+        assert!(!needs_recreate(&current, &limits(Some(6144), Some(2048), Some(1.5))));
+        assert!(needs_recreate(&current, &limits(Some(4096), None, None)));
+        assert!(needs_recreate(&current, &limits(None, None, Some(2.0))));
+    }
+
+    #[test]
+    fn limits_map_to_docker_units() {
+        let mut host_config = HostConfig { cpu_quota: Some(50_000), ..Default::default() };
+        apply(&limits(Some(2048), Some(1024), Some(1.5)), &mut host_config);
+        assert_eq!(host_config.memory, Some(2048 * MB));
+        assert_eq!(host_config.memory_swap, Some(2048 * MB));
+        assert_eq!(host_config.memory_reservation, Some(1024 * MB));
+        assert_eq!(host_config.nano_cpus, Some(1_500_000_000));
+        assert_eq!(host_config.cpu_quota, None);
+
+        let options = update_options(&limits(None, None, Some(0.5)));
+        assert_eq!(options.memory, None);
+        assert_eq!(options.nano_cpus, Some(500_000_000));
+    }
+}
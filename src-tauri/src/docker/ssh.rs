@@ -0,0 +1,306 @@
+//! SSH tunnels to Docker hosts that only listen on their socket
+//!
+//! The system `ssh` client forwards a local port to the remote Docker socket and
+//! bollard talks plain HTTP to that port. A supervisor task starts `ssh` again
+//! when the connection drops, on the same local port so the bollard client stays
+//! usable, and kills it when the tunnel is closed or dropped. Login is by key or
+//! agent only, `BatchMode` keeps ssh from prompting for anything.
+
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+
+use super::error::DockerError;
+use super::types::{DockerConnection, DockerSshConfig, TunnelState, TunnelStatus, DEFAULT_REMOTE_SOCKET, DEFAULT_SSH_PORT};
+
+/// How long a new ssh process gets to log in and open the forward
+const READY_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Longest wait between reconnect attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Failed attempts in a row before the tunnel is reported as failed, it keeps retrying
+const FAILED_AFTER: u32 = 5;
+
+/// How long closing waits for ssh to exit
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A running tunnel, ssh is killed when this is dropped
+pub struct SshTunnel {
+    local_port: u16,
+    status: Arc<Mutex<TunnelStatus>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl SshTunnel {
+    /// Start ssh for `connection` and wait until the forward accepts connections
+    pub async fn open(connection: &DockerConnection) -> Result<Self, DockerError> {
+        let ssh = connection.ssh.as_ref()
+            .ok_or_else(|| DockerError::from("SSH connections need a user name"))?;
+        let local_port = free_port().map_err(|e| format!("Failed to reserve a port for the SSH tunnel: {}", e))?;
+        let args = ssh_args(connection, ssh, local_port)?;
+
+        let mut child = spawn(&args)?;
+        wait_ready(&mut child, local_port).await
+            .map_err(|message| tunnel_error(connection.hostname(), &message))?;
+        println!("🔐 SSH tunnel to {} is up on 127.0.0.1:{}", connection.hostname(), local_port);
+
+        let status = Arc::new(Mutex::new(TunnelStatus { state: TunnelState::Connected, error: None }));
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let task = tokio::spawn(supervise(child, args, local_port, status.clone(), shutdown_rx, connection.name.clone()));
+        Ok(Self { local_port, status, shutdown: Some(shutdown), task })
+    }
+
+    /// Address bollard connects to
+    pub fn endpoint(&self) -> String {
+        format!("tcp://127.0.0.1:{}", self.local_port)
+    }
+
+    pub fn status(&self) -> TunnelStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Stop ssh and wait for it to exit
+    pub async fn close(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = tokio::time::timeout(CLOSE_TIMEOUT, &mut self.task).await;
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        // The child is spawned with kill_on_drop, aborting the supervisor kills it
+        self.task.abort();
+    }
+}
+
+/// Arguments for an ssh process forwarding `local_port` to the remote Docker socket
+fn ssh_args(connection: &DockerConnection, ssh: &DockerSshConfig, local_port: u16) -> Result<Vec<String>, DockerError> {
+    let host = connection.hostname().trim_start_matches('[').trim_end_matches(']');
+    let user = ssh.user.trim();
+    if host.is_empty() || host.starts_with('-') {
+        return Err("Invalid SSH host".into());
+    }
+    if user.is_empty() || user.starts_with('-') || user.contains('@') {
+        return Err("Invalid SSH user name".into());
+    }
+    let socket = ssh.remote_socket.as_deref()
+        .map(str::trim)
+        .filter(|socket| !socket.is_empty())
+        .unwrap_or(DEFAULT_REMOTE_SOCKET);
+
+    let mut args: Vec<String> = ["-N", "-T",
+        "-o", "BatchMode=yes",
+        "-o", "ExitOnForwardFailure=yes",
+        "-o", "ServerAliveInterval=15",
+        "-o", "ServerAliveCountMax=3",
+        "-o", "StrictHostKeyChecking=accept-new",
+    ].iter().map(|arg| arg.to_string()).collect();
+    args.extend(["-p".to_string(), connection.port_or(DEFAULT_SSH_PORT).to_string()]);
+    if let Some(key) = ssh.key_path.as_ref().filter(|key| !key.as_os_str().is_empty()) {
+        if !key.is_file() {
+            return Err(format!("SSH key not found at {}", key.display()).into());
+        }
+        args.extend(["-i".to_string(), key.to_string_lossy().to_string(), "-o".to_string(), "IdentitiesOnly=yes".to_string()]);
+    }
+    args.extend([
+        "-L".to_string(), format!("127.0.0.1:{}:{}", local_port, socket),
+        "-l".to_string(), user.to_string(),
+        "--".to_string(), host.to_string(),
+    ]);
+    Ok(args)
+}
+
+fn spawn(args: &[String]) -> Result<Child, DockerError> {
+    let mut command = Command::new("ssh");
+    command.args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    command.creation_flags(0x0800_0000); // CREATE_NO_WINDOW
+
+    command.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => DockerError::from("The ssh client was not found, install OpenSSH to use SSH connections"),
+        _ => DockerError::from(format!("Failed to start ssh: {}", e)),
+    })
+}
+
+/// A port nothing listens on right now
+fn free_port() -> std::io::Result<u16> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Wait until the forward accepts connections, or return what ssh printed when it exits
+async fn wait_ready(child: &mut Child, local_port: u16) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + READY_TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            let output = stderr_text(child).await;
+            return Err(if output.is_empty() { format!("ssh exited with {}", status) } else { output });
+        }
+        if tokio::net::TcpStream::connect(("127.0.0.1", local_port)).await.is_ok() {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            let _ = child.kill().await;
+            return Err(format!("ssh didn't open the tunnel within {}s", READY_TIMEOUT.as_secs()));
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+async fn stderr_text(child: &mut Child) -> String {
+    let mut output = String::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        let _ = stderr.read_to_string(&mut output).await;
+    }
+    // Warnings such as "Permanently added ... to the list of known hosts" aren't the reason
+    output.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("Warning: Permanently added"))
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
+/// Login and host key problems aren't fixed by checking the network
+fn tunnel_error(host: &str, output: &str) -> DockerError {
+    let message = format!("SSH tunnel to {} failed: {}", host, output);
+    let lower = output.to_lowercase();
+    if ["permission denied", "host key verification failed", "no such identity", "bad permissions", "invalid format"]
+        .iter()
+        .any(|marker| lower.contains(marker))
+    {
+        DockerError::from(message)
+    } else {
+        DockerError::Network { message }
+    }
+}
+
+fn backoff(failures: u32) -> Duration {
+    Duration::from_secs(1u64 << failures.min(6)).min(MAX_BACKOFF)
+}
+
+fn set_status(status: &Mutex<TunnelStatus>, state: TunnelState, error: Option<String>) {
+    *status.lock().unwrap() = TunnelStatus { state, error };
+}
+
+/// Keep the tunnel up until `shutdown` fires
+async fn supervise(
+    mut child: Child,
+    args: Vec<String>,
+    local_port: u16,
+    status: Arc<Mutex<TunnelStatus>>,
+    mut shutdown: oneshot::Receiver<()>,
+    name: String,
+) {
+    loop {
+        let mut error = tokio::select! {
+            _ = &mut shutdown => {
+                let _ = child.kill().await;
+                return;
+            }
+            exit = child.wait() => {
+                let output = stderr_text(&mut child).await;
+                match exit {
+                    Ok(status) if output.is_empty() => format!("ssh exited with {}", status),
+                    Ok(_) => output,
+                    Err(e) => e.to_string(),
+                }
+            }
+        };
+        println!("🔌 SSH tunnel for {} dropped, reconnecting: {}", name, error);
+
+        let mut failures = 0;
+        loop {
+            let state = if failures >= FAILED_AFTER { TunnelState::Failed } else { TunnelState::Reconnecting };
+            set_status(&status, state, Some(error.clone()));
+            tokio::select! {
+                _ = &mut shutdown => return,
+                _ = tokio::time::sleep(backoff(failures)) => {}
+            }
+
+            let attempt = match spawn(&args) {
+                Ok(mut next) => wait_ready(&mut next, local_port).await.map(|()| next),
+                Err(e) => Err(e.message().to_string()),
+            };
+            match attempt {
+                Ok(next) => {
+                    child = next;
+                    set_status(&status, TunnelState::Connected, None);
+                    println!("🔐 SSH tunnel for {} is back up", name);
+                    break;
+                }
+                Err(e) => {
+                    failures += 1;
+                    error = e;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::types::DockerConnectionType;
+
+    fn connection(host: &str, port: Option<u16>, user: &str) -> DockerConnection {
+        DockerConnection {
+            id: "vps".to_string(),
+            name: "VPS".to_string(),
+            host: host.to_string(),
+            port,
+            connection_type: DockerConnectionType::Ssh,
+            is_connected: false,
+            tls: None,
+            ssh: Some(DockerSshConfig { user: user.to_string(), ..Default::default() }),
+            tunnel: None,
+            mock: false,
+        }
+    }
+
+    fn args(connection: &DockerConnection) -> Result<Vec<String>, DockerError> {
+        ssh_args(connection, connection.ssh.as_ref().unwrap(), 40000)
+    }
+
+    #[test]
+    fn forwards_the_docker_socket() {
+        let joined = args(&connection("ssh://vps.example.com:2222", None, "deploy")).unwrap().join(" ");
+        assert!(joined.contains("-p 2222"));
+        assert!(joined.contains("-L 127.0.0.1:40000:/var/run/docker.sock"));
+        assert!(joined.ends_with("-l deploy -- vps.example.com"));
+        assert!(!joined.contains("-i "));
+
+        let ipv6 = args(&connection("[2001:db8::1]", None, "root")).unwrap();
+        assert_eq!(ipv6.last().map(String::as_str), Some("2001:db8::1"));
+        assert!(ipv6.join(" ").contains("-p 22"));
+    }
+
+    #[test]
+    fn rejects_option_like_hosts_and_users() {
+        assert!(args(&connection("-oProxyCommand=x", None, "root")).is_err());
+        assert!(args(&connection("vps", None, "-oProxyCommand=x")).is_err());
+        assert!(args(&connection("vps", None, "")).is_err());
+
+        let mut missing_key = connection("vps", None, "root");
+        missing_key.ssh.as_mut().unwrap().key_path = Some("/missing/id_ed25519".into());
+        assert!(args(&missing_key).is_err());
+    }
+
+    #[test]
+    fn login_failures_are_not_network_errors() {
+        assert!(matches!(tunnel_error("vps", "deploy@vps: Permission denied (publickey)."), DockerError::Other { .. }));
+        assert!(matches!(tunnel_error("vps", "ssh: connect to host vps port 22: Connection refused"), DockerError::Network { .. }));
+        assert_eq!(backoff(0), Duration::from_secs(1));
+        assert_eq!(backoff(20), MAX_BACKOFF);
+    }
+}
@@ -1,3 +1,8 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::ServerDeploymentRequest;
+
 /// Generate Dockerfile for Minecraft server
 pub fn generate_dockerfile(java_version: &str) -> String {
     format!(r#"FROM openjdk:{}-alpine
@@ -163,4 +168,230 @@ volumes:
         memory_limit = memory_limit,
         env_vars = env_vars.iter().map(|var| format!("      - {}", var)).collect::<Vec<_>>().join("\n")
     )
-}
\ No newline at end of file
+}
+/// Image every template runs on, tagged by the Java version the game needs
+const SERVER_IMAGE: &str = "itzg/minecraft-server";
+const PAPER_PROJECT_URL: &str = "https://api.papermc.io/v2/projects/paper";
+const PURPUR_PROJECT_URL: &str = "https://api.purpurmc.org/v2/purpur";
+
+/// Template used when a deployment doesn't name one
+pub const DEFAULT_TEMPLATE_ID: &str = "vanilla";
+
+/// Server software, the `TYPE` itzg/minecraft-server installs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerType {
+    Vanilla,
+    Paper,
+    Purpur,
+    Fabric,
+    Forge,
+}
+
+impl ServerType {
+    fn itzg_type(self) -> &'static str {
+        match self {
+            ServerType::Vanilla => "VANILLA",
+            ServerType::Paper => "PAPER",
+            ServerType::Purpur => "PURPUR",
+            ServerType::Fabric => "FABRIC",
+            ServerType::Forge => "FORGE",
+        }
+    }
+
+    /// The server type of an itzg `TYPE` or the launcher's server type label
+    pub fn from_itzg(value: &str) -> Option<Self> {
+        [ServerType::Vanilla, ServerType::Paper, ServerType::Purpur, ServerType::Fabric, ServerType::Forge]
+            .into_iter()
+            .find(|server_type| server_type.itzg_type().eq_ignore_ascii_case(value))
+    }
+}
+
+/// A kind of server the deploy dialog offers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub server_type: ServerType,
+    /// Image repository, the tag is picked from the Minecraft version
+    pub image: String,
+    pub recommended_memory_mb: u64,
+    /// Environment on top of the launcher's defaults, the deployment's own variables win
+    pub env: BTreeMap<String, String>,
+}
+
+/// Container settings worked out from a deployment request and its template
+#[derive(Debug, Clone)]
+pub struct ServerPlan {
+    pub template: ServerTemplate,
+    pub minecraft_version: String,
+    pub image: String,
+    pub env: Vec<String>,
+    pub labels: HashMap<String, String>,
+    /// Generated for each server unless the request sets `RCON_PASSWORD`
+    pub rcon_password: String,
+}
+
+fn template(id: &str, name: &str, description: &str, server_type: ServerType, memory_mb: u64, env: &[(&str, &str)]) -> ServerTemplate {
+    ServerTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        server_type,
+        image: SERVER_IMAGE.to_string(),
+        recommended_memory_mb: memory_mb,
+        env: env.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+    }
+}
+
+/// Every template the deploy dialog can offer
+pub fn server_templates() -> Vec<ServerTemplate> {
+    vec![
+        template("vanilla", "Vanilla", "The official server, no plugins or mods", ServerType::Vanilla, 2048, &[]),
+        template("paper", "Paper", "Fast Bukkit and Spigot compatible server for plugins", ServerType::Paper, 3072,
+            &[("USE_AIKAR_FLAGS", "true")]),
+        template("purpur", "Purpur", "Paper fork with extra gameplay settings", ServerType::Purpur, 3072,
+            &[("USE_AIKAR_FLAGS", "true")]),
+        template("fabric", "Fabric", "Lightweight mod loader, put server mods in the mods folder", ServerType::Fabric, 4096, &[]),
+        template("forge", "Forge", "Mod loader for large modpacks", ServerType::Forge, 6144, &[]),
+    ]
+}
+
+/// The Java image tag a Minecraft version runs on
+pub fn java_tag(mc_version: &str) -> &'static str {
+    match crate::minecraft::versions::get_required_java_version(mc_version) {
+        21 => "java21",
+        17 => "java17",
+        _ => "java8",
+    }
+}
+
+/// Look up the request's template and build the container environment and labels
+///
+/// `minecraft_version` on the request overrides the instance's version.
+pub fn plan_deployment(request: &ServerDeploymentRequest, instance_version: &str) -> Result<ServerPlan, String> {
+    let template_id = request.template_id.as_deref().unwrap_or(DEFAULT_TEMPLATE_ID);
+    let template = server_templates().into_iter()
+        .find(|template| template.id == template_id)
+        .ok_or_else(|| format!("Unknown server template '{}'", template_id))?;
+    let minecraft_version = request.minecraft_version.clone()
+        .filter(|version| !version.trim().is_empty())
+        .unwrap_or_else(|| instance_version.to_string());
+
+    let mut env: BTreeMap<String, String> = [
+        ("EULA", "TRUE".to_string()),
+        ("TYPE", template.server_type.itzg_type().to_string()),
+        ("VERSION", minecraft_version.clone()),
+        ("MAX_PLAYERS", request.max_players.to_string()),
+        ("MOTD", request.name.clone()),
+        ("MEMORY", format!("{}M", request.memory_limit)),
+        ("ONLINE_MODE", "TRUE".to_string()),
+        // RCON for commands, query for player lists
+        ("ENABLE_RCON", "true".to_string()),
+        ("RCON_PASSWORD", uuid::Uuid::new_v4().simple().to_string()),
+        ("RCON_PORT", super::rcon::CONTAINER_RCON_PORT.to_string()),
+        ("ENABLE_QUERY", "true".to_string()),
+    ].into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+    env.extend(template.env.clone());
+    env.extend(request.environment_vars.clone());
+
+    let rcon_password = env["RCON_PASSWORD"].clone();
+    let labels = HashMap::from([
+        ("chailauncher.template".to_string(), template.id.clone()),
+        ("chailauncher.server_type".to_string(), template.server_type.itzg_type().to_lowercase()),
+        ("chailauncher.minecraft_version".to_string(), minecraft_version.clone()),
+    ]);
+
+    Ok(ServerPlan {
+        image: format!("{}:{}", template.image, java_tag(&minecraft_version)),
+        env: env.into_iter().map(|(key, value)| format!("{}={}", key, value)).collect(),
+        labels,
+        minecraft_version,
+        template,
+        rcon_password,
+    })
+}
+
+/// Fail when the server software has no build for `mc_version`
+///
+/// Version lists that can't be fetched, e.g. offline, let the deployment through.
+pub async fn validate_version(server_type: ServerType, mc_version: &str) -> Result<(), String> {
+    let listed = match server_type {
+        ServerType::Vanilla => crate::minecraft::commands::fetch_version_manifest(false).await
+            .map(|manifest| manifest.versions.iter().any(|version| version.id == mc_version)),
+        ServerType::Paper => project_lists_version(PAPER_PROJECT_URL, mc_version).await,
+        ServerType::Purpur => project_lists_version(PURPUR_PROJECT_URL, mc_version).await,
+        ServerType::Fabric | ServerType::Forge => {
+            let loader = if server_type == ServerType::Fabric { "fabric" } else { "forge" };
+            let loaders = crate::mods::loaders::ModLoaderManager::new(std::path::PathBuf::new());
+            return loaders.check_loader_available(loader, mc_version).await.map_err(|e| e.to_string());
+        }
+    };
+
+    match listed {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("{:?} has no server builds for Minecraft {}", server_type, mc_version)),
+        Err(e) => {
+            println!("⚠️  Couldn't check {:?} versions, deploying anyway: {}", server_type, e);
+            Ok(())
+        }
+    }
+}
+
+/// Whether a Paper-style project API lists the version
+async fn project_lists_version(url: &str, mc_version: &str) -> Result<bool, String> {
+    let response = crate::http::client().get(url)
+        .timeout(std::time::Duration::from_secs(10))
+        .send().await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let project: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    Ok(project.get("versions")
+        .and_then(|versions| versions.as_array())
+        .is_some_and(|versions| versions.iter().any(|version| version.as_str() == Some(mc_version))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(template_id: Option<&str>) -> ServerDeploymentRequest {
+        ServerDeploymentRequest {
+            name: "survival".to_string(),
+            minecraft_instance_id: "instance".to_string(),
+            docker_connection_id: "local".to_string(),
+            port: 25565,
+            auto_assign_port: false,
+            max_players: 10,
+            memory_limit: 4096,
+            environment_vars: HashMap::from([("USE_AIKAR_FLAGS".to_string(), "false".to_string())]),
+            auto_start: false,
+            template_id: template_id.map(str::to_string),
+            minecraft_version: None,
+            resources: Default::default(),
+        }
+    }
+
+    #[test]
+    fn template_env_sits_between_defaults_and_overrides() {
+        let plan = plan_deployment(&request(Some("paper")), "1.20.6").unwrap();
+        assert_eq!(plan.image, "itzg/minecraft-server:java21");
+        assert!(plan.env.contains(&"TYPE=PAPER".to_string()));
+        assert!(plan.env.contains(&"VERSION=1.20.6".to_string()));
+        assert!(plan.env.contains(&"USE_AIKAR_FLAGS=false".to_string()));
+        assert_eq!(plan.env.iter().filter(|var| var.starts_with("USE_AIKAR_FLAGS=")).count(), 1);
+        assert_eq!(plan.labels["chailauncher.template"], "paper");
+        assert!(plan.env.contains(&format!("RCON_PASSWORD={}", plan.rcon_password)));
+        assert_ne!(plan.rcon_password, plan_deployment(&request(Some("paper")), "1.20.6").unwrap().rcon_password);
+
+        let mut older = request(None);
+        older.minecraft_version = Some("1.12.2".to_string());
+        let plan = plan_deployment(&older, "1.20.6").unwrap();
+        assert_eq!(plan.template.server_type, ServerType::Vanilla);
+        assert_eq!(plan.image, "itzg/minecraft-server:java8");
+        assert_eq!(plan.minecraft_version, "1.12.2");
+
+        assert!(plan_deployment(&request(Some("bukkit")), "1.20.1").is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use super::error::DockerError;
+use crate::storage::DockerTimeouts;
+use bollard::Docker;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+/// Run a Docker API call within `budget`
+///
+/// When the budget runs out the daemon is pinged to tell an unreachable host
+/// apart from an operation that is just slow.
+pub async fn with_timeout<T, E: Display>(
+    docker: &Docker,
+    timeouts: &DockerTimeouts,
+    operation: &str,
+    budget: Duration,
+    call: impl Future<Output = Result<T, E>>,
+) -> Result<T, DockerError> {
+    match tokio::time::timeout(budget, call).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(DockerError::from(format!("Failed to {}: {}", operation, e))),
+        Err(_) => Err(classify_timeout(docker, timeouts, operation, budget).await),
+    }
+}
+
+/// Turn an elapsed budget into a typed error by checking whether the daemon still answers
+pub async fn classify_timeout(
+    docker: &Docker,
+    timeouts: &DockerTimeouts,
+    operation: &str,
+    budget: Duration,
+) -> DockerError {
+    match ping(docker, timeouts).await {
+        Ok(()) => DockerError::timeout(operation, budget),
+        Err(_) => DockerError::host_unreachable(operation, budget),
+    }
+}
+
+/// Ping the daemon within the connect timeout
+pub async fn ping(docker: &Docker, timeouts: &DockerTimeouts) -> Result<(), DockerError> {
+    match tokio::time::timeout(timeouts.connect(), docker.ping()).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(super::tls::classify("Docker ping failed", &e)),
+        Err(_) => Err(DockerError::host_unreachable("ping", timeouts.connect())),
+    }
+}
@@ -1,14 +1,118 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Port remote daemons listen on when the connection doesn't name one
+pub const DEFAULT_REMOTE_PORT: u16 = 2376;
+
+/// Port SSH connections use when the connection doesn't name one
+pub const DEFAULT_SSH_PORT: u16 = 22;
+
+/// Docker socket SSH tunnels forward to when the connection doesn't name one
+pub const DEFAULT_REMOTE_SOCKET: &str = "/var/run/docker.sock";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DockerConnection {
     pub id: String,
     pub name: String,
+    /// Host name, `tcp://host:port` is accepted for remote connections too
     pub host: String,
     pub port: Option<u16>,
     pub connection_type: DockerConnectionType,
     pub is_connected: bool,
+    /// Client TLS for remote and swarm connections, plain TCP when missing
+    #[serde(default)]
+    pub tls: Option<DockerTlsConfig>,
+    /// Login for SSH connections, `host` and `port` are those of the SSH server
+    #[serde(default)]
+    pub ssh: Option<DockerSshConfig>,
+    /// State of the SSH tunnel, filled in when connections are listed
+    #[serde(default, skip_deserializing)]
+    pub tunnel: Option<TunnelStatus>,
+    /// Set on data produced by the mock backend
+    #[serde(default)]
+    pub mock: bool,
+}
+
+impl DockerConnection {
+    /// Host without scheme or port, also where published server ports are reached
+    pub fn hostname(&self) -> &str {
+        self.split_host().0
+    }
+
+    /// `tcp://host:port` of a remote daemon
+    pub fn endpoint(&self) -> String {
+        format!("tcp://{}:{}", self.hostname(), self.port_or(DEFAULT_REMOTE_PORT))
+    }
+
+    /// Configured port, then one embedded in `host`, then `default`
+    pub fn port_or(&self, default: u16) -> u16 {
+        self.port.or(self.split_host().1).unwrap_or(default)
+    }
+
+    fn split_host(&self) -> (&str, Option<u16>) {
+        let host = self.host.trim();
+        let host = ["tcp://", "https://", "http://", "ssh://"].iter()
+            .find_map(|scheme| host.strip_prefix(scheme))
+            .unwrap_or(host)
+            .trim_end_matches('/');
+        match host.rsplit_once(':') {
+            // A bare IPv6 address has more than one colon and no brackets
+            Some((name, port)) if !name.contains(':') || name.ends_with(']') => match port.parse() {
+                Ok(port) => (name, Some(port)),
+                Err(_) => (host, None),
+            },
+            _ => (host, None),
+        }
+    }
+}
+
+/// Certificates for a TLS connection, as `docker --tlsverify` uses them
+///
+/// Each one is a file path or PEM text. PEM text never reaches the saved
+/// connection, it is moved to the secret store when the connection is added.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DockerTlsConfig {
+    pub ca_path: Option<PathBuf>,
+    pub cert_path: Option<PathBuf>,
+    pub key_path: Option<PathBuf>,
+    #[serde(default, skip_serializing)]
+    pub ca_pem: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub cert_pem: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub key_pem: Option<String>,
+    /// PEM text for this connection is in the secret store
+    #[serde(default)]
+    pub pem_stored: bool,
+}
+
+/// Key based login for a Docker host reached through SSH
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DockerSshConfig {
+    pub user: String,
+    /// Private key, the ssh agent and default keys are used when missing
+    #[serde(default)]
+    pub key_path: Option<PathBuf>,
+    /// Docker socket on the remote host
+    #[serde(default)]
+    pub remote_socket: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunnelState {
+    Connected,
+    Reconnecting,
+    /// Reconnecting failed several times in a row, or the tunnel never came up
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TunnelStatus {
+    pub state: TunnelState,
+    /// Why the last attempt failed
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +123,8 @@ pub enum DockerConnectionType {
     UnixSocket,
     Remote,
     Swarm,
+    /// Docker socket of a remote host, forwarded over SSH
+    Ssh,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,12 +135,51 @@ pub struct ServerInstance {
     pub docker_connection_id: String,
     pub container_id: Option<String>,
     pub status: ServerStatus,
+    /// Host port the game is published on, the one players join with
     pub port: u16,
     pub max_players: u32,
-    pub memory_limit: u64, // in MB
+    /// Java heap in MB
+    pub memory_limit: u64,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_started: Option<chrono::DateTime<chrono::Utc>>,
     pub environment_vars: HashMap<String, String>,
+    /// Host port RCON is published on, game port + 10 when missing
+    #[serde(default)]
+    pub rcon_port: Option<u16>,
+    /// Container limits, servers deployed before these existed report none
+    #[serde(default)]
+    pub resources: ServerResourceLimits,
+    /// The most recent version upgrade, kept for rolling it back
+    #[serde(default)]
+    pub last_upgrade: Option<ServerUpgradeRecord>,
+    /// Set on data produced by the mock backend
+    #[serde(default)]
+    pub mock: bool,
+}
+
+/// What a server ran before `upgrade_server` moved it to another version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerUpgradeRecord {
+    pub previous_version: String,
+    pub previous_image: String,
+    pub minecraft_version: String,
+    /// Backup of the data folder taken before the upgrade, relative to `/data`
+    pub backup: String,
+    pub upgraded_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// CPU and memory limits of a server container, missing ones are unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerResourceLimits {
+    /// Hard memory limit in MB
+    #[serde(default)]
+    pub memory_limit_mb: Option<u64>,
+    /// Memory in MB the container is pushed back to when the host runs short
+    #[serde(default)]
+    pub memory_reservation_mb: Option<u64>,
+    /// Cores the container may use, e.g. 1.5
+    #[serde(default)]
+    pub cpus: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -64,26 +209,101 @@ pub enum LogLevel {
     Debug,
 }
 
+impl LogLevel {
+    /// Level of a console line, from the level Minecraft prints in it
+    pub fn of(message: &str) -> Self {
+        if message.contains("ERROR") {
+            LogLevel::Error
+        } else if message.contains("WARN") {
+            LogLevel::Warn
+        } else if message.contains("DEBUG") {
+            LogLevel::Debug
+        } else {
+            LogLevel::Info
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerDeploymentRequest {
     pub name: String,
     pub minecraft_instance_id: String,
     pub docker_connection_id: String,
     pub port: u16,
+    /// Take the next free port of the configured range when `port` is taken
+    #[serde(default)]
+    pub auto_assign_port: bool,
     pub max_players: u32,
     pub memory_limit: u64,
     pub environment_vars: HashMap<String, String>,
     pub auto_start: bool,
+    /// Id from `get_server_templates`, vanilla when missing
+    #[serde(default)]
+    pub template_id: Option<String>,
+    /// Overrides the Minecraft version of the instance the server is made from
+    #[serde(default)]
+    pub minecraft_version: Option<String>,
+    #[serde(default)]
+    pub resources: ServerResourceLimits,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerStats {
+    /// Percent of one core, above 100 when several are busy
     pub cpu_usage: f64,
     pub memory_usage: u64,
+    /// Bytes the container may use, the host's memory when it has no limit
     pub memory_limit: u64,
     pub network_in: u64,
     pub network_out: u64,
     pub uptime: u64,
+    #[serde(default)]
+    pub online_players: Vec<String>,
+    /// Limits the server is configured with
+    #[serde(default)]
+    pub resources: ServerResourceLimits,
+    /// Cores the container may use, its CPU limit or every core of the host
+    #[serde(default)]
+    pub cpu_cores: f64,
+    /// Java heap in MB
+    #[serde(default)]
+    pub heap_mb: u64,
+    /// Limits the server doesn't fit in
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub mock: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerQuerySource {
+    Query,
+    Rcon,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerSample {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub online_players: u32,
+    pub max_players: u32,
+    pub players: Vec<String>,
+    pub source: PlayerQuerySource,
+    #[serde(default)]
+    pub mock: bool,
+}
+
+/// Image pull progress, emitted as `docker_pull_progress` whenever a layer reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullProgress {
+    pub image: String,
+    pub layer: String,
+    pub status: String,
+    pub current: Option<u64>,
+    pub total: Option<u64>,
+    pub layers_done: usize,
+    pub layers_total: usize,
+    pub percent: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
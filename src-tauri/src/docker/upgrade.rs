@@ -0,0 +1,238 @@
+//! Moving a deployed server to another Minecraft version
+//!
+//! The server's data folder is packed into a backup on its own volume before the
+//! container is recreated with the new `VERSION` and the image tag that version's
+//! Java needs. The new container is started and its log watched until the server
+//! reports it finished loading. If it fails instead, the backup is unpacked over
+//! the data folder and the previous container configuration is put back.
+
+use bollard::container::Config;
+use serde::Serialize;
+
+use super::types::PullProgress;
+use crate::minecraft::versions::version_compare;
+
+/// How long a recreated server may take to finish loading, world conversion included
+pub const VERIFY_TIMEOUT_SECS: u64 = 15 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerUpgradeStage {
+    Backup,
+    Pull,
+    Recreate,
+    Verify,
+    RollBack,
+}
+
+impl ServerUpgradeStage {
+    /// Share of the whole upgrade the stage covers, as start and end percent
+    fn band(self) -> (u32, u32) {
+        match self {
+            ServerUpgradeStage::Backup => (0, 20),
+            ServerUpgradeStage::Pull => (20, 60),
+            ServerUpgradeStage::Recreate => (60, 70),
+            ServerUpgradeStage::Verify => (70, 100),
+            ServerUpgradeStage::RollBack => (0, 100),
+        }
+    }
+}
+
+/// Sent as `server_upgrade_progress` while a server is upgraded
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerUpgradeProgress {
+    pub stage: ServerUpgradeStage,
+    /// Of the whole upgrade, 0-100
+    pub percent: u32,
+    /// Image download while the stage is `pull`
+    pub pull: Option<PullProgress>,
+}
+
+impl ServerUpgradeProgress {
+    /// Progress `stage_percent` of the way through `stage`
+    pub fn new(stage: ServerUpgradeStage, stage_percent: u32) -> Self {
+        let (start, end) = stage.band();
+        Self { stage, percent: start + (end - start) * stage_percent.min(100) / 100, pull: None }
+    }
+
+    pub fn pulling(progress: PullProgress) -> Self {
+        let percent = progress.percent;
+        Self { pull: Some(progress), ..Self::new(ServerUpgradeStage::Pull, percent) }
+    }
+}
+
+/// Outcome of `upgrade_server`
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerUpgradeResult {
+    pub previous_version: String,
+    pub minecraft_version: String,
+    pub image: String,
+    /// Backup of the data folder, relative to `/data` on the server
+    pub backup: String,
+}
+
+/// Version a server runs when its container doesn't pin one
+const UNPINNED: &str = "LATEST";
+
+/// Whether `version` is a plain release like 1.20.4, the only kind that compares reliably
+fn is_release(version: &str) -> bool {
+    version.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Refuse moving from `current` to `target` when it isn't clearly an upgrade
+///
+/// Worlds saved by a newer version can't be opened by an older one, so downgrades
+/// and versions that can't be ordered, like snapshots, need `force`.
+pub fn check_versions(current: &str, target: &str, force: bool) -> Result<(), String> {
+    let current = match current.trim() {
+        "" => UNPINNED,
+        current => current,
+    };
+    let target = target.trim();
+    if target.is_empty() {
+        return Err("Choose the Minecraft version to upgrade to".to_string());
+    }
+    if target.eq_ignore_ascii_case(current) {
+        return Err(format!("The server already runs Minecraft {}", current));
+    }
+    if force || target.eq_ignore_ascii_case(UNPINNED) {
+        return Ok(());
+    }
+    if !is_release(current) || !is_release(target) {
+        return Err(format!(
+            "Can't tell whether Minecraft {} is newer than the server's {}. Force the upgrade if it is",
+            target, current));
+    }
+    if version_compare(target, current) < 0 {
+        return Err(format!(
+            "Minecraft {} is older than the server's {}, and worlds can't be opened by older versions. \
+             Force the downgrade to do it anyway",
+            target, current));
+    }
+    Ok(())
+}
+
+/// The server image for `version`, keeping the repository of `current_image`
+pub fn image_for(current_image: &str, version: &str) -> String {
+    let repository = match current_image.rsplit_once(':') {
+        // A colon inside the last path segment starts the tag, elsewhere it's a registry port
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => current_image,
+    };
+    format!("{}:{}", repository, super::templates::java_tag(version))
+}
+
+/// Point a server container's configuration at `version` running on `image`
+pub fn retarget(config: &mut Config<String>, image: &str, version: &str) {
+    config.image = Some(image.to_string());
+    let env = config.env.get_or_insert_with(Vec::new);
+    env.retain(|var| !var.starts_with("VERSION="));
+    env.push(format!("VERSION={}", version));
+    config.labels.get_or_insert_with(Default::default)
+        .insert("chailauncher.minecraft_version".to_string(), version.to_string());
+}
+
+/// Whether a server log line shows the server finished starting or failed to
+pub fn startup_outcome(line: &str) -> Option<Result<(), String>> {
+    const FAILURES: &[&str] = &[
+        "Failed to start the minecraft server",
+        "Exception in server tick loop",
+        "Encountered an unexpected exception",
+        "FAILED TO BIND TO PORT",
+        "This world was saved with a newer version",
+    ];
+    if line.contains("]: Done (") || (line.contains("Done (") && line.contains("For help, type")) {
+        return Some(Ok(()));
+    }
+    FAILURES.iter()
+        .any(|failure| line.contains(failure))
+        .then(|| Err(line.trim().to_string()))
+}
+
+/// Packs `/data` into `upgrade-backups/` before the container is replaced
+///
+/// Runs in a helper container with the server's volumes while the server is stopped.
+/// Expects `NAME`, the archive's file name, and prints `backup=<path>` when done.
+pub const BACKUP_SCRIPT: &str = r#"set -e
+cd /data
+mkdir -p upgrade-backups
+tar -czf "upgrade-backups/.$NAME.partial" --exclude=./upgrade-backups --exclude=./logs .
+mv "upgrade-backups/.$NAME.partial" "upgrade-backups/$NAME"
+echo "backup=upgrade-backups/$NAME"
+"#;
+
+/// Replaces everything under `/data` but backups and logs with the `BACKUP` archive
+pub const RESTORE_SCRIPT: &str = r#"set -e
+cd /data
+test -f "$BACKUP"
+find . -mindepth 1 -maxdepth 1 ! -name upgrade-backups ! -name logs -exec rm -rf {} +
+tar -xzf "$BACKUP"
+echo "restored=$BACKUP"
+"#;
+
+/// File name of the backup taken before moving away from `version`
+pub fn backup_name(version: &str, stamp: &str) -> String {
+    let version: String = version.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    format!("pre-upgrade-{}-{}.tar.gz", version, stamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_clear_upgrades_pass_without_force() {
+        assert!(check_versions("1.20.4", "1.21", false).is_ok());
+        assert!(check_versions("1.20.4", "1.20.10", false).is_ok());
+        assert!(check_versions("1.20.4", "LATEST", false).is_ok());
+
+        assert!(check_versions("1.21", "1.20.4", false).unwrap_err().contains("older than the server's 1.21"));
+        assert!(check_versions("1.21", "1.20.4", true).is_ok());
+        assert!(check_versions("", "1.21", false).unwrap_err().contains("server's LATEST"));
+        assert!(check_versions("1.20.4", "24w14a", false).is_err());
+        assert!(check_versions("1.20.4", "24w14a", true).is_ok());
+        assert!(check_versions("1.21", " 1.21 ", true).unwrap_err().contains("already runs"));
+        assert!(check_versions("1.21", "", true).is_err());
+    }
+
+    #[test]
+    fn keeps_the_image_repository() {
+        assert_eq!(image_for("itzg/minecraft-server:java17", "1.21"), "itzg/minecraft-server:java21");
+        assert_eq!(image_for("registry.local:5000/minecraft-server", "1.16.5"), "registry.local:5000/minecraft-server:java8");
+        assert_eq!(image_for("registry.local:5000/minecraft-server:java8", "1.18.2"), "registry.local:5000/minecraft-server:java17");
+    }
+
+    #[test]
+    fn retargets_version_image_and_label() {
+        let mut config = Config {
+            image: Some("itzg/minecraft-server:java17".to_string()),
+            env: Some(vec!["EULA=TRUE".to_string(), "VERSION=1.20.4".to_string(), "VERSIONED=1".to_string()]),
+            ..Default::default()
+        };
+        retarget(&mut config, "itzg/minecraft-server:java21", "1.21");
+        assert_eq!(config.image.as_deref(), Some("itzg/minecraft-server:java21"));
+        assert_eq!(config.env.unwrap(), vec!["EULA=TRUE", "VERSIONED=1", "VERSION=1.21"]);
+        assert_eq!(config.labels.unwrap()["chailauncher.minecraft_version"], "1.21");
+    }
+
+    #[test]
+    fn spots_startup_success_and_failure() {
+        assert_eq!(
+            startup_outcome("[12:00:01] [Server thread/INFO]: Done (4.512s)! For help, type \"help\""),
+            Some(Ok(())));
+        assert!(startup_outcome("[12:00:01] [Server thread/ERROR]: Encountered an unexpected exception").unwrap().is_err());
+        assert!(startup_outcome("[12:00:01] [Server thread/WARN]: **** FAILED TO BIND TO PORT!").unwrap().is_err());
+        assert_eq!(startup_outcome("[12:00:00] [Server thread/INFO]: Preparing level \"world\""), None);
+    }
+
+    #[test]
+    fn maps_stages_onto_overall_percent() {
+        assert_eq!(ServerUpgradeProgress::new(ServerUpgradeStage::Backup, 50).percent, 10);
+        assert_eq!(ServerUpgradeProgress::new(ServerUpgradeStage::Pull, 50).percent, 40);
+        assert_eq!(ServerUpgradeProgress::new(ServerUpgradeStage::Verify, 100).percent, 100);
+        assert_eq!(ServerUpgradeProgress::new(ServerUpgradeStage::Recreate, 250).percent, 70);
+        assert_eq!(backup_name("1.20.4 (old)", "20240501-100000"), "pre-upgrade-1.20.4__old_-20240501-100000.tar.gz");
+    }
+}
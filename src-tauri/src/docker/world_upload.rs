@@ -0,0 +1,297 @@
+//! Moving a singleplayer world onto a server
+//!
+//! The world is packed into a gzipped tar, which Docker's archive endpoint
+//! unpacks into a staging folder under `/data` while it is uploaded. A short
+//! lived helper container sharing the server's volumes then backs up the old
+//! world, puts the new one in its place under the server's level name and hands
+//! it to the user the server runs as. Everything happens while the server is
+//! stopped, so the archive endpoint and the helper work on remote hosts too.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::minecraft::versions::version_compare;
+use crate::minecraft::worlds::WorldVersion;
+
+/// Files the game holds open while a world is loaded, never uploaded
+const SKIPPED_FILES: &[&str] = &["session.lock"];
+
+/// Owner written to the archive, the helper fixes it up to match the volume
+const ARCHIVE_UID: u64 = 1000;
+
+/// Largest file size the ustar header can describe
+const MAX_ENTRY_SIZE: u64 = 0o77777777777;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorldUploadStage {
+    Compressing,
+    Transferring,
+    Extracting,
+}
+
+/// Sent as `world_upload_progress` while a world is uploaded
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldUploadProgress {
+    pub stage: WorldUploadStage,
+    /// Bytes compressed or sent so far
+    pub current: u64,
+    pub total: u64,
+    /// Progress of the whole upload, 0 to 100
+    pub percent: u32,
+}
+
+impl WorldUploadProgress {
+    /// Compressing counts for the first 40%, the transfer up to 90%
+    pub fn new(stage: WorldUploadStage, current: u64, total: u64) -> Self {
+        let (start, span) = match stage {
+            WorldUploadStage::Compressing => (0, 40),
+            WorldUploadStage::Transferring => (40, 50),
+            WorldUploadStage::Extracting => (90, 10),
+        };
+        let done = (current.min(total) * span).checked_div(total).unwrap_or(0);
+        Self { stage, current, total, percent: (start + done) as u32 }
+    }
+}
+
+/// Outcome of `upload_world`
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldUploadResult {
+    /// Folder the world now lives in, the server's level name
+    pub level_name: String,
+    /// Archive of the replaced world, relative to the server's data folder
+    pub backup: Option<String>,
+    /// The server was running and was started again
+    pub restarted: bool,
+}
+
+/// Refuse worlds saved by a newer game than the server runs, it can't load them
+///
+/// `LATEST`, `SNAPSHOT` and empty server versions aren't checked.
+pub fn check_compatible(world: &WorldVersion, server_version: &str) -> Result<(), String> {
+    let server_version = server_version.trim();
+    if server_version.is_empty() || server_version.eq_ignore_ascii_case("latest") || server_version.eq_ignore_ascii_case("snapshot") {
+        return Ok(());
+    }
+    let Some(name) = &world.name else {
+        // Worlds from before 1.9 don't record a version, any newer server upgrades them
+        return Ok(());
+    };
+    let data_version = world.data_version.map(|id| format!(" (DataVersion {})", id)).unwrap_or_default();
+
+    if world.snapshot && name != server_version {
+        return Err(format!("The world was saved by snapshot {}{}, the server runs {}", name, data_version, server_version));
+    }
+    if version_compare(name, server_version) > 0 {
+        return Err(format!(
+            "The world was saved by Minecraft {}{}, which is newer than the server's {}. Update the server first",
+            name, data_version, server_version));
+    }
+    Ok(())
+}
+
+/// Staging folder under `/data` the archive unpacks into
+pub fn staging_folder() -> String {
+    format!(".chai-upload-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// Pack `world_dir` into a gzipped tar at `archive`, its entries under `folder/`
+///
+/// `on_progress` gets the bytes read so far and the total.
+pub fn pack(world_dir: &Path, folder: &str, archive: &Path, mut on_progress: impl FnMut(u64, u64)) -> Result<(), String> {
+    let mut entries: Vec<(PathBuf, String, bool, u64)> = Vec::new();
+    for entry in walkdir::WalkDir::new(world_dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| format!("Failed to read the world: {}", e))?;
+        let relative = entry.path().strip_prefix(world_dir).unwrap_or(entry.path());
+        if entry.file_type().is_symlink() || SKIPPED_FILES.iter().any(|skipped| relative == Path::new(skipped)) {
+            continue;
+        }
+        let name = std::iter::once(folder.to_string())
+            .chain(relative.components().map(|part| part.as_os_str().to_string_lossy().to_string()))
+            .collect::<Vec<_>>()
+            .join("/");
+        let size = if entry.file_type().is_file() {
+            entry.metadata().map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?.len()
+        } else {
+            0
+        };
+        entries.push((entry.path().to_path_buf(), name, entry.file_type().is_dir(), size));
+    }
+    let total: u64 = entries.iter().map(|(_, _, _, size)| size).sum();
+
+    let file = File::create(archive).map_err(|e| format!("Failed to create {}: {}", archive.display(), e))?;
+    let mut tar = flate2::write::GzEncoder::new(BufWriter::new(file), flate2::Compression::fast());
+    let mut done = 0;
+    let mut buffer = vec![0u8; 256 * 1024];
+    let write_error = |e: std::io::Error| format!("Failed to write the world archive: {}", e);
+
+    for (path, name, is_dir, size) in entries {
+        if is_dir {
+            write_header(&mut tar, &format!("{}/", name), 0, true).map_err(write_error)?;
+            continue;
+        }
+        if size > MAX_ENTRY_SIZE {
+            return Err(format!("{} is too large to upload", path.display()));
+        }
+        write_header(&mut tar, &name, size, false).map_err(write_error)?;
+
+        // Files the game rewrote since listing are cut or padded to the listed size
+        let mut source = File::open(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.take(size);
+        let mut written = 0;
+        loop {
+            let read = source.read(&mut buffer).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if read == 0 {
+                break;
+            }
+            tar.write_all(&buffer[..read]).map_err(write_error)?;
+            written += read as u64;
+            done += read as u64;
+            on_progress(done, total);
+        }
+        write_zeros(&mut tar, (size - written) as usize).map_err(write_error)?;
+        write_zeros(&mut tar, padding(size)).map_err(write_error)?;
+    }
+
+    // Two empty blocks end the archive
+    write_zeros(&mut tar, 1024).map_err(write_error)?;
+    tar.finish().and_then(|mut file| file.flush()).map_err(write_error)?;
+    on_progress(total, total);
+    Ok(())
+}
+
+fn padding(size: u64) -> usize {
+    ((512 - size % 512) % 512) as usize
+}
+
+fn write_zeros(out: &mut impl Write, mut len: usize) -> std::io::Result<()> {
+    let zeros = [0u8; 512];
+    while len > 0 {
+        let chunk = len.min(zeros.len());
+        out.write_all(&zeros[..chunk])?;
+        len -= chunk;
+    }
+    Ok(())
+}
+
+fn octal(field: &mut [u8], value: u64) {
+    let text = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..text.len()].copy_from_slice(text.as_bytes());
+}
+
+fn header(name: &[u8], size: u64, kind: u8, mode: u64) -> [u8; 512] {
+    let mut header = [0u8; 512];
+    header[..name.len()].copy_from_slice(name);
+    octal(&mut header[100..108], mode);
+    octal(&mut header[108..116], ARCHIVE_UID);
+    octal(&mut header[116..124], ARCHIVE_UID);
+    octal(&mut header[124..136], size);
+    octal(&mut header[136..148], chrono::Utc::now().timestamp().max(0) as u64);
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|byte| *byte as u64).sum();
+    octal(&mut header[148..155], checksum);
+    header
+}
+
+/// Write a ustar header, with a GNU long name entry before it when the name doesn't fit
+fn write_header(out: &mut impl Write, name: &str, size: u64, is_dir: bool) -> std::io::Result<()> {
+    let bytes = name.as_bytes();
+    if bytes.len() >= 100 {
+        out.write_all(&header(b"././@LongLink", bytes.len() as u64 + 1, b'L', 0o644))?;
+        out.write_all(bytes)?;
+        write_zeros(out, 1 + padding(bytes.len() as u64 + 1))?;
+    }
+    let short = &bytes[..bytes.len().min(99)];
+    let (kind, mode) = if is_dir { (b'5', 0o755) } else { (b'0', 0o644) };
+    out.write_all(&header(short, size, kind, mode))
+}
+
+/// Shell script the helper container runs from `/data`
+///
+/// Reads `UPLOAD`, `BACKUP` and `STAMP` from its environment and the level name
+/// from `LEVEL` or server.properties, like the server does. Bukkit style servers
+/// keep the nether and end next to the world, those are replaced as well.
+pub const SWAP_SCRIPT: &str = r#"set -e
+cd /data
+trap 'rm -rf "$UPLOAD"' EXIT
+level="${LEVEL:-$(sed -n 's/^level-name=//p' server.properties 2>/dev/null | tail -n 1)}"
+level="${level:-world}"
+set --
+for dir in "$level" "${level}_nether" "${level}_the_end"; do
+  if [ -e "$dir" ]; then set -- "$@" "$dir"; fi
+done
+if [ "$#" -gt 0 ]; then
+  if [ "$BACKUP" = 1 ]; then
+    mkdir -p world-backups
+    tar -czf "world-backups/$level-$STAMP.tar.gz" "$@"
+    echo "backup=world-backups/$level-$STAMP.tar.gz"
+  fi
+  rm -rf "$@"
+fi
+mv "$UPLOAD" "$level"
+chown -R "$(stat -c %u:%g /data)" "$level"
+echo "level=$level"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(name: Option<&str>, snapshot: bool) -> WorldVersion {
+        WorldVersion { data_version: Some(3953), name: name.map(str::to_string), snapshot }
+    }
+
+    #[test]
+    fn refuses_worlds_newer_than_the_server() {
+        assert!(check_compatible(&world(Some("1.20.4"), false), "1.21").is_ok());
+        assert!(check_compatible(&world(Some("1.21"), false), "1.21").is_ok());
+        assert!(check_compatible(&world(None, false), "1.8.9").is_ok());
+        assert!(check_compatible(&world(Some("1.21.4"), false), "LATEST").is_ok());
+
+        let error = check_compatible(&world(Some("1.21.4"), false), "1.20.1").unwrap_err();
+        assert!(error.contains("DataVersion 3953"));
+        assert!(check_compatible(&world(Some("24w14a"), true), "1.20.4").is_err());
+    }
+
+    #[test]
+    fn packs_the_world_as_a_tar() {
+        let dir = std::env::temp_dir().join(format!("chai-upload-{}", uuid::Uuid::new_v4()));
+        let world_dir = dir.join("My World");
+        let long = "r".repeat(120);
+        std::fs::create_dir_all(world_dir.join("region")).unwrap();
+        std::fs::write(world_dir.join("level.dat"), b"level").unwrap();
+        std::fs::write(world_dir.join("session.lock"), b"lock").unwrap();
+        std::fs::write(world_dir.join("region").join(&long), vec![7u8; 600]).unwrap();
+
+        let archive = dir.join("world.tar.gz");
+        let mut last = (0, 0);
+        pack(&world_dir, ".chai-upload-test", &archive, |done, total| last = (done, total)).unwrap();
+        assert_eq!(last, (605, 605));
+
+        let mut tar = Vec::new();
+        flate2::read::GzDecoder::new(File::open(&archive).unwrap()).read_to_end(&mut tar).unwrap();
+        assert_eq!(tar.len() % 512, 0);
+        let text = String::from_utf8_lossy(&tar);
+        assert!(text.contains(".chai-upload-test/level.dat"));
+        assert!(text.contains(&format!(".chai-upload-test/region/{}", long)));
+        assert!(!text.contains("session.lock"));
+
+        // Every header checksum matches its block
+        let mut offset = 0;
+        while tar[offset] != 0 {
+            let block = &tar[offset..offset + 512];
+            let stored = u64::from_str_radix(String::from_utf8_lossy(&block[148..154]).trim(), 8).unwrap();
+            let sum: u64 = block.iter().enumerate().map(|(i, byte)| if (148..156).contains(&i) { 32 } else { *byte as u64 }).sum();
+            assert_eq!(stored, sum);
+            let size = u64::from_str_radix(String::from_utf8_lossy(&block[124..135]).trim(), 8).unwrap();
+            offset += 512 + (size as usize).div_ceil(512) * 512;
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
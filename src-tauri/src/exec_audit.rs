@@ -0,0 +1,322 @@
+//! Audit log of the external programs the launcher runs for the user
+//!
+//! Launch hooks run whatever the user typed into them. Every run is appended to
+//! `logs/exec-audit.jsonl` in the launcher folder with its exit code and how long
+//! it took. With `confirm_external_commands` on, a command string that hasn't been
+//! decided yet is sent to the UI as `exec_confirmation_required` and waits for
+//! `respond_exec_confirmation`. Decisions are kept per command string, so a denied
+//! command is refused until it is edited.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+pub const AUDIT_FILE: &str = "exec-audit.jsonl";
+/// The previous log, kept until the next rotation
+pub const AUDIT_ARCHIVE_FILE: &str = "exec-audit.1.jsonl";
+const DECISIONS_FILE: &str = "exec-decisions.json";
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+const DEFAULT_AUDIT_LIMIT: usize = 200;
+/// A run nobody answers for is refused, without remembering the refusal
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// What made the launcher run a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecFeature {
+    PreLaunchHook,
+    PostExitHook,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecOutcome {
+    /// Ran to the end, `exit_code` says how it went
+    Exited,
+    TimedOut,
+    FailedToStart,
+    /// Refused by the user, or never confirmed
+    Denied,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecRecord {
+    pub feature: ExecFeature,
+    /// The command as the user wrote it
+    pub command: String,
+    /// Program and arguments the launcher started to run it
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: String,
+    pub instance_id: Option<String>,
+    pub outcome: ExecOutcome,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// RFC 3339, when the run started
+    pub timestamp: String,
+}
+
+/// A program the launcher is about to run
+pub struct ExecRequest<'a> {
+    pub feature: ExecFeature,
+    pub command: &'a str,
+    pub program: &'a str,
+    pub args: &'a [String],
+    pub cwd: &'a Path,
+    pub instance_id: Option<&'a str>,
+}
+
+/// Sent as `exec_confirmation_required`, answered with `respond_exec_confirmation`
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecConfirmation {
+    pub request_id: String,
+    pub feature: ExecFeature,
+    pub command: String,
+    pub cwd: String,
+    pub instance_id: Option<String>,
+}
+
+/// The user's answer for one command string
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ExecDecision {
+    allowed: bool,
+    decided_at: String,
+}
+
+/// Confirmations waiting for the UI, by request id
+static PENDING: OnceLock<Mutex<HashMap<String, oneshot::Sender<bool>>>> = OnceLock::new();
+
+/// Serializes appends to the log and writes to the decisions file
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+fn pending() -> &'static Mutex<HashMap<String, oneshot::Sender<bool>>> {
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn logs_dir() -> PathBuf {
+    crate::storage::get_launcher_dir().join("logs")
+}
+
+/// Check the user allowed `request` to run when confirmations are on
+///
+/// The first run of a command string waits for the UI to answer. A refusal is
+/// recorded in the audit log and remembered until the command string changes.
+pub async fn authorize(app_handle: &AppHandle, request: &ExecRequest<'_>) -> Result<(), String> {
+    let confirm = match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().confirm_external_commands,
+        Err(_) => false,
+    };
+    if !confirm {
+        return Ok(());
+    }
+
+    let dir = logs_dir();
+    let decided = {
+        let dir = dir.clone();
+        let command = request.command.to_string();
+        tokio::task::spawn_blocking(move || decision(&dir, &command)).await.ok().flatten()
+    };
+    let allowed = match decided {
+        Some(allowed) => allowed,
+        None => match confirm_with_user(app_handle, request).await {
+            Some(allowed) => {
+                let dir = dir.clone();
+                let command = request.command.to_string();
+                let stored = tokio::task::spawn_blocking(move || remember(&dir, &command, allowed)).await;
+                if let Ok(Err(e)) = stored {
+                    println!("⚠️  Failed to remember decision for command: {}", e);
+                }
+                allowed
+            }
+            None => false,
+        },
+    };
+
+    if allowed {
+        return Ok(());
+    }
+    record(request, ExecOutcome::Denied, None, Duration::ZERO).await;
+    Err(format!("Running '{}' was not allowed, edit the command to be asked again", request.command))
+}
+
+/// Ask the UI about `request`, None when nobody answers in time
+async fn confirm_with_user(app_handle: &AppHandle, request: &ExecRequest<'_>) -> Option<bool> {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    pending().lock().unwrap().insert(request_id.clone(), tx);
+
+    println!("🛡️  Asking to run {:?} command: {}", request.feature, request.command);
+    let _ = app_handle.emit("exec_confirmation_required", ExecConfirmation {
+        request_id: request_id.clone(),
+        feature: request.feature,
+        command: request.command.to_string(),
+        cwd: request.cwd.to_string_lossy().to_string(),
+        instance_id: request.instance_id.map(String::from),
+    });
+
+    let answer = tokio::time::timeout(CONFIRM_TIMEOUT, rx).await.ok().and_then(Result::ok);
+    pending().lock().unwrap().remove(&request_id);
+    answer
+}
+
+/// Append a finished (or refused) run to the audit log
+///
+/// Failures are logged and never fail the run.
+pub async fn record(request: &ExecRequest<'_>, outcome: ExecOutcome, exit_code: Option<i32>, duration: Duration) {
+    let started = chrono::Utc::now() - chrono::Duration::from_std(duration).unwrap_or_default();
+    let entry = ExecRecord {
+        feature: request.feature,
+        command: request.command.to_string(),
+        program: request.program.to_string(),
+        args: request.args.to_vec(),
+        cwd: request.cwd.to_string_lossy().to_string(),
+        instance_id: request.instance_id.map(String::from),
+        outcome,
+        exit_code,
+        duration_ms: duration.as_millis() as u64,
+        timestamp: started.to_rfc3339(),
+    };
+
+    let dir = logs_dir();
+    let result = tokio::task::spawn_blocking(move || append(&dir, &entry)).await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+    if let Err(e) = result {
+        println!("⚠️  Failed to write execution audit log: {}", e);
+    }
+}
+
+fn append(dir: &Path, entry: &ExecRecord) -> std::io::Result<()> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    std::fs::create_dir_all(dir)?;
+
+    let log_path = dir.join(AUDIT_FILE);
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    std::fs::OpenOptions::new().create(true).append(true).open(&log_path)?.write_all(&line)?;
+
+    if std::fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0) > MAX_LOG_BYTES {
+        std::fs::rename(&log_path, dir.join(AUDIT_ARCHIVE_FILE))?;
+    }
+    Ok(())
+}
+
+/// Newest `limit` runs, newest first
+fn read_recent(dir: &Path, limit: usize) -> std::io::Result<Vec<ExecRecord>> {
+    let mut entries = read_log(&dir.join(AUDIT_ARCHIVE_FILE))?;
+    entries.extend(read_log(&dir.join(AUDIT_FILE))?);
+    entries.reverse();
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+/// Every entry of a log file, skipping lines that don't parse
+fn read_log(path: &Path) -> std::io::Result<Vec<ExecRecord>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut entries = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+fn read_decisions(dir: &Path) -> HashMap<String, ExecDecision> {
+    std::fs::read_to_string(dir.join(DECISIONS_FILE)).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Whether `command` was allowed, None when the user hasn't been asked
+fn decision(dir: &Path, command: &str) -> Option<bool> {
+    read_decisions(dir).get(command).map(|decision| decision.allowed)
+}
+
+fn remember(dir: &Path, command: &str, allowed: bool) -> std::io::Result<()> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    std::fs::create_dir_all(dir)?;
+    let mut decisions = read_decisions(dir);
+    decisions.insert(command.to_string(), ExecDecision { allowed, decided_at: chrono::Utc::now().to_rfc3339() });
+
+    let path = dir.join(DECISIONS_FILE);
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(&decisions)?)?;
+    std::fs::rename(tmp_path, path)
+}
+
+/// Programs the launcher ran for hooks, newest first
+#[command]
+pub async fn get_execution_audit(limit: Option<usize>) -> Result<Vec<ExecRecord>, String> {
+    let limit = limit.unwrap_or(DEFAULT_AUDIT_LIMIT);
+    let dir = logs_dir();
+    tokio::task::spawn_blocking(move || read_recent(&dir, limit)).await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to read execution audit log: {}", e))
+}
+
+/// Answer an `exec_confirmation_required` event
+#[command]
+pub async fn respond_exec_confirmation(request_id: String, allow: bool) -> Result<(), String> {
+    let sender = pending().lock().unwrap().remove(&request_id)
+        .ok_or_else(|| format!("No command is waiting for confirmation '{}'", request_id))?;
+    let _ = sender.send(allow);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(command: &str) -> ExecRecord {
+        ExecRecord {
+            feature: ExecFeature::PreLaunchHook,
+            command: command.to_string(),
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), command.to_string()],
+            cwd: "/instances/test".to_string(),
+            instance_id: Some("test".to_string()),
+            outcome: ExecOutcome::Exited,
+            exit_code: Some(0),
+            duration_ms: 12,
+            timestamp: "2024-05-01T12:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn runs_are_read_back_newest_first_across_rotation() {
+        let dir = std::env::temp_dir().join(format!("chai-exec-audit-{}", uuid::Uuid::new_v4()));
+        append(&dir, &entry("first")).unwrap();
+        std::fs::rename(dir.join(AUDIT_FILE), dir.join(AUDIT_ARCHIVE_FILE)).unwrap();
+        append(&dir, &entry("second")).unwrap();
+        append(&dir, &entry("third")).unwrap();
+
+        let commands: Vec<String> = read_recent(&dir, 10).unwrap().into_iter().map(|e| e.command).collect();
+        assert_eq!(commands, vec!["third", "second", "first"]);
+        assert_eq!(read_recent(&dir, 1).unwrap()[0].command, "third");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn decisions_hold_until_the_command_changes() {
+        let dir = std::env::temp_dir().join(format!("chai-exec-decisions-{}", uuid::Uuid::new_v4()));
+        assert_eq!(decision(&dir, "./backup.sh"), None);
+
+        remember(&dir, "./backup.sh", false).unwrap();
+        remember(&dir, "echo hi", true).unwrap();
+        assert_eq!(decision(&dir, "./backup.sh"), Some(false));
+        assert_eq!(decision(&dir, "./backup.sh --all"), None);
+        assert_eq!(decision(&dir, "echo hi"), Some(true));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
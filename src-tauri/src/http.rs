@@ -0,0 +1,192 @@
+//! The HTTP client every request of the launcher goes through
+//!
+//! It carries the proxy from the settings, the launcher's user agent and
+//! connect and read timeouts. It is built at startup and again whenever the
+//! settings are saved, and `client` hands out clones sharing one connection pool.
+//! In system mode reqwest reads the OS proxy configuration and the `HTTP_PROXY`,
+//! `HTTPS_PROXY` and `NO_PROXY` variables. A manual proxy is never used for
+//! local addresses, so servers in Docker containers on this computer stay direct.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{PoisonError, RwLock};
+use std::time::Duration;
+
+use crate::net::download::USER_AGENT;
+
+/// Secret name of the manual proxy's password
+pub const PROXY_PASSWORD_KEY: &str = "proxy_password";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Longest wait for the next bytes of a response, large downloads still finish
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Hosts a manual proxy is bypassed for besides the ones in the settings
+const LOCAL_HOSTS: &str = "localhost,127.0.0.1,::1";
+
+static CLIENT: RwLock<Option<reqwest::Client>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    /// The proxy the OS or environment is configured with
+    #[default]
+    System,
+    Manual,
+    /// Always connect directly
+    None,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProxySettings {
+    #[serde(default)]
+    pub mode: ProxyMode,
+    /// Manual proxy like `http://proxy.example.com:3128`, `http://` is assumed without a scheme
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Only passed in when saving, it lives in the credential store
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Comma separated hosts, `.domain` suffixes and CIDR ranges the manual proxy is bypassed for
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+/// A client builder configured for `settings`, failing on an unusable manual proxy
+pub fn builder(settings: &ProxySettings, password: Option<&str>) -> Result<reqwest::ClientBuilder, String> {
+    let builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT);
+
+    match settings.mode {
+        ProxyMode::System => Ok(builder),
+        ProxyMode::None => Ok(builder.no_proxy()),
+        ProxyMode::Manual => {
+            let url = non_empty(settings.url.as_deref()).ok_or("A manual proxy needs a URL")?;
+            let url = if url.contains("://") { url.to_string() } else { format!("http://{}", url) };
+            let mut proxy = reqwest::Proxy::all(&url).map_err(|e| format!("Invalid proxy URL {}: {}", url, e))?;
+            if let Some(username) = non_empty(settings.username.as_deref()) {
+                proxy = proxy.basic_auth(username, password.unwrap_or_default());
+            }
+            let no_proxy = match non_empty(settings.no_proxy.as_deref()) {
+                Some(extra) => format!("{},{}", LOCAL_HOSTS, extra),
+                None => LOCAL_HOSTS.to_string(),
+            };
+            Ok(builder.proxy(proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy))))
+        }
+    }
+}
+
+/// Fail when `settings` name a manual proxy that can't be used
+pub fn validate(settings: &ProxySettings) -> Result<(), String> {
+    builder(settings, None).map(|_| ())
+}
+
+/// Rebuild the shared client for `settings`, reading the proxy password from the credential store
+pub async fn configure(settings: &ProxySettings) -> Result<(), String> {
+    let password = match settings.mode {
+        ProxyMode::Manual if non_empty(settings.username.as_deref()).is_some() => {
+            crate::secrets::get(PROXY_PASSWORD_KEY).await
+                .map_err(|e| format!("Failed to read proxy password: {}", e))?
+        }
+        _ => None,
+    };
+    let client = builder(settings, password.as_deref())?
+        .build()
+        .map_err(|e| format!("Failed to set up HTTP client: {}", e))?;
+    *CLIENT.write().unwrap_or_else(PoisonError::into_inner) = Some(client);
+    println!("🌐 HTTP client uses {:?} proxy mode", settings.mode);
+    Ok(())
+}
+
+/// The shared client, with the system proxy until `configure` has run
+pub fn client() -> reqwest::Client {
+    if let Some(client) = CLIENT.read().unwrap_or_else(PoisonError::into_inner).as_ref() {
+        return client.clone();
+    }
+    let client = builder(&ProxySettings::default(), None)
+        .and_then(|builder| builder.build().map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            eprintln!("⚠️  Failed to set up HTTP client, using defaults: {}", e);
+            reqwest::Client::new()
+        });
+    CLIENT.write().unwrap_or_else(PoisonError::into_inner).get_or_insert(client).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Accept one connection, answer 204 and return the request head
+    async fn answer_once(listener: TcpListener) -> String {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut head = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while !head.ends_with(b"\r\n\r\n") {
+            let read = socket.read(&mut buffer).await.unwrap();
+            assert!(read > 0, "connection closed before the request head ended");
+            head.extend_from_slice(&buffer[..read]);
+        }
+        socket.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+        String::from_utf8(head).unwrap()
+    }
+
+    fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+        head.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    }
+
+    fn manual(url: String) -> ProxySettings {
+        ProxySettings { mode: ProxyMode::Manual, url: Some(url), username: Some("steve".to_string()), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn manual_proxy_receives_requests_with_credentials() {
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let settings = manual(proxy.local_addr().unwrap().to_string());
+        let client = builder(&settings, Some("hunter2")).unwrap().build().unwrap();
+
+        let proxied = tokio::spawn(answer_once(proxy));
+        let response = client.get("http://launcher.test/news").send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+        let head = proxied.await.unwrap();
+        assert!(head.starts_with("GET http://launcher.test/news HTTP/1.1"), "{}", head);
+        // steve:hunter2
+        assert_eq!(header(&head, "proxy-authorization"), Some("Basic c3RldmU6aHVudGVyMg=="));
+        assert_eq!(header(&head, "user-agent"), Some(USER_AGENT));
+    }
+
+    #[tokio::test]
+    async fn local_addresses_bypass_the_manual_proxy() {
+        // Nothing listens on port 9 of the proxy address, a proxied request would fail
+        let settings = manual("http://127.0.0.2:9".to_string());
+        let client = builder(&settings, None).unwrap().build().unwrap();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/status", server.local_addr().unwrap());
+        let direct = tokio::spawn(answer_once(server));
+        client.get(&url).send().await.unwrap();
+        assert!(direct.await.unwrap().starts_with("GET /status HTTP/1.1"));
+    }
+
+    #[test]
+    fn manual_mode_needs_a_usable_url() {
+        let missing = ProxySettings { mode: ProxyMode::Manual, ..Default::default() };
+        assert!(validate(&missing).is_err());
+        assert!(validate(&manual("http://[::1".to_string())).is_err());
+        assert!(validate(&manual("proxy.example.com:3128".to_string())).is_ok());
+        assert!(validate(&ProxySettings { mode: ProxyMode::None, ..Default::default() }).is_ok());
+    }
+}
@@ -0,0 +1,222 @@
+//! Recently played instances in the OS shell menu of the launcher
+//!
+//! The menu is rebuilt whenever `instances_updated` goes out, which covers play
+//! time changes and deleted instances, so ids never outlive their instance. Each
+//! entry starts the launcher with `chailauncher://launch/<id>`; the id is kept
+//! until the frontend asks for it with `take_pending_launch`.
+//!
+//! Linux gets desktop entry actions, which GNOME and KDE show on the launcher's
+//! icon. Windows jump lists and macOS dock menus need shell bindings this build
+//! doesn't have, so there the menu is left alone.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{command, AppHandle};
+
+use crate::minecraft::timestamps;
+use crate::storage::InstanceMetadata;
+
+/// Entries shown in the menu
+pub const MAX_ENTRIES: usize = 5;
+
+const LAUNCH_URL_PREFIX: &str = "chailauncher://launch/";
+
+/// Instance id from the launch URL the launcher was started with
+static PENDING_LAUNCH: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JumpListEntry {
+    pub instance_id: String,
+    pub title: String,
+    pub url: String,
+}
+
+pub fn launch_url(instance_id: &str) -> String {
+    format!("{}{}", LAUNCH_URL_PREFIX, urlencoding::encode(instance_id))
+}
+
+/// Instance id of a `chailauncher://launch/<id>` URL
+pub fn parse_launch_url(url: &str) -> Option<String> {
+    let id = url.strip_prefix(LAUNCH_URL_PREFIX)?.trim_end_matches('/');
+    let id = urlencoding::decode(id).ok()?.into_owned();
+    (!id.is_empty() && !id.contains('/')).then_some(id)
+}
+
+/// Remember the instance a launch URL in the arguments asks for
+pub fn capture_launch_args(args: impl IntoIterator<Item = String>) {
+    if let Some(instance_id) = args.into_iter().find_map(|arg| parse_launch_url(&arg)) {
+        println!("🚀 Launch of instance {} requested by the shell", instance_id);
+        *PENDING_LAUNCH.lock().unwrap() = Some(instance_id);
+    }
+}
+
+/// The most recently played instances, newest first
+pub fn recent_entries(instances: &[InstanceMetadata], limit: usize) -> Vec<JumpListEntry> {
+    let mut played: Vec<(i64, &InstanceMetadata)> = instances.iter()
+        .filter_map(|instance| {
+            let last_played = instance.last_played.as_deref().and_then(timestamps::to_epoch_millis)?;
+            Some((last_played, instance))
+        })
+        .collect();
+    played.sort_by_key(|p| std::cmp::Reverse(p.0));
+
+    played.into_iter()
+        .take(limit)
+        .map(|(_, instance)| JumpListEntry {
+            instance_id: instance.id.clone(),
+            title: instance.name.clone(),
+            url: launch_url(&instance.id),
+        })
+        .collect()
+}
+
+/// Rebuild the menu from storage, or clear it when the setting is off
+pub async fn refresh(app_handle: &AppHandle) {
+    let entries = match crate::storage::shared().await {
+        Ok(storage) => {
+            let storage = storage.read().await;
+            if storage.get_settings().jump_list_enabled {
+                let instances: Vec<InstanceMetadata> = storage.get_all_instances().into_iter().cloned().collect();
+                recent_entries(&instances, MAX_ENTRIES)
+            } else {
+                Vec::new()
+            }
+        }
+        Err(e) => {
+            println!("⚠️  Not updating the jump list: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = platform::apply(app_handle, &entries) {
+        println!("⚠️  Failed to update the jump list: {}", e);
+    }
+}
+
+/// Instance the launcher was started to launch, only returned once
+#[command]
+pub async fn take_pending_launch() -> Result<Option<String>, String> {
+    Ok(PENDING_LAUNCH.lock().unwrap().take())
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::JumpListEntry;
+    use tauri::AppHandle;
+
+    /// A user desktop entry named after the app id, which the shell matches to the window
+    pub fn apply(app_handle: &AppHandle, entries: &[JumpListEntry]) -> Result<(), String> {
+        let Some(data_dir) = dirs::data_dir() else {
+            return Ok(());
+        };
+        let path = data_dir.join("applications").join(format!("{}.desktop", app_handle.config().identifier));
+        if entries.is_empty() {
+            return match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+                _ => Ok(()),
+            };
+        }
+
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let name = app_handle.package_info().name.clone();
+        let contents = desktop_entry(&name, &exe.to_string_lossy(), entries);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&path, contents).map_err(|e| e.to_string())
+    }
+
+    pub(super) fn desktop_entry(name: &str, exe: &str, entries: &[JumpListEntry]) -> String {
+        let exe = quote(exe);
+        let actions: Vec<String> = (0..entries.len()).map(|i| format!("instance-{}", i)).collect();
+        let mut contents = format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec={}\nNoDisplay=true\nActions={};\n",
+            single_line(name), exe, actions.join(";")
+        );
+        for (action, entry) in actions.iter().zip(entries) {
+            contents.push_str(&format!(
+                "\n[Desktop Action {}]\nName={}\nExec={} {}\n",
+                action, single_line(&entry.title), exe, quote(&entry.url)
+            ));
+        }
+        contents
+    }
+
+    /// Quote an Exec argument as the desktop entry spec asks
+    fn quote(arg: &str) -> String {
+        let mut quoted = String::from("\"");
+        for c in arg.chars() {
+            if matches!(c, '"' | '`' | '$' | '\\') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        // Exec values go through the string escape rules first
+        quoted.replace('\\', "\\\\").replace('%', "%%")
+    }
+
+    fn single_line(value: &str) -> String {
+        value.replace(['\n', '\r'], " ")
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::JumpListEntry;
+    use tauri::AppHandle;
+
+    pub fn apply(_app_handle: &AppHandle, _entries: &[JumpListEntry]) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(id: &str, last_played: Option<&str>) -> InstanceMetadata {
+        InstanceMetadata {
+            name: format!("Instance {}", id),
+            last_played: last_played.map(str::to_string),
+            ..crate::storage::test_instance(id, std::path::PathBuf::from(format!("/instances/{}", id)))
+        }
+    }
+
+    #[test]
+    fn most_recently_played_instances_come_first() {
+        let mut instances: Vec<InstanceMetadata> = (0..7)
+            .map(|day| instance(&format!("i{}", day), Some(&format!("2024-05-0{}T12:00:00+00:00", day + 1))))
+            .collect();
+        instances.push(instance("never", None));
+
+        let entries = recent_entries(&instances, MAX_ENTRIES);
+        let ids: Vec<&str> = entries.iter().map(|entry| entry.instance_id.as_str()).collect();
+        assert_eq!(ids, vec!["i6", "i5", "i4", "i3", "i2"]);
+        assert_eq!(entries[0].url, "chailauncher://launch/i6");
+    }
+
+    #[test]
+    fn launch_urls_round_trip() {
+        let id = "0b6c2f1e-8d7a-4e53-9a1c-5f2d7b8e9c10";
+        assert_eq!(parse_launch_url(&launch_url(id)).as_deref(), Some(id));
+        assert_eq!(parse_launch_url("chailauncher://launch/abc/").as_deref(), Some("abc"));
+        assert_eq!(parse_launch_url("chailauncher://launch/"), None);
+        assert_eq!(parse_launch_url("chailauncher://launch/a/b"), None);
+        assert_eq!(parse_launch_url("--flag"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn desktop_entry_lists_an_action_per_instance() {
+        let entries = vec![JumpListEntry {
+            instance_id: "abc".to_string(),
+            title: "Create: 100%\nmods".to_string(),
+            url: launch_url("abc"),
+        }];
+        let contents = platform::desktop_entry("ChaiLauncher", "/opt/Chai Launcher/chailauncher", &entries);
+        assert!(contents.contains("Actions=instance-0;\n"));
+        assert!(contents.contains("\n[Desktop Action instance-0]\nName=Create: 100% mods\n"));
+        assert!(contents.contains("Exec=\"/opt/Chai Launcher/chailauncher\" \"chailauncher://launch/abc\"\n"));
+    }
+}
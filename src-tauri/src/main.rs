@@ -5,18 +5,30 @@ mod minecraft;
 mod types;
 mod commands;
 mod storage;
+mod migrations;
 mod modpack;
 mod mods;
 mod auth;
 mod docker;
+mod tasks;
+mod portable;
+mod net;
+mod http;
+mod notifications;
+mod provenance;
+mod exec_audit;
+mod skins;
+mod shutdown;
+mod jumplist;
+mod secrets;
+mod search;
 
-use reqwest;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 #[tauri::command]
 async fn fetch_news() -> Result<String, String> {
     let url = "https://net-secondary.web.minecraft-services.net/api/v1.0/en-us/search?pageSize=24&sortType=Recent&category=News&newsOnly=true";
-    let resp = reqwest::get(url).await.map_err(|e| e.to_string())?;
+    let resp = http::client().get(url).send().await.map_err(|e| e.to_string())?;
     let body = resp.text().await.map_err(|e| e.to_string())?;
     Ok(body)
 }
@@ -27,7 +39,7 @@ async fn fetch_news_page(page: u32) -> Result<String, String> {
         "https://net-secondary.web.minecraft-services.net/api/v1.0/en-us/search?page={}&pageSize=24&sortType=Recent&category=News&newsOnly=true",
         page
     );
-    let resp = reqwest::get(&url).await.map_err(|e| e.to_string())?;
+    let resp = http::client().get(&url).send().await.map_err(|e| e.to_string())?;
     let body = resp.text().await.map_err(|e| e.to_string())?;
     Ok(body)
 }
@@ -39,9 +51,28 @@ fn get_app_version() -> &'static str {
 }
 
 fn main() {
+    if std::env::args().any(|arg| arg == minecraft::graphics::PROBE_FLAG) {
+        minecraft::graphics::run_probe_process();
+        return;
+    }
+    if std::env::args_os().nth(1).is_some_and(|arg| arg == minecraft::environment::CLEAN_ENV_FLAG) {
+        std::process::exit(minecraft::environment::run_clean_env_process(std::env::args_os().skip(2)));
+    }
+
+    // Decide between portable and profile data before anything touches storage
+    let locations = portable::data_locations();
+    println!("📁 Launcher data: {}", locations.launcher_dir.display());
+    jumplist::capture_launch_args(std::env::args().skip(1));
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(tasks::TaskManagerState::new(tasks::TaskManager::new()))
+        .manage(net::manager::shared())
+        .manage(modpack::ModpackInstallsState::new(modpack::ModpackInstalls::new()))
+        .manage(minecraft::sessions::PlaySessionsState::new(minecraft::sessions::PlaySessions::new()))
+        .manage(docker::log_stream::LogStreamsState::new(docker::log_stream::LogStreams::new()))
         .invoke_handler(tauri::generate_handler![
             minecraft::commands::get_minecraft_versions,
             minecraft::commands::create_instance,
@@ -51,14 +82,20 @@ fn main() {
             minecraft::commands::download_and_install_java,
             minecraft::commands::download_and_install_java8,
             minecraft::commands::download_and_install_java17,
+            minecraft::commands::download_and_install_java21,
             minecraft::commands::download_and_install_java_version,
             minecraft::commands::download_and_install_both_java,
             minecraft::commands::get_java_installations,
+            minecraft::commands::get_java_runtimes,
+            minecraft::commands::list_java_runtimes,
+            minecraft::commands::remove_java_installation,
             minecraft::commands::get_required_java_version,
             minecraft::commands::get_java_for_minecraft_version,
             minecraft::commands::is_java_version_installed,
             minecraft::commands::validate_java_installation,
             minecraft::commands::get_system_memory,
+            minecraft::commands::get_system_memory_info,
+            minecraft::commands::probe_graphics,
             minecraft::commands::download_minecraft_assets,
             minecraft::commands::detect_gdlauncher_instances,
             minecraft::commands::detect_all_external_instances,
@@ -68,26 +105,97 @@ fn main() {
             minecraft::commands::import_orphaned_instances,
             minecraft::commands::save_instance,
             minecraft::commands::delete_instance,
+            minecraft::commands::resolve_duplicate_instances,
+            minecraft::commands::repair_instance_paths,
             minecraft::commands::update_instance,
+            minecraft::commands::update_instance_hooks,
+            minecraft::commands::update_instance_environment,
+            minecraft::commands::update_instance_window,
+            minecraft::commands::update_instance_jvm_preset,
+            minecraft::commands::get_jvm_presets,
+            minecraft::commands::validate_launch_config,
             minecraft::commands::get_launcher_settings,
             minecraft::commands::update_launcher_settings,
             minecraft::commands::install_minecraft_version,
+            minecraft::commands::prepare_instance,
             minecraft::commands::backup_instance,
             minecraft::commands::restore_instance,
             minecraft::commands::refresh_instance_sizes,
+            minecraft::commands::clean_instance_logs,
+            minecraft::commands::diagnose_launch,
+            minecraft::commands::save_settings_preset,
+            minecraft::commands::list_settings_presets,
+            minecraft::commands::apply_settings_preset,
+            minecraft::commands::delete_settings_preset,
+            minecraft::commands::clone_instance_to_version,
+            minecraft::commands::duplicate_instance,
+            minecraft::commands::rename_instance,
+            minecraft::commands::import_minecraft_folder_zip,
+            minecraft::commands::check_port_open,
+            minecraft::commands::get_lan_world_info,
+            minecraft::commands::list_instance_worlds,
+            minecraft::commands::launch_instance_into_world,
+            minecraft::commands::join_server,
+            minecraft::commands::get_recent_servers,
+            minecraft::commands::get_playtime_stats,
+            minecraft::commands::get_running_instances,
+            minecraft::commands::kill_instance,
+            minecraft::commands::get_instance_stability,
+            minecraft::commands::get_instance_health,
+            minecraft::commands::get_latest_crash_report,
+            minecraft::commands::get_recent_game_log,
+            minecraft::commands::get_storage_migration_log,
+            minecraft::commands::get_data_locations,
+            minecraft::commands::convert_to_portable,
             modpack::search_modpacks,
             modpack::get_modpack_versions,
             modpack::install_modpack,
+            modpack::cancel_modpack_install,
+            modpack::update_modpack_instance,
+            modpack::install_local_server,
+            modpack::accept_server_eula,
             modpack::create_modpack,
             mods::commands::search_mods,
             mods::commands::get_mod_details,
             mods::commands::install_mod,
+            mods::commands::import_local_mod,
             mods::commands::uninstall_mod,
+            mods::commands::list_mod_trash,
+            mods::commands::restore_deleted_mod,
+            mods::commands::repair_instance_mods,
+            mods::commands::empty_mod_trash,
+            mods::commands::get_mod_dependency_graph,
+            mods::commands::get_mod_conflicts,
+            tasks::get_task,
+            tasks::get_tasks,
+            tasks::cancel_task,
+            tasks::clear_finished_tasks,
+            provenance::get_file_provenance,
+            exec_audit::get_execution_audit,
+            exec_audit::respond_exec_confirmation,
+            shutdown::get_previous_session_report,
+            shutdown::dismiss_previous_session_report,
+            shutdown::get_background_work,
+            jumplist::take_pending_launch,
+            search::search_local,
+            search::rebuild_search_index,
+            net::cache::get_download_cache_stats,
+            net::cache::clear_download_cache,
+            net::manager::list_active_downloads,
+            net::manager::cancel_download,
             mods::commands::update_mod,
+            mods::commands::update_all_mods,
+            mods::commands::export_mod_list,
+            mods::commands::compare_instance_to_manifest,
+            mods::commands::sync_instance_to_manifest,
             mods::commands::get_installed_mods,
             mods::commands::set_mod_enabled,
+            mods::commands::set_mod_group,
+            mods::commands::list_mod_groups,
+            mods::commands::set_group_enabled,
             mods::commands::check_mod_updates,
             mods::commands::get_mod_loader_versions,
+            mods::commands::get_loader_availability,
             mods::commands::install_mod_loader,
             mods::commands::get_installed_mod_loader,
             mods::commands::get_featured_mods,
@@ -104,11 +212,19 @@ fn main() {
             auth::get_stored_accounts,
             auth::refresh_minecraft_token,
             auth::remove_minecraft_account,
+            auth::start_device_code_auth,
+            auth::poll_device_code_auth,
+            auth::get_active_account,
+            auth::set_active_account,
+            auth::get_player_skin,
+            auth::upload_skin,
+            auth::reset_skin,
             fetch_news,
             fetch_news_page,
             get_app_version,
             docker::commands::test_docker_connection,
             docker::commands::add_docker_connection,
+            docker::commands::get_server_templates,
             docker::commands::deploy_minecraft_server,
             docker::commands::start_server,
             docker::commands::stop_server,
@@ -122,18 +238,81 @@ fn main() {
             docker::commands::restart_server,
             docker::commands::backup_server,
             docker::commands::get_server_stats,
+            docker::commands::get_server_players,
+            docker::commands::get_server_player_history,
+            docker::commands::send_rcon_command,
+            docker::commands::get_server_properties,
+            docker::commands::update_server_properties,
+            docker::commands::update_server_resources,
+            docker::commands::upload_world,
+            docker::commands::upgrade_server,
+            docker::commands::export_server_compose,
+            docker::commands::start_server_log_stream,
+            docker::commands::stop_server_log_stream,
+            docker::commands::get_server_players_config,
+            docker::commands::update_server_players_config,
+            docker::commands::is_docker_mock,
             minecraft::commands::analyze_instance_java_requirements,
             minecraft::commands::get_mod_java_requirements
         ])
         .setup(|app| {
+            // Load storage once, every command shares it through the lock
+            let storage = tauri::async_runtime::block_on(storage::shared())
+                .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+            app.manage(storage.clone());
+            net::manager::shared().attach(app.handle().clone());
+
+            // Before anything goes online, with a broken proxy setting the system proxy is used
+            let proxy = tauri::async_runtime::block_on(async { storage.read().await.get_settings().proxy.clone() });
+            if let Err(e) = tauri::async_runtime::block_on(http::configure(&proxy)) {
+                eprintln!("⚠️  {}", e);
+            }
+
+            let shutdown = tauri::async_runtime::block_on(shutdown::Shutdown::begin());
+            let previous_session = shutdown.previous_session();
+            app.manage(shutdown::ShutdownState::new(shutdown));
+
+            // Refresh account tokens ahead of expiry so launches don't wait on sign-in
+            auth::start_token_refresh(app.handle().clone());
+
             // Initialize Docker manager and MCVM concurrently
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
+                if let Some(previous_session) = previous_session {
+                    let _ = app_handle.emit("previous_session_interrupted", previous_session);
+                }
+
+                jumplist::refresh(&app_handle).await;
+
+                // Manual tokens saved in the config by older versions
+                secrets::migrate_auth_token(&storage).await;
+                minecraft::integrity::start_sweep(app_handle.clone());
+
+                // Uninstalled mods past the retention setting
+                let mods_dirs: Vec<std::path::PathBuf> = storage.read().await.get_all_instances()
+                    .into_iter()
+                    .map(|instance| instance.game_dir.join("mods"))
+                    .collect();
+                for mods_dir in mods_dirs {
+                    mods::trash::purge_expired(&mods_dir).await;
+                }
+
+                // Instances sharing a game directory break each other on delete
+                let duplicates = storage.read().await.find_duplicate_game_dirs();
+                if !duplicates.is_empty() {
+                    eprintln!("⚠️  {} game directories are used by more than one instance", duplicates.len());
+                    let _ = app_handle.emit("duplicate_instances_detected", &duplicates);
+                }
+
+                // Stored paths broken by a renamed user folder or a new drive letter
+                minecraft::relocate::check_at_startup(&app_handle, &storage).await;
+
                 // Initialize Docker manager state with persistent storage
-                match docker::DockerManager::new().await {
+                match docker::DockerBackend::new().await {
                     Ok(docker_manager) => {
                         app_handle.manage(docker::commands::DockerManagerState::new(docker_manager));
                         println!("✅ Docker manager initialized with persistent storage");
+                        docker::commands::start_stats_poller(app_handle.clone());
                     }
                     Err(e) => {
                         eprintln!("⚠️  Failed to initialize Docker manager: {}", e);
@@ -150,6 +329,14 @@ fn main() {
             
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Cancel background work so it can checkpoint, then write out queued saves
+            if let tauri::RunEvent::Exit = event {
+                let shutdown = app_handle.state::<shutdown::ShutdownState>().inner().clone();
+                let app_handle = app_handle.clone();
+                tauri::async_runtime::block_on(async move { shutdown.run(&app_handle).await });
+            }
+        });
 }
\ No newline at end of file
@@ -0,0 +1,730 @@
+//! Storage schema migrations
+//!
+//! Each migration upgrades the raw config JSON by one `config_version`. Steps run
+//! in order on load, must be idempotent, and only fill in what is missing so user
+//! values are never overwritten. New config fields should land here as a new step.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::minecraft::crashes::InstanceStability;
+use crate::storage::{DockerTimeouts, LauncherConfig, LauncherSettings, LogCleanupPolicy, NotificationCategories, ServerPortRange};
+
+/// A single ordered schema migration
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&mut Value) -> Result<()>,
+}
+
+/// Every migration, ordered by version
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Add Docker connections and servers to pre-2.1.0 configs",
+        apply: add_docker_collections,
+    },
+    Migration {
+        version: 2,
+        description: "Add Java analysis fields to instances",
+        apply: add_java_analysis_fields,
+    },
+    Migration {
+        version: 3,
+        description: "Add log cleanup policy",
+        apply: add_log_cleanup_policy,
+    },
+    Migration {
+        version: 4,
+        description: "Add per-instance game arguments",
+        apply: add_instance_game_args,
+    },
+    Migration {
+        version: 5,
+        description: "Add Docker mock setting and flags",
+        apply: add_docker_mock_flags,
+    },
+    Migration {
+        version: 6,
+        description: "Add CurseForge API key setting",
+        apply: add_curseforge_api_key,
+    },
+    Migration {
+        version: 7,
+        description: "Add asset download concurrency setting",
+        apply: add_max_concurrent_downloads,
+    },
+    Migration {
+        version: 8,
+        description: "Add Docker timeouts",
+        apply: add_docker_timeouts,
+    },
+    Migration {
+        version: 9,
+        description: "Add bundled-only Java setting",
+        apply: add_java_bundled_only,
+    },
+    Migration {
+        version: 10,
+        description: "Add Microsoft sign-in timeout setting",
+        apply: add_oauth_timeout,
+    },
+    Migration {
+        version: 11,
+        description: "Add modpack download concurrency setting",
+        apply: add_modpack_download_concurrency,
+    },
+    Migration {
+        version: 12,
+        description: "Add notification settings",
+        apply: add_notification_settings,
+    },
+    Migration {
+        version: 13,
+        description: "Add instance stability tracking",
+        apply: add_instance_stability,
+    },
+    Migration {
+        version: 14,
+        description: "Add download cache size setting",
+        apply: add_download_cache_max_mb,
+    },
+    Migration {
+        version: 15,
+        description: "Add multiple launches setting",
+        apply: add_allow_multiple_instances,
+    },
+    Migration {
+        version: 16,
+        description: "Add exit confirmation setting",
+        apply: add_confirm_exit_with_background_work,
+    },
+    Migration {
+        version: 17,
+        description: "Add launch hooks",
+        apply: add_launch_hooks,
+    },
+    Migration {
+        version: 18,
+        description: "Add instance environment variables",
+        apply: add_instance_env_vars,
+    },
+    Migration {
+        version: 19,
+        description: "Add jump list setting",
+        apply: add_jump_list_enabled,
+    },
+    Migration {
+        version: 20,
+        description: "Add window size and fullscreen options",
+        apply: add_window_options,
+    },
+    Migration {
+        version: 21,
+        description: "Add mod trash retention setting",
+        apply: add_mod_trash_retention,
+    },
+    Migration {
+        version: 22,
+        description: "Add instance recent servers",
+        apply: add_recent_servers,
+    },
+    Migration {
+        version: 23,
+        description: "Add active account setting",
+        apply: add_active_account,
+    },
+    Migration {
+        version: 24,
+        description: "Add integrity sweep settings and instance health",
+        apply: add_instance_health,
+    },
+    Migration {
+        version: 25,
+        description: "Add offline launch settings",
+        apply: add_offline_launch,
+    },
+    Migration {
+        version: 26,
+        description: "Add external command confirmation setting",
+        apply: add_command_confirmation,
+    },
+    Migration {
+        version: 27,
+        description: "Add graphics probe setting",
+        apply: add_graphics_probe,
+    },
+    Migration {
+        version: 28,
+        description: "Add instance search roots",
+        apply: add_instance_search_roots,
+    },
+    Migration {
+        version: 29,
+        description: "Add token storage setting",
+        apply: add_token_storage,
+    },
+    Migration {
+        version: 30,
+        description: "Add server port range setting",
+        apply: add_server_port_range,
+    },
+    Migration {
+        version: 31,
+        description: "Add JVM argument presets",
+        apply: add_jvm_args_presets,
+    },
+    Migration {
+        version: 32,
+        description: "Add download mirror setting",
+        apply: add_download_mirror,
+    },
+    Migration {
+        version: 33,
+        description: "Add proxy settings",
+        apply: add_proxy_settings,
+    },
+];
+
+/// Schema version written by this build
+pub const CURRENT_CONFIG_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub description: String,
+}
+
+/// Record of one migration run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationLogEntry {
+    pub started_at: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub applied: Vec<AppliedMigration>,
+    pub backup_path: Option<String>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Read the schema version of a raw config, 0 for configs that predate versioning
+pub fn config_version(config: &Value) -> u32 {
+    config.get("config_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32
+}
+
+/// Apply all pending migrations to a raw config in order
+pub fn migrate(config: &mut Value) -> Result<Vec<AppliedMigration>> {
+    let current = config_version(config);
+    let mut applied = Vec::new();
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        (migration.apply)(config)
+            .with_context(|| format!("Migration {} ({}) failed", migration.version, migration.description))?;
+
+        root(config)?.insert("config_version".to_string(), json!(migration.version));
+        applied.push(AppliedMigration {
+            version: migration.version,
+            description: migration.description.to_string(),
+        });
+    }
+
+    Ok(applied)
+}
+
+/// Migrate the config file at `config_path` if it is out of date
+///
+/// The original file is backed up before anything runs and the migrated config is
+/// only written once it parses. On failure the backup is restored.
+pub async fn migrate_config_file(config_path: &Path, log_path: &Path) -> Result<Option<MigrationLogEntry>> {
+    let content = fs::read_to_string(config_path).await
+        .context("Failed to read config file")?;
+    let mut config: Value = serde_json::from_str(&content)
+        .context("Failed to parse config file as JSON")?;
+
+    let from_version = config_version(&config);
+    if from_version >= CURRENT_CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let backup_path = backup_path(config_path, from_version);
+    fs::copy(config_path, &backup_path).await
+        .context("Failed to back up config before migrating")?;
+
+    let mut entry = MigrationLogEntry {
+        started_at: chrono::Utc::now().to_rfc3339(),
+        from_version,
+        to_version: from_version,
+        applied: Vec::new(),
+        backup_path: Some(backup_path.to_string_lossy().to_string()),
+        success: false,
+        error: None,
+    };
+
+    let result = async {
+        let applied = migrate(&mut config)?;
+        serde_json::from_value::<LauncherConfig>(config.clone())
+            .context("Migrated config does not match the current schema")?;
+
+        let temp_path = config_path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(&config)?).await
+            .context("Failed to write migrated config")?;
+        fs::rename(&temp_path, config_path).await
+            .context("Failed to replace config with migrated version")?;
+
+        Ok::<_, anyhow::Error>(applied)
+    }.await;
+
+    match result {
+        Ok(applied) => {
+            entry.to_version = config_version(&config);
+            entry.applied = applied;
+            entry.success = true;
+            append_log(log_path, &entry).await?;
+
+            println!("✅ Migrated config from schema v{} to v{}", from_version, entry.to_version);
+            Ok(Some(entry))
+        }
+        Err(e) => {
+            let _ = fs::copy(&backup_path, config_path).await;
+            entry.error = Some(format!("{:#}", e));
+            let _ = append_log(log_path, &entry).await;
+
+            eprintln!("❌ Config migration failed, restored backup: {:#}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Read all recorded migration runs, oldest first
+pub async fn read_log(log_path: &Path) -> Result<Vec<MigrationLogEntry>> {
+    if !log_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(log_path).await
+        .context("Failed to read migration log")?;
+    serde_json::from_str(&content)
+        .context("Failed to parse migration log")
+}
+
+async fn append_log(log_path: &Path, entry: &MigrationLogEntry) -> Result<()> {
+    let mut log = read_log(log_path).await.unwrap_or_default();
+    log.push(entry.clone());
+
+    fs::write(log_path, serde_json::to_string_pretty(&log)?).await
+        .context("Failed to write migration log")
+}
+
+fn backup_path(config_path: &Path, from_version: u32) -> PathBuf {
+    let file_name = config_path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "config.json".to_string());
+    config_path.with_file_name(format!("{}.v{}.bak", file_name, from_version))
+}
+
+fn root(config: &mut Value) -> Result<&mut Map<String, Value>> {
+    config.as_object_mut().context("Config is not a JSON object")
+}
+
+fn insert_missing(object: &mut Map<String, Value>, key: &str, value: Value) {
+    object.entry(key.to_string()).or_insert(value);
+}
+
+/// Apply `f` to every record in a top-level map such as `instances`
+fn for_each_record(config: &mut Value, key: &str, f: impl Fn(&mut Map<String, Value>)) -> Result<()> {
+    let records = root(config)?
+        .entry(key.to_string())
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+        .with_context(|| format!("`{}` is not a JSON object", key))?;
+
+    for record in records.values_mut() {
+        let record = record.as_object_mut()
+            .with_context(|| format!("Entry in `{}` is not a JSON object", key))?;
+        f(record);
+    }
+
+    Ok(())
+}
+
+fn settings(config: &mut Value) -> Result<&mut Map<String, Value>> {
+    root(config)?
+        .entry("settings".to_string())
+        .or_insert(serde_json::to_value(LauncherSettings::default())?)
+        .as_object_mut()
+        .context("`settings` is not a JSON object")
+}
+
+fn add_docker_collections(config: &mut Value) -> Result<()> {
+    let root = root(config)?;
+    insert_missing(root, "instances", json!({}));
+    insert_missing(root, "docker_connections", json!({}));
+    insert_missing(root, "servers", json!({}));
+    if !root.contains_key("settings") {
+        root.insert("settings".to_string(), serde_json::to_value(LauncherSettings::default())?);
+    }
+    root.insert("version".to_string(), json!("2.1.0"));
+    Ok(())
+}
+
+fn add_java_analysis_fields(config: &mut Value) -> Result<()> {
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "resolved_java_version", Value::Null);
+        insert_missing(instance, "java_analysis_date", Value::Null);
+    })
+}
+
+fn add_log_cleanup_policy(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "log_cleanup", serde_json::to_value(LogCleanupPolicy::default())?);
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "log_cleanup", Value::Null);
+    })
+}
+
+fn add_instance_game_args(config: &mut Value) -> Result<()> {
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "game_args", Value::Null);
+    })
+}
+
+fn add_docker_mock_flags(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "docker_mock", json!(false));
+    for_each_record(config, "docker_connections", |connection| {
+        insert_missing(connection, "mock", json!(false));
+    })?;
+    for_each_record(config, "servers", |server| {
+        insert_missing(server, "mock", json!(false));
+    })
+}
+
+fn add_curseforge_api_key(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "curseforge_api_key", Value::Null);
+    Ok(())
+}
+
+fn add_max_concurrent_downloads(config: &mut Value) -> Result<()> {
+    let concurrency = crate::minecraft::commands::versions::DEFAULT_ASSET_CONCURRENCY;
+    insert_missing(settings(config)?, "max_concurrent_downloads", json!(concurrency));
+    Ok(())
+}
+
+fn add_docker_timeouts(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "docker_timeouts", serde_json::to_value(DockerTimeouts::default())?);
+    Ok(())
+}
+
+fn add_java_bundled_only(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "java_bundled_only", json!(false));
+    Ok(())
+}
+
+fn add_oauth_timeout(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "oauth_timeout_secs", json!(crate::storage::default_oauth_timeout_secs()));
+    Ok(())
+}
+
+fn add_modpack_download_concurrency(config: &mut Value) -> Result<()> {
+    let concurrency = crate::modpack::types::DEFAULT_DOWNLOAD_CONCURRENCY;
+    insert_missing(settings(config)?, "modpack_download_concurrency", json!(concurrency));
+    Ok(())
+}
+
+fn add_notification_settings(config: &mut Value) -> Result<()> {
+    let settings = settings(config)?;
+    insert_missing(settings, "notifications_enabled", json!(true));
+    insert_missing(settings, "notification_categories", serde_json::to_value(NotificationCategories::default())?);
+    Ok(())
+}
+
+fn add_download_cache_max_mb(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "download_cache_max_mb", json!(crate::net::cache::DEFAULT_MAX_MB));
+    Ok(())
+}
+
+fn add_allow_multiple_instances(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "allow_multiple_instances", json!(false));
+    Ok(())
+}
+
+fn add_confirm_exit_with_background_work(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "confirm_exit_with_background_work", json!(false));
+    Ok(())
+}
+
+fn add_jump_list_enabled(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "jump_list_enabled", json!(true));
+    Ok(())
+}
+
+fn add_window_options(config: &mut Value) -> Result<()> {
+    let settings = settings(config)?;
+    insert_missing(settings, "window_width", json!(0));
+    insert_missing(settings, "window_height", json!(0));
+    insert_missing(settings, "fullscreen", json!(false));
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "window_width", Value::Null);
+        insert_missing(instance, "window_height", Value::Null);
+        insert_missing(instance, "fullscreen", Value::Null);
+    })
+}
+
+fn add_mod_trash_retention(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "mod_trash_retention_days", json!(crate::mods::trash::DEFAULT_RETENTION_DAYS));
+    Ok(())
+}
+
+fn add_recent_servers(config: &mut Value) -> Result<()> {
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "recent_servers", json!([]));
+    })
+}
+
+fn add_active_account(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "active_account_uuid", Value::Null);
+    Ok(())
+}
+
+fn add_instance_health(config: &mut Value) -> Result<()> {
+    let settings = settings(config)?;
+    insert_missing(settings, "integrity_sweep_enabled", json!(false));
+    insert_missing(settings, "integrity_sweep_pace_ms", json!(crate::minecraft::integrity::DEFAULT_PACE_MS));
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "health", Value::Null);
+    })
+}
+
+fn add_offline_launch(config: &mut Value) -> Result<()> {
+    let settings = settings(config)?;
+    insert_missing(settings, "allow_offline_launch", json!(false));
+    insert_missing(settings, "offline_username", Value::Null);
+    Ok(())
+}
+
+fn add_command_confirmation(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "confirm_external_commands", json!(false));
+    Ok(())
+}
+
+fn add_graphics_probe(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "probe_graphics_before_launch", json!(true));
+    Ok(())
+}
+
+fn add_instance_search_roots(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "instance_search_roots", json!([]));
+    Ok(())
+}
+
+/// Plaintext tokens are moved out at runtime, the credential store isn't reachable from here
+fn add_token_storage(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "token_storage", json!("keyring"));
+    Ok(())
+}
+
+fn add_server_port_range(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "server_port_range", serde_json::to_value(ServerPortRange::default())?);
+    Ok(())
+}
+
+fn add_jvm_args_presets(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "jvm_args_preset", json!("default"));
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "jvm_args_preset", Value::Null);
+    })
+}
+
+fn add_download_mirror(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "download_mirror", json!({ "kind": "none" }));
+    Ok(())
+}
+
+fn add_proxy_settings(config: &mut Value) -> Result<()> {
+    insert_missing(settings(config)?, "proxy", json!({ "mode": "system" }));
+    Ok(())
+}
+
+fn add_launch_hooks(config: &mut Value) -> Result<()> {
+    let timeout = crate::minecraft::hooks::DEFAULT_TIMEOUT_SECS;
+    insert_missing(settings(config)?, "hook_timeout_secs", json!(timeout));
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "pre_launch_command", Value::Null);
+        insert_missing(instance, "post_exit_command", Value::Null);
+    })
+}
+
+fn add_instance_env_vars(config: &mut Value) -> Result<()> {
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "env_vars", Value::Null);
+        insert_missing(instance, "clear_inherited_env", json!(false));
+    })
+}
+
+fn add_instance_stability(config: &mut Value) -> Result<()> {
+    let stability = serde_json::to_value(InstanceStability::default())?;
+    for_each_record(config, "instances", |instance| {
+        insert_missing(instance, "stability", stability.clone());
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG_V2_0_0: &str = include_str!("fixtures/config_v2_0_0.json");
+    const CONFIG_V2_1_0: &str = include_str!("fixtures/config_v2_1_0.json");
+
+    fn fixture(content: &str) -> Value {
+        serde_json::from_str(content).unwrap()
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn migrations_are_ordered_from_one() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as u32 + 1);
+        }
+        assert_eq!(CURRENT_CONFIG_VERSION, MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn migrates_v2_0_0_config() {
+        let mut config = fixture(CONFIG_V2_0_0);
+        assert_eq!(config_version(&config), 0);
+
+        let applied = migrate(&mut config).unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(config_version(&config), CURRENT_CONFIG_VERSION);
+
+        let migrated: LauncherConfig = serde_json::from_value(config).unwrap();
+        assert_eq!(migrated.version, "2.1.0");
+        assert!(migrated.docker_connections.is_empty());
+        assert!(!migrated.settings.docker_mock);
+        assert!(migrated.settings.curseforge_api_key.is_none());
+        assert_eq!(migrated.settings.max_concurrent_downloads, 16);
+        assert_eq!(migrated.settings.docker_timeouts.request_secs, 30);
+        assert!(!migrated.settings.java_bundled_only);
+        assert_eq!(migrated.settings.oauth_timeout_secs, 300);
+        assert_eq!(migrated.settings.modpack_download_concurrency, 8);
+        assert!(migrated.settings.notifications_enabled);
+        assert!(migrated.settings.notification_categories.servers);
+        assert_eq!(migrated.settings.download_cache_max_mb, 4096);
+        assert!(!migrated.settings.allow_multiple_instances);
+        assert!(!migrated.settings.confirm_exit_with_background_work);
+        assert_eq!(migrated.settings.hook_timeout_secs, 60);
+        assert!(migrated.settings.jump_list_enabled);
+        assert_eq!(migrated.settings.window_width, 0);
+        assert!(!migrated.settings.fullscreen);
+        assert_eq!(migrated.settings.mod_trash_retention_days, 30);
+        assert!(migrated.settings.active_account_uuid.is_none());
+        assert!(!migrated.settings.integrity_sweep_enabled);
+        assert_eq!(migrated.settings.integrity_sweep_pace_ms, 20);
+        assert!(!migrated.settings.allow_offline_launch);
+        assert!(migrated.settings.offline_username.is_none());
+        assert!(!migrated.settings.confirm_external_commands);
+        assert!(migrated.settings.probe_graphics_before_launch);
+        assert!(migrated.settings.instance_search_roots.is_empty());
+        assert_eq!(migrated.settings.token_storage, crate::secrets::TokenStorage::Keyring);
+        assert_eq!(migrated.settings.server_port_range, ServerPortRange::default());
+        assert_eq!(migrated.settings.jvm_args_preset, crate::minecraft::jvm::JvmArgsPreset::Default);
+        assert_eq!(migrated.settings.download_mirror, crate::net::DownloadMirror::None);
+        assert_eq!(migrated.settings.proxy, crate::http::ProxySettings::default());
+
+        let instance = &migrated.instances["old-instance"];
+        assert_eq!(instance.name, "Old Survival");
+        assert!(instance.resolved_java_version.is_none());
+        assert!(instance.game_args.is_none());
+        assert!(instance.pre_launch_command.is_none());
+        assert!(instance.env_vars.is_none());
+        assert!(instance.jvm_args_preset.is_none());
+        assert!(!instance.clear_inherited_env);
+        assert!(instance.fullscreen.is_none());
+        assert!(instance.recent_servers.is_empty());
+        assert!(instance.health.is_none());
+        assert_eq!(instance.stability.clean_exits, 0);
+    }
+
+    #[test]
+    fn migrates_v2_1_0_config_and_keeps_values() {
+        let mut config = fixture(CONFIG_V2_1_0);
+        migrate(&mut config).unwrap();
+
+        let migrated: LauncherConfig = serde_json::from_value(config).unwrap();
+        assert_eq!(migrated.settings.theme, "light");
+        assert_eq!(migrated.settings.log_cleanup.max_age_days, Some(30));
+
+        let instance = &migrated.instances["java-instance"];
+        assert_eq!(instance.resolved_java_version, Some(17));
+        assert!(!migrated.servers["server-1"].mock);
+        assert!(!migrated.docker_connections["local"].mock);
+    }
+
+    #[test]
+    fn migrations_are_idempotent() {
+        let mut config = fixture(CONFIG_V2_1_0);
+        migrate(&mut config).unwrap();
+        let once = config.clone();
+
+        assert!(migrate(&mut config).unwrap().is_empty());
+        assert_eq!(config, once);
+
+        // Re-running every step directly must not change anything either
+        for migration in MIGRATIONS {
+            (migration.apply)(&mut config).unwrap();
+        }
+        assert_eq!(config, once);
+    }
+
+    #[tokio::test]
+    async fn migrates_file_with_backup_and_log() {
+        let dir = temp_dir();
+        let config_path = dir.join("config.json");
+        let log_path = dir.join("migration_log.json");
+        std::fs::write(&config_path, CONFIG_V2_0_0).unwrap();
+
+        let entry = migrate_config_file(&config_path, &log_path).await.unwrap().unwrap();
+        assert!(entry.success);
+        assert_eq!(entry.from_version, 0);
+        assert_eq!(entry.to_version, CURRENT_CONFIG_VERSION);
+
+        let backup = std::fs::read_to_string(entry.backup_path.unwrap()).unwrap();
+        assert_eq!(backup, CONFIG_V2_0_0);
+
+        let migrated: Value = serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(config_version(&migrated), CURRENT_CONFIG_VERSION);
+
+        // Up-to-date configs are left alone
+        assert!(migrate_config_file(&config_path, &log_path).await.unwrap().is_none());
+        assert_eq!(read_log(&log_path).await.unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn failed_migration_restores_original() {
+        let dir = temp_dir();
+        let config_path = dir.join("config.json");
+        let log_path = dir.join("migration_log.json");
+        let broken = r#"{ "instances": [], "settings": {}, "version": "2.0.0" }"#;
+        std::fs::write(&config_path, broken).unwrap();
+
+        assert!(migrate_config_file(&config_path, &log_path).await.is_err());
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), broken);
+
+        let log = read_log(&log_path).await.unwrap();
+        assert_eq!(log.len(), 1);
+        assert!(!log[0].success);
+        assert!(log[0].error.is_some());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,8 +1,144 @@
 //! Asset Management using MCVM
 //! 
 //! This module handles Minecraft asset downloading and management using MCVM.
+//!
+//! Versions before 1.7.3 can't read the hashed object store. The "legacy" index is
+//! `virtual`, so its objects are copied under `assets/virtual/legacy/` by their
+//! names, and "pre-1.6" maps to resources, so they go to the instance's
+//! `resources/` folder where 1.5 and older look for sounds.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
 
 use super::MCVMCore;
+use super::versions::load_version_manifest;
+
+/// Limit on `inheritsFrom` chains, which are one level deep in practice
+const MAX_INHERITANCE_DEPTH: usize = 5;
+
+/// Asset index a version needs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetIndexRef {
+    pub id: String,
+    pub url: Option<String>,
+}
+
+/// Result of checking that an instance's asset index file exists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetIndexCheck {
+    pub id: String,
+    pub path: String,
+    pub present: bool,
+    /// The index was missing and has been downloaded by this check
+    pub downloaded: bool,
+    pub error: Option<String>,
+}
+
+/// Where a version reads its assets from, decided by flags in its asset index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetLayout {
+    /// Objects named by hash under `assets/objects`, 1.7.3 and later
+    Hashed,
+    /// Named copies under `assets/virtual/<index>`
+    Virtual,
+    /// Named copies in the instance's `resources` folder
+    Resources,
+}
+
+impl AssetLayout {
+    /// Layout an index asks for with its `virtual` and `map_to_resources` flags
+    ///
+    /// The two legacy ids are recognized too, should an index have lost its flags.
+    pub fn of(index_id: &str, index_json: &Value) -> Self {
+        let flag = |name: &str| index_json.get(name).and_then(|v| v.as_bool()).unwrap_or(false);
+        if flag("map_to_resources") || index_id == "pre-1.6" {
+            AssetLayout::Resources
+        } else if flag("virtual") || index_id == "legacy" {
+            AssetLayout::Virtual
+        } else {
+            AssetLayout::Hashed
+        }
+    }
+
+    /// Folder the game's `${game_assets}` points at
+    pub fn game_assets_dir(self, game_dir: &Path, index_id: &str) -> PathBuf {
+        match self {
+            AssetLayout::Hashed => game_dir.join("assets"),
+            AssetLayout::Virtual => game_dir.join("assets").join("virtual").join(index_id),
+            AssetLayout::Resources => game_dir.join("resources"),
+        }
+    }
+}
+
+/// Layout of a downloaded asset index, None when the file can't be read
+pub async fn read_layout(game_dir: &Path, index_id: &str) -> Option<AssetLayout> {
+    let index_file = game_dir.join("assets").join("indexes").join(format!("{}.json", index_id));
+    let content = tokio::fs::read_to_string(index_file).await.ok()?;
+    let index_json: Value = serde_json::from_str(&content).ok()?;
+    Some(AssetLayout::of(index_id, &index_json))
+}
+
+/// Copy downloaded objects to the names a virtual or resources index expects
+///
+/// Copies matching their object in size are kept. Returns how many files
+/// were copied, objects that are not downloaded are skipped.
+pub async fn build_named_layout(game_dir: &Path, index_id: &str) -> Result<usize, String> {
+    let index_file = game_dir.join("assets").join("indexes").join(format!("{}.json", index_id));
+    let content = tokio::fs::read_to_string(&index_file).await
+        .map_err(|e| format!("Failed to read asset index {}: {}", index_id, e))?;
+    let index_json: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Asset index {} is not valid JSON: {}", index_id, e))?;
+
+    let layout = AssetLayout::of(index_id, &index_json);
+    if layout == AssetLayout::Hashed {
+        return Ok(0);
+    }
+    let target_dir = layout.game_assets_dir(game_dir, index_id);
+    let objects_dir = game_dir.join("assets").join("objects");
+
+    let mut copied = 0;
+    for (name, info) in index_json.get("objects").and_then(|v| v.as_object()).into_iter().flatten() {
+        let Some(hash) = info.get("hash").and_then(|v| v.as_str()).filter(|hash| hash.len() > 2) else {
+            continue;
+        };
+        // Names come from the index, never let one point outside the target folder
+        let relative = Path::new(name);
+        if relative.components().any(|c| !matches!(c, std::path::Component::Normal(_))) {
+            continue;
+        }
+        let object = objects_dir.join(&hash[0..2]).join(hash);
+        let target = target_dir.join(relative);
+
+        let Ok(object_meta) = tokio::fs::metadata(&object).await else {
+            continue;
+        };
+        if tokio::fs::metadata(&target).await.is_ok_and(|existing| existing.len() == object_meta.len()) {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        tokio::fs::copy(&object, &target).await
+            .map_err(|e| format!("Failed to copy asset {}: {}", name, e))?;
+        copied += 1;
+    }
+
+    if copied > 0 {
+        println!("📂 Copied {} assets to {} for index {}", copied, target_dir.display(), index_id);
+    }
+    Ok(copied)
+}
+
+/// Build the named layout for a version whose asset index needs one
+pub async fn prepare_named_assets(game_dir: &Path, version: &str) -> Result<(), String> {
+    let Some(asset_index) = resolve_asset_index(game_dir, version).await? else {
+        return Ok(());
+    };
+    build_named_layout(game_dir, &asset_index.id).await.map(|_| ())
+}
 
 /// Download and manage Minecraft assets for a version (placeholder)
 pub async fn download_assets(
@@ -45,4 +181,197 @@ pub async fn assets_available(_version: &str, game_dir: &std::path::Path) -> boo
     
     // Basic check for asset structure
     assets_dir.exists() && indexes_dir.exists() && objects_dir.exists()
-}
\ No newline at end of file
+}
+
+/// Asset index named by a version JSON
+///
+/// Many versions share an index (1.20.1 uses "5"), so the version id is never a safe
+/// stand-in. Old version JSONs may only carry an `assets` name such as "legacy" or "pre-1.6".
+pub fn asset_index_from_json(version_json: &Value) -> Option<AssetIndexRef> {
+    if let Some(asset_index) = version_json.get("assetIndex") {
+        if let Some(id) = asset_index.get("id").and_then(|v| v.as_str()) {
+            return Some(AssetIndexRef {
+                id: id.to_string(),
+                url: asset_index.get("url").and_then(|v| v.as_str()).map(str::to_string),
+            });
+        }
+    }
+
+    version_json.get("assets")
+        .and_then(|v| v.as_str())
+        .map(|id| AssetIndexRef { id: id.to_string(), url: None })
+}
+
+/// Asset index of an installed version, following `inheritsFrom` for mod loader versions
+pub async fn resolve_asset_index(game_dir: &std::path::Path, version: &str) -> Result<Option<AssetIndexRef>, String> {
+    let mut current = version.to_string();
+
+    for _ in 0..MAX_INHERITANCE_DEPTH {
+        let Some(version_json) = load_version_manifest(game_dir, &current).await? else {
+            return Ok(None);
+        };
+
+        if let Some(asset_index) = asset_index_from_json(&version_json) {
+            return Ok(Some(asset_index));
+        }
+
+        match version_json.get("inheritsFrom").and_then(|v| v.as_str()) {
+            Some(parent) => current = parent.to_string(),
+            None => return Ok(None),
+        }
+    }
+
+    Err(format!("Version {} inherits too deeply to resolve its asset index", version))
+}
+
+/// Check the asset index file for a version exists under assets/indexes, downloading it if missing
+pub async fn ensure_asset_index(game_dir: &std::path::Path, version: &str) -> Result<Option<AssetIndexCheck>, String> {
+    let Some(asset_index) = resolve_asset_index(game_dir, version).await? else {
+        return Ok(None);
+    };
+
+    let indexes_dir = game_dir.join("assets").join("indexes");
+    let index_file = indexes_dir.join(format!("{}.json", asset_index.id));
+    let mut check = AssetIndexCheck {
+        id: asset_index.id.clone(),
+        path: index_file.to_string_lossy().to_string(),
+        present: index_file.exists(),
+        downloaded: false,
+        error: None,
+    };
+
+    if check.present {
+        return Ok(Some(check));
+    }
+
+    let Some(url) = asset_index.url else {
+        check.error = Some(format!("Asset index {} is missing and the version has no download URL", asset_index.id));
+        return Ok(Some(check));
+    };
+
+    println!("📥 Asset index {} is missing for {}, downloading it", asset_index.id, version);
+    match download_asset_index(&url, &index_file).await {
+        Ok(()) => {
+            check.present = true;
+            check.downloaded = true;
+        }
+        Err(e) => {
+            println!("❌ {}", e);
+            check.error = Some(e);
+        }
+    }
+
+    Ok(Some(check))
+}
+
+async fn download_asset_index(url: &str, index_file: &std::path::Path) -> Result<(), String> {
+    crate::net::download(url, index_file, Default::default()).await
+        .map_err(|e| format!("Failed to download asset index: {}", e))?;
+
+    let content = tokio::fs::read_to_string(index_file).await
+        .map_err(|e| format!("Failed to read asset index: {}", e))?;
+    if let Err(e) = serde_json::from_str::<Value>(&content) {
+        let _ = tokio::fs::remove_file(index_file).await;
+        return Err(format!("Downloaded asset index is not valid JSON: {}", e));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION_1_20_1: &str = include_str!("fixtures/version_1_20_1.json");
+    const LEGACY_INDEX: &str = include_str!("fixtures/assets_legacy.json");
+    const PRE_1_6_INDEX: &str = include_str!("fixtures/assets_pre_1_6.json");
+
+    #[test]
+    fn reads_asset_index_id_instead_of_version_id() {
+        let version_json: Value = serde_json::from_str(VERSION_1_20_1).unwrap();
+        let asset_index = asset_index_from_json(&version_json).unwrap();
+
+        assert_eq!(version_json["id"], "1.20.1");
+        assert_eq!(asset_index.id, "5");
+        assert!(asset_index.url.unwrap().ends_with("/5.json"));
+    }
+
+    #[test]
+    fn falls_back_to_legacy_assets_name() {
+        let version_json = serde_json::json!({ "id": "1.5.2", "assets": "pre-1.6" });
+        let asset_index = asset_index_from_json(&version_json).unwrap();
+
+        assert_eq!(asset_index.id, "pre-1.6");
+        assert!(asset_index.url.is_none());
+    }
+
+    #[test]
+    fn legacy_indexes_ask_for_named_layouts() {
+        let legacy: Value = serde_json::from_str(LEGACY_INDEX).unwrap();
+        let pre_1_6: Value = serde_json::from_str(PRE_1_6_INDEX).unwrap();
+        assert_eq!(AssetLayout::of("legacy", &legacy), AssetLayout::Virtual);
+        assert_eq!(AssetLayout::of("pre-1.6", &pre_1_6), AssetLayout::Resources);
+        assert_eq!(AssetLayout::of("5", &serde_json::json!({ "objects": {} })), AssetLayout::Hashed);
+
+        // The well known ids still work without their flags, and flags work under any id
+        assert_eq!(AssetLayout::of("legacy", &serde_json::json!({})), AssetLayout::Virtual);
+        assert_eq!(AssetLayout::of("pre-1.6", &serde_json::json!({})), AssetLayout::Resources);
+        assert_eq!(AssetLayout::of("custom", &serde_json::json!({ "virtual": true })), AssetLayout::Virtual);
+
+        let game_dir = Path::new("/games/old");
+        assert_eq!(AssetLayout::Virtual.game_assets_dir(game_dir, "legacy"), PathBuf::from("/games/old/assets/virtual/legacy"));
+        assert_eq!(AssetLayout::Resources.game_assets_dir(game_dir, "pre-1.6"), PathBuf::from("/games/old/resources"));
+        assert_eq!(AssetLayout::Hashed.game_assets_dir(game_dir, "5"), PathBuf::from("/games/old/assets"));
+    }
+
+    /// Put an index and the objects it lists, named by hash, under `game_dir`
+    fn write_index(game_dir: &Path, id: &str, index: &str) {
+        let assets = game_dir.join("assets");
+        std::fs::create_dir_all(assets.join("indexes")).unwrap();
+        std::fs::write(assets.join("indexes").join(format!("{}.json", id)), index).unwrap();
+        let json: Value = serde_json::from_str(index).unwrap();
+        for (name, info) in json["objects"].as_object().unwrap() {
+            let hash = info["hash"].as_str().unwrap();
+            let dir = assets.join("objects").join(&hash[0..2]);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(hash), format!("contents of {}", name)).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn copies_objects_to_their_names() {
+        let game_dir = std::env::temp_dir().join(format!("chai-assets-{}", uuid::Uuid::new_v4()));
+        write_index(&game_dir, "legacy", LEGACY_INDEX);
+        write_index(&game_dir, "pre-1.6", PRE_1_6_INDEX);
+
+        assert_eq!(build_named_layout(&game_dir, "legacy").await.unwrap(), 2);
+        let sound = game_dir.join("assets/virtual/legacy/sound/step/grass1.ogg");
+        assert_eq!(std::fs::read_to_string(&sound).unwrap(), "contents of sound/step/grass1.ogg");
+
+        assert_eq!(build_named_layout(&game_dir, "pre-1.6").await.unwrap(), 2);
+        assert!(game_dir.join("resources/newsound/random/click.ogg").is_file());
+
+        // Copies already in place are left alone
+        assert_eq!(build_named_layout(&game_dir, "legacy").await.unwrap(), 0);
+
+        std::fs::remove_dir_all(game_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn follows_inherits_from_for_mod_loader_versions() {
+        let game_dir = std::env::temp_dir().join(format!("chai-assets-{}", uuid::Uuid::new_v4()));
+        let fabric = "fabric-loader-0.15.11-1.20.1";
+        for (id, content) in [
+            ("1.20.1", VERSION_1_20_1.to_string()),
+            (fabric, serde_json::json!({ "id": fabric, "inheritsFrom": "1.20.1" }).to_string()),
+        ] {
+            let dir = game_dir.join("versions").join(id);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(format!("{}.json", id)), content).unwrap();
+        }
+
+        let asset_index = resolve_asset_index(&game_dir, fabric).await.unwrap().unwrap();
+        assert_eq!(asset_index.id, "5");
+
+        std::fs::remove_dir_all(game_dir).unwrap();
+    }
+}
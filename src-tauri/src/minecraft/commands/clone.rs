@@ -0,0 +1,331 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use tokio::fs;
+
+use crate::mods::{ModManager, ModLoader};
+use crate::mods::api::common::ApiUtils;
+use crate::mods::loaders::ModLoaderManager;
+use crate::storage::{StorageState, InstanceMetadata};
+use super::instances::{copy_directory_recursive, copy_times, save_instance};
+use super::versions::download_minecraft_assets_with_progress;
+
+/// Top-level entries that are tied to the Minecraft version and are not copied
+const VERSION_SPECIFIC_ENTRIES: &[&str] = &[
+    "versions",
+    "libraries",
+    "natives",
+    "mods",
+    "mods_metadata.json",
+    "logs",
+    "crash-reports",
+    ".fabric",
+];
+
+/// Folder inside the cloned instance's mods directory that holds mods without a compatible version
+const INCOMPATIBLE_MODS_DIR: &str = ".incompatible";
+
+/// A mod that could not be carried over to the new version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncompatibleMod {
+    pub name: String,
+    pub file_name: String,
+    pub reason: String,
+}
+
+/// Result of cloning an instance to a new Minecraft version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloneInstanceResult {
+    pub instance_id: String,
+    pub name: String,
+    pub minecraft_version: String,
+    pub loader: Option<String>,
+    pub migrated_mods: Vec<String>,
+    pub incompatible_mods: Vec<IncompatibleMod>,
+}
+
+/// Clone an instance into a new instance on a different Minecraft version
+///
+/// The source instance is never modified. Mods without a compatible version are
+/// listed in the result and copied into `mods/.incompatible/` for reference.
+#[command]
+pub async fn clone_instance_to_version(
+    instance_id: String,
+    new_mc_version: String,
+    new_name: String,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<CloneInstanceResult, String> {
+    let new_name = new_name.trim().to_string();
+    if new_name.is_empty() {
+        return Err("Instance name cannot be empty".to_string());
+    }
+
+    // Read what's needed up front, saving the clone takes the lock again
+    let (source, default_instances_dir) = {
+        let storage = storage.read().await;
+        let source = storage.get_instance(&instance_id)
+            .ok_or_else(|| format!("Instance '{}' not found", instance_id))?
+            .clone();
+        (source, storage.get_settings().instances_dir.clone())
+    };
+
+    let new_id = uuid::Uuid::new_v4().to_string();
+    let instances_dir = source.game_dir.parent()
+        .map(Path::to_path_buf)
+        .unwrap_or(default_instances_dir);
+    let new_dir = instances_dir.join(ApiUtils::sanitize_filename(&new_name));
+
+    if new_dir.exists() {
+        return Err(format!("Instance directory already exists: {}", new_dir.display()));
+    }
+
+    println!("🧬 Cloning instance '{}' to Minecraft {} as '{}'", source.name, new_mc_version, new_name);
+
+    let result = clone_into(&source, &new_id, &new_dir, &new_mc_version, &new_name, &app_handle).await;
+
+    match result {
+        Ok(result) => {
+            println!("✅ Cloned '{}' to '{}' ({} mods migrated, {} incompatible)",
+                source.name, new_name, result.migrated_mods.len(), result.incompatible_mods.len());
+            Ok(result)
+        }
+        Err(e) => {
+            // Leave no half-built instance behind
+            let _ = fs::remove_dir_all(&new_dir).await;
+            emit_progress(&app_handle, &new_id, &instance_id, "failed", 100);
+            Err(e)
+        }
+    }
+}
+
+async fn clone_into(
+    source: &InstanceMetadata,
+    new_id: &str,
+    new_dir: &PathBuf,
+    new_mc_version: &str,
+    new_name: &str,
+    app_handle: &AppHandle,
+) -> Result<CloneInstanceResult, String> {
+    // Phase 1: copy user data
+    emit_progress(app_handle, new_id, &source.id, "copying", 0);
+    copy_instance_files(&source.game_dir, new_dir).await?;
+    let new_mods_dir = new_dir.join("mods");
+    fs::create_dir_all(&new_mods_dir).await
+        .map_err(|e| format!("Failed to create mods directory: {}", e))?;
+
+    // Phase 2: install the new Minecraft version
+    emit_progress(app_handle, new_id, &source.id, "minecraft", 20);
+    download_minecraft_assets_with_progress(
+        new_mc_version.to_string(),
+        new_dir.to_string_lossy().to_string(),
+        new_id,
+        app_handle,
+    ).await?;
+
+    // Phase 3: install a matching loader build
+    emit_progress(app_handle, new_id, &source.id, "loader", 40);
+    let source_loader = ModLoaderManager::new(source.game_dir.clone()).get_installed_loader().await;
+    let loader = match &source_loader {
+        Some(source_loader) => Some(install_matching_loader(source_loader, new_dir, new_mc_version).await?),
+        None => None,
+    };
+
+    // Phase 4: migrate mods
+    emit_progress(app_handle, new_id, &source.id, "mods", 50);
+    let source_mods = ModManager::read_installed_mods(&source.game_dir).await
+        .map_err(|e| format!("Failed to read installed mods: {}", e))?;
+
+    let mut migrated_mods = Vec::new();
+    let mut incompatible_mods = Vec::new();
+
+    if let Some(loader) = &loader {
+        let mut manager = ModManager::new(new_dir.clone()).await
+            .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+        let total = source_mods.len().max(1);
+        for (i, installed_mod) in source_mods.values().enumerate() {
+            emit_progress(app_handle, new_id, &source.id, "mods", 50 + (45 * i / total) as u32);
+
+            match manager.install_compatible_version(installed_mod, new_mc_version, loader.name(), |_, _| {}).await {
+                Ok(Some(_)) => {
+                    if !installed_mod.enabled {
+                        let _ = manager.set_mod_enabled(&installed_mod.mod_info.id, false).await;
+                    }
+                    migrated_mods.push(installed_mod.mod_info.name.clone());
+                }
+                Ok(None) => incompatible_mods.push(IncompatibleMod {
+                    name: installed_mod.mod_info.name.clone(),
+                    file_name: file_name(&installed_mod.install_path),
+                    reason: format!("No version for Minecraft {} on {}", new_mc_version, loader.name()),
+                }),
+                Err(e) => incompatible_mods.push(IncompatibleMod {
+                    name: installed_mod.mod_info.name.clone(),
+                    file_name: file_name(&installed_mod.install_path),
+                    reason: format!("Compatibility check failed: {}", e),
+                }),
+            }
+        }
+    } else {
+        incompatible_mods.extend(source_mods.values().map(|installed_mod| IncompatibleMod {
+            name: installed_mod.mod_info.name.clone(),
+            file_name: file_name(&installed_mod.install_path),
+            reason: "Source instance has no mod loader".to_string(),
+        }));
+    }
+
+    // Jars without metadata can't be looked up, so they are kept for reference only
+    let tracked: Vec<&PathBuf> = source_mods.values().map(|m| &m.install_path).collect();
+    if let Ok(mut entries) = fs::read_dir(source.game_dir.join("mods")).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let is_jar = path.extension().is_some_and(|ext| ext == "jar");
+            if is_jar && !tracked.contains(&&path) {
+                incompatible_mods.push(IncompatibleMod {
+                    name: file_name(&path),
+                    file_name: file_name(&path),
+                    reason: "Unknown mod source".to_string(),
+                });
+            }
+        }
+    }
+
+    // Keep copies of incompatible mods for reference
+    if !incompatible_mods.is_empty() {
+        let incompatible_dir = new_mods_dir.join(INCOMPATIBLE_MODS_DIR);
+        fs::create_dir_all(&incompatible_dir).await
+            .map_err(|e| format!("Failed to create incompatible mods directory: {}", e))?;
+
+        for incompatible in &incompatible_mods {
+            let source_path = source_mods.values()
+                .find(|m| file_name(&m.install_path) == incompatible.file_name)
+                .map(|m| m.install_path.clone())
+                .unwrap_or_else(|| source.game_dir.join("mods").join(&incompatible.file_name));
+
+            if source_path.exists() {
+                fs::copy(&source_path, incompatible_dir.join(&incompatible.file_name)).await
+                    .map_err(|e| format!("Failed to copy incompatible mod: {}", e))?;
+            }
+        }
+    }
+
+    // Phase 5: register the new instance
+    emit_progress(app_handle, new_id, &source.id, "saving", 95);
+    let metadata = InstanceMetadata {
+        id: new_id.to_string(),
+        name: new_name.to_string(),
+        version: new_mc_version.to_string(),
+        modpack: None,
+        modpack_version: None,
+        game_dir: new_dir.clone(),
+        java_path: None,
+        jvm_args: source.jvm_args.clone(),
+        last_played: None,
+        total_play_time: 0,
+        icon: source.icon.clone(),
+        is_modded: loader.is_some(),
+        mods_count: migrated_mods.len() as u32,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        size_mb: None,
+        description: Some(format!("Cloned from {} ({})", source.name, source.version)),
+        tags: source.tags.clone(),
+        resolved_java_version: None,
+        java_analysis_date: None,
+        log_cleanup: source.log_cleanup.clone(),
+        game_args: source.game_args.clone(),
+        pre_launch_command: source.pre_launch_command.clone(),
+        post_exit_command: source.post_exit_command.clone(),
+        env_vars: source.env_vars.clone(),
+        clear_inherited_env: source.clear_inherited_env,
+        window_width: source.window_width,
+        window_height: source.window_height,
+        fullscreen: source.fullscreen,
+        jvm_args_preset: source.jvm_args_preset,
+        stability: Default::default(),
+        recent_servers: Vec::new(),
+        health: None,
+    };
+    save_instance(metadata, app_handle.clone(), app_handle.state()).await?;
+
+    emit_progress(app_handle, new_id, &source.id, "complete", 100);
+
+    Ok(CloneInstanceResult {
+        instance_id: new_id.to_string(),
+        name: new_name.to_string(),
+        minecraft_version: new_mc_version.to_string(),
+        loader: loader.map(|l| format!("{}-{}", l.name(), l.version())),
+        migrated_mods,
+        incompatible_mods,
+    })
+}
+
+/// Copy an instance directory, skipping version-specific entries
+async fn copy_instance_files(source_dir: &Path, dest_dir: &PathBuf) -> Result<(), String> {
+    fs::create_dir_all(dest_dir).await
+        .map_err(|e| format!("Failed to create instance directory: {}", e))?;
+
+    let mut entries = fs::read_dir(source_dir).await
+        .map_err(|e| format!("Failed to read instance directory: {}", e))?;
+
+    while let Some(entry) = entries.next_entry().await
+        .map_err(|e| format!("Failed to read instance directory: {}", e))? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if VERSION_SPECIFIC_ENTRIES.contains(&name.as_str()) {
+            continue;
+        }
+
+        let src_path = entry.path();
+        let dst_path = dest_dir.join(&name);
+        if src_path.is_dir() {
+            copy_directory_recursive(&src_path, &dst_path, true).await?;
+        } else {
+            fs::copy(&src_path, &dst_path).await
+                .map_err(|e| format!("Failed to copy {}: {}", name, e))?;
+            copy_times(&src_path, &dst_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Install the newest build of the source's loader that supports the target version
+async fn install_matching_loader(
+    source_loader: &ModLoader,
+    new_dir: &Path,
+    new_mc_version: &str,
+) -> Result<ModLoader, String> {
+    let loader_manager = ModLoaderManager::new(new_dir.to_path_buf());
+
+    let versions = loader_manager.get_available_versions(source_loader.name(), new_mc_version).await
+        .map_err(|e| format!("Failed to get {} versions: {}", source_loader.name(), e))?;
+    let version = versions.into_iter().next()
+        .ok_or_else(|| format!("No {} build available for Minecraft {}", source_loader.name(), new_mc_version))?;
+
+    let loader = match source_loader {
+        ModLoader::Forge(_) => ModLoader::Forge(version),
+        ModLoader::Fabric(_) => ModLoader::Fabric(version),
+        ModLoader::Quilt(_) => ModLoader::Quilt(version),
+        ModLoader::NeoForge(_) => ModLoader::NeoForge(version),
+        other => return Err(format!("Unsupported loader: {}", other.name())),
+    };
+
+    loader_manager.install_loader(&loader, new_mc_version).await
+        .map_err(|e| format!("Failed to install mod loader: {}", e))?;
+
+    Ok(loader)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn emit_progress(app_handle: &AppHandle, instance_id: &str, source_instance_id: &str, stage: &str, progress: u32) {
+    let _ = app_handle.emit("clone_progress", serde_json::json!({
+        "instanceId": instance_id,
+        "sourceInstanceId": source_instance_id,
+        "stage": stage,
+        "progress": progress
+    }));
+}
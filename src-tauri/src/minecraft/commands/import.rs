@@ -0,0 +1,460 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State};
+use tokio::fs;
+
+use crate::modpack::overrides::{extract_archive, OverrideNormalizer};
+use crate::mods::api::common::ApiUtils;
+use crate::mods::ModLoader;
+use crate::storage::{InstanceMetadata, StorageState};
+use super::instances::save_instance;
+
+/// Top-level entries that are regenerated or redownloaded, removed when stripping junk
+const JUNK_ENTRIES: &[&str] = &[
+    "logs",
+    "crash-reports",
+    "assets",
+    "webcache",
+    "webcache2",
+    ".mixin.out",
+];
+
+/// Entries that mark the root of a .minecraft folder inside an archive
+const ROOT_MARKERS: &[&str] = &[
+    "versions",
+    "mods",
+    "saves",
+    "config",
+    "resourcepacks",
+    "options.txt",
+    "launcher_profiles.json",
+];
+
+/// Tag on imported instances whose Minecraft version still has to be installed
+pub const NEEDS_INSTALL_TAG: &str = "needs-install";
+
+/// Result of importing a zipped .minecraft folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderImportResult {
+    pub instance_id: String,
+    pub name: String,
+    pub game_dir: String,
+    /// Detected Minecraft version, `None` when nothing in the archive names one
+    pub minecraft_version: Option<String>,
+    /// Detected loader, e.g. `fabric-0.15.11`
+    pub loader: Option<String>,
+    pub mods_count: u32,
+    /// The archive had no installed versions, so the installer has to run before launching
+    pub needs_install: bool,
+    /// Top-level entries removed as junk
+    pub stripped: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Minecraft version and loader found in a .minecraft folder
+#[derive(Debug, Default)]
+struct DetectedVersion {
+    minecraft_version: Option<String>,
+    loader: Option<ModLoader>,
+    needs_install: bool,
+}
+
+/// Everything the blocking part of the import finds out
+struct ExtractedFolder {
+    detected: DetectedVersion,
+    mods_count: u32,
+    stripped: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Import a zipped .minecraft folder, e.g. one copied from the vanilla launcher, as a new instance
+///
+/// Archives without installed versions are still imported and tagged `needs-install`
+/// so the UI can run the installer for the detected or a chosen version.
+#[command]
+pub async fn import_minecraft_folder_zip(
+    zip_path: String,
+    instance_name: String,
+    strip_junk: Option<bool>,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<FolderImportResult, String> {
+    let zip_path = PathBuf::from(zip_path);
+    let name = instance_name.trim().to_string();
+    if name.is_empty() {
+        return Err("Instance name cannot be empty".to_string());
+    }
+
+    let instances_dir = storage.read().await.get_settings().instances_dir.clone();
+    let game_dir = instances_dir.join(ApiUtils::sanitize_filename(&name));
+    if game_dir.exists() {
+        return Err(format!("Instance directory already exists: {}", game_dir.display()));
+    }
+
+    println!("📦 Importing .minecraft folder from {} as '{}'", zip_path.display(), name);
+
+    let source = zip_path.clone();
+    let target = game_dir.clone();
+    let strip_junk = strip_junk.unwrap_or(false);
+    let extracted = tokio::task::spawn_blocking(move || extract_folder(&source, &target, strip_junk))
+        .await
+        .map_err(|e| format!("Import task failed: {}", e))
+        .and_then(|result| result);
+
+    let extracted = match extracted {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            // Leave no half-extracted instance behind
+            let _ = fs::remove_dir_all(&game_dir).await;
+            return Err(e);
+        }
+    };
+
+    let detected = extracted.detected;
+    let mut tags = vec!["imported".to_string()];
+    if detected.needs_install {
+        tags.push(NEEDS_INSTALL_TAG.to_string());
+    }
+
+    let instance_id = uuid::Uuid::new_v4().to_string();
+    let metadata = InstanceMetadata {
+        id: instance_id.clone(),
+        name: name.clone(),
+        version: detected.minecraft_version.clone().unwrap_or_else(|| "unknown".to_string()),
+        modpack: None,
+        modpack_version: None,
+        game_dir: game_dir.clone(),
+        java_path: None,
+        jvm_args: None,
+        last_played: None,
+        total_play_time: 0,
+        icon: None,
+        is_modded: detected.loader.is_some() || extracted.mods_count > 0,
+        mods_count: extracted.mods_count,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        size_mb: None,
+        description: zip_path.file_name()
+            .map(|file| format!("Imported from {}", file.to_string_lossy())),
+        tags,
+        resolved_java_version: None,
+        java_analysis_date: None,
+        log_cleanup: None,
+        game_args: None,
+        pre_launch_command: None,
+        post_exit_command: None,
+        env_vars: None,
+        clear_inherited_env: false,
+        window_width: None,
+        window_height: None,
+        fullscreen: None,
+        jvm_args_preset: None,
+        stability: Default::default(),
+        recent_servers: Vec::new(),
+        health: None,
+    };
+
+    if let Err(e) = save_instance(metadata, app_handle, storage).await {
+        let _ = fs::remove_dir_all(&game_dir).await;
+        return Err(e);
+    }
+
+    let loader = detected.loader.map(|l| format!("{}-{}", l.name(), l.version()));
+    println!("✅ Imported '{}' (Minecraft {}, loader {}, {} mods{})",
+        name,
+        detected.minecraft_version.as_deref().unwrap_or("unknown"),
+        loader.as_deref().unwrap_or("none"),
+        extracted.mods_count,
+        if detected.needs_install { ", needs install" } else { "" });
+
+    Ok(FolderImportResult {
+        instance_id,
+        name,
+        game_dir: game_dir.to_string_lossy().to_string(),
+        minecraft_version: detected.minecraft_version,
+        loader,
+        mods_count: extracted.mods_count,
+        needs_install: detected.needs_install,
+        stripped: extracted.stripped,
+        warnings: extracted.warnings,
+    })
+}
+
+/// Extract the archive into `game_dir` and inspect the result
+fn extract_folder(zip_path: &Path, game_dir: &Path, strip_junk: bool) -> Result<ExtractedFolder, String> {
+    let file = std::fs::File::open(zip_path)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    let root = archive_root(&names)
+        .ok_or("The archive doesn't contain a .minecraft folder")?;
+
+    std::fs::create_dir_all(game_dir)
+        .map_err(|e| format!("Failed to create instance directory: {}", e))?;
+
+    let prefix = (!root.is_empty()).then_some(root.as_str());
+    let mut normalizer = OverrideNormalizer::new();
+    extract_archive(&mut archive, game_dir, prefix, &mut normalizer)
+        .map_err(|e| format!("Failed to extract archive: {}", e))?;
+
+    let stripped = if strip_junk { strip_junk_entries(game_dir)? } else { Vec::new() };
+
+    Ok(ExtractedFolder {
+        detected: detect_version(game_dir),
+        mods_count: count_mods(&game_dir.join("mods")),
+        stripped,
+        warnings: normalizer.warnings(),
+    })
+}
+
+/// Path prefix of the .minecraft folder inside an archive, `""` when it is the archive root
+///
+/// Descends through folders that wrap everything else, like `.minecraft/` or `backup/.minecraft/`.
+fn archive_root(names: &[String]) -> Option<String> {
+    let mut prefix = String::new();
+    loop {
+        let children: BTreeSet<&str> = names.iter()
+            .filter_map(|name| name.strip_prefix(prefix.as_str()))
+            .filter_map(|rest| rest.split('/').next())
+            .filter(|first| !first.is_empty())
+            .collect();
+
+        if children.iter().any(|child| ROOT_MARKERS.iter().any(|marker| marker.eq_ignore_ascii_case(child))) {
+            return Some(prefix);
+        }
+
+        let mut children = children.into_iter();
+        match (children.next(), children.next()) {
+            (Some(only), None) => prefix = format!("{}{}/", prefix, only),
+            _ => return None,
+        }
+    }
+}
+
+fn strip_junk_entries(game_dir: &Path) -> Result<Vec<String>, String> {
+    let mut stripped = Vec::new();
+    for entry in JUNK_ENTRIES {
+        let path = game_dir.join(entry);
+        let removed = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else if path.is_file() {
+            std::fs::remove_file(&path)
+        } else {
+            continue;
+        };
+        removed.map_err(|e| format!("Failed to remove {}: {}", entry, e))?;
+        stripped.push(entry.to_string());
+    }
+    Ok(stripped)
+}
+
+pub(crate) fn count_mods(mods_dir: &Path) -> u32 {
+    let Ok(entries) = std::fs::read_dir(mods_dir) else {
+        return 0;
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter(|entry| entry.file_name().to_string_lossy().to_lowercase().ends_with(".jar"))
+        .count() as u32
+}
+
+/// Work out the Minecraft version and loader from an extracted .minecraft folder
+///
+/// The vanilla launcher's most recently used profile wins, then any loader version,
+/// then the newest installed version.
+fn detect_version(game_dir: &Path) -> DetectedVersion {
+    let versions_dir = game_dir.join("versions");
+    let installed = installed_versions(&versions_dir);
+
+    let chosen = last_used_profile_version(game_dir)
+        .filter(|id| installed.is_empty() || installed.iter().any(|(installed_id, _)| installed_id == id))
+        .or_else(|| {
+            installed.iter()
+                .max_by(|(a_id, a_json), (b_id, b_json)| {
+                    let a_key = (parse_loader_id(a_id).is_some(), release_time(a_json));
+                    let b_key = (parse_loader_id(b_id).is_some(), release_time(b_json));
+                    a_key.cmp(&b_key)
+                })
+                .map(|(id, _)| id.clone())
+        });
+
+    let Some(id) = chosen else {
+        return DetectedVersion { needs_install: true, ..Default::default() };
+    };
+
+    let version_json = installed.iter()
+        .find(|(installed_id, _)| *installed_id == id)
+        .map(|(_, json)| json);
+    let parsed = parse_loader_id(&id);
+
+    let minecraft_version = match version_json {
+        Some(json) => json.get("inheritsFrom")
+            .or_else(|| json.get("id"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        None => match &parsed {
+            Some((_, minecraft_version)) => minecraft_version.clone(),
+            None => Some(id.clone()),
+        },
+    };
+
+    DetectedVersion {
+        minecraft_version,
+        loader: parsed.map(|(loader, _)| loader),
+        needs_install: installed.is_empty(),
+    }
+}
+
+/// Version ids in `versions/` that have a version JSON, with the parsed JSON
+fn installed_versions(versions_dir: &Path) -> Vec<(String, serde_json::Value)> {
+    let Ok(entries) = std::fs::read_dir(versions_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().to_string();
+            let content = std::fs::read_to_string(entry.path().join(format!("{}.json", id))).ok()?;
+            let json = serde_json::from_str(&content).ok()?;
+            Some((id, json))
+        })
+        .collect()
+}
+
+fn release_time(version_json: &serde_json::Value) -> &str {
+    version_json.get("releaseTime").and_then(|v| v.as_str()).unwrap_or_default()
+}
+
+/// `lastVersionId` of the most recently used vanilla launcher profile
+fn last_used_profile_version(game_dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(game_dir.join("launcher_profiles.json")).ok()?;
+    let profiles: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+    profiles.get("profiles")?.as_object()?.values()
+        .filter_map(|profile| {
+            let version = profile.get("lastVersionId")?.as_str()?;
+            let last_used = profile.get("lastUsed").and_then(|v| v.as_str()).unwrap_or_default();
+            Some((last_used, version))
+        })
+        // latest-release and latest-snapshot only name a moving target
+        .filter(|(_, version)| !version.starts_with("latest-"))
+        .max_by_key(|(last_used, _)| *last_used)
+        .map(|(_, version)| version.to_string())
+}
+
+/// Loader and Minecraft version from a loader version id
+///
+/// Handles `fabric-loader-0.15.11-1.20.1`, `quilt-loader-0.21.0-1.20.1`, `1.20.1-forge-47.2.0`,
+/// `1.7.10-Forge10.13.4.1614-1.7.10` and `neoforge-20.4.80`, which doesn't name the Minecraft version.
+fn parse_loader_id(id: &str) -> Option<(ModLoader, Option<String>)> {
+    let lower = id.to_lowercase();
+
+    if let Some(rest) = lower.strip_prefix("fabric-loader-") {
+        let (loader_version, minecraft_version) = rest.split_once('-')?;
+        return Some((ModLoader::Fabric(loader_version.to_string()), Some(minecraft_version.to_string())));
+    }
+    if let Some(rest) = lower.strip_prefix("quilt-loader-") {
+        let (loader_version, minecraft_version) = rest.split_once('-')?;
+        return Some((ModLoader::Quilt(loader_version.to_string()), Some(minecraft_version.to_string())));
+    }
+    if let Some(loader_version) = lower.strip_prefix("neoforge-") {
+        return Some((ModLoader::NeoForge(loader_version.to_string()), None));
+    }
+
+    let (minecraft_version, rest) = lower.split_once("-forge")?;
+    let loader_version = rest.trim_start_matches('-').split('-').next()?;
+    Some((ModLoader::Forge(loader_version.to_string()), Some(minecraft_version.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-import-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_version(game_dir: &Path, id: &str, json: serde_json::Value) {
+        let dir = game_dir.join("versions").join(id);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{}.json", id)), json.to_string()).unwrap();
+    }
+
+    #[test]
+    fn finds_root_below_wrapping_folders() {
+        assert_eq!(archive_root(&names(&["versions/1.20.1/1.20.1.json", "options.txt"])), Some(String::new()));
+        assert_eq!(archive_root(&names(&[".minecraft/", ".minecraft/saves/world/level.dat"])), Some(".minecraft/".to_string()));
+        assert_eq!(archive_root(&names(&["backup/.minecraft/Mods/a.jar"])), Some("backup/.minecraft/".to_string()));
+        assert_eq!(archive_root(&names(&["photos/a.png", "notes.txt"])), None);
+    }
+
+    #[test]
+    fn parses_loader_version_ids() {
+        let cases = [
+            ("fabric-loader-0.15.11-1.20.1", "fabric", "0.15.11", Some("1.20.1")),
+            ("quilt-loader-0.21.0-1.20.1", "quilt", "0.21.0", Some("1.20.1")),
+            ("1.20.1-forge-47.2.0", "forge", "47.2.0", Some("1.20.1")),
+            ("1.7.10-Forge10.13.4.1614-1.7.10", "forge", "10.13.4.1614", Some("1.7.10")),
+            ("neoforge-20.4.80", "neoforge", "20.4.80", None),
+        ];
+
+        for (id, name, version, minecraft_version) in cases {
+            let (loader, parsed_minecraft) = parse_loader_id(id).unwrap();
+            assert_eq!((loader.name(), loader.version()), (name, version), "{}", id);
+            assert_eq!(parsed_minecraft.as_deref(), minecraft_version, "{}", id);
+        }
+        assert!(parse_loader_id("1.20.1").is_none());
+    }
+
+    #[test]
+    fn prefers_loader_version_and_counts_mods() {
+        let dir = temp_dir();
+        write_version(&dir, "1.20.1", serde_json::json!({ "id": "1.20.1", "releaseTime": "2023-06-12T13:25:51+00:00" }));
+        write_version(&dir, "1.21", serde_json::json!({ "id": "1.21", "releaseTime": "2024-06-13T08:24:03+00:00" }));
+        write_version(&dir, "fabric-loader-0.15.11-1.20.1", serde_json::json!({
+            "id": "fabric-loader-0.15.11-1.20.1",
+            "inheritsFrom": "1.20.1",
+        }));
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::write(dir.join("mods").join("sodium.jar"), b"jar").unwrap();
+        std::fs::write(dir.join("mods").join("readme.txt"), b"txt").unwrap();
+
+        let detected = detect_version(&dir);
+        assert_eq!(detected.minecraft_version.as_deref(), Some("1.20.1"));
+        assert_eq!(detected.loader.map(|l| l.name().to_string()).as_deref(), Some("fabric"));
+        assert!(!detected.needs_install);
+        assert_eq!(count_mods(&dir.join("mods")), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_versions_needs_install_with_profile_version() {
+        let dir = temp_dir();
+        std::fs::write(dir.join("launcher_profiles.json"), serde_json::json!({
+            "profiles": {
+                "old": { "lastVersionId": "1.19.4", "lastUsed": "2023-01-01T00:00:00.000Z" },
+                "latest": { "lastVersionId": "latest-release", "lastUsed": "2024-05-01T00:00:00.000Z" },
+                "recent": { "lastVersionId": "1.20.1-forge-47.2.0", "lastUsed": "2024-02-01T00:00:00.000Z" },
+            }
+        }).to_string()).unwrap();
+
+        let detected = detect_version(&dir);
+        assert!(detected.needs_install);
+        assert_eq!(detected.minecraft_version.as_deref(), Some("1.20.1"));
+        assert_eq!(detected.loader.map(|l| l.name().to_string()).as_deref(), Some("forge"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,11 +1,19 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
-use tauri::{command, AppHandle, Emitter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{command, AppHandle, Emitter, Manager, State};
 use tokio::fs;
 use reqwest;
 use serde_json;
 
-use crate::storage::{StorageManager, InstanceMetadata};
-use crate::minecraft::{MinecraftInstance, AuthInfo};
+use crate::mods::api::common::ApiUtils;
+use crate::storage::{StorageState, InstanceMetadata};
+use crate::minecraft::{MinecraftInstance, AuthInfo, LaunchResult, readiness, relocate, servers, timestamps};
+use crate::minecraft::relocate::PathRepairPlan;
+use crate::minecraft::integrity::RepairAction;
+use crate::minecraft::platform::Platform;
+use crate::minecraft::servers::ServerAddress;
+use crate::minecraft::sessions::{PlaySessionsState, PlaytimeStats, RunningInstance};
 use super::versions::download_minecraft_assets_with_progress;
 
 /// Create a new Minecraft instance
@@ -15,6 +23,7 @@ pub async fn create_instance(
     version: String,
     game_dir: String,
     app_handle: AppHandle,
+    storage: State<'_, StorageState>,
 ) -> Result<(), String> {
     let instance = InstanceMetadata {
         id: uuid::Uuid::new_v4().to_string(),
@@ -36,10 +45,23 @@ pub async fn create_instance(
         tags: vec![],
         resolved_java_version: None,
         java_analysis_date: None,
+        log_cleanup: None,
+        game_args: None,
+        pre_launch_command: None,
+        post_exit_command: None,
+        env_vars: None,
+        clear_inherited_env: false,
+        window_width: None,
+        window_height: None,
+        fullscreen: None,
+        jvm_args_preset: None,
+        stability: Default::default(),
+        recent_servers: Vec::new(),
+        health: None,
     };
     
     // Save the instance first
-    save_instance(instance.clone(), app_handle.clone()).await?;
+    save_instance(instance.clone(), app_handle.clone(), storage).await?;
     
     // Download assets for the new instance
     println!("📦 Downloading assets for new instance '{}'...", name);
@@ -57,12 +79,34 @@ pub async fn create_instance(
     Ok(())
 }
 
+/// Emit `instances_updated` once per save window rather than once per change
+///
+/// The event goes out after the window so listeners reload after the batched save.
+pub(crate) fn notify_instances_updated(app_handle: &AppHandle) {
+    static PENDING: AtomicBool = AtomicBool::new(false);
+    if PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(crate::storage::SAVE_COALESCE_WINDOW).await;
+        PENDING.store(false, Ordering::SeqCst);
+        let _ = app_handle.emit("instances_updated", ());
+        crate::jumplist::refresh(&app_handle).await;
+        crate::search::refresh().await;
+    });
+}
+
 /// Launch Minecraft using the modular system
 #[command]
 pub async fn launch_minecraft(
     instance: MinecraftInstance,
     java_path: Option<String>,
     memory: u32,
+    account_uuid: Option<String>,
+    app_handle: AppHandle,
+    sessions: State<'_, PlaySessionsState>,
 ) -> Result<(), String> {
     // Use provided java_path or let the system choose
     let mut launch_instance = instance;
@@ -70,20 +114,47 @@ pub async fn launch_minecraft(
         launch_instance.java_path = Some(java);
     }
     
-    let auth_info = get_auth_info().await.unwrap_or_default();
+    ensure_not_running(&launch_instance.id, &sessions).await?;
+
+    let auth_info = launch_auth_info(account_uuid.as_deref()).await?;
     println!("🔐 Using authentication: {} ({})", auth_info.username, auth_info.user_type);
     
-    match crate::minecraft::launch_minecraft(&launch_instance, Some(auth_info), memory).await {
-        Ok(result) => {
-            if result.success {
-                println!("✓ Minecraft launched successfully with PID: {}", result.process_id);
-                Ok(())
-            } else {
-                Err(result.error.unwrap_or("Unknown launch error".to_string()))
-            }
-        }
-        Err(e) => Err(e),
+    let result = crate::minecraft::launch_minecraft(&launch_instance, Some(auth_info), memory, &app_handle).await?;
+    track_launch(result, &launch_instance, &app_handle, &sessions)
+}
+
+/// Refuse to launch an instance that is already running, unless settings allow it
+pub(crate) async fn ensure_not_running(instance_id: &str, sessions: &PlaySessionsState) -> Result<(), String> {
+    if !sessions.is_running(instance_id) {
+        return Ok(());
+    }
+    let allow_multiple = match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().allow_multiple_instances,
+        Err(_) => false,
+    };
+    if allow_multiple {
+        return Ok(());
     }
+    Err("This instance is already running. Close it first, or allow multiple launches in the settings".to_string())
+}
+
+/// Report a launch result, tracking the game as a play session once it started
+pub(crate) fn track_launch(
+    mut result: LaunchResult,
+    instance: &MinecraftInstance,
+    app_handle: &AppHandle,
+    sessions: &PlaySessionsState,
+) -> Result<(), String> {
+    if !result.success {
+        return Err(result.error.unwrap_or("Unknown launch error".to_string()));
+    }
+
+    println!("✓ Minecraft launched successfully with PID: {}", result.process_id);
+    if let Some(mut process) = result.process.take() {
+        crate::minecraft::game_log::capture(app_handle, &instance.id, &instance.game_dir, &mut process);
+        sessions.start(app_handle, &instance.id, instance.game_dir.clone(), process);
+    }
+    Ok(())
 }
 
 /// Main launch command that matches the original signature
@@ -95,13 +166,43 @@ pub async fn launch_instance(
     java_path: String,
     memory: u32,
     jvm_args: Vec<String>,
+    server_address: Option<String>,
+    account_uuid: Option<String>,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+    sessions: State<'_, PlaySessionsState>,
 ) -> Result<(), String> {
     println!("🚀 Launching Minecraft {} using modular system", version);
+    ensure_not_running(&instance_id, &sessions).await?;
+    let server = server_address.as_deref()
+        .filter(|address| !address.trim().is_empty())
+        .map(ServerAddress::parse)
+        .transpose()?;
     
     // Debug: Check Java requirements for version
     let java_version = crate::minecraft::versions::get_required_java_version(&version);
     println!("📋 Minecraft {} requires Java {}", version, java_version);
     
+    // Custom game arguments, environment variables and window options are only stored with the instance
+    let storage = storage.read().await;
+    let stored = storage.get_instance(&instance_id);
+    let mut game_args = stored.and_then(|i| i.game_args.clone());
+    let env_vars = stored.and_then(|i| i.env_vars.clone());
+    let clear_inherited_env = stored.is_some_and(|i| i.clear_inherited_env);
+    let window_width = stored.and_then(|i| i.window_width);
+    let window_height = stored.and_then(|i| i.window_height);
+    let fullscreen = stored.and_then(|i| i.fullscreen);
+    let jvm_args_preset = stored.and_then(|i| i.jvm_args_preset);
+    // Edits made just before launching should not be lost if the game takes the launcher down
+    storage.flush().await
+        .map_err(|e| format!("Failed to save launcher config: {}", e))?;
+    drop(storage);
+
+    if let Some(server) = &server {
+        println!("🌐 Joining {} once the game has started", server);
+        game_args.get_or_insert_with(Vec::new).extend(servers::join_args(&version, server));
+    }
+
     // Create instance from parameters
     let instance = MinecraftInstance {
         id: instance_id,
@@ -121,48 +222,180 @@ pub async fn launch_instance(
         external_launcher: None,
         resolved_java_version: None,
         java_analysis_date: None,
+        log_cleanup: None,
+        game_args,
+        pre_launch_command: None,
+        post_exit_command: None,
+        env_vars,
+        clear_inherited_env,
+        window_width,
+        window_height,
+        fullscreen,
+        jvm_args_preset,
+        created_at: None,
+        size_mb: None,
+        ready: true,
+        not_ready_reason: None,
+        unstable: false,
+        health: None,
     };
     
     // Try to get auth info from storage
-    let auth_info = get_auth_info().await.unwrap_or_default();
+    let auth_info = launch_auth_info(account_uuid.as_deref()).await?;
     println!("🔐 Using authentication: {} ({})", auth_info.username, auth_info.user_type);
     
     // Launch using the modular system
-    match crate::minecraft::launch_minecraft(&instance, Some(auth_info), memory).await {
-        Ok(result) => {
-            if result.success {
-                println!("✓ Minecraft launched successfully with PID: {}", result.process_id);
-                Ok(())
-            } else {
-                Err(result.error.unwrap_or("Unknown launch error".to_string()))
-            }
+    let result = crate::minecraft::launch_minecraft(&instance, Some(auth_info), memory, &app_handle).await?;
+    track_launch(result, &instance, &app_handle, &sessions)?;
+    if let Some(server) = &server {
+        super::servers::record_join(&instance.id, server, &app_handle).await;
+    }
+    Ok(())
+}
+
+/// Orderings accepted by `load_instances`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceSortKey {
+    Name,
+    LastPlayed,
+    CreatedAt,
+    Size,
+    PlayTime,
+}
+
+/// Sort instances for display
+///
+/// Names sort A-Z ignoring case, every other key puts the largest or most recent
+/// value first. Instances missing the value go last, ties fall back to name then id.
+pub fn sort_instances(instances: &mut [MinecraftInstance], key: InstanceSortKey) {
+    use std::cmp::Ordering;
+
+    fn descending<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => b.cmp(&a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
         }
-        Err(e) => Err(e),
     }
+
+    let by_name = |a: &MinecraftInstance, b: &MinecraftInstance| {
+        a.name.to_lowercase().cmp(&b.name.to_lowercase())
+            .then_with(|| a.id.cmp(&b.id))
+    };
+
+    instances.sort_by(|a, b| {
+        let primary = match key {
+            InstanceSortKey::Name => Ordering::Equal,
+            InstanceSortKey::LastPlayed => descending(a.last_played, b.last_played),
+            InstanceSortKey::CreatedAt => descending(a.created_at, b.created_at),
+            InstanceSortKey::Size => descending(a.size_mb, b.size_mb),
+            InstanceSortKey::PlayTime => descending(Some(a.total_play_time), Some(b.total_play_time)),
+        };
+        primary.then_with(|| by_name(a, b))
+    });
 }
 
-/// Load instances from storage
+/// Play time per instance and in total, in seconds
 #[command]
-pub async fn load_instances() -> Result<Vec<MinecraftInstance>, String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+pub async fn get_playtime_stats(
+    storage: State<'_, StorageState>,
+    sessions: State<'_, PlaySessionsState>,
+) -> Result<PlaytimeStats, String> {
+    let storage = storage.read().await;
+    Ok(sessions.stats(&storage.get_all_instances()))
+}
+
+/// Session outcome ratios and the most frequent crash of an instance
+#[command]
+pub async fn get_instance_stability(
+    instance_id: String,
+    storage: State<'_, StorageState>,
+) -> Result<crate::minecraft::crashes::StabilitySummary, String> {
+    storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.stability.summary())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))
+}
+
+/// Result of the last integrity check of an instance, None until the sweep reached it
+#[command]
+pub async fn get_instance_health(
+    instance_id: String,
+    storage: State<'_, StorageState>,
+) -> Result<Option<crate::minecraft::integrity::InstanceHealth>, String> {
+    storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.health.clone())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))
+}
+
+/// The newest crash report of an instance, None if it never crashed
+#[command]
+pub async fn get_latest_crash_report(
+    instance_id: String,
+    storage: State<'_, StorageState>,
+) -> Result<Option<crate::minecraft::crashes::CrashReport>, String> {
+    let game_dir = storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.game_dir.clone())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    Ok(crate::minecraft::crashes::latest_crash_report(&game_dir).await)
+}
+
+/// The last lines of game output the launcher logged for an instance, oldest first
+#[command]
+pub async fn get_recent_game_log(
+    instance_id: String,
+    max_lines: Option<usize>,
+    storage: State<'_, StorageState>,
+) -> Result<Vec<crate::minecraft::game_log::GameLogLine>, String> {
+    let game_dir = storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.game_dir.clone())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    Ok(crate::minecraft::game_log::recent_lines(&instance_id, &game_dir, max_lines.unwrap_or(1000).min(10_000)).await)
+}
+
+/// Force-stop every game running from an instance, returning how many were stopped
+#[command]
+pub async fn kill_instance(instance_id: String, sessions: State<'_, PlaySessionsState>) -> Result<usize, String> {
+    sessions.kill(&instance_id).await
+}
+
+/// Games started by the launcher that are still running
+#[command]
+pub async fn get_running_instances(sessions: State<'_, PlaySessionsState>) -> Result<Vec<RunningInstance>, String> {
+    Ok(sessions.running_instances())
+}
+
+/// Load instances from storage, optionally sorted
+#[command]
+pub async fn load_instances(sort_by: Option<InstanceSortKey>, storage: State<'_, StorageState>) -> Result<Vec<MinecraftInstance>, String> {
+    let storage = storage.read().await;
     
-    let instances: Vec<MinecraftInstance> = storage.get_all_instances()
+    let mut instances: Vec<MinecraftInstance> = storage.get_all_instances()
         .into_iter()
         .cloned()
         .map(|metadata| metadata.into())
         .collect();
+
+    if let Some(key) = sort_by {
+        sort_instances(&mut instances, key);
+    }
     
     Ok(instances)
 }
 
 /// Scan instances directory and import orphaned instances
 #[command]
-pub async fn import_orphaned_instances() -> Result<Vec<String>, String> {
+pub async fn import_orphaned_instances(storage: State<'_, StorageState>) -> Result<Vec<String>, String> {
     use std::fs;
     
-    let mut storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+    let mut storage = storage.write().await;
     
     let instances_dir = storage.get_settings().instances_dir.clone();
     let mut imported = Vec::new();
@@ -235,6 +468,19 @@ pub async fn import_orphaned_instances() -> Result<Vec<String>, String> {
                 tags: vec!["imported".to_string()],
                 resolved_java_version: None,
                 java_analysis_date: None,
+                log_cleanup: None,
+                game_args: None,
+                pre_launch_command: None,
+                post_exit_command: None,
+                env_vars: None,
+                clear_inherited_env: false,
+                window_width: None,
+                window_height: None,
+                fullscreen: None,
+                jvm_args_preset: None,
+                stability: Default::default(),
+                recent_servers: Vec::new(),
+                health: None,
             };
             
             // Add to storage
@@ -251,49 +497,532 @@ pub async fn import_orphaned_instances() -> Result<Vec<String>, String> {
 
 /// Save instance to storage
 #[command]
-pub async fn save_instance(instance: InstanceMetadata, app_handle: AppHandle) -> Result<(), String> {
-    let mut storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
-    storage.add_instance(instance).await
+pub async fn save_instance(
+    instance: InstanceMetadata,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<(), String> {
+    storage.write().await.add_instance(instance).await
         .map_err(|e| format!("Failed to save instance: {}", e))?;
     
     // No need to call save() again as add_instance() already calls it
     
     // Emit event to notify frontend that instances have been updated
-    let _ = app_handle.emit("instances_updated", ());
+    notify_instances_updated(&app_handle);
     
     Ok(())
 }
 
 /// Delete instance
 #[command]
-pub async fn delete_instance(instance_id: String, app_handle: AppHandle) -> Result<(), String> {
-    let mut storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
-    storage.remove_instance(&instance_id).await
+pub async fn delete_instance(
+    instance_id: String,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<(), String> {
+    storage.write().await.remove_instance(&instance_id).await
         .map_err(|e| format!("Failed to remove instance: {}", e))?;
     
     // No need to call save() again as remove_instance() already calls it
     
     // Emit event to notify frontend that instances have been updated
-    let _ = app_handle.emit("instances_updated", ());
+    notify_instances_updated(&app_handle);
     
     Ok(())
 }
 
+/// Resolve two instances sharing a game directory by merging `remove_id` into `keep_id`
+#[command]
+pub async fn resolve_duplicate_instances(
+    keep_id: String,
+    remove_id: String,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<InstanceMetadata, String> {
+    let kept = storage.write().await.merge_duplicate_instance(&keep_id, &remove_id).await
+        .map_err(|e| format!("Failed to resolve duplicate instances: {}", e))?;
+
+    println!("🔗 Merged duplicate instance {} into '{}'", remove_id, kept.name);
+    notify_instances_updated(&app_handle);
+
+    Ok(kept)
+}
+
+/// Find new locations for instances whose game directory is missing
+///
+/// With `dry_run` only the proposed mapping is returned. Otherwise every repair,
+/// or just those for `instance_ids`, is saved and the applied plan returned.
+#[command]
+pub async fn repair_instance_paths(
+    dry_run: bool,
+    instance_ids: Option<Vec<String>>,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<PathRepairPlan, String> {
+    let mut storage = storage.write().await;
+    let instances: Vec<InstanceMetadata> = storage.get_all_instances().into_iter().cloned().collect();
+    let mut plan = relocate::plan(&instances, &relocate::search_roots(storage.get_settings(), &instances));
+    if let Some(ids) = &instance_ids {
+        plan.repairs.retain(|repair| ids.contains(&repair.instance_id));
+        plan.unresolved.retain(|missing| ids.contains(&missing.instance_id));
+    }
+    if dry_run || plan.repairs.is_empty() {
+        return Ok(plan);
+    }
+
+    for repair in &plan.repairs {
+        let Some(mut instance) = storage.get_instance(&repair.instance_id).cloned() else {
+            continue;
+        };
+        instance.game_dir = repair.new_path.clone();
+        storage.update_instance(instance).await
+            .map_err(|e| format!("Failed to repair path of '{}': {}", repair.name, e))?;
+        println!("🔧 Moved '{}' from {} to {}", repair.name, repair.old_path.display(), repair.new_path.display());
+    }
+    notify_instances_updated(&app_handle);
+
+    Ok(plan)
+}
+
+/// Top-level folders a duplicated instance starts without
+const DUPLICATE_SKIPPED_ENTRIES: &[&str] = &["logs", "crash-reports"];
+
+/// Copy an instance into a sibling directory as a new instance
+///
+/// Logs, crash reports and play time are not carried over. Copy progress is sent
+/// as `install_progress` for the new instance, which is registered before copying
+/// starts and removed again if the copy fails. Returns the new instance id.
+#[command]
+pub async fn duplicate_instance(
+    instance_id: String,
+    new_name: String,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<String, String> {
+    let name = new_name.trim().to_string();
+    if name.is_empty() {
+        return Err("Instance name cannot be empty".to_string());
+    }
+
+    let source = storage.read().await
+        .get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    let parent = source.game_dir.parent()
+        .ok_or_else(|| format!("Instance directory has no parent: {}", source.game_dir.display()))?;
+    let new_dir = parent.join(ApiUtils::sanitize_filename(&name));
+
+    // create_dir fails if the directory exists, so nothing is ever overwritten
+    fs::create_dir(&new_dir).await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => format!("Instance directory already exists: {}", new_dir.display()),
+            _ => format!("Failed to create instance directory: {}", e),
+        })?;
+
+    println!("📋 Duplicating instance '{}' as '{}'", source.name, name);
+
+    let new_id = uuid::Uuid::new_v4().to_string();
+    let metadata = InstanceMetadata {
+        id: new_id.clone(),
+        name: name.clone(),
+        game_dir: new_dir.clone(),
+        last_played: None,
+        total_play_time: 0,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        size_mb: None,
+        stability: Default::default(),
+        recent_servers: Vec::new(),
+        health: None,
+        ..source.clone()
+    };
+    if let Err(e) = storage.write().await.add_instance(metadata.clone()).await {
+        let _ = fs::remove_dir_all(&new_dir).await;
+        return Err(format!("Failed to save instance: {}", e));
+    }
+    notify_instances_updated(&app_handle);
+
+    let copied = copy_instance_dir(&source.game_dir, &new_dir, |current, copied, total| {
+        let progress = (95 * copied).checked_div(total).unwrap_or(0);
+        let _ = app_handle.emit("install_progress", serde_json::json!({
+            "instanceId": new_id,
+            "stage": "copying",
+            "progress": progress,
+            "currentFile": current,
+            "bytesDownloaded": copied,
+            "totalBytes": total
+        }));
+    }).await;
+
+    let copied = match copied {
+        Ok(copied) => copied,
+        Err(e) => {
+            // remove_instance also deletes the partial copy
+            let _ = storage.write().await.remove_instance(&new_id).await;
+            notify_instances_updated(&app_handle);
+            let _ = app_handle.emit("install_complete", serde_json::json!({
+                "instanceId": new_id,
+                "success": false,
+                "error": e
+            }));
+            return Err(e);
+        }
+    };
+
+    let metadata = InstanceMetadata {
+        size_mb: Some(copied / 1024 / 1024),
+        ..metadata
+    };
+    storage.write().await.update_instance(metadata).await
+        .map_err(|e| format!("Failed to save instance: {}", e))?;
+    readiness::invalidate(&new_dir);
+    notify_instances_updated(&app_handle);
+
+    let _ = app_handle.emit("install_complete", serde_json::json!({
+        "instanceId": new_id,
+        "success": true,
+        "version": source.version
+    }));
+
+    println!("✅ Duplicated '{}' as '{}'", source.name, name);
+    Ok(new_id)
+}
+
+/// Copy an instance folder except the entries a duplicate starts without
+///
+/// `progress` gets the entry being copied, bytes copied so far and the total.
+/// Returns the number of bytes copied.
+async fn copy_instance_dir(
+    source_dir: &std::path::Path,
+    dest_dir: &std::path::Path,
+    progress: impl Fn(&str, u64, u64),
+) -> Result<u64, String> {
+    let mut entries = Vec::new();
+    let mut read_dir = fs::read_dir(source_dir).await
+        .map_err(|e| format!("Failed to read instance directory: {}", e))?;
+    while let Some(entry) = read_dir.next_entry().await
+        .map_err(|e| format!("Failed to read instance directory: {}", e))? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !DUPLICATE_SKIPPED_ENTRIES.contains(&name.as_str()) {
+            entries.push((name, entry.path()));
+        }
+    }
+
+    // Sizes are summed up front so progress can be reported in bytes
+    let sized = tokio::task::spawn_blocking(move || {
+        entries.into_iter()
+            .map(|(name, path)| {
+                let size = walkdir::WalkDir::new(&path).into_iter()
+                    .filter_map(|entry| entry.ok())
+                    .filter_map(|entry| entry.metadata().ok())
+                    .filter(|metadata| metadata.is_file())
+                    .map(|metadata| metadata.len())
+                    .sum::<u64>();
+                (name, path, size)
+            })
+            .collect::<Vec<_>>()
+    }).await.map_err(|e| format!("Copy task failed: {}", e))?;
+
+    let total: u64 = sized.iter().map(|(_, _, size)| size).sum();
+    let mut copied = 0;
+    for (name, src_path, size) in sized {
+        progress(&name, copied, total);
+        let dst_path = dest_dir.join(&name);
+        if src_path.is_dir() {
+            copy_directory_recursive(&src_path, &dst_path, true).await?;
+        } else {
+            fs::copy(&src_path, &dst_path).await
+                .map_err(|e| format!("Failed to copy {}: {}", name, e))?;
+            copy_times(&src_path, &dst_path)?;
+        }
+        copied += size;
+    }
+    progress("", copied, total);
+
+    Ok(copied)
+}
+
+/// How recently latest.log must have been written for an instance to count as running
+const RUNNING_LOG_WINDOW: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Error from `rename_instance`, sent to the UI tagged by `kind`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InstanceRenameError {
+    /// The game is running or another program holds the folder open
+    InUse { message: String },
+    /// A folder with the new name is already there
+    AlreadyExists { message: String },
+    Failed { message: String },
+}
+
+impl InstanceRenameError {
+    pub fn message(&self) -> &str {
+        match self {
+            InstanceRenameError::InUse { message }
+            | InstanceRenameError::AlreadyExists { message }
+            | InstanceRenameError::Failed { message } => message,
+        }
+    }
+}
+
+impl std::fmt::Display for InstanceRenameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl From<String> for InstanceRenameError {
+    fn from(message: String) -> Self {
+        InstanceRenameError::Failed { message }
+    }
+}
+
+/// Rename an instance, and with `rename_directory` its folder on disk as well
+///
+/// The folder is only renamed within its parent. That is refused when the target
+/// exists or the game appears to be running in the instance. Returns the renamed instance.
+#[command]
+pub async fn rename_instance(
+    instance_id: String,
+    new_name: String,
+    rename_directory: bool,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+    sessions: State<'_, PlaySessionsState>,
+) -> Result<MinecraftInstance, InstanceRenameError> {
+    let name = new_name.trim().to_string();
+    if name.is_empty() {
+        return Err("Instance name cannot be empty".to_string().into());
+    }
+
+    // Held throughout so nothing else saves the instance while its folder moves
+    let mut storage = storage.write().await;
+    let mut instance = storage.get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    let old_dir = instance.game_dir.clone();
+
+    if rename_directory {
+        let parent = old_dir.parent()
+            .ok_or_else(|| format!("Instance directory has no parent: {}", old_dir.display()))?;
+        let new_dir = parent.join(ApiUtils::sanitize_filename(&name));
+        if new_dir != old_dir {
+            if sessions.is_running(&instance_id) {
+                return Err(InstanceRenameError::InUse {
+                    message: "Minecraft is running in this instance. Close it and try again".to_string(),
+                });
+            }
+            move_instance_dir(&old_dir, &new_dir).await?;
+            readiness::invalidate(&old_dir);
+            instance.game_dir = new_dir;
+        }
+    }
+
+    let old_name = std::mem::replace(&mut instance.name, name.clone());
+    let new_dir = instance.game_dir.clone();
+    if let Err(e) = storage.update_instance(instance.clone()).await {
+        // Put the folder back so the saved metadata still points at it
+        if new_dir != old_dir {
+            let _ = fs::rename(&new_dir, &old_dir).await;
+        }
+        return Err(format!("Failed to save instance: {}", e).into());
+    }
+    drop(storage);
+
+    println!("✏️  Renamed instance '{}' to '{}'", old_name, name);
+    notify_instances_updated(&app_handle);
+    Ok(MinecraftInstance::from(instance))
+}
+
+/// Rename an instance folder, refusing when it's in use or the target exists
+async fn move_instance_dir(old_dir: &std::path::Path, new_dir: &std::path::Path) -> Result<(), InstanceRenameError> {
+    // A case-only rename on a case-insensitive file system finds the folder itself
+    let same_folder = match (fs::canonicalize(old_dir).await, fs::canonicalize(new_dir).await) {
+        (Ok(old), Ok(new)) => old == new,
+        _ => false,
+    };
+    if new_dir.exists() && !same_folder {
+        return Err(InstanceRenameError::AlreadyExists {
+            message: format!("A folder named {} already exists", new_dir.display()),
+        });
+    }
+    if appears_running(old_dir).await {
+        return Err(InstanceRenameError::InUse {
+            message: "The instance appears to be running. Close Minecraft and try again".to_string(),
+        });
+    }
+
+    fs::rename(old_dir, new_dir).await.map_err(|e| {
+        if is_locked_error(&e) {
+            InstanceRenameError::InUse {
+                message: "The instance folder is in use by another program. Close Minecraft or any open files and try again".to_string(),
+            }
+        } else {
+            InstanceRenameError::Failed { message: format!("Failed to rename instance directory: {}", e) }
+        }
+    })
+}
+
+/// Whether the game seems to be running in `game_dir`
+///
+/// Catches games the launcher isn't tracking, such as ones started before it was
+/// restarted, by looking for a latest.log that was written recently and doesn't
+/// end with Minecraft's shutdown message.
+async fn appears_running(game_dir: &std::path::Path) -> bool {
+    let log_path = game_dir.join("logs").join("latest.log");
+    let recent = fs::metadata(&log_path).await
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < RUNNING_LOG_WINDOW);
+    if !recent {
+        return false;
+    }
+
+    let content = fs::read(&log_path).await.unwrap_or_default();
+    let content = String::from_utf8_lossy(&content);
+    let stopped = content.lines().rev().take(20).any(|line| line.contains("Stopping!"));
+    !stopped
+}
+
+/// Whether a rename failed because a process holds files in the folder open
+#[cfg(windows)]
+fn is_locked_error(e: &std::io::Error) -> bool {
+    // ERROR_ACCESS_DENIED and ERROR_SHARING_VIOLATION
+    matches!(e.raw_os_error(), Some(5) | Some(32))
+}
+
+#[cfg(not(windows))]
+fn is_locked_error(e: &std::io::Error) -> bool {
+    e.kind() == std::io::ErrorKind::ResourceBusy
+}
+
 /// Update instance
 #[command]
-pub async fn update_instance(instance: InstanceMetadata) -> Result<(), String> {
-    let mut storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
+pub async fn update_instance(instance: InstanceMetadata, storage: State<'_, StorageState>) -> Result<(), String> {
+    crate::minecraft::environment::validate(instance.env_vars.as_ref())?;
+    crate::minecraft::window::validate(instance.window_width, instance.window_height)?;
+    // update_instance() saves the config itself
+    storage.write().await.update_instance(instance).await
+        .map_err(|e| format!("Failed to update instance: {}", e))
+}
+
+/// Set or clear the commands run before launching an instance and after it exits
+#[command]
+pub async fn update_instance_hooks(
+    instance_id: String,
+    pre_launch_command: Option<String>,
+    post_exit_command: Option<String>,
+    storage: State<'_, StorageState>,
+) -> Result<(), String> {
+    let mut storage = storage.write().await;
+    let mut instance = storage.get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    let trimmed = |command: Option<String>| command.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    instance.pre_launch_command = trimmed(pre_launch_command);
+    instance.post_exit_command = trimmed(post_exit_command);
     storage.update_instance(instance).await
-        .map_err(|e| format!("Failed to update instance: {}", e))?;
-    
-    storage.save().await
-        .map_err(|e| format!("Failed to save storage: {}", e))
+        .map_err(|e| format!("Failed to update instance: {}", e))
+}
+
+/// Set the environment variables an instance launches with
+///
+/// Empty names are dropped. With `clear_inherited_env` the game gets only these
+/// variables, none of the launcher's environment.
+#[command]
+pub async fn update_instance_environment(
+    instance_id: String,
+    env_vars: HashMap<String, String>,
+    clear_inherited_env: bool,
+    storage: State<'_, StorageState>,
+) -> Result<(), String> {
+    let env_vars: HashMap<String, String> = env_vars.into_iter()
+        .map(|(name, value)| (name.trim().to_string(), value))
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    crate::minecraft::environment::validate(Some(&env_vars))?;
+
+    let mut storage = storage.write().await;
+    let mut instance = storage.get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    instance.env_vars = if env_vars.is_empty() { None } else { Some(env_vars) };
+    instance.clear_inherited_env = clear_inherited_env;
+    storage.update_instance(instance).await
+        .map_err(|e| format!("Failed to update instance: {}", e))
+}
+
+/// Set an instance's window size and fullscreen overrides
+///
+/// None falls back to the launcher settings, a size of 0 lets the game decide.
+#[command]
+pub async fn update_instance_window(
+    instance_id: String,
+    window_width: Option<u32>,
+    window_height: Option<u32>,
+    fullscreen: Option<bool>,
+    storage: State<'_, StorageState>,
+) -> Result<(), String> {
+    crate::minecraft::window::validate(window_width, window_height)?;
+
+    let mut storage = storage.write().await;
+    let mut instance = storage.get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    instance.window_width = window_width;
+    instance.window_height = window_height;
+    instance.fullscreen = fullscreen;
+    storage.update_instance(instance).await
+        .map_err(|e| format!("Failed to update instance: {}", e))
+}
+
+/// Set an instance's JVM argument preset, None follows the launcher setting
+#[command]
+pub async fn update_instance_jvm_preset(
+    instance_id: String,
+    preset: Option<crate::minecraft::jvm::JvmArgsPreset>,
+    storage: State<'_, StorageState>,
+) -> Result<(), String> {
+    let mut storage = storage.write().await;
+    let mut instance = storage.get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    instance.jvm_args_preset = preset;
+    storage.update_instance(instance).await
+        .map_err(|e| format!("Failed to update instance: {}", e))
+}
+
+/// JVM argument presets with the flags they expand to for `memory` MB
+///
+/// Without `memory` the default memory from the settings is used.
+#[command]
+pub async fn get_jvm_presets(
+    memory: Option<u32>,
+    storage: State<'_, StorageState>,
+) -> Result<Vec<crate::minecraft::jvm::JvmPresetInfo>, String> {
+    let memory = match memory {
+        Some(memory) => memory,
+        None => storage.read().await.get_settings().default_memory,
+    };
+    Ok(crate::minecraft::jvm::JvmArgsPreset::ALL.iter().map(|preset| preset.info(memory)).collect())
+}
+
+/// Check `memory` MB and the instance's JVM arguments against the memory of this computer
+#[command]
+pub async fn validate_launch_config(
+    instance_id: String,
+    memory: u32,
+    storage: State<'_, StorageState>,
+) -> Result<crate::minecraft::memory::LaunchValidation, String> {
+    let jvm_args = storage.read().await
+        .get_instance(&instance_id)
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?
+        .jvm_args
+        .clone()
+        .unwrap_or_default();
+    Ok(crate::minecraft::memory::validate(memory, &jvm_args, super::system_memory().ok()))
 }
 
 /// Install Minecraft version
@@ -309,6 +1038,116 @@ pub async fn install_minecraft_version(
     let instance_dir = game_path.join(&instance_name);
     
     println!("🚀 Installing Minecraft {} for instance '{}'", version_id, instance_name);
+    install_version_files(&version_id, &instance_dir, &instance_id, &app_handle).await?;
+    
+    // Save the instance to storage so it persists
+    let instance_metadata = InstanceMetadata {
+        id: instance_id.clone(),
+        name: instance_name.clone(),
+        version: version_id.clone(),
+        modpack: None,
+        modpack_version: None,
+        game_dir: instance_dir,
+        java_path: None,
+        jvm_args: None,
+        last_played: None,
+        total_play_time: 0,
+        icon: None,
+        is_modded: false,
+        mods_count: 0,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        size_mb: None,
+        description: None,
+        tags: vec![],
+        resolved_java_version: None,
+        java_analysis_date: None,
+        log_cleanup: None,
+        game_args: None,
+        pre_launch_command: None,
+        post_exit_command: None,
+        env_vars: None,
+        clear_inherited_env: false,
+        window_width: None,
+        window_height: None,
+        fullscreen: None,
+        jvm_args_preset: None,
+        stability: Default::default(),
+        recent_servers: Vec::new(),
+        health: None,
+    };
+    
+    // Save to storage
+    save_instance(instance_metadata, app_handle.clone(), app_handle.state()).await?;
+    
+    let _ = app_handle.emit("install_complete", serde_json::json!({
+        "instanceId": instance_id,
+        "success": true,
+        "version": version_id
+    }));
+    
+    println!("✅ Minecraft {} installation completed for '{}'", version_id, instance_name);
+    Ok(())
+}
+
+/// Download whatever an instance is missing so it can launch
+///
+/// Returns the instance with its refreshed readiness.
+#[command]
+pub async fn prepare_instance(
+    instance_id: String,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+) -> Result<MinecraftInstance, String> {
+    let metadata = storage.read().await
+        .get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    
+    readiness::invalidate(&metadata.game_dir);
+    let flagged = metadata.health.as_ref().is_some_and(|health| health.needs(RepairAction::PrepareInstance));
+    let reason = match readiness::check(&metadata.game_dir, &metadata.version) {
+        Err(reason) => Some(reason),
+        Ok(()) if flagged => Some("its integrity check found missing files".to_string()),
+        Ok(()) => None,
+    };
+    if let Some(reason) = reason {
+        println!("🔧 Preparing '{}': {}", metadata.name, reason);
+        install_version_files(&metadata.version, &metadata.game_dir, &instance_id, &app_handle).await?;
+    }
+    if flagged {
+        crate::minecraft::integrity::refresh(&instance_id, &app_handle).await;
+    }
+    
+    let instance = MinecraftInstance::from(metadata);
+    if let Some(reason) = &instance.not_ready_reason {
+        return Err(format!("Instance is still not ready to launch: {}", reason));
+    }
+    
+    println!("✅ Instance '{}' is ready to launch", instance.name);
+    notify_instances_updated(&app_handle);
+    Ok(instance)
+}
+
+/// Fetch a version's JSON using Mojang's version manifest
+async fn download_version_json(version_id: &str, dest: &std::path::Path) -> Result<(), String> {
+    let manifest = super::versions::fetch_version_manifest(false).await?;
+    let entry = manifest.versions.iter()
+        .find(|v| v.id == version_id)
+        .ok_or_else(|| format!("Minecraft {} is not in the version manifest", version_id))?;
+    
+    let mirror = crate::net::mirror::current().await;
+    crate::net::meta::fetch_version_json(&mirror, version_id, &entry.url, entry.sha1.as_deref(), dest).await
+        .map_err(|e| format!("Failed to download version JSON: {}", e))?;
+    Ok(())
+}
+
+/// Download whatever of a version's JSON, client jar, libraries and assets an instance is missing
+pub(crate) async fn install_version_files(
+    version_id: &str,
+    instance_dir: &std::path::Path,
+    instance_id: &str,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
     let _ = app_handle.emit("install_progress", serde_json::json!({
         "instanceId": instance_id,
         "stage": "starting",
@@ -337,7 +1176,12 @@ pub async fn install_minecraft_version(
         "totalBytes": 0
     }));
     
-    let version_manifest = crate::minecraft::versions::load_version_manifest(&instance_dir, &version_id).await
+    let version_json = versions_dir.join(version_id).join(format!("{}.json", version_id));
+    if !version_json.exists() {
+        download_version_json(version_id, &version_json).await?;
+    }
+    
+    let version_manifest = crate::minecraft::versions::load_version_manifest(instance_dir, version_id).await
         .map_err(|e| format!("Failed to load version manifest: {}", e))?
         .ok_or_else(|| format!("Version manifest for {} not found", version_id))?;
     
@@ -351,22 +1195,24 @@ pub async fn install_minecraft_version(
         "totalBytes": 0
     }));
     
+    let http = crate::http::client();
+    let mirror = crate::net::mirror::current().await;
+    // Every file the install should leave behind, checked once the downloads are done
+    let mut expected: Vec<PathBuf> = Vec::new();
+    let mut failed: Vec<String> = Vec::new();
     println!("📥 Downloading Minecraft client JAR...");
     if let Some(downloads) = version_manifest.get("downloads") {
         if let Some(client) = downloads.get("client") {
             if let Some(url) = client.get("url").and_then(|u| u.as_str()) {
-                let version_jar = versions_dir.join(&version_id).join(format!("{}.jar", version_id));
-                fs::create_dir_all(version_jar.parent().unwrap()).await
-                    .map_err(|e| format!("Failed to create version directory: {}", e))?;
-                
-                let response = reqwest::get(url).await
-                    .map_err(|e| format!("Failed to download client JAR: {}", e))?;
-                let bytes = response.bytes().await
-                    .map_err(|e| format!("Failed to read client JAR: {}", e))?;
-                fs::write(&version_jar, &bytes).await
-                    .map_err(|e| format!("Failed to write client JAR: {}", e))?;
-                
-                println!("✓ Downloaded client JAR: {}", version_jar.display());
+                let version_jar = versions_dir.join(version_id).join(format!("{}.jar", version_id));
+                expected.push(version_jar.clone());
+                if !version_jar.exists() {
+                    crate::net::mirror::download(&mirror, url, &version_jar, artifact_options(&http, client)).await
+                        .map_err(|e| format!("Failed to download client JAR: {}", e))?;
+                    crate::provenance::record_download(instance_dir, &version_jar, url, artifact_sha1(client)).await;
+                    
+                    println!("✓ Downloaded client JAR: {}", version_jar.display());
+                }
             }
         }
     }
@@ -381,29 +1227,26 @@ pub async fn install_minecraft_version(
         "totalBytes": 0
     }));
     
+    let platform = Platform::current();
     if let Some(libraries) = version_manifest.get("libraries").and_then(|v| v.as_array()) {
+        let libraries = crate::minecraft::platform::libraries_for(libraries, platform);
         println!("📦 Downloading {} libraries...", libraries.len());
         
         for (i, library) in libraries.iter().enumerate() {
-            if !should_include_library(library) {
-                continue;
-            }
             
             if let Some(downloads) = library.get("downloads") {
                 if let Some(artifact) = downloads.get("artifact") {
                     if let Some(url) = artifact.get("url").and_then(|u| u.as_str()) {
                         if let Some(path) = artifact.get("path").and_then(|p| p.as_str()) {
                             let lib_path = libraries_dir.join(path);
+                            expected.push(lib_path.clone());
                             
                             if !lib_path.exists() {
-                                if let Some(parent) = lib_path.parent() {
-                                    fs::create_dir_all(parent).await
-                                        .map_err(|e| format!("Failed to create library directory: {}", e))?;
-                                }
-                                
-                                if let Ok(response) = reqwest::get(url).await {
-                                    if let Ok(bytes) = response.bytes().await {
-                                        let _ = fs::write(&lib_path, &bytes).await;
+                                match crate::net::mirror::download(&mirror, url, &lib_path, artifact_options(&http, artifact)).await {
+                                    Ok(_) => crate::provenance::record_download(instance_dir, &lib_path, url, artifact_sha1(artifact)).await,
+                                    Err(e) => {
+                                        println!("⚠️  Failed to download library {}: {}", path, e);
+                                        failed.push(path.to_string());
                                     }
                                 }
                             }
@@ -411,30 +1254,22 @@ pub async fn install_minecraft_version(
                     }
                 }
                 
-                // Handle natives for current OS
-                let os_key = if cfg!(target_os = "windows") {
-                    "natives-windows"
-                } else if cfg!(target_os = "macos") {
-                    "natives-osx"
-                } else {
-                    "natives-linux"
-                };
+                // Handle natives for current OS and architecture
+                let os_key = crate::minecraft::platform::natives_classifier(library, platform);
                 
-                if let Some(classifiers) = downloads.get("classifiers") {
-                    if let Some(native) = classifiers.get(os_key) {
+                if let (Some(classifiers), Some(os_key)) = (downloads.get("classifiers"), os_key) {
+                    if let Some(native) = classifiers.get(&os_key) {
                         if let Some(url) = native.get("url").and_then(|u| u.as_str()) {
                             if let Some(path) = native.get("path").and_then(|p| p.as_str()) {
                                 let lib_path = libraries_dir.join(path);
+                                expected.push(lib_path.clone());
                                 
                                 if !lib_path.exists() {
-                                    if let Some(parent) = lib_path.parent() {
-                                        fs::create_dir_all(parent).await
-                                            .map_err(|e| format!("Failed to create library directory: {}", e))?;
-                                    }
-                                    
-                                    if let Ok(response) = reqwest::get(url).await {
-                                        if let Ok(bytes) = response.bytes().await {
-                                            let _ = fs::write(&lib_path, &bytes).await;
+                                    match crate::net::mirror::download(&mirror, url, &lib_path, artifact_options(&http, native)).await {
+                                        Ok(_) => crate::provenance::record_download(instance_dir, &lib_path, url, artifact_sha1(native)).await,
+                                        Err(e) => {
+                                            println!("⚠️  Failed to download native library {}: {}", path, e);
+                                            failed.push(path.to_string());
                                         }
                                     }
                                 }
@@ -451,42 +1286,26 @@ pub async fn install_minecraft_version(
                     let version = parts[2];
                     let jar_name = format!("{}-{}.jar", artifact, version);
                     let lib_path = libraries_dir.join(&group).join(artifact).join(version).join(&jar_name);
+                    expected.push(lib_path.clone());
                     
                     if !lib_path.exists() {
-                        if let Some(parent) = lib_path.parent() {
-                            fs::create_dir_all(parent).await
-                                .map_err(|e| format!("Failed to create library directory: {}", e))?;
-                        }
-                        
+                        // Try Minecraft libraries repository first, then Maven Central
                         let mut downloaded = false;
-                        
-                        // Try Minecraft libraries repository first
-                        let mc_url = format!("https://libraries.minecraft.net/{}/{}/{}/{}", 
-                            group, artifact, version, jar_name);
-                        
-                        if let Ok(response) = reqwest::get(&mc_url).await {
-                            if response.status().is_success() {
-                                if let Ok(bytes) = response.bytes().await {
-                                    let _ = fs::write(&lib_path, &bytes).await;
-                                    downloaded = true;
-                                }
+                        for repo in ["https://libraries.minecraft.net", "https://repo1.maven.org/maven2"] {
+                            let url = format!("{}/{}/{}/{}/{}", repo, group, artifact, version, jar_name);
+                            let options = crate::net::DownloadOptions {
+                                client: Some(http.clone()),
+                                ..Default::default()
+                            };
+                            if crate::net::mirror::download(&mirror, &url, &lib_path, options).await.is_ok() {
+                                crate::provenance::record_download(instance_dir, &lib_path, &url, None).await;
+                                downloaded = true;
+                                break;
                             }
                         }
-                        
-                        // Fallback to Maven Central
                         if !downloaded {
-                            let maven_url = format!(
-                                "https://repo1.maven.org/maven2/{}/{}/{}/{}",
-                                group, artifact, version, jar_name
-                            );
-                            
-                            if let Ok(response) = reqwest::get(&maven_url).await {
-                                if response.status().is_success() {
-                                    if let Ok(bytes) = response.bytes().await {
-                                        let _ = fs::write(&lib_path, &bytes).await;
-                                    }
-                                }
-                            }
+                            println!("⚠️  Failed to download library {} from any repository", name);
+                            failed.push(name.to_string());
                         }
                     }
                 }
@@ -509,6 +1328,13 @@ pub async fn install_minecraft_version(
         }
     }
     
+    if !failed.is_empty() {
+        return Err(listed_files_error(
+            &format!("Failed to download {} of Minecraft {}", plural_libraries(failed.len()), version_id),
+            &failed,
+        ));
+    }
+    
     // Download assets
     let _ = app_handle.emit("install_progress", serde_json::json!({
         "instanceId": instance_id,
@@ -519,51 +1345,52 @@ pub async fn install_minecraft_version(
         "totalBytes": 0
     }));
     
-    download_minecraft_assets_with_progress(version_id.clone(), instance_dir.to_string_lossy().to_string(), &instance_id, &app_handle).await?;
+    download_minecraft_assets_with_progress(version_id.to_string(), instance_dir.to_string_lossy().to_string(), instance_id, app_handle).await?;
     
-    // Save the instance to storage so it persists
-    let instance_metadata = InstanceMetadata {
-        id: instance_id.clone(),
-        name: instance_name.clone(),
-        version: version_id.clone(),
-        modpack: None,
-        modpack_version: None,
-        game_dir: instance_dir,
-        java_path: None,
-        jvm_args: None,
-        last_played: None,
-        total_play_time: 0,
-        icon: None,
-        is_modded: false,
-        mods_count: 0,
-        created_at: chrono::Utc::now().to_rfc3339(),
-        size_mb: None,
-        description: None,
-        tags: vec![],
-        resolved_java_version: None,
-        java_analysis_date: None,
-    };
-    
-    // Save to storage
-    save_instance(instance_metadata, app_handle.clone()).await?;
+    readiness::invalidate(instance_dir);
     
-    let _ = app_handle.emit("install_complete", serde_json::json!({
-        "instanceId": instance_id,
-        "success": true,
-        "version": version_id
-    }));
-    
-    println!("✅ Minecraft {} installation completed for '{}'", version_id, instance_name);
+    // A download that reported success may still have left nothing behind
+    let mut missing: Vec<String> = expected.iter()
+        .filter(|path| !path.is_file())
+        .map(|path| path.strip_prefix(instance_dir).unwrap_or(path).display().to_string())
+        .collect();
+    missing.extend(super::versions::missing_asset_objects(instance_dir, version_id).await);
+    if !missing.is_empty() {
+        return Err(listed_files_error(
+            &format!("Minecraft {} is missing {} files after installing", version_id, missing.len()),
+            &missing,
+        ));
+    }
     Ok(())
 }
 
+/// `summary` followed by the first few of `files`
+fn listed_files_error(summary: &str, files: &[String]) -> String {
+    const SHOWN: usize = 10;
+    
+    let mut listed = files.iter().take(SHOWN).cloned().collect::<Vec<_>>().join(", ");
+    if files.len() > SHOWN {
+        listed.push_str(&format!(" and {} more", files.len() - SHOWN));
+    }
+    format!("{}: {}", summary, listed)
+}
+
+fn plural_libraries(count: usize) -> String {
+    if count == 1 { "1 library".to_string() } else { format!("{} libraries", count) }
+}
+
 /// Backup instance
 #[command]
-pub async fn backup_instance(instance_id: String, backup_path: String) -> Result<(), String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+pub async fn backup_instance(
+    instance_id: String,
+    backup_path: String,
+    preserve_metadata: Option<bool>,
+    storage: State<'_, StorageState>,
+) -> Result<(), String> {
+    // Copying can take a while, so don't hold the storage lock for it
+    let instance = storage.read().await.get_instance(&instance_id).cloned();
     
-    if let Some(instance) = storage.get_instance(&instance_id) {
+    if let Some(instance) = instance {
         let source_path = &instance.game_dir;
         let backup_dest = PathBuf::from(&backup_path);
         
@@ -576,7 +1403,7 @@ pub async fn backup_instance(instance_id: String, backup_path: String) -> Result
         }
         
         // Copy instance directory to backup location
-        copy_directory_recursive(source_path, &backup_dest).await
+        copy_directory_recursive(source_path, &backup_dest, preserve_metadata.unwrap_or(true)).await
             .map_err(|e| format!("Failed to backup instance: {}", e))?;
         
         println!("✅ Instance '{}' backed up successfully", instance.name);
@@ -588,11 +1415,15 @@ pub async fn backup_instance(instance_id: String, backup_path: String) -> Result
 
 /// Restore instance
 #[command]
-pub async fn restore_instance(instance_id: String, backup_path: String) -> Result<(), String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+pub async fn restore_instance(
+    instance_id: String,
+    backup_path: String,
+    preserve_metadata: Option<bool>,
+    storage: State<'_, StorageState>,
+) -> Result<(), String> {
+    let instance = storage.read().await.get_instance(&instance_id).cloned();
     
-    if let Some(instance) = storage.get_instance(&instance_id) {
+    if let Some(instance) = instance {
         let backup_source = PathBuf::from(&backup_path);
         let restore_dest = &instance.game_dir;
         
@@ -609,7 +1440,7 @@ pub async fn restore_instance(instance_id: String, backup_path: String) -> Resul
         }
         
         // Copy backup to instance location
-        copy_directory_recursive(&backup_source, restore_dest).await
+        copy_directory_recursive(&backup_source, restore_dest, preserve_metadata.unwrap_or(true)).await
             .map_err(|e| format!("Failed to restore instance: {}", e))?;
         
         println!("✅ Instance '{}' restored successfully", instance.name);
@@ -621,13 +1452,14 @@ pub async fn restore_instance(instance_id: String, backup_path: String) -> Resul
 
 /// Refresh instance sizes
 #[command]
-pub async fn refresh_instance_sizes() -> Result<(), String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
+pub async fn refresh_instance_sizes(storage: State<'_, StorageState>) -> Result<(), String> {
     println!("📊 Refreshing instance sizes...");
     
-    let instances = storage.get_all_instances();
+    let instances: Vec<InstanceMetadata> = storage.read().await
+        .get_all_instances()
+        .into_iter()
+        .cloned()
+        .collect();
     let mut updated_count = 0;
     
     for instance in instances {
@@ -644,56 +1476,193 @@ pub async fn refresh_instance_sizes() -> Result<(), String> {
     Ok(())
 }
 
+/// Remove old rotated logs and crash reports according to the instance's cleanup policy
+#[command]
+pub async fn clean_instance_logs(instance_id: String) -> Result<crate::minecraft::logs::LogCleanupResult, String> {
+    crate::minecraft::logs::clean_instance_logs(&instance_id).await
+}
+
+/// Report the effective game arguments for an instance launch, with the access token redacted
+///
+/// Doubles as a launch dry run: the environment the game would get is resolved
+/// the same way a launch does, without starting anything. Also checks the version's asset index file exists and downloads it when missing.
+/// `world_folder` or `server_address` add the quick play arguments of launching into that world or server.
+#[command]
+pub async fn diagnose_launch(
+    instance_id: String,
+    world_folder: Option<String>,
+    server_address: Option<String>,
+    storage: State<'_, StorageState>,
+) -> Result<crate::minecraft::LaunchDiagnostics, String> {
+    let instance = storage.read().await
+        .get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    let mut launched = MinecraftInstance::from(instance.clone());
+    if let Some(world_folder) = world_folder.filter(|folder| !folder.is_empty()) {
+        crate::minecraft::worlds::world_path(&instance.game_dir, &world_folder)?;
+        if let Some(args) = crate::minecraft::worlds::quick_play_args(&instance.version, &world_folder) {
+            launched.game_args.get_or_insert_with(Vec::new).extend(args);
+        }
+    }
+    if let Some(address) = server_address.filter(|address| !address.trim().is_empty()) {
+        let server = ServerAddress::parse(&address)?;
+        launched.game_args.get_or_insert_with(Vec::new).extend(servers::join_args(&instance.version, &server));
+    }
+
+    let auth_info = get_auth_info(None).await.unwrap_or_default();
+
+    // The index id comes from the version JSON, several versions share one
+    let asset_index = crate::minecraft::assets::ensure_asset_index(&instance.game_dir, &instance.version).await?;
+    let assets_dir = instance.game_dir.join("assets").to_string_lossy().to_string();
+    // Before 1.7.3 the game reads named copies from a virtual or resources folder
+    let asset_layout = match &asset_index {
+        Some(check) => crate::minecraft::assets::read_layout(&instance.game_dir, &check.id).await
+            .map(|layout| (layout, layout.game_assets_dir(&instance.game_dir, &check.id))),
+        None => None,
+    };
+    let game_assets = asset_layout.as_ref()
+        .map(|(_, dir)| dir.to_string_lossy().to_string())
+        .unwrap_or_else(|| assets_dir.clone());
+    let profile = crate::minecraft::profile::load(&instance.game_dir, &instance.version).await;
+
+    let mut game_args = match profile.as_ref().filter(|profile| profile.legacy_arguments) {
+        // Legacy profiles carry the whole argument string, tweak classes included
+        Some(profile) => profile.resolve_args(&HashMap::from([
+            ("auth_player_name", auth_info.username),
+            ("version_name", profile.id.clone()),
+            ("game_directory", instance.game_dir.to_string_lossy().to_string()),
+            ("assets_root", assets_dir.clone()),
+            ("game_assets", game_assets),
+            ("assets_index_name", asset_index.as_ref().map(|check| check.id.clone()).unwrap_or_default()),
+            ("auth_uuid", auth_info.uuid),
+            ("auth_access_token", "[REDACTED]".to_string()),
+            ("auth_session", "[REDACTED]".to_string()),
+            ("user_properties", "{}".to_string()),
+            ("user_type", auth_info.user_type),
+            ("version_type", "release".to_string()),
+        ])),
+        None => {
+            let mut args = vec![
+                "--username".to_string(), auth_info.username,
+                "--version".to_string(), instance.version.clone(),
+                "--gameDir".to_string(), instance.game_dir.to_string_lossy().to_string(),
+                "--uuid".to_string(), auth_info.uuid,
+                "--accessToken".to_string(), "[REDACTED]".to_string(),
+                "--userType".to_string(), auth_info.user_type,
+            ];
+            // Resources-mapped versions find their assets in the game directory
+            let resources = matches!(asset_layout, Some((crate::minecraft::assets::AssetLayout::Resources, _)));
+            if let Some(check) = asset_index.as_ref().filter(|_| !resources) {
+                args.extend(["--assetsDir".to_string(), assets_dir, "--assetIndex".to_string(), check.id.clone()]);
+            }
+            args
+        }
+    };
+    let added = crate::minecraft::launcher::AddedGameArgs::resolve(&launched, profile.as_ref(), &game_args).await;
+    let rejected_game_args = added.rejected.clone();
+    game_args.extend(added.into_args());
+    let classpath = profile.as_ref()
+        .map(|profile| profile.classpath(&instance.game_dir.join("libraries"), &instance.game_dir.join("versions")))
+        .unwrap_or_default();
+
+    let java_version = crate::minecraft::versions::required_java_for_instance(&instance.id, &instance.game_dir, &instance.version).await;
+    let (java, java_error) = match crate::minecraft::java::resolve_java(java_version).await {
+        Ok(java) => (Some(java), None),
+        Err(e) => (None, Some(e)),
+    };
+    let graphics = crate::minecraft::graphics::for_launch().await;
+    let graphics_warnings = graphics.as_ref()
+        .map(|probe| crate::minecraft::graphics::launch_warnings(probe, &instance.version))
+        .unwrap_or_default();
+
+    Ok(crate::minecraft::LaunchDiagnostics {
+        game_args,
+        rejected_game_args,
+        asset_index,
+        java,
+        java_error,
+        mod_provenance: crate::provenance::live_entries(&instance.game_dir, "mods/"),
+        stability: instance.stability.summary(),
+        environment: crate::minecraft::environment::preview(instance.env_vars.as_ref(), instance.clear_inherited_env),
+        profile,
+        classpath,
+        graphics,
+        graphics_warnings,
+    })
+}
+
 // Helper functions
 
 /// Get authentication info for debugging (public version of get_auth_info)
 #[command]
 pub async fn get_auth_info_debug() -> Result<AuthInfo, String> {
-    get_auth_info().await
+    get_auth_info(None).await
+}
+
+/// Auth for a launch, as `account_uuid` when one was picked
+pub(crate) async fn launch_auth_info(account_uuid: Option<&str>) -> Result<AuthInfo, String> {
+    get_auth_info(account_uuid.filter(|uuid| !uuid.is_empty())).await
 }
 
 /// Get authentication info from storage
-async fn get_auth_info() -> Result<AuthInfo, String> {
+///
+/// `account_uuid` launches as that stored account instead of the active one.
+pub(crate) async fn get_auth_info(account_uuid: Option<&str>) -> Result<AuthInfo, String> {
     // First priority: Try to get Microsoft account info
-    if let Ok(accounts) = crate::auth::get_stored_accounts().await {
-        if let Some(account) = accounts.first() {
-            // Check if token is still valid and refresh if needed
-            match crate::auth::get_active_account_token().await {
-                Ok(Some(active_token)) => {
-                    return Ok(AuthInfo {
-                        username: account.username.clone(),
-                        uuid: account.uuid.clone(),
-                        access_token: active_token,
-                        user_type: "msa".to_string(),
-                    });
-                }
-                Ok(None) => {
-                    println!("⚠️  Microsoft account token expired or invalid");
-                }
-                Err(e) => {
-                    println!("⚠️  Failed to get Microsoft account token: {}", e);
-                }
-            }
+    let account = match crate::auth::resolve_account(account_uuid).await {
+        Ok(Some((account, Some(active_token)))) => {
+            return Ok(AuthInfo {
+                username: account.username,
+                uuid: account.uuid,
+                access_token: active_token,
+                user_type: "msa".to_string(),
+            });
         }
-    }
+        Ok(Some((account, None))) => {
+            println!("⚠️  Microsoft account token expired or invalid");
+            Some(account)
+        }
+        Ok(None) => None,
+        Err(e) if account_uuid.is_some() => return Err(e.to_string()),
+        Err(e) => {
+            println!("⚠️  Failed to get Microsoft account token: {}", e);
+            None
+        }
+    };
     
     // Second priority: Try to get manual auth token from settings
-    let storage = crate::storage::StorageManager::new().await
+    let storage = crate::storage::shared().await
         .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+    crate::secrets::migrate_auth_token(&storage).await;
+    let settings = storage.read().await.get_settings().clone();
+    let manual_token = match crate::secrets::get(crate::secrets::AUTH_TOKEN_KEY).await {
+        Ok(token) => token,
+        Err(e) => {
+            println!("⚠️  Failed to read the manual auth token: {}", e);
+            None
+        }
+    };
     
-    if let Some(token) = storage.get_settings().auth_token.clone() {
+    if let Some(token) = manual_token.clone() {
         // If we have accounts but no valid token, use account info with manual token
-        if let Ok(accounts) = crate::auth::get_stored_accounts().await {
-            if let Some(account) = accounts.first() {
-                return Ok(AuthInfo {
-                    username: account.username.clone(),
-                    uuid: account.uuid.clone(),
-                    access_token: token,
-                    user_type: "msa".to_string(),
-                });
-            }
+        if let Some(account) = account {
+            return Ok(AuthInfo {
+                username: account.username,
+                uuid: account.uuid,
+                access_token: token,
+                user_type: "msa".to_string(),
+            });
         }
-        
+    }
+    
+    // Third priority: offline mode, never for a launch that picked an account
+    if settings.allow_offline_launch && account_uuid.is_none() {
+        return offline_auth_info(settings.offline_username.as_deref());
+    }
+    
+    if manual_token.is_some() {
         // Require a Microsoft account when using manual tokens
         return Err("Manual authentication token provided, but no Microsoft account found. Please sign in with a Microsoft account first, then set your authentication token.".to_string());
     }
@@ -702,78 +1671,114 @@ async fn get_auth_info() -> Result<AuthInfo, String> {
     Err("No valid authentication found. ChaiLauncher requires either a Microsoft account or a valid authentication token. Please sign in with a Microsoft account or configure an authentication token.".to_string())
 }
 
-/// Check if a library should be included based on rules
-fn should_include_library(library: &serde_json::Value) -> bool {
-    let Some(rules) = library.get("rules").and_then(|v| v.as_array()) else {
-        return true; // No rules means include
-    };
-    
-    if rules.is_empty() {
-        return true;
-    }
-    
-    let current_os = if cfg!(target_os = "windows") {
-        "windows"
-    } else if cfg!(target_os = "macos") {
-        "osx"
-    } else {
-        "linux"
-    };
-    
-    let mut allow = false;
-    
-    for rule in rules {
-        if let Some(action) = rule.get("action").and_then(|v| v.as_str()) {
-            let rule_applies = if let Some(os_obj) = rule.get("os") {
-                if let Some(os_name) = os_obj.get("name").and_then(|v| v.as_str()) {
-                    os_name == current_os
-                } else {
-                    true
-                }
-            } else {
-                true
-            };
-            
-            if rule_applies {
-                match action {
-                    "allow" => allow = true,
-                    "disallow" => allow = false,
-                    _ => {}
-                }
-            }
-        }
+/// Offline identity with the UUID the game derives from the name
+fn offline_auth_info(username: Option<&str>) -> Result<AuthInfo, String> {
+    let username = username
+        .filter(|name| !name.is_empty())
+        .ok_or("Offline launches are enabled, but no offline username is set")?;
+    crate::auth::validate_offline_username(username)?;
+
+    let uuid = crate::auth::offline_uuid(username);
+    println!("🔌 OFFLINE MODE: launching as {} ({}), online servers will reject this session", username, uuid);
+    Ok(AuthInfo {
+        username: username.to_string(),
+        uuid,
+        access_token: "offline".to_string(),
+        user_type: "legacy".to_string(),
+    })
+}
+
+/// Download options for a version manifest entry carrying `size` and `sha1`
+fn artifact_options(client: &reqwest::Client, artifact: &serde_json::Value) -> crate::net::DownloadOptions {
+    crate::net::DownloadOptions {
+        client: Some(client.clone()),
+        expected_size: artifact.get("size").and_then(|v| v.as_u64()),
+        sha1: artifact_sha1(artifact).map(String::from),
+        ..Default::default()
     }
-    
-    allow
+}
+
+fn artifact_sha1(artifact: &serde_json::Value) -> Option<&str> {
+    artifact.get("sha1").and_then(|v| v.as_str())
 }
 
 /// Copy directory recursively for backup/restore
-async fn copy_directory_recursive(src: &PathBuf, dst: &PathBuf) -> Result<(), String> {
+///
+/// With `preserve_metadata`, modified times and unix permissions are carried over so
+/// restored files don't all look new. Turn it off for filesystems that reject them.
+pub(super) async fn copy_directory_recursive(src: &PathBuf, dst: &PathBuf, preserve_metadata: bool) -> Result<(), String> {
+    let src = src.clone();
+    let dst = dst.clone();
+
+    tokio::task::spawn_blocking(move || copy_tree(&src, &dst, preserve_metadata))
+        .await
+        .map_err(|e| format!("Copy task failed: {}", e))?
+}
+
+fn copy_tree(src: &std::path::Path, dst: &std::path::Path, preserve_metadata: bool) -> Result<(), String> {
     use walkdir::WalkDir;
-    
+
+    let mut copied_dirs = Vec::new();
+
     for entry in WalkDir::new(src) {
         let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
         let src_path = entry.path();
         let relative_path = src_path.strip_prefix(src)
             .map_err(|e| format!("Failed to get relative path: {}", e))?;
         let dst_path = dst.join(relative_path);
-        
-        if src_path.is_dir() {
-            fs::create_dir_all(&dst_path).await
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dst_path)
                 .map_err(|e| format!("Failed to create directory: {}", e))?;
+            if preserve_metadata {
+                copied_dirs.push((src_path.to_path_buf(), dst_path));
+            }
         } else {
             if let Some(parent) = dst_path.parent() {
-                fs::create_dir_all(parent).await
+                std::fs::create_dir_all(parent)
                     .map_err(|e| format!("Failed to create parent directory: {}", e))?;
             }
-            fs::copy(src_path, &dst_path).await
-                .map_err(|e| format!("Failed to copy file: {}", e))?;
+
+            if preserve_metadata {
+                // std::fs::copy also copies the permission bits
+                std::fs::copy(src_path, &dst_path)
+                    .map_err(|e| format!("Failed to copy file: {}", e))?;
+                copy_times(src_path, &dst_path)?;
+            } else {
+                let mut reader = std::fs::File::open(src_path)
+                    .map_err(|e| format!("Failed to open file: {}", e))?;
+                let mut writer = std::fs::File::create(&dst_path)
+                    .map_err(|e| format!("Failed to create file: {}", e))?;
+                std::io::copy(&mut reader, &mut writer)
+                    .map_err(|e| format!("Failed to copy file: {}", e))?;
+            }
         }
     }
-    
+
+    // Writing into a directory updates its mtime, so directories are done last, deepest first
+    for (src_dir, dst_dir) in copied_dirs.iter().rev() {
+        let permissions = std::fs::metadata(src_dir)
+            .map_err(|e| format!("Failed to read directory metadata: {}", e))?
+            .permissions();
+        std::fs::set_permissions(dst_dir, permissions)
+            .map_err(|e| format!("Failed to set directory permissions: {}", e))?;
+        copy_times(src_dir, dst_dir)?;
+    }
+
     Ok(())
 }
 
+/// Copy access and modified times from `src` to `dst`
+pub(super) fn copy_times(src: &std::path::Path, dst: &std::path::Path) -> Result<(), String> {
+    let metadata = std::fs::metadata(src)
+        .map_err(|e| format!("Failed to read metadata of {}: {}", src.display(), e))?;
+    let modified = filetime::FileTime::from_last_modification_time(&metadata);
+    let accessed = filetime::FileTime::from_last_access_time(&metadata);
+
+    filetime::set_file_times(dst, accessed, modified)
+        .map_err(|e| format!("Failed to set file times on {} (disable metadata preservation on filesystems without timestamp support): {}", dst.display(), e))
+}
+
 /// Calculate directory size recursively
 async fn calculate_directory_size(dir: &PathBuf) -> Result<u64, String> {
     use walkdir::WalkDir;
@@ -795,6 +1800,7 @@ async fn calculate_directory_size(dir: &PathBuf) -> Result<u64, String> {
 // Conversion implementation for InstanceMetadata -> MinecraftInstance
 impl From<InstanceMetadata> for MinecraftInstance {
     fn from(metadata: InstanceMetadata) -> Self {
+        let (ready, not_ready_reason) = readiness::status(&metadata.game_dir, &metadata.version);
         Self {
             id: metadata.id,
             name: metadata.name,
@@ -804,7 +1810,7 @@ impl From<InstanceMetadata> for MinecraftInstance {
             game_dir: metadata.game_dir,
             java_path: metadata.java_path,
             jvm_args: metadata.jvm_args,
-            last_played: metadata.last_played,
+            last_played: metadata.last_played.as_deref().and_then(timestamps::to_epoch_millis),
             total_play_time: metadata.total_play_time,
             icon: metadata.icon,
             is_modded: metadata.is_modded,
@@ -812,7 +1818,252 @@ impl From<InstanceMetadata> for MinecraftInstance {
             is_external: None,
             external_launcher: None,
             resolved_java_version: metadata.resolved_java_version,
-            java_analysis_date: metadata.java_analysis_date,
+            java_analysis_date: metadata.java_analysis_date.as_deref().and_then(timestamps::to_epoch_millis),
+            log_cleanup: metadata.log_cleanup,
+            game_args: metadata.game_args,
+            pre_launch_command: metadata.pre_launch_command,
+            post_exit_command: metadata.post_exit_command,
+            env_vars: metadata.env_vars,
+            clear_inherited_env: metadata.clear_inherited_env,
+            window_width: metadata.window_width,
+            window_height: metadata.window_height,
+            fullscreen: metadata.fullscreen,
+            jvm_args_preset: metadata.jvm_args_preset,
+            created_at: timestamps::to_epoch_millis(&metadata.created_at),
+            size_mb: metadata.size_mb,
+            ready,
+            not_ready_reason,
+            unstable: metadata.stability.summary().unstable,
+            health: metadata.health.as_ref().map(|health| health.status),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filetime::FileTime;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-copy-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn mtime(path: &std::path::Path) -> FileTime {
+        FileTime::from_last_modification_time(&std::fs::metadata(path).unwrap())
+    }
+
+    #[tokio::test]
+    async fn backup_and_restore_keep_mtimes_and_permissions() {
+        let root = temp_dir();
+        let instance = root.join("instance");
+        let backup = root.join("backup");
+        let restored = root.join("restored");
+
+        std::fs::create_dir_all(instance.join("config")).unwrap();
+        std::fs::write(instance.join("options.txt"), "fov:70").unwrap();
+        std::fs::write(instance.join("config").join("mod.toml"), "enabled = true").unwrap();
+        std::fs::write(instance.join("run.sh"), "#!/bin/sh\n").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(instance.join("run.sh"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let old = FileTime::from_unix_time(1_600_000_000, 0);
+        for path in [instance.join("options.txt"), instance.join("config").join("mod.toml"), instance.join("run.sh")] {
+            filetime::set_file_mtime(&path, old).unwrap();
+        }
+        filetime::set_file_mtime(instance.join("config"), old).unwrap();
+
+        copy_directory_recursive(&instance, &backup, true).await.unwrap();
+        copy_directory_recursive(&backup, &restored, true).await.unwrap();
+
+        assert_eq!(mtime(&restored.join("options.txt")), old);
+        assert_eq!(mtime(&restored.join("config").join("mod.toml")), old);
+        assert_eq!(mtime(&restored.join("config")), old);
+        assert_eq!(std::fs::read_to_string(restored.join("options.txt")).unwrap(), "fov:70");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(restored.join("run.sh")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn duplicate_copy_skips_logs_and_reports_bytes() {
+        let root = temp_dir();
+        let source = root.join("source");
+        let dest = root.join("dest");
+
+        std::fs::create_dir_all(source.join("config")).unwrap();
+        std::fs::create_dir_all(source.join("logs")).unwrap();
+        std::fs::create_dir_all(source.join("crash-reports")).unwrap();
+        std::fs::write(source.join("options.txt"), "fov:70").unwrap();
+        std::fs::write(source.join("config").join("mod.toml"), "a = 1").unwrap();
+        std::fs::write(source.join("logs").join("latest.log"), "log").unwrap();
+        std::fs::write(source.join("crash-reports").join("crash.txt"), "crash").unwrap();
+        std::fs::create_dir_all(&dest).unwrap();
+
+        let reports = std::sync::Mutex::new(Vec::new());
+        let copied = copy_instance_dir(&source, &dest, |current, copied, total| {
+            reports.lock().unwrap().push((current.to_string(), copied, total));
+        }).await.unwrap();
+
+        assert_eq!(copied, 11);
+        assert_eq!(std::fs::read_to_string(dest.join("config").join("mod.toml")).unwrap(), "a = 1");
+        assert!(dest.join("options.txt").exists());
+        assert!(!dest.join("logs").exists());
+        assert!(!dest.join("crash-reports").exists());
+        assert_eq!(reports.into_inner().unwrap().last(), Some(&(String::new(), 11, 11)));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn running_instance_is_detected_from_latest_log() {
+        let root = temp_dir();
+        assert!(!appears_running(&root).await);
+
+        std::fs::create_dir_all(root.join("logs")).unwrap();
+        let log = root.join("logs").join("latest.log");
+        std::fs::write(&log, "[Render thread/INFO]: Loaded 7 recipes\n").unwrap();
+        assert!(appears_running(&root).await);
+
+        std::fs::write(&log, "[Render thread/INFO]: Stopping!\n").unwrap();
+        assert!(!appears_running(&root).await);
+
+        // An old log without the shutdown message means the game crashed long ago
+        std::fs::write(&log, "[Render thread/INFO]: Loaded 7 recipes\n").unwrap();
+        filetime::set_file_mtime(&log, FileTime::from_unix_time(1_600_000_000, 0)).unwrap();
+        assert!(!appears_running(&root).await);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn moving_instance_dir_refuses_existing_target() {
+        let root = temp_dir();
+        std::fs::create_dir_all(root.join("old")).unwrap();
+        std::fs::create_dir_all(root.join("taken")).unwrap();
+        std::fs::write(root.join("old").join("options.txt"), "fov:70").unwrap();
+
+        let err = move_instance_dir(&root.join("old"), &root.join("taken")).await.unwrap_err();
+        assert!(matches!(err, InstanceRenameError::AlreadyExists { .. }));
+
+        move_instance_dir(&root.join("old"), &root.join("new")).await.unwrap();
+        assert!(!root.join("old").exists());
+        assert_eq!(std::fs::read_to_string(root.join("new").join("options.txt")).unwrap(), "fov:70");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn copy_without_metadata_uses_fresh_times() {
+        let root = temp_dir();
+        let source = root.join("source");
+        let dest = root.join("dest");
+
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("options.txt"), "fov:70").unwrap();
+        let old = FileTime::from_unix_time(1_600_000_000, 0);
+        filetime::set_file_mtime(source.join("options.txt"), old).unwrap();
+
+        copy_directory_recursive(&source, &dest, false).await.unwrap();
+
+        assert_ne!(mtime(&dest.join("options.txt")), old);
+        assert_eq!(std::fs::read_to_string(dest.join("options.txt")).unwrap(), "fov:70");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    fn metadata(name: &str, last_played: Option<&str>, created_at: &str) -> InstanceMetadata {
+        InstanceMetadata {
+            name: name.to_string(),
+            last_played: last_played.map(str::to_string),
+            created_at: created_at.to_string(),
+            ..crate::storage::test_instance(&name.to_lowercase(), PathBuf::from("/tmp").join(name))
+        }
+    }
+
+    #[test]
+    fn metadata_dates_convert_to_epoch_millis() {
+        let instance = MinecraftInstance::from(metadata(
+            "Survival",
+            Some("2024-05-01T12:00:00+00:00"),
+            "2024-01-01T00:00:00.500Z",
+        ));
+
+        assert_eq!(instance.last_played, Some(1_714_564_800_000));
+        assert_eq!(instance.created_at, Some(1_704_067_200_500));
+
+        let json = serde_json::to_value(&instance).unwrap();
+        assert_eq!(json["lastPlayed"], 1_714_564_800_000i64);
+        assert_eq!(json["createdAt"], 1_704_067_200_500i64);
+
+        let stored = InstanceMetadata::from(instance);
+        assert_eq!(timestamps::to_epoch_millis(stored.last_played.as_deref().unwrap()), Some(1_714_564_800_000));
+        assert_eq!(timestamps::to_epoch_millis(&stored.created_at), Some(1_704_067_200_500));
+    }
+
+    #[test]
+    fn old_string_dates_still_deserialize() {
+        let mut json = serde_json::to_value(MinecraftInstance::from(metadata("Old", None, "garbage"))).unwrap();
+        json["lastPlayed"] = serde_json::json!("2024-05-01T12:00:00Z");
+        json["javaAnalysisDate"] = serde_json::json!("not a date");
+        json.as_object_mut().unwrap().remove("createdAt");
+
+        let instance: MinecraftInstance = serde_json::from_value(json).unwrap();
+
+        assert_eq!(instance.last_played, Some(1_714_564_800_000));
+        assert_eq!(instance.java_analysis_date, None);
+        assert_eq!(instance.created_at, None);
+    }
+
+    #[test]
+    fn sorting_puts_missing_values_last_and_is_stable() {
+        let mut instances: Vec<MinecraftInstance> = vec![
+            metadata("beta", None, "2024-03-01T00:00:00Z"),
+            metadata("Alpha", Some("2024-02-01T00:00:00Z"), "2024-01-01T00:00:00Z"),
+            metadata("gamma", Some("2024-04-01T00:00:00Z"), "bad"),
+            metadata("Delta", None, "2024-02-01T00:00:00Z"),
+        ].into_iter().map(MinecraftInstance::from).collect();
+
+        let names = |instances: &[MinecraftInstance]| -> Vec<String> {
+            instances.iter().map(|i| i.name.clone()).collect()
+        };
+
+        sort_instances(&mut instances, InstanceSortKey::LastPlayed);
+        assert_eq!(names(&instances), vec!["gamma", "Alpha", "beta", "Delta"]);
+
+        sort_instances(&mut instances, InstanceSortKey::CreatedAt);
+        assert_eq!(names(&instances), vec!["beta", "Delta", "Alpha", "gamma"]);
+
+        // Nothing has a size, so the name tie-break decides regardless of the previous order
+        sort_instances(&mut instances, InstanceSortKey::Size);
+        assert_eq!(names(&instances), vec!["Alpha", "beta", "Delta", "gamma"]);
+        instances.reverse();
+        sort_instances(&mut instances, InstanceSortKey::PlayTime);
+        assert_eq!(names(&instances), vec!["Alpha", "beta", "Delta", "gamma"]);
+
+        sort_instances(&mut instances, InstanceSortKey::Name);
+        assert_eq!(names(&instances), vec!["Alpha", "beta", "Delta", "gamma"]);
+    }
+
+    #[test]
+    fn install_errors_list_the_first_missing_files() {
+        let files: Vec<String> = (1..=12).map(|i| format!("lib{}.jar", i)).collect();
+        assert_eq!(
+            listed_files_error(&format!("Failed to download {}", plural_libraries(1)), &files[..1]),
+            "Failed to download 1 library: lib1.jar"
+        );
+        let error = listed_files_error("Minecraft 1.21 is missing 12 files after installing", &files);
+        assert!(error.ends_with("lib9.jar, lib10.jar and 2 more"), "{}", error);
+    }
+}
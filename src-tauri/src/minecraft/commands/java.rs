@@ -1,23 +1,24 @@
-use tauri::{command, AppHandle, Emitter};
+use tauri::{command, AppHandle, Emitter, State};
 use std::process::Command;
 use std::path::PathBuf;
 use tokio::fs;
-use reqwest;
-use std::io::Write;
-use futures::StreamExt;
 
-use crate::storage::StorageManager;
+use crate::notifications::{self, LauncherNotification, NotificationCategory, NotificationTarget};
+use crate::storage::StorageState;
+use crate::net::manager::{DownloadKind, DownloadPriority};
 
 /// Get bundled Java path (defaults to Java 17)
 #[command]
 pub async fn get_bundled_java_path() -> Result<String, String> {
-    get_bundled_java_path_for_version(17).await
+    Ok(get_bundled_java_path_for_version(17).await?.path)
 }
 
-/// Get bundled Java path for specific version
+/// Get the Java runtime for a specific version and where it came from
+///
+/// Falls back to a system install when the launcher has none, see `resolve_java`.
 #[command] 
-pub async fn get_bundled_java_path_for_version(major_version: u32) -> Result<String, String> {
-    crate::minecraft::versions::get_java_for_version(major_version).await
+pub async fn get_bundled_java_path_for_version(major_version: u32) -> Result<crate::minecraft::java::ResolvedJava, String> {
+    crate::minecraft::java::resolve_java(major_version).await
 }
 
 /// Download and install Java 17 (default)
@@ -38,6 +39,12 @@ pub async fn download_and_install_java17(app_handle: AppHandle) -> Result<String
     download_and_install_java_version(17, app_handle).await
 }
 
+/// Download and install Java 21
+#[command]
+pub async fn download_and_install_java21(app_handle: AppHandle) -> Result<String, String> {
+    download_and_install_java_version(21, app_handle).await
+}
+
 /// Download and install both Java 8 and Java 17
 #[command]
 pub async fn download_and_install_both_java(app_handle: AppHandle) -> Result<String, String> {
@@ -78,22 +85,38 @@ pub async fn download_and_install_both_java(app_handle: AppHandle) -> Result<Str
 /// Download and install specific Java version
 #[command]
 pub async fn download_and_install_java_version(major_version: u32, app_handle: AppHandle) -> Result<String, String> {
+    let started = std::time::Instant::now();
+    let result = install_java_version(major_version, &app_handle).await;
+
+    let (title, body) = match &result {
+        Ok(_) => ("Java installed", format!("Java {} is ready to use", major_version)),
+        Err(e) => ("Java install failed", format!("Java {}: {}", major_version, e)),
+    };
+    let target = NotificationTarget::View { view: "settings".to_string() };
+    notifications::send_if_slow(&app_handle, started, LauncherNotification::new(NotificationCategory::Installs, title, &body, Some(target))).await;
+
+    result
+}
+
+async fn install_java_version(major_version: u32, app_handle: &AppHandle) -> Result<String, String> {
     use std::fs;
     
     println!("🚀 Starting Java {} installation...", major_version);
     
-    let launcher_dir = crate::storage::get_launcher_dir();
-    let java_dir = launcher_dir.join("java").join(format!("java{}", major_version));
-    
-    // Check if already installed
-    #[cfg(target_os = "windows")]
-    let java_exe = java_dir.join("bin").join("java.exe");
-    #[cfg(not(target_os = "windows"))]
-    let java_exe = java_dir.join("bin").join("java");
+    let java_dir = crate::minecraft::java::runtime_dir(major_version);
     
-    if java_exe.exists() {
-        println!("✓ Java {} already installed at: {}", major_version, java_exe.display());
-        return Ok(java_exe.to_string_lossy().to_string());
+    // Check if already installed, a half-extracted or broken install is replaced
+    if java_dir.exists() {
+        match crate::minecraft::java::check_runtime(major_version).await {
+            Ok(path) => {
+                println!("✓ Java {} already installed at: {}", major_version, path);
+                return Ok(path);
+            }
+            Err(reason) => {
+                println!("🧹 Java {} install is broken ({}), reinstalling", major_version, reason);
+                crate::minecraft::java::remove_runtime(major_version).await?;
+            }
+        }
     }
     
     // Create directories
@@ -105,7 +128,7 @@ pub async fn download_and_install_java_version(major_version: u32, app_handle: A
     println!("📥 Fetching download info from: {}", api_url);
     
     // Fetch the API response to get the actual download URL
-    let api_response = reqwest::get(&api_url).await
+    let api_response = crate::http::client().get(&api_url).send().await
         .map_err(|e| format!("Failed to fetch Java download info: {}", e))?;
     
     if !api_response.status().is_success() {
@@ -172,20 +195,35 @@ pub async fn download_and_install_java_version(major_version: u32, app_handle: A
 }
 
 /// Get Java installations
+///
+/// Kept for older frontends, see `get_java_runtimes` for the details of each runtime.
 #[command]
 pub async fn get_java_installations() -> Result<Vec<String>, String> {
-    let mut java_paths = Vec::new();
-    
-    // Try to find Java 8 and 17
-    for version in [8, 17] {
-        if let Ok(java_path) = crate::minecraft::versions::get_java_for_version(version).await {
-            java_paths.push(format!("Java {}: {}", version, java_path));
-        } else {
-            java_paths.push(format!("Java {}: Not installed", version));
-        }
-    }
-    
-    Ok(java_paths)
+    let runtimes = get_java_runtimes().await?;
+    Ok([8, 17, 21].into_iter()
+        .map(|version| match runtimes.iter().find(|runtime| runtime.major_version == version) {
+            Some(runtime) => format!("Java {}: {}", version, runtime.path),
+            None => format!("Java {}: Not installed", version),
+        })
+        .collect())
+}
+
+/// Java runtimes on this machine with their version, vendor and architecture
+#[command]
+pub async fn get_java_runtimes() -> Result<Vec<crate::minecraft::java::JavaRuntime>, String> {
+    Ok(crate::minecraft::java::discover_runtimes().await)
+}
+
+/// Java runtimes the launcher installed, with their disk usage and whether they run
+#[command]
+pub async fn list_java_runtimes() -> Result<Vec<crate::minecraft::java::BundledJavaRuntime>, String> {
+    crate::minecraft::java::list_runtimes().await
+}
+
+/// Delete the launcher's Java installation of a major version, returning the bytes freed
+#[command]
+pub async fn remove_java_installation(major_version: u32) -> Result<u64, String> {
+    crate::minecraft::java::remove_runtime(major_version).await
 }
 
 /// Get required Java version for a Minecraft version
@@ -199,7 +237,7 @@ pub async fn get_required_java_version(minecraft_version: String) -> Result<u32,
 pub async fn get_java_for_minecraft_version(minecraft_version: String) -> Result<String, String> {
     let required_java_version = crate::minecraft::versions::get_required_java_version(&minecraft_version);
     println!("Minecraft {} requires Java {}", minecraft_version, required_java_version);
-    crate::minecraft::versions::get_java_for_version(required_java_version).await
+    Ok(crate::minecraft::java::resolve_java(required_java_version).await?.path)
 }
 
 /// Check if Java version is installed
@@ -227,62 +265,145 @@ pub async fn validate_java_installation(java_path: String) -> Result<String, Str
     }
 }
 
-/// Get system memory
+/// Total and currently available physical memory, in MB
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct SystemMemory {
+    pub total_mb: u64,
+    pub available_mb: u64,
+}
+
+/// Get total system memory in MB
+///
+/// Kept for callers that only need the total, see `get_system_memory_info`.
 #[command]
 pub async fn get_system_memory() -> Result<u64, String> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::mem;
-        use winapi::um::sysinfoapi::{GetPhysicallyInstalledSystemMemory, MEMORYSTATUSEX, GlobalMemoryStatusEx};
-        
-        unsafe {
-            let mut memory_kb = 0u64;
-            if GetPhysicallyInstalledSystemMemory(&mut memory_kb) != 0 {
-                Ok(memory_kb / 1024) // Convert to MB
-            } else {
-                // Fallback to GlobalMemoryStatusEx
-                let mut mem_status = MEMORYSTATUSEX {
-                    dwLength: mem::size_of::<MEMORYSTATUSEX>() as u32,
-                    ..mem::zeroed()
-                };
-                
-                if GlobalMemoryStatusEx(&mut mem_status) != 0 {
-                    Ok((mem_status.ullTotalPhys / (1024 * 1024)) as u64)
-                } else {
-                    Err("Failed to get system memory".to_string())
-                }
-            }
-        }
-    }
-    
-    #[cfg(not(target_os = "windows"))]
-    {
-        // For non-Windows systems, try reading /proc/meminfo
-        if let Ok(meminfo) = std::fs::read_to_string("/proc/meminfo") {
-            for line in meminfo.lines() {
-                if line.starts_with("MemTotal:") {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        if let Ok(kb) = parts[1].parse::<u64>() {
-                            return Ok(kb / 1024); // Convert to MB
-                        }
-                    }
-                }
-            }
+    Ok(system_memory()?.total_mb)
+}
+
+/// OpenGL version and renderer of the graphics driver, cached for a day unless `refresh` is set
+#[command]
+pub async fn probe_graphics(refresh: Option<bool>) -> Result<crate::minecraft::graphics::GraphicsProbe, String> {
+    Ok(crate::minecraft::graphics::probe(refresh.unwrap_or(false)).await)
+}
+
+/// Get total and available system memory
+#[command]
+pub async fn get_system_memory_info() -> Result<SystemMemory, String> {
+    system_memory()
+}
+
+/// Read total and available physical memory from the OS
+#[cfg(target_os = "windows")]
+pub fn system_memory() -> Result<SystemMemory, String> {
+    use std::mem;
+    use winapi::um::sysinfoapi::{GetPhysicallyInstalledSystemMemory, MEMORYSTATUSEX, GlobalMemoryStatusEx};
+
+    unsafe {
+        let mut mem_status = MEMORYSTATUSEX {
+            dwLength: mem::size_of::<MEMORYSTATUSEX>() as u32,
+            ..mem::zeroed()
+        };
+        if GlobalMemoryStatusEx(&mut mem_status) == 0 {
+            return Err("Failed to get system memory".to_string());
         }
-        
-        // Fallback to a reasonable default
-        Ok(8192) // 8GB default
+
+        // Installed memory matches what the user bought, GlobalMemoryStatusEx excludes reserved RAM
+        let mut memory_kb = 0u64;
+        let total_mb = if GetPhysicallyInstalledSystemMemory(&mut memory_kb) != 0 {
+            memory_kb / 1024
+        } else {
+            mem_status.ullTotalPhys / (1024 * 1024)
+        };
+
+        Ok(SystemMemory {
+            total_mb,
+            available_mb: mem_status.ullAvailPhys / (1024 * 1024),
+        })
     }
 }
 
+/// Read total and available physical memory from the OS
+#[cfg(target_os = "macos")]
+pub fn system_memory() -> Result<SystemMemory, String> {
+    let output = Command::new("sysctl")
+        .args(["-n", "hw.memsize"])
+        .output()
+        .map_err(|e| format!("Failed to run sysctl: {}", e))?;
+    let total_bytes = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("Failed to parse hw.memsize: {}", e))?;
+    let total_mb = total_bytes / (1024 * 1024);
+
+    // vm_stat reports host_statistics64 page counts
+    let available_mb = Command::new("vm_stat")
+        .output()
+        .ok()
+        .and_then(|o| parse_vm_stat(&String::from_utf8_lossy(&o.stdout)))
+        .map(|bytes| bytes / (1024 * 1024))
+        .unwrap_or(total_mb);
+
+    Ok(SystemMemory { total_mb, available_mb: available_mb.min(total_mb) })
+}
+
+/// Read total and available physical memory from the OS
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn system_memory() -> Result<SystemMemory, String> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo")
+        .map_err(|e| format!("Failed to read /proc/meminfo: {}", e))?;
+    parse_meminfo(&meminfo).ok_or_else(|| "MemTotal missing from /proc/meminfo".to_string())
+}
+
+/// Parse `/proc/meminfo`, falling back to free + cached memory on kernels without MemAvailable
+#[cfg_attr(any(target_os = "windows", target_os = "macos"), allow(dead_code))]
+fn parse_meminfo(meminfo: &str) -> Option<SystemMemory> {
+    let field = |name: &str| {
+        meminfo.lines()
+            .find(|line| line.starts_with(name) && line[name.len()..].starts_with(':'))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<u64>().ok())
+    };
+
+    let total_kb = field("MemTotal")?;
+    let available_kb = field("MemAvailable").unwrap_or_else(|| {
+        field("MemFree").unwrap_or(0) + field("Buffers").unwrap_or(0) + field("Cached").unwrap_or(0)
+    });
+
+    Some(SystemMemory {
+        total_mb: total_kb / 1024,
+        available_mb: available_kb.min(total_kb) / 1024,
+    })
+}
+
+/// Available bytes from `vm_stat` output: free, inactive, speculative and purgeable pages
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_vm_stat(output: &str) -> Option<u64> {
+    let page_size = output.lines().next()
+        .and_then(|line| line.split("page size of ").nth(1))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|size| size.parse::<u64>().ok())?;
+
+    let pages = |name: &str| {
+        output.lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| line.rsplit(':').next())
+            .and_then(|count| count.trim().trim_end_matches('.').parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+
+    let available = pages("Pages free") + pages("Pages inactive") + pages("Pages speculative") + pages("Pages purgeable");
+    Some(available * page_size)
+}
+
 /// Analyze Java requirements for an instance by scanning mod JARs
 #[command]
-pub async fn analyze_instance_java_requirements(instance_id: String) -> Result<crate::minecraft::mod_scanner::InstanceJavaAnalysis, String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
-    let instance = storage.get_instance(&instance_id)
+pub async fn analyze_instance_java_requirements(
+    instance_id: String,
+    storage: State<'_, StorageState>,
+) -> Result<crate::minecraft::mod_scanner::InstanceJavaAnalysis, String> {
+    let instance = storage.read().await
+        .get_instance(&instance_id)
+        .cloned()
         .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
     
     println!("🔍 Analyzing Java requirements for instance '{}'", instance.name);
@@ -366,36 +487,22 @@ async fn download_file_with_progress(
 ) -> Result<(), String> {
     println!("📥 Downloading from: {}", url);
     
-    let response = reqwest::get(url).await
-        .map_err(|e| format!("Failed to start download: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Download failed with status: {}", response.status()));
-    }
-    
-    let total_size = response.content_length().unwrap_or(0);
-    let mut downloaded = 0u64;
-    
-    let mut file = std::fs::File::create(dest)
-        .map_err(|e| format!("Failed to create file: {}", e))?;
-    
-    let mut stream = response.bytes_stream();
-    
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("Download error: {}", e))?;
-        file.write_all(&chunk)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
-        
-        downloaded += chunk.len() as u64;
-        
-        if total_size > 0 {
-            let progress = (downloaded as f64 / total_size as f64) * 80.0; // Reserve 20% for extraction
-            let _ = app_handle.emit("java_install_progress", serde_json::json!({
-                "stage": "Downloading Java...",
-                "progress": progress as u32
-            }));
-        }
-    }
+    let app_handle = app_handle.clone();
+    let options = crate::net::DownloadOptions {
+        progress: Some(std::sync::Arc::new(move |downloaded, total_size| {
+            if total_size > 0 {
+                let progress = (downloaded as f64 / total_size as f64) * 80.0; // Reserve 20% for extraction
+                let _ = app_handle.emit("java_install_progress", serde_json::json!({
+                    "stage": "Downloading Java...",
+                    "progress": progress as u32
+                }));
+            }
+        })),
+        ..Default::default()
+    };
+    let downloads = crate::net::manager::shared();
+    downloads.download(DownloadKind::Java, DownloadPriority::High, url, dest, options).await
+        .map_err(|e| format!("Failed to download Java: {}", e))?;
     
     println!("✓ Download completed: {}", dest.display());
     Ok(())
@@ -565,4 +672,33 @@ fn find_java_executable(java_dir: &PathBuf) -> Result<PathBuf, String> {
     }
     
     Err(format!("Java executable not found in {}", java_dir.display()))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meminfo_reports_total_and_available() {
+        let meminfo = "MemTotal:       16318480 kB\nMemFree:         1024000 kB\nMemAvailable:    8159240 kB\nBuffers:          204800 kB\n";
+        let memory = parse_meminfo(meminfo).unwrap();
+        assert_eq!(memory.total_mb, 15936);
+        assert_eq!(memory.available_mb, 7968);
+    }
+
+    #[test]
+    fn meminfo_without_mem_available_uses_free_and_cache() {
+        let meminfo = "MemTotal: 4194304 kB\nMemFree: 1048576 kB\nBuffers: 102400 kB\nCached: 946176 kB\n";
+        assert_eq!(parse_meminfo(meminfo).unwrap().available_mb, 2048);
+    }
+
+    #[test]
+    fn vm_stat_counts_reclaimable_pages() {
+        let output = "Mach Virtual Memory Statistics: (page size of 16384 bytes)\n\
+Pages free:                               10000.\n\
+Pages active:                            200000.\n\
+Pages inactive:                           50000.\n\
+Pages speculative:                         4000.\n\
+Pages purgeable:                           1536.\n";
+        assert_eq!(parse_vm_stat(output), Some(65536 * 16384));
+    }
+}
@@ -3,6 +3,12 @@ pub mod java;
 pub mod instances;
 pub mod external;
 pub mod settings;
+pub mod clone;
+pub mod network;
+pub mod import;
+pub mod worlds;
+pub mod servers;
+pub mod presets;
 
 // Re-export all commands for backwards compatibility
 pub use versions::*;
@@ -10,6 +16,12 @@ pub use java::*;
 pub use instances::*;
 pub use external::*;
 pub use settings::*;
+pub use clone::*;
+pub use network::*;
+pub use import::*;
+pub use worlds::*;
+pub use servers::*;
+pub use presets::*;
 
 // Re-export types that may be used by other modules
 pub use external::ExternalInstance;
\ No newline at end of file
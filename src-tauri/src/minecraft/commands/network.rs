@@ -0,0 +1,43 @@
+use tauri::{command, State};
+
+use crate::storage::StorageState;
+use crate::minecraft::lan::{self, LanWorldInfo, PortCheckResult};
+use crate::minecraft::sessions::PlaySessionsState;
+
+/// Check whether a port can be bound locally, optionally testing a loopback connection
+#[command]
+pub async fn check_port_open(port: u16, check_connect: Option<bool>) -> Result<PortCheckResult, String> {
+    let check_connect = check_connect.unwrap_or(true);
+    tokio::task::spawn_blocking(move || lan::check_port(port, check_connect)).await
+        .map_err(|e| format!("Port check failed: {}", e))
+}
+
+/// Get the LAN port of a world opened from a running instance along with this machine's addresses
+#[command]
+pub async fn get_lan_world_info(
+    instance_id: String,
+    storage: State<'_, StorageState>,
+    sessions: State<'_, PlaySessionsState>,
+) -> Result<LanWorldInfo, String> {
+    let game_dir = storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.game_dir.clone())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    let session_start = sessions.running_since(&instance_id).map(std::time::SystemTime::from);
+    let port = lan::find_lan_port(&game_dir, session_start).await?;
+    let ips = lan::get_lan_ips();
+
+    let addresses = match port {
+        Some(port) => ips.iter()
+            .map(|ip| std::net::SocketAddr::new(*ip, port).to_string())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(LanWorldInfo {
+        port,
+        lan_ips: ips.iter().map(|ip| ip.to_string()).collect(),
+        addresses,
+    })
+}
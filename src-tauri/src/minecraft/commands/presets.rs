@@ -0,0 +1,63 @@
+use tauri::{command, State};
+
+use crate::minecraft::presets::{self, PresetApplyResult, SettingsPreset};
+use crate::storage::StorageState;
+
+/// Save an instance's keybinds and the files or folders in `include_paths` as a named preset
+///
+/// A preset with the same name is replaced.
+#[command]
+pub async fn save_settings_preset(
+    name: String,
+    instance_id: String,
+    include_paths: Vec<String>,
+    storage: State<'_, StorageState>,
+) -> Result<SettingsPreset, String> {
+    let instance = storage.read().await
+        .get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    let preset = presets::save_preset(&presets::presets_dir(), &name, Some(instance.name.clone()), &instance.game_dir, &include_paths).await?;
+    println!("💾 Saved preset '{}' from {}: {} keybinds, {} files", preset.name, instance.name, preset.keybinds.len(), preset.files.len());
+    Ok(preset)
+}
+
+#[command]
+pub async fn list_settings_presets() -> Result<Vec<SettingsPreset>, String> {
+    Ok(presets::list_presets(&presets::presets_dir()).await)
+}
+
+/// Merge a preset's keybinds into an instance's options.txt and copy its files in
+///
+/// Keybinds and files the instance already has with other values are only
+/// replaced when `overwrite` is set, after backing them up.
+#[command]
+pub async fn apply_settings_preset(
+    instance_id: String,
+    name: String,
+    overwrite: bool,
+    storage: State<'_, StorageState>,
+) -> Result<PresetApplyResult, String> {
+    let game_dir = storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.game_dir.clone())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    let result = presets::apply_preset(&presets::presets_dir(), &game_dir, &name, overwrite).await?;
+    println!(
+        "🎛️  Applied preset '{}' to {}: {} keybinds added, {} files copied, {} conflicts, {} skipped",
+        name,
+        instance_id,
+        result.keybinds_added.len(),
+        result.files_copied.len(),
+        result.keybind_conflicts.len() + result.file_conflicts.len(),
+        result.skipped.len()
+    );
+    Ok(result)
+}
+
+#[command]
+pub async fn delete_settings_preset(name: String) -> Result<(), String> {
+    presets::delete_preset(&presets::presets_dir(), &name).await
+}
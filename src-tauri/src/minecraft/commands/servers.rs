@@ -0,0 +1,65 @@
+use tauri::{command, AppHandle, State};
+
+use crate::minecraft::sessions::PlaySessionsState;
+use crate::minecraft::servers::{self, RecentServer, ServerAddress};
+use crate::minecraft::MinecraftInstance;
+use crate::storage::StorageState;
+
+/// Servers an instance launched into, most recent first
+#[command]
+pub async fn get_recent_servers(instance_id: String, storage: State<'_, StorageState>) -> Result<Vec<RecentServer>, String> {
+    storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.recent_servers.clone())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))
+}
+
+/// Launch an instance and join a multiplayer server once the game has started
+///
+/// `address` is `host[:port]`, the port defaults to 25565. Without `memory` the
+/// launcher's default is used.
+#[command]
+pub async fn join_server(
+    instance_id: String,
+    address: String,
+    memory: Option<u32>,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+    sessions: State<'_, PlaySessionsState>,
+) -> Result<(), String> {
+    let server = ServerAddress::parse(&address)?;
+    super::instances::ensure_not_running(&instance_id, &sessions).await?;
+
+    let storage = storage.read().await;
+    let metadata = storage.get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    let memory = memory.unwrap_or(storage.get_settings().default_memory);
+    storage.flush().await
+        .map_err(|e| format!("Failed to save launcher config: {}", e))?;
+    drop(storage);
+
+    let mut instance = MinecraftInstance::from(metadata);
+    println!("🌐 Launching {} into server {}", instance.name, server);
+    instance.game_args.get_or_insert_with(Vec::new).extend(servers::join_args(&instance.version, &server));
+
+    let auth_info = super::instances::launch_auth_info(None).await?;
+    println!("🔐 Using authentication: {} ({})", auth_info.username, auth_info.user_type);
+
+    let result = crate::minecraft::launch_minecraft(&instance, Some(auth_info), memory, &app_handle).await?;
+    super::instances::track_launch(result, &instance, &app_handle, &sessions)?;
+    record_join(&instance.id, &server, &app_handle).await;
+    Ok(())
+}
+
+/// Remember a server an instance was launched into
+pub(crate) async fn record_join(instance_id: &str, server: &ServerAddress, app_handle: &AppHandle) {
+    let Ok(storage) = crate::storage::shared().await else {
+        return;
+    };
+    if let Err(e) = storage.write().await.record_server_join(instance_id, server, chrono::Utc::now()).await {
+        println!("⚠️  Failed to remember server {} for instance {}: {}", server, instance_id, e);
+        return;
+    }
+    super::instances::notify_instances_updated(app_handle);
+}
@@ -1,23 +1,68 @@
-use tauri::command;
-use crate::storage::StorageManager;
+use tauri::{command, AppHandle, State};
+use crate::storage::StorageState;
 
 /// Get launcher settings
 #[command]
-pub async fn get_launcher_settings() -> Result<crate::storage::LauncherSettings, String> {
-    let storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
-    Ok(storage.get_settings().clone())
+pub async fn get_launcher_settings(storage: State<'_, StorageState>) -> Result<crate::storage::LauncherSettings, String> {
+    Ok(storage.read().await.get_settings().clone())
 }
 
 /// Update launcher settings
 #[command]
-pub async fn update_launcher_settings(settings: crate::storage::LauncherSettings) -> Result<(), String> {
-    let mut storage = StorageManager::new().await
-        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
-    
-    let _ = storage.update_settings(settings).await;
-    
-    storage.save().await
-        .map_err(|e| format!("Failed to save storage: {}", e))
-}
\ No newline at end of file
+pub async fn update_launcher_settings(
+    mut settings: crate::storage::LauncherSettings,
+    storage: State<'_, StorageState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    crate::minecraft::window::validate(Some(settings.window_width), Some(settings.window_height))?;
+    crate::http::validate(&settings.proxy)?;
+    if settings.allow_offline_launch {
+        let username = settings.offline_username.as_deref().unwrap_or_default();
+        crate::auth::validate_offline_username(username)?;
+    }
+    let mut storage = storage.write().await;
+    // The active account is only changed through set_active_account
+    settings.active_account_uuid = storage.get_settings().active_account_uuid.clone();
+    // The manual token lives in the credential store, never in the config
+    let auth_token = settings.auth_token.take();
+    // So is the proxy password, an empty one removes it
+    let proxy_password = settings.proxy.password.take();
+    let proxy = settings.proxy.clone();
+    storage.update_settings(settings).await
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    drop(storage);
+    if let Some(token) = auth_token {
+        crate::secrets::set(crate::secrets::AUTH_TOKEN_KEY, &token).await
+            .map_err(|e| format!("Failed to save auth token: {}", e))?;
+    }
+    match proxy_password.as_deref() {
+        Some("") => crate::secrets::delete(crate::http::PROXY_PASSWORD_KEY).await
+            .map_err(|e| format!("Failed to remove proxy password: {}", e))?,
+        Some(password) => crate::secrets::set(crate::http::PROXY_PASSWORD_KEY, password).await
+            .map_err(|e| format!("Failed to save proxy password: {}", e))?,
+        None => {}
+    }
+    crate::http::configure(&proxy).await?;
+    // Turning the jump list off has to clear what the shell already shows
+    crate::jumplist::refresh(&app_handle).await;
+    Ok(())
+}
+
+/// Get the record of storage migrations that have run
+#[command]
+pub async fn get_storage_migration_log() -> Result<Vec<crate::migrations::MigrationLogEntry>, String> {
+    crate::migrations::read_log(&crate::storage::get_migration_log_path()).await
+        .map_err(|e| format!("Failed to read migration log: {}", e))
+}
+
+/// Get the data directories used in this session and whether portable mode is on
+#[command]
+pub async fn get_data_locations() -> Result<crate::portable::DataLocations, String> {
+    Ok(crate::portable::data_locations().clone())
+}
+
+/// Copy launcher data beside `dest` so ChaiLauncher can run portably from there
+#[command]
+pub async fn convert_to_portable(dest: String) -> Result<crate::portable::PortableConversionResult, String> {
+    crate::portable::convert_to_portable(std::path::Path::new(&dest)).await
+}
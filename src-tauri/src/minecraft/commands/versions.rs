@@ -1,39 +1,31 @@
-use serde::{Deserialize, Serialize};
 use tauri::{command, Emitter};
 use std::path::PathBuf;
 use tokio::fs;
 use reqwest;
+use futures::stream::{self, StreamExt};
 
-use crate::minecraft::versions::load_version_manifest;
+use crate::minecraft::assets::resolve_asset_index;
+use crate::minecraft::versions::{VersionFilter, VersionManifest};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct VersionManifest {
-    pub latest: LatestVersions,
-    pub versions: Vec<MinecraftVersionInfo>,
-}
+/// Asset objects downloaded at once unless the settings say otherwise
+pub const DEFAULT_ASSET_CONCURRENCY: u32 = 16;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LatestVersions {
-    pub release: String,
-    pub snapshot: String,
-}
+/// Extra attempts for an asset object before it counts as failed
+const ASSET_RETRIES: u32 = 3;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct MinecraftVersionInfo {
-    pub id: String,
-    pub r#type: String,
-    pub url: String,
-    pub time: String,
-    #[serde(rename = "releaseTime")]
-    pub release_time: String,
+/// Entry from an asset index
+struct AssetObject {
+    hash: String,
+    size: u64,
 }
 
-/// Get Minecraft versions from Mojang API
-#[command]
-pub async fn get_minecraft_versions() -> Result<VersionManifest, String> {
+/// Mojang's version manifest with the latest versions flagged
+///
+/// Served from the on-disk copy when it is recent or Mojang can't be reached,
+/// `force_refresh` asks Mojang regardless.
+pub async fn fetch_version_manifest(force_refresh: bool) -> Result<VersionManifest, String> {
     let url = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
-    
-    let response = reqwest::get(url).await
+    let cached = crate::net::meta::fetch_json::<VersionManifest>(url, "version_manifest_v2.json", force_refresh).await
         .map_err(|e| {
             let os_info = if cfg!(target_os = "macos") {
                 "macOS"
@@ -42,12 +34,31 @@ pub async fn get_minecraft_versions() -> Result<VersionManifest, String> {
             } else {
                 "Linux"
             };
-            format!("Failed to fetch version manifest from {} (Platform: {}): {}", url, os_info, e)
+            format!("Failed to load version manifest (Platform: {}): {}", os_info, e)
         })?;
     
-    let manifest: VersionManifest = response.json().await
-        .map_err(|e| format!("Failed to parse version manifest JSON: {}. The response may be malformed or the API may have changed.", e))?;
-    
+    let mut manifest = cached.value;
+    manifest.cache_age_secs = cached.age_secs;
+    manifest.mark_latest();
+    Ok(manifest)
+}
+
+/// Get Minecraft versions from Mojang API
+///
+/// Each `include_*` left out counts as true, so without them every version is listed.
+#[command]
+pub async fn get_minecraft_versions(
+    force_refresh: Option<bool>,
+    include_snapshots: Option<bool>,
+    include_old_beta: Option<bool>,
+    include_old_alpha: Option<bool>,
+) -> Result<VersionManifest, String> {
+    let mut manifest = fetch_version_manifest(force_refresh.unwrap_or(false)).await?;
+    manifest.retain(VersionFilter {
+        snapshots: include_snapshots.unwrap_or(true),
+        old_beta: include_old_beta.unwrap_or(true),
+        old_alpha: include_old_alpha.unwrap_or(true),
+    });
     Ok(manifest)
 }
 
@@ -62,67 +73,60 @@ pub async fn download_minecraft_assets(version: String, game_dir: String) -> Res
         .map_err(|e| format!("Failed to create assets directory: {}", e))?;
     
     // Load version manifest to get asset index
-    if let Ok(Some(version_json)) = load_version_manifest(&game_path, &version).await {
-        if let Some(asset_index) = version_json.get("assetIndex") {
-            let index_id = asset_index.get("id").and_then(|v| v.as_str()).unwrap_or(&version);
-            let index_url = asset_index.get("url").and_then(|v| v.as_str());
+    if let Ok(Some(asset_index)) = resolve_asset_index(&game_path, &version).await {
+        let index_id = asset_index.id.as_str();
+        
+        if let Some(url) = asset_index.url.as_deref() {
+            println!("📥 Downloading asset index for {}", version);
             
-            if let Some(url) = index_url {
-                println!("📥 Downloading asset index for {}", version);
-                
-                // Download asset index
-                let indexes_dir = assets_dir.join("indexes");
-                fs::create_dir_all(&indexes_dir).await
-                    .map_err(|e| format!("Failed to create indexes directory: {}", e))?;
-                
-                let index_file = indexes_dir.join(format!("{}.json", index_id));
-                let response = reqwest::get(url).await
-                    .map_err(|e| format!("Failed to download asset index: {}", e))?;
-                
-                let index_content = response.text().await
-                    .map_err(|e| format!("Failed to read asset index: {}", e))?;
-                
-                fs::write(&index_file, &index_content).await
-                    .map_err(|e| format!("Failed to write asset index: {}", e))?;
-                
-                // Parse and download assets
-                if let Ok(index_json) = serde_json::from_str::<serde_json::Value>(&index_content) {
-                    if let Some(objects) = index_json.get("objects").and_then(|v| v.as_object()) {
-                        let objects_dir = assets_dir.join("objects");
-                        fs::create_dir_all(&objects_dir).await
-                            .map_err(|e| format!("Failed to create objects directory: {}", e))?;
-                        
-                        let mut downloaded = 0;
-                        let total = objects.len();
-                        
-                        for (_name, asset_info) in objects.iter() {
-                            if let Some(hash) = asset_info.get("hash").and_then(|v| v.as_str()) {
-                                let hash_prefix = &hash[0..2];
-                                let object_dir = objects_dir.join(hash_prefix);
-                                let object_file = object_dir.join(hash);
-                                
-                                if !object_file.exists() {
-                                    fs::create_dir_all(&object_dir).await
-                                        .map_err(|e| format!("Failed to create object directory: {}", e))?;
-                                    
-                                    let asset_url = format!("https://resources.download.minecraft.net/{}/{}", hash_prefix, hash);
-                                    
-                                    if let Ok(response) = reqwest::get(&asset_url).await {
-                                        if let Ok(bytes) = response.bytes().await {
-                                            let _ = fs::write(&object_file, &bytes).await;
-                                        }
-                                    }
-                                }
-                            }
-                            downloaded += 1;
+            // Download asset index
+            let indexes_dir = assets_dir.join("indexes");
+            fs::create_dir_all(&indexes_dir).await
+                .map_err(|e| format!("Failed to create indexes directory: {}", e))?;
+            
+            let index_file = indexes_dir.join(format!("{}.json", index_id));
+            let mirror = crate::net::mirror::current().await;
+            crate::net::mirror::download(&mirror, url, &index_file, Default::default()).await
+                .map_err(|e| format!("Failed to download asset index: {}", e))?;
+            
+            let index_content = fs::read_to_string(&index_file).await
+                .map_err(|e| format!("Failed to read asset index: {}", e))?;
+            
+            // Parse and download assets
+            if let Ok(index_json) = serde_json::from_str::<serde_json::Value>(&index_content) {
+                if let Some(objects) = index_json.get("objects").and_then(|v| v.as_object()) {
+                    let objects_dir = assets_dir.join("objects");
+                    fs::create_dir_all(&objects_dir).await
+                        .map_err(|e| format!("Failed to create objects directory: {}", e))?;
+                    
+                    let mut downloaded = 0;
+                    let total = objects.len();
+                    
+                    for (_name, asset_info) in objects.iter() {
+                        if let Some(hash) = asset_info.get("hash").and_then(|v| v.as_str()) {
+                            let hash_prefix = &hash[0..2];
+                            let object_file = objects_dir.join(hash_prefix).join(hash);
                             
-                            if downloaded % 50 == 0 {
-                                println!("📦 Downloaded {}/{} assets", downloaded, total);
+                            if !object_file.exists() {
+                                let asset_url = format!("https://resources.download.minecraft.net/{}/{}", hash_prefix, hash);
+                                let options = crate::net::DownloadOptions {
+                                    sha1: Some(hash.to_string()),
+                                    ..Default::default()
+                                };
+                                let _ = crate::net::mirror::download(&mirror, &asset_url, &object_file, options).await;
                             }
                         }
+                        downloaded += 1;
                         
-                        println!("✓ Downloaded {} assets for {}", total, version);
+                        if downloaded % 50 == 0 {
+                            println!("📦 Downloaded {}/{} assets", downloaded, total);
+                        }
                     }
+                    
+                    println!("✓ Downloaded {} assets for {}", total, version);
+                    
+                    // Versions before 1.7.3 read them by name
+                    crate::minecraft::assets::build_named_layout(&game_path, index_id).await?;
                 }
             }
         }
@@ -154,100 +158,190 @@ pub async fn download_minecraft_assets_with_progress(
     }));
     
     // Load version manifest to get asset index
-    if let Ok(Some(version_json)) = load_version_manifest(&game_path, &version).await {
-        if let Some(asset_index) = version_json.get("assetIndex") {
-            let index_id = asset_index.get("id").and_then(|v| v.as_str()).unwrap_or(&version);
-            let index_url = asset_index.get("url").and_then(|v| v.as_str());
+    if let Ok(Some(asset_index)) = resolve_asset_index(&game_path, &version).await {
+        let index_id = asset_index.id.as_str();
+        
+        if let Some(url) = asset_index.url.as_deref() {
+            // Emit progress update
+            let _ = app_handle.emit("download_progress", serde_json::json!({
+                "instance_id": instance_id,
+                "phase": "assets",
+                "progress": 10,
+                "message": format!("Downloading asset index for {}", version)
+            }));
             
-            if let Some(url) = index_url {
-                // Emit progress update
-                let _ = app_handle.emit("download_progress", serde_json::json!({
-                    "instance_id": instance_id,
-                    "phase": "assets",
-                    "progress": 10,
-                    "message": format!("Downloading asset index for {}", version)
-                }));
-                
-                // Download asset index
-                let indexes_dir = assets_dir.join("indexes");
-                fs::create_dir_all(&indexes_dir).await
-                    .map_err(|e| format!("Failed to create indexes directory: {}", e))?;
-                
-                let index_file = indexes_dir.join(format!("{}.json", index_id));
-                let response = reqwest::get(url).await
-                    .map_err(|e| format!("Failed to download asset index: {}", e))?;
-                
-                let index_content = response.text().await
-                    .map_err(|e| format!("Failed to read asset index: {}", e))?;
-                
-                fs::write(&index_file, &index_content).await
-                    .map_err(|e| format!("Failed to write asset index: {}", e))?;
-                
-                // Parse and download assets
-                if let Ok(index_json) = serde_json::from_str::<serde_json::Value>(&index_content) {
-                    if let Some(objects) = index_json.get("objects").and_then(|v| v.as_object()) {
-                        let objects_dir = assets_dir.join("objects");
-                        fs::create_dir_all(&objects_dir).await
-                            .map_err(|e| format!("Failed to create objects directory: {}", e))?;
-                        
-                        let mut downloaded = 0;
-                        let total = objects.len();
-                        
-                        // Emit progress update
-                        let _ = app_handle.emit("download_progress", serde_json::json!({
-                            "instance_id": instance_id,
-                            "phase": "assets",
-                            "progress": 20,
-                            "message": format!("Downloading {} assets", total)
-                        }));
-                        
-                        for (_name, asset_info) in objects.iter() {
-                            if let Some(hash) = asset_info.get("hash").and_then(|v| v.as_str()) {
-                                let hash_prefix = &hash[0..2];
-                                let object_dir = objects_dir.join(hash_prefix);
-                                let object_file = object_dir.join(hash);
-                                
-                                if !object_file.exists() {
-                                    fs::create_dir_all(&object_dir).await
-                                        .map_err(|e| format!("Failed to create object directory: {}", e))?;
-                                    
-                                    let asset_url = format!("https://resources.download.minecraft.net/{}/{}", hash_prefix, hash);
-                                    
-                                    if let Ok(response) = reqwest::get(&asset_url).await {
-                                        if let Ok(bytes) = response.bytes().await {
-                                            let _ = fs::write(&object_file, &bytes).await;
-                                        }
-                                    }
-                                }
-                            }
-                            downloaded += 1;
-                            
-                            // Update progress every 50 assets
-                            if downloaded % 50 == 0 {
-                                let progress = 20 + ((downloaded as f64 / total as f64) * 70.0) as u32;
-                                let _ = app_handle.emit("download_progress", serde_json::json!({
-                                    "instance_id": instance_id,
-                                    "phase": "assets",
-                                    "progress": progress,
-                                    "message": format!("Downloaded {}/{} assets", downloaded, total)
-                                }));
+            // Download asset index
+            let indexes_dir = assets_dir.join("indexes");
+            fs::create_dir_all(&indexes_dir).await
+                .map_err(|e| format!("Failed to create indexes directory: {}", e))?;
+            
+            let index_file = indexes_dir.join(format!("{}.json", index_id));
+            let mirror = crate::net::mirror::current().await;
+            crate::net::mirror::download(&mirror, url, &index_file, Default::default()).await
+                .map_err(|e| format!("Failed to download asset index: {}", e))?;
+            
+            let index_content = fs::read_to_string(&index_file).await
+                .map_err(|e| format!("Failed to read asset index: {}", e))?;
+            
+            // Parse and download assets
+            if let Ok(index_json) = serde_json::from_str::<serde_json::Value>(&index_content) {
+                if let Some(objects) = index_json.get("objects").and_then(|v| v.as_object()) {
+                    let objects_dir = assets_dir.join("objects");
+                    fs::create_dir_all(&objects_dir).await
+                        .map_err(|e| format!("Failed to create objects directory: {}", e))?;
+                    
+                    let assets: Vec<AssetObject> = objects.values()
+                        .filter_map(|info| {
+                            let hash = info.get("hash").and_then(|v| v.as_str())?;
+                            (hash.len() > 2).then(|| AssetObject {
+                                hash: hash.to_string(),
+                                size: info.get("size").and_then(|v| v.as_u64()).unwrap_or(0),
+                            })
+                        })
+                        .collect();
+                    let total = assets.len();
+                    let total_bytes: u64 = assets.iter().map(|asset| asset.size).sum();
+                    let concurrency = asset_download_concurrency().await;
+                    
+                    // Emit progress update
+                    let _ = app_handle.emit("download_progress", serde_json::json!({
+                        "instance_id": instance_id,
+                        "phase": "assets",
+                        "progress": 20,
+                        "message": format!("Downloading {} assets", total),
+                        "total_bytes": total_bytes
+                    }));
+                    
+                    let client = crate::http::client();
+                    let mut results = stream::iter(assets)
+                        .map(|asset| download_asset_object(&client, &mirror, &objects_dir, asset))
+                        .buffer_unordered(concurrency);
+                    
+                    let mut downloaded = 0;
+                    let mut downloaded_bytes = 0u64;
+                    let mut failed = Vec::new();
+                    
+                    while let Some((asset, result)) = results.next().await {
+                        downloaded += 1;
+                        match result {
+                            Ok(()) => downloaded_bytes += asset.size,
+                            Err(e) => {
+                                eprintln!("❌ Failed to download asset {}: {}", asset.hash, e);
+                                failed.push(asset.hash);
                             }
                         }
                         
-                        // Emit completion
-                        let _ = app_handle.emit("download_progress", serde_json::json!({
-                            "instance_id": instance_id,
-                            "phase": "assets",
-                            "progress": 100,
-                            "message": format!("Downloaded {} assets for {}", total, version)
-                        }));
-                        
-                        println!("✓ Downloaded {} assets for {}", total, version);
+                        // Update progress every 10 assets
+                        if downloaded % 10 == 0 || downloaded == total {
+                            let progress = 20 + ((downloaded as f64 / total as f64) * 70.0) as u32;
+                            let _ = app_handle.emit("download_progress", serde_json::json!({
+                                "instance_id": instance_id,
+                                "phase": "assets",
+                                "progress": progress,
+                                "message": format!("Downloaded {}/{} assets", downloaded, total),
+                                "downloaded_bytes": downloaded_bytes,
+                                "total_bytes": total_bytes
+                            }));
+                        }
+                    }
+                    
+                    if !failed.is_empty() {
+                        return Err(failed_assets_error(&failed, total));
                     }
+                    
+                    // Emit completion
+                    let _ = app_handle.emit("download_progress", serde_json::json!({
+                        "instance_id": instance_id,
+                        "phase": "assets",
+                        "progress": 100,
+                        "message": format!("Downloaded {} assets for {}", total, version)
+                    }));
+                    
+                    println!("✓ Downloaded {} assets for {}", total, version);
+                    
+                    // Versions before 1.7.3 read them by name
+                    crate::minecraft::assets::build_named_layout(&game_path, index_id).await?;
                 }
             }
         }
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Hashes of the objects in a version's asset index that are not on disk
+///
+/// Empty when the version has no asset index or it isn't downloaded, as there is nothing to compare against.
+pub(crate) async fn missing_asset_objects(game_dir: &std::path::Path, version: &str) -> Vec<String> {
+    let Ok(Some(asset_index)) = resolve_asset_index(game_dir, version).await else {
+        return Vec::new();
+    };
+    let assets_dir = game_dir.join("assets");
+    let index_file = assets_dir.join("indexes").join(format!("{}.json", asset_index.id));
+    let Some(index_json) = fs::read_to_string(&index_file).await.ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok()) else {
+        return Vec::new();
+    };
+    let Some(objects) = index_json.get("objects").and_then(|v| v.as_object()) else {
+        return Vec::new();
+    };
+
+    objects.values()
+        .filter_map(|info| info.get("hash").and_then(|v| v.as_str()))
+        .filter(|hash| hash.len() > 2 && !assets_dir.join("objects").join(&hash[0..2]).join(hash).is_file())
+        .map(String::from)
+        .collect()
+}
+
+/// Concurrency from the launcher settings, falling back to the default
+async fn asset_download_concurrency() -> usize {
+    let concurrency = match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().max_concurrent_downloads,
+        Err(_) => DEFAULT_ASSET_CONCURRENCY,
+    };
+    concurrency.max(1) as usize
+}
+
+/// Download one asset object unless it already exists, retrying failed attempts
+async fn download_asset_object(
+    client: &reqwest::Client,
+    mirror: &crate::net::DownloadMirror,
+    objects_dir: &std::path::Path,
+    asset: AssetObject,
+) -> (AssetObject, Result<(), String>) {
+    let hash_prefix = &asset.hash[0..2];
+    let object_file = objects_dir.join(hash_prefix).join(&asset.hash);
+    
+    if object_file.exists() {
+        return (asset, Ok(()));
+    }
+    
+    let url = format!("https://resources.download.minecraft.net/{}/{}", hash_prefix, asset.hash);
+    let options = crate::net::DownloadOptions {
+        client: Some(client.clone()),
+        expected_size: (asset.size > 0).then_some(asset.size),
+        sha1: Some(asset.hash.clone()),
+        retries: ASSET_RETRIES,
+        ..Default::default()
+    };
+    let result = crate::net::mirror::download(mirror, &url, &object_file, options).await
+        .map(|_| ())
+        .map_err(|e| e.to_string());
+    
+    (asset, result)
+}
+
+/// Error listing the assets that still failed after all retries
+fn failed_assets_error(failed: &[String], total: usize) -> String {
+    const SHOWN: usize = 10;
+    
+    let mut hashes = failed.iter().take(SHOWN).cloned().collect::<Vec<_>>().join(", ");
+    if failed.len() > SHOWN {
+        hashes.push_str(&format!(" and {} more", failed.len() - SHOWN));
+    }
+    
+    format!(
+        "Failed to download {} of {} assets after {} retries: {}",
+        failed.len(), total, ASSET_RETRIES, hashes
+    )
+}
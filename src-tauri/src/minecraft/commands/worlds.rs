@@ -0,0 +1,63 @@
+use tauri::{command, AppHandle, State};
+
+use crate::minecraft::sessions::PlaySessionsState;
+use crate::storage::StorageState;
+use crate::minecraft::MinecraftInstance;
+use crate::minecraft::worlds::{self, InstanceWorlds};
+
+/// List an instance's singleplayer worlds and whether it can launch straight into one
+#[command]
+pub async fn list_instance_worlds(instance_id: String, storage: State<'_, StorageState>) -> Result<InstanceWorlds, String> {
+    let (game_dir, version) = storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| (instance.game_dir.clone(), instance.version.clone()))
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    Ok(InstanceWorlds {
+        quick_play_supported: worlds::supports_quick_play(&version),
+        worlds: worlds::list_worlds(&game_dir).await,
+    })
+}
+
+/// Launch an instance and open a singleplayer world once the game has started
+///
+/// Versions before 1.20 can't do this, they launch to the title screen instead.
+#[command]
+pub async fn launch_instance_into_world(
+    instance_id: String,
+    world_folder: String,
+    memory: u32,
+    app_handle: AppHandle,
+    storage: State<'_, StorageState>,
+    sessions: State<'_, PlaySessionsState>,
+) -> Result<(), String> {
+    super::instances::ensure_not_running(&instance_id, &sessions).await?;
+
+    let storage = storage.read().await;
+    let metadata = storage.get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    storage.flush().await
+        .map_err(|e| format!("Failed to save launcher config: {}", e))?;
+    drop(storage);
+
+    worlds::world_path(&metadata.game_dir, &world_folder)?;
+
+    let mut instance = MinecraftInstance::from(metadata);
+    match worlds::quick_play_args(&instance.version, &world_folder) {
+        Some(args) => {
+            println!("🌍 Launching {} into world '{}'", instance.name, world_folder);
+            instance.game_args.get_or_insert_with(Vec::new).extend(args);
+        }
+        None => println!(
+            "⚠️  Minecraft {} does not support quick play, launching {} without opening '{}'",
+            instance.version, instance.name, world_folder
+        ),
+    }
+
+    let auth_info = super::instances::launch_auth_info(None).await?;
+    println!("🔐 Using authentication: {} ({})", auth_info.username, auth_info.user_type);
+
+    let result = crate::minecraft::launch_minecraft(&instance, Some(auth_info), memory, &app_handle).await?;
+    super::instances::track_launch(result, &instance, &app_handle, &sessions)
+}
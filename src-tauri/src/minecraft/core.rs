@@ -39,6 +39,8 @@ pub struct SimpleMCVMInstance {
     pub version: String,
     pub game_dir: PathBuf,
     pub config: InstanceStoredConfig,
+    /// Spawn the game with only `config.launch.env`
+    pub clear_inherited_env: bool,
 }
 
 /// ChaiLauncher's MCVM integration wrapper
@@ -50,9 +52,7 @@ impl MCVMCore {
         println!("Initializing MCVM integration with ChaiLauncher paths...");
         
         // Get ChaiLauncher's data directory
-        let chai_data_dir = dirs::data_local_dir()
-            .ok_or("Failed to get local data directory".to_string())?
-            .join("ChaiLauncher");
+        let chai_data_dir = crate::storage::get_launcher_dir();
         
         // Create MCVM paths using ChaiLauncher's directory structure
         let paths = Paths::with_data_dir(chai_data_dir).await
@@ -129,6 +129,7 @@ impl MCVMCore {
             version: version.to_string(),
             game_dir,
             config: stored_config,
+            clear_inherited_env: false,
         };
         
         println!("MCVM instance created for '{}' version {} with modloader: {:?}", name, version, modloader);
@@ -171,6 +172,11 @@ impl MCVMCore {
         
         // Create MCVM instance with proper configuration
         let instance_id: InstanceID = Arc::from(instance.name.as_str());
+        let mut config = instance.config;
+        // The game would otherwise inherit the launcher's environment when it is spawned
+        if instance.clear_inherited_env {
+            config.launch.wrapper = Some(super::environment::clean_env_wrapper(&config.launch.env)?);
+        }
         
         // MCVM will use the working directory we launch it from to find mods and config
         // Make sure we set the current directory to the instance's game directory
@@ -185,7 +191,7 @@ impl MCVMCore {
                 }
             },
             instance_id,
-            instance.config,
+            config,
         );
 
         // Set ChaiLauncher's custom game directory instead of letting MCVM create its own
@@ -0,0 +1,515 @@
+//! Crash signatures and per-instance stability
+//!
+//! Every finished game session is counted as a clean exit, a crash or a JVM hard
+//! crash. Crashes are grouped by signature, the exception class plus the mod
+//! suspected of causing it, so a crash that keeps coming back stands out. The
+//! newest crash report can also be read in full, with a hint for common failures.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Distinct crash signatures kept per instance, the least recently seen is dropped first
+const MAX_SIGNATURES: usize = 20;
+
+/// Sessions needed before an instance can be called unstable
+const MIN_SESSIONS_FOR_UNSTABLE: u32 = 3;
+
+/// Share of crashed sessions at which an instance is unstable
+const UNSTABLE_CRASH_RATE: f64 = 0.3;
+
+/// Packages whose frames say nothing about which mod crashed
+const NON_MOD_PACKAGES: &[&str] = &[
+    "java.", "javax.", "jdk.", "sun.", "com.sun.", "net.minecraft.", "com.mojang.",
+    "org.lwjgl.", "net.fabricmc.", "org.quiltmc.loader.", "net.minecraftforge.", "net.neoforged.",
+    "cpw.mods.", "org.spongepowered.", "io.netty.", "com.google.", "org.apache.",
+];
+
+/// Text found in a crash report and what it usually means, the first match wins
+const CRASH_HINTS: &[(&[&str], &str)] = &[
+    (&["java.lang.OutOfMemoryError"],
+        "Minecraft ran out of memory. Give the instance more memory in its settings."),
+    (&["java.lang.UnsupportedClassVersionError", "has been compiled by a more recent version of the Java Runtime"],
+        "A mod needs a newer Java version than the instance uses. Pick a newer Java in the instance settings."),
+    (&["DuplicateModsFoundException", "Found duplicate mods", "Duplicate mods found"],
+        "The same mod is installed twice. Remove one of the copies from the mods folder."),
+    (&["Missing or unsupported mandatory dependencies", "MissingModsException", "ModResolutionException", "Incompatible mod set", "requires any version of"],
+        "A mod is missing a dependency or needs a different version of one. Install or update the mods it names."),
+    (&["MixinApplyError", "InvalidInjectionException", "InvalidMixinException", "MixinTransformerError", "Mixin apply failed"],
+        "A mixin failed to apply. Two mods conflict, or one doesn't support this Minecraft or loader version."),
+    (&["java.lang.NoClassDefFoundError", "java.lang.ClassNotFoundException"],
+        "A mod uses code that isn't there. This is usually a missing dependency or a mod made for another loader or Minecraft version."),
+    (&["java.lang.NoSuchMethodError", "java.lang.NoSuchFieldError", "java.lang.AbstractMethodError"],
+        "A mod was built for a different version of Minecraft or of a mod it depends on. Update it or the dependency."),
+    (&["Pixel format not accelerated", "GLFW error 65542", "does not appear to support OpenGL"],
+        "Your graphics driver doesn't support the OpenGL version Minecraft needs. Update your graphics drivers."),
+    (&["java.lang.StackOverflowError"],
+        "The game got stuck in endless recursion, which usually means two mods conflict."),
+    (&["java.util.ConcurrentModificationException"],
+        "A mod changed game data from the wrong thread. This is a bug in the suspected mod, try updating it."),
+];
+
+/// How a game session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionOutcome {
+    Clean,
+    /// Minecraft crashed, with a crash report or a non-zero exit code
+    Crash,
+    /// The JVM itself died and wrote an `hs_err_pid*.log`
+    JvmCrash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashSignature {
+    /// Exception class, or the signal for JVM crashes
+    pub exception: String,
+    /// Mod named by the crash report or the native library for JVM crashes
+    pub suspected_mod: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashSignatureCount {
+    #[serde(flatten)]
+    pub signature: CrashSignature,
+    pub count: u32,
+    pub last_seen: String,
+}
+
+/// The interesting parts of a Minecraft crash report
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub file_name: String,
+    pub path: String,
+    /// As written in the report, in the player's local time
+    pub time: Option<String>,
+    pub description: Option<String>,
+    /// Exception class
+    pub exception: Option<String>,
+    pub exception_message: Option<String>,
+    /// From `Suspected Mod`, `Mod File` and `Mod ID` lines
+    pub suspected_mods: Vec<String>,
+    pub minecraft_version: Option<String>,
+    pub loader: Option<String>,
+    pub loader_version: Option<String>,
+    /// What the crash usually means, for common failures
+    pub hint: Option<String>,
+}
+
+/// Payload of `crash_detected`
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashDetected {
+    pub instance_id: String,
+    pub report: CrashReport,
+}
+
+/// Session outcomes of an instance, stored with its metadata
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstanceStability {
+    pub clean_exits: u32,
+    pub crashes: u32,
+    pub jvm_crashes: u32,
+    pub last_clean_session: Option<String>,
+    pub last_crash: Option<String>,
+    /// Least recently seen first
+    #[serde(default)]
+    pub signatures: Vec<CrashSignatureCount>,
+}
+
+/// Ratios and the most common crash of an instance, for the UI and launch diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StabilitySummary {
+    pub sessions: u32,
+    pub clean_exits: u32,
+    pub crashes: u32,
+    pub jvm_crashes: u32,
+    pub clean_rate: f64,
+    pub crash_rate: f64,
+    pub jvm_crash_rate: f64,
+    pub most_frequent_crash: Option<CrashSignatureCount>,
+    pub last_clean_session: Option<String>,
+    pub last_crash: Option<String>,
+    pub unstable: bool,
+}
+
+impl InstanceStability {
+    /// Count a finished session that ended at `ended_at` (RFC 3339)
+    pub fn record(&mut self, outcome: SessionOutcome, signature: Option<CrashSignature>, ended_at: &str) {
+        match outcome {
+            SessionOutcome::Clean => {
+                self.clean_exits += 1;
+                self.last_clean_session = Some(ended_at.to_string());
+                return;
+            }
+            SessionOutcome::Crash => self.crashes += 1,
+            SessionOutcome::JvmCrash => self.jvm_crashes += 1,
+        }
+        self.last_crash = Some(ended_at.to_string());
+
+        let Some(signature) = signature else {
+            return;
+        };
+        let mut entry = match self.signatures.iter().position(|entry| entry.signature == signature) {
+            Some(index) => self.signatures.remove(index),
+            None => CrashSignatureCount { signature, count: 0, last_seen: String::new() },
+        };
+        entry.count += 1;
+        entry.last_seen = ended_at.to_string();
+        self.signatures.push(entry);
+
+        if self.signatures.len() > MAX_SIGNATURES {
+            self.signatures.remove(0);
+        }
+    }
+
+    pub fn summary(&self) -> StabilitySummary {
+        let sessions = self.clean_exits + self.crashes + self.jvm_crashes;
+        let rate = |count: u32| if sessions == 0 { 0.0 } else { count as f64 / sessions as f64 };
+
+        StabilitySummary {
+            sessions,
+            clean_exits: self.clean_exits,
+            crashes: self.crashes,
+            jvm_crashes: self.jvm_crashes,
+            clean_rate: rate(self.clean_exits),
+            crash_rate: rate(self.crashes),
+            jvm_crash_rate: rate(self.jvm_crashes),
+            // Ties go to the most recently seen
+            most_frequent_crash: self.signatures.iter().max_by_key(|entry| entry.count).cloned(),
+            last_clean_session: self.last_clean_session.clone(),
+            last_crash: self.last_crash.clone(),
+            unstable: sessions >= MIN_SESSIONS_FOR_UNSTABLE
+                && rate(self.crashes + self.jvm_crashes) >= UNSTABLE_CRASH_RATE,
+        }
+    }
+}
+
+/// Work out how a session in `game_dir` that started at `since` ended
+///
+/// A JVM crash log wins over a crash report, and a non-zero exit without either
+/// is still a crash, just without a signature.
+pub async fn analyze_exit(game_dir: &Path, since: SystemTime, exited_cleanly: bool) -> (SessionOutcome, Option<CrashSignature>) {
+    if let Some(log) = super::logs::newest_jvm_crash_log_since(game_dir, since).await {
+        let text = tokio::fs::read_to_string(&log).await.unwrap_or_default();
+        return (SessionOutcome::JvmCrash, parse_jvm_crash_log(&text));
+    }
+    if let Some(report) = super::logs::newest_crash_report_since(game_dir, since).await {
+        let text = tokio::fs::read_to_string(&report).await.unwrap_or_default();
+        return (SessionOutcome::Crash, parse_crash_report(&text));
+    }
+
+    if exited_cleanly {
+        (SessionOutcome::Clean, None)
+    } else {
+        (SessionOutcome::Crash, None)
+    }
+}
+
+/// The newest crash report in `game_dir`, parsed
+pub async fn latest_crash_report(game_dir: &Path) -> Option<CrashReport> {
+    let path = super::logs::newest_crash_report_since(game_dir, SystemTime::UNIX_EPOCH).await?;
+    read_crash_report(&path).await
+}
+
+pub async fn read_crash_report(path: &Path) -> Option<CrashReport> {
+    let text = tokio::fs::read(path).await.ok()?;
+    let mut report = parse_crash_report_details(&String::from_utf8_lossy(&text));
+    report.file_name = path.file_name()?.to_string_lossy().to_string();
+    report.path = path.to_string_lossy().to_string();
+    Some(report)
+}
+
+/// Everything but the file name and path of a crash report
+pub fn parse_crash_report_details(report: &str) -> CrashReport {
+    let (exception, exception_message) = match exception_line(report) {
+        Some(line) => match line.split_once(':') {
+            Some((class, message)) => (class.trim(), Some(message.trim().to_string()).filter(|message| !message.is_empty())),
+            None => (line.trim(), None),
+        },
+        None => ("", None),
+    };
+    let exception = Some(exception.to_string()).filter(|exception| {
+        !exception.is_empty() && !exception.contains(char::is_whitespace)
+    });
+    let (loader, loader_version) = match loader_line(report) {
+        Some((loader, version)) => (Some(loader.to_string()), Some(version)),
+        None => (None, None),
+    };
+
+    CrashReport {
+        time: header_value(report, "Time:"),
+        description: header_value(report, "Description:"),
+        exception,
+        exception_message,
+        suspected_mods: suspected_mods(report),
+        minecraft_version: header_value(report, "Minecraft Version:"),
+        loader,
+        loader_version,
+        hint: crash_hint(report),
+        ..Default::default()
+    }
+}
+
+/// What a crash usually means, if it looks like one of the common failures
+pub fn crash_hint(report: &str) -> Option<String> {
+    CRASH_HINTS.iter()
+        .find(|(needles, _)| needles.iter().any(|needle| report.contains(needle)))
+        .map(|(_, hint)| hint.to_string())
+}
+
+/// Signature of a Minecraft crash report
+///
+/// The exception is the first line after `Description:`. The suspected mod comes
+/// from Forge's `Suspected Mod` line, else from the first stack frame outside
+/// Minecraft, the JDK and the loaders.
+pub fn parse_crash_report(report: &str) -> Option<CrashSignature> {
+    let exception = exception_line(report)?.split(':').next()?.trim();
+    if exception.is_empty() || exception.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let suspected_mod = suspected_mod_line(report).or_else(|| first_mod_frame(report));
+    Some(CrashSignature { exception: exception.to_string(), suspected_mod })
+}
+
+/// Signature of an `hs_err_pid*.log`: the signal and the native library it hit
+pub fn parse_jvm_crash_log(log: &str) -> Option<CrashSignature> {
+    let exception = log.lines()
+        .find(|line| line.starts_with('#') && line.contains(" at pc="))?
+        .trim_start_matches('#')
+        .split_whitespace()
+        .next()?
+        .to_string();
+
+    let suspected_mod = log.lines()
+        .skip_while(|line| !line.starts_with("# Problematic frame:"))
+        .nth(1)
+        .and_then(|frame| {
+            let library = frame.split_once('[')?.1.split(['+', ']']).next()?;
+            Some(library.to_string()).filter(|library| !library.is_empty())
+        });
+
+    Some(CrashSignature { exception, suspected_mod })
+}
+
+/// First line after `Description:`, the exception and its message
+fn exception_line(report: &str) -> Option<&str> {
+    report.lines()
+        .skip_while(|line| !line.starts_with("Description:"))
+        .skip(1)
+        .find(|line| !line.trim().is_empty())
+}
+
+/// Value of the first `key` line, wherever it is indented
+fn header_value(report: &str, key: &str) -> Option<String> {
+    report.lines()
+        .find_map(|line| line.trim().strip_prefix(key))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Mods named by the report, most specific first and without duplicates
+fn suspected_mods(report: &str) -> Vec<String> {
+    let mut mods: Vec<String> = suspected_mod_line(report).into_iter().collect();
+    for line in report.lines().map(str::trim) {
+        let found = if let Some(file) = line.strip_prefix("Mod File:") {
+            // Forge writes the full path, the jar name is enough
+            file.trim().rsplit(['/', '\\']).next().map(str::to_string)
+        } else {
+            line.strip_prefix("Mod ID:").map(|id| id.trim().to_string())
+        };
+        if let Some(found) = found.filter(|found| !found.is_empty() && !mods.contains(found)) {
+            mods.push(found);
+        }
+    }
+    mods
+}
+
+/// Mod loader and its version from the report's system details
+fn loader_line(report: &str) -> Option<(&'static str, String)> {
+    report.lines().map(str::trim).find_map(|line| {
+        // `fabricloader: Fabric Loader 0.15.7`, `quilt_loader: Quilt Loader 0.23.1`
+        if let Some(version) = line.strip_prefix("fabricloader: Fabric Loader ") {
+            return Some(("fabric", version.trim().to_string()));
+        }
+        if let Some(version) = line.strip_prefix("quilt_loader: Quilt Loader ") {
+            return Some(("quilt", version.trim().to_string()));
+        }
+        // `Forge: net.minecraftforge:47.2.0`, `NeoForge: net.neoforged:20.4.80-beta`
+        let (loader, value) = if let Some(value) = line.strip_prefix("NeoForge:") {
+            ("neoforge", value)
+        } else {
+            ("forge", line.strip_prefix("Forge:")?)
+        };
+        let version = value.trim().rsplit(':').next()?.trim();
+        (!version.is_empty()).then(|| (loader, version.to_string()))
+    })
+}
+
+/// `Sodium (sodium)` from `Suspected Mod: Sodium (sodium), Version: 0.5.3`
+fn suspected_mod_line(report: &str) -> Option<String> {
+    let mut lines = report.lines();
+    let value = lines.by_ref()
+        .find_map(|line| line.trim().strip_prefix("Suspected Mod")?.split_once(':').map(|(_, value)| value.trim()))?;
+    // Forge puts the mod on the next line when the value is empty
+    let value = match value {
+        "" => lines.next()?.trim(),
+        value => value,
+    };
+
+    let name = value.split(", Version").next()?.trim();
+    (!name.is_empty() && !name.eq_ignore_ascii_case("none") && !name.eq_ignore_ascii_case("unknown"))
+        .then(|| name.to_string())
+}
+
+/// The mod id or package of the first stack frame that isn't Minecraft, the JDK or a loader
+fn first_mod_frame(report: &str) -> Option<String> {
+    report.lines()
+        .filter_map(|line| line.trim().strip_prefix("at "))
+        .find_map(|frame| {
+            // Forge frames are `TRANSFORMER/modid@version/com.example.Class.method(...)`
+            if let Some((prefix, _)) = frame.split_once('@') {
+                let mod_id = prefix.rsplit('/').next()?;
+                return (!mod_id.is_empty() && !matches!(mod_id, "minecraft" | "forge" | "neoforge"))
+                    .then(|| mod_id.to_string());
+            }
+
+            let method = frame.split('(').next()?;
+            if NON_MOD_PACKAGES.iter().any(|package| method.starts_with(package)) {
+                return None;
+            }
+            let segments: Vec<&str> = method.split('.').collect();
+            // Drop the class and method, keep at most three package segments
+            let package = &segments[..segments.len().saturating_sub(2).min(3)];
+            (!package.is_empty()).then(|| package.join("."))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORGE_REPORT: &str = "---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2024-05-01 18:22:10
+Description: Ticking entity
+
+java.lang.NullPointerException: Cannot invoke \"net.minecraft.world.entity.Entity.getId()\"
+\tat TRANSFORMER/create@0.5.1.f/com.simibubi.create.content.Foo.tick(Foo.java:42)
+\tat TRANSFORMER/minecraft@1.20.1/net.minecraft.world.level.Level.tick(Level.java:1)
+
+-- Head --
+Thread: Server thread
+Suspected Mod:
+\tCreate (create), Version: 0.5.1.f
+\t\tIssue tracker URL: https://github.com/Creators-of-Create/Create/issues
+";
+
+    const FABRIC_REPORT: &str = "---- Minecraft Crash Report ----
+Time: 2024-05-01 18:22:10
+Description: Rendering overlay
+
+java.lang.IllegalStateException: Buffer not building
+\tat com.mojang.blaze3d.vertex.BufferBuilder.end(BufferBuilder.java:10)
+\tat me.jellysquid.mods.sodium.client.render.Renderer.draw(Renderer.java:55)
+";
+
+    const HS_ERR: &str = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=0x00007ffb1a2b3c4d, pid=4242, tid=1337
+#
+# JRE version: OpenJDK Runtime Environment (17.0.8+7) (build 17.0.8+7)
+# Problematic frame:
+# C  [atio6axx.dll+0x1b3c4d]
+#
+";
+
+    #[test]
+    fn crash_reports_give_exception_and_suspected_mod() {
+        assert_eq!(parse_crash_report(FORGE_REPORT), Some(CrashSignature {
+            exception: "java.lang.NullPointerException".to_string(),
+            suspected_mod: Some("Create (create)".to_string()),
+        }));
+        assert_eq!(parse_crash_report(FABRIC_REPORT), Some(CrashSignature {
+            exception: "java.lang.IllegalStateException".to_string(),
+            suspected_mod: Some("me.jellysquid.mods".to_string()),
+        }));
+        assert_eq!(parse_crash_report("not a crash report"), None);
+    }
+
+    #[test]
+    fn crash_report_details_and_hints() {
+        let report = "---- Minecraft Crash Report ----
+Time: 2024-05-01 18:22:10
+Description: Mod loading error has occurred
+
+java.lang.Exception: Mod Loading has failed
+\tat net.minecraftforge.logging.CrashReportExtender.dumpModLoadingCrashReport(CrashReportExtender.java:60)
+
+-- Mod loading issue for: examplemod --
+Details:
+\tMod File: /home/player/.minecraft/mods/examplemod-1.0.jar
+\tFailure message: examplemod has failed to load correctly
+\t\tjava.lang.NoSuchMethodError: 'void net.minecraft.world.Foo.bar()'
+\tMod ID: examplemod
+
+-- System Details --
+Details:
+\tMinecraft Version: 1.20.1
+\tForge: net.minecraftforge:47.2.0
+";
+        let details = parse_crash_report_details(report);
+        assert_eq!(details.time.as_deref(), Some("2024-05-01 18:22:10"));
+        assert_eq!(details.description.as_deref(), Some("Mod loading error has occurred"));
+        assert_eq!(details.exception.as_deref(), Some("java.lang.Exception"));
+        assert_eq!(details.exception_message.as_deref(), Some("Mod Loading has failed"));
+        assert_eq!(details.suspected_mods, vec!["examplemod-1.0.jar", "examplemod"]);
+        assert_eq!(details.minecraft_version.as_deref(), Some("1.20.1"));
+        assert_eq!((details.loader.as_deref(), details.loader_version.as_deref()), (Some("forge"), Some("47.2.0")));
+        assert!(details.hint.unwrap().contains("different version of Minecraft"));
+
+        let fabric = parse_crash_report_details(&format!("{}\tfabricloader: Fabric Loader 0.15.7\n", FABRIC_REPORT));
+        assert_eq!((fabric.loader.as_deref(), fabric.loader_version.as_deref()), (Some("fabric"), Some("0.15.7")));
+        assert_eq!(fabric.hint, None);
+        assert_eq!(parse_crash_report_details(FORGE_REPORT).suspected_mods, vec!["Create (create)"]);
+
+        let oom = crash_hint("Description: Unexpected error\n\njava.lang.OutOfMemoryError: Java heap space\n\tat java.lang.NoClassDefFoundError");
+        assert!(oom.unwrap().contains("ran out of memory"));
+    }
+
+    #[test]
+    fn jvm_crash_logs_give_signal_and_library() {
+        assert_eq!(parse_jvm_crash_log(HS_ERR), Some(CrashSignature {
+            exception: "EXCEPTION_ACCESS_VIOLATION".to_string(),
+            suspected_mod: Some("atio6axx.dll".to_string()),
+        }));
+    }
+
+    #[test]
+    fn stability_counts_sessions_and_repeated_crashes() {
+        let mut stability = InstanceStability::default();
+        let npe = CrashSignature { exception: "java.lang.NullPointerException".to_string(), suspected_mod: Some("create".to_string()) };
+        let oom = CrashSignature { exception: "java.lang.OutOfMemoryError".to_string(), suspected_mod: None };
+
+        stability.record(SessionOutcome::Clean, None, "2024-05-01T10:00:00+00:00");
+        stability.record(SessionOutcome::Crash, Some(npe.clone()), "2024-05-02T10:00:00+00:00");
+        assert!(!stability.summary().unstable);
+
+        stability.record(SessionOutcome::Crash, Some(oom), "2024-05-03T10:00:00+00:00");
+        stability.record(SessionOutcome::Crash, Some(npe.clone()), "2024-05-04T10:00:00+00:00");
+        stability.record(SessionOutcome::JvmCrash, None, "2024-05-05T10:00:00+00:00");
+
+        let summary = stability.summary();
+        assert_eq!(summary.sessions, 5);
+        assert_eq!(summary.crashes, 3);
+        assert_eq!(summary.jvm_crashes, 1);
+        assert!((summary.clean_rate - 0.2).abs() < f64::EPSILON);
+        assert!(summary.unstable);
+        let top = summary.most_frequent_crash.unwrap();
+        assert_eq!((top.signature, top.count), (npe, 2));
+        assert_eq!(summary.last_clean_session.as_deref(), Some("2024-05-01T10:00:00+00:00"));
+        assert_eq!(summary.last_crash.as_deref(), Some("2024-05-05T10:00:00+00:00"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Environment variables an instance sets for the game
+//!
+//! Overrides are merged over the launcher's environment and handed to MCVM with
+//! the launch options. MCVM spawns Java itself and the child inherits whatever the
+//! launcher has, so an instance that clears the inherited environment is started
+//! through a wrapper: a child copy of the launcher run with `CLEAN_ENV_FLAG`, which
+//! starts the game with only the instance's variables. The launcher's own
+//! environment is never touched.
+
+use mcvm::core::launch::WrapperCommand;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+
+/// First argument of the launcher when it runs as the clean environment wrapper
+pub const CLEAN_ENV_FLAG: &str = "--clean-env-exec";
+
+/// Prefix of the wrapper arguments naming a variable to pass on
+const KEEP_PREFIX: &str = "--keep=";
+
+/// The environment a launch would start the game with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchEnvironment {
+    /// Every variable the game gets, sorted by name
+    pub variables: BTreeMap<String, String>,
+    /// Variables set by the instance, including ones replacing an inherited value
+    pub overridden: Vec<String>,
+    /// Whether the launcher's own environment is left out
+    pub cleared_inherited: bool,
+}
+
+/// Merge an instance's variables over `inherited`
+pub fn resolve(
+    inherited: impl IntoIterator<Item = (String, String)>,
+    env_vars: Option<&HashMap<String, String>>,
+    clear_inherited: bool,
+) -> LaunchEnvironment {
+    let mut variables: BTreeMap<String, String> = if clear_inherited {
+        BTreeMap::new()
+    } else {
+        inherited.into_iter().collect()
+    };
+    let mut overridden = Vec::new();
+    for (name, value) in env_vars.into_iter().flatten() {
+        variables.insert(name.clone(), value.clone());
+        overridden.push(name.clone());
+    }
+    overridden.sort();
+
+    LaunchEnvironment { variables, overridden, cleared_inherited: clear_inherited }
+}
+
+/// What launching the instance now would give the game
+pub fn preview(env_vars: Option<&HashMap<String, String>>, clear_inherited: bool) -> LaunchEnvironment {
+    resolve(std::env::vars_os().map(lossy_pair), env_vars, clear_inherited)
+}
+
+/// Reject names the OS can't set, so a bad entry fails when saved rather than at launch
+pub fn validate(env_vars: Option<&HashMap<String, String>>) -> Result<(), String> {
+    for (name, value) in env_vars.into_iter().flatten() {
+        if name.trim().is_empty() {
+            return Err("Environment variable names can't be empty".to_string());
+        }
+        if name.contains('=') || name.contains('\0') {
+            return Err(format!("Invalid environment variable name '{}'", name));
+        }
+        if value.contains('\0') {
+            return Err(format!("The value of {} contains a NUL character", name));
+        }
+    }
+    Ok(())
+}
+
+/// Wrapper MCVM starts the game through so it only sees the variables in `keep`
+///
+/// MCVM sets the instance's variables on the wrapper, which passes just those on.
+pub fn clean_env_wrapper(keep: &HashMap<String, String>) -> Result<WrapperCommand, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to find the launcher executable: {}", e))?;
+    let mut names: Vec<&String> = keep.keys().collect();
+    names.sort();
+    let mut args = vec![CLEAN_ENV_FLAG.to_string()];
+    args.extend(names.into_iter().map(|name| format!("{}{}", KEEP_PREFIX, name)));
+    args.push("--".to_string());
+    Ok(WrapperCommand { cmd: exe.to_string_lossy().into_owned(), args })
+}
+
+/// Names to keep, the program and its arguments from the wrapper's arguments after the flag
+fn parse_wrapper_args(args: impl IntoIterator<Item = OsString>) -> Option<(HashSet<OsString>, OsString, Vec<OsString>)> {
+    let mut args = args.into_iter();
+    let mut keep = HashSet::new();
+    loop {
+        let arg = args.next()?;
+        if arg == "--" {
+            break;
+        }
+        let name = arg.to_str()?.strip_prefix(KEEP_PREFIX)?;
+        keep.insert(OsString::from(name));
+    }
+    let program = args.next()?;
+    Some((keep, program, args.collect()))
+}
+
+/// Run as the wrapper: start the game with only the kept variables and return its exit code
+///
+/// On Unix the wrapper becomes the game, so the PID the launcher tracks stays the game's.
+pub fn run_clean_env_process(args: impl IntoIterator<Item = OsString>) -> i32 {
+    let Some((keep, program, game_args)) = parse_wrapper_args(args) else {
+        eprintln!("❌ Usage: {} [{}NAME]... -- <program> [args]...", CLEAN_ENV_FLAG, KEEP_PREFIX);
+        return 2;
+    };
+    let mut command = std::process::Command::new(&program);
+    command.args(game_args)
+        .env_clear()
+        .envs(std::env::vars_os().filter(|(name, _)| keep.contains(name)));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let e = command.exec();
+        eprintln!("❌ Failed to start {}: {}", program.to_string_lossy(), e);
+        1
+    }
+    #[cfg(not(unix))]
+    match command.status() {
+        Ok(status) => status.code().unwrap_or(1),
+        Err(e) => {
+            eprintln!("❌ Failed to start {}: {}", program.to_string_lossy(), e);
+            1
+        }
+    }
+}
+
+fn lossy_pair((name, value): (OsString, OsString)) -> (String, String) {
+    (name.to_string_lossy().into_owned(), value.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn overrides_merge_over_or_replace_the_inherited_environment() {
+        let inherited = || vec![
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("__GL_THREADED_OPTIMIZATIONS".to_string(), "1".to_string()),
+        ];
+        let overrides = vars(&[("__GL_THREADED_OPTIMIZATIONS", "0"), ("MESA_GL_VERSION_OVERRIDE", "4.5")]);
+
+        let merged = resolve(inherited(), Some(&overrides), false);
+        assert_eq!(merged.variables["PATH"], "/usr/bin");
+        assert_eq!(merged.variables["__GL_THREADED_OPTIMIZATIONS"], "0");
+        assert_eq!(merged.overridden, vec!["MESA_GL_VERSION_OVERRIDE", "__GL_THREADED_OPTIMIZATIONS"]);
+
+        let cleared = resolve(inherited(), Some(&overrides), true);
+        assert_eq!(cleared.variables.len(), 2);
+        assert!(!cleared.variables.contains_key("PATH"));
+        assert!(cleared.cleared_inherited);
+
+        assert_eq!(resolve(inherited(), None, false).variables.len(), 2);
+    }
+
+    #[test]
+    fn wrapper_passes_on_only_the_instance_variables() {
+        let wrapper = clean_env_wrapper(&vars(&[("MESA_DEBUG", "silent"), ("DRI_PRIME", "1")])).unwrap();
+        assert_eq!(wrapper.args, [CLEAN_ENV_FLAG, "--keep=DRI_PRIME", "--keep=MESA_DEBUG", "--"]);
+
+        let launched = ["/usr/bin/java", "-Xmx4G", "--", "net.minecraft.client.main.Main"];
+        let args = wrapper.args[1..].iter().map(String::as_str).chain(launched).map(OsString::from);
+        let (keep, program, game_args) = parse_wrapper_args(args).unwrap();
+        assert_eq!(keep, HashSet::from([OsString::from("DRI_PRIME"), OsString::from("MESA_DEBUG")]));
+        assert_eq!(program, "/usr/bin/java");
+        // A `--` among the game's own arguments is passed through
+        assert_eq!(game_args, ["-Xmx4G", "--", "net.minecraft.client.main.Main"]);
+
+        assert!(parse_wrapper_args(["--", "java"].map(OsString::from)).is_some_and(|(keep, _, _)| keep.is_empty()));
+        assert!(parse_wrapper_args(["--keep=A"].map(OsString::from)).is_none());
+        assert!(parse_wrapper_args(["java"].map(OsString::from)).is_none());
+    }
+
+    #[test]
+    fn names_the_os_cannot_set_are_rejected() {
+        assert!(validate(Some(&vars(&[("MESA_DEBUG", "silent")]))).is_ok());
+        assert!(validate(None).is_ok());
+        assert!(validate(Some(&vars(&[("", "1")]))).is_err());
+        assert!(validate(Some(&vars(&[("A=B", "1")]))).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! Live game output
+//!
+//! The game's stdout and stderr are read line by line and sent to the frontend as
+//! `game_log` batches every 100ms. Every line is also appended to
+//! `logs/chailauncher/game.log` in the instance, so the console can show what
+//! happened before it was opened.
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+/// How often collected lines are sent to the frontend
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lines per batch the frontend gets, older lines in a bigger batch are skipped
+const MAX_LINES_PER_BATCH: usize = 500;
+
+const LOG_DIR: &str = "chailauncher";
+const LOG_FILE: &str = "game.log";
+const ROTATED_LOG_FILE: &str = "game.1.log";
+
+/// game.log is moved to game.1.log once it grows past this
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameLogStream {
+    Stdout,
+    Stderr,
+}
+
+impl GameLogStream {
+    fn as_str(self) -> &'static str {
+        match self {
+            GameLogStream::Stdout => "stdout",
+            GameLogStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// One line of game output, `game_log` events carry a list of these
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameLogLine {
+    pub instance_id: String,
+    pub stream: GameLogStream,
+    pub line: String,
+}
+
+/// Start forwarding the game's stdout and stderr, if the launch piped them
+pub fn capture(app_handle: &AppHandle, instance_id: &str, game_dir: &Path, process: &mut Child) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let stdout = process.stdout.take().map(|pipe| spawn_reader(pipe, GameLogStream::Stdout, sender.clone()));
+    let stderr = process.stderr.take().map(|pipe| spawn_reader(pipe, GameLogStream::Stderr, sender));
+    if stdout.is_none() && stderr.is_none() {
+        println!("ℹ️  Output of instance {} isn't piped, only logs/latest.log has it", instance_id);
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let instance_id = instance_id.to_string();
+    let log_dir = game_dir.join("logs").join(LOG_DIR);
+    tauri::async_runtime::spawn(async move {
+        forward(&app_handle, &instance_id, &log_dir, receiver).await;
+    });
+}
+
+/// Read a pipe until the game closes it
+///
+/// Lines are still read after the forwarder is gone so the game never blocks on a full pipe.
+fn spawn_reader<R: Read + Send + 'static>(
+    pipe: R,
+    stream: GameLogStream,
+    sender: mpsc::UnboundedSender<(GameLogStream, String)>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf).trim_end_matches(['\r', '\n']).to_string();
+                    let _ = sender.send((stream, line));
+                }
+            }
+        }
+    })
+}
+
+/// Batch received lines every `BATCH_INTERVAL` until both pipes are closed
+async fn forward(
+    app_handle: &AppHandle,
+    instance_id: &str,
+    log_dir: &Path,
+    mut receiver: mpsc::UnboundedReceiver<(GameLogStream, String)>,
+) {
+    let mut log = GameLogFile::open(log_dir).await;
+    let mut interval = tokio::time::interval(BATCH_INTERVAL);
+    let mut pending = Vec::new();
+
+    loop {
+        let open = tokio::select! {
+            received = receiver.recv() => match received {
+                Some(entry) => {
+                    pending.push(entry);
+                    continue;
+                }
+                None => false,
+            },
+            _ = interval.tick() => true,
+        };
+
+        if !pending.is_empty() {
+            let lines = std::mem::take(&mut pending);
+            if let Some(log) = log.as_mut() {
+                if let Err(e) = log.append(&lines).await {
+                    println!("⚠️  Failed to write game log for instance {}: {}", instance_id, e);
+                }
+            }
+            let _ = app_handle.emit("game_log", ui_batch(instance_id, lines));
+        }
+        if !open {
+            break;
+        }
+    }
+}
+
+/// The lines of a batch sent to the frontend, with a marker for skipped ones
+fn ui_batch(instance_id: &str, lines: Vec<(GameLogStream, String)>) -> Vec<GameLogLine> {
+    let skipped = lines.len().saturating_sub(MAX_LINES_PER_BATCH);
+    let mut batch = Vec::with_capacity(lines.len() - skipped + 1);
+    if skipped > 0 {
+        batch.push(GameLogLine {
+            instance_id: instance_id.to_string(),
+            stream: GameLogStream::Stderr,
+            line: format!("[ChaiLauncher] {} lines skipped, see logs/chailauncher/game.log", skipped),
+        });
+    }
+    batch.extend(lines.into_iter().skip(skipped).map(|(stream, line)| GameLogLine {
+        instance_id: instance_id.to_string(),
+        stream,
+        line,
+    }));
+    batch
+}
+
+/// The launcher's copy of the game output, rotated at `MAX_LOG_BYTES`
+struct GameLogFile {
+    dir: PathBuf,
+    file: tokio::fs::File,
+    size: u64,
+}
+
+impl GameLogFile {
+    async fn open(dir: &Path) -> Option<Self> {
+        let opened = async {
+            tokio::fs::create_dir_all(dir).await?;
+            let file = tokio::fs::OpenOptions::new().create(true).append(true).open(dir.join(LOG_FILE)).await?;
+            let size = file.metadata().await?.len();
+            Ok::<_, std::io::Error>(Self { dir: dir.to_path_buf(), file, size })
+        };
+        match opened.await {
+            Ok(log) => Some(log),
+            Err(e) => {
+                println!("⚠️  Failed to open game log in {}: {}", dir.display(), e);
+                None
+            }
+        }
+    }
+
+    async fn append(&mut self, lines: &[(GameLogStream, String)]) -> std::io::Result<()> {
+        let text: String = lines.iter().map(|(stream, line)| format_line(*stream, line)).collect();
+        if self.size > 0 && self.size + text.len() as u64 > MAX_LOG_BYTES {
+            self.rotate().await?;
+        }
+        self.file.write_all(text.as_bytes()).await?;
+        self.size += text.len() as u64;
+        Ok(())
+    }
+
+    async fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush().await?;
+        tokio::fs::rename(self.dir.join(LOG_FILE), self.dir.join(ROTATED_LOG_FILE)).await?;
+        self.file = tokio::fs::OpenOptions::new().create(true).append(true).open(self.dir.join(LOG_FILE)).await?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn format_line(stream: GameLogStream, line: &str) -> String {
+    format!("[{}] {}\n", stream.as_str(), line)
+}
+
+fn parse_line(instance_id: &str, text: &str) -> GameLogLine {
+    let (stream, line) = if let Some(line) = text.strip_prefix("[stderr] ") {
+        (GameLogStream::Stderr, line)
+    } else {
+        (GameLogStream::Stdout, text.strip_prefix("[stdout] ").unwrap_or(text))
+    };
+    GameLogLine { instance_id: instance_id.to_string(), stream, line: line.to_string() }
+}
+
+/// Add lines the launcher produced itself, such as hook output, to an instance's log
+pub async fn append_lines(game_dir: &Path, lines: &[(GameLogStream, String)]) {
+    let dir = game_dir.join("logs").join(LOG_DIR);
+    if let Some(mut log) = GameLogFile::open(&dir).await {
+        if let Err(e) = log.append(lines).await {
+            println!("⚠️  Failed to write game log in {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// The last `max_lines` lines the launcher logged for an instance, oldest first
+pub async fn recent_lines(instance_id: &str, game_dir: &Path, max_lines: usize) -> Vec<GameLogLine> {
+    let dir = game_dir.join("logs").join(LOG_DIR);
+    let mut lines = Vec::new();
+
+    for file in [LOG_FILE, ROTATED_LOG_FILE] {
+        if lines.len() >= max_lines {
+            break;
+        }
+        let Ok(text) = tokio::fs::read_to_string(dir.join(file)).await else {
+            continue;
+        };
+        let wanted = max_lines - lines.len();
+        let mut older: Vec<GameLogLine> = text.lines().rev().take(wanted).map(|line| parse_line(instance_id, line)).collect();
+        older.reverse();
+        older.append(&mut lines);
+        lines = older;
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn big_batches_keep_the_newest_lines() {
+        let lines: Vec<_> = (0..MAX_LINES_PER_BATCH + 20)
+            .map(|i| (GameLogStream::Stdout, format!("line {}", i)))
+            .collect();
+        let batch = ui_batch("inst", lines);
+
+        assert_eq!(batch.len(), MAX_LINES_PER_BATCH + 1);
+        assert!(batch[0].line.contains("20 lines skipped"));
+        assert_eq!(batch[1].line, "line 20");
+        assert_eq!(batch.last().unwrap().line, format!("line {}", MAX_LINES_PER_BATCH + 19));
+    }
+
+    #[tokio::test]
+    async fn log_rotates_and_recent_lines_span_both_files() {
+        let game_dir = std::env::temp_dir().join(format!("chai-game-log-{}", uuid::Uuid::new_v4()));
+        let dir = game_dir.join("logs").join(LOG_DIR);
+        let mut log = GameLogFile::open(&dir).await.unwrap();
+
+        log.append(&[(GameLogStream::Stdout, "first".to_string())]).await.unwrap();
+        log.size = MAX_LOG_BYTES;
+        log.append(&[
+            (GameLogStream::Stderr, "second".to_string()),
+            (GameLogStream::Stdout, "[stdout] third".to_string()),
+        ]).await.unwrap();
+        assert!(dir.join(ROTATED_LOG_FILE).exists());
+
+        let recent = recent_lines("inst", &game_dir, 10).await;
+        let text: Vec<(GameLogStream, &str)> = recent.iter().map(|line| (line.stream, line.line.as_str())).collect();
+        assert_eq!(text, vec![
+            (GameLogStream::Stdout, "first"),
+            (GameLogStream::Stderr, "second"),
+            (GameLogStream::Stdout, "[stdout] third"),
+        ]);
+        assert_eq!(recent_lines("inst", &game_dir, 1).await[0].line, "[stdout] third");
+
+        std::fs::remove_dir_all(game_dir).unwrap();
+    }
+}
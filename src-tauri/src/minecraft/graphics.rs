@@ -0,0 +1,459 @@
+//! Pre-launch OpenGL probe
+//!
+//! Broken or missing GPU drivers make the game crash before its window opens. The
+//! probe creates a tiny offscreen context and reads the driver's GL version and
+//! renderer. It runs in a child copy of the launcher started with `PROBE_FLAG`, so
+//! a driver that crashes takes only that process down. Results are cached for
+//! `CACHE_TTL_HOURS`. Launches only ever warn: a failed or skipped probe never
+//! holds one back.
+//!
+//! Context creation needs the `gl-probe` feature. Builds without it report the
+//! probe as unavailable.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::MinecraftInstance;
+
+/// Argument that turns the launcher into the probe process
+pub const PROBE_FLAG: &str = "--probe-graphics";
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const CACHE_TTL_HOURS: i64 = 24;
+const UNAVAILABLE: &str = "This build of the launcher can't probe OpenGL";
+
+/// Renderer names of CPU rasterizers, lowercase
+const SOFTWARE_RENDERERS: &[&str] = &[
+    "llvmpipe",
+    "softpipe",
+    "swrast",
+    "software rasterizer",
+    "microsoft basic render",
+    "gdi generic",
+    "apple software renderer",
+];
+
+/// What the probe found out about the OpenGL driver
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphicsProbe {
+    /// `GL_VERSION`, e.g. `4.6 (Core Profile) Mesa 24.0.5`
+    pub gl_version: Option<String>,
+    pub renderer: Option<String>,
+    pub vendor: Option<String>,
+    /// Whether a CPU rasterizer such as llvmpipe draws instead of the GPU
+    pub software: bool,
+    /// Why no context could be created, None when it worked
+    pub error: Option<String>,
+    /// Whether the probe process died, which usually means the driver crashed
+    pub crashed: bool,
+    pub probed_at: String,
+}
+
+impl GraphicsProbe {
+    #[cfg_attr(not(feature = "gl-probe"), allow(dead_code))]
+    fn found(vendor: String, renderer: String, gl_version: String) -> Self {
+        Self {
+            software: is_software_renderer(&renderer),
+            gl_version: Some(gl_version),
+            renderer: Some(renderer),
+            vendor: Some(vendor),
+            error: None,
+            crashed: false,
+            probed_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    fn failed(error: String, crashed: bool) -> Self {
+        Self {
+            gl_version: None,
+            renderer: None,
+            vendor: None,
+            software: false,
+            error: Some(error),
+            crashed,
+            probed_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// `(major, minor)` of the context the probe got
+    pub fn gl_version_number(&self) -> Option<(u32, u32)> {
+        self.gl_version.as_deref().and_then(parse_gl_version)
+    }
+
+    fn is_fresh(&self) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&self.probed_at)
+            .is_ok_and(|time| chrono::Utc::now() - time.to_utc() < chrono::Duration::hours(CACHE_TTL_HOURS))
+    }
+}
+
+/// Entry point of the probe process: print the result as JSON on stdout
+pub fn run_probe_process() {
+    #[cfg(feature = "gl-probe")]
+    let probe = match context::gl_strings() {
+        Ok((vendor, renderer, gl_version)) => GraphicsProbe::found(vendor, renderer, gl_version),
+        Err(e) => GraphicsProbe::failed(e, false),
+    };
+    #[cfg(not(feature = "gl-probe"))]
+    let probe = GraphicsProbe::failed(UNAVAILABLE.to_string(), false);
+    println!("{}", serde_json::to_string(&probe).unwrap_or_default());
+}
+
+/// The driver's OpenGL details, cached for a day unless `refresh` is set
+pub async fn probe(refresh: bool) -> GraphicsProbe {
+    if !cfg!(feature = "gl-probe") {
+        return GraphicsProbe::failed(UNAVAILABLE.to_string(), false);
+    }
+
+    if !refresh {
+        if let Some(cached) = read_cached().await.filter(GraphicsProbe::is_fresh) {
+            return cached;
+        }
+    }
+
+    let probe = run_child().await;
+    match (&probe.renderer, &probe.error) {
+        (Some(renderer), _) => println!("🎮 OpenGL {} on {}", probe.gl_version.as_deref().unwrap_or("?"), renderer),
+        (None, Some(error)) => println!("⚠️  OpenGL probe failed: {}", error),
+        (None, None) => {}
+    }
+    if let Err(e) = write_cached(&probe).await {
+        println!("⚠️  Failed to cache the OpenGL probe: {}", e);
+    }
+    probe
+}
+
+/// Probe for a launch, None when it is turned off in the settings
+pub async fn for_launch() -> Option<GraphicsProbe> {
+    let enabled = match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().probe_graphics_before_launch,
+        Err(_) => true,
+    };
+    if enabled {
+        Some(probe(false).await)
+    } else {
+        None
+    }
+}
+
+/// Log the probe's warnings for `instance` and send them to the frontend as `graphics_warning`
+pub async fn warn_before_launch(instance: &MinecraftInstance, app_handle: &AppHandle) {
+    let Some(probe) = for_launch().await else {
+        return;
+    };
+    let warnings = launch_warnings(&probe, &instance.version);
+    if warnings.is_empty() {
+        return;
+    }
+    for warning in &warnings {
+        println!("⚠️  {}", warning);
+    }
+    let _ = app_handle.emit("graphics_warning", serde_json::json!({
+        "instance_id": instance.id,
+        "warnings": warnings,
+        "probe": probe,
+    }));
+}
+
+/// What is worth telling the user before launching Minecraft `mc_version`
+pub fn launch_warnings(probe: &GraphicsProbe, mc_version: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if probe.crashed {
+        warnings.push("The graphics driver crashed while being probed, the game may crash on startup too. Updating the GPU driver usually fixes this".to_string());
+    }
+    if probe.software {
+        warnings.push(format!(
+            "OpenGL is rendered in software by {}, so the game will be very slow. Install the driver for your GPU",
+            probe.renderer.as_deref().unwrap_or("the CPU")
+        ));
+    }
+    if let (Some(required), Some(available)) = (minimum_gl_version(mc_version), probe.gl_version_number()) {
+        if available < required {
+            warnings.push(format!(
+                "Minecraft {} needs OpenGL {}.{} but the driver only offers {}.{}",
+                mc_version, required.0, required.1, available.0, available.1
+            ));
+        }
+    }
+    warnings
+}
+
+/// Oldest OpenGL a Minecraft version starts with, None when it isn't checked
+pub fn minimum_gl_version(mc_version: &str) -> Option<(u32, u32)> {
+    (super::versions::version_compare(mc_version, "1.17") >= 0).then_some((3, 2))
+}
+
+/// Leading `major.minor` of a `GL_VERSION` string
+pub fn parse_gl_version(gl_version: &str) -> Option<(u32, u32)> {
+    let mut numbers = gl_version.split(|c: char| !c.is_ascii_digit());
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Whether `renderer` names a CPU rasterizer
+pub fn is_software_renderer(renderer: &str) -> bool {
+    let renderer = renderer.to_lowercase();
+    SOFTWARE_RENDERERS.iter().any(|name| renderer.contains(name))
+}
+
+/// Run the probe in a child copy of the launcher
+async fn run_child() -> GraphicsProbe {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return GraphicsProbe::failed(format!("Failed to find the launcher executable: {}", e), false),
+    };
+    let child = tokio::process::Command::new(exe)
+        .arg(PROBE_FLAG)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => return GraphicsProbe::failed(format!("Failed to start the probe: {}", e), false),
+    };
+
+    let output = match tokio::time::timeout(PROBE_TIMEOUT, child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return GraphicsProbe::failed(format!("Failed to run the probe: {}", e), false),
+        Err(_) => {
+            return GraphicsProbe::failed(format!("The graphics driver didn't answer within {}s", PROBE_TIMEOUT.as_secs()), false);
+        }
+    };
+    parse_child_output(&String::from_utf8_lossy(&output.stdout))
+        .unwrap_or_else(|| GraphicsProbe::failed(format!("The probe exited with {}", output.status), !output.status.success()))
+}
+
+/// The result line of the probe process, ignoring anything a driver printed around it
+fn parse_child_output(stdout: &str) -> Option<GraphicsProbe> {
+    stdout.lines().rev().find_map(|line| serde_json::from_str(line.trim()).ok())
+}
+
+fn cache_path() -> PathBuf {
+    crate::storage::get_launcher_dir().join("cache").join("graphics-probe.json")
+}
+
+async fn read_cached() -> Option<GraphicsProbe> {
+    let json = tokio::fs::read_to_string(cache_path()).await.ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+async fn write_cached(probe: &GraphicsProbe) -> std::io::Result<()> {
+    let path = cache_path();
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, serde_json::to_vec_pretty(probe)?).await
+}
+
+/// Offscreen OpenGL context creation through glutin
+#[cfg(feature = "gl-probe")]
+mod context {
+    use glutin::config::ConfigTemplateBuilder;
+    use glutin::context::{ContextApi, ContextAttributes, ContextAttributesBuilder, GlProfile, Version};
+    use glutin::display::GlDisplay;
+    use std::ffi::{c_char, c_void, CStr};
+
+    const GL_VENDOR: u32 = 0x1F00;
+    const GL_RENDERER: u32 = 0x1F01;
+    const GL_VERSION: u32 = 0x1F02;
+
+    /// `(vendor, renderer, version)` reported by a fresh context
+    pub type GlStrings = (String, String, String);
+
+    /// The core 3.2 context Minecraft 1.17+ asks for, then whatever the driver gives
+    fn context_attempts() -> [ContextAttributes; 2] {
+        [
+            ContextAttributesBuilder::new()
+                .with_context_api(ContextApi::OpenGl(Some(Version::new(3, 2))))
+                .with_profile(GlProfile::Core)
+                .build(None),
+            ContextAttributesBuilder::new()
+                .with_context_api(ContextApi::OpenGl(None))
+                .build(None),
+        ]
+    }
+
+    /// Create a context on `display` with the first attempt the driver accepts
+    fn create_context<D: GlDisplay>(display: &D, config: &D::Config) -> Result<D::NotCurrentContext, String> {
+        let mut last_error = None;
+        for attributes in context_attempts() {
+            match unsafe { display.create_context(config, &attributes) } {
+                Ok(context) => return Ok(context),
+                Err(e) => last_error = Some(e.to_string()),
+            }
+        }
+        Err(format!("The driver refused to create an OpenGL context: {}", last_error.unwrap_or_default()))
+    }
+
+    fn first_config<D: GlDisplay>(display: &D, template: glutin::config::ConfigTemplate) -> Result<D::Config, String> {
+        unsafe { display.find_configs(template) }
+            .map_err(|e| format!("Failed to list OpenGL configurations: {}", e))?
+            .next()
+            .ok_or_else(|| "The driver offers no OpenGL configuration".to_string())
+    }
+
+    /// Read the driver strings once a context is current
+    fn read_strings<D: GlDisplay>(display: &D) -> Result<GlStrings, String> {
+        let address = display.get_proc_address(c"glGetString");
+        if address.is_null() {
+            return Err("The driver doesn't export glGetString".to_string());
+        }
+        let get_string: extern "system" fn(u32) -> *const c_char = unsafe { std::mem::transmute::<*const c_void, _>(address) };
+        let read = |name: u32| {
+            let value = get_string(name);
+            if value.is_null() {
+                String::new()
+            } else {
+                unsafe { CStr::from_ptr(value) }.to_string_lossy().trim().to_string()
+            }
+        };
+        Ok((read(GL_VENDOR), read(GL_RENDERER), read(GL_VERSION)))
+    }
+
+    /// Surfaceless context on the first GPU EGL lists, its software device only as a last resort
+    #[cfg(all(unix, not(target_os = "macos")))]
+    pub fn gl_strings() -> Result<GlStrings, String> {
+        use glutin::api::egl::{device::Device, display::Display};
+        use glutin::config::{Api, ConfigSurfaceTypes};
+
+        let mut devices: Vec<Device> = Device::query_devices()
+            .map_err(|e| format!("Failed to list EGL devices: {}", e))?
+            .collect();
+        devices.sort_by_key(|device| device.extensions().contains("EGL_MESA_device_software"));
+        let device = devices.first().ok_or("EGL lists no devices")?;
+
+        let display = unsafe { Display::with_device(device, None) }
+            .map_err(|e| format!("Failed to open the EGL device: {}", e))?;
+        let template = ConfigTemplateBuilder::new()
+            .with_api(Api::OPENGL)
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .build();
+        let config = first_config(&display, template)?;
+        let _context = create_context(&display, &config)?
+            .make_current_surfaceless()
+            .map_err(|e| format!("Failed to make the OpenGL context current: {}", e))?;
+        read_strings(&display)
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn gl_strings() -> Result<GlStrings, String> {
+        use glutin::api::cgl::display::Display;
+        use raw_window_handle::{AppKitDisplayHandle, RawDisplayHandle};
+
+        let display = unsafe { Display::new(RawDisplayHandle::AppKit(AppKitDisplayHandle::new())) }
+            .map_err(|e| format!("Failed to open CGL: {}", e))?;
+        let config = first_config(&display, ConfigTemplateBuilder::new().build())?;
+        let _context = create_context(&display, &config)?
+            .make_current_surfaceless()
+            .map_err(|e| format!("Failed to make the OpenGL context current: {}", e))?;
+        read_strings(&display)
+    }
+
+    /// WGL needs a window to pick a pixel format, so a hidden 1x1 one is made
+    #[cfg(windows)]
+    pub fn gl_strings() -> Result<GlStrings, String> {
+        use glutin::api::wgl::display::Display;
+        use raw_window_handle::{RawDisplayHandle, RawWindowHandle, Win32WindowHandle, WindowsDisplayHandle};
+        use std::num::NonZeroIsize;
+        use winapi::um::libloaderapi::GetModuleHandleW;
+        use winapi::um::winuser::{
+            CreateWindowExW, DefWindowProcW, DestroyWindow, RegisterClassExW, CS_OWNDC, WNDCLASSEXW, WS_POPUP,
+        };
+
+        struct HiddenWindow(winapi::shared::windef::HWND);
+        impl Drop for HiddenWindow {
+            fn drop(&mut self) {
+                unsafe { DestroyWindow(self.0) };
+            }
+        }
+
+        let class_name: Vec<u16> = "ChaiLauncherGlProbe\0".encode_utf16().collect();
+        let hinstance = unsafe { GetModuleHandleW(std::ptr::null()) };
+        let class = WNDCLASSEXW {
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            style: CS_OWNDC,
+            lpfnWndProc: Some(DefWindowProcW),
+            hInstance: hinstance,
+            lpszClassName: class_name.as_ptr(),
+            ..unsafe { std::mem::zeroed() }
+        };
+        unsafe { RegisterClassExW(&class) };
+        let hwnd = unsafe {
+            CreateWindowExW(
+                0, class_name.as_ptr(), class_name.as_ptr(), WS_POPUP,
+                0, 0, 1, 1,
+                std::ptr::null_mut(), std::ptr::null_mut(), hinstance, std::ptr::null_mut(),
+            )
+        };
+        if hwnd.is_null() {
+            return Err(format!("Failed to create a window: {}", std::io::Error::last_os_error()));
+        }
+        let window = HiddenWindow(hwnd);
+
+        let mut handle = Win32WindowHandle::new(NonZeroIsize::new(window.0 as isize).ok_or("Invalid window handle")?);
+        handle.hinstance = NonZeroIsize::new(hinstance as isize);
+        let window_handle = RawWindowHandle::Win32(handle);
+
+        let display = unsafe { Display::new(RawDisplayHandle::Windows(WindowsDisplayHandle::new()), Some(window_handle)) }
+            .map_err(|e| format!("Failed to load WGL: {}", e))?;
+        let template = ConfigTemplateBuilder::new()
+            .compatible_with_native_window(window_handle)
+            .build();
+        let config = first_config(&display, template)?;
+        let _context = create_context(&display, &config)?
+            .make_current_surfaceless()
+            .map_err(|e| format!("Failed to make the OpenGL context current: {}", e))?;
+        read_strings(&display)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_driver_version_strings() {
+        assert_eq!(parse_gl_version("4.5 (Core Profile) Mesa 22.3.6"), Some((4, 5)));
+        assert_eq!(parse_gl_version("4.6.0 NVIDIA 531.79"), Some((4, 6)));
+        assert_eq!(parse_gl_version("1.1.0"), Some((1, 1)));
+        assert_eq!(parse_gl_version("garbage"), None);
+    }
+
+    #[test]
+    fn spots_software_renderers() {
+        assert!(is_software_renderer("llvmpipe (LLVM 15.0.6, 256 bits)"));
+        assert!(is_software_renderer("GDI Generic"));
+        assert!(is_software_renderer("Microsoft Basic Render Driver"));
+        assert!(!is_software_renderer("NVIDIA GeForce RTX 3060/PCIe/SSE2"));
+        assert!(!is_software_renderer("AMD Radeon RX 6600 (radeonsi, navi23, LLVM 15.0.6)"));
+    }
+
+    #[test]
+    fn warns_about_old_or_software_opengl() {
+        let intel = GraphicsProbe::found("Intel".to_string(), "Mesa Intel(R) HD Graphics 3000".to_string(), "3.1 Mesa 22.3.6".to_string());
+        assert!(launch_warnings(&intel, "1.16.5").is_empty());
+        let warnings = launch_warnings(&intel, "1.20.1");
+        assert_eq!(warnings, vec!["Minecraft 1.20.1 needs OpenGL 3.2 but the driver only offers 3.1".to_string()]);
+
+        let llvmpipe = GraphicsProbe::found("Mesa".to_string(), "llvmpipe (LLVM 15.0.6, 256 bits)".to_string(), "4.5 (Core Profile) Mesa 22.3.6".to_string());
+        assert!(llvmpipe.software);
+        assert_eq!(launch_warnings(&llvmpipe, "1.20.1").len(), 1);
+
+        let crashed = GraphicsProbe::failed("The probe exited with signal: 11".to_string(), true);
+        assert_eq!(launch_warnings(&crashed, "1.20.1").len(), 1);
+        assert!(launch_warnings(&GraphicsProbe::failed("no EGL".to_string(), false), "1.20.1").is_empty());
+    }
+
+    #[test]
+    fn reads_the_result_line_of_the_probe() {
+        let probe = GraphicsProbe::found("Mesa".to_string(), "llvmpipe".to_string(), "4.5".to_string());
+        let stdout = format!("libEGL warning: DRI2: failed to authenticate\n{}\n", serde_json::to_string(&probe).unwrap());
+        assert_eq!(parse_child_output(&stdout), Some(probe));
+        assert_eq!(parse_child_output("Segmentation fault\n"), None);
+    }
+}
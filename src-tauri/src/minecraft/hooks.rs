@@ -0,0 +1,290 @@
+//! Commands run before a launch and after the game exits
+//!
+//! Hooks run through the system shell in the instance directory, with
+//! `CHAI_INSTANCE_ID`, `CHAI_INSTANCE_DIR` and `CHAI_MC_VERSION` set. Their output
+//! goes to the instance's `logs/chailauncher/game.log`. A hook still running after
+//! `hook_timeout_secs` is killed with every process it started, so a stuck one can't
+//! hold up launches. Processes a hook leaves running in the background don't hold it
+//! up either. Every run goes through `exec_audit`, which may ask the user first.
+
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::game_log::{self, GameLogStream};
+use crate::exec_audit::{self, ExecFeature, ExecOutcome, ExecRequest};
+
+/// Hook timeout used when the settings can't be read
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Output kept per stream, the rest is read and dropped so the hook never blocks on a full pipe
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// How long output is still read after the shell exits, a process it left running may hold the pipes
+const OUTPUT_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HookKind {
+    PreLaunch,
+    PostExit,
+}
+
+impl HookKind {
+    fn label(self) -> &'static str {
+        match self {
+            HookKind::PreLaunch => "pre-launch",
+            HookKind::PostExit => "post-exit",
+        }
+    }
+
+    fn feature(self) -> ExecFeature {
+        match self {
+            HookKind::PreLaunch => ExecFeature::PreLaunchHook,
+            HookKind::PostExit => ExecFeature::PostExitHook,
+        }
+    }
+}
+
+/// How a hook run went
+struct HookRun {
+    outcome: ExecOutcome,
+    exit_code: Option<i32>,
+    result: Result<(), String>,
+}
+
+/// The instance a hook runs for
+struct HookTarget<'a> {
+    instance_id: &'a str,
+    game_dir: &'a Path,
+    version: &'a str,
+}
+
+/// Run the instance's pre-launch hook, if it has one
+///
+/// A hook that fails, times out or isn't allowed aborts the launch, the error carries its stderr.
+pub async fn run_pre_launch(instance_id: &str, app_handle: &AppHandle) -> Result<(), String> {
+    run_configured(instance_id, HookKind::PreLaunch, app_handle).await
+}
+
+/// Run the instance's post-exit hook, if it has one
+///
+/// The game is already gone, so a failure is only logged.
+pub async fn run_post_exit(instance_id: &str, app_handle: &AppHandle) {
+    if let Err(e) = run_configured(instance_id, HookKind::PostExit, app_handle).await {
+        println!("⚠️  {}", e);
+    }
+}
+
+async fn run_configured(instance_id: &str, kind: HookKind, app_handle: &AppHandle) -> Result<(), String> {
+    // Read from storage rather than the launch request so only saved hooks ever run
+    let storage = crate::storage::shared().await
+        .map_err(|e| format!("Failed to read {} hook: {}", kind.label(), e))?;
+    let storage = storage.read().await;
+    let Some(instance) = storage.get_instance(instance_id).cloned() else {
+        return Ok(());
+    };
+    let timeout = Duration::from_secs(storage.get_settings().hook_timeout_secs.max(1));
+    drop(storage);
+
+    let command = match kind {
+        HookKind::PreLaunch => instance.pre_launch_command,
+        HookKind::PostExit => instance.post_exit_command,
+    };
+    let Some(command) = command.filter(|command| !command.trim().is_empty()) else {
+        return Ok(());
+    };
+
+    let (program, args) = shell(&command);
+    let request = ExecRequest {
+        feature: kind.feature(),
+        command: &command,
+        program,
+        args: &args,
+        cwd: &instance.game_dir,
+        instance_id: Some(instance_id),
+    };
+    exec_audit::authorize(app_handle, &request).await?;
+
+    let target = HookTarget { instance_id, game_dir: &instance.game_dir, version: &instance.version };
+    let started = Instant::now();
+    let run = run(kind, &command, &target, timeout).await;
+    exec_audit::record(&request, run.outcome, run.exit_code, started.elapsed()).await;
+    run.result
+}
+
+async fn run(kind: HookKind, command: &str, target: &HookTarget<'_>, timeout: Duration) -> HookRun {
+    println!("🪝 Running {} hook for instance {}: {}", kind.label(), target.instance_id, command);
+    let prefix = format!("[{} hook]", kind.label());
+
+    let (program, args) = shell(command);
+    let mut process = tokio::process::Command::new(program);
+    process.args(&args)
+        .current_dir(target.game_dir)
+        .env("CHAI_INSTANCE_ID", target.instance_id)
+        .env("CHAI_INSTANCE_DIR", target.game_dir)
+        .env("CHAI_MC_VERSION", target.version)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Its own group, so a timeout stops what the hook started along with the shell
+    #[cfg(unix)]
+    process.process_group(0);
+
+    let failed_to_start = |message: String| HookRun { outcome: ExecOutcome::FailedToStart, exit_code: None, result: Err(message) };
+    let mut child = match process.spawn() {
+        Ok(child) => child,
+        Err(e) => return failed_to_start(format!("Failed to start {} hook: {}", kind.label(), e)),
+    };
+
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let stderr = Arc::new(Mutex::new(Vec::new()));
+    let readers = [
+        child.stdout.take().map(|pipe| tokio::spawn(read_capped(pipe, stdout.clone()))),
+        child.stderr.take().map(|pipe| tokio::spawn(read_capped(pipe, stderr.clone()))),
+    ];
+    let mut readers: Vec<_> = readers.into_iter().flatten().collect();
+
+    let status = match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(Ok(status)) => status,
+        Ok(Err(e)) => return failed_to_start(format!("Failed to run {} hook: {}", kind.label(), e)),
+        Err(_) => {
+            kill_tree(&mut child).await;
+            for reader in &readers {
+                reader.abort();
+            }
+            let message = format!("{} hook timed out after {}s and was stopped", capitalize(kind.label()), timeout.as_secs());
+            game_log::append_lines(target.game_dir, &[(GameLogStream::Stderr, format!("{} {}", prefix, message))]).await;
+            return HookRun { outcome: ExecOutcome::TimedOut, exit_code: None, result: Err(message) };
+        }
+    };
+
+    // Pipes held open by a background process never close, keep what came so far
+    let _ = tokio::time::timeout(OUTPUT_GRACE, async {
+        for reader in &mut readers {
+            let _ = reader.await;
+        }
+    }).await;
+    for reader in &readers {
+        reader.abort();
+    }
+
+    let stdout = String::from_utf8_lossy(&stdout.lock().unwrap()).to_string();
+    let stderr = String::from_utf8_lossy(&stderr.lock().unwrap()).to_string();
+    let mut lines: Vec<(GameLogStream, String)> = stdout.lines()
+        .map(|line| (GameLogStream::Stdout, format!("{} {}", prefix, line)))
+        .chain(stderr.lines().map(|line| (GameLogStream::Stderr, format!("{} {}", prefix, line))))
+        .collect();
+    lines.push((GameLogStream::Stdout, format!("{} exited with {}", prefix, status)));
+    game_log::append_lines(target.game_dir, &lines).await;
+
+    let exit_code = status.code();
+    if status.success() {
+        return HookRun { outcome: ExecOutcome::Exited, exit_code, result: Ok(()) };
+    }
+    let stderr = stderr.trim();
+    let message = if stderr.is_empty() {
+        format!("{} hook failed ({})", capitalize(kind.label()), status)
+    } else {
+        format!("{} hook failed ({}): {}", capitalize(kind.label()), status, stderr)
+    };
+    HookRun { outcome: ExecOutcome::Exited, exit_code, result: Err(message) }
+}
+
+/// Read `pipe` to the end, keeping the first `MAX_OUTPUT_BYTES`
+async fn read_capped(mut pipe: impl AsyncRead + Unpin, kept: Arc<Mutex<Vec<u8>>>) {
+    let mut buffer = [0u8; 8192];
+    while let Ok(read @ 1..) = pipe.read(&mut buffer).await {
+        let mut kept = kept.lock().unwrap();
+        let room = MAX_OUTPUT_BYTES.saturating_sub(kept.len());
+        kept.extend_from_slice(&buffer[..read.min(room)]);
+    }
+}
+
+/// Force the hook and every process it started to stop
+#[cfg(windows)]
+async fn kill_tree(child: &mut tokio::process::Child) {
+    if let Some(pid) = child.id() {
+        let _ = tokio::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .status()
+            .await;
+    }
+    let _ = child.kill().await;
+}
+
+#[cfg(unix)]
+async fn kill_tree(child: &mut tokio::process::Child) {
+    if let Some(pid) = child.id() {
+        // The shell leads the group, a negative pid signals all of it
+        let _ = tokio::process::Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", pid)])
+            .status()
+            .await;
+    }
+    let _ = child.kill().await;
+}
+
+/// Program and arguments that run `command` through the system shell
+#[cfg(windows)]
+fn shell(command: &str) -> (&'static str, Vec<String>) {
+    ("cmd", vec!["/C".to_string(), command.to_string()])
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> (&'static str, Vec<String>) {
+    ("sh", vec!["-c".to_string(), command.to_string()])
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hooks_see_the_instance_and_fail_with_their_stderr() {
+        let game_dir = std::env::temp_dir().join(format!("chai-hooks-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&game_dir).unwrap();
+        let target = HookTarget { instance_id: "inst", game_dir: &game_dir, version: "1.20.1" };
+        let timeout = Duration::from_secs(10);
+
+        run(HookKind::PreLaunch, "echo \"$CHAI_INSTANCE_ID $CHAI_MC_VERSION\" > hook.txt", &target, timeout).await.result.unwrap();
+        assert_eq!(std::fs::read_to_string(game_dir.join("hook.txt")).unwrap(), "inst 1.20.1\n");
+
+        let failed = run(HookKind::PreLaunch, "echo 'saves are locked' >&2; exit 3", &target, timeout).await;
+        assert_eq!(failed.exit_code, Some(3));
+        let error = failed.result.unwrap_err();
+        assert!(error.starts_with("Pre-launch hook failed"));
+        assert!(error.ends_with("saves are locked"));
+
+        let timed_out = run(HookKind::PostExit, "sleep 5", &target, Duration::from_millis(200)).await;
+        assert_eq!(timed_out.outcome, ExecOutcome::TimedOut);
+        assert!(timed_out.result.unwrap_err().contains("timed out"));
+
+        let started = Instant::now();
+        run(HookKind::PreLaunch, "sleep 3 & echo started", &target, timeout).await.result.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2), "a background process held up the hook");
+
+        run(HookKind::PostExit, "sleep 30 & echo $! > background.pid; wait", &target, Duration::from_millis(200)).await;
+        let background = std::fs::read_to_string(game_dir.join("background.pid")).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Killed children may linger as zombies until something reaps them
+        let state = std::process::Command::new("ps").args(["-o", "stat=", "-p", background.trim()]).output().unwrap();
+        let state = String::from_utf8_lossy(&state.stdout);
+        assert!(state.trim().is_empty() || state.trim().starts_with('Z'), "the timed out hook left its child running");
+
+        let log = game_log::recent_lines("inst", &game_dir, 10).await;
+        assert!(log.iter().any(|line| line.line == "[pre-launch hook] saves are locked"));
+        std::fs::remove_dir_all(game_dir).unwrap();
+    }
+}
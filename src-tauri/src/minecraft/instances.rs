@@ -28,6 +28,7 @@ impl Instance {
         ).await?;
 
         // Create ChaiLauncher instance
+        let (ready, not_ready_reason) = super::readiness::status(&game_dir, &version);
         let chai_instance = MinecraftInstance {
             id: uuid::Uuid::new_v4().to_string(),
             name: name.clone(),
@@ -46,36 +47,32 @@ impl Instance {
             external_launcher: None,
             resolved_java_version: None,
             java_analysis_date: None,
+            log_cleanup: None,
+            game_args: None,
+            pre_launch_command: None,
+            post_exit_command: None,
+            env_vars: None,
+            clear_inherited_env: false,
+            window_width: None,
+            window_height: None,
+            fullscreen: None,
+            jvm_args_preset: None,
+            created_at: Some(chrono::Utc::now().timestamp_millis()),
+            size_mb: None,
+            ready,
+            not_ready_reason,
+            unstable: false,
+            health: None,
         };
 
         // Store in ChaiLauncher's storage system
-        let mut storage = crate::storage::StorageManager::new().await
+        let storage = crate::storage::shared().await
             .map_err(|e| format!("Failed to initialize storage: {}", e))?;
         
-        // Convert to InstanceMetadata for storage
-        let instance_metadata = crate::storage::InstanceMetadata {
-            id: chai_instance.id.clone(),
-            name: chai_instance.name.clone(),
-            version: chai_instance.version.clone(),
-            game_dir: chai_instance.game_dir.clone(),
-            java_path: chai_instance.java_path.clone(),
-            jvm_args: chai_instance.jvm_args.clone(),
-            last_played: chai_instance.last_played.clone(),
-            total_play_time: chai_instance.total_play_time,
-            icon: chai_instance.icon.clone(),
-            is_modded: chai_instance.is_modded,
-            mods_count: chai_instance.mods_count,
-            modpack: chai_instance.modpack.clone(),
-            modpack_version: chai_instance.modpack_version.clone(),
-            created_at: chrono::Utc::now().to_rfc3339(),
-            size_mb: None, // Will be calculated later
-            description: None,
-            tags: vec![],
-            resolved_java_version: chai_instance.resolved_java_version,
-            java_analysis_date: chai_instance.java_analysis_date.clone(),
-        };
+        // Convert to InstanceMetadata for storage, size is calculated when added
+        let instance_metadata = crate::storage::InstanceMetadata::from(chai_instance.clone());
         
-        storage.add_instance(instance_metadata).await
+        storage.write().await.add_instance(instance_metadata).await
             .map_err(|e| format!("Failed to add instance: {}", e))?;
 
         println!("✅ Instance '{}' created successfully", chai_instance.name);
@@ -102,31 +99,14 @@ impl Instance {
     /// Get instance information using ChaiLauncher's storage
     pub async fn get_info(instance_id: &str) -> Result<Option<MinecraftInstance>, String> {
         // Load from ChaiLauncher's storage system
-        let storage = crate::storage::StorageManager::new().await
+        let storage = crate::storage::shared().await
             .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+        let storage = storage.read().await;
         
         match storage.get_instance(instance_id) {
             Some(metadata) => {
-                // Convert from InstanceMetadata to MinecraftInstance
-                let instance = MinecraftInstance {
-                    id: metadata.id.clone(),
-                    name: metadata.name.clone(),
-                    version: metadata.version.clone(),
-                    game_dir: metadata.game_dir.clone(),
-                    java_path: metadata.java_path.clone(),
-                    jvm_args: metadata.jvm_args.clone(),
-                    last_played: metadata.last_played.clone(),
-                    total_play_time: metadata.total_play_time,
-                    icon: metadata.icon.clone(),
-                    is_modded: metadata.is_modded,
-                    mods_count: metadata.mods_count,
-                    is_external: Some(false), // Default for MCVM-managed instances
-                    external_launcher: None,
-                    modpack: metadata.modpack.clone(),
-                    modpack_version: metadata.modpack_version.clone(),
-                    resolved_java_version: metadata.resolved_java_version,
-                    java_analysis_date: metadata.java_analysis_date.clone(),
-                };
+                let mut instance = MinecraftInstance::from(metadata.clone());
+                instance.is_external = Some(false); // Default for MCVM-managed instances
                 Ok(Some(instance))
             },
             None => Ok(None)
@@ -135,33 +115,17 @@ impl Instance {
 
     /// List all instances from ChaiLauncher's storage
     pub async fn list_all() -> Result<Vec<MinecraftInstance>, String> {
-        let storage = crate::storage::StorageManager::new().await
+        let storage = crate::storage::shared().await
             .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+        let storage = storage.read().await;
         
         // Get all instances from storage and convert them
         let metadata_instances = storage.get_all_instances();
         
         let mut instances = Vec::new();
         for metadata in metadata_instances {
-            let instance = MinecraftInstance {
-                id: metadata.id.clone(),
-                name: metadata.name.clone(),
-                version: metadata.version.clone(),
-                game_dir: metadata.game_dir.clone(),
-                java_path: metadata.java_path.clone(),
-                jvm_args: metadata.jvm_args.clone(),
-                last_played: metadata.last_played.clone(),
-                total_play_time: metadata.total_play_time,
-                icon: metadata.icon.clone(),
-                is_modded: metadata.is_modded,
-                mods_count: metadata.mods_count,
-                is_external: Some(false),
-                external_launcher: None,
-                modpack: metadata.modpack.clone(),
-                modpack_version: metadata.modpack_version.clone(),
-                resolved_java_version: metadata.resolved_java_version,
-                java_analysis_date: metadata.java_analysis_date.clone(),
-            };
+            let mut instance = MinecraftInstance::from(metadata.clone());
+            instance.is_external = Some(false);
             instances.push(instance);
         }
         
@@ -170,10 +134,10 @@ impl Instance {
 
     /// Delete an instance
     pub async fn delete(instance_id: &str) -> Result<(), String> {
-        let mut storage = crate::storage::StorageManager::new().await
+        let storage = crate::storage::shared().await
             .map_err(|e| format!("Failed to initialize storage: {}", e))?;
         
-        storage.remove_instance(instance_id).await
+        storage.write().await.remove_instance(instance_id).await
             .map_err(|e| format!("Failed to remove instance: {}", e))?;
 
         println!("✅ Instance '{}' deleted successfully", instance_id);
@@ -0,0 +1,339 @@
+//! Background integrity sweep and the health it records per instance
+//!
+//! When enabled, an idle launcher checks one instance at a time: version files
+//! and libraries, the asset index, the mod manifest against the mods folder and
+//! whether the installed mod loader fits the mods. The sweep waits between files
+//! so it stays out of the way, and drops the check in progress as soon as a task,
+//! a game or a modpack install starts. Results are kept with the instance and
+//! point at the command that repairs them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::sessions::PlaySessionsState;
+use super::{assets, profile, readiness};
+use crate::modpack::ModpackInstallsState;
+use crate::tasks::TaskManagerState;
+
+/// How often the sweep looks for an instance to check
+const SWEEP_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often an idle launcher is checked for having become busy mid-check
+const BUSY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An instance is checked again once its last result is this old
+const RECHECK_AFTER_HOURS: i64 = 24;
+
+/// Pause between files when the settings can't be read
+pub const DEFAULT_PACE_MS: u32 = 20;
+
+/// Names listed in an issue before the rest are counted
+const MAX_NAMED: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Issues,
+}
+
+/// What part of an instance an issue is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    VersionFiles,
+    Libraries,
+    AssetIndex,
+    Mods,
+    Loader,
+}
+
+/// Command that fixes an issue, serialized as its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairAction {
+    /// Downloads missing version files, libraries and assets
+    PrepareInstance,
+    /// Downloads mods the manifest lists but the mods folder lacks
+    RepairInstanceMods,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthIssue {
+    pub kind: IssueKind,
+    pub message: String,
+    /// None when the player has to decide, e.g. which of two loaders to keep
+    pub repair: Option<RepairAction>,
+}
+
+/// Result of the last integrity check of an instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceHealth {
+    pub status: HealthStatus,
+    pub issues: Vec<HealthIssue>,
+    /// RFC 3339
+    pub checked_at: String,
+}
+
+impl InstanceHealth {
+    fn new(issues: Vec<HealthIssue>) -> Self {
+        Self {
+            status: if issues.is_empty() { HealthStatus::Ok } else { HealthStatus::Issues },
+            issues,
+            checked_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Whether one of the issues is fixed by `action`
+    pub fn needs(&self, action: RepairAction) -> bool {
+        self.issues.iter().any(|issue| issue.repair == Some(action))
+    }
+}
+
+/// Check an instance, waiting `pace` after each file looked at
+pub async fn check(game_dir: &Path, version: &str, pace: Duration) -> InstanceHealth {
+    let mut issues = Vec::new();
+    let issue = |kind, message: String, repair| HealthIssue { kind, message, repair };
+
+    if !game_dir.is_dir() {
+        issues.push(issue(IssueKind::VersionFiles, "Instance folder is missing".to_string(), None));
+        return InstanceHealth::new(issues);
+    }
+
+    // Version files and libraries, through the same profile the launch uses
+    let version_dir = game_dir.join("versions").join(version);
+    if !version_dir.join(format!("{}.json", version)).is_file() {
+        issues.push(issue(IssueKind::VersionFiles, format!("Version information for Minecraft {} is not downloaded", version), Some(RepairAction::PrepareInstance)));
+    } else if !version_dir.join(format!("{}.jar", version)).is_file() {
+        issues.push(issue(IssueKind::VersionFiles, format!("Minecraft {} is not downloaded", version), Some(RepairAction::PrepareInstance)));
+    }
+    if let Some(profile) = profile::load(game_dir, version).await {
+        let mut missing = Vec::new();
+        for jar in profile.classpath(&game_dir.join("libraries"), &game_dir.join("versions")) {
+            if !jar.is_file() && !jar.ends_with(format!("{}.jar", version)) {
+                missing.push(jar.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default());
+            }
+            tokio::time::sleep(pace).await;
+        }
+        if !missing.is_empty() {
+            issues.push(issue(IssueKind::Libraries, format!("{} missing: {}", plural(missing.len(), "library", "libraries"), named(&missing)), Some(RepairAction::PrepareInstance)));
+        }
+    }
+
+    match assets::resolve_asset_index(game_dir, version).await {
+        Ok(Some(index)) if !game_dir.join("assets").join("indexes").join(format!("{}.json", index.id)).is_file() => {
+            issues.push(issue(IssueKind::AssetIndex, format!("Asset index {} is missing", index.id), Some(RepairAction::PrepareInstance)));
+        }
+        Err(e) => issues.push(issue(IssueKind::AssetIndex, e, None)),
+        _ => {}
+    }
+    tokio::time::sleep(pace).await;
+
+    // Mod manifest against the mods folder
+    let installed_mods = crate::mods::ModManager::read_installed_mods(game_dir).await;
+    match &installed_mods {
+        Ok(installed_mods) => {
+            let mut missing = Vec::new();
+            for installed_mod in installed_mods.values() {
+                if !installed_mod.install_path.is_file() {
+                    missing.push(installed_mod.mod_info.name.clone());
+                }
+                tokio::time::sleep(pace).await;
+            }
+            if !missing.is_empty() {
+                missing.sort();
+                issues.push(issue(IssueKind::Mods, format!("{} missing from the mods folder: {}", plural(missing.len(), "mod is", "mods are"), named(&missing)), Some(RepairAction::RepairInstanceMods)));
+            }
+        }
+        Err(e) => issues.push(issue(IssueKind::Mods, format!("The mod list can't be read: {}", e), None)),
+    }
+
+    // Loader consistency
+    let loaders = readiness::installed_loaders(game_dir);
+    if loaders.len() > 1 {
+        issues.push(issue(IssueKind::Loader, format!("Several mod loaders are installed: {}", loaders.join(", ")), None));
+    } else if let (Some(loader), Ok(installed_mods)) = (loaders.first(), &installed_mods) {
+        let mut mismatched: Vec<String> = installed_mods.values()
+            .filter(|m| m.enabled && !runs_on(&m.installed_file.loaders, loader))
+            .map(|m| m.mod_info.name.clone())
+            .collect();
+        if !mismatched.is_empty() {
+            mismatched.sort();
+            issues.push(issue(IssueKind::Loader, format!("{} not made for {}: {}", plural(mismatched.len(), "mod is", "mods are"), loader, named(&mismatched)), None));
+        }
+    }
+
+    InstanceHealth::new(issues)
+}
+
+/// Whether a mod file listing `loaders` runs on `loader`, unknown files are given the benefit of the doubt
+fn runs_on(loaders: &[String], loader: &str) -> bool {
+    loaders.is_empty() || loaders.iter().any(|l| {
+        l.eq_ignore_ascii_case(loader) || (loader == "Quilt" && l.eq_ignore_ascii_case("fabric"))
+    })
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    if count == 1 { format!("1 {}", one) } else { format!("{} {}", count, many) }
+}
+
+fn named(names: &[String]) -> String {
+    match names.len() {
+        n if n > MAX_NAMED => format!("{} and {} more", names[..MAX_NAMED].join(", "), n - MAX_NAMED),
+        _ => names.join(", "),
+    }
+}
+
+/// The instance the sweep should check next: never checked first, then the longest ago
+///
+/// `instances` are `(id, checked_at)`. Instances checked in the last day are skipped.
+pub fn next_due<'a>(instances: impl IntoIterator<Item = (&'a str, Option<&'a str>)>, now: DateTime<Utc>) -> Option<&'a str> {
+    let cutoff = now - chrono::Duration::hours(RECHECK_AFTER_HOURS);
+    instances.into_iter()
+        .map(|(id, checked_at)| (id, checked_at.and_then(|t| DateTime::parse_from_rfc3339(t).ok()).map(|t| t.with_timezone(&Utc))))
+        .filter(|(_, checked_at)| checked_at.is_none_or(|t| t < cutoff))
+        .min_by_key(|(id, checked_at)| (*checked_at, *id))
+        .map(|(id, _)| id)
+}
+
+/// Check an instance right away and store the result, e.g. after a repair
+pub async fn refresh(instance_id: &str, app_handle: &AppHandle) -> Option<InstanceHealth> {
+    let storage = crate::storage::shared().await.ok()?;
+    let (game_dir, version) = storage.read().await
+        .get_instance(instance_id)
+        .map(|instance| (instance.game_dir.clone(), instance.version.clone()))?;
+    let health = check(&game_dir, &version, Duration::ZERO).await;
+    record(instance_id, health.clone(), app_handle).await;
+    Some(health)
+}
+
+async fn record(instance_id: &str, health: InstanceHealth, app_handle: &AppHandle) {
+    let Ok(storage) = crate::storage::shared().await else {
+        return;
+    };
+    if let Err(e) = storage.write().await.record_instance_health(instance_id, health).await {
+        println!("⚠️  Failed to save the health of instance {}: {}", instance_id, e);
+        return;
+    }
+    super::commands::instances::notify_instances_updated(app_handle);
+}
+
+/// No tasks, games or modpack installs running
+fn is_idle(app_handle: &AppHandle) -> bool {
+    app_handle.state::<TaskManagerState>().active_tasks().is_empty()
+        && app_handle.state::<PlaySessionsState>().running_instances().is_empty()
+        && app_handle.state::<ModpackInstallsState>().active().is_empty()
+}
+
+/// Resolves once the launcher is no longer idle
+async fn busy(app_handle: &AppHandle) {
+    let tasks = app_handle.state::<TaskManagerState>().inner().clone();
+    tokio::select! {
+        _ = tasks.task_started() => {}
+        _ = async {
+            while is_idle(app_handle) {
+                tokio::time::sleep(BUSY_POLL_INTERVAL).await;
+            }
+        } => {}
+    }
+}
+
+/// Check instances in the background while the launcher is idle, if the settings allow it
+pub fn start_sweep(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if crate::shutdown::is_shutting_down() {
+                break;
+            }
+            sweep_next(&app_handle).await;
+        }
+    });
+}
+
+async fn sweep_next(app_handle: &AppHandle) {
+    let Ok(storage) = crate::storage::shared().await else {
+        return;
+    };
+    let (pace, instance) = {
+        let storage = storage.read().await;
+        let settings = storage.get_settings();
+        if !settings.integrity_sweep_enabled {
+            return;
+        }
+        let instances = storage.get_all_instances();
+        let next = next_due(
+            instances.iter().map(|i| (i.id.as_str(), i.health.as_ref().map(|h| h.checked_at.as_str()))),
+            Utc::now(),
+        );
+        let instance = next.and_then(|id| instances.iter().find(|i| i.id == id))
+            .map(|i| (i.id.clone(), i.name.clone(), i.game_dir.clone(), i.version.clone()));
+        (Duration::from_millis(settings.integrity_sweep_pace_ms as u64), instance)
+    };
+    let Some((id, name, game_dir, version)) = instance else {
+        return;
+    };
+    if !is_idle(app_handle) {
+        return;
+    }
+
+    let health = tokio::select! {
+        health = check(&game_dir, &version, pace) => health,
+        _ = busy(app_handle) => {
+            println!("⏸️  Integrity check of {} paused, the launcher is busy", name);
+            return;
+        }
+    };
+    match health.status {
+        HealthStatus::Ok => println!("🩺 {} passed its integrity check", name),
+        HealthStatus::Issues => println!("🩺 {} has {} integrity issue(s)", name, health.issues.len()),
+    }
+    record(&id, health, app_handle).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchecked_and_stale_instances_come_first() {
+        let now = DateTime::parse_from_rfc3339("2024-05-10T12:00:00+00:00").unwrap().with_timezone(&Utc);
+        let fresh = "2024-05-10T08:00:00+00:00";
+        let stale = "2024-05-08T12:00:00+00:00";
+        let older = "2024-05-01T12:00:00+00:00";
+
+        assert_eq!(next_due([("a", Some(fresh)), ("b", None), ("c", Some(stale))], now), Some("b"));
+        assert_eq!(next_due([("a", Some(fresh)), ("c", Some(stale)), ("d", Some(older))], now), Some("d"));
+        assert_eq!(next_due([("a", Some(fresh))], now), None);
+    }
+
+    #[tokio::test]
+    async fn missing_files_and_mods_are_reported_with_their_repair() {
+        let dir = std::env::temp_dir().join(format!("chai-health-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::write(dir.join("mods").join(".fabric_installed"), "fabric-0.15.11").unwrap();
+        std::fs::write(dir.join("mods").join(".forge_installed"), "forge-47.2.0").unwrap();
+
+        let health = check(&dir, "1.20.1", Duration::ZERO).await;
+        assert_eq!(health.status, HealthStatus::Issues);
+        assert!(health.needs(RepairAction::PrepareInstance));
+        assert!(!health.needs(RepairAction::RepairInstanceMods));
+        let loader = health.issues.iter().find(|issue| issue.kind == IssueKind::Loader).unwrap();
+        assert_eq!(loader.message, "Several mod loaders are installed: Forge, Fabric");
+        assert!(loader.repair.is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn quilt_runs_fabric_mods() {
+        assert!(runs_on(&["fabric".to_string()], "Quilt"));
+        assert!(!runs_on(&["forge".to_string()], "Fabric"));
+        assert!(runs_on(&[], "Forge"));
+        assert_eq!(named(&(1..=7).map(|i| i.to_string()).collect::<Vec<_>>()), "1, 2, 3, 4, 5 and 2 more");
+    }
+}
@@ -10,11 +10,15 @@
 //! - Supports multiple Java versions (8, 17, 21)
 //! - Platform-aware (Windows, macOS, Linux)
 //!
-//! ChaiLauncher manages its own Java installations, independent of system Java. MCVM integration does not affect this behavior.
+//! ChaiLauncher prefers its own Java installations and only falls back to a system
+//! install of the exact major version, see [`resolve_java`]. MCVM integration does not affect this behavior.
 
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tauri::Emitter;
 
+use crate::net::manager::{DownloadKind, DownloadPriority};
+
 /// Represents information about a Java installation managed by ChaiLauncher.
 ///
 /// Fields:
@@ -161,37 +165,22 @@ async fn download_file_with_progress(
 ) -> Result<(), String> {
     println!("📥 Downloading from: {}", url);
     
-    let response = reqwest::get(url).await
-        .map_err(|e| format!("Failed to start download: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Download failed with status: {}", response.status()));
-    }
-    
-    let total_size = response.content_length().unwrap_or(0);
-    let mut downloaded = 0u64;
-    
-    let mut file = std::fs::File::create(dest)
-        .map_err(|e| format!("Failed to create file: {}", e))?;
-    
-    let mut stream = response.bytes_stream();
-    use futures::StreamExt;
-    
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("Download error: {}", e))?;
-        std::io::Write::write_all(&mut file, &chunk)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
-        
-        downloaded += chunk.len() as u64;
-        
-        if total_size > 0 {
-            let progress = (downloaded as f64 / total_size as f64) * 80.0; // Reserve 20% for extraction
-            let _ = app_handle.emit("java_install_progress", serde_json::json!({
-                "stage": "Downloading Java...",
-                "progress": progress as u32
-            }));
-        }
-    }
+    let app_handle = app_handle.clone();
+    let options = crate::net::DownloadOptions {
+        progress: Some(std::sync::Arc::new(move |downloaded, total_size| {
+            if total_size > 0 {
+                let progress = (downloaded as f64 / total_size as f64) * 80.0; // Reserve 20% for extraction
+                let _ = app_handle.emit("java_install_progress", serde_json::json!({
+                    "stage": "Downloading Java...",
+                    "progress": progress as u32
+                }));
+            }
+        })),
+        ..Default::default()
+    };
+    let downloads = crate::net::manager::shared();
+    downloads.download(DownloadKind::Java, DownloadPriority::High, url, dest, options).await
+        .map_err(|e| format!("Failed to download Java: {}", e))?;
     
     println!("✓ Download completed: {}", dest.display());
     Ok(())
@@ -394,4 +383,367 @@ pub async fn validate_java_installation(java_path: &str) -> Result<String, Strin
     } else {
         Err("Java validation failed".to_string())
     }
-}
\ No newline at end of file
+}
+/// Where a resolved Java runtime came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JavaSource {
+    /// Installed by ChaiLauncher into its own java directory
+    Bundled,
+    /// Found on the system through JAVA_HOME, PATH or a vendor install folder
+    System,
+    /// Chosen as the Java path in the launcher settings
+    Custom,
+}
+
+/// The Java runtime picked for a major version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedJava {
+    pub major_version: u32,
+    pub path: String,
+    pub source: JavaSource,
+    /// Full version reported by `java -version`, e.g. `17.0.9` or `1.8.0_381`
+    pub version: String,
+}
+
+/// Find a Java runtime for `major_version`
+///
+/// The launcher's own install wins. Otherwise a system install reporting exactly
+/// that major version is used, unless the `java_bundled_only` setting is on.
+pub async fn resolve_java(major_version: u32) -> Result<ResolvedJava, String> {
+    let bundled_error = match super::versions::get_java_for_version(major_version).await {
+        Ok(path) => match probe_java(&path).await {
+            Some((major, version)) if major == major_version => {
+                return Ok(ResolvedJava { major_version, path, source: JavaSource::Bundled, version });
+            }
+            Some((major, _)) => format!("Bundled Java at {} reports Java {}", path, major),
+            None => format!("Bundled Java at {} failed to run", path),
+        },
+        Err(e) => e,
+    };
+
+    if bundled_only().await {
+        return Err(format!("{}\n\nSystem Java installations are disabled in the Java settings.", bundled_error));
+    }
+
+    for candidate in system_java_candidates() {
+        let path = candidate.to_string_lossy().to_string();
+        if let Some((major, version)) = probe_java(&path).await {
+            if major == major_version {
+                println!("☕ Using system Java {} ({}) at {}", major_version, version, path);
+                return Ok(ResolvedJava { major_version, path, source: JavaSource::System, version });
+            }
+        }
+    }
+
+    Err(format!("{}\n\nNo system installation of Java {} was found either.", bundled_error, major_version))
+}
+
+/// Find a Java runtime for `major_version`, installing the launcher's own when there is none
+///
+/// The download reports progress through `java_install_progress` like a manual install.
+pub async fn ensure_java(major_version: u32, app_handle: &tauri::AppHandle) -> Result<ResolvedJava, String> {
+    if let Ok(java) = resolve_java(major_version).await {
+        return Ok(java);
+    }
+    println!("☕ Java {} is missing, installing it before launch", major_version);
+    super::commands::download_and_install_java_version(major_version, app_handle.clone()).await
+        .map_err(|e| format!("Java {} is required but could not be installed: {}", major_version, e))?;
+    resolve_java(major_version).await
+}
+
+/// A Java runtime found on this machine, described by running it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JavaRuntime {
+    pub path: String,
+    pub major_version: u32,
+    /// e.g. `17.0.9` or `1.8.0_381`
+    pub full_version: String,
+    /// `java.vendor`, e.g. `Eclipse Adoptium`
+    pub vendor: Option<String>,
+    /// `os.arch`, e.g. `amd64` or `aarch64`
+    pub arch: Option<String>,
+    pub is_bundled: bool,
+    pub source: JavaSource,
+}
+
+/// Every Java runtime that runs, the launcher's own first
+///
+/// Looks in the launcher's java directory, at the Java path from the settings and
+/// in the places `resolve_java` searches. A runtime reachable through several
+/// paths, like a PATH symlink into /usr/lib/jvm, is listed once.
+pub async fn discover_runtimes() -> Vec<JavaRuntime> {
+    let mut candidates: Vec<(String, JavaSource)> = Vec::new();
+    if let Ok(bundled) = list_runtimes().await {
+        candidates.extend(bundled.into_iter().filter_map(|runtime| runtime.path).map(|path| (path, JavaSource::Bundled)));
+    }
+    if let Ok(storage) = crate::storage::shared().await {
+        let custom = storage.read().await.get_settings().default_java_path.clone();
+        candidates.extend(custom.filter(|path| !path.trim().is_empty()).map(|path| (path, JavaSource::Custom)));
+    }
+    candidates.extend(system_java_candidates().into_iter().map(|path| (path.to_string_lossy().to_string(), JavaSource::System)));
+
+    let mut seen = std::collections::HashSet::new();
+    let mut runtimes = Vec::new();
+    for (path, source) in candidates {
+        let canonical = std::fs::canonicalize(&path).unwrap_or_else(|_| PathBuf::from(&path));
+        if !seen.insert(canonical) {
+            continue;
+        }
+        if let Some(runtime) = describe_java(&path, source) {
+            runtimes.push(runtime);
+        }
+    }
+    runtimes
+}
+
+/// Run the Java at `path` and read its version, vendor and architecture
+fn describe_java(path: &str, source: JavaSource) -> Option<JavaRuntime> {
+    let output = std::process::Command::new(path)
+        .args(["-XshowSettings:properties", "-version"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8_lossy(&output.stderr);
+    let (major_version, full_version) = parse_java_version(&output)?;
+    let properties = parse_java_properties(&output);
+    Some(JavaRuntime {
+        path: path.to_string(),
+        major_version,
+        full_version,
+        vendor: properties.get("java.vendor").cloned(),
+        arch: properties.get("os.arch").cloned(),
+        is_bundled: source == JavaSource::Bundled,
+        source,
+    })
+}
+
+/// `key = value` lines printed by `-XshowSettings:properties`
+///
+/// Values listed over several lines, like `java.library.path`, keep their first line.
+fn parse_java_properties(output: &str) -> std::collections::HashMap<String, String> {
+    output.lines()
+        .filter_map(|line| line.trim().split_once(" = "))
+        .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// A Java runtime in the launcher's own java directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledJavaRuntime {
+    pub major_version: u32,
+    pub directory: String,
+    /// Executable found in the directory, None when extraction never finished
+    pub path: Option<String>,
+    /// Full version reported by `java -version`, None when it doesn't run
+    pub version: Option<String>,
+    /// Whether the runtime runs and reports the major version of its directory
+    pub healthy: bool,
+    pub size_bytes: u64,
+}
+
+/// Directory the launcher installs Java `major_version` into
+pub fn runtime_dir(major_version: u32) -> PathBuf {
+    crate::storage::get_launcher_dir().join("java").join(format!("java{}", major_version))
+}
+
+/// The launcher's Java `major_version` when it runs, the reason it can't otherwise
+pub async fn check_runtime(major_version: u32) -> Result<String, String> {
+    let path = super::versions::get_java_for_version(major_version).await
+        .map_err(|_| "no Java executable in it".to_string())?;
+    match probe_java(&path).await {
+        Some((major, _)) if major == major_version => Ok(path),
+        Some((major, _)) => Err(format!("{} reports Java {}", path, major)),
+        None => Err(format!("{} failed to run", path)),
+    }
+}
+
+/// Runtimes in the launcher's java directory with their disk usage
+pub async fn list_runtimes() -> Result<Vec<BundledJavaRuntime>, String> {
+    let java_root = crate::storage::get_launcher_dir().join("java");
+    let mut entries = match tokio::fs::read_dir(&java_root).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", java_root.display(), e)),
+    };
+
+    let mut runtimes = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(major_version) = name.strip_prefix("java").and_then(|major| major.parse().ok()) else {
+            continue;
+        };
+        let directory = entry.path();
+        let path = super::versions::get_java_for_version(major_version).await.ok();
+        let probed = match &path {
+            Some(path) => probe_java(path).await,
+            None => None,
+        };
+        let size_root = directory.clone();
+        let size_bytes = tokio::task::spawn_blocking(move || directory_size(&size_root)).await.unwrap_or(0);
+        runtimes.push(BundledJavaRuntime {
+            major_version,
+            directory: directory.to_string_lossy().to_string(),
+            path,
+            healthy: probed.as_ref().is_some_and(|(major, _)| *major == major_version),
+            version: probed.map(|(_, version)| version),
+            size_bytes,
+        });
+    }
+    runtimes.sort_by_key(|runtime| runtime.major_version);
+    Ok(runtimes)
+}
+
+/// Delete the launcher's Java `major_version`, returning the bytes freed
+///
+/// Only the launcher's own directory is touched. A symlink in its place is
+/// unlinked without following it.
+pub async fn remove_runtime(major_version: u32) -> Result<u64, String> {
+    let directory = runtime_dir(major_version);
+    let metadata = match tokio::fs::symlink_metadata(&directory).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!("Java {} is not installed by the launcher", major_version));
+        }
+        Err(e) => return Err(format!("Failed to read {}: {}", directory.display(), e)),
+    };
+    if !metadata.is_dir() {
+        tokio::fs::remove_file(&directory).await
+            .map_err(|e| format!("Failed to remove {}: {}", directory.display(), e))?;
+        return Ok(0);
+    }
+
+    let size_root = directory.clone();
+    let size_bytes = tokio::task::spawn_blocking(move || directory_size(&size_root)).await.unwrap_or(0);
+    tokio::fs::remove_dir_all(&directory).await
+        .map_err(|e| format!("Failed to remove Java {} at {}: {}", major_version, directory.display(), e))?;
+    println!("🗑️  Removed Java {} ({} MB)", major_version, size_bytes / 1024 / 1024);
+    Ok(size_bytes)
+}
+
+/// Total size of the files under `path`, symlinks are not followed
+fn directory_size(path: &std::path::Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+async fn bundled_only() -> bool {
+    match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().java_bundled_only,
+        Err(_) => false,
+    }
+}
+
+/// Major and full version of the Java at `java_path`, None when it doesn't run
+async fn probe_java(java_path: &str) -> Option<(u32, String)> {
+    let output = validate_java_installation(java_path).await.ok()?;
+    parse_java_version(&output)
+}
+
+/// Parse the quoted version out of `java -version` output
+///
+/// Handles both the legacy `1.8.0_381` scheme and `17.0.9` or `21-ea`.
+fn parse_java_version(output: &str) -> Option<(u32, String)> {
+    let version = output.lines()
+        .find_map(|line| line.split_once("version \"").map(|(_, rest)| rest))?
+        .split('"')
+        .next()?
+        .to_string();
+
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let first: u32 = parts.next()?.parse().ok()?;
+    let major = if first == 1 { parts.next()?.parse().ok()? } else { first };
+
+    Some((major, version))
+}
+
+/// Java executables worth probing, from JAVA_HOME, PATH and the usual vendor folders
+fn system_java_candidates() -> Vec<PathBuf> {
+    let exe = if cfg!(target_os = "windows") { "java.exe" } else { "java" };
+    let mut candidates = Vec::new();
+
+    if let Some(java_home) = std::env::var_os("JAVA_HOME") {
+        candidates.push(PathBuf::from(java_home).join("bin").join(exe));
+    }
+    if let Some(path) = std::env::var_os("PATH") {
+        candidates.extend(std::env::split_paths(&path).map(|dir| dir.join(exe)));
+    }
+
+    let mut install_roots: Vec<(PathBuf, &[&str])> = Vec::new();
+    if cfg!(target_os = "windows") {
+        for program_files in ["ProgramFiles", "ProgramFiles(x86)"].iter().filter_map(std::env::var_os) {
+            let program_files = PathBuf::from(program_files);
+            for vendor in ["Java", "Eclipse Adoptium", "Microsoft", "Zulu", "BellSoft", "Amazon Corretto"] {
+                install_roots.push((program_files.join(vendor), &["bin"]));
+            }
+        }
+    } else if cfg!(target_os = "macos") {
+        install_roots.push((PathBuf::from("/Library/Java/JavaVirtualMachines"), &["Contents", "Home", "bin"]));
+    } else {
+        install_roots.push((PathBuf::from("/usr/lib/jvm"), &["bin"]));
+    }
+
+    for (root, bin) in install_roots {
+        let Ok(entries) = std::fs::read_dir(&root) else { continue };
+        for entry in entries.flatten() {
+            let mut java = entry.path();
+            java.extend(bin);
+            candidates.push(java.join(exe));
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    candidates.retain(|path| path.is_file() && seen.insert(path.clone()));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_legacy_and_modern_version_strings() {
+        let java8 = "java version \"1.8.0_381\"\nJava(TM) SE Runtime Environment (build 1.8.0_381-b09)\n";
+        assert_eq!(parse_java_version(java8), Some((8, "1.8.0_381".to_string())));
+
+        let java17 = "Picked up JAVA_TOOL_OPTIONS: -Xmx1g\nopenjdk version \"17.0.9\" 2023-10-17\nOpenJDK Runtime Environment Temurin-17.0.9+9 (build 17.0.9+9)\n";
+        assert_eq!(parse_java_version(java17), Some((17, "17.0.9".to_string())));
+
+        assert_eq!(parse_java_version("openjdk version \"21-ea\" 2023-09-19"), Some((21, "21-ea".to_string())));
+        assert_eq!(parse_java_version("Error: could not find libjava.so"), None);
+    }
+
+    #[test]
+    fn reads_vendor_and_arch_from_properties() {
+        let temurin = "Property settings:\n    file.encoding = UTF-8\n    java.library.path = /usr/java/packages/lib\n        /usr/lib64\n        /lib64\n    java.vendor = Eclipse Adoptium\n    java.version = 17.0.9\n    os.arch = amd64\n\nopenjdk version \"17.0.9\" 2023-10-17\nOpenJDK Runtime Environment Temurin-17.0.9+9 (build 17.0.9+9)\n";
+        let properties = parse_java_properties(temurin);
+        assert_eq!(properties["java.vendor"], "Eclipse Adoptium");
+        assert_eq!(properties["os.arch"], "amd64");
+        assert_eq!(properties["java.library.path"], "/usr/java/packages/lib");
+        assert_eq!(parse_java_version(temurin), Some((17, "17.0.9".to_string())));
+
+        let oracle8 = "Property settings:\r\n    java.vendor = Oracle Corporation\r\n    os.arch = x86\r\n\r\njava version \"1.8.0_381\"\r\n";
+        let properties = parse_java_properties(oracle8);
+        assert_eq!(properties["java.vendor"], "Oracle Corporation");
+        assert_eq!(properties["os.arch"], "x86");
+        assert_eq!(parse_java_version(oracle8), Some((8, "1.8.0_381".to_string())));
+    }
+
+    #[test]
+    fn sums_file_sizes_under_a_runtime() {
+        let dir = std::env::temp_dir().join(format!("chai-java-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("jdk-21").join("bin")).unwrap();
+        std::fs::write(dir.join("jdk-21").join("bin").join("java"), vec![0u8; 300]).unwrap();
+        std::fs::write(dir.join("release"), vec![0u8; 40]).unwrap();
+        assert_eq!(directory_size(&dir), 340);
+        assert_eq!(directory_size(&dir.join("missing")), 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,307 @@
+//! JVM arguments passed to the game
+//!
+//! A preset supplies garbage collector flags sized to the instance's memory. The
+//! launcher settings hold the default preset and instances can override it. The
+//! user's own JVM arguments come after the preset's and win wherever both set the
+//! same option, so a custom `-Xmx` replaces the one derived from the memory setting.
+
+use serde::{Deserialize, Serialize};
+
+/// Memory above which Aikar's flags switch to their large heap values, in MB
+const AIKAR_LARGE_HEAP_MB: u32 = 12 * 1024;
+
+/// First Java release with a production ready ZGC
+const ZGC_MIN_JAVA: u32 = 15;
+
+/// Flags that pick a garbage collector, only one may be enabled
+const GC_SELECTORS: &[&str] = &[
+    "UseG1GC",
+    "UseZGC",
+    "UseShenandoahGC",
+    "UseParallelGC",
+    "UseSerialGC",
+    "UseConcMarkSweepGC",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JvmArgsPreset {
+    /// Only the heap size, the JVM picks everything else
+    #[default]
+    Default,
+    /// Aikar's G1 flags, tuned for short pauses
+    Aikar,
+    /// The G1 flags the vanilla launcher shipped with
+    G1gcBalanced,
+    /// ZGC with a preallocated heap, for large modpacks on Java 15+
+    ZgcLargeHeap,
+}
+
+/// A preset with the flags it expands to, for picking one in the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct JvmPresetInfo {
+    pub id: JvmArgsPreset,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub args: Vec<String>,
+}
+
+impl JvmArgsPreset {
+    pub const ALL: [JvmArgsPreset; 4] = [
+        JvmArgsPreset::Default,
+        JvmArgsPreset::Aikar,
+        JvmArgsPreset::G1gcBalanced,
+        JvmArgsPreset::ZgcLargeHeap,
+    ];
+
+    pub fn info(self, memory_mb: u32) -> JvmPresetInfo {
+        let (name, description) = match self {
+            JvmArgsPreset::Default => ("Default", "Only sets the heap size"),
+            JvmArgsPreset::Aikar => ("Aikar's flags", "G1 tuned for short pauses, the usual choice for modded play"),
+            JvmArgsPreset::G1gcBalanced => ("G1 balanced", "The G1 flags of the vanilla launcher"),
+            JvmArgsPreset::ZgcLargeHeap => ("ZGC large heap", "Near pause-free collection for 8 GB and more, needs Java 15+"),
+        };
+        JvmPresetInfo { id: self, name, description, args: self.args(memory_mb) }
+    }
+
+    /// The preset's flags for a heap of `memory_mb`, `-Xmx` included
+    pub fn args(self, memory_mb: u32) -> Vec<String> {
+        let mut args = vec![format!("-Xmx{}M", memory_mb)];
+        let flags: Vec<String> = match self {
+            JvmArgsPreset::Default => Vec::new(),
+            JvmArgsPreset::Aikar => {
+                // Aikar raises the young generation and region size for large heaps
+                let (new_size, max_new_size, region, reserve, occupancy) = if memory_mb > AIKAR_LARGE_HEAP_MB {
+                    (40, 50, "16M", 15, 20)
+                } else {
+                    (30, 40, "8M", 20, 15)
+                };
+                vec![
+                    format!("-Xms{}M", memory_mb),
+                    "-XX:+UseG1GC".to_string(),
+                    "-XX:+ParallelRefProcEnabled".to_string(),
+                    "-XX:MaxGCPauseMillis=200".to_string(),
+                    "-XX:+UnlockExperimentalVMOptions".to_string(),
+                    "-XX:+DisableExplicitGC".to_string(),
+                    "-XX:+AlwaysPreTouch".to_string(),
+                    format!("-XX:G1NewSizePercent={}", new_size),
+                    format!("-XX:G1MaxNewSizePercent={}", max_new_size),
+                    format!("-XX:G1HeapRegionSize={}", region),
+                    format!("-XX:G1ReservePercent={}", reserve),
+                    "-XX:G1HeapWastePercent=5".to_string(),
+                    "-XX:G1MixedGCCountTarget=4".to_string(),
+                    format!("-XX:InitiatingHeapOccupancyPercent={}", occupancy),
+                    "-XX:G1MixedGCLiveThresholdPercent=90".to_string(),
+                    "-XX:G1RSetUpdatingPauseTimePercent=5".to_string(),
+                    "-XX:SurvivorRatio=32".to_string(),
+                    "-XX:+PerfDisableSharedMem".to_string(),
+                    "-XX:MaxTenuringThreshold=1".to_string(),
+                    "-Dusing.aikars.flags=https://mcflags.emc.gs".to_string(),
+                    "-Daikars.new.flags=true".to_string(),
+                ]
+            }
+            JvmArgsPreset::G1gcBalanced => vec![
+                "-XX:+UnlockExperimentalVMOptions".to_string(),
+                "-XX:+UseG1GC".to_string(),
+                "-XX:G1NewSizePercent=20".to_string(),
+                "-XX:G1ReservePercent=20".to_string(),
+                "-XX:MaxGCPauseMillis=50".to_string(),
+                format!("-XX:G1HeapRegionSize={}", if memory_mb >= 8 * 1024 { "32M" } else { "16M" }),
+            ],
+            JvmArgsPreset::ZgcLargeHeap => vec![
+                format!("-Xms{}M", memory_mb),
+                "-XX:+UseZGC".to_string(),
+                "-XX:+AlwaysPreTouch".to_string(),
+                "-XX:+DisableExplicitGC".to_string(),
+                "-XX:+PerfDisableSharedMem".to_string(),
+            ],
+        };
+        args.extend(flags);
+        args
+    }
+
+    /// This preset, or G1 balanced when it needs a newer Java than `java_major`
+    fn for_java(self, java_major: u32) -> Self {
+        match self {
+            JvmArgsPreset::ZgcLargeHeap if java_major < ZGC_MIN_JAVA => {
+                println!("⚠️  ZGC needs Java {}+, using the G1 balanced preset on Java {}", ZGC_MIN_JAVA, java_major);
+                JvmArgsPreset::G1gcBalanced
+            }
+            preset => preset,
+        }
+    }
+}
+
+/// The preset an instance launches with, its own or the one from the saved settings
+pub async fn preset(instance_preset: Option<JvmArgsPreset>) -> JvmArgsPreset {
+    if let Some(preset) = instance_preset {
+        return preset;
+    }
+    match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().jvm_args_preset,
+        Err(_) => JvmArgsPreset::default(),
+    }
+}
+
+/// JVM arguments for a launch, the preset's followed by the user's
+///
+/// A preset flag the user also sets is dropped, and so is the preset's collector
+/// when the user picks another one. Of user flags given twice the last is kept.
+pub fn launch_args(preset: JvmArgsPreset, memory_mb: u32, java_major: u32, user_args: &[String]) -> Vec<String> {
+    let user_args = last_of_each(user_args);
+    let mut user_keys: Vec<String> = user_args.iter().filter_map(|arg| option_key(arg)).collect();
+    // The preset's initial heap follows the memory setting and may exceed a smaller custom -Xmx
+    if user_keys.iter().any(|key| key == "-Xmx") {
+        user_keys.push("-Xms".to_string());
+    }
+    let user_picks_gc = user_args.iter().any(|arg| selected_gc(arg).is_some());
+
+    let mut args: Vec<String> = preset.for_java(java_major).args(memory_mb).into_iter()
+        .filter(|arg| option_key(arg).is_none_or(|key| !user_keys.contains(&key)))
+        .filter(|arg| !(user_picks_gc && selected_gc(arg).is_some()))
+        .collect();
+    args.extend(user_args);
+    args
+}
+
+/// The maximum heap the last `-Xmx` in `args` sets, in MB
+///
+/// Sizes take the JVM's `k`, `m` and `g` suffixes in either case, a bare number is bytes.
+pub fn max_heap_mb(args: &[String]) -> Option<u64> {
+    let size = args.iter().rev().find_map(|arg| arg.strip_prefix("-Xmx"))?;
+    let (digits, unit) = match size.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&size[..i], c.to_ascii_lowercase()),
+        _ => (size, 'b'),
+    };
+    let value = digits.parse::<u64>().ok()?;
+    match unit {
+        'b' => Some(value / (1024 * 1024)),
+        'k' => Some(value / 1024),
+        'm' => Some(value),
+        'g' => Some(value * 1024),
+        _ => None,
+    }
+}
+
+/// What a JVM option sets, so two values of it can be told apart
+///
+/// `-Xmx4G` and `-Xmx8G` share `-Xmx`, `-XX:+Foo`, `-XX:-Foo` and `-XX:Foo=1`
+/// share `-XX:Foo`, and `-Dname=value` is keyed by the property name. Other
+/// arguments have no key and are never treated as duplicates.
+fn option_key(arg: &str) -> Option<String> {
+    if let Some(size) = ["-Xmx", "-Xms", "-Xss", "-Xmn"].iter().find(|size| arg.starts_with(**size)) {
+        return Some(size.to_string());
+    }
+    if let Some(option) = arg.strip_prefix("-XX:") {
+        let name = option.strip_prefix(['+', '-']).unwrap_or(option);
+        return Some(format!("-XX:{}", name.split('=').next().unwrap_or(name)));
+    }
+    let property = arg.strip_prefix("-D")?;
+    Some(format!("-D{}", property.split('=').next().unwrap_or(property)))
+}
+
+/// The collector an argument enables, if it enables one
+fn selected_gc(arg: &str) -> Option<&'static str> {
+    let name = arg.strip_prefix("-XX:+")?;
+    GC_SELECTORS.iter().copied().find(|gc| *gc == name)
+}
+
+/// `args` with only the last value of every keyed option, in their original order
+fn last_of_each(args: &[String]) -> Vec<String> {
+    args.iter().enumerate()
+        .filter(|(i, arg)| {
+            let Some(key) = option_key(arg) else {
+                return true;
+            };
+            !args[i + 1..].iter().any(|later| option_key(later).as_ref() == Some(&key))
+        })
+        .map(|(_, arg)| arg.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn count(args: &[String], prefix: &str) -> usize {
+        args.iter().filter(|arg| arg.starts_with(prefix)).count()
+    }
+
+    #[test]
+    fn user_heap_size_replaces_the_presets() {
+        for preset in JvmArgsPreset::ALL {
+            let launch = launch_args(preset, 4096, 21, &args(&["-Xmx6G", "-Dfoo=bar"]));
+            assert_eq!(count(&launch, "-Xmx"), 1, "{:?}: {:?}", preset, launch);
+            assert!(launch.contains(&"-Xmx6G".to_string()));
+            assert_eq!(launch.last().map(String::as_str), Some("-Dfoo=bar"));
+        }
+
+        // A heap size given twice keeps the last one
+        let launch = launch_args(JvmArgsPreset::Aikar, 4096, 21, &args(&["-Xmx2G", "-Xms1G", "-Xmx3G"]));
+        assert_eq!(count(&launch, "-Xmx"), 1);
+        assert_eq!(count(&launch, "-Xms"), 1);
+        assert!(launch.contains(&"-Xmx3G".to_string()) && launch.contains(&"-Xms1G".to_string()));
+
+        // The preset's -Xms would be larger than a smaller custom -Xmx
+        let launch = launch_args(JvmArgsPreset::ZgcLargeHeap, 8192, 21, &args(&["-Xmx2G"]));
+        assert_eq!(count(&launch, "-Xms"), 0, "{:?}", launch);
+    }
+
+    #[test]
+    fn presets_follow_the_configured_memory() {
+        assert_eq!(JvmArgsPreset::Default.args(3072), args(&["-Xmx3072M"]));
+
+        let aikar = JvmArgsPreset::Aikar.args(8192);
+        assert!(aikar.contains(&"-Xms8192M".to_string()));
+        assert!(aikar.contains(&"-XX:G1HeapRegionSize=8M".to_string()));
+        let aikar = JvmArgsPreset::Aikar.args(16384);
+        assert!(aikar.contains(&"-Xmx16384M".to_string()));
+        assert!(aikar.contains(&"-XX:G1NewSizePercent=40".to_string()));
+        assert!(aikar.contains(&"-XX:G1HeapRegionSize=16M".to_string()));
+
+        assert!(JvmArgsPreset::G1gcBalanced.args(4096).contains(&"-XX:G1HeapRegionSize=16M".to_string()));
+        assert!(JvmArgsPreset::G1gcBalanced.args(8192).contains(&"-XX:G1HeapRegionSize=32M".to_string()));
+        assert!(JvmArgsPreset::ZgcLargeHeap.args(12288).starts_with(&args(&["-Xmx12288M", "-Xms12288M"])));
+    }
+
+    #[test]
+    fn user_flags_override_preset_flags() {
+        let launch = launch_args(JvmArgsPreset::Aikar, 4096, 17, &args(&["-XX:MaxGCPauseMillis=100", "-XX:-AlwaysPreTouch"]));
+        assert_eq!(count(&launch, "-XX:MaxGCPauseMillis"), 1);
+        assert!(launch.contains(&"-XX:MaxGCPauseMillis=100".to_string()));
+        assert!(!launch.contains(&"-XX:+AlwaysPreTouch".to_string()));
+
+        // Picking another collector drops the preset's, the JVM refuses two
+        let launch = launch_args(JvmArgsPreset::Aikar, 4096, 21, &args(&["-XX:+UseShenandoahGC"]));
+        assert!(!launch.contains(&"-XX:+UseG1GC".to_string()));
+        assert_eq!(launch.iter().filter(|arg| selected_gc(arg).is_some()).count(), 1);
+    }
+
+    #[test]
+    fn reads_the_effective_max_heap() {
+        assert_eq!(max_heap_mb(&args(&["-Xmx4096M"])), Some(4096));
+        assert_eq!(max_heap_mb(&args(&["-Xmx2g", "-Xms1G", "-Xmx6G"])), Some(6144));
+        assert_eq!(max_heap_mb(&args(&["-Xmx1048576k"])), Some(1024));
+        assert_eq!(max_heap_mb(&args(&["-Xmx2147483648"])), Some(2048));
+        assert_eq!(max_heap_mb(&args(&["-Xmxlots", "-Dfoo=bar"])), None);
+        assert_eq!(max_heap_mb(&[]), None);
+    }
+
+    #[test]
+    fn zgc_falls_back_on_old_java() {
+        assert!(launch_args(JvmArgsPreset::ZgcLargeHeap, 8192, 21, &[]).contains(&"-XX:+UseZGC".to_string()));
+        let java8 = launch_args(JvmArgsPreset::ZgcLargeHeap, 8192, 8, &[]);
+        assert!(!java8.contains(&"-XX:+UseZGC".to_string()));
+        assert!(java8.contains(&"-XX:+UseG1GC".to_string()));
+    }
+
+    #[test]
+    fn preset_ids_match_the_frontend() {
+        let ids: Vec<String> = JvmArgsPreset::ALL.iter().map(|preset| serde_json::to_string(preset).unwrap()).collect();
+        assert_eq!(ids, ["\"default\"", "\"aikar\"", "\"g1gc_balanced\"", "\"zgc_large_heap\""]);
+    }
+}
@@ -0,0 +1,146 @@
+//! LAN world and port helpers
+//!
+//! Used by the UI to check whether a port can be reached and to show a
+//! copy-paste address for worlds opened to LAN.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Log lines printed when a world is opened to LAN
+const LAN_LOG_MARKERS: &[&str] = &["Local game hosted on port", "Started serving on"];
+
+/// Result of a local port check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortCheckResult {
+    pub port: u16,
+    /// A listener could be bound on all interfaces
+    pub bindable: bool,
+    /// Something accepted a loopback connection, if checked
+    pub listening: Option<bool>,
+    pub hint: Option<String>,
+}
+
+/// Address information for a world opened to LAN
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanWorldInfo {
+    pub port: Option<u16>,
+    pub lan_ips: Vec<String>,
+    pub addresses: Vec<String>,
+}
+
+/// Check whether a port can be bound locally and optionally whether something answers on loopback
+pub fn check_port(port: u16, check_connect: bool) -> PortCheckResult {
+    let bind_error = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).err();
+    let bindable = bind_error.is_none();
+
+    let listening = check_connect.then(|| {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok()
+    });
+
+    let hint = bind_error.map(|e| match (e.kind(), listening) {
+        (_, Some(true)) => format!(
+            "Port {} is already in use by a running server or game. If friends still can't connect, allow Java (javaw.exe) through your firewall for private networks.",
+            port
+        ),
+        (std::io::ErrorKind::PermissionDenied, _) => format!(
+            "Permission denied binding port {}. Use a port above 1024 or check your firewall and security software.",
+            port
+        ),
+        (std::io::ErrorKind::AddrInUse, _) => format!(
+            "Port {} is in use by another application. Pick a different port or close the application using it.",
+            port
+        ),
+        _ => format!(
+            "Could not bind port {} ({}). Check that your firewall allows Java to accept incoming connections.",
+            port, e
+        ),
+    });
+
+    PortCheckResult {
+        port,
+        bindable,
+        listening,
+        hint,
+    }
+}
+
+/// Find the most recent LAN port the running game announced in its latest.log
+///
+/// `session_start` is when that game was launched, None when none is running. The game
+/// starts a fresh latest.log, so one last written before the launch is left from an earlier run.
+pub async fn find_lan_port(game_dir: &Path, session_start: Option<SystemTime>) -> Result<Option<u16>, String> {
+    let Some(session_start) = session_start else {
+        return Ok(None);
+    };
+    let log_path = game_dir.join("logs").join("latest.log");
+    let modified = match tokio::fs::metadata(&log_path).await.and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read latest.log: {}", e)),
+    };
+    if modified < session_start {
+        return Ok(None);
+    }
+
+    let content = tokio::fs::read(&log_path).await
+        .map_err(|e| format!("Failed to read latest.log: {}", e))?;
+    let content = String::from_utf8_lossy(&content);
+
+    Ok(content.lines().rev().find_map(parse_lan_port))
+}
+
+fn parse_lan_port(line: &str) -> Option<u16> {
+    LAN_LOG_MARKERS.iter().find_map(|marker| {
+        let (_, rest) = line.split_once(marker)?;
+        let digits: String = rest.chars()
+            .skip_while(|c| !c.is_ascii_digit())
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    })
+}
+
+/// Get this machine's LAN addresses
+///
+/// Connecting a UDP socket sends no packets but makes the OS pick the outgoing interface.
+pub fn get_lan_ips() -> Vec<IpAddr> {
+    let probes: [(SocketAddr, SocketAddr); 2] = [
+        ((Ipv4Addr::UNSPECIFIED, 0).into(), (Ipv4Addr::new(8, 8, 8, 8), 53).into()),
+        ((Ipv6Addr::UNSPECIFIED, 0).into(), (Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888), 53).into()),
+    ];
+
+    probes.iter()
+        .filter_map(|(bind, target)| {
+            let socket = UdpSocket::bind(bind).ok()?;
+            socket.connect(target).ok()?;
+            let ip = socket.local_addr().ok()?.ip();
+            (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn only_the_running_game_reports_a_lan_port() {
+        let game_dir = std::env::temp_dir().join(format!("chai-lan-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(game_dir.join("logs")).unwrap();
+        std::fs::write(
+            game_dir.join("logs").join("latest.log"),
+            "[12:00:01] [Server thread/INFO]: Started serving on 41234\n",
+        ).unwrap();
+        let now = SystemTime::now();
+
+        assert_eq!(find_lan_port(&game_dir, None).await.unwrap(), None);
+        assert_eq!(find_lan_port(&game_dir, Some(now + Duration::from_secs(60))).await.unwrap(), None);
+        assert_eq!(find_lan_port(&game_dir, Some(now - Duration::from_secs(60))).await.unwrap(), Some(41234));
+        std::fs::remove_dir_all(game_dir).unwrap();
+    }
+}
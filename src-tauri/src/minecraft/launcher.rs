@@ -4,6 +4,65 @@
 //! while maintaining ChaiLauncher's Java management and API compatibility.
 
 use super::{MinecraftInstance, AuthInfo, LaunchResult, MCVMCore, versions};
+use super::profile::LaunchProfile;
+
+/// Game arguments that users may not override, as they carry authentication
+const DENIED_GAME_ARGS: &[&str] = &["--accessToken", "--uuid", "--username"];
+
+/// Split custom game arguments into those allowed at launch and those rejected by the deny-list
+///
+/// A denied flag also takes its value with it, whether passed as `--flag value` or `--flag=value`.
+pub fn filter_game_args(args: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut allowed = Vec::new();
+    let mut rejected = Vec::new();
+    let mut iter = args.iter().peekable();
+
+    while let Some(arg) = iter.next() {
+        let flag = arg.split('=').next().unwrap_or(arg);
+        if DENIED_GAME_ARGS.iter().any(|denied| denied.eq_ignore_ascii_case(flag)) {
+            rejected.push(arg.clone());
+            if !arg.contains('=') {
+                if let Some(value) = iter.next_if(|next| !next.starts_with("--")) {
+                    rejected.push(value.clone());
+                }
+            }
+        } else {
+            allowed.push(arg.clone());
+        }
+    }
+
+    (allowed, rejected)
+}
+
+/// Game arguments the launcher adds after the ones MCVM generates
+pub struct AddedGameArgs {
+    pub window: Vec<String>,
+    /// `--tweakClass` arguments legacy Forge needs to reach LaunchWrapper
+    pub tweak: Vec<String>,
+    pub custom: Vec<String>,
+    /// Custom arguments dropped by the deny-list
+    pub rejected: Vec<String>,
+}
+
+impl AddedGameArgs {
+    /// Arguments added for `instance`, leaving out tweak classes `generated` already passes
+    pub async fn resolve(instance: &MinecraftInstance, profile: Option<&LaunchProfile>, generated: &[String]) -> Self {
+        let (custom, rejected) = filter_game_args(instance.game_args.as_deref().unwrap_or_default());
+        let window = super::window::options(instance.window_width, instance.window_height, instance.fullscreen).await;
+        let window = super::window::launch_args(&instance.game_dir, &instance.version, &window, &custom).await;
+        // Legacy Forge only loads when its tweak class reaches LaunchWrapper
+        let tweak = match profile.filter(|profile| profile.uses_launchwrapper()) {
+            Some(profile) => profile.tweak_args(&[generated, &custom].concat()),
+            None => Vec::new(),
+        };
+        Self { window, tweak, custom, rejected }
+    }
+
+    /// All added arguments in launch order
+    pub fn into_args(self) -> Vec<String> {
+        [self.window, self.tweak, self.custom].concat()
+    }
+}
 
 /// Launch a Minecraft instance using MCVM integration
 pub async fn launch_instance(
@@ -17,13 +76,18 @@ pub async fn launch_instance(
     super::instances::Instance::validate(instance).await?;
 
     // Get required Java version and ensure it's installed
-    let java_version = versions::get_required_java_version(&instance.version);
-    let java_path = versions::get_java_for_version(java_version).await?;
+    let java_version = versions::required_java_for_instance(&instance.id, &instance.game_dir, &instance.version).await;
+    let java = super::java::resolve_java(java_version).await?;
+    let java_path = java.path;
 
-    println!("☕ Using Java {}: {}", java_version, java_path);
+    println!("☕ Using {:?} Java {} ({}): {}", java.source, java_version, java.version, java_path);
 
     // Launch using MCVM - no fallback since it doesn't work
-    let result = try_mcvm_launch(instance, &auth, memory, &java_path).await?;
+    let preset = super::jvm::preset(instance.jvm_args_preset).await;
+    let jvm_args = super::jvm::launch_args(preset, memory, java_version, instance.jvm_args.as_deref().unwrap_or_default());
+    println!("🧠 JVM arguments ({:?} preset): {}", preset, jvm_args.join(" "));
+
+    let result = try_mcvm_launch(instance, &auth, memory, &java_path, jvm_args).await?;
     println!("✅ Minecraft launched successfully with MCVM (PID: {})", result.process_id);
     Ok(result)
 }
@@ -34,16 +98,39 @@ async fn try_mcvm_launch(
     auth: &AuthInfo,
     memory: u32,
     java_path: &str,
+    jvm_args: Vec<String>,
 ) -> Result<LaunchResult, String> {
     // Create MCVM instance
-    let mcvm_instance = MCVMCore::create_launch_instance(
+    let mut mcvm_instance = MCVMCore::create_launch_instance(
         &instance.id,
         &instance.version,
         instance.game_dir.clone(),
     ).await?;
+
+    // Custom game arguments are appended after the generated ones
+    let profile = super::profile::load(&instance.game_dir, &instance.version).await;
+    let added = AddedGameArgs::resolve(instance, profile.as_ref(), &[]).await;
+    if !added.rejected.is_empty() {
+        println!("⚠️  Ignoring protected game arguments: {}", added.rejected.join(" "));
+    }
+    if !added.window.is_empty() {
+        println!("🖥️  Window arguments: {}", added.window.join(" "));
+    }
+    if let Some(profile) = profile.as_ref().filter(|_| !added.tweak.is_empty()) {
+        println!("🧵 {} starts through LaunchWrapper, tweak arguments: {}", profile.id, added.tweak.join(" "));
+    }
+    mcvm_instance.config.launch.game_args = added.into_args();
+    mcvm_instance.config.launch.jvm_args = jvm_args;
+
+    if let Some(env_vars) = &instance.env_vars {
+        println!("🌿 Setting {} environment variable(s){}", env_vars.len(),
+            if instance.clear_inherited_env { ", without the launcher's environment" } else { "" });
+        mcvm_instance.config.launch.env = env_vars.clone();
+    }
+    mcvm_instance.clear_inherited_env = instance.clear_inherited_env;
     
     // Launch with MCVM using the proper API
-    let _handle = MCVMCore::launch_instance_with_mcvm(
+    let handle = MCVMCore::launch_instance_with_mcvm(
         mcvm_instance,
         java_path.to_string(),
         memory,
@@ -55,17 +142,18 @@ async fn try_mcvm_launch(
     ).await?;
 
     println!("✅ Launched with MCVM, handle created successfully");
-    
-    // Extract process ID from MCVM handle by getting the internal process
-    // We need to consume the handle to get the process, so we'll get a placeholder PID
-    let process_id = 1; // MCVM manages the process internally
-    
+
+    // The caller tracks the process as a play session, which also cleans up logs on exit
+    let process = handle.get_process();
+    let process_id = process.id();
+
     println!("✓ Minecraft launched successfully with PID: {}", process_id);
-    
+
     Ok(LaunchResult {
         process_id,
         success: true,
         error: None,
+        process: Some(process),
     })
 }
 
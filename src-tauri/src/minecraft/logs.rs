@@ -0,0 +1,267 @@
+//! Instance log cleanup
+//!
+//! Removes old rotated logs and crash reports from an instance according to the
+//! launcher-wide log cleanup policy or the instance's own override.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use crate::storage::LogCleanupPolicy;
+
+const LOGS_DIR: &str = "logs";
+const CRASH_REPORTS_DIR: &str = "crash-reports";
+const LATEST_LOG: &str = "latest.log";
+
+/// Summary of a log cleanup run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogCleanupResult {
+    pub files_removed: u32,
+    pub bytes_removed: u64,
+}
+
+struct LogFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// Resolve the policy for an instance and clean its logs
+pub async fn clean_instance_logs(instance_id: &str) -> Result<LogCleanupResult, String> {
+    let storage = crate::storage::shared().await
+        .map_err(|e| format!("Failed to initialize storage: {}", e))?;
+
+    let (game_dir, policy) = {
+        let storage = storage.read().await;
+        let instance = storage.get_instance(instance_id)
+            .ok_or_else(|| format!("Instance not found: {}", instance_id))?;
+        let policy = instance.log_cleanup.clone()
+            .unwrap_or_else(|| storage.get_settings().log_cleanup.clone());
+        (instance.game_dir.clone(), policy)
+    };
+
+    clean_logs(&game_dir, &policy).await
+}
+
+/// Delete rotated logs and crash reports in `game_dir` that exceed the policy
+///
+/// latest.log and the most recent crash report are always kept, and nothing
+/// outside logs/ and crash-reports/ is touched.
+pub async fn clean_logs(game_dir: &Path, policy: &LogCleanupPolicy) -> Result<LogCleanupResult, String> {
+    let mut kept_size = 0u64;
+    let mut candidates = Vec::new();
+
+    for file in list_files(&game_dir.join(LOGS_DIR)).await? {
+        if file.path.file_name().is_some_and(|name| name == LATEST_LOG) {
+            kept_size += file.size;
+        } else {
+            candidates.push(file);
+        }
+    }
+
+    let mut crash_reports = list_files(&game_dir.join(CRASH_REPORTS_DIR)).await?;
+    crash_reports.sort_by_key(|file| file.modified);
+    if let Some(newest) = crash_reports.pop() {
+        kept_size += newest.size;
+    }
+    candidates.extend(crash_reports);
+
+    // Oldest first so size limits remove the oldest files
+    candidates.sort_by_key(|file| file.modified);
+
+    let max_age = policy.max_age_days
+        .map(|days| Duration::from_secs(days as u64 * 24 * 60 * 60));
+    let max_size = policy.max_total_size_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    let now = SystemTime::now();
+
+    let mut total_size = kept_size + candidates.iter().map(|file| file.size).sum::<u64>();
+    let mut result = LogCleanupResult::default();
+
+    for file in candidates {
+        let too_old = max_age.is_some_and(|max_age| {
+            now.duration_since(file.modified).unwrap_or_default() > max_age
+        });
+        let too_large = max_size.is_some_and(|max_size| total_size > max_size);
+
+        if !too_old && !too_large {
+            continue;
+        }
+
+        match tokio::fs::remove_file(&file.path).await {
+            Ok(()) => {
+                total_size -= file.size;
+                result.files_removed += 1;
+                result.bytes_removed += file.size;
+            }
+            Err(e) => {
+                println!("⚠️  Failed to remove {}: {}", file.path.display(), e);
+            }
+        }
+    }
+
+    if result.files_removed > 0 {
+        println!("🧹 Removed {} log files ({} bytes) from {}",
+            result.files_removed, result.bytes_removed, game_dir.display());
+    }
+
+    Ok(result)
+}
+
+/// Newest crash report Minecraft wrote in `game_dir` at or after `since`
+pub async fn newest_crash_report_since(game_dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    newest_since(list_files(&game_dir.join(CRASH_REPORTS_DIR)).await.ok()?, since)
+}
+
+/// Newest `hs_err_pid*.log` the JVM wrote in `game_dir` at or after `since`
+pub async fn newest_jvm_crash_log_since(game_dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    let logs = list_files(game_dir).await.ok()?.into_iter()
+        .filter(|file| file.path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.starts_with("hs_err_pid") && name.ends_with(".log")
+        }))
+        .collect();
+    newest_since(logs, since)
+}
+
+fn newest_since(files: Vec<LogFile>, since: SystemTime) -> Option<PathBuf> {
+    files.into_iter()
+        .filter(|file| file.modified >= since)
+        .max_by_key(|file| file.modified)
+        .map(|file| file.path)
+}
+
+/// List regular files directly inside `dir`, ignoring symlinks and subdirectories
+async fn list_files(dir: &Path) -> Result<Vec<LogFile>, String> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    let mut entries = tokio::fs::read_dir(dir).await
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    while let Some(entry) = entries.next_entry().await
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))? {
+        let metadata = match tokio::fs::symlink_metadata(entry.path()).await {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        files.push(LogFile {
+            path: entry.path(),
+            size: metadata.len(),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filetime::FileTime;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn temp_game_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-logs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join(LOGS_DIR)).unwrap();
+        std::fs::create_dir_all(dir.join(CRASH_REPORTS_DIR)).unwrap();
+        dir
+    }
+
+    /// Write `size` bytes to `path` and backdate it by `days_old`
+    fn write_file(path: &Path, size: usize, days_old: u64) {
+        std::fs::write(path, vec![b'x'; size]).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(days_old * DAY);
+        filetime::set_file_mtime(path, FileTime::from_system_time(modified)).unwrap();
+    }
+
+    fn policy(max_total_size_mb: Option<u64>, max_age_days: Option<u32>) -> LogCleanupPolicy {
+        LogCleanupPolicy { max_total_size_mb, max_age_days }
+    }
+
+    #[tokio::test]
+    async fn removes_old_logs_but_keeps_latest_and_newest_crash_report() {
+        let dir = temp_game_dir();
+        let logs = dir.join(LOGS_DIR);
+        let crashes = dir.join(CRASH_REPORTS_DIR);
+
+        write_file(&logs.join(LATEST_LOG), 10, 30);
+        write_file(&logs.join("2024-01-01-1.log.gz"), 100, 30);
+        write_file(&logs.join("2024-05-01-1.log.gz"), 200, 1);
+        write_file(&crashes.join("crash-1.txt"), 300, 40);
+        write_file(&crashes.join("crash-2.txt"), 400, 35);
+        write_file(&crashes.join("crash-3.txt"), 500, 20);
+
+        std::fs::create_dir_all(logs.join("archive")).unwrap();
+        write_file(&logs.join("archive").join("old.log.gz"), 100, 60);
+        write_file(&dir.join("options.txt"), 100, 60);
+        #[cfg(unix)]
+        {
+            write_file(&dir.join("outside.log"), 100, 60);
+            std::os::unix::fs::symlink(dir.join("outside.log"), logs.join("linked.log")).unwrap();
+        }
+
+        let result = clean_logs(&dir, &policy(None, Some(7))).await.unwrap();
+
+        assert_eq!(result.files_removed, 3);
+        assert_eq!(result.bytes_removed, 100 + 300 + 400);
+        assert!(!logs.join("2024-01-01-1.log.gz").exists());
+        assert!(!crashes.join("crash-1.txt").exists());
+        assert!(!crashes.join("crash-2.txt").exists());
+
+        assert!(logs.join(LATEST_LOG).exists());
+        assert!(logs.join("2024-05-01-1.log.gz").exists());
+        assert!(crashes.join("crash-3.txt").exists());
+        assert!(logs.join("archive").join("old.log.gz").exists());
+        assert!(dir.join("options.txt").exists());
+        #[cfg(unix)]
+        {
+            assert!(logs.join("linked.log").symlink_metadata().is_ok());
+            assert!(dir.join("outside.log").exists());
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn size_limit_removes_oldest_files_first() {
+        let dir = temp_game_dir();
+        let logs = dir.join(LOGS_DIR);
+        const KIB: usize = 1024;
+
+        write_file(&logs.join(LATEST_LOG), 512 * KIB, 0);
+        write_file(&logs.join("oldest.log.gz"), 400 * KIB, 3);
+        write_file(&logs.join("older.log.gz"), 400 * KIB, 2);
+        write_file(&logs.join("newer.log.gz"), 100 * KIB, 1);
+
+        let result = clean_logs(&dir, &policy(Some(1), None)).await.unwrap();
+
+        assert_eq!(result.files_removed, 1);
+        assert_eq!(result.bytes_removed, 400 * KIB as u64);
+        assert!(!logs.join("oldest.log.gz").exists());
+        assert!(logs.join("older.log.gz").exists());
+        assert!(logs.join("newer.log.gz").exists());
+        assert!(logs.join(LATEST_LOG).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn huge_size_limit_does_not_overflow() {
+        let dir = temp_game_dir();
+        write_file(&dir.join(LOGS_DIR).join("old.log.gz"), 100, 30);
+
+        let result = clean_logs(&dir, &policy(Some(u64::MAX), None)).await.unwrap();
+
+        assert_eq!(result.files_removed, 0);
+        assert_eq!(result.bytes_removed, 0);
+        assert!(dir.join(LOGS_DIR).join("old.log.gz").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+//! Heap size checks against the memory of this computer
+//!
+//! A heap larger than physical memory makes the JVM fail with an unhelpful error
+//! or swap the system to a crawl, so launches are refused before Java starts.
+
+use serde::{Deserialize, Serialize};
+
+use super::commands::SystemMemory;
+
+/// Smallest memory setting the game starts with reliably, in MB
+pub const MIN_HEAP_MB: u32 = 512;
+
+/// Share of the available memory a heap may take before swapping becomes likely
+const AVAILABLE_SHARE: f64 = 0.75;
+
+/// Outcome of checking a launch's memory before Java is started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchValidation {
+    /// Memory setting the game launches with, raised to the minimum if it was below
+    pub memory_mb: u32,
+    /// Heap the JVM is given, a custom `-Xmx` wins over the memory setting
+    pub heap_mb: u64,
+    /// None when the system memory could not be read
+    pub system: Option<SystemMemory>,
+    /// Why the launch would be refused
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl LaunchValidation {
+    /// The errors as one message, for refusing a launch
+    pub fn into_result(self) -> Result<Self, String> {
+        if self.errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(self.errors.join(". "))
+        }
+    }
+}
+
+/// Check `memory_mb` and the user's JVM arguments against `system`
+pub fn validate(memory_mb: u32, user_args: &[String], system: Option<SystemMemory>) -> LaunchValidation {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let memory = if memory_mb < MIN_HEAP_MB {
+        warnings.push(format!(
+            "{} MB is too little for Minecraft to start, the memory was raised to {} MB",
+            memory_mb, MIN_HEAP_MB
+        ));
+        MIN_HEAP_MB
+    } else {
+        memory_mb
+    };
+
+    let custom_heap = super::jvm::max_heap_mb(user_args);
+    let heap_mb = custom_heap.unwrap_or(memory as u64);
+    if custom_heap.is_some_and(|heap| heap < MIN_HEAP_MB as u64) {
+        warnings.push(format!(
+            "The JVM arguments limit the heap to {} MB, Minecraft needs at least {} MB to start",
+            heap_mb, MIN_HEAP_MB
+        ));
+    }
+
+    if let Some(system) = system {
+        if heap_mb > system.total_mb {
+            errors.push(format!(
+                "The game is set to use {} MB of memory but this computer only has {} MB, lower it to at most {} MB",
+                heap_mb, system.total_mb, (system.total_mb as f64 * AVAILABLE_SHARE) as u64
+            ));
+        } else if heap_mb as f64 > system.available_mb as f64 * AVAILABLE_SHARE {
+            warnings.push(format!(
+                "{} MB is more than three quarters of the {} MB currently free, the game may stutter or other programs may slow down",
+                heap_mb, system.available_mb
+            ));
+        }
+    }
+
+    LaunchValidation { memory_mb: memory, heap_mb, system, errors, warnings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(total_mb: u64, available_mb: u64) -> Option<SystemMemory> {
+        Some(SystemMemory { total_mb, available_mb })
+    }
+
+    #[test]
+    fn refuses_a_heap_larger_than_physical_memory() {
+        let check = validate(16384, &[], system(8192, 6000));
+        assert_eq!(check.heap_mb, 16384);
+        assert_eq!(check.errors.len(), 1);
+        assert!(check.into_result().is_err());
+
+        // A custom -Xmx is what the JVM gets, whatever the memory setting says
+        let check = validate(4096, &["-Xmx12G".to_string()], system(8192, 6000));
+        assert_eq!(check.heap_mb, 12288);
+        assert!(!check.errors.is_empty());
+    }
+
+    #[test]
+    fn warns_when_most_free_memory_is_taken() {
+        let check = validate(6144, &[], system(16384, 7000));
+        assert!(check.errors.is_empty());
+        assert_eq!(check.warnings.len(), 1);
+
+        let check = validate(4096, &[], system(16384, 7000));
+        assert!(check.errors.is_empty() && check.warnings.is_empty());
+    }
+
+    #[test]
+    fn raises_tiny_memory_settings() {
+        let check = validate(128, &[], system(16384, 12000));
+        assert_eq!(check.memory_mb, MIN_HEAP_MB);
+        assert_eq!(check.heap_mb, MIN_HEAP_MB as u64);
+        assert_eq!(check.warnings.len(), 1);
+
+        // Without system memory only the minimum is checked
+        let check = validate(65536, &[], None);
+        assert!(check.errors.is_empty() && check.warnings.is_empty());
+    }
+}
@@ -3,6 +3,7 @@
 //! This module provides a simplified, MCVM-powered backend for Minecraft launching
 //! while maintaining the same Tauri API surface for the frontend.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 
@@ -15,6 +16,26 @@ pub mod assets;        // Asset management using MCVM
 pub mod java;          // Java management (enhanced with MCVM)
 pub mod commands;      // Tauri commands (refactored into submodules)
 pub mod mod_scanner;   // Mod JAR scanning for Java requirements
+pub mod logs;          // Log and crash report cleanup
+pub mod lan;           // LAN world and port helpers
+pub mod worlds;        // Singleplayer world listing and quick play
+pub mod readiness;     // Launch readiness checks for instances
+pub mod sessions;      // Running games and play time tracking
+pub mod crashes;       // Crash signatures and instance stability
+pub mod game_log;      // Live game output and the launcher's copy of it
+pub mod timestamps;    // Epoch millisecond conversions for instance dates
+pub mod hooks;         // Pre-launch and post-exit commands
+pub mod environment;   // Per-instance environment variables
+pub mod window;        // Game window size and fullscreen
+pub mod jvm;           // JVM argument presets
+pub mod memory;        // Heap size checks against system memory
+pub mod servers;       // Joining multiplayer servers from launch
+pub mod profile;       // Merged version profiles and legacy LaunchWrapper support
+pub mod platform;      // Library rules and natives for this OS and CPU
+pub mod integrity;     // Background integrity sweep and instance health
+pub mod graphics;      // Pre-launch OpenGL probe
+pub mod presets;       // Keybind and config presets shared between instances
+pub mod relocate;      // Repairing instance paths broken by a moved user folder or drive
 
 // Re-export main types for compatibility
 pub use core::MCVMCore;
@@ -34,8 +55,9 @@ pub struct MinecraftInstance {
     pub java_path: Option<String>,
     #[serde(rename = "jvmArgs")]
     pub jvm_args: Option<Vec<String>>,
-    #[serde(rename = "lastPlayed")]
-    pub last_played: Option<String>,
+    /// Epoch milliseconds
+    #[serde(rename = "lastPlayed", alias = "last_played", default, with = "timestamps::option_epoch_millis")]
+    pub last_played: Option<i64>,
     #[serde(rename = "totalPlayTime")]
     pub total_play_time: u64,
     pub icon: Option<String>,
@@ -49,8 +71,45 @@ pub struct MinecraftInstance {
     pub external_launcher: Option<String>,
     #[serde(rename = "resolvedJavaVersion")]
     pub resolved_java_version: Option<u32>,
-    #[serde(rename = "javaAnalysisDate")]
-    pub java_analysis_date: Option<String>,
+    /// Epoch milliseconds
+    #[serde(rename = "javaAnalysisDate", alias = "java_analysis_date", default, with = "timestamps::option_epoch_millis")]
+    pub java_analysis_date: Option<i64>,
+    #[serde(rename = "logCleanup", default)]
+    pub log_cleanup: Option<crate::storage::LogCleanupPolicy>,
+    #[serde(rename = "gameArgs", default)]
+    pub game_args: Option<Vec<String>>,
+    #[serde(rename = "preLaunchCommand", default)]
+    pub pre_launch_command: Option<String>,
+    #[serde(rename = "postExitCommand", default)]
+    pub post_exit_command: Option<String>,
+    #[serde(rename = "envVars", default)]
+    pub env_vars: Option<HashMap<String, String>>,
+    #[serde(rename = "clearInheritedEnv", default)]
+    pub clear_inherited_env: bool,
+    #[serde(rename = "windowWidth", default)]
+    pub window_width: Option<u32>,
+    #[serde(rename = "windowHeight", default)]
+    pub window_height: Option<u32>,
+    #[serde(default)]
+    pub fullscreen: Option<bool>,
+    #[serde(rename = "jvmArgsPreset", default)]
+    pub jvm_args_preset: Option<jvm::JvmArgsPreset>,
+    /// Epoch milliseconds
+    #[serde(rename = "createdAt", alias = "created_at", default, with = "timestamps::option_epoch_millis")]
+    pub created_at: Option<i64>,
+    #[serde(rename = "sizeMb", alias = "size_mb", default)]
+    pub size_mb: Option<u64>,
+    /// Whether the game files needed to launch are present
+    #[serde(default)]
+    pub ready: bool,
+    #[serde(rename = "notReadyReason", default)]
+    pub not_ready_reason: Option<String>,
+    /// Whether enough recent sessions crashed to call the setup flaky
+    #[serde(default)]
+    pub unstable: bool,
+    /// Outcome of the last integrity check, None until one ran
+    #[serde(default)]
+    pub health: Option<integrity::HealthStatus>,
 }
 
 /// Authentication information
@@ -79,6 +138,35 @@ pub struct LaunchResult {
     pub process_id: u32,
     pub success: bool,
     pub error: Option<String>,
+    /// The game process, to be tracked with `PlaySessions`
+    pub process: Option<std::process::Child>,
+}
+
+/// Effective launch arguments reported by the launch diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchDiagnostics {
+    pub game_args: Vec<String>,
+    pub rejected_game_args: Vec<String>,
+    /// Asset index the version resolves to, None when its version JSON is not installed
+    pub asset_index: Option<assets::AssetIndexCheck>,
+    /// Java the instance would launch with
+    pub java: Option<java::ResolvedJava>,
+    /// Why no Java could be resolved
+    pub java_error: Option<String>,
+    /// Where the installed mods were downloaded from
+    pub mod_provenance: Vec<crate::provenance::ProvenanceEntry>,
+    /// How past sessions ended, to tell a one-off crash from a chronic one
+    pub stability: crashes::StabilitySummary,
+    /// Environment variables the game would start with
+    pub environment: environment::LaunchEnvironment,
+    /// Main class, arguments and libraries of the merged version profile, None when it isn't installed
+    pub profile: Option<profile::LaunchProfile>,
+    /// Jars the profile puts on the classpath, resolved against the instance folder
+    pub classpath: Vec<PathBuf>,
+    /// OpenGL driver details, None when probing is turned off in the settings
+    pub graphics: Option<graphics::GraphicsProbe>,
+    /// Software rendering or a too old OpenGL for the version
+    pub graphics_warnings: Vec<String>,
 }
 
 /// Main entry point for Minecraft operations
@@ -90,7 +178,24 @@ pub async fn initialize_minecraft() -> Result<(), String> {
 pub async fn launch_minecraft(
     instance: &MinecraftInstance, 
     auth: Option<AuthInfo>, 
-    memory: u32
+    memory: u32,
+    app_handle: &tauri::AppHandle,
 ) -> Result<LaunchResult, String> {
+    let memory_check = memory::validate(
+        memory,
+        instance.jvm_args.as_deref().unwrap_or_default(),
+        commands::system_memory().ok(),
+    ).into_result()?;
+    for warning in &memory_check.warnings {
+        println!("⚠️  {}", warning);
+    }
+    let memory = memory_check.memory_mb;
+
+    hooks::run_pre_launch(&instance.id, app_handle).await?;
+    graphics::warn_before_launch(instance, app_handle).await;
+    java::ensure_java(versions::required_java_for_instance(&instance.id, &instance.game_dir, &instance.version).await, app_handle).await?;
+    if let Err(e) = assets::prepare_named_assets(&instance.game_dir, &instance.version).await {
+        println!("⚠️  Could not lay out legacy assets, sounds may be missing: {}", e);
+    }
     launcher::launch_instance(instance, auth.unwrap_or_default(), memory).await
 }
\ No newline at end of file
@@ -0,0 +1,201 @@
+//! Which libraries of a version JSON belong to this OS and CPU
+//!
+//! Library rules name an OS and sometimes an architecture. From 1.19 LWJGL also
+//! ships one natives artifact per architecture in the main library list, e.g.
+//! `natives-macos` beside `natives-macos-arm64`, all allowed on the same OS. The
+//! generic one is built for x86_64, so it is left out when an artifact for this
+//! architecture sits next to it, and artifacts for other architectures are skipped.
+
+use serde_json::Value;
+
+/// An OS as version JSONs name it and a CPU architecture as Rust names it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Platform {
+    /// `windows`, `osx` or `linux`
+    pub os: &'static str,
+    /// `x86_64`, `aarch64` or `x86`
+    pub arch: &'static str,
+}
+
+impl Platform {
+    /// The computer the launcher runs on
+    pub fn current() -> Self {
+        let os = match std::env::consts::OS {
+            "macos" => "osx",
+            os => os,
+        };
+        Self { os, arch: std::env::consts::ARCH }
+    }
+
+    fn is_64_bit(&self) -> bool {
+        !matches!(self.arch, "x86" | "arm")
+    }
+}
+
+/// Rust's name for an architecture as spelled in a rule or classifier
+fn normalize_arch(arch: &str) -> &str {
+    match arch {
+        "arm64" | "aarch64" | "aarch_64" => "aarch64",
+        "x64" | "amd64" | "x86_64" => "x86_64",
+        arch => arch,
+    }
+}
+
+/// Whether library `rules` allow it on `platform`, as the vanilla launcher applies them
+///
+/// A rule matches when its OS name and architecture, where given, both fit. The
+/// last matching rule wins and no rules at all means allowed.
+pub fn rules_allow(rules: Option<&Value>, platform: Platform) -> bool {
+    let Some(rules) = rules.and_then(|v| v.as_array()) else {
+        return true;
+    };
+    let mut allowed = false;
+    for rule in rules {
+        let name_fits = rule.pointer("/os/name").and_then(|v| v.as_str()).is_none_or(|name| name == platform.os);
+        let arch_fits = rule.pointer("/os/arch").and_then(|v| v.as_str()).is_none_or(|arch| normalize_arch(arch) == platform.arch);
+        if name_fits && arch_fits {
+            allowed = rule.get("action").and_then(|v| v.as_str()) == Some("allow");
+        }
+    }
+    allowed
+}
+
+/// Architecture a `natives-<os>-<arch>` classifier is built for, None for plain `natives-<os>`
+fn classifier_arch(classifier: &str) -> Option<&str> {
+    let rest = classifier.strip_prefix("natives-")?;
+    let (_, arch) = rest.split_once('-')?;
+    Some(normalize_arch(arch))
+}
+
+/// `group:artifact:version` and the classifier of a library name, if it has one
+fn split_classifier(name: &str) -> (&str, Option<&str>) {
+    let name = name.split('@').next().unwrap_or(name);
+    match name.match_indices(':').nth(2) {
+        Some((i, _)) => (&name[..i], Some(&name[i + 1..])),
+        None => (name, None),
+    }
+}
+
+/// Entries of a version JSON's `libraries` that apply to `platform`, in their order
+pub fn libraries_for(entries: &[Value], platform: Platform) -> Vec<&Value> {
+    let allowed: Vec<&Value> = entries.iter().filter(|entry| rules_allow(entry.get("rules"), platform)).collect();
+    let names: Vec<(&str, Option<&str>)> = allowed.iter()
+        .map(|entry| split_classifier(entry.get("name").and_then(|v| v.as_str()).unwrap_or_default()))
+        .collect();
+
+    allowed.iter().zip(&names)
+        .filter(|(_, (coordinates, classifier))| {
+            let Some(classifier) = classifier.filter(|c| c.starts_with("natives-")) else {
+                return true;
+            };
+            match classifier_arch(classifier) {
+                Some(arch) => arch == platform.arch,
+                // The generic natives give way to ones built for this architecture
+                None => !names.iter().any(|(other, other_classifier)| {
+                    other == coordinates && other_classifier.is_some_and(|other_classifier| {
+                        other_classifier.strip_prefix(classifier).is_some_and(|suffix| suffix.starts_with('-'))
+                            && classifier_arch(other_classifier) == Some(platform.arch)
+                    })
+                }),
+            }
+        })
+        .map(|(entry, _)| *entry)
+        .collect()
+}
+
+/// Classifier of the natives a pre-1.19 library extracts on `platform`
+///
+/// Read from the library's `natives` map, with `${arch}` filled in. When the
+/// classifiers also hold a build for this architecture, as some third-party
+/// version JSONs add, that one is picked instead.
+pub fn natives_classifier(library: &Value, platform: Platform) -> Option<String> {
+    let classifier = library.get("natives")?.get(platform.os)?.as_str()?
+        .replace("${arch}", if platform.is_64_bit() { "64" } else { "32" });
+    let classifiers = library.pointer("/downloads/classifiers").and_then(|v| v.as_object());
+    let specific = classifiers.and_then(|classifiers| {
+        classifiers.keys()
+            .find(|key| key.strip_prefix(classifier.as_str()).is_some_and(|suffix| suffix.starts_with('-'))
+                && classifier_arch(key) == Some(platform.arch))
+    });
+    Some(specific.cloned().unwrap_or(classifier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const VERSION_1_19_4: &str = include_str!("fixtures/version_1_19_4.json");
+
+    const MAC_ARM: Platform = Platform { os: "osx", arch: "aarch64" };
+    const MAC_INTEL: Platform = Platform { os: "osx", arch: "x86_64" };
+    const LINUX: Platform = Platform { os: "linux", arch: "x86_64" };
+    const LINUX_ARM: Platform = Platform { os: "linux", arch: "aarch64" };
+    const WINDOWS: Platform = Platform { os: "windows", arch: "x86_64" };
+    const WINDOWS_32: Platform = Platform { os: "windows", arch: "x86" };
+    const WINDOWS_ARM: Platform = Platform { os: "windows", arch: "aarch64" };
+
+    fn natives(entries: &[Value], platform: Platform) -> Vec<String> {
+        libraries_for(entries, platform).iter()
+            .filter_map(|entry| entry["name"].as_str())
+            .filter(|name| name.contains(":natives-"))
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn picks_per_arch_lwjgl_natives_from_1_19() {
+        let json: Value = serde_json::from_str(VERSION_1_19_4).unwrap();
+        let entries = json["libraries"].as_array().unwrap();
+
+        assert_eq!(natives(entries, MAC_ARM), ["org.lwjgl:lwjgl:3.3.1:natives-macos-arm64", "org.lwjgl:lwjgl-glfw:3.3.1:natives-macos-arm64"]);
+        assert_eq!(natives(entries, MAC_INTEL), ["org.lwjgl:lwjgl:3.3.1:natives-macos", "org.lwjgl:lwjgl-glfw:3.3.1:natives-macos"]);
+        assert_eq!(natives(entries, WINDOWS), ["org.lwjgl:lwjgl:3.3.1:natives-windows", "org.lwjgl:lwjgl-glfw:3.3.1:natives-windows"]);
+        assert_eq!(natives(entries, WINDOWS_ARM), ["org.lwjgl:lwjgl:3.3.1:natives-windows-arm64", "org.lwjgl:lwjgl-glfw:3.3.1:natives-windows-arm64"]);
+        assert_eq!(natives(entries, WINDOWS_32), ["org.lwjgl:lwjgl:3.3.1:natives-windows-x86", "org.lwjgl:lwjgl-glfw:3.3.1:natives-windows-x86"]);
+        assert_eq!(natives(entries, LINUX), ["org.lwjgl:lwjgl:3.3.1:natives-linux", "org.lwjgl:lwjgl-glfw:3.3.1:natives-linux"]);
+
+        // Vanilla has no ARM Linux build, the generic natives stay rather than leaving none
+        assert_eq!(natives(entries, LINUX_ARM), natives(entries, LINUX));
+        // Plain libraries are kept everywhere
+        assert!(libraries_for(entries, MAC_ARM).iter().any(|entry| entry["name"] == "org.lwjgl:lwjgl:3.3.1"));
+    }
+
+    #[test]
+    fn arm_linux_natives_replace_the_generic_ones() {
+        let entries = vec![
+            json!({ "name": "org.lwjgl:lwjgl:3.3.3:natives-linux", "rules": [{ "action": "allow", "os": { "name": "linux" } }] }),
+            json!({ "name": "org.lwjgl:lwjgl:3.3.3:natives-linux-arm64", "rules": [{ "action": "allow", "os": { "name": "linux" } }] }),
+        ];
+        assert_eq!(natives(&entries, LINUX_ARM), ["org.lwjgl:lwjgl:3.3.3:natives-linux-arm64"]);
+        assert_eq!(natives(&entries, LINUX), ["org.lwjgl:lwjgl:3.3.3:natives-linux"]);
+    }
+
+    #[test]
+    fn rules_honor_the_architecture() {
+        let rules = json!([{ "action": "allow", "os": { "name": "osx", "arch": "arm64" } }]);
+        assert!(rules_allow(Some(&rules), MAC_ARM));
+        assert!(!rules_allow(Some(&rules), MAC_INTEL));
+
+        let rules = json!([{ "action": "allow" }, { "action": "disallow", "os": { "arch": "x86" } }]);
+        assert!(rules_allow(Some(&rules), WINDOWS));
+        assert!(!rules_allow(Some(&rules), WINDOWS_32));
+        assert!(rules_allow(None, LINUX));
+    }
+
+    #[test]
+    fn legacy_natives_map_fills_in_the_arch() {
+        let library = json!({
+            "name": "tv.twitch:twitch-platform:6.5",
+            "natives": { "linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows-${arch}" },
+            "downloads": { "classifiers": {
+                "natives-osx": {}, "natives-osx-arm64": {}, "natives-linux": {}, "natives-windows-32": {}, "natives-windows-64": {}
+            } }
+        });
+        assert_eq!(natives_classifier(&library, WINDOWS).as_deref(), Some("natives-windows-64"));
+        assert_eq!(natives_classifier(&library, WINDOWS_32).as_deref(), Some("natives-windows-32"));
+        assert_eq!(natives_classifier(&library, MAC_ARM).as_deref(), Some("natives-osx-arm64"));
+        assert_eq!(natives_classifier(&library, MAC_INTEL).as_deref(), Some("natives-osx"));
+        assert_eq!(natives_classifier(&json!({ "name": "a:b:1" }), LINUX), None);
+    }
+}
@@ -0,0 +1,457 @@
+//! Named settings presets shared between instances
+//!
+//! A preset bundles the keybind lines of an instance's `options.txt` with chosen
+//! config files, e.g. a minimap's settings. Presets live in `presets/<id>` in the
+//! launcher folder: `preset.json` describes one and `files` holds its config files
+//! at their path relative to the instance. Applying a preset merges only its
+//! keybinds into `options.txt` and copies its files in. Anything it replaces is
+//! backed up to `.preset-backups/<timestamp>` in the instance first.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+const PRESET_FILE: &str = "preset.json";
+const FILES_DIR: &str = "files";
+const OPTIONS_FILE: &str = "options.txt";
+const BACKUP_DIR: &str = ".preset-backups";
+
+/// An `options.txt` line whose key starts with `key_`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsPreset {
+    pub name: String,
+    /// Folder name under `presets`, derived from the name
+    pub id: String,
+    pub created_at: String,
+    /// Name of the instance the preset was saved from
+    pub source_instance: Option<String>,
+    /// Keybinds in `options.txt` order
+    pub keybinds: Vec<KeyBinding>,
+    /// Config files relative to the instance folder, `/` separated
+    pub files: Vec<String>,
+}
+
+/// A keybind the instance already had with another value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeybindConflict {
+    pub key: String,
+    pub instance_value: String,
+    pub preset_value: String,
+}
+
+/// Something in the preset that wasn't applied, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+/// What applying a preset changed
+///
+/// Conflicts were replaced by the preset's version when `overwrite` was set and
+/// kept otherwise.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresetApplyResult {
+    pub overwrite: bool,
+    /// Keybinds the instance didn't set before
+    pub keybinds_added: Vec<String>,
+    pub keybind_conflicts: Vec<KeybindConflict>,
+    pub files_copied: Vec<String>,
+    /// Files the instance already had with other contents
+    pub file_conflicts: Vec<String>,
+    pub skipped: Vec<SkippedFile>,
+    /// Where replaced originals were backed up, None when nothing was replaced
+    pub backup_dir: Option<String>,
+}
+
+pub fn presets_dir() -> PathBuf {
+    crate::storage::get_launcher_dir().join("presets")
+}
+
+/// Folder name of a preset, lowercase letters, digits and dashes
+pub fn preset_id(name: &str) -> Result<String, String> {
+    let mut id = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    let id = id.trim_end_matches('-').to_string();
+    if id.is_empty() {
+        return Err(format!("'{}' can't be used as a preset name, use letters or digits", name));
+    }
+    Ok(id)
+}
+
+/// A path inside the instance folder, rejecting absolute paths and `..`
+pub fn instance_relative(path: &str) -> Result<PathBuf, String> {
+    let relative = PathBuf::from(path.trim().replace('\\', "/"));
+    let mut normalized = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            _ => return Err(format!("'{}' is not a path inside the instance", path)),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        return Err(format!("'{}' is not a path inside the instance", path));
+    }
+    Ok(normalized)
+}
+
+/// Save `include_paths` and the keybinds of `game_dir` as a preset, replacing one with the same name
+///
+/// Folders are saved with everything in them. `options.txt` itself is never
+/// copied, only its keybind lines.
+pub async fn save_preset(
+    presets_dir: &Path,
+    name: &str,
+    source_instance: Option<String>,
+    game_dir: &Path,
+    include_paths: &[String],
+) -> Result<SettingsPreset, String> {
+    let id = preset_id(name)?;
+
+    let options = tokio::fs::read_to_string(game_dir.join(OPTIONS_FILE)).await.unwrap_or_default();
+    let keybinds = parse_keybinds(&options);
+
+    let mut files = Vec::new();
+    for include in include_paths {
+        let relative = instance_relative(include)?;
+        let source = game_dir.join(&relative);
+        if !source.exists() {
+            return Err(format!("'{}' doesn't exist in the instance", include));
+        }
+        for entry in walkdir::WalkDir::new(&source).follow_links(false) {
+            let entry = entry.map_err(|e| format!("Failed to read '{}': {}", include, e))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let file = entry.path().strip_prefix(game_dir)
+                .map_err(|e| e.to_string())?
+                .to_string_lossy()
+                .replace('\\', "/");
+            if file != OPTIONS_FILE && !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    files.sort();
+
+    if keybinds.is_empty() && files.is_empty() {
+        return Err("Nothing to save: the instance has no keybinds and no files were picked".to_string());
+    }
+
+    // Built next to the old preset and swapped in, so a failed save keeps it
+    let dir = presets_dir.join(&id);
+    let staging = presets_dir.join(format!(".{}-{}", id, uuid::Uuid::new_v4()));
+    let result = write_preset(&staging, game_dir, SettingsPreset {
+        name: name.trim().to_string(),
+        id,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        source_instance,
+        keybinds,
+        files,
+    }).await;
+    let preset = match result {
+        Ok(preset) => preset,
+        Err(e) => {
+            let _ = tokio::fs::remove_dir_all(&staging).await;
+            return Err(e);
+        }
+    };
+
+    if dir.exists() {
+        tokio::fs::remove_dir_all(&dir).await
+            .map_err(|e| format!("Failed to replace preset '{}': {}", preset.name, e))?;
+    }
+    tokio::fs::rename(&staging, &dir).await
+        .map_err(|e| format!("Failed to save preset '{}': {}", preset.name, e))?;
+    Ok(preset)
+}
+
+async fn write_preset(dir: &Path, game_dir: &Path, preset: SettingsPreset) -> Result<SettingsPreset, String> {
+    for file in &preset.files {
+        let destination = dir.join(FILES_DIR).join(file);
+        if let Some(parent) = destination.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+        }
+        tokio::fs::copy(game_dir.join(file), &destination).await
+            .map_err(|e| format!("Failed to copy '{}': {}", file, e))?;
+    }
+    tokio::fs::create_dir_all(dir).await.map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&preset).map_err(|e| e.to_string())?;
+    tokio::fs::write(dir.join(PRESET_FILE), json).await
+        .map_err(|e| format!("Failed to write preset '{}': {}", preset.name, e))?;
+    Ok(preset)
+}
+
+/// Every saved preset, sorted by name
+pub async fn list_presets(presets_dir: &Path) -> Vec<SettingsPreset> {
+    let mut presets = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(presets_dir).await else {
+        return presets;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        match read_preset(&entry.path()).await {
+            Ok(preset) => presets.push(preset),
+            Err(e) => println!("⚠️  Skipping preset {}: {}", entry.path().display(), e),
+        }
+    }
+    presets.sort_by_key(|preset| preset.name.to_lowercase());
+    presets
+}
+
+async fn read_preset(dir: &Path) -> Result<SettingsPreset, String> {
+    let json = tokio::fs::read_to_string(dir.join(PRESET_FILE)).await.map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+pub async fn delete_preset(presets_dir: &Path, name: &str) -> Result<(), String> {
+    let dir = presets_dir.join(preset_id(name)?);
+    if !dir.exists() {
+        return Err(format!("Preset '{}' not found", name));
+    }
+    tokio::fs::remove_dir_all(&dir).await
+        .map_err(|e| format!("Failed to delete preset '{}': {}", name, e))
+}
+
+/// Merge the preset called `name` into `game_dir`
+///
+/// Conflicting keybinds and files are only replaced when `overwrite` is set.
+/// Whatever gets replaced is backed up first.
+pub async fn apply_preset(presets_dir: &Path, game_dir: &Path, name: &str, overwrite: bool) -> Result<PresetApplyResult, String> {
+    let dir = presets_dir.join(preset_id(name)?);
+    let preset = read_preset(&dir).await
+        .map_err(|_| format!("Preset '{}' not found", name))?;
+    let backup_dir = game_dir.join(BACKUP_DIR).join(chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string());
+    let mut result = PresetApplyResult { overwrite, ..Default::default() };
+    let mut backed_up = false;
+
+    if !preset.keybinds.is_empty() {
+        let options_path = game_dir.join(OPTIONS_FILE);
+        let options = tokio::fs::read_to_string(&options_path).await.unwrap_or_default();
+        let merge = merge_keybinds(&options, &preset.keybinds, overwrite);
+        result.keybinds_added = merge.added;
+        result.keybind_conflicts = merge.conflicts;
+
+        if merge.options != options {
+            if options_path.exists() {
+                backup(game_dir, OPTIONS_FILE, &backup_dir).await?;
+                backed_up = true;
+            }
+            tokio::fs::write(&options_path, merge.options).await
+                .map_err(|e| format!("Failed to write {}: {}", OPTIONS_FILE, e))?;
+        }
+    }
+
+    for file in &preset.files {
+        let source = dir.join(FILES_DIR).join(file);
+        let skip = |reason: &str| SkippedFile { path: file.clone(), reason: reason.to_string() };
+        if instance_relative(file).is_err() {
+            result.skipped.push(skip("Not a path inside the instance"));
+            continue;
+        }
+        let Ok(contents) = tokio::fs::read(&source).await else {
+            result.skipped.push(skip("Missing from the saved preset"));
+            continue;
+        };
+
+        let destination = game_dir.join(file);
+        if destination.is_dir() {
+            result.skipped.push(skip("A folder with this name is in the way"));
+            continue;
+        }
+        if let Ok(existing) = tokio::fs::read(&destination).await {
+            if existing == contents {
+                result.skipped.push(skip("Already the same"));
+                continue;
+            }
+            result.file_conflicts.push(file.clone());
+            if !overwrite {
+                result.skipped.push(skip("Differs from the instance's copy, which was kept"));
+                continue;
+            }
+            backup(game_dir, file, &backup_dir).await?;
+            backed_up = true;
+        }
+
+        if let Some(parent) = destination.parent() {
+            if let Err(e) = tokio::fs::create_dir_all(parent).await {
+                result.skipped.push(skip(&e.to_string()));
+                continue;
+            }
+        }
+        match tokio::fs::write(&destination, contents).await {
+            Ok(()) => result.files_copied.push(file.clone()),
+            Err(e) => result.skipped.push(skip(&e.to_string())),
+        }
+    }
+
+    if backed_up {
+        result.backup_dir = Some(backup_dir.to_string_lossy().to_string());
+    }
+    Ok(result)
+}
+
+async fn backup(game_dir: &Path, file: &str, backup_dir: &Path) -> Result<(), String> {
+    let destination = backup_dir.join(file);
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await
+            .map_err(|e| format!("Failed to back up '{}': {}", file, e))?;
+    }
+    tokio::fs::copy(game_dir.join(file), &destination).await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to back up '{}': {}", file, e))
+}
+
+/// `key_*` lines of an `options.txt`
+pub fn parse_keybinds(options: &str) -> Vec<KeyBinding> {
+    options.lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| key.starts_with("key_"))
+        .map(|(key, value)| KeyBinding { key: key.to_string(), value: value.trim_end().to_string() })
+        .collect()
+}
+
+struct KeybindMerge {
+    options: String,
+    added: Vec<String>,
+    conflicts: Vec<KeybindConflict>,
+}
+
+/// Put `keybinds` into `options`, leaving every other line as it is
+fn merge_keybinds(options: &str, keybinds: &[KeyBinding], overwrite: bool) -> KeybindMerge {
+    let mut lines: Vec<String> = options.lines().map(String::from).collect();
+    let mut added = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for keybind in keybinds {
+        let existing = lines.iter().position(|line| {
+            line.split_once(':').is_some_and(|(key, _)| key == keybind.key)
+        });
+        match existing {
+            Some(index) => {
+                let current = lines[index].split_once(':').map(|(_, value)| value.trim_end()).unwrap_or_default();
+                if current == keybind.value {
+                    continue;
+                }
+                conflicts.push(KeybindConflict {
+                    key: keybind.key.clone(),
+                    instance_value: current.to_string(),
+                    preset_value: keybind.value.clone(),
+                });
+                if overwrite {
+                    lines[index] = format!("{}:{}", keybind.key, keybind.value);
+                }
+            }
+            None => {
+                lines.push(format!("{}:{}", keybind.key, keybind.value));
+                added.push(keybind.key.clone());
+            }
+        }
+    }
+
+    if added.is_empty() && (conflicts.is_empty() || !overwrite) {
+        return KeybindMerge { options: options.to_string(), added, conflicts };
+    }
+    let mut options = lines.join("\n");
+    options.push('\n');
+    KeybindMerge { options, added, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-presets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn names_and_paths_are_checked() {
+        assert_eq!(preset_id("Controller-friendly").unwrap(), "controller-friendly");
+        assert_eq!(preset_id("  PvP  (1.8) ").unwrap(), "pvp-1-8");
+        assert!(preset_id("!!!").is_err());
+
+        assert_eq!(instance_relative("config\\xaero.txt").unwrap(), PathBuf::from("config/xaero.txt"));
+        assert!(instance_relative("../other/options.txt").is_err());
+        assert!(instance_relative("/etc/passwd").is_err());
+        assert!(instance_relative("").is_err());
+    }
+
+    #[test]
+    fn merges_only_the_preset_keybinds() {
+        let options = "version:3465\nkey_key.jump:key.keyboard.space\nkey_key.sneak:key.keyboard.left.shift\nfov:0.5\n";
+        let keybinds = parse_keybinds("key_key.sneak:key.keyboard.c\nkey_key.zoom:key.keyboard.z\nfov:1.0\n");
+        assert_eq!(keybinds.len(), 2);
+
+        let kept = merge_keybinds(options, &keybinds, false);
+        assert_eq!(kept.added, vec!["key_key.zoom".to_string()]);
+        assert_eq!(kept.conflicts[0].instance_value, "key.keyboard.left.shift");
+        assert!(kept.options.contains("key_key.sneak:key.keyboard.left.shift"));
+        assert!(kept.options.ends_with("key_key.zoom:key.keyboard.z\n"));
+
+        let replaced = merge_keybinds(options, &keybinds, true);
+        assert_eq!(
+            replaced.options,
+            "version:3465\nkey_key.jump:key.keyboard.space\nkey_key.sneak:key.keyboard.c\nfov:0.5\nkey_key.zoom:key.keyboard.z\n"
+        );
+
+        let unchanged = merge_keybinds(&replaced.options, &keybinds, true);
+        assert_eq!(unchanged.options, replaced.options);
+        assert!(unchanged.added.is_empty() && unchanged.conflicts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn saves_and_applies_presets_with_backups() {
+        let root = temp_dir();
+        let presets = root.join("presets");
+        let source = root.join("source");
+        std::fs::create_dir_all(source.join("config").join("minimap")).unwrap();
+        std::fs::write(source.join("options.txt"), "key_key.sneak:key.keyboard.c\nfov:1.0\n").unwrap();
+        std::fs::write(source.join("config").join("minimap").join("minimap.txt"), "zoom:2").unwrap();
+        std::fs::write(source.join("config").join("other.toml"), "a = 1").unwrap();
+
+        let saved = save_preset(&presets, "PvP", Some("Source".to_string()), &source, &["config/minimap".to_string(), "options.txt".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(saved.files, vec!["config/minimap/minimap.txt".to_string()]);
+        assert_eq!(list_presets(&presets).await.len(), 1);
+
+        let target = root.join("target");
+        std::fs::create_dir_all(target.join("config").join("minimap")).unwrap();
+        std::fs::write(target.join("options.txt"), "key_key.sneak:key.keyboard.left.shift\n").unwrap();
+        std::fs::write(target.join("config").join("minimap").join("minimap.txt"), "zoom:1").unwrap();
+
+        let kept = apply_preset(&presets, &target, "pvp", false).await.unwrap();
+        assert_eq!(kept.file_conflicts, vec!["config/minimap/minimap.txt".to_string()]);
+        assert_eq!(kept.skipped.len(), 1);
+        assert!(kept.backup_dir.is_none());
+        assert_eq!(std::fs::read_to_string(target.join("config").join("minimap").join("minimap.txt")).unwrap(), "zoom:1");
+
+        let replaced = apply_preset(&presets, &target, "PvP", true).await.unwrap();
+        assert_eq!(replaced.files_copied, vec!["config/minimap/minimap.txt".to_string()]);
+        assert_eq!(std::fs::read_to_string(target.join("options.txt")).unwrap(), "key_key.sneak:key.keyboard.c\n");
+        let backup = PathBuf::from(replaced.backup_dir.unwrap());
+        assert_eq!(std::fs::read_to_string(backup.join("config").join("minimap").join("minimap.txt")).unwrap(), "zoom:1");
+        assert_eq!(std::fs::read_to_string(backup.join("options.txt")).unwrap(), "key_key.sneak:key.keyboard.left.shift\n");
+
+        delete_preset(&presets, "PvP").await.unwrap();
+        assert!(list_presets(&presets).await.is_empty());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,389 @@
+//! The merged version profile an instance starts with
+//!
+//! Loader installers add a version JSON that inherits from the vanilla one. The
+//! loader's main class and `minecraftArguments` replace the vanilla ones, modern
+//! `arguments` lists are appended, and the loader's libraries come first.
+//!
+//! Forge for 1.7.10 to 1.12.2 starts through LaunchWrapper: the arguments are one
+//! string with `--tweakClass` in it, and launchwrapper and ASM have to be on the
+//! classpath although vanilla manifests never list them. Profiles converted by
+//! other launchers sometimes drop them, so they are added back when missing.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::platform::{self, Platform};
+use super::versions::version_compare;
+
+/// Main class of profiles that start through LaunchWrapper
+pub const LAUNCHWRAPPER_MAIN_CLASS: &str = "net.minecraft.launchwrapper.Launch";
+
+const LAUNCHWRAPPER_LIBRARY: &str = "net.minecraft:launchwrapper:1.12";
+
+/// Limit on `inheritsFrom` chains, which are one level deep in practice
+const MAX_INHERITANCE_DEPTH: usize = 8;
+
+/// A version JSON merged with the versions it inherits from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchProfile {
+    /// Id of the most specific version, e.g. `1.12.2-forge-14.23.5.2859`
+    pub id: String,
+    pub main_class: String,
+    /// Game arguments with `${...}` placeholders left in
+    pub game_args: Vec<String>,
+    /// Maven coordinates in classpath order
+    pub libraries: Vec<String>,
+    /// Version whose jar is the game, `jar` in the profile or the root version
+    pub client_jar: String,
+    /// Whether the arguments came from the single-string `minecraftArguments`
+    pub legacy_arguments: bool,
+}
+
+impl LaunchProfile {
+    /// Merge version JSONs ordered child first
+    pub fn merge(chain: &[Value]) -> Option<Self> {
+        let child = chain.first()?;
+        let id = child.get("id")?.as_str()?.to_string();
+        let field = |key: &str| chain.iter().find_map(|json| json.get(key).and_then(|v| v.as_str()));
+
+        let main_class = field("mainClass")?.to_string();
+        let client_jar = field("jar")
+            .or_else(|| chain.last().and_then(|root| root.get("id")).and_then(|v| v.as_str()))
+            .unwrap_or(&id)
+            .to_string();
+
+        // minecraftArguments is replaced by the child, arguments.game is appended to
+        let (game_args, legacy_arguments) = match field("minecraftArguments") {
+            Some(arguments) => (arguments.split_whitespace().map(str::to_string).collect(), true),
+            None => {
+                let args = chain.iter().rev()
+                    .filter_map(|json| json.pointer("/arguments/game").and_then(|v| v.as_array()))
+                    .flatten()
+                    .filter_map(|arg| arg.as_str().map(str::to_string))
+                    .collect();
+                (args, false)
+            }
+        };
+
+        let mut seen = HashSet::new();
+        let mut libraries = Vec::new();
+        for json in chain {
+            let Some(entries) = json.get("libraries").and_then(|v| v.as_array()) else {
+                continue;
+            };
+            for entry in platform::libraries_for(entries, Platform::current()) {
+                let Some(name) = entry.get("name").and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                // Natives from the legacy `natives` map are extracted, not put on the classpath
+                if entry.get("natives").is_some() {
+                    continue;
+                }
+                if seen.insert(library_key(name)) {
+                    libraries.push(name.to_string());
+                }
+            }
+        }
+
+        let mut profile = Self { id, main_class, game_args, libraries, client_jar, legacy_arguments };
+        profile.add_launchwrapper_libraries();
+        Some(profile)
+    }
+
+    pub fn uses_launchwrapper(&self) -> bool {
+        self.main_class == LAUNCHWRAPPER_MAIN_CLASS
+    }
+
+    /// Classes passed with `--tweakClass`, in order
+    pub fn tweak_classes(&self) -> Vec<String> {
+        tweak_classes_in(&self.game_args)
+    }
+
+    /// `--tweakClass` arguments for the classes `custom_args` doesn't already pass
+    pub fn tweak_args(&self, custom_args: &[String]) -> Vec<String> {
+        let passed = tweak_classes_in(custom_args);
+        self.tweak_classes().into_iter()
+            .filter(|class| !passed.contains(class))
+            .flat_map(|class| ["--tweakClass".to_string(), class])
+            .collect()
+    }
+
+    /// Game arguments with placeholders filled in from `vars`
+    pub fn resolve_args(&self, vars: &HashMap<&str, String>) -> Vec<String> {
+        self.game_args.iter().map(|arg| substitute(arg, vars)).collect()
+    }
+
+    /// Jar paths in classpath order, the game jar last
+    pub fn classpath(&self, libraries_dir: &Path, versions_dir: &Path) -> Vec<PathBuf> {
+        self.libraries.iter()
+            .filter_map(|name| library_path(name))
+            .map(|path| libraries_dir.join(path))
+            .chain(std::iter::once(versions_dir.join(&self.client_jar).join(format!("{}.jar", self.client_jar))))
+            .collect()
+    }
+
+    /// LaunchWrapper and ASM are only listed by the loader profile, add them when it didn't
+    fn add_launchwrapper_libraries(&mut self) {
+        if !self.uses_launchwrapper() {
+            return;
+        }
+        let has = |artifacts: &[&str], libraries: &[String]| libraries.iter().any(|name| {
+            let key = library_key(name);
+            artifacts.iter().any(|artifact| key.ends_with(&format!(":{}", artifact)))
+        });
+        let mut missing = Vec::new();
+        if !has(&["launchwrapper"], &self.libraries) {
+            missing.push(LAUNCHWRAPPER_LIBRARY.to_string());
+        }
+        if !has(&["asm-all", "asm-debug-all"], &self.libraries) {
+            // 1.12 Forge moved to the debug build of ASM 5.2
+            let asm = if version_compare(&self.client_jar, "1.12") >= 0 {
+                "org.ow2.asm:asm-debug-all:5.2"
+            } else {
+                "org.ow2.asm:asm-all:5.0.3"
+            };
+            missing.push(asm.to_string());
+        }
+        if !missing.is_empty() {
+            println!("🧵 Adding {} to the classpath of {}", missing.join(", "), self.id);
+            self.libraries.splice(0..0, missing);
+        }
+    }
+}
+
+/// Merged profile for an instance, starting from a loader profile for `version` if one is installed
+pub async fn load(game_dir: &Path, version: &str) -> Option<LaunchProfile> {
+    LaunchProfile::merge(&load_chain(game_dir, version).await)
+}
+
+/// Version JSONs from the most specific to the root
+///
+/// Instances store the Minecraft version, so a loader version inheriting from it
+/// is where the chain starts when there is one.
+pub async fn load_chain(game_dir: &Path, version: &str) -> Vec<Value> {
+    let versions_dir = game_dir.join("versions");
+    let mut current = loader_profile_id(&versions_dir, version).await.unwrap_or_else(|| version.to_string());
+    let mut chain = Vec::new();
+    for _ in 0..MAX_INHERITANCE_DEPTH {
+        let Some(json) = read_version_json(&versions_dir, &current).await else {
+            break;
+        };
+        let parent = json.get("inheritsFrom").and_then(|v| v.as_str()).map(str::to_string);
+        chain.push(json);
+        match parent {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+    chain
+}
+
+/// Newest installed version that inherits from `version`
+async fn loader_profile_id(versions_dir: &Path, version: &str) -> Option<String> {
+    let mut entries = tokio::fs::read_dir(versions_dir).await.ok()?;
+    let mut best: Option<(String, String)> = None;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let id = entry.file_name().to_string_lossy().to_string();
+        if id == version {
+            continue;
+        }
+        let Some(json) = read_version_json(versions_dir, &id).await else {
+            continue;
+        };
+        if json.get("inheritsFrom").and_then(|v| v.as_str()) != Some(version) {
+            continue;
+        }
+        let time = json.get("time").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        if best.as_ref().is_none_or(|(best_time, _)| time > *best_time) {
+            best = Some((time, id));
+        }
+    }
+    best.map(|(_, id)| id)
+}
+
+async fn read_version_json(versions_dir: &Path, id: &str) -> Option<Value> {
+    let content = tokio::fs::read_to_string(versions_dir.join(id).join(format!("{}.json", id))).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn tweak_classes_in(args: &[String]) -> Vec<String> {
+    let mut classes = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--tweakClass" {
+            classes.extend(args.next().cloned());
+        } else if let Some(class) = arg.strip_prefix("--tweakClass=") {
+            classes.push(class.to_string());
+        }
+    }
+    classes
+}
+
+/// Replace `${name}` placeholders, unknown names are left as they are
+pub fn substitute(arg: &str, vars: &HashMap<&str, String>) -> String {
+    let mut result = String::with_capacity(arg.len());
+    let mut rest = arg;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + end];
+        result.push_str(&rest[..start]);
+        match vars.get(name) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Path of a library under `libraries`, from `group:artifact:version[:classifier]`
+pub fn library_path(name: &str) -> Option<PathBuf> {
+    let (name, extension) = name.split_once('@').unwrap_or((name, "jar"));
+    let mut parts = name.split(':');
+    let (group, artifact, version) = (parts.next()?, parts.next()?, parts.next()?);
+    let file = match parts.next() {
+        Some(classifier) => format!("{}-{}-{}.{}", artifact, version, classifier, extension),
+        None => format!("{}-{}.{}", artifact, version, extension),
+    };
+    let mut path: PathBuf = group.split('.').collect();
+    path.push(artifact);
+    path.push(version);
+    path.push(file);
+    Some(path)
+}
+
+/// `group:artifact[:classifier]`, two versions of one library share it
+fn library_key(name: &str) -> String {
+    let parts: Vec<&str> = name.split('@').next().unwrap_or(name).split(':').collect();
+    match parts.as_slice() {
+        [group, artifact, _, classifier, ..] => format!("{}:{}:{}", group, artifact, classifier),
+        [group, artifact, ..] => format!("{}:{}", group, artifact),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FORGE_1_7_10: &str = include_str!("fixtures/forge_1_7_10.json");
+    const FORGE_1_12_2: &str = include_str!("fixtures/forge_1_12_2.json");
+
+    fn vanilla(id: &str, arguments: &str) -> Value {
+        json!({
+            "id": id,
+            "mainClass": "net.minecraft.client.main.Main",
+            "minecraftArguments": arguments,
+            "libraries": [
+                { "name": "com.google.guava:guava:15.0" },
+                { "name": "org.lwjgl.lwjgl:lwjgl:2.9.1" },
+                {
+                    "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.1",
+                    "natives": { "linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows" }
+                },
+                {
+                    "name": "ca.weblounge:osx-only:1.0",
+                    "rules": [{ "action": "allow", "os": { "name": "osx-never" } }]
+                }
+            ]
+        })
+    }
+
+    fn forge_profile(fixture: &str, parent: Value) -> LaunchProfile {
+        LaunchProfile::merge(&[serde_json::from_str(fixture).unwrap(), parent]).unwrap()
+    }
+
+    #[test]
+    fn forge_1_7_10_starts_through_launchwrapper_with_the_fml_tweaker() {
+        let profile = forge_profile(FORGE_1_7_10, vanilla("1.7.10", "--username ${auth_player_name} --version ${version_name}"));
+
+        assert_eq!(profile.main_class, LAUNCHWRAPPER_MAIN_CLASS);
+        assert!(profile.legacy_arguments);
+        assert_eq!(profile.tweak_classes(), vec!["cpw.mods.fml.common.launcher.FMLTweaker"]);
+        assert_eq!(profile.client_jar, "1.7.10");
+        // Forge's guava replaces the vanilla one, natives and other platforms are left out
+        assert!(profile.libraries.contains(&"com.google.guava:guava:17.0".to_string()));
+        assert!(!profile.libraries.contains(&"com.google.guava:guava:15.0".to_string()));
+        assert!(profile.libraries.contains(&"org.lwjgl.lwjgl:lwjgl:2.9.1".to_string()));
+        assert!(!profile.libraries.iter().any(|name| name.contains("lwjgl-platform") || name.contains("osx-only")));
+
+        let vars = HashMap::from([
+            ("auth_player_name", "Steve".to_string()),
+            ("version_name", profile.id.clone()),
+            ("user_properties", "{}".to_string()),
+        ]);
+        let args = profile.resolve_args(&vars);
+        assert_eq!(&args[..4], ["--username", "Steve", "--version", "1.7.10-Forge10.13.4.1614-1.7.10"]);
+        assert!(args.windows(2).any(|pair| pair == ["--userProperties", "{}"]));
+        assert!(args.windows(2).any(|pair| pair == ["--tweakClass", "cpw.mods.fml.common.launcher.FMLTweaker"]));
+    }
+
+    #[test]
+    fn forge_1_12_2_keeps_its_own_asm_and_passes_its_tweaker_once() {
+        let profile = forge_profile(FORGE_1_12_2, vanilla("1.12.2", "--username ${auth_player_name}"));
+
+        assert_eq!(profile.main_class, LAUNCHWRAPPER_MAIN_CLASS);
+        assert_eq!(profile.tweak_classes(), vec!["net.minecraftforge.fml.common.launcher.FMLTweaker"]);
+        assert_eq!(profile.client_jar, "1.12.2");
+        assert!(profile.libraries.contains(&"org.ow2.asm:asm-debug-all:5.2".to_string()));
+        assert!(!profile.libraries.iter().any(|name| name.starts_with("org.ow2.asm:asm-all")));
+
+        assert_eq!(profile.tweak_args(&[]), vec!["--tweakClass", "net.minecraftforge.fml.common.launcher.FMLTweaker"]);
+        let custom = vec!["--tweakClass=net.minecraftforge.fml.common.launcher.FMLTweaker".to_string()];
+        assert!(profile.tweak_args(&custom).is_empty());
+
+        let classpath = profile.classpath(Path::new("/libraries"), Path::new("/versions"));
+        assert!(classpath.contains(&PathBuf::from("/libraries/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar")));
+        assert_eq!(classpath.last().unwrap(), &PathBuf::from("/versions/1.12.2/1.12.2.jar"));
+    }
+
+    #[test]
+    fn launchwrapper_and_asm_are_added_when_a_profile_leaves_them_out() {
+        let mut stripped: Value = serde_json::from_str(FORGE_1_7_10).unwrap();
+        stripped["libraries"].as_array_mut().unwrap()
+            .retain(|lib| !lib["name"].as_str().unwrap().contains("launchwrapper") && !lib["name"].as_str().unwrap().contains("asm"));
+
+        let profile = LaunchProfile::merge(&[stripped, vanilla("1.7.10", "")]).unwrap();
+        assert_eq!(&profile.libraries[..2], ["net.minecraft:launchwrapper:1.12", "org.ow2.asm:asm-all:5.0.3"]);
+
+        let vanilla_profile = LaunchProfile::merge(&[vanilla("1.7.10", "--username ${auth_player_name}")]).unwrap();
+        assert!(!vanilla_profile.uses_launchwrapper());
+        assert!(!vanilla_profile.libraries.iter().any(|name| name.contains("launchwrapper")));
+    }
+
+    #[test]
+    fn modern_arguments_are_appended_after_the_parent() {
+        let fabric = json!({
+            "id": "fabric-loader-0.15.0-1.20.1",
+            "inheritsFrom": "1.20.1",
+            "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+            "arguments": { "game": [] }
+        });
+        let parent: Value = serde_json::from_str(include_str!("fixtures/version_1_20_1.json")).unwrap();
+        let profile = LaunchProfile::merge(&[fabric, parent]).unwrap();
+
+        assert_eq!(profile.main_class, "net.fabricmc.loader.impl.launch.knot.KnotClient");
+        assert!(!profile.legacy_arguments);
+        assert_eq!(&profile.game_args[..2], ["--username", "${auth_player_name}"]);
+        assert!(profile.tweak_classes().is_empty());
+    }
+
+    #[test]
+    fn placeholders_and_library_paths() {
+        let vars = HashMap::from([("game_directory", "/games/a".to_string())]);
+        assert_eq!(substitute("${game_directory}/mods", &vars), "/games/a/mods");
+        assert_eq!(substitute("${unknown} ${game_directory}", &vars), "${unknown} /games/a");
+        assert_eq!(substitute("${broken", &vars), "${broken");
+
+        assert_eq!(library_path("org.ow2.asm:asm-all:5.0.3").unwrap(), PathBuf::from("org/ow2/asm/asm-all/5.0.3/asm-all-5.0.3.jar"));
+        assert_eq!(library_path("lzma:lzma:0.0.1").unwrap(), PathBuf::from("lzma/lzma/0.0.1/lzma-0.0.1.jar"));
+        assert_eq!(library_path("a.b:c:1:natives-linux").unwrap(), PathBuf::from("a/b/c/1/c-1-natives-linux.jar"));
+        assert_eq!(library_path("bad"), None);
+    }
+}
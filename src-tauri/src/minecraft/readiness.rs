@@ -0,0 +1,135 @@
+//! Whether an instance has the game files it needs to launch
+//!
+//! Checked for every instance when the list loads, so results are cached and
+//! only recomputed when one of the directories involved changes.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Marker files the loader installer leaves in the mods folder
+const LOADER_MARKERS: &[(&str, &str)] = &[
+    (".forge_installed", "Forge"),
+    (".fabric_installed", "Fabric"),
+    (".quilt_installed", "Quilt"),
+    (".neoforge_installed", "NeoForge"),
+];
+
+/// Modification times of every directory the check looks at
+#[derive(Debug, Clone, PartialEq)]
+struct DirStamps {
+    game_dir: Option<SystemTime>,
+    version_dir: Option<SystemTime>,
+    mods_dir: Option<SystemTime>,
+}
+
+struct CachedCheck {
+    version: String,
+    stamps: DirStamps,
+    result: Result<(), String>,
+}
+
+static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedCheck>>> = OnceLock::new();
+
+fn cache() -> &'static Mutex<HashMap<PathBuf, CachedCheck>> {
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Ok when the instance can launch, otherwise why it can't
+pub fn check(game_dir: &Path, version: &str) -> Result<(), String> {
+    let stamps = dir_stamps(game_dir, version);
+
+    if let Some(cached) = cache().lock().unwrap().get(game_dir) {
+        if cached.version == version && cached.stamps == stamps {
+            return cached.result.clone();
+        }
+    }
+
+    let result = check_files(game_dir, version);
+    cache().lock().unwrap().insert(game_dir.to_path_buf(), CachedCheck {
+        version: version.to_string(),
+        stamps,
+        result: result.clone(),
+    });
+    result
+}
+
+/// `(ready, not_ready_reason)` as reported on an instance
+pub fn status(game_dir: &Path, version: &str) -> (bool, Option<String>) {
+    match check(game_dir, version) {
+        Ok(()) => (true, None),
+        Err(reason) => (false, Some(reason)),
+    }
+}
+
+/// Forget the cached result, e.g. after files were installed
+pub fn invalidate(game_dir: &Path) {
+    cache().lock().unwrap().remove(game_dir);
+}
+
+fn dir_stamps(game_dir: &Path, version: &str) -> DirStamps {
+    let modified = |path: PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    DirStamps {
+        game_dir: modified(game_dir.to_path_buf()),
+        version_dir: modified(game_dir.join("versions").join(version)),
+        mods_dir: modified(game_dir.join("mods")),
+    }
+}
+
+/// Mod loaders whose installer marker is in the mods folder
+pub fn installed_loaders(game_dir: &Path) -> Vec<&'static str> {
+    let mods_dir = game_dir.join("mods");
+    LOADER_MARKERS.iter()
+        .filter(|(marker, _)| mods_dir.join(marker).is_file())
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+fn check_files(game_dir: &Path, version: &str) -> Result<(), String> {
+    if !game_dir.is_dir() {
+        return Err("Instance folder is missing".to_string());
+    }
+
+    let version_dir = game_dir.join("versions").join(version);
+    if !version_dir.join(format!("{}.json", version)).is_file() {
+        return Err(format!("Version information for Minecraft {} is not downloaded", version));
+    }
+    if !version_dir.join(format!("{}.jar", version)).is_file() {
+        return Err(format!("Minecraft {} is not downloaded", version));
+    }
+
+    let loaders = installed_loaders(game_dir);
+    if loaders.len() > 1 {
+        return Err(format!("Several mod loaders are installed: {}", loaders.join(", ")));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_missing_files_and_recovers_once_installed() {
+        let dir = std::env::temp_dir().join(format!("chai-ready-{}", uuid::Uuid::new_v4()));
+        let version_dir = dir.join("versions").join("1.20.1");
+        std::fs::create_dir_all(&version_dir).unwrap();
+
+        assert_eq!(check(&dir, "1.20.1"), Err("Version information for Minecraft 1.20.1 is not downloaded".to_string()));
+
+        std::fs::write(version_dir.join("1.20.1.json"), "{}").unwrap();
+        std::fs::write(version_dir.join("1.20.1.jar"), "").unwrap();
+        invalidate(&dir);
+        assert_eq!(check(&dir, "1.20.1"), Ok(()));
+
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::write(dir.join("mods").join(".forge_installed"), "forge-47.2.0").unwrap();
+        std::fs::write(dir.join("mods").join(".fabric_installed"), "fabric-0.15.11").unwrap();
+        invalidate(&dir);
+        assert_eq!(check(&dir, "1.20.1"), Err("Several mod loaders are installed: Forge, Fabric".to_string()));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+//! Repairing instance paths after the user folder or drive letter changed
+//!
+//! Game directories are stored as absolute paths, so renaming the OS user or
+//! moving a drive to another letter leaves every instance pointing at a folder
+//! that no longer exists. A missing folder is looked up by its name under the
+//! current `instances_dir`, next to the other instances that still resolve and
+//! under `instance_search_roots`. On Windows the same path on every other drive
+//! letter is tried as well. A candidate whose instance.json names another
+//! instance id is never used.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use crate::storage::{InstanceMetadata, LauncherSettings, StorageState};
+
+/// How the new location of an instance was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairMatch {
+    /// instance.json in the folder names this instance
+    InstanceId,
+    /// Same folder name under a search root
+    FolderName,
+    /// Same path on another drive letter
+    DriveLetter,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathRepair {
+    pub instance_id: String,
+    pub name: String,
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub matched_by: RepairMatch,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingInstance {
+    pub instance_id: String,
+    pub name: String,
+    pub game_dir: PathBuf,
+}
+
+/// Proposed old to new mapping, nothing is changed until it is applied
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PathRepairPlan {
+    pub repairs: Vec<PathRepair>,
+    /// Missing instances with no usable candidate
+    pub unresolved: Vec<MissingInstance>,
+}
+
+/// Roots searched for missing instance folders, most likely first
+pub fn search_roots(settings: &LauncherSettings, instances: &[InstanceMetadata]) -> Vec<PathBuf> {
+    let mut roots = vec![settings.instances_dir.clone()];
+    roots.extend(instances.iter()
+        .filter(|instance| instance.game_dir.is_dir())
+        .filter_map(|instance| instance.game_dir.parent().map(Path::to_path_buf)));
+    roots.extend(settings.instance_search_roots.iter().cloned());
+
+    let mut seen = HashSet::new();
+    roots.retain(|root| seen.insert(root.clone()));
+    roots
+}
+
+/// Work out where each instance with a missing game directory went
+pub fn plan(instances: &[InstanceMetadata], roots: &[PathBuf]) -> PathRepairPlan {
+    // Folders already used by an instance can't be handed to another one
+    let mut taken: HashSet<PathBuf> = instances.iter()
+        .filter(|instance| instance.game_dir.is_dir())
+        .map(|instance| instance.game_dir.clone())
+        .collect();
+
+    let mut plan = PathRepairPlan::default();
+    for instance in instances {
+        if instance.game_dir.as_os_str().is_empty() || instance.game_dir.exists() {
+            continue;
+        }
+
+        match locate(instance, roots, &taken) {
+            Some((new_path, matched_by)) => {
+                taken.insert(new_path.clone());
+                plan.repairs.push(PathRepair {
+                    instance_id: instance.id.clone(),
+                    name: instance.name.clone(),
+                    old_path: instance.game_dir.clone(),
+                    new_path,
+                    matched_by,
+                });
+            }
+            None => plan.unresolved.push(MissingInstance {
+                instance_id: instance.id.clone(),
+                name: instance.name.clone(),
+                game_dir: instance.game_dir.clone(),
+            }),
+        }
+    }
+    plan
+}
+
+fn locate(instance: &InstanceMetadata, roots: &[PathBuf], taken: &HashSet<PathBuf>) -> Option<(PathBuf, RepairMatch)> {
+    let mut candidates: Vec<(PathBuf, RepairMatch)> = drive_letter_variants(&instance.game_dir)
+        .into_iter()
+        .map(|path| (path, RepairMatch::DriveLetter))
+        .collect();
+    if let Some(dir_name) = instance.game_dir.file_name() {
+        candidates.extend(roots.iter().map(|root| (root.join(dir_name), RepairMatch::FolderName)));
+    }
+    candidates.retain(|(path, _)| !taken.contains(path) && looks_like_game_dir(path));
+
+    let mut fallback = None;
+    for (path, matched_by) in candidates {
+        match stored_instance_id(&path) {
+            Some(id) if id == instance.id => return Some((path, RepairMatch::InstanceId)),
+            Some(_) => {}
+            None => {
+                if fallback.is_none() {
+                    fallback = Some((path, matched_by));
+                }
+            }
+        }
+    }
+    fallback
+}
+
+/// The same path on every other drive letter, empty for non-drive paths
+fn drive_letter_variants(path: &Path) -> Vec<PathBuf> {
+    let text = path.to_string_lossy();
+    let bytes = text.as_bytes();
+    if bytes.len() < 3 || !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' || !matches!(bytes[2], b'\\' | b'/') {
+        return Vec::new();
+    }
+
+    let current = bytes[0].to_ascii_uppercase();
+    (b'A'..=b'Z')
+        .filter(|letter| *letter != current)
+        .map(|letter| PathBuf::from(format!("{}{}", letter as char, &text[1..])))
+        .collect()
+}
+
+fn looks_like_game_dir(path: &Path) -> bool {
+    path.is_dir()
+        && ["versions", "options.txt", "mods", "saves", "instance.json"]
+            .iter()
+            .any(|entry| path.join(entry).exists())
+}
+
+/// Instance id recorded in the folder's instance.json, if any
+fn stored_instance_id(dir: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(dir.join("instance.json")).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    ["id", "instance_id"]
+        .iter()
+        .find_map(|key| value.get(*key).and_then(|id| id.as_str()).map(str::to_string))
+}
+
+/// Tell the frontend about instances that can be repaired
+pub async fn check_at_startup(app_handle: &AppHandle, storage: &StorageState) {
+    let plan = {
+        let storage = storage.read().await;
+        let instances: Vec<InstanceMetadata> = storage.get_all_instances().into_iter().cloned().collect();
+        plan(&instances, &search_roots(storage.get_settings(), &instances))
+    };
+
+    if !plan.unresolved.is_empty() {
+        eprintln!("⚠️  {} instances point at missing folders", plan.unresolved.len());
+    }
+    if !plan.repairs.is_empty() {
+        println!("🔧 Found new locations for {} instances with broken paths", plan.repairs.len());
+        let _ = app_handle.emit("instance_paths_broken", &plan);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_instance;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-relocate-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn game_dir(path: &Path, id: Option<&str>) {
+        std::fs::create_dir_all(path.join("saves")).unwrap();
+        if let Some(id) = id {
+            std::fs::write(path.join("instance.json"), format!(r#"{{"id":"{}"}}"#, id)).unwrap();
+        }
+    }
+
+    #[test]
+    fn finds_moved_folders_by_name_and_id() {
+        let dir = temp_dir();
+        let old_root = dir.join("Users").join("old").join("instances");
+        let new_root = dir.join("Users").join("new").join("instances");
+        game_dir(&new_root.join("Survival"), None);
+        game_dir(&new_root.join("Modded"), Some("modded"));
+        game_dir(&new_root.join("Stolen"), Some("someone-else"));
+
+        let instances = vec![
+            test_instance("survival", old_root.join("Survival")),
+            test_instance("modded", old_root.join("Modded")),
+            test_instance("stolen", old_root.join("Stolen")),
+            test_instance("gone", old_root.join("Gone")),
+        ];
+        let plan = plan(&instances, std::slice::from_ref(&new_root));
+
+        assert_eq!(plan.repairs.len(), 2);
+        assert_eq!(plan.repairs[0].new_path, new_root.join("Survival"));
+        assert_eq!(plan.repairs[0].matched_by, RepairMatch::FolderName);
+        assert_eq!(plan.repairs[1].matched_by, RepairMatch::InstanceId);
+
+        let unresolved: Vec<&str> = plan.unresolved.iter().map(|m| m.instance_id.as_str()).collect();
+        assert_eq!(unresolved, vec!["stolen", "gone"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn never_reuses_a_folder() {
+        let dir = temp_dir();
+        let root = dir.join("instances");
+        game_dir(&root.join("World"), None);
+
+        let instances = vec![
+            test_instance("live", root.join("World")),
+            test_instance("broken", dir.join("old").join("World")),
+        ];
+        let plan = plan(&instances, &[root]);
+        assert!(plan.repairs.is_empty());
+        assert_eq!(plan.unresolved.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn swaps_drive_letters() {
+        let variants = drive_letter_variants(Path::new(r"D:\Games\Chai\instances\Survival"));
+        assert_eq!(variants.len(), 25);
+        assert!(variants.contains(&PathBuf::from(r"E:\Games\Chai\instances\Survival")));
+        assert!(!variants.contains(&PathBuf::from(r"D:\Games\Chai\instances\Survival")));
+        assert!(drive_letter_variants(Path::new("/home/user/instances")).is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+//! Joining a multiplayer server straight from launch
+//!
+//! 1.20 and newer take `--quickPlayMultiplayer host:port`, older versions
+//! `--server` and `--port`. Servers an instance joined are kept with it so the
+//! UI can offer them again.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::worlds::supports_quick_play;
+
+pub const DEFAULT_PORT: u16 = 25565;
+
+/// Servers remembered per instance
+pub const MAX_RECENT_SERVERS: usize = 10;
+
+/// A validated `host[:port]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerAddress {
+    pub host: String,
+    pub port: u16,
+}
+
+impl ServerAddress {
+    /// Parse `host`, `host:port`, `[ipv6]` or `[ipv6]:port`
+    pub fn parse(address: &str) -> Result<Self, String> {
+        let address = address.trim();
+        if address.is_empty() {
+            return Err("Server address can't be empty".to_string());
+        }
+
+        let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+            let (host, rest) = rest.split_once(']')
+                .ok_or_else(|| format!("Invalid server address '{}'", address))?;
+            if host.parse::<std::net::Ipv6Addr>().is_err() {
+                return Err(format!("Invalid IPv6 address '{}'", host));
+            }
+            match rest {
+                "" => (host, None),
+                _ => (host, Some(rest.strip_prefix(':').ok_or_else(|| format!("Invalid server address '{}'", address))?)),
+            }
+        } else {
+            match address.split_once(':') {
+                Some((_, port)) if port.contains(':') => {
+                    return Err(format!("Put IPv6 addresses in brackets, like [{}]", address));
+                }
+                Some((host, port)) => (host, Some(port)),
+                None => (address, None),
+            }
+        };
+
+        let port = match port {
+            Some(port) => match port.parse::<u16>() {
+                Ok(port) if port > 0 => port,
+                _ => return Err(format!("Invalid port '{}'", port)),
+            },
+            None => DEFAULT_PORT,
+        };
+
+        let is_ipv6 = address.starts_with('[');
+        let valid_host = is_ipv6 || (host.len() <= 253
+            && !host.starts_with(['.', '-'])
+            && !host.ends_with('-')
+            && !host.contains("..")
+            && host.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')));
+        if host.is_empty() || !valid_host {
+            return Err(format!("Invalid server host '{}'", host));
+        }
+
+        Ok(Self { host: host.to_ascii_lowercase(), port })
+    }
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// A server an instance launched into
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentServer {
+    /// `host:port`
+    pub address: String,
+    /// RFC 3339
+    pub last_joined: String,
+}
+
+/// Game arguments that join `address` once the game has started
+pub fn join_args(version: &str, address: &ServerAddress) -> Vec<String> {
+    if supports_quick_play(version) {
+        vec!["--quickPlayMultiplayer".to_string(), address.to_string()]
+    } else {
+        vec![
+            "--server".to_string(), address.host.clone(),
+            "--port".to_string(), address.port.to_string(),
+        ]
+    }
+}
+
+/// Move `address` to the front of `recent`, dropping the oldest past the limit
+pub fn remember(recent: &mut Vec<RecentServer>, address: &ServerAddress, joined_at: &str) {
+    let address = address.to_string();
+    recent.retain(|server| server.address != address);
+    recent.insert(0, RecentServer { address, last_joined: joined_at.to_string() });
+    recent.truncate(MAX_RECENT_SERVERS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses_get_the_default_port_and_bad_ones_are_rejected() {
+        assert_eq!(ServerAddress::parse(" Play.Example.com ").unwrap(), ServerAddress { host: "play.example.com".to_string(), port: 25565 });
+        assert_eq!(ServerAddress::parse("127.0.0.1:25570").unwrap().port, 25570);
+        assert_eq!(ServerAddress::parse("[::1]:25570").unwrap().to_string(), "[::1]:25570");
+        assert_eq!(ServerAddress::parse("[::1]").unwrap().port, 25565);
+
+        for bad in ["", "host:", "host:0", "host:70000", "::1", "bad host", "-host", "a..b", "[nope]:1", "host:25565/x"] {
+            assert!(ServerAddress::parse(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn join_arguments_depend_on_the_version() {
+        let address = ServerAddress::parse("mc.example.com:25570").unwrap();
+        assert_eq!(join_args("1.20.1", &address), vec!["--quickPlayMultiplayer", "mc.example.com:25570"]);
+        assert_eq!(join_args("1.12.2", &address), vec!["--server", "mc.example.com", "--port", "25570"]);
+    }
+
+    #[test]
+    fn rejoining_moves_a_server_to_the_front() {
+        let mut recent = Vec::new();
+        for i in 0..12 {
+            remember(&mut recent, &ServerAddress::parse(&format!("server{}", i)).unwrap(), "2024-05-01T12:00:00+00:00");
+        }
+        assert_eq!(recent.len(), MAX_RECENT_SERVERS);
+        assert_eq!(recent[0].address, "server11:25565");
+
+        remember(&mut recent, &ServerAddress::parse("server5").unwrap(), "2024-05-02T12:00:00+00:00");
+        assert_eq!(recent.len(), MAX_RECENT_SERVERS);
+        assert_eq!(recent[0].address, "server5:25565");
+        assert_eq!(recent.iter().filter(|s| s.address == "server5:25565").count(), 1);
+    }
+}
@@ -0,0 +1,398 @@
+//! Running games and the play time they add up to
+//!
+//! Every launched game process is kept here until it exits. Its run time is then
+//! added to the instance, whether the game quit normally or crashed. A crash report
+//! written during the session is sent to the frontend as `crash_detected`. A hung
+//! game can be killed, which goes through the same bookkeeping.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, Utc};
+use tauri::{AppHandle, Emitter};
+
+use super::crashes::{CrashDetected, SessionOutcome};
+
+pub type PlaySessionsState = Arc<PlaySessions>;
+
+/// How often running games are checked for having exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A crash report this close to the exit means the game crashed, even with exit code 0
+const CRASH_REPORT_WINDOW: Duration = Duration::from_secs(60);
+
+/// How long a killed game gets to stop on its own before it is forced to
+#[cfg(unix)]
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+struct PlaySession {
+    instance_id: String,
+    game_dir: PathBuf,
+    process: Arc<Mutex<Child>>,
+    started_at: DateTime<Utc>,
+    /// Stopped from the launcher, so the exit says nothing about stability
+    killed: bool,
+}
+
+/// A game the launcher started that is still running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunningInstance {
+    pub instance_id: String,
+    pub pid: u32,
+    pub started_at: String,
+    pub runtime_seconds: u64,
+}
+
+/// Payload of `game_exited`
+#[derive(Debug, Clone, Serialize)]
+pub struct GameExited {
+    pub instance_id: String,
+    /// None when the process was killed by a signal or couldn't be checked
+    pub exit_code: Option<i32>,
+    pub runtime_seconds: u64,
+    pub crashed: bool,
+    /// Stopped with `kill_instance`
+    pub killed: bool,
+}
+
+/// Game processes started by the launcher, keyed by session id
+#[derive(Default)]
+pub struct PlaySessions {
+    running: Mutex<HashMap<u64, PlaySession>>,
+    next_id: AtomicU64,
+}
+
+/// Play time of one instance, in seconds
+#[derive(Debug, Clone, Serialize)]
+pub struct InstancePlaytime {
+    pub instance_id: String,
+    pub name: String,
+    pub total_play_time: u64,
+    pub last_played: Option<String>,
+    pub running: bool,
+}
+
+/// Play time of every instance, most played first
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaytimeStats {
+    pub instances: Vec<InstancePlaytime>,
+    pub total_play_time: u64,
+}
+
+impl PlaySessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a launched game until it exits, then record its play time
+    pub fn start(self: &Arc<Self>, app_handle: &AppHandle, instance_id: &str, game_dir: PathBuf, process: Child) {
+        let session_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let process = Arc::new(Mutex::new(process));
+        self.running.lock().unwrap().insert(session_id, PlaySession {
+            instance_id: instance_id.to_string(),
+            game_dir,
+            process: process.clone(),
+            started_at: Utc::now(),
+            killed: false,
+        });
+
+        let sessions = self.clone();
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let status = wait_for_exit(&process).await;
+            sessions.finish(&app_handle, session_id, status).await;
+        });
+    }
+
+    /// Whether a game launched from this instance is still running
+    pub fn is_running(&self, instance_id: &str) -> bool {
+        self.running.lock().unwrap().values().any(|session| {
+            // The session is only removed on the next poll after the game exits
+            session.instance_id == instance_id
+                && matches!(session.process.lock().unwrap().try_wait(), Ok(None))
+        })
+    }
+
+    /// When the newest game still running from this instance was launched
+    pub fn running_since(&self, instance_id: &str) -> Option<DateTime<Utc>> {
+        self.running.lock().unwrap().values()
+            .filter(|session| {
+                session.instance_id == instance_id
+                    && matches!(session.process.lock().unwrap().try_wait(), Ok(None))
+            })
+            .map(|session| session.started_at)
+            .max()
+    }
+
+    /// Stop every game running from this instance, returning how many were stopped
+    ///
+    /// Play time is recorded by the exit watcher as for any other exit.
+    pub async fn kill(&self, instance_id: &str) -> Result<usize, String> {
+        let processes: Vec<Arc<Mutex<Child>>> = self.running.lock().unwrap().values_mut()
+            .filter(|session| session.instance_id == instance_id)
+            .map(|session| {
+                session.killed = true;
+                session.process.clone()
+            })
+            .collect();
+
+        let mut killed = 0;
+        for process in processes {
+            if has_exited(&process) {
+                continue;
+            }
+            terminate_tree(&process).await?;
+            killed += 1;
+        }
+        if killed > 0 {
+            println!("🛑 Killed {} game process(es) of instance {}", killed, instance_id);
+        }
+        Ok(killed)
+    }
+
+    /// Games that are still running, oldest first
+    pub fn running_instances(&self) -> Vec<RunningInstance> {
+        let now = Utc::now();
+        let mut running: Vec<RunningInstance> = self.running.lock().unwrap().values()
+            .filter_map(|session| {
+                let mut process = session.process.lock().unwrap();
+                matches!(process.try_wait(), Ok(None)).then(|| RunningInstance {
+                    instance_id: session.instance_id.clone(),
+                    pid: process.id(),
+                    started_at: session.started_at.to_rfc3339(),
+                    runtime_seconds: session_seconds(session.started_at, now),
+                })
+            })
+            .collect();
+        running.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        running
+    }
+
+    async fn finish(&self, app_handle: &AppHandle, session_id: u64, status: Option<ExitStatus>) {
+        let Some(session) = self.running.lock().unwrap().remove(&session_id) else {
+            return;
+        };
+        let ended_at = Utc::now();
+        let seconds = session_seconds(session.started_at, ended_at);
+
+        let since = SystemTime::from(session.started_at).max(SystemTime::now() - CRASH_REPORT_WINDOW);
+        let exited_cleanly = status.is_some_and(|status| status.success());
+        let (outcome, signature) = super::crashes::analyze_exit(&session.game_dir, since, exited_cleanly).await;
+        let crashed = outcome != SessionOutcome::Clean && !session.killed;
+
+        match status {
+            _ if session.killed => {
+                println!("🛑 Instance {} was killed after {}s", session.instance_id, seconds);
+            }
+            Some(_) if !crashed => {
+                println!("🎮 Instance {} played for {}s", session.instance_id, seconds);
+            }
+            Some(status) => {
+                println!("💥 Instance {} crashed ({}) after {}s", session.instance_id, status, seconds);
+            }
+            None => println!("⚠️  Lost track of instance {} after {}s", session.instance_id, seconds),
+        }
+
+        let _ = app_handle.emit("game_exited", GameExited {
+            instance_id: session.instance_id.clone(),
+            exit_code: status.and_then(|status| status.code()),
+            runtime_seconds: seconds,
+            crashed,
+            killed: session.killed,
+        });
+
+        if crashed && outcome == SessionOutcome::Crash {
+            let report = match super::logs::newest_crash_report_since(&session.game_dir, since).await {
+                Some(path) => super::crashes::read_crash_report(&path).await,
+                None => None,
+            };
+            if let Some(report) = report {
+                println!("📄 Crash report for instance {}: {}", session.instance_id, report.file_name);
+                let _ = app_handle.emit("crash_detected", CrashDetected {
+                    instance_id: session.instance_id.clone(),
+                    report,
+                });
+            }
+        }
+
+        match crate::storage::shared().await {
+            Ok(storage) => {
+                let mut storage = storage.write().await;
+                let recorded = match storage.record_play_session(&session.instance_id, seconds, ended_at).await {
+                    Ok(_) if session.killed => Ok(()),
+                    Ok(_) => storage.record_session_outcome(&session.instance_id, outcome, signature, ended_at).await,
+                    Err(e) => Err(e),
+                };
+                drop(storage);
+                match recorded {
+                    Ok(_) => super::commands::instances::notify_instances_updated(app_handle),
+                    Err(e) => println!("⚠️  Failed to record play time for instance {}: {}", session.instance_id, e),
+                }
+            }
+            Err(e) => println!("⚠️  Failed to record play time for instance {}: {}", session.instance_id, e),
+        }
+
+        super::hooks::run_post_exit(&session.instance_id, app_handle).await;
+
+        // Apply the log cleanup policy now the game no longer writes logs
+        if let Err(e) = super::logs::clean_instance_logs(&session.instance_id).await {
+            println!("⚠️  Log cleanup failed for instance {}: {}", session.instance_id, e);
+        }
+        crate::mods::trash::purge_expired(&session.game_dir.join("mods")).await;
+    }
+
+    /// Play time of every registered instance
+    pub fn stats(&self, instances: &[&crate::storage::InstanceMetadata]) -> PlaytimeStats {
+        let mut instances: Vec<InstancePlaytime> = instances.iter()
+            .map(|instance| InstancePlaytime {
+                instance_id: instance.id.clone(),
+                name: instance.name.clone(),
+                total_play_time: instance.total_play_time,
+                last_played: instance.last_played.clone(),
+                running: self.is_running(&instance.id),
+            })
+            .collect();
+        instances.sort_by(|a, b| b.total_play_time.cmp(&a.total_play_time).then_with(|| a.name.cmp(&b.name)));
+
+        PlaytimeStats {
+            total_play_time: instances.iter().map(|instance| instance.total_play_time).sum(),
+            instances,
+        }
+    }
+}
+
+/// Wait for the process to exit without holding its lock, None if it can't be checked
+async fn wait_for_exit(process: &Mutex<Child>) -> Option<ExitStatus> {
+    loop {
+        match process.lock().unwrap().try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) => {}
+            Err(e) => {
+                println!("⚠️  Failed to check game process: {}", e);
+                return None;
+            }
+        }
+        tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+    }
+}
+
+fn has_exited(process: &Mutex<Child>) -> bool {
+    !matches!(process.lock().unwrap().try_wait(), Ok(None))
+}
+
+/// Force the game and the processes it started to stop
+#[cfg(windows)]
+async fn terminate_tree(process: &Mutex<Child>) -> Result<(), String> {
+    let pid = process.lock().unwrap().id();
+    let status = tokio::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .status()
+        .await
+        .map_err(|e| format!("Failed to run taskkill: {}", e))?;
+    // taskkill also fails when the game exited in the meantime
+    if !status.success() && !has_exited(process) {
+        return Err(format!("taskkill could not stop process {} ({})", pid, status));
+    }
+    Ok(())
+}
+
+/// Ask the game and its child processes to stop, then force them after `KILL_GRACE_PERIOD`
+#[cfg(unix)]
+async fn terminate_tree(process: &Mutex<Child>) -> Result<(), String> {
+    let pid = process.lock().unwrap().id();
+    signal_tree(pid, "TERM").await;
+
+    let deadline = std::time::Instant::now() + KILL_GRACE_PERIOD;
+    while std::time::Instant::now() < deadline {
+        if has_exited(process) {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    println!("⚠️  Process {} ignored SIGTERM, sending SIGKILL", pid);
+    signal_tree(pid, "KILL").await;
+    // Works even without a `kill` binary, the game itself is what matters most
+    let _ = process.lock().unwrap().kill();
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn signal_tree(pid: u32, signal: &str) {
+    let signal = format!("-{}", signal);
+    let pid = pid.to_string();
+    // Children first, they are reparented once the game is gone
+    let _ = tokio::process::Command::new("pkill").args([signal.as_str(), "-P", &pid]).status().await;
+    let _ = tokio::process::Command::new("kill").args([signal.as_str(), &pid]).status().await;
+}
+
+/// Whole seconds between start and end, 0 if the clock went backwards
+fn session_seconds(started_at: DateTime<Utc>, ended_at: DateTime<Utc>) -> u64 {
+    (ended_at - started_at).num_seconds().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_length_never_goes_negative() {
+        let start = Utc::now();
+        assert_eq!(session_seconds(start, start + chrono::Duration::seconds(125)), 125);
+        assert_eq!(session_seconds(start, start - chrono::Duration::seconds(5)), 0);
+    }
+
+    #[tokio::test]
+    async fn crashed_processes_are_reported_as_exited() {
+        #[cfg(windows)]
+        let child = std::process::Command::new("cmd").args(["/C", "exit 3"]).spawn().unwrap();
+        #[cfg(not(windows))]
+        let child = std::process::Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+
+        let status = wait_for_exit(&Mutex::new(child)).await.unwrap();
+        assert!(!status.success());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn killed_games_stop_and_are_marked() {
+        let child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let sessions = PlaySessions::new();
+        sessions.running.lock().unwrap().insert(0, PlaySession {
+            instance_id: "inst".to_string(),
+            game_dir: std::env::temp_dir(),
+            process: Arc::new(Mutex::new(child)),
+            started_at: Utc::now(),
+            killed: false,
+        });
+
+        assert_eq!(sessions.kill("other").await.unwrap(), 0);
+        assert_eq!(sessions.kill("inst").await.unwrap(), 1);
+        let running = sessions.running.lock().unwrap();
+        assert!(running[&0].killed);
+        assert!(has_exited(&running[&0].process));
+    }
+
+    #[tokio::test]
+    async fn new_crash_reports_are_found() {
+        let dir = std::env::temp_dir().join(format!("chai-sessions-{}", uuid::Uuid::new_v4()));
+        let started = SystemTime::now() - Duration::from_secs(1);
+        assert_eq!(crate::minecraft::crashes::analyze_exit(&dir, started, true).await, (SessionOutcome::Clean, None));
+        assert_eq!(crate::minecraft::crashes::analyze_exit(&dir, started, false).await, (SessionOutcome::Crash, None));
+
+        std::fs::create_dir_all(dir.join("crash-reports")).unwrap();
+        std::fs::write(dir.join("crash-reports").join("crash-2024-01-01_12.00.00-client.txt"), "---- Minecraft Crash Report ----").unwrap();
+        assert_eq!(crate::minecraft::crashes::analyze_exit(&dir, started, true).await.0, SessionOutcome::Crash);
+        let later = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(crate::minecraft::crashes::analyze_exit(&dir, later, true).await.0, SessionOutcome::Clean);
+
+        std::fs::write(dir.join("hs_err_pid4242.log"), "#  SIGSEGV (0xb) at pc=0x0000, pid=4242").unwrap();
+        assert_eq!(crate::minecraft::crashes::analyze_exit(&dir, started, false).await.0, SessionOutcome::JvmCrash);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,55 @@
+//! Timestamp conversions for instance data
+//!
+//! Storage keeps RFC3339 strings while the frontend gets epoch milliseconds, which
+//! sort and compare without re-parsing. Older clients may still send strings, so
+//! deserialization accepts both.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Parse a stored timestamp into epoch milliseconds
+pub fn to_epoch_millis(value: &str) -> Option<i64> {
+    if let Ok(millis) = value.trim().parse::<i64>() {
+        return Some(millis);
+    }
+
+    DateTime::parse_from_rfc3339(value.trim()).ok()
+        .map(|date| date.timestamp_millis())
+}
+
+/// Format epoch milliseconds for storage
+pub fn to_rfc3339(millis: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+/// Serde helpers for `Option<i64>` epoch milliseconds that also read RFC3339 strings
+pub mod option_epoch_millis {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Millis(i64),
+        Float(f64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(value: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(millis) => serializer.serialize_i64(*millis),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+        Ok(match Option::<Timestamp>::deserialize(deserializer)? {
+            Some(Timestamp::Millis(millis)) => Some(millis),
+            Some(Timestamp::Float(millis)) => Some(millis as i64),
+            // Unparseable strings are treated as missing rather than failing the whole instance
+            Some(Timestamp::Text(text)) => to_epoch_millis(&text),
+            None => None,
+        })
+    }
+}
@@ -5,20 +5,34 @@
 
 use serde::{Deserialize, Serialize};
 
-/// Minecraft version information
+/// An entry of Mojang's version manifest
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VersionInfo {
     pub id: String,
+    /// `release`, `snapshot`, `old_beta` or `old_alpha`
     pub r#type: String,
+    pub url: String,
+    /// SHA1 of the version JSON, only in the v2 manifest
+    #[serde(default)]
+    pub sha1: Option<String>,
+    pub time: String,
+    #[serde(rename = "releaseTime")]
     pub release_time: String,
-    pub compatible_java_versions: Vec<u32>,
+    #[serde(rename = "isLatestRelease", default)]
+    pub is_latest_release: bool,
+    /// Only set on a snapshot, when the newest version is a release no entry has it
+    #[serde(rename = "isLatestSnapshot", default)]
+    pub is_latest_snapshot: bool,
 }
 
-/// Version manifest structure
+/// Mojang's version manifest, `version_manifest_v2.json`
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VersionManifest {
     pub latest: LatestVersions,
     pub versions: Vec<VersionInfo>,
+    /// Seconds since the manifest was last fetched from Mojang, 0 when just now
+    #[serde(rename = "cacheAgeSecs", default)]
+    pub cache_age_secs: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -27,20 +41,126 @@ pub struct LatestVersions {
     pub snapshot: String,
 }
 
+/// Version types to list, everything unless turned off
+#[derive(Debug, Clone, Copy)]
+pub struct VersionFilter {
+    pub snapshots: bool,
+    pub old_beta: bool,
+    pub old_alpha: bool,
+}
+
+impl Default for VersionFilter {
+    fn default() -> Self {
+        Self { snapshots: true, old_beta: true, old_alpha: true }
+    }
+}
+
+impl VersionFilter {
+    fn allows(&self, version_type: &str) -> bool {
+        match version_type {
+            "snapshot" => self.snapshots,
+            "old_beta" => self.old_beta,
+            "old_alpha" => self.old_alpha,
+            _ => true,
+        }
+    }
+}
+
+impl VersionManifest {
+    /// Flag the entries `latest` points at
+    pub fn mark_latest(&mut self) {
+        for version in &mut self.versions {
+            version.is_latest_release = version.r#type == "release" && version.id == self.latest.release;
+            version.is_latest_snapshot = version.r#type == "snapshot" && version.id == self.latest.snapshot;
+        }
+    }
+
+    /// Drop the versions whose type `filter` leaves out
+    pub fn retain(&mut self, filter: VersionFilter) {
+        self.versions.retain(|version| filter.allows(&version.r#type));
+    }
+}
+
+/// Java version an instance needs
+///
+/// The `javaVersion` of the instance's version JSON wins, so April Fools versions
+/// and loader profiles get what Mojang ships them with. The value is cached with
+/// the instance until its version changes. Without a version JSON the version
+/// string is mapped by `get_required_java_version`.
+pub async fn required_java_for_instance(instance_id: &str, game_dir: &std::path::Path, version: &str) -> u32 {
+    let storage = crate::storage::shared().await.ok();
+    if let Some(storage) = &storage {
+        let cached = storage.read().await.get_instance(instance_id)
+            .filter(|stored| stored.version == version)
+            .and_then(|stored| stored.resolved_java_version);
+        if let Some(major) = cached {
+            return major;
+        }
+    }
+
+    let chain = super::profile::load_chain(game_dir, version).await;
+    let Some(major) = java_version_from_chain(&chain) else {
+        return get_required_java_version(version);
+    };
+    println!("📋 Version JSON of {} asks for Java {}", version, major);
+    if let Some(storage) = &storage {
+        // External instances aren't stored, they read the JSON every launch
+        let _ = storage.write().await.record_java_version(instance_id, version, major).await;
+    }
+    major
+}
+
+/// Java version asked for by the most specific version JSON of a chain that sets one
+pub fn java_version_from_chain(chain: &[serde_json::Value]) -> Option<u32> {
+    let major = chain.iter()
+        .find_map(|json| json.pointer("/javaVersion/majorVersion")?.as_u64())?;
+    match u32::try_from(major).ok()? {
+        0 => None,
+        // 1.17 asks for Java 16, which the launcher doesn't install, 17 runs it
+        16 => Some(17),
+        major => Some(major),
+    }
+}
+
 /// Get required Java version for a Minecraft version
+///
+/// Snapshots like `24w14a` are placed by their year and week, pre-releases and
+/// release candidates like `1.20.5-pre1` by the release they lead up to.
 pub fn get_required_java_version(version: &str) -> u32 {
-    // Use version parsing logic to determine Java requirements
-    if version_compare(version, "1.21") >= 0 {
-        21 // Java 21+ required for 1.21+
-    } else if version_compare(version, "1.17") >= 0 {
+    if let Some(week) = snapshot_week(version) {
+        return if week >= (24, 14) {
+            21 // 24w14a moved to Java 21 ahead of 1.20.5
+        } else if week >= (21, 19) {
+            17 // 21w19a moved to Java 16, the launcher runs 1.17 snapshots on 17
+        } else {
+            8
+        };
+    }
+
+    let release = version.split(['-', ' ']).next().unwrap_or(version);
+    if !release.starts_with(|c: char| c.is_ascii_digit()) {
+        8 // Alpha and beta ids like b1.7.3
+    } else if version_compare(release, "1.20.5") >= 0 {
+        21 // Java 21+ required for 1.20.5+
+    } else if version_compare(release, "1.17") >= 0 {
         17 // Java 17+ required for 1.17+
-    } else if version_compare(version, "1.12") >= 0 {
-        8 // Java 8+ required for 1.12+
     } else {
         8 // Java 8 for older versions
     }
 }
 
+/// Year and week of a snapshot id like `24w14a`
+fn snapshot_week(version: &str) -> Option<(u32, u32)> {
+    let (year, rest) = version.split_once('w')?;
+    let week = rest.get(..2)?;
+    let suffix = &rest[2..];
+    let two_digits = |part: &str| part.len() == 2 && part.chars().all(|c| c.is_ascii_digit());
+    if !two_digits(year) || !two_digits(week) || suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    Some((year.parse().ok()?, week.parse().ok()?))
+}
+
 /// Get ChaiLauncher's own Java executable path for a version
 /// This maintains ChaiLauncher's independent Java management
 pub async fn get_java_for_version(java_version: u32) -> Result<String, String> {
@@ -189,4 +309,107 @@ pub async fn load_version_manifest(
             Err(format!("Failed to read version manifest: {}", e))
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_releases_to_java_versions() {
+        assert_eq!(get_required_java_version("1.12.2"), 8);
+        assert_eq!(get_required_java_version("1.16.5"), 8);
+        assert_eq!(get_required_java_version("1.17"), 17);
+        assert_eq!(get_required_java_version("1.20.4"), 17);
+        assert_eq!(get_required_java_version("1.20.5"), 21);
+        assert_eq!(get_required_java_version("1.21.1"), 21);
+    }
+
+    #[test]
+    fn maps_snapshots_and_pre_releases() {
+        assert_eq!(get_required_java_version("24w13a"), 17);
+        assert_eq!(get_required_java_version("24w14a"), 21);
+        assert_eq!(get_required_java_version("21w19a"), 17);
+        assert_eq!(get_required_java_version("20w45a"), 8);
+        assert_eq!(get_required_java_version("1.20.5-pre1"), 21);
+        assert_eq!(get_required_java_version("1.20.5-rc1"), 21);
+        assert_eq!(get_required_java_version("1.17 Pre-release 1"), 17);
+        assert_eq!(get_required_java_version("b1.7.3"), 8);
+    }
+
+    const VERSION_MANIFEST: &str = include_str!("fixtures/version_manifest_v2.json");
+
+    fn manifest() -> VersionManifest {
+        let mut manifest: VersionManifest = serde_json::from_str(VERSION_MANIFEST).unwrap();
+        manifest.mark_latest();
+        manifest
+    }
+
+    fn ids(manifest: &VersionManifest) -> Vec<&str> {
+        manifest.versions.iter().map(|version| version.id.as_str()).collect()
+    }
+
+    #[test]
+    fn flags_the_latest_release_and_snapshot() {
+        let manifest = manifest();
+        let latest_release: Vec<_> = manifest.versions.iter().filter(|v| v.is_latest_release).map(|v| v.id.as_str()).collect();
+        let latest_snapshot: Vec<_> = manifest.versions.iter().filter(|v| v.is_latest_snapshot).map(|v| v.id.as_str()).collect();
+        assert_eq!(latest_release, ["1.20.4"]);
+        assert_eq!(latest_snapshot, ["24w14a"]);
+        assert_eq!(manifest.versions[0].sha1.as_deref(), Some("6a7d1b7f1b5d0a6e3e2c9d3bd0b1f3a6cdd8e4f2"));
+
+        // Right after a release both point at it, it is no snapshot though
+        let mut manifest = manifest;
+        manifest.latest.snapshot = "1.20.4".to_string();
+        manifest.mark_latest();
+        assert!(manifest.versions.iter().all(|v| !v.is_latest_snapshot));
+    }
+
+    #[test]
+    fn filters_versions_by_type() {
+        let mut releases = manifest();
+        releases.retain(VersionFilter { snapshots: false, old_beta: false, old_alpha: false });
+        assert_eq!(ids(&releases), ["1.20.4", "1.20.3", "1.8.9", "1.0"]);
+
+        let mut no_alpha = manifest();
+        no_alpha.retain(VersionFilter { old_alpha: false, ..Default::default() });
+        assert_eq!(ids(&no_alpha), ["24w14a", "24w13a", "1.20.4", "1.20.3", "23w51b", "1.8.9", "1.0", "b1.7.3"]);
+
+        let mut everything = manifest();
+        everything.retain(VersionFilter::default());
+        assert_eq!(everything.versions.len(), 11);
+    }
+
+    fn fixture(json: &str) -> serde_json::Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn reads_java_version_from_version_json() {
+        let cases = [
+            (include_str!("fixtures/version_1_8.json"), 8),
+            (include_str!("fixtures/version_1_16_5.json"), 8),
+            (include_str!("fixtures/version_1_17.json"), 17),
+            (include_str!("fixtures/version_1_20_4.json"), 17),
+            (include_str!("fixtures/version_1_20_6.json"), 21),
+        ];
+        for (json, expected) in cases {
+            let json = fixture(json);
+            assert_eq!(java_version_from_chain(std::slice::from_ref(&json)), Some(expected), "{}", json["id"]);
+        }
+    }
+
+    #[test]
+    fn loader_profiles_inherit_the_java_version() {
+        let loader = serde_json::json!({ "id": "fabric-loader-0.15.11-1.20.6", "inheritsFrom": "1.20.6" });
+        let chain = [loader, fixture(include_str!("fixtures/version_1_20_6.json"))];
+        assert_eq!(java_version_from_chain(&chain), Some(21));
+
+        // A loader that sets its own wins over the vanilla one
+        let loader = serde_json::json!({ "id": "custom", "javaVersion": { "majorVersion": 17 } });
+        assert_eq!(java_version_from_chain(&[loader, chain[1].clone()]), Some(17));
+
+        let no_java = serde_json::json!({ "id": "1.7.10" });
+        assert_eq!(java_version_from_chain(&[no_java]), None);
+        assert_eq!(java_version_from_chain(&[]), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Window size and fullscreen options passed to the game
+//!
+//! Launcher settings hold the default and instances can override it. Modern
+//! version JSONs list the resolution arguments behind a `has_custom_resolution`
+//! feature rule; when one does, those arguments are used with the size filled
+//! in. Legacy `minecraftArguments` versions get `--width` and `--height`.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+use super::profile;
+
+/// Smallest width or height the launcher passes on
+pub const MIN_DIMENSION: u32 = 320;
+/// Largest width or height the launcher passes on
+pub const MAX_DIMENSION: u32 = 16384;
+
+/// What the game window starts as, None leaves the size to the game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct WindowOptions {
+    pub resolution: Option<(u32, u32)>,
+    pub fullscreen: bool,
+}
+
+impl WindowOptions {
+    /// Instance values override the settings, None falls back and 0 lets the game decide
+    pub fn resolve(
+        width: Option<u32>,
+        height: Option<u32>,
+        fullscreen: Option<bool>,
+        settings: &crate::storage::LauncherSettings,
+    ) -> Self {
+        let width = width.unwrap_or(settings.window_width);
+        let height = height.unwrap_or(settings.window_height);
+        Self {
+            resolution: (width > 0 && height > 0).then_some((width, height)),
+            fullscreen: fullscreen.unwrap_or(settings.fullscreen),
+        }
+    }
+}
+
+/// Options for an instance's overrides on top of the saved launcher settings
+pub async fn options(width: Option<u32>, height: Option<u32>, fullscreen: Option<bool>) -> WindowOptions {
+    match crate::storage::shared().await {
+        Ok(storage) => WindowOptions::resolve(width, height, fullscreen, storage.read().await.get_settings()),
+        Err(_) => WindowOptions::resolve(width, height, fullscreen, &crate::storage::LauncherSettings::default()),
+    }
+}
+
+/// Reject sizes no monitor has, 0 and None are fine
+pub fn validate(width: Option<u32>, height: Option<u32>) -> Result<(), String> {
+    for (label, value) in [("width", width), ("height", height)] {
+        if let Some(value) = value.filter(|value| *value != 0) {
+            if !(MIN_DIMENSION..=MAX_DIMENSION).contains(&value) {
+                return Err(format!(
+                    "Window {} must be between {} and {}, or 0 to let the game decide",
+                    label, MIN_DIMENSION, MAX_DIMENSION
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Window arguments for an installed version and any loader version inheriting from it
+///
+/// Flags already in `custom_args` are left out, the game refuses an option given twice.
+pub async fn launch_args(game_dir: &Path, version: &str, options: &WindowOptions, custom_args: &[String]) -> Vec<String> {
+    let has = |flag: &str| custom_args.iter().any(|arg| arg == flag || arg.starts_with(&format!("{}=", flag)));
+    let options = WindowOptions {
+        resolution: options.resolution.filter(|_| !has("--width") && !has("--height")),
+        fullscreen: options.fullscreen && !has("--fullscreen"),
+    };
+    if options == WindowOptions::default() {
+        return Vec::new();
+    }
+
+    window_args(&profile::load_chain(game_dir, version).await, &options)
+}
+
+/// Window arguments for a version and the versions it inherits from, child first
+pub fn window_args(version_jsons: &[Value], options: &WindowOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some((width, height)) = options.resolution {
+        let from_rule = version_jsons.iter().find_map(custom_resolution_args);
+        match from_rule {
+            Some(rule_args) => args.extend(rule_args.into_iter().map(|arg| {
+                arg.replace("${resolution_width}", &width.to_string())
+                    .replace("${resolution_height}", &height.to_string())
+            })),
+            None => args.extend([
+                "--width".to_string(), width.to_string(),
+                "--height".to_string(), height.to_string(),
+            ]),
+        }
+    }
+    if options.fullscreen {
+        args.push("--fullscreen".to_string());
+    }
+    args
+}
+
+/// Game arguments a version only passes when `has_custom_resolution` is on
+fn custom_resolution_args(version_json: &Value) -> Option<Vec<String>> {
+    let game_args = version_json.get("arguments")?.get("game")?.as_array()?;
+    let mut args = Vec::new();
+    for arg in game_args {
+        let requires_resolution = arg.get("rules")
+            .and_then(|rules| rules.as_array())
+            .is_some_and(|rules| rules.iter().any(|rule| {
+                rule.pointer("/features/has_custom_resolution").and_then(|v| v.as_bool()) == Some(true)
+            }));
+        if !requires_resolution {
+            continue;
+        }
+        match arg.get("value") {
+            Some(Value::String(value)) => args.push(value.clone()),
+            Some(Value::Array(values)) => args.extend(values.iter().filter_map(|v| v.as_str()).map(str::to_string)),
+            _ => {}
+        }
+    }
+    (!args.is_empty()).then_some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn modern_json() -> Value {
+        json!({
+            "id": "1.20.1",
+            "arguments": {
+                "game": [
+                    "--username", "${auth_player_name}",
+                    {
+                        "rules": [{ "action": "allow", "features": { "is_demo_user": true } }],
+                        "value": "--demo"
+                    },
+                    {
+                        "rules": [{ "action": "allow", "features": { "has_custom_resolution": true } }],
+                        "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]
+                    }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn resolution_uses_the_feature_rule_or_falls_back_to_flags() {
+        let options = WindowOptions { resolution: Some((1920, 1080)), fullscreen: true };
+        let fabric = json!({ "id": "fabric-loader-0.15.0-1.20.1", "inheritsFrom": "1.20.1" });
+
+        assert_eq!(
+            window_args(&[fabric, modern_json()], &options),
+            vec!["--width", "1920", "--height", "1080", "--fullscreen"]
+        );
+        let legacy = json!({ "id": "1.12.2", "minecraftArguments": "--username ${auth_player_name}" });
+        assert_eq!(custom_resolution_args(&modern_json()).unwrap(), vec!["--width", "${resolution_width}", "--height", "${resolution_height}"]);
+        assert_eq!(custom_resolution_args(&legacy), None);
+        assert_eq!(window_args(&[legacy], &options), vec!["--width", "1920", "--height", "1080", "--fullscreen"]);
+        assert!(window_args(&[modern_json()], &WindowOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn instance_values_override_settings_and_zero_lets_the_game_decide() {
+        let settings = crate::storage::LauncherSettings { window_width: 1280, window_height: 720, ..Default::default() };
+
+        assert_eq!(WindowOptions::resolve(None, None, None, &settings).resolution, Some((1280, 720)));
+        assert_eq!(WindowOptions::resolve(Some(1920), Some(1080), Some(true), &settings),
+            WindowOptions { resolution: Some((1920, 1080)), fullscreen: true });
+        assert_eq!(WindowOptions::resolve(Some(0), None, None, &settings).resolution, None);
+    }
+
+    #[test]
+    fn absurd_sizes_are_rejected() {
+        assert!(validate(Some(1920), Some(1080)).is_ok());
+        assert!(validate(Some(0), None).is_ok());
+        assert!(validate(Some(100), Some(1080)).is_err());
+        assert!(validate(Some(1920), Some(20000)).is_err());
+    }
+}
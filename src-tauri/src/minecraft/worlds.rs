@@ -0,0 +1,293 @@
+//! Singleplayer world helpers
+//!
+//! Lists the saves of an instance, builds the quick play arguments used to
+//! launch straight into one of them and reads which version last saved a world.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// Deepest NBT nesting read from level.dat, real files stay far below this
+const MAX_NBT_DEPTH: usize = 64;
+
+/// A singleplayer world in an instance's saves folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldSummary {
+    /// Folder name under `saves`, passed back when launching into the world
+    pub folder: String,
+    /// Epoch milliseconds of the last level.dat write
+    pub last_played: Option<i64>,
+    pub has_icon: bool,
+}
+
+/// Worlds of an instance and whether it can launch into one directly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceWorlds {
+    pub quick_play_supported: bool,
+    pub worlds: Vec<WorldSummary>,
+}
+
+/// Whether a Minecraft version understands `--quickPlaySingleplayer`
+///
+/// Added in 1.20 (snapshot 23w14a). Unknown version formats are treated as unsupported.
+pub fn supports_quick_play(version: &str) -> bool {
+    // Snapshots look like 23w14a
+    if let Some((year, rest)) = version.split_once('w') {
+        if let (Ok(year), Ok(week)) = (year.parse::<u32>(), rest.trim_end_matches(char::is_alphabetic).parse::<u32>()) {
+            return (year, week) >= (23, 14);
+        }
+    }
+
+    // Pre-releases and release candidates carry a suffix, e.g. 1.20-pre1
+    let release = version.split(['-', ' ']).next().unwrap_or(version);
+    let mut parts = release.split('.').map(|p| p.parse::<u32>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => (major, minor) >= (1, 20),
+        _ => false,
+    }
+}
+
+/// Game arguments that open `world_folder` on startup, None when the version can't
+pub fn quick_play_args(version: &str, world_folder: &str) -> Option<Vec<String>> {
+    supports_quick_play(version)
+        .then(|| vec!["--quickPlaySingleplayer".to_string(), world_folder.to_string()])
+}
+
+/// Path of a world folder inside the instance, rejecting names that would escape `saves`
+pub fn world_path(game_dir: &Path, world_folder: &str) -> Result<PathBuf, String> {
+    let is_plain_name = !world_folder.is_empty()
+        && world_folder != "."
+        && world_folder != ".."
+        && !world_folder.contains(['/', '\\']);
+    if !is_plain_name {
+        return Err(format!("Invalid world folder name: {}", world_folder));
+    }
+
+    let path = game_dir.join("saves").join(world_folder);
+    if !path.join("level.dat").is_file() {
+        return Err(format!("World '{}' not found", world_folder));
+    }
+    Ok(path)
+}
+
+/// Game version a world was last saved with, from `Data.Version` in level.dat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldVersion {
+    /// `DataVersion`, missing in worlds older than 1.9
+    pub data_version: Option<i32>,
+    pub name: Option<String>,
+    pub snapshot: bool,
+}
+
+/// The parts of an NBT tree level.dat is read for
+#[derive(Debug)]
+enum Nbt {
+    Byte(i8),
+    Int(i32),
+    String(String),
+    Compound(Vec<(String, Nbt)>),
+    Other,
+}
+
+impl Nbt {
+    fn get(&self, key: &str) -> Option<&Nbt> {
+        match self {
+            Nbt::Compound(entries) => entries.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+struct NbtReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl NbtReader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        if self.bytes.len() < len {
+            return Err("level.dat ends early".to_string());
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn int(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn array_len(&mut self) -> Result<usize, String> {
+        usize::try_from(self.int()?).map_err(|_| "Negative NBT array length".to_string())
+    }
+
+    fn payload(&mut self, tag: u8, depth: usize) -> Result<Nbt, String> {
+        if depth > MAX_NBT_DEPTH {
+            return Err("level.dat is nested too deeply".to_string());
+        }
+        Ok(match tag {
+            1 => Nbt::Byte(self.take(1)?[0] as i8),
+            2 => { self.take(2)?; Nbt::Other }
+            3 => Nbt::Int(self.int()?),
+            4 | 6 => { self.take(8)?; Nbt::Other }
+            5 => { self.take(4)?; Nbt::Other }
+            7 => { let len = self.array_len()?; self.take(len)?; Nbt::Other }
+            8 => Nbt::String(self.string()?),
+            9 => {
+                let item = self.take(1)?[0];
+                for _ in 0..self.array_len()? {
+                    self.payload(item, depth + 1)?;
+                }
+                Nbt::Other
+            }
+            10 => {
+                let mut entries = Vec::new();
+                loop {
+                    let tag = self.take(1)?[0];
+                    if tag == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(tag, depth + 1)?));
+                }
+                Nbt::Compound(entries)
+            }
+            11 => { let len = self.array_len()?; self.take(len.saturating_mul(4))?; Nbt::Other }
+            12 => { let len = self.array_len()?; self.take(len.saturating_mul(8))?; Nbt::Other }
+            _ => return Err(format!("Unknown NBT tag {}", tag)),
+        })
+    }
+}
+
+/// Parse level.dat contents, gzip compressed as the game writes them or plain
+fn parse_world_version(bytes: &[u8]) -> Result<WorldVersion, String> {
+    let mut plain = Vec::new();
+    let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+        flate2::read::GzDecoder::new(bytes).read_to_end(&mut plain)
+            .map_err(|e| format!("Failed to decompress level.dat: {}", e))?;
+        &plain[..]
+    } else {
+        bytes
+    };
+
+    let mut reader = NbtReader { bytes };
+    if reader.take(1)?[0] != 10 {
+        return Err("level.dat doesn't start with a compound".to_string());
+    }
+    reader.string()?;
+    let root = reader.payload(10, 0)?;
+    let data = root.get("Data").ok_or("level.dat has no Data section")?;
+
+    let version = data.get("Version");
+    let data_version = match (data.get("DataVersion"), version.and_then(|version| version.get("Id"))) {
+        (Some(Nbt::Int(id)), _) | (None, Some(Nbt::Int(id))) => Some(*id),
+        _ => None,
+    };
+    let name = match version.and_then(|version| version.get("Name")) {
+        Some(Nbt::String(name)) => Some(name.clone()),
+        _ => None,
+    };
+    let snapshot = matches!(version.and_then(|version| version.get("Snapshot")), Some(Nbt::Byte(1)));
+    Ok(WorldVersion { data_version, name, snapshot })
+}
+
+/// Version that last saved the world in `world_dir`
+pub fn read_world_version(world_dir: &Path) -> Result<WorldVersion, String> {
+    let bytes = std::fs::read(world_dir.join("level.dat"))
+        .map_err(|e| format!("Failed to read level.dat: {}", e))?;
+    parse_world_version(&bytes)
+}
+
+/// Worlds in the instance's saves folder, most recently played first
+pub async fn list_worlds(game_dir: &Path) -> Vec<WorldSummary> {
+    let mut worlds = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(game_dir.join("saves")).await else {
+        return worlds;
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let Ok(level) = tokio::fs::metadata(path.join("level.dat")).await else {
+            continue;
+        };
+
+        let last_played = level.modified().ok()
+            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).timestamp_millis());
+        worlds.push(WorldSummary {
+            folder: entry.file_name().to_string_lossy().to_string(),
+            last_played,
+            has_icon: path.join("icon.png").is_file(),
+        });
+    }
+
+    worlds.sort_by(|a, b| b.last_played.cmp(&a.last_played).then_with(|| a.folder.cmp(&b.folder)));
+    worlds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quick_play_needs_1_20_or_newer() {
+        assert!(supports_quick_play("1.20"));
+        assert!(supports_quick_play("1.20.4"));
+        assert!(supports_quick_play("1.21-pre2"));
+        assert!(supports_quick_play("23w14a"));
+        assert!(supports_quick_play("24w10a"));
+        assert!(!supports_quick_play("1.19.4"));
+        assert!(!supports_quick_play("23w13a"));
+        assert!(!supports_quick_play("b1.7.3"));
+        assert_eq!(quick_play_args("1.8.9", "New World"), None);
+    }
+
+    #[test]
+    fn world_path_rejects_escaping_names() {
+        let dir = std::env::temp_dir().join(format!("chai-worlds-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("saves").join("My World")).unwrap();
+        std::fs::write(dir.join("saves").join("My World").join("level.dat"), b"").unwrap();
+
+        assert!(world_path(&dir, "My World").is_ok());
+        assert!(world_path(&dir, "Missing").is_err());
+        assert!(world_path(&dir, "..").is_err());
+        assert!(world_path(&dir, "../saves/My World").is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn named(tag: u8, name: &str, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![tag];
+        bytes.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn reads_the_version_from_level_dat() {
+        let mut version = named(3, "Id", &3953i32.to_be_bytes());
+        version.extend(named(8, "Name", &[&4u16.to_be_bytes()[..], b"1.21"].concat()));
+        version.extend(named(1, "Snapshot", &[0]));
+        version.push(0);
+
+        let mut data = named(9, "ServerBrands", &[&[8u8][..], &1i32.to_be_bytes(), &7u16.to_be_bytes(), b"vanilla"].concat());
+        data.extend(named(11, "CustomBossEvents", &[&2i32.to_be_bytes()[..], &[0; 8]].concat()));
+        data.extend(named(10, "Version", &version));
+        data.extend(named(3, "DataVersion", &3953i32.to_be_bytes()));
+        data.push(0);
+        let mut root = named(10, "Data", &data);
+        root.push(0);
+        let level = named(10, "", &root);
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut gzip, &level).unwrap();
+        let parsed = parse_world_version(&gzip.finish().unwrap()).unwrap();
+        assert_eq!(parsed, WorldVersion { data_version: Some(3953), name: Some("1.21".to_string()), snapshot: false });
+
+        assert!(parse_world_version(&level[..level.len() - 10]).is_err());
+    }
+}
@@ -1,5 +1,12 @@
 use anyhow::{Result, Context};
 
+use crate::mods::api::common::ApiUtils;
+use crate::mods::api::ModrinthApi;
+use crate::mods::loaders::ModLoaderManager;
+use crate::net::manager::{DownloadKind, DownloadPriority};
+
+use super::mrpack::{self, InstalledPack, LocalJar, MrpackIndex, MrpackIndexFile};
+use super::overrides::{extract_archive, matching_dirs, ExtractedFiles, OverrideNormalizer};
 use super::types::*;
 
 impl ModpackInstaller {
@@ -84,12 +91,16 @@ impl ModpackInstaller {
         &self,
         version: &ModrinthVersion,
         progress_callback: impl Fn(ModpackInstallProgress) + Send + Sync,
-    ) -> Result<()> {
-        progress_callback(ModpackInstallProgress {
-            instance_dir: self.instance_dir.to_string_lossy().to_string(),
-            progress: 0.0,
-            stage: "Starting modpack installation".to_string(),
-        });
+    ) -> Result<ModpackInstallReport> {
+        let report = |progress: f64, stage: &str| {
+            progress_callback(ModpackInstallProgress {
+                instance_dir: self.instance_dir.to_string_lossy().to_string(),
+                progress,
+                stage: stage.to_string(),
+            });
+        };
+
+        report(0.0, "Starting modpack installation");
 
         // Get the primary modpack file
         let modpack_file = version.files.iter()
@@ -97,47 +108,104 @@ impl ModpackInstaller {
             .or_else(|| version.files.first())
             .context("No modpack file found")?;
 
-        // Download modpack file
-        progress_callback(ModpackInstallProgress {
-            instance_dir: self.instance_dir.to_string_lossy().to_string(),
-            progress: 10.0,
-            stage: "Downloading modpack".to_string(),
-        });
-
-        let response = self.client.get(&modpack_file.url).send().await
-            .context("Failed to download modpack")?;
-        
-        let modpack_data = response.bytes().await
-            .context("Failed to read modpack data")?;
-
-        // Create temporary file for the modpack
+        // The archive is kept until the install succeeds so a rerun can reuse it
         let temp_file = std::env::temp_dir().join(&modpack_file.filename);
-        tokio::fs::write(&temp_file, &modpack_data).await
-            .context("Failed to write modpack file")?;
+        let mut counts = DownloadCounts::default();
+        let installed = self.install_modrinth_archive(version, modpack_file, &temp_file, &mut counts, &report).await;
+        if installed.as_ref().map_or_else(is_cancelled, |_| true) {
+            let _ = tokio::fs::remove_file(&temp_file).await;
+        }
+        let warnings = installed?;
+        for warning in &warnings {
+            println!("⚠️  {}", warning);
+        }
 
-        progress_callback(ModpackInstallProgress {
+        report(100.0, &format!("Modpack installation complete: {}", counts.summary()));
+
+        Ok(ModpackInstallReport {
             instance_dir: self.instance_dir.to_string_lossy().to_string(),
-            progress: 30.0,
-            stage: "Extracting modpack".to_string(),
-        });
+            warnings,
+            manual_downloads: Vec::new(),
+        })
+    }
+
+    /// Download, extract and fetch the listed files of a Modrinth pack archive
+    ///
+    /// The installed version and its files are recorded for `update_modpack`.
+    async fn install_modrinth_archive(
+        &self,
+        version: &ModrinthVersion,
+        modpack_file: &ModrinthFile,
+        temp_file: &std::path::Path,
+        counts: &mut DownloadCounts,
+        report: &impl Fn(f64, &str),
+    ) -> Result<Vec<String>> {
+        report(10.0, "Downloading modpack");
+        self.download_verified(&modpack_file.url, temp_file, Some(modpack_file.size), modpack_file.hashes.get("sha1").cloned(), counts).await
+            .context("Failed to download modpack")?;
+        self.check_cancelled()?;
 
-        // Extract modpack to instance directory
-        self.extract_modpack(&temp_file).await
+        report(30.0, "Extracting modpack");
+        let (index, warnings) = self.extract_modpack(temp_file).await
             .context("Failed to extract modpack")?;
 
-        progress_callback(ModpackInstallProgress {
-            instance_dir: self.instance_dir.to_string_lossy().to_string(),
-            progress: 100.0,
-            stage: "Modpack installation complete".to_string(),
-        });
+        if let Some(index) = index {
+            report(60.0, "Downloading modpack files");
+            self.install_modpack_mods(&index, counts, |fraction, stage| report(60.0 + 30.0 * fraction, stage)).await?;
 
-        // Clean up temporary file
-        let _ = tokio::fs::remove_file(&temp_file).await;
+            let files: Vec<&MrpackIndexFile> = index.files.iter().filter(|file| file.supports_client()).collect();
+            InstalledPack::new(&version.project_id, &version.id, &version.version_number, &files)
+                .write(&self.instance_dir).await?;
+        }
 
+        Ok(warnings)
+    }
+
+    /// Download `url` to `dest` unless a file with the expected SHA1 is already there
+    pub(crate) async fn download_verified(
+        &self,
+        url: &str,
+        dest: &std::path::Path,
+        expected_size: Option<u64>,
+        sha1: Option<String>,
+        counts: &mut DownloadCounts,
+    ) -> Result<()> {
+        counts.add(self.fetch_verified(url, dest, expected_size, sha1, None).await?);
         Ok(())
     }
 
-    async fn extract_modpack(&self, modpack_path: &std::path::Path) -> Result<()> {
+    /// Like `download_verified`, returning how the file got there
+    pub(crate) async fn fetch_verified(
+        &self,
+        url: &str,
+        dest: &std::path::Path,
+        expected_size: Option<u64>,
+        sha1: Option<String>,
+        progress: Option<crate::net::download::ProgressCallback>,
+    ) -> Result<Fetched> {
+        let expected_size = expected_size.filter(|size| *size > 0);
+        if let Some(sha1) = &sha1 {
+            if crate::net::is_downloaded(dest, expected_size, sha1).await {
+                return Ok(Fetched::Present);
+            }
+        }
+
+        let options = crate::net::DownloadOptions {
+            client: Some(self.client.clone()),
+            expected_size,
+            sha1: sha1.clone(),
+            progress,
+            cancel: self.cancel.clone(),
+            ..Default::default()
+        };
+        let downloads = crate::net::manager::shared();
+        let fetched = downloads.fetch_cached(DownloadKind::Modpack, DownloadPriority::Low, url, dest, options).await?;
+        crate::provenance::record_download(&self.instance_dir, dest, url, sha1.as_deref()).await;
+        Ok(if fetched.cache_hit { Fetched::Cached } else { Fetched::Downloaded(fetched.size) })
+    }
+
+    /// Extract a modpack archive, returning the .mrpack index and warnings for the install report
+    async fn extract_modpack(&self, modpack_path: &std::path::Path) -> Result<(Option<mrpack::MrpackIndex>, Vec<String>)> {
         // Create instance directory if it doesn't exist
         tokio::fs::create_dir_all(&self.instance_dir).await
             .context("Failed to create instance directory")?;
@@ -148,63 +216,60 @@ impl ModpackInstaller {
             .unwrap_or("");
 
         match extension.to_lowercase().as_str() {
-            "zip" => self.extract_zip(modpack_path).await,
-            "mrpack" => self.extract_mrpack(modpack_path).await,
+            "zip" => Ok((None, self.extract_zip(modpack_path).await?)),
+            "mrpack" => {
+                let (index, _, warnings) = self.extract_mrpack(modpack_path).await?;
+                Ok((Some(index), warnings))
+            }
             _ => Err(anyhow::anyhow!("Unsupported modpack format: {}", extension)),
         }
     }
 
-    async fn extract_zip(&self, zip_path: &std::path::Path) -> Result<()> {
+    async fn extract_zip(&self, zip_path: &std::path::Path) -> Result<Vec<String>> {
         use zip::ZipArchive;
         use std::fs::File;
-        use std::io::Read;
 
         let zip_path = zip_path.to_path_buf();
         let instance_dir = self.instance_dir.clone();
         
-        tokio::task::spawn_blocking(move || -> Result<()> {
+        tokio::task::spawn_blocking(move || -> Result<Vec<String>> {
             let file = File::open(&zip_path)
                 .context("Failed to open ZIP file")?;
             
             let mut archive = ZipArchive::new(file)
                 .context("Failed to read ZIP archive")?;
 
-            for i in 0..archive.len() {
-                let mut file = archive.by_index(i)
-                    .context("Failed to read file from archive")?;
-                
-                let outpath = match file.enclosed_name() {
-                    Some(path) => instance_dir.join(path),
-                    None => continue,
-                };
-
-                if file.name().ends_with('/') {
-                    std::fs::create_dir_all(&outpath)
-                        .context("Failed to create directory")?;
-                } else {
-                    if let Some(p) = outpath.parent() {
-                        std::fs::create_dir_all(p)
-                            .context("Failed to create parent directory")?;
-                    }
-                    
-                    let mut buffer = Vec::new();
-                    file.read_to_end(&mut buffer)
-                        .context("Failed to read file content")?;
-                    
-                    std::fs::write(&outpath, &buffer)
-                        .context("Failed to write extracted file")?;
-                }
-            }
+            let mut normalizer = OverrideNormalizer::new();
+            extract_archive(&mut archive, &instance_dir, None, &mut normalizer)?;
 
-            Ok(())
-        }).await.map_err(|e| anyhow::anyhow!("Task join error: {}", e))??;
-
-        Ok(())
+            Ok(normalizer.warnings())
+        }).await.map_err(|e| anyhow::anyhow!("Task join error: {}", e))?
     }
 
-    async fn extract_mrpack(&self, mrpack_path: &std::path::Path) -> Result<()> {
-        // .mrpack files are essentially ZIP files with a specific structure
-        self.extract_zip(mrpack_path).await
+    /// Read the index and extract overrides and client-overrides
+    pub(super) async fn extract_mrpack(&self, mrpack_path: &std::path::Path) -> Result<(mrpack::MrpackIndex, ExtractedFiles, Vec<String>)> {
+        let mrpack_path = mrpack_path.to_path_buf();
+        let instance_dir = self.instance_dir.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<(mrpack::MrpackIndex, ExtractedFiles, Vec<String>)> {
+            let file = std::fs::File::open(&mrpack_path)
+                .context("Failed to open modpack archive")?;
+            let mut archive = zip::ZipArchive::new(file)
+                .context("Failed to read modpack archive")?;
+
+            let index = mrpack::read_index(&mut archive)?;
+
+            // client-overrides are applied after overrides so they take precedence
+            let mut normalizer = OverrideNormalizer::new();
+            let mut extracted = ExtractedFiles::default();
+            for prefix in ["overrides/", "client-overrides/"] {
+                let files = extract_archive(&mut archive, &instance_dir, Some(prefix), &mut normalizer)?;
+                extracted.written += files.written;
+                extracted.unchanged += files.unchanged;
+            }
+
+            Ok((index, extracted, normalizer.warnings()))
+        }).await.map_err(|e| anyhow::anyhow!("Task join error: {}", e))?
     }
 }
 
@@ -213,7 +278,7 @@ impl ModpackCreator {
         &self,
         request: &ModpackCreationRequest,
         progress_callback: F,
-    ) -> Result<String>
+    ) -> Result<ModpackCreationResult>
     where
         F: Fn(f64, String) + Send + Sync,
     {
@@ -221,9 +286,13 @@ impl ModpackCreator {
         
         let instance_path = std::path::PathBuf::from(&request.instance_path);
         let modpack_name = request.metadata.name.replace(" ", "_");
+        let extension = match request.format {
+            ModpackFormat::Zip => "zip",
+            ModpackFormat::Mrpack => "mrpack",
+        };
         let output_path = instance_path.parent()
             .unwrap_or(&std::path::PathBuf::from("."))
-            .join(format!("{}_v{}.zip", modpack_name, request.metadata.version));
+            .join(format!("{}_v{}.{}", modpack_name, request.metadata.version, extension));
         
         progress_callback(0.0, "Initializing modpack creation".to_string());
         
@@ -231,6 +300,10 @@ impl ModpackCreator {
             return Err(anyhow::anyhow!("Instance path does not exist: {}", instance_path.display()));
         }
         
+        if request.format == ModpackFormat::Mrpack {
+            return self.create_mrpack(request, &instance_path, &output_path, &progress_callback).await;
+        }
+        
         progress_callback(10.0, "Analyzing instance files".to_string());
         
         // Create a temporary directory for modpack assembly
@@ -241,41 +314,25 @@ impl ModpackCreator {
         progress_callback(20.0, "Collecting mods and dependencies".to_string());
         
         // Copy mods directory
-        let mods_src = instance_path.join("mods");
-        if mods_src.exists() {
-            let mods_dst = temp_dir.join("mods");
-            self.copy_directory_sync(&mods_src, &mods_dst)?;
-        }
+        self.copy_canonical_dir(&instance_path, &temp_dir, "mods", &progress_callback)?;
         
         progress_callback(40.0, "Copying configuration files".to_string());
         
         // Copy config directory if requested
         if request.metadata.include_config {
-            let config_src = instance_path.join("config");
-            if config_src.exists() {
-                let config_dst = temp_dir.join("config");
-                self.copy_directory_sync(&config_src, &config_dst)?;
-            }
+            self.copy_canonical_dir(&instance_path, &temp_dir, "config", &progress_callback)?;
         }
         
         progress_callback(60.0, "Including resource packs and shaders".to_string());
         
         // Copy resource packs if requested
         if request.metadata.include_resource_packs {
-            let rp_src = instance_path.join("resourcepacks");
-            if rp_src.exists() {
-                let rp_dst = temp_dir.join("resourcepacks");
-                self.copy_directory_sync(&rp_src, &rp_dst)?;
-            }
+            self.copy_canonical_dir(&instance_path, &temp_dir, "resourcepacks", &progress_callback)?;
         }
         
         // Copy shader packs if requested
         if request.metadata.include_shader_packs {
-            let sp_src = instance_path.join("shaderpacks");
-            if sp_src.exists() {
-                let sp_dst = temp_dir.join("shaderpacks");
-                self.copy_directory_sync(&sp_src, &sp_dst)?;
-            }
+            self.copy_canonical_dir(&instance_path, &temp_dir, "shaderpacks", &progress_callback)?;
         }
         
         progress_callback(80.0, "Creating modpack archive".to_string());
@@ -291,9 +348,125 @@ impl ModpackCreator {
         
         progress_callback(100.0, "Modpack creation complete".to_string());
         
-        Ok(output_path.to_string_lossy().to_string())
+        Ok(ModpackCreationResult {
+            output_path: output_path.to_string_lossy().to_string(),
+            override_mods: Vec::new(),
+        })
     }
     
+    /// Build a Modrinth .mrpack, linking mods Modrinth hosts and bundling the rest as overrides
+    async fn create_mrpack<F>(
+        &self,
+        request: &ModpackCreationRequest,
+        instance_path: &std::path::Path,
+        output_path: &std::path::Path,
+        progress_callback: &F,
+    ) -> Result<ModpackCreationResult>
+    where
+        F: Fn(f64, String) + Send + Sync,
+    {
+        let metadata = &request.metadata;
+        
+        progress_callback(10.0, "Hashing mods".to_string());
+        
+        let mut jars = Vec::new();
+        for dir in matching_dirs(instance_path, "mods") {
+            for entry in std::fs::read_dir(&dir).context("Failed to read mods directory")?.flatten() {
+                let path = entry.path();
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                if !path.is_file() || !file_name.to_lowercase().ends_with(".jar") {
+                    continue;
+                }
+                
+                let sha1 = ApiUtils::file_sha1(&path).await
+                    .with_context(|| format!("Failed to hash {}", file_name))?;
+                jars.push(LocalJar { file_name, path, sha1 });
+            }
+        }
+        
+        progress_callback(30.0, "Looking up mods on Modrinth".to_string());
+        
+        let sha1s: Vec<String> = jars.iter().map(|jar| jar.sha1.clone()).collect();
+        let versions = ModrinthApi::new().get_versions_by_hashes(&sha1s).await
+            .context("Failed to look up mods on Modrinth")?;
+        let (files, unmatched) = mrpack::link_mods(jars, &versions);
+        
+        progress_callback(50.0, "Collecting overrides".to_string());
+        
+        let mut overrides: Vec<(String, std::path::PathBuf)> = unmatched.iter()
+            .map(|jar| (format!("mods/{}", jar.file_name), jar.path.clone()))
+            .collect();
+        let included = [
+            (metadata.include_config, "config"),
+            (metadata.include_resource_packs, "resourcepacks"),
+            (metadata.include_shader_packs, "shaderpacks"),
+        ];
+        for (include, canonical) in included {
+            if include {
+                for dir in matching_dirs(instance_path, canonical) {
+                    overrides.extend(mrpack::override_files(&dir, canonical)?);
+                }
+            }
+        }
+        
+        let loader = ModLoaderManager::new(instance_path.to_path_buf()).get_installed_loader().await;
+        let index = MrpackIndex {
+            format_version: 1,
+            game: "minecraft".to_string(),
+            version_id: metadata.version.clone(),
+            name: metadata.name.clone(),
+            summary: Some(metadata.description.clone()).filter(|summary| !summary.trim().is_empty()),
+            files,
+            dependencies: mrpack::pack_dependencies(&metadata.minecraft_version, loader.as_ref())?,
+        };
+        
+        progress_callback(80.0, "Creating modpack archive".to_string());
+        
+        mrpack::write_mrpack(output_path, &index, &overrides)
+            .context("Failed to create modpack archive")?;
+        
+        let override_mods: Vec<String> = unmatched.into_iter().map(|jar| jar.file_name).collect();
+        if !override_mods.is_empty() {
+            println!("⚠️  {} mods are not on Modrinth and were bundled as overrides: {}", override_mods.len(), override_mods.join(", "));
+        }
+        
+        progress_callback(100.0, "Modpack creation complete".to_string());
+        
+        Ok(ModpackCreationResult {
+            output_path: output_path.to_string_lossy().to_string(),
+            override_mods,
+        })
+    }
+    
+    /// Copy every casing of a known directory into its canonical name in the modpack
+    fn copy_canonical_dir<F>(
+        &self,
+        instance_path: &std::path::Path,
+        temp_dir: &std::path::Path,
+        canonical: &str,
+        progress_callback: &F,
+    ) -> Result<()>
+    where
+        F: Fn(f64, String) + Send + Sync,
+    {
+        let sources = matching_dirs(instance_path, canonical);
+        if sources.len() > 1 {
+            let names: Vec<String> = sources.iter()
+                .filter_map(|dir| dir.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .collect();
+            let warning = format!("Merged directories {} into {}/", names.join(", "), canonical);
+            println!("⚠️  {}", warning);
+            progress_callback(40.0, warning);
+        }
+
+        for source in sources {
+            self.copy_directory_sync(&source, &temp_dir.join(canonical))?;
+        }
+
+        Ok(())
+    }
+
     fn copy_directory_sync(&self, src: &std::path::Path, dst: &std::path::Path) -> Result<()> {
         use walkdir::WalkDir;
         use std::fs;
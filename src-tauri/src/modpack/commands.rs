@@ -1,7 +1,80 @@
-use tauri::{command, Emitter};
+use tauri::{command, Emitter, State};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::watch;
 
 use super::types::*;
+use crate::notifications::{self, LauncherNotification, NotificationCategory, NotificationTarget};
+
+pub type ModpackInstallsState = Arc<ModpackInstalls>;
+
+/// Arguments of an `install_modpack` call, kept so an interrupted install can be resumed
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ModpackInstallRequest {
+    pub project_id: String,
+    pub version_id: String,
+    pub instance_name: String,
+    pub instance_dir: String,
+    pub platform: String,
+}
+
+/// Running modpack installs and their cancellation senders, keyed by instance directory
+#[derive(Default)]
+pub struct ModpackInstalls {
+    cancellations: Mutex<HashMap<String, (watch::Sender<bool>, ModpackInstallRequest)>>,
+}
+
+impl ModpackInstalls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn start(&self, instance_dir: &str, request: ModpackInstallRequest) -> Result<watch::Receiver<bool>, String> {
+        let mut cancellations = self.cancellations.lock().unwrap();
+        if cancellations.contains_key(instance_dir) {
+            return Err(format!("A modpack is already being installed into {}", instance_dir));
+        }
+        let (sender, cancel) = watch::channel(false);
+        cancellations.insert(instance_dir.to_string(), (sender, request));
+        Ok(cancel)
+    }
+
+    /// Installs that haven't finished yet
+    pub fn active(&self) -> Vec<ModpackInstallRequest> {
+        self.cancellations.lock().unwrap().values().map(|(_, request)| request.clone()).collect()
+    }
+
+    pub fn cancel_all(&self) {
+        for (sender, _) in self.cancellations.lock().unwrap().values() {
+            let _ = sender.send(true);
+        }
+    }
+
+    fn finish(&self, instance_dir: &str) {
+        self.cancellations.lock().unwrap().remove(instance_dir);
+    }
+
+    /// Returns false when no install is running for the directory
+    fn cancel(&self, instance_dir: &str) -> bool {
+        match self.cancellations.lock().unwrap().get(instance_dir) {
+            Some((sender, _)) => {
+                let _ = sender.send(true);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Pack files downloaded at once, from the launcher settings
+async fn download_concurrency() -> u32 {
+    match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().modpack_download_concurrency,
+        Err(_) => DEFAULT_DOWNLOAD_CONCURRENCY,
+    }
+}
 
 /// Search for modpacks
 #[command]
@@ -39,32 +112,246 @@ pub async fn get_modpack_versions(
 }
 
 /// Install a modpack
+///
+/// For CurseForge `project_id` and `version_id` are the numeric project and file ids.
+/// A cancelled install removes the instance directory it created and fails with
+/// the `cancelled` error kind. An install cancelled by the launcher exiting keeps
+/// its files for `previous_session_interrupted` to resume.
 #[command]
 pub async fn install_modpack(
     project_id: String,
     version_id: String,
     instance_name: String,
     instance_dir: String,
+    platform: Option<String>,
+    installs: State<'_, ModpackInstallsState>,
     app_handle: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<ModpackInstallReport, ModpackInstallError> {
     let instance_path = PathBuf::from(&instance_dir).join(&instance_name);
-    let installer = ModpackInstaller::new(instance_path.clone());
+    let platform = platform.unwrap_or_else(|| "modrinth".to_string());
+    if !matches!(platform.as_str(), "modrinth" | "curseforge") {
+        return Err("Unsupported platform".to_string().into());
+    }
+
+    // Progress events report the instance path, so cancelling uses the same key
+    let instance_key = instance_path.to_string_lossy().to_string();
+    let cancel = installs.start(&instance_key, ModpackInstallRequest {
+        project_id: project_id.clone(),
+        version_id: version_id.clone(),
+        instance_name: instance_name.clone(),
+        instance_dir: instance_dir.clone(),
+        platform: platform.clone(),
+    })?;
+    let created_dir = !instance_path.exists();
+    let installer = ModpackInstaller::new(instance_path.clone())
+        .with_cancel(cancel)
+        .with_concurrency(download_concurrency().await);
+    let emit = |progress: ModpackInstallProgress| {
+        let _ = app_handle.emit("modpack_install_progress", progress);
+    };
+
+    let started = Instant::now();
+    let result = match platform.as_str() {
+        "curseforge" => install_curseforge(&installer, &project_id, &version_id, emit).await,
+        _ => install_modrinth(&installer, &project_id, &version_id, emit).await,
+    };
+    installs.finish(&instance_key);
+
+    if !result.as_ref().is_err_and(is_cancelled) {
+        let (title, body) = match &result {
+            Ok(_) => ("Modpack installed", instance_name.clone()),
+            Err(e) => ("Modpack install failed", format!("{}: {:#}", instance_name, e)),
+        };
+        let target = NotificationTarget::View { view: "instances".to_string() };
+        notifications::send_if_slow(&app_handle, started, LauncherNotification::new(NotificationCategory::Installs, title, &body, Some(target))).await;
+    }
+
+    match result {
+        Ok(report) => {
+            println!("✅ Modpack {} installed successfully to: {}", project_id, instance_path.display());
+            Ok(report)
+        }
+        Err(e) if is_cancelled(&e) => {
+            println!("🛑 Modpack installation into {} cancelled", instance_path.display());
+            // Keep what was downloaded when the launcher exits, so the install can be resumed
+            if created_dir && !crate::shutdown::is_shutting_down() {
+                let _ = tokio::fs::remove_dir_all(&instance_path).await;
+            }
+            emit(ModpackInstallProgress {
+                instance_dir: instance_key,
+                progress: 0.0,
+                stage: "cancelled".to_string(),
+            });
+            Err(ModpackInstallError::cancelled())
+        }
+        Err(e) => Err(format!("Failed to install modpack: {:#}", e).into()),
+    }
+}
+
+/// Update a Modrinth pack instance to another version of the pack
+///
+/// Only files whose path or SHA1 changed since the installed version are
+/// downloaded, and files the new version dropped are removed. Progress goes out
+/// as `modpack_install_progress` and `cancel_modpack_install` stops it; a
+/// cancelled update leaves the files fetched so far and can simply be run again.
+#[command]
+pub async fn update_modpack_instance(
+    instance_id: String,
+    project_id: String,
+    version_id: String,
+    installs: State<'_, ModpackInstallsState>,
+    sessions: State<'_, crate::minecraft::sessions::PlaySessionsState>,
+    storage: State<'_, crate::storage::StorageState>,
+    app_handle: tauri::AppHandle,
+) -> Result<ModpackUpdateReport, ModpackInstallError> {
+    let instance = storage.read().await.get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+    if sessions.is_running(&instance_id) {
+        return Err("Close the game before updating its modpack".to_string().into());
+    }
+
+    let instance_key = instance.game_dir.to_string_lossy().to_string();
+    let cancel = installs.start(&instance_key, ModpackInstallRequest {
+        project_id: project_id.clone(),
+        version_id: version_id.clone(),
+        instance_name: instance.name.clone(),
+        instance_dir: instance.game_dir.parent().map(|dir| dir.to_string_lossy().to_string()).unwrap_or_default(),
+        platform: "modrinth".to_string(),
+    })?;
+    let installer = ModpackInstaller::new(instance.game_dir.clone())
+        .with_cancel(cancel)
+        .with_concurrency(download_concurrency().await);
+    let emit = |progress: ModpackInstallProgress| {
+        let _ = app_handle.emit("modpack_install_progress", progress);
+    };
+
+    let started = Instant::now();
+    let result = update_modrinth(&installer, &project_id, &version_id, emit).await;
+    installs.finish(&instance_key);
+
+    if !result.as_ref().is_err_and(is_cancelled) {
+        let (title, body) = match &result {
+            Ok(_) => ("Modpack updated", instance.name.clone()),
+            Err(e) => ("Modpack update failed", format!("{}: {:#}", instance.name, e)),
+        };
+        let target = NotificationTarget::View { view: "instances".to_string() };
+        notifications::send_if_slow(&app_handle, started, LauncherNotification::new(NotificationCategory::Installs, title, &body, Some(target))).await;
+    }
+
+    match result {
+        Ok((report, version_number)) => {
+            println!("✅ Modpack of {} updated to {}: {} downloaded of {} bytes",
+                instance.name, version_number, report.downloaded_bytes, report.full_download_bytes);
+            let mut storage = storage.write().await;
+            if let Some(mut updated) = storage.get_instance(&instance_id).cloned() {
+                updated.modpack_version = Some(version_number);
+                storage.update_instance(updated).await
+                    .map_err(|e| format!("Modpack updated, but saving the instance failed: {}", e))?;
+            }
+            drop(storage);
+            crate::minecraft::commands::instances::notify_instances_updated(&app_handle);
+            Ok(report)
+        }
+        Err(e) if is_cancelled(&e) => {
+            println!("🛑 Modpack update of {} cancelled", instance.name);
+            emit(ModpackInstallProgress {
+                instance_dir: instance_key,
+                progress: 0.0,
+                stage: "cancelled".to_string(),
+            });
+            Err(ModpackInstallError::cancelled())
+        }
+        Err(e) => Err(format!("Failed to update modpack: {:#}", e).into()),
+    }
+}
+
+/// Cancel a running `install_modpack`, `instance_dir` is the path its progress events report
+#[command]
+pub async fn cancel_modpack_install(
+    instance_dir: String,
+    installs: State<'_, ModpackInstallsState>,
+) -> Result<(), String> {
+    if installs.cancel(&instance_dir) {
+        println!("🛑 Cancelling modpack installation into {}", instance_dir);
+        Ok(())
+    } else {
+        Err(format!("No modpack installation is running for {}", instance_dir))
+    }
+}
+
+async fn install_modrinth(
+    installer: &ModpackInstaller,
+    project_id: &str,
+    version_id: &str,
+    progress_callback: impl Fn(ModpackInstallProgress) + Send + Sync,
+) -> anyhow::Result<ModpackInstallReport> {
+    let versions = installer.get_modpack_versions(project_id).await?;
+    let version = versions.into_iter()
+        .find(|v| v.id == version_id)
+        .ok_or_else(|| anyhow::anyhow!("Modpack version not found"))?;
+
+    installer.download_and_install_modpack(&version, progress_callback).await
+}
+
+/// Returns the report and the version number the instance now has
+async fn update_modrinth(
+    installer: &ModpackInstaller,
+    project_id: &str,
+    version_id: &str,
+    progress_callback: impl Fn(ModpackInstallProgress) + Send + Sync,
+) -> anyhow::Result<(ModpackUpdateReport, String)> {
+    let versions = installer.get_modpack_versions(project_id).await?;
+    let version = versions.into_iter()
+        .find(|v| v.id == version_id)
+        .ok_or_else(|| anyhow::anyhow!("Modpack version not found"))?;
+
+    let report = installer.update_modpack(&version, progress_callback).await?;
+    Ok((report, version.version_number))
+}
+
+async fn install_curseforge(
+    installer: &ModpackInstaller,
+    project_id: &str,
+    file_id: &str,
+    progress_callback: impl Fn(ModpackInstallProgress) + Send + Sync,
+) -> anyhow::Result<ModpackInstallReport> {
+    let project_id: u64 = project_id.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid CurseForge project id: {}", project_id))?;
+    let file_id: u64 = file_id.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid CurseForge file id: {}", file_id))?;
+
+    installer.install_curseforge_pack(project_id, file_id, progress_callback).await
+}
+
+/// Prepare a runnable local server from a Modrinth pack
+#[command]
+pub async fn install_local_server(
+    project_id: String,
+    version_id: String,
+    dest_dir: String,
+    app_handle: tauri::AppHandle,
+) -> Result<LocalServerInstallResult, String> {
+    let installer = ModpackInstaller::new(PathBuf::from(&dest_dir))
+        .with_concurrency(download_concurrency().await);
 
-    // Get the specific version
     let versions = installer.get_modpack_versions(&project_id).await
         .map_err(|e| format!("Failed to get modpack versions: {}", e))?;
-    
+
     let version = versions.into_iter()
         .find(|v| v.id == version_id)
         .ok_or_else(|| "Modpack version not found".to_string())?;
 
-    // Install with progress reporting
-    installer.download_and_install_modpack(&version, |progress| {
+    installer.install_local_server(&version, |progress| {
         let _ = app_handle.emit("modpack_install_progress", progress);
-    }).await.map_err(|e| format!("Failed to install modpack: {}", e))?;
+    }).await.map_err(|e| format!("Failed to install server pack: {:#}", e))
+}
 
-    println!("✅ Modpack '{}' installed successfully to: {}", version.name, instance_path.display());
-    Ok(())
+/// Accept the Minecraft EULA for a local server directory
+#[command]
+pub async fn accept_server_eula(dest_dir: String) -> Result<(), String> {
+    super::server::accept_eula(&PathBuf::from(dest_dir)).await
+        .map_err(|e| format!("Failed to accept EULA: {}", e))
 }
 
 /// Create a modpack from an existing instance
@@ -72,7 +359,7 @@ pub async fn install_modpack(
 pub async fn create_modpack(
     request: ModpackCreationRequest,
     app_handle: tauri::AppHandle,
-) -> Result<String, String> {
+) -> Result<ModpackCreationResult, String> {
     let creator = ModpackCreator::new();
     let instance_id = request.instance_id.clone(); // Clone before moving into closure
     
@@ -86,7 +373,7 @@ pub async fn create_modpack(
     }).await;
     
     match result {
-        Ok(modpack_path) => Ok(modpack_path),
+        Ok(created) => Ok(created),
         Err(e) => Err(format!("Failed to create modpack: {}", e))
     }
 }
\ No newline at end of file
@@ -0,0 +1,304 @@
+//! CurseForge modpack installation
+//!
+//! CurseForge packs ship a manifest.json listing project/file id pairs plus an
+//! overrides folder. Files are resolved through the CurseForge API, which needs
+//! the API key from the launcher settings.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use crate::mods::api::{CurseForgeApi, ModApi};
+use crate::mods::loaders::ModLoaderManager;
+use crate::mods::types::{ModFile, ModLoader};
+
+use super::overrides::{extract_archive, OverrideNormalizer};
+use super::types::*;
+
+#[derive(Debug, Deserialize)]
+struct CurseForgeManifest {
+    minecraft: ManifestMinecraft,
+    #[serde(default)]
+    files: Vec<ManifestFile>,
+    #[serde(default = "default_overrides")]
+    overrides: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestMinecraft {
+    version: String,
+    #[serde(default)]
+    mod_loaders: Vec<ManifestLoader>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestLoader {
+    id: String,
+    #[serde(default)]
+    primary: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestFile {
+    #[serde(rename = "projectID")]
+    project_id: u64,
+    #[serde(rename = "fileID")]
+    file_id: u64,
+    #[serde(default = "default_required")]
+    required: bool,
+}
+
+fn default_overrides() -> String {
+    "overrides".to_string()
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl CurseForgeManifest {
+    /// The primary entry of `modLoaders`, or the first one
+    fn loader(&self) -> Option<ModLoader> {
+        self.minecraft.mod_loaders.iter()
+            .find(|loader| loader.primary)
+            .or_else(|| self.minecraft.mod_loaders.first())
+            .and_then(|loader| parse_loader_id(&loader.id))
+    }
+}
+
+/// Loader from a manifest id such as `forge-47.2.0` or `fabric-0.15.11`
+fn parse_loader_id(id: &str) -> Option<ModLoader> {
+    let (name, version) = id.split_once('-')?;
+    let version = version.to_string();
+    match name.to_lowercase().as_str() {
+        "forge" => Some(ModLoader::Forge(version)),
+        "neoforge" => Some(ModLoader::NeoForge(version)),
+        "fabric" => Some(ModLoader::Fabric(version)),
+        "quilt" => Some(ModLoader::Quilt(version)),
+        _ => None,
+    }
+}
+
+impl ModpackInstaller {
+    /// Install a CurseForge modpack file into the instance directory
+    ///
+    /// Files whose authors block third-party downloads are listed in the report
+    /// instead of failing the install.
+    pub async fn install_curseforge_pack(
+        &self,
+        project_id: u64,
+        file_id: u64,
+        progress_callback: impl Fn(ModpackInstallProgress) + Send + Sync,
+    ) -> Result<ModpackInstallReport> {
+        let report = |progress: f64, stage: &str| {
+            progress_callback(ModpackInstallProgress {
+                instance_dir: self.instance_dir.to_string_lossy().to_string(),
+                progress,
+                stage: stage.to_string(),
+            });
+        };
+
+        report(0.0, "Starting modpack installation");
+
+        let api = CurseForgeApi::new();
+        let pack_file = api.get_mod_file(&project_id.to_string(), &file_id.to_string()).await
+            .context("Failed to get modpack file")?;
+        if pack_file.download_url.is_empty() {
+            return Err(anyhow::anyhow!(
+                "The author of {} does not allow downloads from other launchers",
+                pack_file.filename
+            ));
+        }
+
+        // The archive is kept until the install succeeds so a rerun can reuse it
+        let temp_file = std::env::temp_dir().join(&pack_file.filename);
+        let mut counts = DownloadCounts::default();
+        let installed = self.install_curseforge_archive(&api, &pack_file, &temp_file, &mut counts, &report).await;
+        if installed.as_ref().map_or_else(is_cancelled, |_| true) {
+            let _ = tokio::fs::remove_file(&temp_file).await;
+        }
+        let (warnings, manual_downloads) = installed?;
+
+        for warning in &warnings {
+            println!("⚠️  {}", warning);
+        }
+
+        report(100.0, &format!("Modpack installation complete: {}", counts.summary()));
+
+        Ok(ModpackInstallReport {
+            instance_dir: self.instance_dir.to_string_lossy().to_string(),
+            warnings,
+            manual_downloads,
+        })
+    }
+
+    /// Download and extract the pack, fetch its files and install the loader
+    ///
+    /// Returns the warnings and the files that must be downloaded by hand.
+    async fn install_curseforge_archive(
+        &self,
+        api: &CurseForgeApi,
+        pack_file: &ModFile,
+        temp_file: &Path,
+        counts: &mut DownloadCounts,
+        report: &impl Fn(f64, &str),
+    ) -> Result<(Vec<String>, Vec<ManualDownload>)> {
+        report(10.0, "Downloading modpack");
+
+        self.download_verified(&pack_file.download_url, temp_file, Some(pack_file.size), pack_file.hashes.get("sha1").cloned(), counts).await
+            .context("Failed to download modpack")?;
+        self.check_cancelled()?;
+
+        report(20.0, "Extracting overrides");
+
+        let (manifest, mut warnings) = self.extract_curseforge_pack(temp_file).await?;
+
+        report(30.0, "Resolving mod files");
+
+        let file_ids: Vec<u64> = manifest.files.iter()
+            .filter(|file| file.required)
+            .map(|file| file.file_id)
+            .collect();
+        let files = api.get_files(&file_ids).await
+            .context("Failed to get modpack files from CurseForge")?;
+
+        let resolved: HashMap<&str, _> = files.iter().map(|file| (file.id.as_str(), file)).collect();
+        let mods_dir = self.instance_dir.join("mods");
+        let mut blocked = Vec::new();
+        let total = manifest.files.iter().filter(|file| file.required).count().max(1);
+
+        for (index, entry) in manifest.files.iter().filter(|file| file.required).enumerate() {
+            self.check_cancelled()?;
+
+            let Some(file) = resolved.get(entry.file_id.to_string().as_str()) else {
+                warnings.push(format!("CurseForge file {} of project {} no longer exists", entry.file_id, entry.project_id));
+                continue;
+            };
+
+            if file.download_url.is_empty() {
+                blocked.push((entry, file.filename.clone()));
+            } else {
+                report(30.0 + 60.0 * index as f64 / total as f64, &format!("Downloading {}", file.filename));
+                self.download_verified(&file.download_url, &mods_dir.join(&file.filename), Some(file.size), file.hashes.get("sha1").cloned(), counts).await
+                    .with_context(|| format!("Failed to download {}", file.filename))?;
+            }
+        }
+
+        let manual_downloads = self.manual_downloads(api, blocked).await;
+        if !manual_downloads.is_empty() {
+            println!("⚠️  {} files must be downloaded manually from CurseForge", manual_downloads.len());
+        }
+
+        self.check_cancelled()?;
+        report(90.0, "Installing mod loader");
+
+        match manifest.loader() {
+            Some(loader) => {
+                ModLoaderManager::new(self.instance_dir.clone())
+                    .install_loader(&loader, &manifest.minecraft.version).await
+                    .with_context(|| format!("Failed to install {} {}", loader.name(), loader.version()))?;
+            }
+            None if !manifest.minecraft.mod_loaders.is_empty() => {
+                warnings.push(format!("Unsupported mod loader: {}", manifest.minecraft.mod_loaders[0].id));
+            }
+            None => {}
+        }
+
+        Ok((warnings, manual_downloads))
+    }
+
+    /// Read manifest.json and extract the overrides folder into the instance
+    async fn extract_curseforge_pack(&self, pack_path: &Path) -> Result<(CurseForgeManifest, Vec<String>)> {
+        let pack_path = pack_path.to_path_buf();
+        let instance_dir = self.instance_dir.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<(CurseForgeManifest, Vec<String>)> {
+            let file = std::fs::File::open(&pack_path)
+                .context("Failed to open modpack archive")?;
+            let mut archive = zip::ZipArchive::new(file)
+                .context("Failed to read modpack archive")?;
+
+            let manifest: CurseForgeManifest = {
+                let mut manifest_file = archive.by_name("manifest.json")
+                    .context("Modpack is missing manifest.json")?;
+                let mut content = String::new();
+                manifest_file.read_to_string(&mut content)
+                    .context("Failed to read manifest.json")?;
+                serde_json::from_str(&content)
+                    .context("Failed to parse manifest.json")?
+            };
+
+            std::fs::create_dir_all(&instance_dir)
+                .context("Failed to create instance directory")?;
+            let mut normalizer = OverrideNormalizer::new();
+            extract_archive(&mut archive, &instance_dir, Some(&format!("{}/", manifest.overrides)), &mut normalizer)?;
+
+            Ok((manifest, normalizer.warnings()))
+        }).await.map_err(|e| anyhow::anyhow!("Task join error: {}", e))?
+    }
+
+    /// Turn blocked files into links to their CurseForge pages
+    async fn manual_downloads(&self, api: &CurseForgeApi, blocked: Vec<(&ManifestFile, String)>) -> Vec<ManualDownload> {
+        let project_ids: Vec<u64> = blocked.iter().map(|(entry, _)| entry.project_id).collect();
+        // Without the project pages the links fall back to the file id alone
+        let pages: HashMap<String, String> = api.get_mods(&project_ids).await
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|project| Some((project.id, project.website_url?)))
+            .collect();
+
+        blocked.into_iter()
+            .map(|(entry, file_name)| {
+                let url = match pages.get(&entry.project_id.to_string()) {
+                    Some(page) => format!("{}/files/{}", page.trim_end_matches('/'), entry.file_id),
+                    None => format!("https://www.curseforge.com/projects/{}", entry.project_id),
+                };
+                ManualDownload {
+                    project_id: entry.project_id,
+                    file_id: entry.file_id,
+                    file_name,
+                    url,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_manifest_and_primary_loader() {
+        let manifest: CurseForgeManifest = serde_json::from_str(r#"{
+            "minecraft": {
+                "version": "1.20.1",
+                "modLoaders": [
+                    { "id": "fabric-0.15.11", "primary": false },
+                    { "id": "forge-47.2.0", "primary": true }
+                ]
+            },
+            "manifestType": "minecraftModpack",
+            "files": [
+                { "projectID": 238222, "fileID": 4712868, "required": true },
+                { "projectID": 32274, "fileID": 4580212 }
+            ]
+        }"#).unwrap();
+
+        assert_eq!(manifest.overrides, "overrides");
+        assert_eq!(manifest.files.len(), 2);
+        assert!(manifest.files[1].required);
+        assert_eq!(manifest.loader(), Some(ModLoader::Forge("47.2.0".to_string())));
+    }
+
+    #[test]
+    fn parses_loader_ids() {
+        assert_eq!(parse_loader_id("neoforge-20.4.80"), Some(ModLoader::NeoForge("20.4.80".to_string())));
+        assert_eq!(parse_loader_id("quilt-0.21.0"), Some(ModLoader::Quilt("0.21.0".to_string())));
+        assert_eq!(parse_loader_id("rift-1.0"), None);
+        assert_eq!(parse_loader_id("forge"), None);
+    }
+}
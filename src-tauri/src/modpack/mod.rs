@@ -1,6 +1,11 @@
 pub mod types;
 pub mod api;
 pub mod commands;
+pub mod server;
+pub mod overrides;
+pub mod mrpack;
+pub mod curseforge;
+pub mod update;
 
 // Re-export all public items for backwards compatibility
 pub use types::*;
@@ -0,0 +1,577 @@
+//! Modrinth .mrpack index, export and file downloads
+//!
+//! Mods Modrinth hosts are linked by URL in modrinth.index.json, anything it
+//! doesn't recognise is bundled under overrides/.
+
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::mods::types::ModLoader;
+use crate::net::download::ProgressCallback;
+
+use super::types::{is_cancelled, DownloadCounts, Fetched, ModpackInstaller};
+
+/// modrinth.index.json, format version 1
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackIndex {
+    pub format_version: u32,
+    pub game: String,
+    pub version_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<MrpackIndexFile>,
+    pub dependencies: BTreeMap<String, String>,
+}
+
+/// A file the installing launcher downloads itself
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackIndexFile {
+    pub path: String,
+    pub hashes: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<MrpackEnv>,
+    pub downloads: Vec<String>,
+    pub file_size: u64,
+}
+
+/// Whether each side needs a file: `required`, `optional` or `unsupported`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MrpackEnv {
+    pub client: String,
+    pub server: String,
+}
+
+impl MrpackIndexFile {
+    pub fn supports_client(&self) -> bool {
+        self.env.as_ref().is_none_or(|env| env.client != "unsupported")
+    }
+
+    pub fn supports_server(&self) -> bool {
+        self.env.as_ref().is_none_or(|env| env.server != "unsupported")
+    }
+}
+
+/// Record of the pack version installed in an instance, kept in the instance folder
+pub const INSTALLED_PACK_FILE: &str = "chailauncher.modpack.json";
+
+/// Which pack version an instance has and the client files it listed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledPack {
+    pub project_id: String,
+    pub version_id: String,
+    pub version_number: String,
+    /// Index path to SHA1
+    pub files: BTreeMap<String, String>,
+}
+
+impl InstalledPack {
+    pub fn new(project_id: &str, version_id: &str, version_number: &str, files: &[&MrpackIndexFile]) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+            version_id: version_id.to_string(),
+            version_number: version_number.to_string(),
+            files: files.iter()
+                .map(|file| (file.path.clone(), file.hashes.get("sha1").cloned().unwrap_or_default()))
+                .collect(),
+        }
+    }
+
+    /// The record in `instance_dir`, None for instances installed before it existed
+    pub async fn read(instance_dir: &Path) -> Option<Self> {
+        let text = tokio::fs::read_to_string(instance_dir.join(INSTALLED_PACK_FILE)).await.ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    pub async fn write(&self, instance_dir: &Path) -> Result<()> {
+        tokio::fs::write(instance_dir.join(INSTALLED_PACK_FILE), serde_json::to_vec_pretty(self)?).await
+            .context("Failed to record the installed pack version")
+    }
+}
+
+/// How the client files of a new pack version compare to the installed ones
+#[derive(Debug, Default)]
+pub struct PackDiff<'a> {
+    pub added: Vec<&'a MrpackIndexFile>,
+    /// Same path, different SHA1
+    pub updated: Vec<&'a MrpackIndexFile>,
+    pub unchanged: Vec<&'a MrpackIndexFile>,
+    /// Paths the installed version had and the new one doesn't
+    pub removed: Vec<String>,
+}
+
+/// Compare index entries by path and SHA1 against the installed files
+pub fn diff_index<'a>(installed: &BTreeMap<String, String>, files: &[&'a MrpackIndexFile]) -> PackDiff<'a> {
+    let mut diff = PackDiff::default();
+    for file in files {
+        match installed.get(&file.path) {
+            None => diff.added.push(*file),
+            Some(sha1) if file.hashes.get("sha1").is_some_and(|hash| hash.eq_ignore_ascii_case(sha1)) => diff.unchanged.push(*file),
+            Some(_) => diff.updated.push(*file),
+        }
+    }
+    let listed: HashSet<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    diff.removed = installed.keys().filter(|path| !listed.contains(path.as_str())).cloned().collect();
+    diff
+}
+
+/// Read modrinth.index.json from a pack archive
+pub fn read_index<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> Result<MrpackIndex> {
+    let mut index_file = archive.by_name("modrinth.index.json")
+        .context("Modpack is missing modrinth.index.json")?;
+    let mut content = String::new();
+    index_file.read_to_string(&mut content)
+        .context("Failed to read modrinth.index.json")?;
+    serde_json::from_str(&content)
+        .context("Failed to parse modrinth.index.json")
+}
+
+/// A jar from the instance's mods folder
+#[derive(Debug, Clone)]
+pub struct LocalJar {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub sha1: String,
+}
+
+/// Index dependencies for the Minecraft version and the instance's loader
+pub fn pack_dependencies(minecraft_version: &str, loader: Option<&ModLoader>) -> Result<BTreeMap<String, String>> {
+    let mut dependencies = BTreeMap::from([("minecraft".to_string(), minecraft_version.to_string())]);
+
+    if let Some(loader) = loader {
+        let key = match loader {
+            ModLoader::Forge(_) => "forge",
+            ModLoader::NeoForge(_) => "neoforge",
+            ModLoader::Fabric(_) => "fabric-loader",
+            ModLoader::Quilt(_) => "quilt-loader",
+            other => return Err(anyhow::anyhow!("{} can't be described in a .mrpack", other.name())),
+        };
+        dependencies.insert(key.to_string(), loader.version().to_string());
+    }
+
+    Ok(dependencies)
+}
+
+/// Split jars into index entries for files Modrinth hosts and jars that must be bundled
+///
+/// `versions` is Modrinth's version_files response, keyed by SHA1.
+pub fn link_mods(jars: Vec<LocalJar>, versions: &HashMap<String, serde_json::Value>) -> (Vec<MrpackIndexFile>, Vec<LocalJar>) {
+    let mut files = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for jar in jars {
+        match versions.get(&jar.sha1).and_then(|version| hosted_file(version, &jar)) {
+            Some(file) => files.push(file),
+            None => unmatched.push(jar),
+        }
+    }
+
+    (files, unmatched)
+}
+
+/// The file of a Modrinth version that is byte for byte the local jar
+fn hosted_file(version: &serde_json::Value, jar: &LocalJar) -> Option<MrpackIndexFile> {
+    let file = version["files"].as_array()?.iter()
+        .find(|file| file["hashes"]["sha1"].as_str().is_some_and(|sha1| sha1.eq_ignore_ascii_case(&jar.sha1)))?;
+
+    Some(MrpackIndexFile {
+        path: format!("mods/{}", jar.file_name),
+        hashes: BTreeMap::from([
+            ("sha1".to_string(), jar.sha1.to_lowercase()),
+            ("sha512".to_string(), file["hashes"]["sha512"].as_str()?.to_string()),
+        ]),
+        env: None,
+        downloads: vec![file["url"].as_str()?.to_string()],
+        file_size: file["size"].as_u64()?,
+    })
+}
+
+/// Files under `dir` paired with their archive path below `canonical`
+pub fn override_files(dir: &Path, canonical: &str) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry.context("Failed to read directory entry")?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative = entry.path().strip_prefix(dir)
+            .context("Failed to get relative path")?;
+        let parts: Vec<String> = relative.components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect();
+        files.push((format!("{}/{}", canonical, parts.join("/")), entry.path().to_path_buf()));
+    }
+
+    Ok(files)
+}
+
+/// Write the index and the `(path below overrides/, source file)` pairs to a .mrpack
+pub fn write_mrpack(output_path: &Path, index: &MrpackIndex, overrides: &[(String, PathBuf)]) -> Result<()> {
+    let file = std::fs::File::create(output_path)
+        .context("Failed to create output .mrpack file")?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::<()>::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    zip.start_file("modrinth.index.json", options)
+        .context("Failed to start modrinth.index.json")?;
+    zip.write_all(serde_json::to_string_pretty(index)?.as_bytes())
+        .context("Failed to write modrinth.index.json")?;
+
+    for (archive_path, source) in overrides {
+        zip.start_file(format!("overrides/{}", archive_path), options)
+            .context("Failed to start ZIP file entry")?;
+        let content = std::fs::read(source)
+            .with_context(|| format!("Failed to read {}", source.display()))?;
+        zip.write_all(&content)
+            .context("Failed to write to ZIP")?;
+    }
+
+    zip.finish()
+        .context("Failed to finalize .mrpack archive")?;
+
+    Ok(())
+}
+
+impl ModpackInstaller {
+    /// Download the client files listed in a pack index
+    ///
+    /// Files already on disk with the listed SHA1 are kept, so rerunning a failed
+    /// install only fetches what is missing. `progress` gets the fraction done.
+    pub async fn install_modpack_mods(
+        &self,
+        index: &MrpackIndex,
+        counts: &mut DownloadCounts,
+        progress: impl Fn(f64, &str),
+    ) -> Result<()> {
+        let files: Vec<&MrpackIndexFile> = index.files.iter()
+            .filter(|file| file.supports_client())
+            .collect();
+        self.download_index_files(&files, counts, progress).await
+    }
+
+    /// Download index entries below the instance directory, `concurrency` at a time
+    ///
+    /// A failed file doesn't stop the others; once every download has finished or
+    /// run out of retries the failures are reported together. `progress` gets the
+    /// fraction of bytes done and a stage string with the current throughput.
+    pub(super) async fn download_index_files(
+        &self,
+        files: &[&MrpackIndexFile],
+        counts: &mut DownloadCounts,
+        progress: impl Fn(f64, &str),
+    ) -> Result<()> {
+        // Two entries for one path would write to the same .part file
+        let mut seen = HashSet::new();
+        let mut downloads = Vec::new();
+        for file in files {
+            if seen.insert(file.path.as_str()) {
+                downloads.push((*file, self.index_file_dest(file)?));
+            }
+        }
+
+        // Create the folders up front so parallel downloads never race on them
+        let parents: BTreeSet<&Path> = downloads.iter().filter_map(|(_, dest)| dest.parent()).collect();
+        for parent in parents {
+            tokio::fs::create_dir_all(parent).await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let total = downloads.len();
+        let total_bytes: u64 = downloads.iter().map(|(file, _)| file.file_size).sum();
+        let received = Arc::new(AtomicU64::new(0));
+        let started = Instant::now();
+
+        // Collected first, a lazy map over borrowed entries makes the command future not Send
+        let tasks: Vec<_> = downloads.iter()
+            .map(|(file, dest)| {
+                let received = received.clone();
+                async move {
+                    let result = self.download_index_file(file, dest, received).await;
+                    (*file, result)
+                }
+            })
+            .collect();
+        let mut results = stream::iter(tasks).buffer_unordered(self.concurrency.max(1));
+
+        let mut finished = 0;
+        let mut finished_bytes = 0u64;
+        let mut failed = Vec::new();
+        while let Some((file, result)) = results.next().await {
+            finished += 1;
+            finished_bytes += file.file_size;
+            match result {
+                Ok(fetched) => counts.add(fetched),
+                // Dropping the stream stops the downloads still running
+                Err(e) if is_cancelled(&e) => return Err(e),
+                Err(e) => {
+                    eprintln!("❌ Failed to download {}: {:#}", file.path, e);
+                    failed.push(file.path.as_str());
+                }
+            }
+
+            let fraction = if total_bytes > 0 {
+                finished_bytes as f64 / total_bytes as f64
+            } else {
+                finished as f64 / total as f64
+            };
+            let rate = throughput(received.load(Ordering::Relaxed), started.elapsed());
+            progress(fraction, &format!("Downloaded {}/{} files ({})", finished, total, rate));
+        }
+        self.check_cancelled()?;
+
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Failed to download {} of {} files: {}",
+                failed.len(), total, failed.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Download one index entry, returning how it got there
+    ///
+    /// Bytes received are added to `received` as they arrive, retries included.
+    async fn download_index_file(&self, file: &MrpackIndexFile, dest: &Path, received: Arc<AtomicU64>) -> Result<Fetched> {
+        let url = file.downloads.first()
+            .context("File has no download URL")?;
+
+        let last = AtomicU64::new(0);
+        let progress: ProgressCallback = Arc::new(move |written, _| {
+            // A retry that starts over reports fewer bytes, count those again
+            let previous = last.swap(written, Ordering::Relaxed);
+            received.fetch_add(written.saturating_sub(previous), Ordering::Relaxed);
+        });
+        self.fetch_verified(url, dest, Some(file.file_size), file.hashes.get("sha1").cloned(), Some(progress)).await
+            .with_context(|| format!("Failed to download {}", file.path))
+    }
+
+    /// Where an index entry goes, refusing paths that leave the instance directory
+    pub(super) fn index_file_dest(&self, file: &MrpackIndexFile) -> Result<PathBuf> {
+        self.index_path_dest(&file.path)
+    }
+
+    /// Where an index path lives in the instance, rejecting paths that leave it
+    pub(super) fn index_path_dest(&self, path: &str) -> Result<PathBuf> {
+        let relative = Path::new(path);
+        if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(anyhow::anyhow!("Refusing to write outside the instance directory: {}", path));
+        }
+        Ok(self.instance_dir.join(relative))
+    }
+}
+
+/// Average download rate, e.g. `4.2 MB/s`
+fn throughput(bytes: u64, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64().max(0.001);
+    format!("{:.1} MB/s", bytes as f64 / 1_048_576.0 / seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-mrpack-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn jar(dir: &Path, name: &str, sha1: &str) -> LocalJar {
+        let path = dir.join(name);
+        std::fs::write(&path, name).unwrap();
+        LocalJar { file_name: name.to_string(), path, sha1: sha1.to_string() }
+    }
+
+    #[test]
+    fn exported_pack_has_valid_index_and_overrides() {
+        let dir = temp_dir();
+        let jars = vec![
+            jar(&dir, "sodium.jar", "aaaa"),
+            jar(&dir, "private.jar", "bbbb"),
+        ];
+        let versions = HashMap::from([("aaaa".to_string(), serde_json::json!({
+            "files": [
+                { "hashes": { "sha1": "cccc", "sha512": "x" }, "url": "https://cdn.modrinth.com/other.jar", "size": 1 },
+                { "hashes": { "sha1": "AAAA", "sha512": "dddd" }, "url": "https://cdn.modrinth.com/sodium.jar", "size": 10 }
+            ]
+        }))]);
+
+        let (files, unmatched) = link_mods(jars, &versions);
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].file_name, "private.jar");
+
+        let index = MrpackIndex {
+            format_version: 1,
+            game: "minecraft".to_string(),
+            version_id: "1.0.0".to_string(),
+            name: "Test Pack".to_string(),
+            summary: None,
+            files,
+            dependencies: pack_dependencies("1.20.1", Some(&ModLoader::Fabric("0.15.11".to_string()))).unwrap(),
+        };
+        let overrides: Vec<(String, PathBuf)> = unmatched.iter()
+            .map(|jar| (format!("mods/{}", jar.file_name), jar.path.clone()))
+            .collect();
+        let output = dir.join("pack.mrpack");
+        write_mrpack(&output, &index, &overrides).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&output).unwrap()).unwrap();
+        assert!(archive.by_name("overrides/mods/private.jar").is_ok());
+
+        let mut content = String::new();
+        archive.by_name("modrinth.index.json").unwrap().read_to_string(&mut content).unwrap();
+        let index: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(index["formatVersion"], 1);
+        assert_eq!(index["game"], "minecraft");
+        assert_eq!(index["versionId"], "1.0.0");
+        assert_eq!(index["name"], "Test Pack");
+        assert_eq!(index["dependencies"], serde_json::json!({ "minecraft": "1.20.1", "fabric-loader": "0.15.11" }));
+
+        let files = index["files"].as_array().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0]["path"], "mods/sodium.jar");
+        assert_eq!(files[0]["hashes"], serde_json::json!({ "sha1": "aaaa", "sha512": "dddd" }));
+        assert_eq!(files[0]["downloads"], serde_json::json!(["https://cdn.modrinth.com/sodium.jar"]));
+        assert_eq!(files[0]["fileSize"], 10);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reads_index_with_env() {
+        let index: MrpackIndex = serde_json::from_str(r#"{
+            "formatVersion": 1,
+            "game": "minecraft",
+            "versionId": "1.0.0",
+            "name": "Test Pack",
+            "files": [
+                {
+                    "path": "mods/shaders.jar",
+                    "hashes": { "sha1": "aaaa", "sha512": "bbbb" },
+                    "env": { "client": "required", "server": "unsupported" },
+                    "downloads": ["https://cdn.modrinth.com/shaders.jar"],
+                    "fileSize": 10
+                },
+                {
+                    "path": "mods/lib.jar",
+                    "hashes": { "sha1": "cccc" },
+                    "downloads": ["https://cdn.modrinth.com/lib.jar"],
+                    "fileSize": 5
+                }
+            ],
+            "dependencies": { "minecraft": "1.20.1", "fabric-loader": "0.15.11" }
+        }"#).unwrap();
+
+        assert!(index.summary.is_none());
+        assert!(index.files[0].supports_client());
+        assert!(!index.files[0].supports_server());
+        assert!(index.files[1].supports_client() && index.files[1].supports_server());
+        assert_eq!(index.dependencies["fabric-loader"], "0.15.11");
+    }
+
+    fn index_file(path: &str, content: &str) -> MrpackIndexFile {
+        use sha1::{Digest, Sha1};
+        MrpackIndexFile {
+            path: path.to_string(),
+            hashes: BTreeMap::from([("sha1".to_string(), hex::encode(Sha1::digest(content)))]),
+            env: None,
+            // Never contacted, every file is already on disk
+            downloads: vec!["http://127.0.0.1:9/unused".to_string()],
+            file_size: content.len() as u64,
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_downloads_skip_present_files_and_report_progress() {
+        let dir = temp_dir();
+        for name in ["a", "b", "c"] {
+            std::fs::create_dir_all(dir.join("mods")).unwrap();
+            std::fs::write(dir.join("mods").join(format!("{}.jar", name)), name).unwrap();
+        }
+        let files = [index_file("mods/a.jar", "a"), index_file("mods/b.jar", "b"), index_file("mods/c.jar", "c")];
+        // The same path listed twice is only fetched once
+        let mut listed: Vec<&MrpackIndexFile> = files.iter().collect();
+        listed.push(&files[0]);
+
+        let installer = ModpackInstaller::new(dir.clone()).with_concurrency(2);
+        let mut counts = DownloadCounts::default();
+        let stages = std::sync::Mutex::new(Vec::new());
+        installer.download_index_files(&listed, &mut counts, |fraction, stage| {
+            stages.lock().unwrap().push((fraction, stage.to_string()));
+        }).await.unwrap();
+
+        assert_eq!(counts.skipped, 3);
+        assert_eq!(counts.downloaded, 0);
+        let stages = stages.into_inner().unwrap();
+        assert_eq!(stages.len(), 3);
+        assert_eq!(stages[2].0, 1.0);
+        assert!(stages[2].1.starts_with("Downloaded 3/3 files ("));
+        assert!(stages[2].1.ends_with(" MB/s)"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn index_diff_compares_paths_and_hashes() {
+        let old_files = [index_file("mods/a.jar", "a"), index_file("mods/b.jar", "b"), index_file("mods/gone.jar", "gone")];
+        let old: Vec<&MrpackIndexFile> = old_files.iter().collect();
+        let installed = InstalledPack::new("pack", "v1", "1.0", &old);
+
+        let new_files = [index_file("mods/a.jar", "a"), index_file("mods/b.jar", "b2"), index_file("mods/new.jar", "new")];
+        let new: Vec<&MrpackIndexFile> = new_files.iter().collect();
+        let diff = diff_index(&installed.files, &new);
+
+        let paths = |files: &[&MrpackIndexFile]| files.iter().map(|file| file.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&diff.unchanged), vec!["mods/a.jar"]);
+        assert_eq!(paths(&diff.updated), vec!["mods/b.jar"]);
+        assert_eq!(paths(&diff.added), vec!["mods/new.jar"]);
+        assert_eq!(diff.removed, vec!["mods/gone.jar"]);
+    }
+
+    #[tokio::test]
+    async fn parallel_downloads_reject_paths_outside_instance() {
+        let dir = temp_dir();
+        let files = [index_file("mods/a.jar", "a"), index_file("../escape.jar", "b")];
+        let listed: Vec<&MrpackIndexFile> = files.iter().collect();
+
+        let installer = ModpackInstaller::new(dir.join("instance"));
+        let err = installer.download_index_files(&listed, &mut DownloadCounts::default(), |_, _| {}).await.unwrap_err();
+        assert!(err.to_string().contains("outside the instance directory"));
+        // Nothing is created before every path is checked
+        assert!(!dir.join("instance").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn throughput_is_megabytes_per_second() {
+        assert_eq!(throughput(3 * 1_048_576, Duration::from_secs(2)), "1.5 MB/s");
+        assert_eq!(throughput(0, Duration::ZERO), "0.0 MB/s");
+    }
+
+    #[test]
+    fn override_files_use_forward_slashes() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(dir.join("Config").join("sub")).unwrap();
+        std::fs::write(dir.join("Config").join("sub").join("mod.toml"), "a = 1").unwrap();
+
+        let files = override_files(&dir.join("Config"), "config").unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, "config/sub/mod.toml");
+        assert!(pack_dependencies("1.12.2", Some(&ModLoader::Rift("1.0".to_string()))).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
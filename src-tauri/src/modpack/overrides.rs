@@ -0,0 +1,362 @@
+use anyhow::{Result, Context};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+/// Top-level instance directories that mods expect in lowercase
+pub const CANONICAL_DIRS: &[&str] = &[
+    "config",
+    "mods",
+    "resourcepacks",
+    "shaderpacks",
+    "saves",
+    "scripts",
+    "defaultconfigs",
+];
+
+/// Archive roots whose children are instance-relative paths
+const OVERRIDE_ROOTS: &[&str] = &["overrides", "client-overrides", "server-overrides"];
+
+/// Canonicalizes the casing of known directories so `Config/` and `config/` end up in one place
+///
+/// On Windows differently cased directories merge on their own, on Linux they would
+/// produce two directories and mods only read one of them.
+#[derive(Debug, Default)]
+pub struct OverrideNormalizer {
+    /// Spellings seen for each canonical directory
+    spellings: BTreeMap<&'static str, BTreeSet<String>>,
+    /// Normalized file path -> original path that was written there
+    written: HashMap<PathBuf, PathBuf>,
+    conflicts: Vec<String>,
+}
+
+impl OverrideNormalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rewrite a relative path so a known top-level directory uses its canonical name
+    ///
+    /// Paths under an override root like `overrides/` are normalized below that root.
+    pub fn normalize(&mut self, relative: &Path) -> PathBuf {
+        let mut components: Vec<String> = relative.components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let index = match components.first() {
+            Some(first) if OVERRIDE_ROOTS.contains(&first.as_str()) => 1,
+            _ => 0,
+        };
+
+        // Only directories are normalized, a bare top-level file named like one is left alone
+        if index + 1 < components.len() {
+            if let Some(canonical) = canonical_dir(&components[index]) {
+                self.spellings.entry(canonical).or_default().insert(components[index].clone());
+                components[index] = canonical.to_string();
+            }
+        }
+
+        components.iter().collect()
+    }
+
+    /// Record a file about to be written and decide whether it should be
+    ///
+    /// When two spellings contain the same file, the canonically cased one wins.
+    pub fn should_write(&mut self, normalized: &Path, original: &Path) -> bool {
+        let Some(existing) = self.written.get(normalized) else {
+            self.written.insert(normalized.to_path_buf(), original.to_path_buf());
+            return true;
+        };
+
+        // Same source path, e.g. server-overrides replacing overrides
+        if existing == original {
+            return true;
+        }
+
+        let existing = existing.as_path();
+        let replace = is_canonical(original) && !is_canonical(existing);
+        let (kept, dropped) = if replace { (original, existing) } else { (existing, original) };
+        self.conflicts.push(format!(
+            "{} and {} both exist; kept {}",
+            dropped.display(), kept.display(), kept.display()
+        ));
+
+        if replace {
+            self.written.insert(normalized.to_path_buf(), original.to_path_buf());
+        }
+        replace
+    }
+
+    /// Warnings for the install report, one per merged directory and per conflicting file
+    pub fn warnings(&self) -> Vec<String> {
+        let merges = self.spellings.iter()
+            .filter(|(_, spellings)| spellings.len() > 1)
+            .map(|(canonical, spellings)| {
+                let spellings: Vec<&str> = spellings.iter().map(String::as_str).collect();
+                format!("Merged directories {} into {}/", spellings.join(", "), canonical)
+            });
+
+        merges.chain(self.conflicts.iter().cloned()).collect()
+    }
+}
+
+/// Canonical name for a directory that matches one case-insensitively
+pub fn canonical_dir(name: &str) -> Option<&'static str> {
+    CANONICAL_DIRS.iter().copied().find(|dir| dir.eq_ignore_ascii_case(name))
+}
+
+/// Whether the first directory below any override root is already canonically cased
+fn is_canonical(path: &Path) -> bool {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .find(|name| !OVERRIDE_ROOTS.contains(name))
+        .is_none_or(|name| canonical_dir(name).is_none_or(|canonical| canonical == name))
+}
+
+/// Files `extract_archive` wrote and those already on disk with the same contents
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ExtractedFiles {
+    pub written: u32,
+    pub unchanged: u32,
+}
+
+/// Extract archive entries into `dest_dir`, canonicalizing known directory names
+///
+/// With a `prefix`, only entries below it are extracted and the prefix is stripped.
+/// Files already on disk with the same contents are left untouched, so a pack
+/// update only rewrites the overrides that changed.
+pub fn extract_archive<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    dest_dir: &Path,
+    prefix: Option<&str>,
+    normalizer: &mut OverrideNormalizer,
+) -> Result<ExtractedFiles> {
+    let mut extracted = ExtractedFiles::default();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)
+            .context("Failed to read file from archive")?;
+
+        let relative = match (file.enclosed_name(), prefix) {
+            (Some(path), Some(prefix)) => match path.strip_prefix(prefix) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => continue,
+            },
+            (Some(path), None) => path,
+            (None, _) => continue,
+        };
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+
+        let normalized = normalizer.normalize(&relative);
+        let outpath = dest_dir.join(&normalized);
+
+        if file.is_dir() {
+            std::fs::create_dir_all(&outpath)
+                .context("Failed to create directory")?;
+        } else {
+            if !normalizer.should_write(&normalized, &relative) {
+                continue;
+            }
+
+            if let Some(p) = outpath.parent() {
+                std::fs::create_dir_all(p)
+                    .context("Failed to create parent directory")?;
+            }
+
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)
+                .context("Failed to read file content")?;
+            if is_same_file(&outpath, &buffer) {
+                extracted.unchanged += 1;
+                continue;
+            }
+            std::fs::write(&outpath, &buffer)
+                .context("Failed to write extracted file")?;
+            extracted.written += 1;
+        }
+    }
+
+    Ok(extracted)
+}
+
+/// Whether `path` already holds exactly `contents`, sizes are compared before reading
+fn is_same_file(path: &Path, contents: &[u8]) -> bool {
+    let same_size = std::fs::metadata(path).is_ok_and(|metadata| metadata.len() == contents.len() as u64);
+    same_size && std::fs::read(path).is_ok_and(|existing| existing == contents)
+}
+
+/// Top-level entries of `instance_dir` that match `canonical` case-insensitively
+///
+/// The canonically cased directory is returned last so its files win when copied in order.
+pub fn matching_dirs(instance_dir: &Path, canonical: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(instance_dir) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(canonical))
+        .map(|entry| entry.path())
+        .collect();
+
+    dirs.sort_by_key(|dir| dir.file_name().is_some_and(|name| name == canonical));
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-overrides-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn build_archive(entries: &[(&str, &str)]) -> zip::ZipArchive<Cursor<Vec<u8>>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::<()>::default();
+        for (name, content) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        zip::ZipArchive::new(writer.finish().unwrap()).unwrap()
+    }
+
+    fn top_level_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn merges_differently_cased_config_dirs() {
+        let dir = temp_dir();
+        let mut archive = build_archive(&[
+            ("overrides/Config/upper.toml", "upper"),
+            ("overrides/config/lower.toml", "lower"),
+            ("overrides/MODS/extra.jar", "jar"),
+        ]);
+
+        let mut normalizer = OverrideNormalizer::new();
+        extract_archive(&mut archive, &dir, Some("overrides/"), &mut normalizer).unwrap();
+
+        assert_eq!(top_level_names(&dir), vec!["config", "mods"]);
+        assert_eq!(top_level_names(&dir.join("config")), vec!["lower.toml", "upper.toml"]);
+        assert!(dir.join("mods").join("extra.jar").exists());
+
+        let warnings = normalizer.warnings();
+        assert_eq!(warnings, vec!["Merged directories Config, config into config/"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn canonical_file_wins_conflicts() {
+        let dir = temp_dir();
+        let mut archive = build_archive(&[
+            ("overrides/config/shared.toml", "canonical"),
+            ("overrides/CONFIG/shared.toml", "shouting"),
+        ]);
+
+        let mut normalizer = OverrideNormalizer::new();
+        extract_archive(&mut archive, &dir, Some("overrides/"), &mut normalizer).unwrap();
+
+        assert_eq!(top_level_names(&dir), vec!["config"]);
+        let content = std::fs::read_to_string(dir.join("config").join("shared.toml")).unwrap();
+        assert_eq!(content, "canonical");
+        assert_eq!(normalizer.warnings().len(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn normalizes_below_override_root_when_extracting_whole_archive() {
+        let dir = temp_dir();
+        let mut archive = build_archive(&[
+            ("overrides/Config/a.toml", "a"),
+            ("overrides/config/b.toml", "b"),
+            ("Saves/world/level.dat", "level"),
+        ]);
+
+        let mut normalizer = OverrideNormalizer::new();
+        extract_archive(&mut archive, &dir, None, &mut normalizer).unwrap();
+
+        assert_eq!(top_level_names(&dir), vec!["overrides", "saves"]);
+        assert_eq!(top_level_names(&dir.join("overrides")), vec!["config"]);
+        assert_eq!(top_level_names(&dir.join("overrides").join("config")), vec!["a.toml", "b.toml"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn server_overrides_replace_overrides_without_warning() {
+        let dir = temp_dir();
+        let mut archive = build_archive(&[
+            ("overrides/config/server.toml", "client"),
+            ("server-overrides/config/server.toml", "server"),
+        ]);
+
+        let mut normalizer = OverrideNormalizer::new();
+        for prefix in ["overrides/", "server-overrides/"] {
+            extract_archive(&mut archive, &dir, Some(prefix), &mut normalizer).unwrap();
+        }
+
+        let content = std::fs::read_to_string(dir.join("config").join("server.toml")).unwrap();
+        assert_eq!(content, "server");
+        assert!(normalizer.warnings().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unchanged_files_are_not_rewritten() {
+        let dir = temp_dir();
+        let mut normalizer = OverrideNormalizer::new();
+        let mut first = build_archive(&[("overrides/config/a.toml", "a"), ("overrides/config/b.toml", "b")]);
+        let extracted = extract_archive(&mut first, &dir, Some("overrides/"), &mut normalizer).unwrap();
+        assert_eq!(extracted, ExtractedFiles { written: 2, unchanged: 0 });
+
+        let mut normalizer = OverrideNormalizer::new();
+        let mut second = build_archive(&[("overrides/config/a.toml", "a"), ("overrides/config/b.toml", "b2")]);
+        let extracted = extract_archive(&mut second, &dir, Some("overrides/"), &mut normalizer).unwrap();
+        assert_eq!(extracted, ExtractedFiles { written: 1, unchanged: 1 });
+        assert_eq!(std::fs::read_to_string(dir.join("config").join("b.toml")).unwrap(), "b2");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn leaves_unknown_directories_and_top_level_files_alone() {
+        let mut normalizer = OverrideNormalizer::new();
+        assert_eq!(normalizer.normalize(Path::new("KubeJS/startup.js")), PathBuf::from("KubeJS/startup.js"));
+        assert_eq!(normalizer.normalize(Path::new("Config")), PathBuf::from("Config"));
+        assert_eq!(normalizer.normalize(Path::new("DefaultConfigs/x.toml")), PathBuf::from("defaultconfigs/x.toml"));
+    }
+
+    #[test]
+    fn matching_dirs_puts_canonical_last() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(dir.join("Config")).unwrap();
+
+        let dirs = matching_dirs(&dir, "config");
+
+        // Case-insensitive filesystems only ever have one of them
+        assert!(!dirs.is_empty());
+        assert_eq!(dirs.last().unwrap().file_name().unwrap(), "config");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
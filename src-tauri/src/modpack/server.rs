@@ -0,0 +1,367 @@
+use anyhow::{Result, Context};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::mrpack::{self, MrpackIndex, MrpackIndexFile};
+use super::overrides::{extract_archive, OverrideNormalizer};
+use super::types::*;
+
+const SERVER_MEMORY_MB: u32 = 4096;
+
+/// Aikar's flags, the usual recommendation for modded servers
+const SERVER_JVM_FLAGS: &[&str] = &[
+    "-XX:+UseG1GC",
+    "-XX:+ParallelRefProcEnabled",
+    "-XX:MaxGCPauseMillis=200",
+    "-XX:+UnlockExperimentalVMOptions",
+    "-XX:+DisableExplicitGC",
+    "-XX:+AlwaysPreTouch",
+    "-XX:G1NewSizePercent=30",
+    "-XX:G1MaxNewSizePercent=40",
+    "-XX:G1HeapRegionSize=8M",
+    "-XX:G1ReservePercent=20",
+    "-XX:G1HeapWastePercent=5",
+    "-XX:G1MixedGCCountTarget=4",
+    "-XX:InitiatingHeapOccupancyPercent=15",
+    "-XX:G1MixedGCLiveThresholdPercent=90",
+    "-XX:G1RSetUpdatingPauseIntervalPercent=5",
+    "-XX:SurvivorRatio=32",
+    "-XX:+PerfDisableSharedMem",
+    "-XX:MaxTenuringThreshold=1",
+];
+
+/// How the installed server is started
+enum ServerLaunch {
+    /// `java -jar <jar>`
+    Jar(String),
+    /// `java @<args file>` as used by modern Forge and NeoForge
+    ArgsFiles { unix: String, windows: String },
+}
+
+impl ModpackInstaller {
+    /// Prepare a runnable server directory from a Modrinth pack version
+    ///
+    /// Only files the pack marks as server-compatible are installed. The EULA is
+    /// left for the user to accept.
+    pub async fn install_local_server(
+        &self,
+        version: &ModrinthVersion,
+        progress_callback: impl Fn(ModpackInstallProgress) + Send + Sync,
+    ) -> Result<LocalServerInstallResult> {
+        let report = |progress: f64, stage: &str| {
+            progress_callback(ModpackInstallProgress {
+                instance_dir: self.instance_dir.to_string_lossy().to_string(),
+                progress,
+                stage: stage.to_string(),
+            });
+        };
+
+        report(0.0, "Starting server pack installation");
+
+        let modpack_file = version.files.iter()
+            .find(|f| f.primary)
+            .or_else(|| version.files.first())
+            .context("No modpack file found")?;
+
+        if !modpack_file.filename.ends_with(".mrpack") {
+            return Err(anyhow::anyhow!("Server packs require a .mrpack file, got {}", modpack_file.filename));
+        }
+
+        report(5.0, "Downloading modpack");
+
+        // The archive is kept until the install succeeds so a rerun can reuse it
+        let temp_file = std::env::temp_dir().join(&modpack_file.filename);
+        let mut counts = DownloadCounts::default();
+        self.download_verified(&modpack_file.url, &temp_file, Some(modpack_file.size), modpack_file.hashes.get("sha1").cloned(), &mut counts).await
+            .context("Failed to download modpack")?;
+
+        tokio::fs::create_dir_all(&self.instance_dir).await
+            .context("Failed to create server directory")?;
+
+        report(15.0, "Extracting server overrides");
+
+        let (index, warnings) = self.extract_server_pack(&temp_file).await?;
+        for warning in &warnings {
+            println!("⚠️  {}", warning);
+        }
+
+        let minecraft_version = index.dependencies.get("minecraft")
+            .cloned()
+            .context("Modpack does not specify a Minecraft version")?;
+
+        // Download server-compatible files
+        let server_files: Vec<&MrpackIndexFile> = index.files.iter()
+            .filter(|f| f.supports_server())
+            .collect();
+        let skipped_client_files = (index.files.len() - server_files.len()) as u32;
+
+        report(20.0, "Downloading server files");
+        self.download_index_files(&server_files, &mut counts, |fraction, stage| report(20.0 + 50.0 * fraction, stage)).await?;
+
+        // Resolve Java for the loader installer and start scripts
+        let java_version = crate::minecraft::versions::get_required_java_version(&minecraft_version);
+        report(70.0, &format!("Preparing Java {}", java_version));
+        let java_path = crate::minecraft::java::resolve_java(java_version).await
+            .map_err(|e| anyhow::anyhow!(e))?
+            .path;
+
+        report(75.0, "Installing server loader");
+
+        let (loader, launch) = self.install_server_loader(&index.dependencies, &minecraft_version, &java_path).await?;
+
+        report(90.0, "Writing start scripts");
+
+        let start_script = self.write_start_script(&launch, &java_path).await?;
+
+        let _ = tokio::fs::remove_file(&temp_file).await;
+        report(100.0, &format!("Server pack installation complete: {}", counts.summary()));
+
+        println!("✅ Server pack '{}' prepared in {} (requires Java {})",
+            version.name, self.instance_dir.display(), java_version);
+
+        Ok(LocalServerInstallResult {
+            server_dir: self.instance_dir.to_string_lossy().to_string(),
+            minecraft_version,
+            loader,
+            java_version,
+            start_script: start_script.to_string_lossy().to_string(),
+            skipped_client_files,
+            warnings,
+        })
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let response = self.client.get(url)
+            .header("User-Agent", crate::net::download::USER_AGENT)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Download a file straight to disk, keeping an existing copy when the source gives a SHA-1 it matches
+    async fn download_to(&self, url: &str, dest: &Path, sha1: Option<String>) -> Result<()> {
+        self.download_verified(url, dest, None, sha1, &mut DownloadCounts::default()).await
+    }
+
+    /// Read the pack index and extract overrides and server-overrides
+    async fn extract_server_pack(&self, pack_path: &Path) -> Result<(MrpackIndex, Vec<String>)> {
+        let pack_path = pack_path.to_path_buf();
+        let server_dir = self.instance_dir.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<(MrpackIndex, Vec<String>)> {
+            let file = std::fs::File::open(&pack_path)
+                .context("Failed to open modpack archive")?;
+            let mut archive = zip::ZipArchive::new(file)
+                .context("Failed to read modpack archive")?;
+
+            let index = mrpack::read_index(&mut archive)?;
+
+            // server-overrides are applied after overrides so they take precedence
+            let mut normalizer = OverrideNormalizer::new();
+            for prefix in ["overrides/", "server-overrides/"] {
+                extract_archive(&mut archive, &server_dir, Some(prefix), &mut normalizer)?;
+            }
+
+            Ok((index, normalizer.warnings()))
+        }).await.map_err(|e| anyhow::anyhow!("Task join error: {}", e))?
+    }
+
+    /// Install the server side of the pack's loader and return how to start it
+    async fn install_server_loader(
+        &self,
+        dependencies: &BTreeMap<String, String>,
+        minecraft_version: &str,
+        java_path: &str,
+    ) -> Result<(String, ServerLaunch)> {
+        if let Some(loader_version) = dependencies.get("fabric-loader") {
+            let launch = self.install_fabric_server(minecraft_version, loader_version).await?;
+            return Ok((format!("fabric-{}", loader_version), launch));
+        }
+
+        if let Some(forge_version) = dependencies.get("forge") {
+            let installer_url = format!(
+                "https://maven.minecraftforge.net/net/minecraftforge/forge/{0}-{1}/forge-{0}-{1}-installer.jar",
+                minecraft_version, forge_version
+            );
+            let args_dir = format!("libraries/net/minecraftforge/forge/{}-{}", minecraft_version, forge_version);
+            let launch = self.run_forge_installer(&installer_url, &args_dir, "forge", java_path).await?;
+            return Ok((format!("forge-{}", forge_version), launch));
+        }
+
+        if let Some(neoforge_version) = dependencies.get("neoforge") {
+            let installer_url = format!(
+                "https://maven.neoforged.net/releases/net/neoforged/neoforge/{0}/neoforge-{0}-installer.jar",
+                neoforge_version
+            );
+            let args_dir = format!("libraries/net/neoforged/neoforge/{}", neoforge_version);
+            let launch = self.run_forge_installer(&installer_url, &args_dir, "neoforge", java_path).await?;
+            return Ok((format!("neoforge-{}", neoforge_version), launch));
+        }
+
+        if dependencies.contains_key("quilt-loader") {
+            return Err(anyhow::anyhow!("Quilt server packs are not supported yet"));
+        }
+
+        let launch = self.install_vanilla_server(minecraft_version).await?;
+        Ok(("vanilla".to_string(), launch))
+    }
+
+    async fn install_fabric_server(&self, minecraft_version: &str, loader_version: &str) -> Result<ServerLaunch> {
+        let installers: Vec<serde_json::Value> = serde_json::from_slice(
+            &self.download("https://meta.fabricmc.net/v2/versions/installer").await
+                .context("Failed to get Fabric installer versions")?
+        ).context("Failed to parse Fabric installer versions")?;
+
+        let installer_version = installers.iter()
+            .find(|v| v["stable"].as_bool().unwrap_or(false))
+            .or_else(|| installers.first())
+            .and_then(|v| v["version"].as_str())
+            .context("No Fabric installer version available")?;
+
+        let url = format!(
+            "https://meta.fabricmc.net/v2/versions/loader/{}/{}/{}/server/jar",
+            minecraft_version, loader_version, installer_version
+        );
+        let jar_name = "fabric-server-launch.jar";
+        self.download_to(&url, &self.instance_dir.join(jar_name), None).await
+            .context("Failed to download Fabric server launcher")?;
+
+        Ok(ServerLaunch::Jar(jar_name.to_string()))
+    }
+
+    async fn run_forge_installer(
+        &self,
+        installer_url: &str,
+        args_dir: &str,
+        loader_name: &str,
+        java_path: &str,
+    ) -> Result<ServerLaunch> {
+        let installer_path = self.instance_dir.join(format!("{}-installer.jar", loader_name));
+        self.download_to(installer_url, &installer_path, None).await
+            .with_context(|| format!("Failed to download {} installer", loader_name))?;
+
+        let output = tokio::process::Command::new(java_path)
+            .arg("-jar")
+            .arg(&installer_path)
+            .arg("--installServer")
+            .current_dir(&self.instance_dir)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {} installer", loader_name))?;
+
+        let _ = tokio::fs::remove_file(&installer_path).await;
+        let _ = tokio::fs::remove_file(installer_path.with_extension("jar.log")).await;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} installer failed: {}",
+                loader_name,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        // Modern installers generate argument files instead of a runnable jar
+        if self.instance_dir.join(args_dir).join("unix_args.txt").exists() {
+            return Ok(ServerLaunch::ArgsFiles {
+                unix: format!("{}/unix_args.txt", args_dir),
+                windows: format!("{}/win_args.txt", args_dir),
+            });
+        }
+
+        let mut entries = tokio::fs::read_dir(&self.instance_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(loader_name) && name.ends_with(".jar") && !name.contains("installer") {
+                return Ok(ServerLaunch::Jar(name));
+            }
+        }
+
+        Err(anyhow::anyhow!("{} installer did not produce a server jar", loader_name))
+    }
+
+    async fn install_vanilla_server(&self, minecraft_version: &str) -> Result<ServerLaunch> {
+        let manifest: serde_json::Value = serde_json::from_slice(
+            &self.download("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json").await
+                .context("Failed to get version manifest")?
+        ).context("Failed to parse version manifest")?;
+
+        let version_url = manifest["versions"].as_array()
+            .and_then(|versions| versions.iter().find(|v| v["id"] == minecraft_version))
+            .and_then(|v| v["url"].as_str())
+            .with_context(|| format!("Minecraft version {} not found", minecraft_version))?;
+
+        let version_info: serde_json::Value = serde_json::from_slice(&self.download(version_url).await?)
+            .context("Failed to parse version info")?;
+
+        let server = &version_info["downloads"]["server"];
+        let server_url = server["url"].as_str()
+            .with_context(|| format!("No server download for Minecraft {}", minecraft_version))?;
+
+        let jar_name = "server.jar";
+        self.download_to(server_url, &self.instance_dir.join(jar_name), server["sha1"].as_str().map(String::from)).await
+            .context("Failed to download server jar")?;
+
+        Ok(ServerLaunch::Jar(jar_name.to_string()))
+    }
+
+    /// Write a start script for the current OS and return its path
+    async fn write_start_script(&self, launch: &ServerLaunch, java_path: &str) -> Result<PathBuf> {
+        let mut jvm_args = vec![
+            format!("-Xms{}M", SERVER_MEMORY_MB),
+            format!("-Xmx{}M", SERVER_MEMORY_MB),
+        ];
+        jvm_args.extend(SERVER_JVM_FLAGS.iter().map(|flag| flag.to_string()));
+
+        let launch_args = match launch {
+            ServerLaunch::Jar(jar) => format!("-jar {}", jar),
+            ServerLaunch::ArgsFiles { unix, windows } => {
+                // The installer reads JVM flags from user_jvm_args.txt
+                tokio::fs::write(self.instance_dir.join("user_jvm_args.txt"), jvm_args.join("\n")).await
+                    .context("Failed to write user_jvm_args.txt")?;
+                jvm_args = vec!["@user_jvm_args.txt".to_string()];
+                format!("@{}", if cfg!(windows) { windows } else { unix })
+            }
+        };
+
+        let command = format!("\"{}\" {} {} nogui", java_path, jvm_args.join(" "), launch_args);
+
+        let script_path = if cfg!(windows) {
+            let path = self.instance_dir.join("start.bat");
+            tokio::fs::write(&path, format!("@echo off\r\ncd /d \"%~dp0\"\r\n{}\r\npause\r\n", command)).await
+                .context("Failed to write start.bat")?;
+            path
+        } else {
+            let path = self.instance_dir.join("start.sh");
+            tokio::fs::write(&path, format!("#!/usr/bin/env sh\ncd \"$(dirname \"$0\")\"\nexec {}\n", command)).await
+                .context("Failed to write start.sh")?;
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).await
+                    .context("Failed to make start.sh executable")?;
+            }
+            path
+        };
+
+        Ok(script_path)
+    }
+}
+
+/// Accept the Minecraft EULA for a local server directory
+pub async fn accept_eula(server_dir: &Path) -> Result<()> {
+    if !server_dir.exists() {
+        return Err(anyhow::anyhow!("Server directory does not exist: {}", server_dir.display()));
+    }
+
+    let content = format!(
+        "#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).\n#{}\neula=true\n",
+        chrono::Utc::now().to_rfc2822()
+    );
+    tokio::fs::write(server_dir.join("eula.txt"), content).await
+        .context("Failed to write eula.txt")?;
+
+    Ok(())
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
 use reqwest::Client;
+use tokio::sync::watch;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModrinthPack {
@@ -61,6 +63,27 @@ pub struct ModpackCreationRequest {
     pub instance_id: String,
     pub instance_path: String,
     pub metadata: ModpackMetadata,
+    #[serde(default)]
+    pub format: ModpackFormat,
+}
+
+/// Archive layout produced by the modpack creator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModpackFormat {
+    /// Plain zip of the instance folders
+    #[default]
+    Zip,
+    /// Modrinth .mrpack with a modrinth.index.json
+    Mrpack,
+}
+
+/// Outcome of creating a modpack
+#[derive(Debug, Serialize, Clone)]
+pub struct ModpackCreationResult {
+    pub output_path: String,
+    /// Mod jars Modrinth didn't recognise, bundled under overrides/ instead of linked by URL
+    pub override_mods: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -93,24 +116,210 @@ pub struct ModpackInstallProgress {
     pub stage: String,
 }
 
+/// Outcome of a modpack install
+#[derive(Debug, Serialize, Clone)]
+pub struct ModpackInstallReport {
+    pub instance_dir: String,
+    /// Problems that did not stop the install, like merged override directories
+    pub warnings: Vec<String>,
+    /// Pack files whose authors block third-party downloads, to be fetched by hand
+    pub manual_downloads: Vec<ManualDownload>,
+}
+
+/// Outcome of a modpack update, with what downloading only the changes saved
+#[derive(Debug, Serialize, Clone)]
+pub struct ModpackUpdateReport {
+    pub instance_dir: String,
+    /// None when the instance has no record of the version it was installed from
+    pub previous_version_id: Option<String>,
+    pub version_id: String,
+    pub added: u32,
+    pub updated: u32,
+    pub removed: u32,
+    /// Files with the same hash in both versions, left untouched
+    pub unchanged: u32,
+    /// Changed files another instance already had in the download cache
+    pub cached: u32,
+    pub overrides_written: u32,
+    pub overrides_unchanged: u32,
+    /// Bytes actually downloaded, the pack archive included
+    pub downloaded_bytes: u64,
+    /// Bytes downloading the archive and every listed file again would have cost
+    pub full_download_bytes: u64,
+    pub warnings: Vec<String>,
+}
+
+/// A CurseForge file the launcher isn't allowed to download
+#[derive(Debug, Serialize, Clone)]
+pub struct ManualDownload {
+    pub project_id: u64,
+    pub file_id: u64,
+    pub file_name: String,
+    /// CurseForge page for the file
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LocalServerInstallResult {
+    pub server_dir: String,
+    pub minecraft_version: String,
+    pub loader: String,
+    pub java_version: u32,
+    pub start_script: String,
+    pub skipped_client_files: u32,
+    pub warnings: Vec<String>,
+}
+
+/// Pack files downloaded at once unless the settings say otherwise
+pub const DEFAULT_DOWNLOAD_CONCURRENCY: u32 = 8;
+
 pub struct ModpackInstaller {
     pub client: Client,
     pub instance_dir: PathBuf,
+    /// Stops the install once it turns true
+    pub cancel: Option<watch::Receiver<bool>>,
+    /// Number of pack files downloaded at once
+    pub concurrency: usize,
 }
 
 impl ModpackInstaller {
     pub fn new(instance_dir: PathBuf) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::client(),
             instance_dir,
+            cancel: None,
+            concurrency: DEFAULT_DOWNLOAD_CONCURRENCY as usize,
+        }
+    }
+
+    pub fn with_cancel(mut self, cancel: watch::Receiver<bool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: u32) -> Self {
+        self.concurrency = concurrency.max(1) as usize;
+        self
+    }
+
+    /// Fails with [`InstallCancelled`] once cancellation was requested
+    pub(crate) fn check_cancelled(&self) -> anyhow::Result<()> {
+        match &self.cancel {
+            Some(cancel) if *cancel.borrow() => Err(InstallCancelled.into()),
+            _ => Ok(()),
         }
     }
 }
 
+/// Files an install downloaded and files it kept from an earlier attempt
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DownloadCounts {
+    pub downloaded: u32,
+    /// Taken from the shared download cache instead of the network
+    pub cached: u32,
+    pub skipped: u32,
+    /// Bytes of the downloaded files, cached and skipped ones cost nothing
+    pub downloaded_bytes: u64,
+}
+
+impl DownloadCounts {
+    /// e.g. `12 files downloaded, 4 from cache, 30 already present`
+    pub fn summary(&self) -> String {
+        format!("{} files downloaded, {} from cache, {} already present", self.downloaded, self.cached, self.skipped)
+    }
+
+    pub fn add(&mut self, fetched: Fetched) {
+        match fetched {
+            Fetched::Present => self.skipped += 1,
+            Fetched::Cached => self.cached += 1,
+            Fetched::Downloaded(bytes) => {
+                self.downloaded += 1;
+                self.downloaded_bytes += bytes;
+            }
+        }
+    }
+}
+
+/// How `fetch_verified` got a file in place
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fetched {
+    /// Already on disk with the expected hash
+    Present,
+    Cached,
+    /// Downloaded, with its size
+    Downloaded(u64),
+}
+
+/// Raised inside the installer when the user cancels
+#[derive(Debug, thiserror::Error)]
+#[error("Modpack installation cancelled")]
+pub struct InstallCancelled;
+
+/// Whether an install error was caused by cancelling, directly or through a download
+pub fn is_cancelled(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.is::<InstallCancelled>()
+            || matches!(cause.downcast_ref::<crate::net::download::DownloadError>(), Some(crate::net::download::DownloadError::Cancelled))
+    })
+}
+
+/// Error from `install_modpack`, sent to the UI tagged by `kind`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ModpackInstallError {
+    /// Stopped through `cancel_modpack_install`
+    Cancelled { message: String },
+    Failed { message: String },
+}
+
+impl ModpackInstallError {
+    pub fn cancelled() -> Self {
+        ModpackInstallError::Cancelled { message: InstallCancelled.to_string() }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ModpackInstallError::Cancelled { message } | ModpackInstallError::Failed { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for ModpackInstallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl From<String> for ModpackInstallError {
+    fn from(message: String) -> Self {
+        ModpackInstallError::Failed { message }
+    }
+}
+
 pub struct ModpackCreator;
 
 impl ModpackCreator {
     pub fn new() -> Self {
         Self
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn cancelled_downloads_and_checks_are_recognised() {
+        let (sender, cancel) = watch::channel(false);
+        let installer = ModpackInstaller::new(PathBuf::from("pack")).with_cancel(cancel);
+        assert!(installer.check_cancelled().is_ok());
+
+        sender.send(true).unwrap();
+        let error = installer.check_cancelled().context("Installing mods").unwrap_err();
+        assert!(is_cancelled(&error));
+
+        let download: anyhow::Result<()> = Err(crate::net::download::DownloadError::Cancelled.into());
+        assert!(is_cancelled(&download.context("Failed to download modpack").unwrap_err()));
+        assert!(!is_cancelled(&anyhow::anyhow!("Modpack version not found")));
+    }
+}
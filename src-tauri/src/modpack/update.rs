@@ -0,0 +1,137 @@
+//! Updating an installed Modrinth pack to another version
+//!
+//! Most pack updates change a handful of mods. The new index is compared with the
+//! one recorded at install time by path and SHA1: unchanged files are left alone,
+//! changed ones are fetched through the shared download cache and files the new
+//! version dropped are removed. Overrides are only rewritten where they differ.
+
+use anyhow::{Context, Result};
+
+use super::mrpack::{self, InstalledPack, MrpackIndexFile};
+use super::types::*;
+
+impl ModpackInstaller {
+    /// Update the pack in this installer's instance directory to `version`
+    pub async fn update_modpack(
+        &self,
+        version: &ModrinthVersion,
+        progress_callback: impl Fn(ModpackInstallProgress) + Send + Sync,
+    ) -> Result<ModpackUpdateReport> {
+        let report = |progress: f64, stage: &str| {
+            progress_callback(ModpackInstallProgress {
+                instance_dir: self.instance_dir.to_string_lossy().to_string(),
+                progress,
+                stage: stage.to_string(),
+            });
+        };
+
+        report(0.0, "Starting modpack update");
+        let installed = InstalledPack::read(&self.instance_dir).await;
+        let mut warnings = Vec::new();
+        if installed.is_none() {
+            warnings.push("No record of the installed pack version, files the new version dropped were left in place".to_string());
+        }
+
+        let modpack_file = version.files.iter()
+            .find(|f| f.primary)
+            .or_else(|| version.files.first())
+            .context("No modpack file found")?;
+
+        report(5.0, "Downloading modpack");
+        let temp_file = std::env::temp_dir().join(&modpack_file.filename);
+        let archive = self.fetch_verified(&modpack_file.url, &temp_file, Some(modpack_file.size), modpack_file.hashes.get("sha1").cloned(), None).await
+            .context("Failed to download modpack")?;
+        self.check_cancelled()?;
+
+        report(25.0, "Applying changed overrides");
+        let updated = self.apply_update(version, installed.as_ref(), &temp_file, &report).await;
+        if updated.as_ref().map_or_else(is_cancelled, |_| true) {
+            let _ = tokio::fs::remove_file(&temp_file).await;
+        }
+        let mut update = updated?;
+
+        let archive_bytes = match archive {
+            Fetched::Downloaded(bytes) => bytes,
+            Fetched::Present | Fetched::Cached => 0,
+        };
+        update.downloaded_bytes += archive_bytes;
+        update.full_download_bytes += modpack_file.size;
+        update.previous_version_id = installed.map(|pack| pack.version_id);
+        update.warnings.splice(0..0, warnings);
+        for warning in &update.warnings {
+            println!("⚠️  {}", warning);
+        }
+
+        report(100.0, &format!(
+            "Modpack updated: {} added, {} updated, {} removed, {} of {} bytes downloaded",
+            update.added, update.updated, update.removed, update.downloaded_bytes, update.full_download_bytes
+        ));
+        Ok(update)
+    }
+
+    /// Extract changed overrides, fetch changed files and remove dropped ones
+    async fn apply_update(
+        &self,
+        version: &ModrinthVersion,
+        installed: Option<&InstalledPack>,
+        archive: &std::path::Path,
+        report: &impl Fn(f64, &str),
+    ) -> Result<ModpackUpdateReport> {
+        let (index, extracted, warnings) = self.extract_mrpack(archive).await
+            .context("Failed to extract modpack")?;
+        self.check_cancelled()?;
+
+        let files: Vec<&MrpackIndexFile> = index.files.iter().filter(|file| file.supports_client()).collect();
+        let installed_files = installed.map(|pack| pack.files.clone()).unwrap_or_default();
+        let diff = mrpack::diff_index(&installed_files, &files);
+
+        // Unchanged files are only fetched again when they were deleted
+        let mut downloads: Vec<&MrpackIndexFile> = diff.added.iter().chain(&diff.updated).copied().collect();
+        for file in &diff.unchanged {
+            if !self.index_file_dest(file)?.exists() {
+                downloads.push(*file);
+            }
+        }
+
+        report(40.0, &format!("Downloading {} changed files", downloads.len()));
+        let mut counts = DownloadCounts::default();
+        self.download_index_files(&downloads, &mut counts, |fraction, stage| report(40.0 + 50.0 * fraction, stage)).await?;
+
+        report(90.0, "Removing files the new version dropped");
+        let mut removed = 0;
+        for path in &diff.removed {
+            let dest = match self.index_path_dest(path) {
+                Ok(dest) => dest,
+                Err(e) => {
+                    println!("⚠️  Not removing {}: {}", path, e);
+                    continue;
+                }
+            };
+            if dest.exists() {
+                tokio::fs::remove_file(&dest).await
+                    .with_context(|| format!("Failed to remove {}", path))?;
+                crate::provenance::record_removed(&self.instance_dir, &dest).await;
+                removed += 1;
+            }
+        }
+
+        InstalledPack::new(&version.project_id, &version.id, &version.version_number, &files)
+            .write(&self.instance_dir).await?;
+
+        Ok(ModpackUpdateReport {
+            instance_dir: self.instance_dir.to_string_lossy().to_string(),
+            previous_version_id: None,
+            version_id: version.id.clone(),
+            added: diff.added.len() as u32,
+            updated: diff.updated.len() as u32,
+            removed,
+            unchanged: diff.unchanged.len() as u32,
+            cached: counts.cached,
+            overrides_written: extracted.written,
+            overrides_unchanged: extracted.unchanged,
+            downloaded_bytes: counts.downloaded_bytes,
+            full_download_bytes: files.iter().map(|file| file.file_size).sum(),
+            warnings,
+        })
+    }
+}
@@ -9,13 +9,16 @@ pub struct ApiUtils;
 impl ApiUtils {
     /// Verify file integrity using SHA1 hash
     pub async fn verify_file_hash(file_path: &Path, expected_hash: &str) -> Result<bool, ModError> {
+        let computed_hash = Self::file_sha1(file_path).await?;
+        Ok(computed_hash.eq_ignore_ascii_case(expected_hash))
+    }
+
+    /// Hex encoded SHA1 of a file
+    pub async fn file_sha1(file_path: &Path) -> Result<String, ModError> {
         let contents = fs::read(file_path).await?;
         let mut hasher = Sha1::new();
         hasher.update(&contents);
-        let result = hasher.finalize();
-        let computed_hash = hex::encode(result);
-        
-        Ok(computed_hash.eq_ignore_ascii_case(expected_hash))
+        Ok(hex::encode(hasher.finalize()))
     }
     
     /// Create a user agent string for API requests
@@ -0,0 +1,460 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use crate::mods::types::*;
+use crate::mods::api::ModApi;
+use std::path::Path;
+use std::sync::Arc;
+use crate::net::{self, DownloadOptions};
+use crate::net::manager::{DownloadKind, DownloadPriority};
+use serde_json;
+use chrono::{DateTime, Utc};
+use tokio::sync::OnceCell;
+
+/// CurseForge game id for Minecraft
+const MINECRAFT_GAME_ID: u32 = 432;
+
+/// CurseForge class id for mods (as opposed to modpacks, resource packs, ...)
+const MODS_CLASS_ID: u32 = 6;
+
+/// Largest page size the CurseForge API accepts
+const MAX_PAGE_SIZE: u32 = 50;
+
+/// CurseForge API client implementation
+#[derive(Debug)]
+pub struct CurseForgeApi {
+    client: Client,
+    base_url: String,
+    /// Read from `LauncherSettings::curseforge_api_key` on first use
+    api_key: OnceCell<Option<String>>,
+}
+
+impl CurseForgeApi {
+    pub fn new() -> Self {
+        Self {
+            client: crate::http::client(),
+            base_url: "https://api.curseforge.com/v1".to_string(),
+            api_key: OnceCell::new(),
+        }
+    }
+
+    async fn api_key(&self) -> Result<&str, ModError> {
+        let key = self.api_key.get_or_init(|| async {
+            let storage = crate::storage::shared().await.ok()?;
+            let key = storage.read().await.get_settings().curseforge_api_key.clone();
+            key.filter(|key| !key.trim().is_empty())
+        }).await;
+
+        key.as_deref().ok_or_else(|| ModError::Anyhow(anyhow::anyhow!(
+            "CurseForge API key is not set. Add one in the launcher settings to use CurseForge."
+        )))
+    }
+
+    async fn make_request(&self, endpoint: &str) -> Result<serde_json::Value, ModError> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        self.send(self.client.get(&url), endpoint).await
+    }
+
+    async fn make_post_request(&self, endpoint: &str, body: serde_json::Value) -> Result<serde_json::Value, ModError> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        self.send(self.client.post(&url).json(&body), endpoint).await
+    }
+
+    /// Send an authenticated request and unwrap the `data` field of the response
+    async fn send(&self, request: reqwest::RequestBuilder, endpoint: &str) -> Result<serde_json::Value, ModError> {
+        let response = request
+            .header("User-Agent", "ChaiLauncher/2.0.0")
+            .header("Accept", "application/json")
+            .header("x-api-key", self.api_key().await?)
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(ModError::NotFound(endpoint.to_string()));
+        }
+        let response = response.error_for_status()?;
+
+        let json: serde_json::Value = response.json().await?;
+        Ok(json["data"].clone())
+    }
+
+    /// Several files in one request, files CurseForge doesn't know are left out
+    pub async fn get_files(&self, file_ids: &[u64]) -> Result<Vec<ModFile>, ModError> {
+        if file_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let data = self.make_post_request("mods/files", serde_json::json!({ "fileIds": file_ids })).await?;
+        data.as_array()
+            .map(|files| files.iter().map(|file| self.convert_curseforge_file_to_mod_file(file)).collect())
+            .unwrap_or_else(|| Ok(Vec::new()))
+    }
+
+    /// Several projects in one request
+    pub async fn get_mods(&self, mod_ids: &[u64]) -> Result<Vec<ModInfo>, ModError> {
+        if mod_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let data = self.make_post_request("mods", serde_json::json!({ "modIds": mod_ids })).await?;
+        data.as_array()
+            .map(|mods| mods.iter().map(|project| self.convert_curseforge_mod_to_mod_info(project)).collect())
+            .unwrap_or_else(|| Ok(Vec::new()))
+    }
+
+    /// Build a `mods/search` query with the filters shared by search and featured mods
+    fn search_endpoint(
+        &self,
+        query: Option<&str>,
+        game_version: Option<&str>,
+        mod_loader: Option<&str>,
+        sort_field: u32,
+        limit: u32,
+        offset: u32,
+    ) -> String {
+        let mut params = vec![
+            ("gameId", MINECRAFT_GAME_ID.to_string()),
+            ("classId", MODS_CLASS_ID.to_string()),
+            ("sortField", sort_field.to_string()),
+            ("sortOrder", "desc".to_string()),
+            ("pageSize", limit.min(MAX_PAGE_SIZE).to_string()),
+            ("index", offset.to_string()),
+        ];
+
+        if let Some(query) = query.filter(|q| !q.is_empty()) {
+            params.push(("searchFilter", query.to_string()));
+        }
+        if let Some(version) = game_version {
+            params.push(("gameVersion", version.to_string()));
+        }
+        if let Some(loader_type) = mod_loader.and_then(Self::mod_loader_type) {
+            params.push(("modLoaderType", loader_type.to_string()));
+        }
+
+        let query_string = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, urlencoding::encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        format!("mods/search?{}", query_string)
+    }
+
+    /// CurseForge `ModLoaderType` for a loader name
+    fn mod_loader_type(loader: &str) -> Option<u32> {
+        match loader.to_lowercase().as_str() {
+            "forge" => Some(1),
+            "fabric" => Some(4),
+            "quilt" => Some(5),
+            "neoforge" => Some(6),
+            _ => None,
+        }
+    }
+
+    fn mod_loader_name(loader_type: u64) -> Option<&'static str> {
+        match loader_type {
+            1 => Some("forge"),
+            4 => Some("fabric"),
+            5 => Some("quilt"),
+            6 => Some("neoforge"),
+            _ => None,
+        }
+    }
+
+    /// Split CurseForge `gameVersions`, which mixes Minecraft versions with loader and side names
+    fn split_game_versions(values: &serde_json::Value) -> (Vec<String>, Vec<String>) {
+        let mut game_versions = Vec::new();
+        let mut loaders = Vec::new();
+
+        for value in values.as_array().into_iter().flatten().filter_map(|v| v.as_str()) {
+            if value.starts_with(|c: char| c.is_ascii_digit()) {
+                game_versions.push(value.to_string());
+            } else if Self::mod_loader_type(value).is_some() {
+                loaders.push(value.to_lowercase());
+            }
+        }
+
+        (game_versions, loaders)
+    }
+
+    fn parse_date(value: &serde_json::Value) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value.as_str().unwrap_or("2020-01-01T00:00:00Z"))
+            .unwrap_or_default()
+            .with_timezone(&Utc)
+    }
+
+    fn convert_curseforge_mod_to_mod_info(&self, project: &serde_json::Value) -> Result<ModInfo, ModError> {
+        let id = project["id"].as_u64()
+            .ok_or_else(|| ModError::InvalidFile("CurseForge mod has no id".to_string()))?
+            .to_string();
+
+        let empty_vec = vec![];
+        let latest_files = project["latestFiles"].as_array().unwrap_or(&empty_vec);
+
+        let mut game_versions = Vec::new();
+        let mut loaders = Vec::new();
+        for file in latest_files {
+            let (versions, file_loaders) = Self::split_game_versions(&file["gameVersions"]);
+            game_versions.extend(versions);
+            loaders.extend(file_loaders);
+        }
+        for index in project["latestFilesIndexes"].as_array().unwrap_or(&empty_vec) {
+            if let Some(version) = index["gameVersion"].as_str() {
+                game_versions.push(version.to_string());
+            }
+            if let Some(loader) = index["modLoader"].as_u64().and_then(Self::mod_loader_name) {
+                loaders.push(loader.to_string());
+            }
+        }
+        game_versions.sort();
+        game_versions.dedup();
+        loaders.sort();
+        loaders.dedup();
+
+        let version = latest_files.iter()
+            .max_by_key(|file| Self::parse_date(&file["fileDate"]))
+            .and_then(|file| file["displayName"].as_str())
+            .unwrap_or("Unknown")
+            .to_string();
+
+        Ok(ModInfo {
+            id,
+            name: project["name"].as_str().unwrap_or_default().to_string(),
+            description: project["summary"].as_str().unwrap_or_default().to_string(),
+            author: project["authors"]
+                .as_array()
+                .and_then(|authors| authors.first())
+                .and_then(|author| author["name"].as_str())
+                .unwrap_or("Unknown")
+                .to_string(),
+            version,
+            game_versions,
+            loaders,
+            downloads: project["downloadCount"].as_f64().unwrap_or(0.0).min(u32::MAX as f64) as u32,
+            icon_url: project["logo"]["thumbnailUrl"].as_str()
+                .or_else(|| project["logo"]["url"].as_str())
+                .map(String::from),
+            website_url: project["links"]["websiteUrl"].as_str().map(String::from),
+            source_url: project["links"]["sourceUrl"].as_str().map(String::from),
+            license: None,
+            categories: project["categories"]
+                .as_array()
+                .unwrap_or(&empty_vec)
+                .iter()
+                .filter_map(|c| c["name"].as_str())
+                .map(String::from)
+                .collect(),
+            side: ModSide::Unknown,
+            source: ModSource::CurseForge,
+            featured: project["isFeatured"].as_bool().unwrap_or(false),
+            date_created: Self::parse_date(&project["dateCreated"]),
+            date_updated: Self::parse_date(&project["dateModified"]),
+        })
+    }
+
+    fn convert_curseforge_file_to_mod_file(&self, file: &serde_json::Value) -> Result<ModFile, ModError> {
+        let id = file["id"].as_u64()
+            .ok_or_else(|| ModError::InvalidFile("CurseForge file has no id".to_string()))?
+            .to_string();
+        let (game_versions, loaders) = Self::split_game_versions(&file["gameVersions"]);
+
+        Ok(ModFile {
+            id,
+            mod_id: file["modId"].as_u64().map(|id| id.to_string()).unwrap_or_default(),
+            filename: file["fileName"].as_str().unwrap_or_default().to_string(),
+            display_name: file["displayName"].as_str().unwrap_or_default().to_string(),
+            version: file["displayName"].as_str().unwrap_or_default().to_string(),
+            size: file["fileLength"].as_u64().unwrap_or(0),
+            // Null for mods whose authors opted out of third-party downloads
+            download_url: file["downloadUrl"].as_str().unwrap_or_default().to_string(),
+            hashes: file["hashes"]
+                .as_array()
+                .unwrap_or(&vec![])
+                .iter()
+                .filter_map(|hash| {
+                    let algo = match hash["algo"].as_u64()? {
+                        1 => "sha1",
+                        2 => "md5",
+                        _ => return None,
+                    };
+                    Some((algo.to_string(), hash["value"].as_str()?.to_string()))
+                })
+                .collect(),
+            dependencies: file["dependencies"]
+                .as_array()
+                .unwrap_or(&vec![])
+                .iter()
+                .filter_map(|dep| {
+                    let dependency_type = match dep["relationType"].as_u64()? {
+                        1 => DependencyType::Embedded,
+                        3 => DependencyType::Required,
+                        5 => DependencyType::Incompatible,
+                        _ => DependencyType::Optional,
+                    };
+                    Some(ModDependency {
+                        mod_id: dep["modId"].as_u64()?.to_string(),
+                        version_id: None,
+                        file_name: None,
+                        dependency_type,
+                    })
+                })
+                .collect(),
+            game_versions,
+            loaders,
+            release_type: match file["releaseType"].as_u64().unwrap_or(1) {
+                2 => ReleaseType::Beta,
+                3 => ReleaseType::Alpha,
+                _ => ReleaseType::Release,
+            },
+            date_published: Self::parse_date(&file["fileDate"]),
+            primary: true,
+        })
+    }
+
+    fn convert_search_results(&self, data: &serde_json::Value, featured: bool) -> Vec<ModInfo> {
+        let empty_vec = vec![];
+        let mut mods = Vec::new();
+
+        for project in data.as_array().unwrap_or(&empty_vec) {
+            match self.convert_curseforge_mod_to_mod_info(project) {
+                Ok(mut mod_info) => {
+                    mod_info.featured |= featured;
+                    mods.push(mod_info);
+                }
+                Err(e) => {
+                    eprintln!("Failed to convert CurseForge mod: {:?}", e);
+                    continue;
+                }
+            }
+        }
+
+        mods
+    }
+}
+
+#[async_trait]
+impl ModApi for CurseForgeApi {
+    async fn search_mods(
+        &self,
+        query: &str,
+        game_version: Option<&str>,
+        mod_loader: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<ModInfo>, ModError> {
+        // Sort field 2 is popularity, which gives the most relevant results for text searches
+        let endpoint = self.search_endpoint(Some(query), game_version, mod_loader, 2, limit, offset);
+        let data = self.make_request(&endpoint).await?;
+        Ok(self.convert_search_results(&data, false))
+    }
+
+    async fn get_mod_details(&self, mod_id: &str) -> Result<ModInfo, ModError> {
+        // CurseForge ids are numeric, skip the request for other sources' ids
+        if mod_id.parse::<u64>().is_err() {
+            return Err(ModError::NotFound(mod_id.to_string()));
+        }
+
+        let project = self.make_request(&format!("mods/{}", mod_id)).await?;
+        self.convert_curseforge_mod_to_mod_info(&project)
+    }
+
+    async fn get_mod_files(&self, mod_id: &str) -> Result<Vec<ModFile>, ModError> {
+        if mod_id.parse::<u64>().is_err() {
+            return Err(ModError::NotFound(mod_id.to_string()));
+        }
+
+        let mut files = Vec::new();
+        let mut index = 0;
+
+        loop {
+            let endpoint = format!("mods/{}/files?pageSize={}&index={}", mod_id, MAX_PAGE_SIZE, index);
+            let data = self.make_request(&endpoint).await?;
+            let page = data.as_array().cloned().unwrap_or_default();
+
+            for file in &page {
+                match self.convert_curseforge_file_to_mod_file(file) {
+                    Ok(file) => files.push(file),
+                    Err(e) => {
+                        eprintln!("Failed to convert CurseForge file: {:?}", e);
+                        continue;
+                    }
+                }
+            }
+
+            if page.len() < MAX_PAGE_SIZE as usize {
+                break;
+            }
+            index += MAX_PAGE_SIZE;
+        }
+
+        // Newest first, matching the order Modrinth returns versions in
+        files.sort_by_key(|f| std::cmp::Reverse(f.date_published));
+        Ok(files)
+    }
+
+    async fn get_mod_file(&self, mod_id: &str, file_id: &str) -> Result<ModFile, ModError> {
+        let file = self.make_request(&format!("mods/{}/files/{}", mod_id, file_id)).await?;
+        self.convert_curseforge_file_to_mod_file(&file)
+    }
+
+    async fn download_mod_file(&self, file: &ModFile, path: &Path, progress_callback: Box<dyn Fn(u64, u64) + Send + Sync>) -> Result<(), ModError> {
+        if file.download_url.is_empty() {
+            return Err(ModError::DownloadFailed(format!(
+                "The author of {} does not allow downloads from other launchers. Download it manually from CurseForge and place it in the instance's mods folder.",
+                file.filename
+            )));
+        }
+
+        let options = DownloadOptions {
+            client: Some(self.client.clone()),
+            expected_size: Some(file.size),
+            sha1: file.hashes.get("sha1").cloned(),
+            progress: Some(Arc::from(progress_callback)),
+            ..Default::default()
+        };
+        net::manager::shared().fetch_cached(DownloadKind::Mod, DownloadPriority::Normal, &file.download_url, path, options).await
+            .map_err(|e| ModError::DownloadFailed(format!("{}: {}", file.filename, e)))?;
+        Ok(())
+    }
+
+    async fn get_featured_mods(
+        &self,
+        game_version: Option<&str>,
+        mod_loader: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<ModInfo>, ModError> {
+        // Sort field 6 is total downloads, like the Modrinth featured list
+        let endpoint = self.search_endpoint(None, game_version, mod_loader, 6, limit, offset);
+        let data = self.make_request(&endpoint).await?;
+        Ok(self.convert_search_results(&data, true))
+    }
+
+    async fn get_categories(&self) -> Result<Vec<String>, ModError> {
+        let endpoint = format!("categories?gameId={}&classId={}", MINECRAFT_GAME_ID, MODS_CLASS_ID);
+        let categories = self.make_request(&endpoint).await?;
+
+        Ok(categories
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .filter_map(|cat| cat["name"].as_str())
+            .map(String::from)
+            .collect())
+    }
+
+    async fn check_updates(&self, installed_mod: &InstalledMod) -> Result<Option<ModFile>, ModError> {
+        if !matches!(installed_mod.mod_info.source, ModSource::CurseForge) {
+            return Ok(None);
+        }
+
+        let files = self.get_mod_files(&installed_mod.mod_info.id).await?;
+
+        // Find the latest file that's newer than the installed one
+        let latest = files
+            .into_iter()
+            .filter(|f| f.date_published > installed_mod.installed_file.date_published)
+            .max_by_key(|f| f.date_published);
+
+        Ok(latest)
+    }
+}
@@ -2,12 +2,13 @@ use async_trait::async_trait;
 use reqwest::Client;
 use crate::mods::types::*;
 use crate::mods::api::ModApi;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+use crate::net::{self, DownloadOptions};
+use crate::net::manager::{DownloadKind, DownloadPriority};
 use serde_json;
 use chrono::{DateTime, Utc};
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use futures::StreamExt;
 
 /// Modrinth API client implementation
 #[derive(Debug)]
@@ -19,7 +20,7 @@ pub struct ModrinthApi {
 impl ModrinthApi {
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::client(),
             base_url: "https://api.modrinth.com/v2".to_string(),
         }
     }
@@ -41,6 +42,36 @@ impl ModrinthApi {
         Ok(json)
     }
 
+    /// Find the Modrinth version a file belongs to by its SHA1, None when Modrinth doesn't know it
+    pub async fn get_version_by_hash(&self, sha1: &str) -> Result<Option<ModFile>, ModError> {
+        let endpoint = format!("version_file/{}?algorithm=sha1", sha1);
+        match self.make_request::<serde_json::Value>(&endpoint).await {
+            Ok(version) => self.convert_modrinth_version_to_mod_file(version).map(Some),
+            Err(ModError::Api(e)) if e.status() == Some(reqwest::StatusCode::NOT_FOUND) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Look up many files at once, keyed by the SHA1s Modrinth recognised
+    ///
+    /// Returns the raw version objects so callers can pick the exact file that matched.
+    pub async fn get_versions_by_hashes(&self, sha1s: &[String]) -> Result<HashMap<String, serde_json::Value>, ModError> {
+        if sha1s.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/version_files", self.base_url))
+            .header("User-Agent", "ChaiLauncher/2.0.0")
+            .json(&serde_json::json!({ "hashes": sha1s, "algorithm": "sha1" }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.json().await?)
+    }
+
     fn convert_modrinth_project_to_mod_info(&self, project: serde_json::Value) -> Result<ModInfo, ModError> {
         // Handle both search results and project details
         let author = project["author"].as_str()
@@ -163,9 +194,16 @@ impl ModrinthApi {
                 .unwrap_or(&vec![])
                 .iter()
                 .filter_map(|dep| {
+                    // Either id may be null, a version id alone still names the project
+                    let mod_id = dep["project_id"].as_str().unwrap_or_default();
+                    let version_id = dep["version_id"].as_str().map(String::from);
+                    if mod_id.is_empty() && version_id.is_none() {
+                        return None;
+                    }
+
                     Some(ModDependency {
-                        mod_id: dep["project_id"].as_str()?.to_string(),
-                        version_id: dep["version_id"].as_str().map(String::from),
+                        mod_id: mod_id.to_string(),
+                        version_id,
                         file_name: dep["file_name"].as_str().map(String::from),
                         dependency_type: match dep["dependency_type"].as_str()? {
                             "required" => DependencyType::Required,
@@ -297,29 +335,15 @@ impl ModApi for ModrinthApi {
     }
 
     async fn download_mod_file(&self, file: &ModFile, path: &Path, progress_callback: Box<dyn Fn(u64, u64) + Send + Sync>) -> Result<(), ModError> {
-        let response = self.client
-            .get(&file.download_url)
-            .header("User-Agent", "ChaiLauncher/2.0.0")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(ModError::DownloadFailed(format!("HTTP {}", response.status())));
-        }
-
-        let total_size = file.size;
-        let mut downloaded = 0u64;
-        let mut stream = response.bytes_stream();
-        let mut file_handle = fs::File::create(path).await?;
-
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
-            file_handle.write_all(&chunk).await?;
-            downloaded += chunk.len() as u64;
-            progress_callback(downloaded, total_size);
-        }
-
-        file_handle.flush().await?;
+        let options = DownloadOptions {
+            client: Some(self.client.clone()),
+            expected_size: Some(file.size),
+            sha1: file.hashes.get("sha1").cloned(),
+            progress: Some(Arc::from(progress_callback)),
+            ..Default::default()
+        };
+        net::manager::shared().fetch_cached(DownloadKind::Mod, DownloadPriority::Normal, &file.download_url, path, options).await
+            .map_err(|e| ModError::DownloadFailed(format!("{}: {}", file.filename, e)))?;
         Ok(())
     }
 
@@ -396,6 +420,10 @@ impl ModApi for ModrinthApi {
     }
 
     async fn check_updates(&self, installed_mod: &InstalledMod) -> Result<Option<ModFile>, ModError> {
+        if !matches!(installed_mod.mod_info.source, ModSource::Modrinth) {
+            return Ok(None);
+        }
+
         let files = self.get_mod_files(&installed_mod.mod_info.id).await?;
         
         // Find the latest file that's newer than the installed one
@@ -1,6 +1,15 @@
-use crate::mods::{ModManager, ModInfo, InstalledMod, ModLoader};
+use crate::mods::{ModManager, ModInfo, InstalledMod, ModInstallSummary, ModLoader, ModUpdateReport};
 use crate::mods::api::ModApi;
-use tauri::{command, AppHandle, Emitter};
+use crate::mods::conflicts::{self, ModConflict};
+use crate::mods::export::{render_mod_list, ModListFormat};
+use crate::mods::graph::{self, ModDependencyGraph};
+use crate::mods::groups::{self, GroupToggleResult, ModGroupSummary};
+use crate::mods::sync::{self, ManifestComparison, ManifestSyncReport};
+use crate::mods::trash::{self, TrashEntry};
+use crate::notifications::{self, LauncherNotification, NotificationCategory, NotificationTarget};
+use crate::tasks::{TaskHandle, TaskManagerState};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, State};
 use std::path::PathBuf;
 use std::collections::HashMap;
 use serde_json;
@@ -50,70 +59,307 @@ pub async fn get_mod_details(mod_id: String) -> Result<ModInfo, String> {
     Err(format!("Mod {} not found", mod_id))
 }
 
+/// Result of queueing a mod install
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModInstallTask {
+    pub task_id: String,
+    /// Set when the caller asked to wait for completion
+    pub summary: Option<ModInstallSummary>,
+}
+
 /// Install a mod to a specific instance
+///
+/// Installs run as background tasks queued per instance so concurrent requests can't
+/// race on the mods manifest. Returns the task id immediately unless `wait` is set.
+/// Missing required dependencies are installed too, optional ones only with `include_optional`.
 #[command]
 pub async fn install_mod(
     instance_id: String,
     mod_id: String,
     version_id: Option<String>,
+    include_optional: Option<bool>,
+    wait: Option<bool>,
     app_handle: AppHandle,
-) -> Result<InstalledMod, String> {
+    tasks: State<'_, TaskManagerState>,
+) -> Result<ModInstallTask, String> {
     let instance_path = get_instance_path(&instance_id)?;
-    let mut manager = ModManager::new(instance_path).await
+    let queue_key = format!("mods:{}", instance_id);
+
+    let instance_name = instance_name(&instance_id).await;
+
+    let task: TaskHandle<ModInstallSummary> = tasks.spawn(app_handle.clone(), "install_mod", &queue_key, move |ctx| async move {
+        ctx.set_subject(&instance_name, Some(NotificationTarget::Instance { id: instance_id.clone() }));
+
+        // Created inside the task so the manifest is read after earlier installs finished
+        let mut manager = ModManager::new(instance_path).await
+            .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+        let progress_ctx = ctx.clone();
+        let app_handle_clone = app_handle.clone();
+        let mod_id_clone = mod_id.clone();
+        let instance_id_clone = instance_id.clone();
+        let summary = manager.install_mod(
+            &mod_id,
+            version_id.as_deref(),
+            include_optional.unwrap_or(false),
+            move |downloaded, total| {
+                let progress = if total > 0 {
+                    (downloaded as f64 / total as f64 * 100.0) as u32
+                } else {
+                    0
+                };
+
+                progress_ctx.set_progress(progress, None);
+                let _ = app_handle_clone.emit("mod_install_progress", serde_json::json!({
+                    "task_id": progress_ctx.id,
+                    "instance_id": instance_id_clone,
+                    "mod_id": mod_id_clone,
+                    "progress": progress,
+                    "downloaded": downloaded,
+                    "total": total
+                }));
+            },
+            |index, total, dependency| {
+                println!("📦 Installing dependency {}/{} for {}: {}",
+                    index, total, mod_id, dependency.name.as_deref().unwrap_or(&dependency.mod_id));
+                let _ = app_handle.emit("mod_dependency_progress", serde_json::json!({
+                    "task_id": ctx.id,
+                    "instance_id": instance_id,
+                    "mod_id": mod_id,
+                    "dependency": dependency,
+                    "index": index,
+                    "total": total
+                }));
+            },
+        ).await
+        .map_err(|e| format!("Failed to install mod: {}", e))?;
+        ctx.set_subject(
+            &format!("{} in {}", summary.installed_mod.mod_info.name, instance_name),
+            Some(NotificationTarget::Instance { id: instance_id.clone() }),
+        );
+
+        let _ = app_handle.emit("mod_installed", serde_json::json!({
+            "task_id": ctx.id,
+            "instance_id": instance_id,
+            "mod": summary.installed_mod,
+            "dependencies_added": summary.dependencies_added
+        }));
+
+        Ok(summary)
+    });
+
+    let task_id = task.id.clone();
+    let summary = if wait.unwrap_or(false) {
+        Some(task.wait().await?)
+    } else {
+        None
+    };
+
+    Ok(ModInstallTask { task_id, summary })
+}
+
+/// Add a mod jar from disk to an instance
+#[command]
+pub async fn import_local_mod(
+    instance_path: String,
+    file_path: String,
+    app_handle: AppHandle,
+) -> Result<InstalledMod, String> {
+    let mut manager = ModManager::new(PathBuf::from(&instance_path)).await
         .map_err(|e| format!("Failed to create mod manager: {}", e))?;
-    
-    let app_handle_clone = app_handle.clone();
-    let mod_id_clone = mod_id.clone();
-    let instance_id_clone = instance_id.clone();
-    let installed_mod = manager.install_mod(
-        &mod_id,
-        version_id.as_deref(),
-        move |downloaded, total| {
-            let progress = if total > 0 {
-                (downloaded as f64 / total as f64 * 100.0) as u32
-            } else {
-                0
-            };
-            
-            let _ = app_handle_clone.emit("mod_install_progress", serde_json::json!({
-                "instance_id": instance_id_clone,
-                "mod_id": mod_id_clone,
-                "progress": progress,
-                "downloaded": downloaded,
-                "total": total
-            }));
-        }
-    ).await
-    .map_err(|e| format!("Failed to install mod: {}", e))?;
-    
-    let _ = app_handle.emit("mod_installed", serde_json::json!({
-        "instance_id": instance_id,
-        "mod": installed_mod
+
+    let installed_mod = manager.import_local_mod(&PathBuf::from(file_path)).await
+        .map_err(|e| format!("Failed to import mod: {}", e))?;
+
+    println!("📦 Imported {} ({})", installed_mod.mod_info.name, installed_mod.mod_info.source.api_name());
+    let mod_conflicts = conflicts::instance_conflicts(&manager).await;
+    for conflict in &mod_conflicts {
+        println!("⚠️  Mod conflict: {}", conflict.message);
+    }
+    let _ = app_handle.emit("mod_imported", serde_json::json!({
+        "instance_path": instance_path,
+        "mod": installed_mod,
+        "mod_conflicts": mod_conflicts
     }));
-    
+
     Ok(installed_mod)
 }
 
-/// Uninstall a mod from an instance
+/// Error from `uninstall_mod`, sent to the UI tagged by `kind`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ModUninstallError {
+    /// Other installed mods require this one, retry with `force` to remove it anyway
+    RequiredBy { message: String, dependents: Vec<String> },
+    Failed { message: String },
+}
+
+impl ModUninstallError {
+    pub fn message(&self) -> &str {
+        match self {
+            ModUninstallError::RequiredBy { message, .. }
+            | ModUninstallError::Failed { message } => message,
+        }
+    }
+}
+
+impl std::fmt::Display for ModUninstallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl From<String> for ModUninstallError {
+    fn from(message: String) -> Self {
+        ModUninstallError::Failed { message }
+    }
+}
+
+/// Nodes and dependency edges between the mods of an instance
+#[command]
+pub async fn get_mod_dependency_graph(instance_id: String) -> Result<ModDependencyGraph, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    Ok(graph::dependency_graph(&manager).await)
+}
+
+/// Enabled jars in an instance that can't be loaded together, with the copies to disable
+#[command]
+pub async fn get_mod_conflicts(instance_id: String) -> Result<Vec<ModConflict>, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    Ok(conflicts::instance_conflicts(&manager).await)
+}
+
+/// Uninstall a mod from an instance, moving its jar to the mod trash
+///
+/// Refused with `RequiredBy` while other installed mods require it, unless `force` is set.
+/// Returns the trash entry to pass to `restore_deleted_mod` for undo.
 #[command]
 pub async fn uninstall_mod(
     instance_id: String,
     mod_id: String,
+    force: Option<bool>,
     app_handle: AppHandle,
-) -> Result<(), String> {
+) -> Result<Option<String>, ModUninstallError> {
     let instance_path = get_instance_path(&instance_id)?;
     let mut manager = ModManager::new(instance_path).await
         .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    if !force.unwrap_or(false) {
+        let graph = graph::dependency_graph(&manager).await;
+        let dependents: Vec<String> = graph.required_by(&mod_id).iter().map(|node| node.name.clone()).collect();
+        if !dependents.is_empty() {
+            return Err(ModUninstallError::RequiredBy {
+                message: format!("{} {} this mod", dependents.join(", "), if dependents.len() == 1 { "requires" } else { "require" }),
+                dependents,
+            });
+        }
+    }
     
-    manager.uninstall_mod(&mod_id).await
+    let trash_entry = manager.uninstall_mod(&mod_id).await
         .map_err(|e| format!("Failed to uninstall mod: {}", e))?;
+    sync_mods_count(&instance_id, &manager, &app_handle).await;
     
     let _ = app_handle.emit("mod_uninstalled", serde_json::json!({
         "instance_id": instance_id,
-        "mod_id": mod_id
+        "mod_id": mod_id,
+        "trash_entry": trash_entry
     }));
     
-    Ok(())
+    Ok(trash_entry)
+}
+
+/// Mods uninstalled from an instance that can still be restored, newest first
+#[command]
+pub async fn list_mod_trash(instance_id: String) -> Result<Vec<TrashEntry>, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    Ok(manager.list_trash().await)
+}
+
+/// Put an uninstalled mod back from the trash
+#[command]
+pub async fn restore_deleted_mod(
+    instance_id: String,
+    trash_entry: String,
+    app_handle: AppHandle,
+) -> Result<Option<InstalledMod>, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let mut manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    let restored = manager.restore_deleted_mod(&trash_entry).await
+        .map_err(|e| format!("Failed to restore mod: {}", e))?;
+    sync_mods_count(&instance_id, &manager, &app_handle).await;
+
+    println!("♻️  Restored {} in instance {}", trash_entry, instance_id);
+    let _ = app_handle.emit("mod_restored", serde_json::json!({
+        "instance_id": instance_id,
+        "trash_entry": trash_entry,
+        "mod": restored
+    }));
+
+    Ok(restored)
+}
+
+/// Download mods the manifest lists but the mods folder lacks, then check the instance again
+///
+/// Returns the names of the mods downloaded.
+#[command]
+pub async fn repair_instance_mods(instance_id: String, app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let mut manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    let restored = manager.redownload_missing_mods().await
+        .map_err(|e| format!("Failed to repair mods: {}", e))?;
+    sync_mods_count(&instance_id, &manager, &app_handle).await;
+    crate::minecraft::integrity::refresh(&instance_id, &app_handle).await;
+
+    println!("🔧 Downloaded {} missing mod(s) for instance {}", restored.len(), instance_id);
+    Ok(restored)
+}
+
+/// Permanently delete trashed mods, only those older than `older_than_days` when given
+#[command]
+pub async fn empty_mod_trash(instance_id: String, older_than_days: Option<u32>) -> Result<u32, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    let removed = trash::purge(manager.mods_path(), older_than_days).await
+        .map_err(|e| format!("Failed to empty mod trash: {}", e))?;
+    println!("🗑️  Deleted {} mod(s) from the trash of instance {}", removed, instance_id);
+    Ok(removed)
+}
+
+/// Store the instance's mod count after jars were moved in or out of its mods folder
+async fn sync_mods_count(instance_id: &str, manager: &ModManager, app_handle: &AppHandle) {
+    let mods_count = crate::minecraft::commands::import::count_mods(manager.mods_path());
+    let Ok(storage) = crate::storage::shared().await else {
+        return;
+    };
+    let mut storage = storage.write().await;
+    let Some(mut instance) = storage.get_instance(instance_id).cloned() else {
+        return;
+    };
+    if instance.mods_count == mods_count {
+        return;
+    }
+    instance.mods_count = mods_count;
+    if let Err(e) = storage.update_instance(instance).await {
+        println!("⚠️  Failed to update mod count of {}: {}", instance_id, e);
+        return;
+    }
+    drop(storage);
+    crate::minecraft::commands::instances::notify_instances_updated(app_handle);
 }
 
 /// Update a mod to the latest version
@@ -158,6 +404,139 @@ pub async fn update_mod(
     Ok(())
 }
 
+/// Update every mod in an instance that has a newer file for its game version and loader
+#[command]
+pub async fn update_all_mods(
+    instance_path: String,
+    game_version: String,
+    mod_loader: String,
+    dry_run: Option<bool>,
+    app_handle: AppHandle,
+) -> Result<ModUpdateReport, String> {
+    let mut manager = ModManager::new(PathBuf::from(&instance_path)).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    let report = manager.update_all_mods(
+        &game_version,
+        &mod_loader,
+        dry_run.unwrap_or(false),
+        |current, total, mod_name, status| {
+            let _ = app_handle.emit("mods_update_progress", serde_json::json!({
+                "instance_path": instance_path,
+                "current": current,
+                "total": total,
+                "mod_name": mod_name,
+                "status": status
+            }));
+        },
+    ).await
+    .map_err(|e| format!("Failed to update mods: {}", e))?;
+
+    println!(
+        "🔄 Mod update{}: {} updated, {} skipped, {} failed",
+        if report.dry_run { " (dry run)" } else { "" },
+        report.updated.len(),
+        report.skipped.len(),
+        report.failed.len()
+    );
+    for conflict in &report.prevented_conflicts {
+        println!("🚫 Held back an update that would conflict: {}", conflict.message);
+    }
+
+    Ok(report)
+}
+
+/// Compare an instance's mods to a server's mod manifest, read from a URL or a file
+#[command]
+pub async fn compare_instance_to_manifest(
+    instance_id: String,
+    manifest_url_or_path: String,
+) -> Result<ManifestComparison, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let manifest = sync::load_manifest(&manifest_url_or_path).await
+        .map_err(|e| format!("Failed to read mod manifest: {}", e))?;
+    let local = sync::scan_mods_folder(&instance_path.join("mods")).await
+        .map_err(|e| format!("Failed to read mods folder: {}", e))?;
+
+    Ok(sync::compare(&manifest, &local))
+}
+
+/// Make an instance's mods match a server's mod manifest
+///
+/// Runs on the instance's mod queue so it can't race with installs. Mismatched jars
+/// are moved to `mods/.extra`, other extras only with `remove_extras`.
+#[command]
+pub async fn sync_instance_to_manifest(
+    instance_id: String,
+    manifest: String,
+    remove_extras: bool,
+    app_handle: AppHandle,
+    tasks: State<'_, TaskManagerState>,
+) -> Result<ManifestSyncReport, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let entries = sync::load_manifest(&manifest).await
+        .map_err(|e| format!("Failed to read mod manifest: {}", e))?;
+    let queue_key = format!("mods:{}", instance_id);
+
+    let task: TaskHandle<ManifestSyncReport> = tasks.spawn(app_handle.clone(), "sync_mods", &queue_key, move |ctx| async move {
+        let mut manager = ModManager::new(instance_path).await
+            .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+        let report = sync::sync_to_manifest(&mut manager, &entries, remove_extras, |current, total, filename, status| {
+            ctx.set_progress((current * 100 / total.max(1)) as u32, Some(filename.to_string()));
+            let _ = app_handle.emit("mods_sync_progress", serde_json::json!({
+                "task_id": ctx.id,
+                "instance_id": instance_id,
+                "current": current,
+                "total": total,
+                "filename": filename,
+                "status": status
+            }));
+        }).await
+        .map_err(|e| format!("Failed to sync mods: {}", e))?;
+
+        println!(
+            "🔁 Synced mods of {}: {} installed, {} moved to .extra, {} failed",
+            instance_id,
+            report.installed.len(),
+            report.quarantined.len(),
+            report.failed.len()
+        );
+        Ok(report)
+    });
+
+    task.wait().await
+}
+
+/// Write an instance's mod list as JSON or a Markdown table to `output_path`
+#[command]
+pub async fn export_mod_list(
+    instance_path: String,
+    format: String,
+    output_path: String,
+) -> Result<String, String> {
+    let format = ModListFormat::parse(&format)
+        .ok_or_else(|| format!("Unsupported mod list format: {}", format))?;
+    let manager = ModManager::new(PathBuf::from(&instance_path)).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    let entries = manager.mod_list().await
+        .map_err(|e| format!("Failed to list mods: {}", e))?;
+    let contents = render_mod_list(&entries, format)
+        .map_err(|e| format!("Failed to render mod list: {}", e))?;
+
+    let output_path = PathBuf::from(output_path);
+    if let Some(parent) = output_path.parent() {
+        tokio::fs::create_dir_all(parent).await
+            .map_err(|e| format!("Failed to create export directory: {}", e))?;
+    }
+    tokio::fs::write(&output_path, contents).await
+        .map_err(|e| format!("Failed to write mod list: {}", e))?;
+
+    println!("📝 Exported {} mods to {}", entries.len(), output_path.display());
+    Ok(output_path.to_string_lossy().to_string())
+}
+
 /// Get all installed mods for an instance
 #[command]
 pub async fn get_installed_mods(instance_id: String) -> Result<HashMap<String, InstalledMod>, String> {
@@ -192,6 +571,57 @@ pub async fn set_mod_enabled(
     Ok(())
 }
 
+/// Put a mod in a named group, None or a blank name removes it from its group
+#[command]
+pub async fn set_mod_group(instance_id: String, mod_id: String, group: Option<String>) -> Result<(), String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let mut manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    manager.set_mod_group(&mod_id, group).await
+        .map_err(|e| format!("Failed to set mod group: {}", e))
+}
+
+/// Groups in an instance with how many of their mods are enabled
+#[command]
+pub async fn list_mod_groups(instance_id: String) -> Result<Vec<ModGroupSummary>, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    Ok(groups::summarize(&manager))
+}
+
+/// Enable or disable every mod in a group
+///
+/// Members that an enabled mod outside the group requires stay enabled, with a warning.
+#[command]
+pub async fn set_group_enabled(
+    instance_id: String,
+    group: String,
+    enabled: bool,
+    app_handle: AppHandle,
+) -> Result<GroupToggleResult, String> {
+    let instance_path = get_instance_path(&instance_id)?;
+    let mut manager = ModManager::new(instance_path).await
+        .map_err(|e| format!("Failed to create mod manager: {}", e))?;
+
+    let result = groups::set_group_enabled(&mut manager, &group, enabled).await
+        .map_err(|e| format!("Failed to switch mod group: {}", e))?;
+    for warning in &result.warnings {
+        println!("⚠️  {}", warning);
+    }
+    for mod_id in &result.changed {
+        let _ = app_handle.emit("mod_enabled_changed", serde_json::json!({
+            "instance_id": instance_id,
+            "mod_id": mod_id,
+            "enabled": enabled
+        }));
+    }
+
+    Ok(result)
+}
+
 /// Check for updates for all mods in an instance
 #[command]
 pub async fn check_mod_updates(
@@ -209,6 +639,16 @@ pub async fn check_mod_updates(
         "instance_id": instance_id,
         "mods_with_updates": mods_with_updates
     }));
+
+    if !mods_with_updates.is_empty() {
+        let body = format!("{} mods in {} can be updated", mods_with_updates.len(), instance_name(&instance_id).await);
+        notifications::send(&app_handle, LauncherNotification::new(
+            NotificationCategory::Updates,
+            "Mod updates available",
+            &body,
+            Some(NotificationTarget::Instance { id: instance_id.clone() }),
+        )).await;
+    }
     
     Ok(mods_with_updates)
 }
@@ -226,6 +666,15 @@ pub async fn get_mod_loader_versions(
         .map_err(|e| format!("Failed to get loader versions: {}", e))
 }
 
+/// Which mod loaders have builds for a Minecraft version
+#[command]
+pub async fn get_loader_availability(mc_version: String) -> Result<Vec<crate::mods::loaders::LoaderAvailability>, String> {
+    let temp_instance_path = std::env::temp_dir().join("temp_loader_check");
+    let loader_manager = crate::mods::loaders::ModLoaderManager::new(temp_instance_path);
+    
+    Ok(loader_manager.get_loader_availability(&mc_version).await)
+}
+
 /// Install a mod loader for an instance
 #[command]
 pub async fn install_mod_loader(
@@ -246,6 +695,9 @@ pub async fn install_mod_loader(
         _ => return Err(format!("Unsupported loader: {}", loader_name)),
     };
     
+    loader_manager.check_loader_available(loader.name(), &mc_version).await
+        .map_err(|e| format!("Cannot install {}: {}", loader_name, e))?;
+    
     loader_manager.install_loader(&loader, &mc_version).await
         .map_err(|e| format!("Failed to install mod loader: {}", e))?;
     
@@ -322,6 +774,16 @@ pub async fn get_mod_categories() -> Result<Vec<String>, String> {
 }
 
 // Helper function to get instance path
+/// Display name of an instance, its id when it isn't registered
+async fn instance_name(instance_id: &str) -> String {
+    let Ok(storage) = crate::storage::shared().await else {
+        return instance_id.to_string();
+    };
+    let storage = storage.read().await;
+    storage.get_instance(instance_id)
+        .map_or_else(|| instance_id.to_string(), |instance| instance.name.clone())
+}
+
 fn get_instance_path(instance_id: &str) -> Result<PathBuf, String> {
     // This should integrate with the existing instance management system
     // For now, we'll use a basic implementation
@@ -0,0 +1,319 @@
+//! Conflicts between the enabled jars of an instance
+//!
+//! Two jars that declare the same mod id, provide an id another jar already
+//! answers to, or register the same mixin config can't be loaded together: the
+//! loader refuses to start or the game crashes applying mixins. Mods nested
+//! inside a jar aren't compared, loaders pick one copy of those themselves.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::mods::graph;
+use crate::mods::local::JarDependencies;
+use crate::mods::{ModFile, ModManager};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModConflictKind {
+    /// Jars whose own metadata declares the same mod id
+    DuplicateId,
+    /// A jar provides an id another jar declares or provides too
+    OverlappingProvides,
+    /// Jars that register a mixin config with the same name
+    SharedMixinConfig,
+}
+
+/// One jar taking part in a conflict
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConflictingMod {
+    /// Key in the mods manifest, unset for jars added to the folder by hand
+    pub mod_id: Option<String>,
+    pub name: String,
+    pub version: Option<String>,
+    pub file_name: String,
+}
+
+impl ConflictingMod {
+    fn label(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} {}", self.name, version),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModConflict {
+    pub kind: ModConflictKind,
+    /// The mod id or mixin config the jars share
+    pub id: String,
+    /// Newest first
+    pub mods: Vec<ConflictingMod>,
+    pub message: String,
+    /// The older copies, disabling them resolves the conflict
+    pub disable: Vec<ConflictingMod>,
+    pub suggestion: String,
+}
+
+/// An enabled jar and what its metadata declares
+#[derive(Debug, Clone)]
+pub struct ScannedJar {
+    pub jar: ConflictingMod,
+    /// Release date of the installed file, or when the jar was written for hand-added ones
+    pub released: Option<DateTime<Utc>>,
+    pub metadata: JarDependencies,
+}
+
+/// An update downloaded next to the jar it will replace
+pub struct PendingUpdate<'a> {
+    pub mod_id: &'a str,
+    pub temp_path: &'a Path,
+    pub file: &'a ModFile,
+}
+
+/// Conflicts between the enabled jars in a manager's mods folder
+pub async fn instance_conflicts(manager: &ModManager) -> Vec<ModConflict> {
+    find_conflicts(&enabled_jars(manager).await)
+}
+
+/// Pending updates that would add a conflict, with the conflict each one causes
+///
+/// Conflicts the instance already has don't count. Updates are taken out until
+/// the rest add none, so applying every update not listed is safe.
+pub async fn conflicting_updates(manager: &ModManager, pending: &[PendingUpdate<'_>]) -> Vec<(String, ModConflict)> {
+    let current = enabled_jars(manager).await;
+    let before: HashSet<(ModConflictKind, String)> = find_conflicts(&current).into_iter()
+        .map(|conflict| (conflict.kind, conflict.id))
+        .collect();
+
+    let mut replacements: HashMap<&str, ScannedJar> = HashMap::new();
+    for update in pending {
+        let Some(old) = current.iter().find(|jar| jar.jar.mod_id.as_deref() == Some(update.mod_id)) else {
+            continue;
+        };
+        let Some(metadata) = graph::read_jar(update.temp_path, update.file.hashes.get("sha1").cloned()).await else {
+            continue;
+        };
+        replacements.insert(update.mod_id, ScannedJar {
+            jar: ConflictingMod {
+                mod_id: Some(update.mod_id.to_string()),
+                name: old.jar.name.clone(),
+                version: Some(update.file.version.clone()),
+                file_name: update.file.filename.clone(),
+            },
+            released: Some(update.file.date_published),
+            metadata,
+        });
+    }
+
+    let mut blocked = Vec::new();
+    loop {
+        let jars: Vec<ScannedJar> = current.iter()
+            .map(|jar| {
+                jar.jar.mod_id.as_deref()
+                    .and_then(|mod_id| replacements.get(mod_id))
+                    .unwrap_or(jar)
+                    .clone()
+            })
+            .collect();
+        let added: Vec<ModConflict> = find_conflicts(&jars).into_iter()
+            .filter(|conflict| !before.contains(&(conflict.kind, conflict.id.clone())))
+            .collect();
+
+        let mut removed_any = false;
+        for conflict in added {
+            for conflicting in &conflict.mods {
+                let Some(mod_id) = &conflicting.mod_id else { continue };
+                if replacements.remove(mod_id.as_str()).is_some() {
+                    blocked.push((mod_id.clone(), conflict.clone()));
+                    removed_any = true;
+                }
+            }
+        }
+        if !removed_any {
+            return blocked;
+        }
+    }
+}
+
+/// `.jar` files in the mods folder, matched to their manifest entries
+async fn enabled_jars(manager: &ModManager) -> Vec<ScannedJar> {
+    let Ok(mut entries) = tokio::fs::read_dir(manager.mods_path()).await else {
+        return Vec::new();
+    };
+
+    let mut jars = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "jar") {
+            continue;
+        }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let installed = manager.get_installed_mods().iter()
+            .find(|(_, installed)| installed.install_path == path);
+        let scanned = match installed {
+            Some((mod_id, installed)) => graph::jar_dependencies(installed).await.map(|metadata| ScannedJar {
+                jar: ConflictingMod {
+                    mod_id: Some(mod_id.clone()),
+                    name: installed.mod_info.name.clone(),
+                    version: Some(installed.installed_file.version.clone()),
+                    file_name,
+                },
+                released: Some(installed.installed_file.date_published),
+                metadata,
+            }),
+            None => graph::read_jar(&path, None).await.map(|metadata| ScannedJar {
+                jar: ConflictingMod { mod_id: None, name: file_name.clone(), version: None, file_name },
+                released: std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok().map(DateTime::from),
+                metadata,
+            }),
+        };
+        jars.extend(scanned);
+    }
+    jars.sort_by(|a, b| a.jar.file_name.cmp(&b.jar.file_name));
+    jars
+}
+
+/// Conflicts between `jars`, one per set of jars
+///
+/// Two copies of one mod share every id and mixin config; they're reported once,
+/// under the first id.
+pub fn find_conflicts(jars: &[ScannedJar]) -> Vec<ModConflict> {
+    // Id to the jars that answer to it, and whether any of them only provides it
+    let mut ids: BTreeMap<&str, (Vec<usize>, bool)> = BTreeMap::new();
+    let mut mixins: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (index, jar) in jars.iter().enumerate() {
+        let own = jar.metadata.own_ids.iter().map(|id| (id, false));
+        let provided = jar.metadata.provides.iter()
+            .filter(|id| !jar.metadata.own_ids.contains(id))
+            .map(|id| (id, true));
+        for (id, is_provided) in own.chain(provided) {
+            let (holders, any_provided) = ids.entry(id.as_str()).or_default();
+            if !holders.contains(&index) {
+                holders.push(index);
+                *any_provided |= is_provided;
+            }
+        }
+        for config in &jar.metadata.mixin_configs {
+            let holders = mixins.entry(config.as_str()).or_default();
+            if !holders.contains(&index) {
+                holders.push(index);
+            }
+        }
+    }
+
+    let candidates = ids.into_iter()
+        .map(|(id, (holders, any_provided))| {
+            let kind = if any_provided { ModConflictKind::OverlappingProvides } else { ModConflictKind::DuplicateId };
+            (kind, id, holders)
+        })
+        .chain(mixins.into_iter().map(|(config, holders)| (ModConflictKind::SharedMixinConfig, config, holders)));
+
+    let mut seen: HashSet<Vec<usize>> = HashSet::new();
+    let mut conflicts = Vec::new();
+    for (kind, id, mut holders) in candidates {
+        if holders.len() < 2 {
+            continue;
+        }
+        holders.sort_unstable();
+        if !seen.insert(holders.clone()) {
+            continue;
+        }
+
+        // Newest first, jars with no date count as oldest
+        holders.sort_by(|&a, &b| {
+            jars[b].released.cmp(&jars[a].released)
+                .then_with(|| jars[a].jar.file_name.cmp(&jars[b].jar.file_name))
+        });
+        let mods: Vec<ConflictingMod> = holders.iter().map(|&index| jars[index].jar.clone()).collect();
+        conflicts.push(conflict(kind, id, mods));
+    }
+    conflicts
+}
+
+fn conflict(kind: ModConflictKind, id: &str, mods: Vec<ConflictingMod>) -> ModConflict {
+    let labels: Vec<String> = mods.iter().map(ConflictingMod::label).collect();
+    let message = match kind {
+        ModConflictKind::DuplicateId => format!("{} all declare mod id '{}'", labels.join(", "), id),
+        ModConflictKind::OverlappingProvides => format!("{} all answer to mod id '{}'", labels.join(", "), id),
+        ModConflictKind::SharedMixinConfig => format!("{} all register mixin config '{}'", labels.join(", "), id),
+    };
+    let disable = mods[1..].to_vec();
+    let suggestion = format!("Keep {} and disable {}", labels[0], labels[1..].join(", "));
+    ModConflict { kind, id: id.to_string(), mods, message, disable, suggestion }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jar(file_name: &str, released: &str, own_ids: &[&str], provides: &[&str], mixins: &[&str]) -> ScannedJar {
+        let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect::<Vec<_>>();
+        ScannedJar {
+            jar: ConflictingMod {
+                mod_id: Some(file_name.trim_end_matches(".jar").to_string()),
+                name: file_name.to_string(),
+                version: None,
+                file_name: file_name.to_string(),
+            },
+            released: Some(released.parse().unwrap()),
+            metadata: JarDependencies {
+                mod_ids: strings(own_ids).into_iter().chain(strings(provides)).collect(),
+                own_ids: strings(own_ids),
+                provides: strings(provides),
+                mixin_configs: strings(mixins),
+                dependencies: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn duplicate_ids_suggest_disabling_the_older_copy() {
+        let jars = vec![
+            jar("sodium-0.5.3.jar", "2023-09-01T00:00:00Z", &["sodium"], &[], &["sodium.mixins.json"]),
+            jar("sodium-0.5.8.jar", "2024-02-01T00:00:00Z", &["sodium"], &[], &["sodium.mixins.json"]),
+            jar("lithium.jar", "2024-01-01T00:00:00Z", &["lithium"], &[], &["lithium.mixins.json"]),
+        ];
+
+        let conflicts = find_conflicts(&jars);
+        assert_eq!(conflicts.len(), 1, "two copies of one mod are one conflict");
+        let conflict = &conflicts[0];
+        assert_eq!(conflict.kind, ModConflictKind::DuplicateId);
+        assert_eq!(conflict.id, "sodium");
+        assert_eq!(conflict.mods[0].file_name, "sodium-0.5.8.jar");
+        let disable: Vec<&str> = conflict.disable.iter().map(|m| m.file_name.as_str()).collect();
+        assert_eq!(disable, vec!["sodium-0.5.3.jar"]);
+    }
+
+    #[test]
+    fn provides_and_mixin_configs_overlap() {
+        let jars = vec![
+            jar("embeddium.jar", "2024-01-01T00:00:00Z", &["embeddium"], &["rubidium"], &[]),
+            jar("rubidium.jar", "2023-01-01T00:00:00Z", &["rubidium"], &[], &[]),
+            jar("a.jar", "2024-01-01T00:00:00Z", &["a"], &[], &["shared.mixins.json"]),
+            jar("b.jar", "2024-01-01T00:00:00Z", &["b"], &[], &["shared.mixins.json"]),
+        ];
+
+        let conflicts = find_conflicts(&jars);
+        let kinds: Vec<(ModConflictKind, &str)> = conflicts.iter()
+            .map(|conflict| (conflict.kind, conflict.id.as_str()))
+            .collect();
+        assert_eq!(kinds, vec![
+            (ModConflictKind::OverlappingProvides, "rubidium"),
+            (ModConflictKind::SharedMixinConfig, "shared.mixins.json"),
+        ]);
+    }
+
+    #[test]
+    fn nested_mods_are_not_conflicts() {
+        let mut fabric_api = jar("fabric-api.jar", "2024-01-01T00:00:00Z", &["fabric-api"], &[], &[]);
+        fabric_api.metadata.mod_ids.push("fabric-api-base".to_string());
+        let mut create = jar("create.jar", "2024-01-01T00:00:00Z", &["create"], &[], &[]);
+        create.metadata.mod_ids.push("fabric-api-base".to_string());
+
+        assert!(find_conflicts(&[fabric_api, create]).is_empty());
+    }
+}
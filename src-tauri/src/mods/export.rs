@@ -0,0 +1,92 @@
+use crate::mods::types::ModListEntry;
+
+/// File formats a mod list can be exported as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModListFormat {
+    Json,
+    Markdown,
+}
+
+impl ModListFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "json" => Some(ModListFormat::Json),
+            "markdown" | "md" => Some(ModListFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// Render a mod list in the given format
+pub fn render_mod_list(entries: &[ModListEntry], format: ModListFormat) -> Result<String, serde_json::Error> {
+    match format {
+        ModListFormat::Json => serde_json::to_string_pretty(entries),
+        ModListFormat::Markdown => Ok(render_markdown(entries)),
+    }
+}
+
+fn render_markdown(entries: &[ModListEntry]) -> String {
+    let mut out = String::from("| Mod | Version | File | Modrinth | SHA1 |\n|---|---|---|---|---|\n");
+
+    for entry in entries {
+        let name = if entry.enabled {
+            escape_cell(&entry.name)
+        } else {
+            format!("{} *(disabled)*", escape_cell(&entry.name))
+        };
+        let project = entry.modrinth_project_id.as_deref()
+            .map(|id| format!("[{}](https://modrinth.com/mod/{})", id, id))
+            .unwrap_or_else(|| "-".to_string());
+
+        out.push_str(&format!(
+            "| {} | {} | `{}` | {} | `{}` |\n",
+            name,
+            escape_cell(entry.version.as_deref().unwrap_or("-")),
+            escape_cell(&entry.file_name),
+            project,
+            entry.sha1.as_deref().unwrap_or("-"),
+        ));
+    }
+
+    let disabled = entries.iter().filter(|e| !e.enabled).count();
+    out.push_str(&format!("\n{} mods, {} disabled\n", entries.len(), disabled));
+    out
+}
+
+/// Keep pipes and newlines in names from breaking the table
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, enabled: bool, project: Option<&str>) -> ModListEntry {
+        ModListEntry {
+            name: name.to_string(),
+            version: project.map(|_| "1.0.0".to_string()),
+            file_name: format!("{}.jar", name),
+            modrinth_project_id: project.map(str::to_string),
+            sha1: Some("da39a3ee5e6b4b0d3255bfef95601890afd80709".to_string()),
+            enabled,
+        }
+    }
+
+    #[test]
+    fn markdown_marks_disabled_and_unmatched_mods() {
+        let entries = vec![entry("Sodium", true, Some("AANobbMI")), entry("custom|tweaks", false, None)];
+        let markdown = render_mod_list(&entries, ModListFormat::Markdown).unwrap();
+
+        assert!(markdown.contains("| Sodium | 1.0.0 | `Sodium.jar` | [AANobbMI](https://modrinth.com/mod/AANobbMI) |"));
+        assert!(markdown.contains("| custom\\|tweaks *(disabled)* | - | `custom\\|tweaks.jar` | - | `da39a3ee5e6b4b0d3255bfef95601890afd80709` |"));
+        assert!(markdown.ends_with("2 mods, 1 disabled\n"));
+    }
+
+    #[test]
+    fn format_names_are_case_insensitive() {
+        assert_eq!(ModListFormat::parse("MD"), Some(ModListFormat::Markdown));
+        assert_eq!(ModListFormat::parse("Json"), Some(ModListFormat::Json));
+        assert_eq!(ModListFormat::parse("csv"), None);
+    }
+}
@@ -0,0 +1,283 @@
+//! Dependency graph of the mods installed in an instance
+//!
+//! Edges come from two places: the dependencies each jar declares in its loader
+//! metadata, which use loader mod ids, and those the mod's platform lists for the
+//! installed file, which use project ids. Both are mapped onto installed mods;
+//! dependencies that nothing installed satisfies become nodes marked not installed.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::mods::api::common::ApiUtils;
+use crate::mods::local::{self, JarDependencies};
+use crate::mods::{DependencyType, InstalledMod, ModDependency, ModManager, ModSource};
+
+/// Ids the loader or the game provide, never installed as mods
+const PLATFORM_IDS: &[&str] = &[
+    "minecraft", "java", "fabricloader", "fabric-loader", "quilt_loader",
+    "forge", "neoforge", "javafml", "lowcodefml", "mixinextras",
+];
+
+/// Parsed jar metadata by SHA1, jars shared between instances are read once
+static JAR_CACHE: OnceLock<Mutex<HashMap<String, JarDependencies>>> = OnceLock::new();
+
+fn jar_cache() -> &'static Mutex<HashMap<String, JarDependencies>> {
+    JAR_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModGraphEdgeKind {
+    Required,
+    Optional,
+    Incompatible,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModGraphNode {
+    /// Installed mod id, or the dependency's id when nothing installed provides it
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+    /// Loader ids from the jar, which is how other jars refer to this mod
+    pub mod_ids: Vec<String>,
+    pub enabled: bool,
+    pub installed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModGraphEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: ModGraphEdgeKind,
+    /// "jar" for loader metadata, otherwise the platform the dependency came from
+    pub source: String,
+    /// Version requirement from the jar, if it has one
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ModDependencyGraph {
+    pub nodes: Vec<ModGraphNode>,
+    pub edges: Vec<ModGraphEdge>,
+}
+
+impl ModDependencyGraph {
+    /// Installed mods with a required dependency on `mod_id`
+    pub fn required_by(&self, mod_id: &str) -> Vec<&ModGraphNode> {
+        let dependents: HashSet<&str> = self.edges.iter()
+            .filter(|edge| edge.to == mod_id && edge.kind == ModGraphEdgeKind::Required)
+            .map(|edge| edge.from.as_str())
+            .collect();
+        self.nodes.iter()
+            .filter(|node| node.installed && dependents.contains(node.id.as_str()))
+            .collect()
+    }
+}
+
+/// What the graph needs to know about one installed mod
+struct GraphMod {
+    id: String,
+    name: String,
+    version: String,
+    enabled: bool,
+    source: ModSource,
+    api_dependencies: Vec<ModDependency>,
+    jar: Option<JarDependencies>,
+}
+
+/// Build the graph for the mods a manager has installed
+pub async fn dependency_graph(manager: &ModManager) -> ModDependencyGraph {
+    let mut mods = Vec::new();
+    for installed in manager.get_installed_mods().values() {
+        let jar = jar_dependencies(installed).await;
+        mods.push(GraphMod {
+            id: installed.mod_info.id.clone(),
+            name: installed.mod_info.name.clone(),
+            version: installed.installed_file.version.clone(),
+            enabled: installed.enabled,
+            source: installed.mod_info.source.clone(),
+            api_dependencies: installed.installed_file.dependencies.clone(),
+            jar,
+        });
+    }
+    mods.sort_by_key(|m| m.name.to_lowercase());
+    build_graph(mods)
+}
+
+/// Dependencies declared in the mod's jar, None when it can't be read
+pub(crate) async fn jar_dependencies(installed: &InstalledMod) -> Option<JarDependencies> {
+    let path = &installed.install_path;
+    let recorded = installed.installed_file.hashes.get("sha1")
+        .filter(|_| file_size(path) == Some(installed.installed_file.size))
+        .cloned();
+    read_jar(path, recorded).await
+}
+
+/// Dependencies declared in any jar, `sha1` skips hashing when it's already known
+pub(crate) async fn read_jar(path: &Path, sha1: Option<String>) -> Option<JarDependencies> {
+    let sha1 = match sha1 {
+        Some(sha1) => sha1,
+        None => ApiUtils::file_sha1(path).await.ok()?,
+    };
+
+    if let Some(cached) = jar_cache().lock().unwrap().get(&sha1) {
+        return Some(cached.clone());
+    }
+
+    let jar_path = path.to_path_buf();
+    let read = tokio::task::spawn_blocking(move || local::read_jar_dependencies(&jar_path)).await;
+    match read {
+        Ok(Ok(dependencies)) => {
+            jar_cache().lock().unwrap().insert(sha1, dependencies.clone());
+            Some(dependencies)
+        }
+        Ok(Err(e)) => {
+            println!("⚠️  Couldn't read dependencies of {}: {}", path.display(), e);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|metadata| metadata.len())
+}
+
+fn build_graph(mods: Vec<GraphMod>) -> ModDependencyGraph {
+    // Loader ids to the installed mod that answers to them
+    let mut by_mod_id: HashMap<String, String> = HashMap::new();
+    for graph_mod in &mods {
+        for mod_id in graph_mod.jar.iter().flat_map(|jar| &jar.mod_ids) {
+            by_mod_id.entry(mod_id.clone()).or_insert_with(|| graph_mod.id.clone());
+        }
+    }
+    let installed_ids: HashSet<&str> = mods.iter().map(|graph_mod| graph_mod.id.as_str()).collect();
+
+    let mut graph = ModDependencyGraph::default();
+    let mut missing: Vec<ModGraphNode> = Vec::new();
+    let mut seen: HashSet<(String, String, String)> = HashSet::new();
+
+    for graph_mod in &mods {
+        let mut targets: Vec<(String, ModGraphEdgeKind, String, Option<String>)> = Vec::new();
+
+        for dependency in graph_mod.jar.iter().flat_map(|jar| &jar.dependencies) {
+            if PLATFORM_IDS.contains(&dependency.mod_id.as_str()) {
+                continue;
+            }
+            let Some(kind) = edge_kind(&dependency.dependency_type) else { continue };
+            let to = by_mod_id.get(&dependency.mod_id).cloned().unwrap_or_else(|| dependency.mod_id.clone());
+            targets.push((to, kind, "jar".to_string(), dependency.version.clone()));
+        }
+        if !matches!(graph_mod.source, ModSource::Local) {
+            for dependency in &graph_mod.api_dependencies {
+                let Some(kind) = edge_kind(&dependency.dependency_type) else { continue };
+                targets.push((dependency.mod_id.clone(), kind, graph_mod.source.api_name().to_string(), None));
+            }
+        }
+
+        for (to, kind, source, version) in targets {
+            if to == graph_mod.id || !seen.insert((graph_mod.id.clone(), to.clone(), format!("{:?}", kind))) {
+                continue;
+            }
+            if !installed_ids.contains(to.as_str()) && !missing.iter().any(|node| node.id == to) {
+                missing.push(ModGraphNode {
+                    id: to.clone(),
+                    name: to.clone(),
+                    version: None,
+                    mod_ids: Vec::new(),
+                    enabled: false,
+                    installed: false,
+                });
+            }
+            graph.edges.push(ModGraphEdge { from: graph_mod.id.clone(), to, kind, source, version });
+        }
+    }
+
+    graph.nodes = mods.into_iter()
+        .map(|graph_mod| ModGraphNode {
+            id: graph_mod.id,
+            name: graph_mod.name,
+            version: Some(graph_mod.version),
+            mod_ids: graph_mod.jar.map(|jar| jar.mod_ids).unwrap_or_default(),
+            enabled: graph_mod.enabled,
+            installed: true,
+        })
+        .chain(missing)
+        .collect();
+    graph
+}
+
+/// Embedded dependencies ship inside the jar and aren't edges between mods
+fn edge_kind(dependency_type: &DependencyType) -> Option<ModGraphEdgeKind> {
+    match dependency_type {
+        DependencyType::Required => Some(ModGraphEdgeKind::Required),
+        DependencyType::Optional => Some(ModGraphEdgeKind::Optional),
+        DependencyType::Incompatible => Some(ModGraphEdgeKind::Incompatible),
+        DependencyType::Embedded => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mods::local::DeclaredDependency;
+
+    fn graph_mod(id: &str, mod_ids: &[&str], dependencies: Vec<DeclaredDependency>) -> GraphMod {
+        GraphMod {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: "1.0".to_string(),
+            enabled: true,
+            source: ModSource::Modrinth,
+            api_dependencies: Vec::new(),
+            jar: Some(JarDependencies {
+                mod_ids: mod_ids.iter().map(|mod_id| mod_id.to_string()).collect(),
+                dependencies,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn declared(mod_id: &str, dependency_type: DependencyType) -> DeclaredDependency {
+        DeclaredDependency { mod_id: mod_id.to_string(), dependency_type, version: None }
+    }
+
+    #[test]
+    fn jar_and_platform_dependencies_map_onto_installed_mods() {
+        let mut create = graph_mod("LNytGWDc", &["create"], vec![
+            declared("fabricloader", DependencyType::Required),
+            declared("fabric-api-base", DependencyType::Required),
+            declared("jei", DependencyType::Optional),
+        ]);
+        create.api_dependencies.push(ModDependency {
+            mod_id: "P7dR8mSH".to_string(),
+            version_id: None,
+            file_name: None,
+            dependency_type: DependencyType::Required,
+        });
+        let fabric_api = graph_mod("P7dR8mSH", &["fabric-api", "fabric-api-base"], Vec::new());
+        let sodium = graph_mod("AANobbMI", &["sodium"], vec![declared("create", DependencyType::Incompatible)]);
+
+        let graph = build_graph(vec![create, fabric_api, sodium]);
+
+        assert_eq!(graph.nodes.len(), 4);
+        let jei = graph.nodes.iter().find(|node| node.id == "jei").unwrap();
+        assert!(!jei.installed);
+
+        let edges: Vec<(&str, &str, &ModGraphEdgeKind, &str)> = graph.edges.iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str(), &edge.kind, edge.source.as_str()))
+            .collect();
+        assert_eq!(edges, vec![
+            ("LNytGWDc", "P7dR8mSH", &ModGraphEdgeKind::Required, "jar"),
+            ("LNytGWDc", "jei", &ModGraphEdgeKind::Optional, "jar"),
+            ("AANobbMI", "LNytGWDc", &ModGraphEdgeKind::Incompatible, "jar"),
+        ]);
+
+        let dependents: Vec<&str> = graph.required_by("P7dR8mSH").iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(dependents, vec!["LNytGWDc"]);
+        assert!(graph.required_by("LNytGWDc").is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+//! Named groups of mods inside an instance
+//!
+//! A group is a label on the manifest entry, which is keyed by mod id rather than
+//! filename, so it survives updates. Turning a group on or off goes through
+//! `ModManager::set_mod_enabled` for each member. A member that an enabled mod
+//! outside the group requires stays enabled when the group is turned off.
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::mods::graph::{self, ModDependencyGraph, ModGraphEdgeKind};
+use crate::mods::{ModError, ModManager};
+
+/// A group and how much of it is turned on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModGroupSummary {
+    pub name: String,
+    pub mod_count: u32,
+    pub enabled_count: u32,
+    /// Share of the group that is enabled, 0 to 1
+    pub enabled_ratio: f32,
+}
+
+/// Outcome of `set_group_enabled`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroupToggleResult {
+    pub group: String,
+    pub enabled: bool,
+    /// Manifest keys of the mods that were switched
+    pub changed: Vec<String>,
+    /// Members left enabled because a mod outside the group requires them
+    pub kept_enabled: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// What planning a toggle needs to know about one installed mod
+#[derive(Debug, Clone)]
+struct Member {
+    /// Manifest key
+    key: String,
+    /// Graph node id
+    id: String,
+    name: String,
+    enabled: bool,
+    in_group: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct TogglePlan {
+    change: Vec<String>,
+    kept: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Trimmed group name, None for an empty one
+pub fn normalize_group(group: Option<String>) -> Option<String> {
+    group.map(|group| group.trim().to_string()).filter(|group| !group.is_empty())
+}
+
+/// Every group in the instance, by name
+pub fn summarize(manager: &ModManager) -> Vec<ModGroupSummary> {
+    let mut groups: Vec<ModGroupSummary> = Vec::new();
+    for installed_mod in manager.get_installed_mods().values() {
+        let Some(name) = &installed_mod.group else { continue };
+        let index = match groups.iter().position(|group| &group.name == name) {
+            Some(index) => index,
+            None => {
+                groups.push(ModGroupSummary { name: name.clone(), mod_count: 0, enabled_count: 0, enabled_ratio: 0.0 });
+                groups.len() - 1
+            }
+        };
+        groups[index].mod_count += 1;
+        if installed_mod.enabled {
+            groups[index].enabled_count += 1;
+        }
+    }
+
+    for group in &mut groups {
+        group.enabled_ratio = group.enabled_count as f32 / group.mod_count as f32;
+    }
+    groups.sort_by_key(|g| g.name.to_lowercase());
+    groups
+}
+
+/// Turn every mod in `group` on or off
+pub async fn set_group_enabled(manager: &mut ModManager, group: &str, enabled: bool) -> Result<GroupToggleResult, ModError> {
+    let members: Vec<Member> = manager.get_installed_mods().iter()
+        .map(|(key, installed_mod)| Member {
+            key: key.clone(),
+            id: installed_mod.mod_info.id.clone(),
+            name: installed_mod.mod_info.name.clone(),
+            enabled: installed_mod.enabled,
+            in_group: installed_mod.group.as_deref() == Some(group),
+        })
+        .collect();
+    if !members.iter().any(|member| member.in_group) {
+        return Err(ModError::NotFound(format!("No mods in group {}", group)));
+    }
+
+    let graph = graph::dependency_graph(manager).await;
+    let plan = plan_toggle(&members, &graph, enabled);
+
+    let mut result = GroupToggleResult {
+        group: group.to_string(),
+        enabled,
+        kept_enabled: plan.kept,
+        warnings: plan.warnings,
+        ..Default::default()
+    };
+    for key in plan.change {
+        match manager.set_mod_enabled(&key, enabled).await {
+            Ok(()) => result.changed.push(key),
+            Err(e) => result.warnings.push(format!("Failed to switch {}: {}", key, e)),
+        }
+    }
+    Ok(result)
+}
+
+fn plan_toggle(members: &[Member], graph: &ModDependencyGraph, enabled: bool) -> TogglePlan {
+    let mut plan = TogglePlan::default();
+    let group: Vec<&Member> = members.iter().filter(|member| member.in_group).collect();
+
+    if enabled {
+        let disabled_outside: HashSet<&str> = members.iter()
+            .filter(|member| !member.in_group && !member.enabled)
+            .map(|member| member.id.as_str())
+            .collect();
+        for member in &group {
+            for edge in graph.edges.iter().filter(|edge| edge.from == member.id && edge.kind == ModGraphEdgeKind::Required) {
+                if disabled_outside.contains(edge.to.as_str()) {
+                    let name = members.iter().find(|m| m.id == edge.to).map_or(edge.to.as_str(), |m| m.name.as_str());
+                    plan.warnings.push(format!("{} requires {}, which is disabled", member.name, name));
+                }
+            }
+        }
+        plan.change = group.iter().filter(|member| !member.enabled).map(|member| member.key.clone()).collect();
+        return plan;
+    }
+
+    // Members stay on while an enabled mod outside the group, or a member kept on, needs them
+    let group_ids: HashSet<&str> = group.iter().map(|member| member.id.as_str()).collect();
+    let mut kept: HashSet<&str> = HashSet::new();
+    loop {
+        let mut changed = false;
+        for member in &group {
+            if !member.enabled || kept.contains(member.id.as_str()) {
+                continue;
+            }
+            let dependents: Vec<&str> = graph.required_by(&member.id).into_iter()
+                .filter(|node| node.enabled && (!group_ids.contains(node.id.as_str()) || kept.contains(node.id.as_str())))
+                .map(|node| node.name.as_str())
+                .collect();
+            if !dependents.is_empty() {
+                kept.insert(member.id.as_str());
+                plan.kept.push(member.key.clone());
+                plan.warnings.push(format!("{} stays enabled, {} requires it", member.name, dependents.join(", ")));
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    plan.change = group.iter()
+        .filter(|member| member.enabled && !kept.contains(member.id.as_str()))
+        .map(|member| member.key.clone())
+        .collect();
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mods::graph::{ModGraphEdge, ModGraphNode};
+
+    fn member(id: &str, enabled: bool, in_group: bool) -> Member {
+        Member { key: id.to_string(), id: id.to_string(), name: id.to_string(), enabled, in_group }
+    }
+
+    fn graph(members: &[Member], required: &[(&str, &str)]) -> ModDependencyGraph {
+        ModDependencyGraph {
+            nodes: members.iter()
+                .map(|member| ModGraphNode {
+                    id: member.id.clone(),
+                    name: member.name.clone(),
+                    version: None,
+                    mod_ids: Vec::new(),
+                    enabled: member.enabled,
+                    installed: true,
+                })
+                .collect(),
+            edges: required.iter()
+                .map(|(from, to)| ModGraphEdge {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    kind: ModGraphEdgeKind::Required,
+                    source: "jar".to_string(),
+                    version: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn disabling_keeps_dependencies_of_mods_outside_the_group() {
+        // create needs flywheel, flywheel needs a library in the group too
+        let members = vec![
+            member("sodium", true, true),
+            member("flywheel", true, true),
+            member("library", true, true),
+            member("create", true, false),
+            member("unused", false, true),
+        ];
+        let graph = graph(&members, &[("create", "flywheel"), ("flywheel", "library"), ("sodium", "library")]);
+
+        let plan = plan_toggle(&members, &graph, false);
+        assert_eq!(plan.change, vec!["sodium".to_string()]);
+        assert_eq!(plan.kept, vec!["flywheel".to_string(), "library".to_string()]);
+        assert_eq!(plan.warnings.len(), 2);
+    }
+
+    #[test]
+    fn enabling_warns_about_disabled_dependencies() {
+        let members = vec![
+            member("iris", false, true),
+            member("sodium", false, false),
+            member("modmenu", true, true),
+        ];
+        let graph = graph(&members, &[("iris", "sodium")]);
+
+        let plan = plan_toggle(&members, &graph, true);
+        assert_eq!(plan.change, vec!["iris".to_string()]);
+        assert_eq!(plan.warnings, vec!["iris requires sodium, which is disabled".to_string()]);
+        assert_eq!(normalize_group(Some("  ".to_string())), None);
+        assert_eq!(normalize_group(Some(" Performance ".to_string())).as_deref(), Some("Performance"));
+    }
+}
@@ -1,8 +1,124 @@
 use crate::mods::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::fs;
 use serde_json;
 use reqwest;
 
+/// How long a loader availability lookup is reused
+const AVAILABILITY_TTL: Duration = Duration::from_secs(3 * 60 * 60);
+
+static AVAILABILITY_CACHE: AvailabilityCache = AvailabilityCache::new();
+
+/// Loader builds published for one Minecraft version, newest first
+#[derive(Debug, Default)]
+struct LoaderBuilds {
+    versions: Vec<String>,
+    recommended: Option<String>,
+}
+
+/// Whether a loader can be installed for a Minecraft version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoaderAvailability {
+    pub loader: String,
+    pub available: bool,
+    pub recommended_version: Option<String>,
+    pub latest_version: Option<String>,
+    /// Newest builds first
+    pub versions: Vec<String>,
+    pub min_java_version: u32,
+    /// Why the builds could not be listed; `available` is false but not certain
+    pub error: Option<String>,
+}
+
+impl LoaderAvailability {
+    fn new(loader: &str, builds: Result<LoaderBuilds, ModError>, min_java_version: u32) -> Self {
+        let (builds, error) = match builds {
+            Ok(builds) => (builds, None),
+            Err(e) => (LoaderBuilds::default(), Some(e.to_string())),
+        };
+        Self {
+            loader: loader.to_string(),
+            available: !builds.versions.is_empty(),
+            recommended_version: builds.recommended.or_else(|| builds.versions.first().cloned()),
+            latest_version: builds.versions.first().cloned(),
+            versions: builds.versions,
+            min_java_version,
+            error,
+        }
+    }
+}
+
+/// When a version's loader availability was fetched, and what it was
+type AvailabilityEntry = (Instant, Vec<LoaderAvailability>);
+
+struct AvailabilityCache(Mutex<Option<HashMap<String, AvailabilityEntry>>>);
+
+impl AvailabilityCache {
+    const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    fn get(&self, mc_version: &str) -> Option<Vec<LoaderAvailability>> {
+        let cache = self.0.lock().unwrap();
+        let (fetched_at, availability) = cache.as_ref()?.get(mc_version)?;
+        (fetched_at.elapsed() < AVAILABILITY_TTL).then(|| availability.clone())
+    }
+
+    fn insert(&self, mc_version: &str, availability: Vec<LoaderAvailability>) {
+        self.0.lock().unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(mc_version.to_string(), (Instant::now(), availability));
+    }
+}
+
+/// GET a JSON document, `None` when the server has nothing for the request
+async fn fetch_json(client: &reqwest::Client, url: &str) -> Result<Option<serde_json::Value>, ModError> {
+    let mirror = crate::net::mirror::current().await;
+    let response = crate::net::mirror::send(&mirror, url, |url| client.get(url).timeout(Duration::from_secs(10))).await?;
+    
+    if matches!(response.status(), reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::BAD_REQUEST) {
+        return Ok(None);
+    }
+    Ok(Some(response.error_for_status()?.json().await?))
+}
+
+/// Loader builds from the Fabric or Quilt meta API, which list newest first
+async fn fetch_meta_loader_builds(url: &str) -> Result<LoaderBuilds, ModError> {
+    let client = crate::http::client();
+    let data = fetch_json(&client, url).await?.unwrap_or_default();
+    let entries = data.as_array().map(Vec::as_slice).unwrap_or_default();
+    
+    let versions: Vec<String> = entries.iter()
+        .filter_map(|v| v.get("loader").and_then(|l| l.get("version")).and_then(|ver| ver.as_str()))
+        .take(10) // Limit to first 10 versions
+        .map(|s| s.to_string())
+        .collect();
+    // Fabric flags stable builds, Quilt marks the others in the version string
+    let recommended = entries.iter()
+        .filter_map(|v| v.get("loader"))
+        .find(|loader| {
+            let version = loader.get("version").and_then(|ver| ver.as_str()).unwrap_or("");
+            loader.get("stable").and_then(|stable| stable.as_bool())
+                .unwrap_or(!version.contains("beta") && !version.contains("pre"))
+        })
+        .and_then(|loader| loader.get("version").and_then(|ver| ver.as_str()))
+        .map(str::to_string);
+    
+    Ok(LoaderBuilds { versions, recommended })
+}
+
+/// Whether a NeoForge build targets `mc_version`, e.g. 20.4.80 for 1.20.4 and 21.0.1 for 1.21
+fn is_neoforge_build_for(build: &str, mc_version: &str) -> bool {
+    let Some(rest) = mc_version.strip_prefix("1.") else {
+        return false;
+    };
+    let (minor, patch) = rest.split_once('.').unwrap_or((rest, "0"));
+    build.starts_with(&format!("{}.{}.", minor, patch))
+}
+
 /// Mod loader installer and manager
 pub struct ModLoaderManager {
     instance_path: std::path::PathBuf,
@@ -330,8 +446,8 @@ impl ModLoaderManager {
     async fn get_forge_versions(&self, mc_version: &str) -> Result<Vec<String>, ModError> {
         // Try to fetch from Forge API
         match self.fetch_forge_versions_from_api(mc_version).await {
-            Ok(versions) => Ok(versions),
-            Err(_) => {
+            Ok(builds) if !builds.versions.is_empty() => Ok(builds.versions),
+            _ => {
                 // Fallback to common versions if API fails
                 Ok(vec![
                     "47.2.20".to_string(), 
@@ -343,51 +459,33 @@ impl ModLoaderManager {
         }
     }
     
-    async fn fetch_forge_versions_from_api(&self, mc_version: &str) -> Result<Vec<String>, ModError> {
-        let client = reqwest::Client::new();
-        let url = format!("https://files.minecraftforge.net/net/minecraftforge/forge/promotions_{}.json", mc_version);
-        
-        let response = client.get(&url)
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
-            .await?;
-            
-        if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
-            if let Some(promos) = data.get("promos") {
-                let mut versions = Vec::new();
-                
-                // Get recommended and latest versions
-                if let Some(recommended) = promos.get(&format!("{}-recommended", mc_version)) {
-                    if let Some(version_str) = recommended.as_str() {
-                        versions.push(version_str.to_string());
-                    }
-                }
-                if let Some(latest) = promos.get(&format!("{}-latest", mc_version)) {
-                    if let Some(version_str) = latest.as_str() {
-                        if !versions.contains(&version_str.to_string()) {
-                            versions.push(version_str.to_string());
-                        }
-                    }
-                }
-                
-                if !versions.is_empty() {
-                    return Ok(versions);
-                }
+    async fn fetch_forge_versions_from_api(&self, mc_version: &str) -> Result<LoaderBuilds, ModError> {
+        let client = crate::http::client();
+        let url = "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+        
+        let data = fetch_json(&client, url).await?.unwrap_or_default();
+        let promo = |kind: &str| data.get("promos")
+            .and_then(|promos| promos.get(format!("{}-{}", mc_version, kind)))
+            .and_then(|version| version.as_str())
+            .map(str::to_string);
+        
+        // Get recommended and latest versions
+        let recommended = promo("recommended");
+        let mut versions: Vec<String> = recommended.iter().cloned().collect();
+        if let Some(latest) = promo("latest") {
+            if !versions.contains(&latest) {
+                versions.push(latest);
             }
         }
         
-        Err(ModError::Io(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No versions found"
-        )))
+        Ok(LoaderBuilds { versions, recommended })
     }
     
     async fn get_fabric_versions(&self, mc_version: &str) -> Result<Vec<String>, ModError> {
         // Try to fetch from Fabric API
         match self.fetch_fabric_versions_from_api(mc_version).await {
-            Ok(versions) => Ok(versions),
-            Err(_) => {
+            Ok(builds) if !builds.versions.is_empty() => Ok(builds.versions),
+            _ => {
                 // Fallback to common versions if API fails
                 Ok(vec![
                     "0.15.3".to_string(),
@@ -399,41 +497,16 @@ impl ModLoaderManager {
         }
     }
     
-    async fn fetch_fabric_versions_from_api(&self, mc_version: &str) -> Result<Vec<String>, ModError> {
-        let client = reqwest::Client::new();
+    async fn fetch_fabric_versions_from_api(&self, mc_version: &str) -> Result<LoaderBuilds, ModError> {
         let url = format!("https://meta.fabricmc.net/v2/versions/loader/{}", mc_version);
-        
-        let response = client.get(&url)
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
-            .await?;
-            
-        if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
-            if let Some(versions_array) = data.as_array() {
-                let versions: Vec<String> = versions_array.iter()
-                    .filter_map(|v| v.get("loader").and_then(|l| l.get("version")).and_then(|ver| ver.as_str()))
-                    .take(10) // Limit to first 10 versions
-                    .map(|s| s.to_string())
-                    .collect();
-                
-                if !versions.is_empty() {
-                    return Ok(versions);
-                }
-            }
-        }
-        
-        Err(ModError::Io(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No versions found"
-        )))
+        fetch_meta_loader_builds(&url).await
     }
     
     async fn get_quilt_versions(&self, mc_version: &str) -> Result<Vec<String>, ModError> {
         // Try to fetch from Quilt API
         match self.fetch_quilt_versions_from_api(mc_version).await {
-            Ok(versions) => Ok(versions),
-            Err(_) => {
+            Ok(builds) if !builds.versions.is_empty() => Ok(builds.versions),
+            _ => {
                 // Fallback to common versions if API fails
                 Ok(vec![
                     "0.21.1".to_string(), 
@@ -445,41 +518,16 @@ impl ModLoaderManager {
         }
     }
     
-    async fn fetch_quilt_versions_from_api(&self, mc_version: &str) -> Result<Vec<String>, ModError> {
-        let client = reqwest::Client::new();
+    async fn fetch_quilt_versions_from_api(&self, mc_version: &str) -> Result<LoaderBuilds, ModError> {
         let url = format!("https://meta.quiltmc.org/v3/versions/loader/{}", mc_version);
-        
-        let response = client.get(&url)
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
-            .await?;
-            
-        if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
-            if let Some(versions_array) = data.as_array() {
-                let versions: Vec<String> = versions_array.iter()
-                    .filter_map(|v| v.get("loader").and_then(|l| l.get("version")).and_then(|ver| ver.as_str()))
-                    .take(10) // Limit to first 10 versions
-                    .map(|s| s.to_string())
-                    .collect();
-                
-                if !versions.is_empty() {
-                    return Ok(versions);
-                }
-            }
-        }
-        
-        Err(ModError::Io(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No versions found"
-        )))
+        fetch_meta_loader_builds(&url).await
     }
     
     async fn get_neoforge_versions(&self, mc_version: &str) -> Result<Vec<String>, ModError> {
         // Try to fetch from NeoForge API
         match self.fetch_neoforge_versions_from_api(mc_version).await {
-            Ok(versions) => Ok(versions),
-            Err(_) => {
+            Ok(builds) if !builds.versions.is_empty() => Ok(builds.versions),
+            _ => {
                 // Fallback to common versions if API fails
                 Ok(vec![
                     "20.4.195".to_string(),
@@ -491,34 +539,79 @@ impl ModLoaderManager {
         }
     }
     
-    async fn fetch_neoforge_versions_from_api(&self, mc_version: &str) -> Result<Vec<String>, ModError> {
-        let client = reqwest::Client::new();
-        let url = format!("https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge");
+    async fn fetch_neoforge_versions_from_api(&self, mc_version: &str) -> Result<LoaderBuilds, ModError> {
+        let client = crate::http::client();
+        let url = "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge";
+        
+        let data = fetch_json(&client, url).await?.unwrap_or_default();
+        // Maven lists oldest first, the picker wants the newest builds
+        let mut versions: Vec<String> = data.get("versions")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .filter(|v| is_neoforge_build_for(v, mc_version))
+            .map(|s| s.to_string())
+            .collect();
+        versions.reverse();
+        versions.truncate(10);
+        
+        let recommended = versions.iter().find(|v| !v.contains("beta")).cloned();
+        Ok(LoaderBuilds { versions, recommended })
+    }
+
+    /// Which loaders have builds for a Minecraft version, cached for a few hours
+    ///
+    /// Loaders whose version list could not be fetched are reported with an
+    /// error and left out of the cache so the next call tries again.
+    pub async fn get_loader_availability(&self, mc_version: &str) -> Vec<LoaderAvailability> {
+        if let Some(cached) = AVAILABILITY_CACHE.get(mc_version) {
+            return cached;
+        }
         
-        let response = client.get(&url)
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
-            .await?;
-            
-        if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
-            if let Some(versions_array) = data.get("versions").and_then(|v| v.as_array()) {
-                let versions: Vec<String> = versions_array.iter()
-                    .filter_map(|v| v.as_str())
-                    .filter(|v| v.starts_with(&format!("{}.", mc_version.replace("1.", ""))))
-                    .take(10) // Limit to first 10 versions
-                    .map(|s| s.to_string())
-                    .collect();
-                
-                if !versions.is_empty() {
-                    return Ok(versions);
-                }
-            }
+        let min_java = crate::minecraft::versions::get_required_java_version(mc_version);
+        let (forge, fabric, quilt, neoforge) = tokio::join!(
+            self.fetch_forge_versions_from_api(mc_version),
+            self.fetch_fabric_versions_from_api(mc_version),
+            self.fetch_quilt_versions_from_api(mc_version),
+            self.fetch_neoforge_versions_from_api(mc_version),
+        );
+        
+        let availability: Vec<LoaderAvailability> = [("forge", forge), ("fabric", fabric), ("quilt", quilt), ("neoforge", neoforge)]
+            .into_iter()
+            .map(|(loader, builds)| LoaderAvailability::new(loader, builds, min_java))
+            .collect();
+        
+        if availability.iter().all(|loader| loader.error.is_none()) {
+            AVAILABILITY_CACHE.insert(mc_version, availability.clone());
+        }
+        availability
+    }
+
+    /// Fail with a readable error when `loader_name` has no builds for `mc_version`
+    ///
+    /// Loaders whose availability is unknown, e.g. offline, are let through.
+    pub async fn check_loader_available(&self, loader_name: &str, mc_version: &str) -> Result<(), ModError> {
+        let availability = self.get_loader_availability(mc_version).await;
+        let Some(requested) = availability.iter().find(|loader| loader.loader.eq_ignore_ascii_case(loader_name)) else {
+            return Err(ModError::LoaderNotSupported(loader_name.to_string()));
+        };
+        if requested.available || requested.error.is_some() {
+            return Ok(());
         }
         
-        Err(ModError::Io(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No versions found"
+        let alternatives: Vec<&str> = availability.iter()
+            .filter(|loader| loader.available)
+            .map(|loader| loader.loader.as_str())
+            .collect();
+        let hint = if alternatives.is_empty() {
+            "No mod loader supports this version".to_string()
+        } else {
+            format!("Available loaders: {}", alternatives.join(", "))
+        };
+        Err(ModError::VersionIncompatible(format!(
+            "{} has no builds for Minecraft {}. {}",
+            requested.loader, mc_version, hint
         )))
     }
 
@@ -580,7 +673,7 @@ impl ModLoaderManager {
 
     /// Download and install Fabric API for better mod compatibility
     async fn install_fabric_api(&self, mc_version: &str) -> Result<(), ModError> {
-        let client = reqwest::Client::new();
+        let client = crate::http::client();
         
         // Search for Fabric API on Modrinth
         let search_url = format!(
@@ -627,19 +720,16 @@ impl ModLoaderManager {
                                             // Download Fabric API
                                             println!("📥 Downloading Fabric API: {}", filename);
                                             
-                                            let fabric_api_response = client
-                                                .get(download_url)
-                                                .send()
-                                                .await?;
-                                                
-                                            if fabric_api_response.status().is_success() {
-                                                let content = fabric_api_response.bytes().await?;
-                                                let mods_dir = self.instance_path.join("mods");
-                                                let fabric_api_path = mods_dir.join(filename);
-                                                
-                                                fs::write(&fabric_api_path, content).await?;
-                                                println!("✅ Fabric API installed: {}", filename);
-                                            }
+                                            let fabric_api_path = self.instance_path.join("mods").join(filename);
+                                            let options = crate::net::DownloadOptions {
+                                                client: Some(client.clone()),
+                                                sha1: primary_file["hashes"]["sha1"].as_str().map(String::from),
+                                                ..Default::default()
+                                            };
+                                            crate::net::download(download_url, &fabric_api_path, options).await
+                                                .map_err(|e| ModError::DownloadFailed(format!("{}: {}", filename, e)))?;
+                                            crate::provenance::record_download(&self.instance_path, &fabric_api_path, download_url, primary_file["hashes"]["sha1"].as_str()).await;
+                                            println!("✅ Fabric API installed: {}", filename);
                                         }
                                     }
                                 }
@@ -652,4 +742,50 @@ impl ModLoaderManager {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neoforge_builds_match_their_minecraft_version() {
+        assert!(is_neoforge_build_for("20.4.80-beta", "1.20.4"));
+        assert!(is_neoforge_build_for("21.0.1", "1.21"));
+        assert!(!is_neoforge_build_for("20.4.80", "1.20"));
+        assert!(!is_neoforge_build_for("20.1.5", "1.20.10"));
+        assert!(!is_neoforge_build_for("16.5.1", "23w31a"));
+    }
+
+    #[test]
+    fn availability_reports_missing_and_unknown_builds() {
+        let builds = LoaderBuilds {
+            versions: vec!["0.16.0".to_string(), "0.15.11".to_string()],
+            recommended: Some("0.15.11".to_string()),
+        };
+        let fabric = LoaderAvailability::new("fabric", Ok(builds), 17);
+        assert!(fabric.available);
+        assert_eq!(fabric.latest_version.as_deref(), Some("0.16.0"));
+        assert_eq!(fabric.recommended_version.as_deref(), Some("0.15.11"));
+        assert_eq!(fabric.min_java_version, 17);
+
+        let neoforge = LoaderAvailability::new("neoforge", Ok(LoaderBuilds::default()), 8);
+        assert!(!neoforge.available && neoforge.error.is_none());
+
+        let offline = LoaderAvailability::new("forge", Err(ModError::NotFound("offline".to_string())), 8);
+        assert!(!offline.available && offline.error.is_some());
+    }
+
+    #[test]
+    fn cached_availability_expires() {
+        let cache = AvailabilityCache::new();
+        let neoforge = LoaderAvailability::new("neoforge", Ok(LoaderBuilds::default()), 8);
+        cache.insert("1.16.5", vec![neoforge.clone()]);
+        assert_eq!(cache.get("1.16.5").map(|cached| cached.len()), Some(1));
+        assert!(cache.get("1.20.1").is_none());
+
+        cache.0.lock().unwrap().as_mut().unwrap()
+            .insert("1.16.5".to_string(), (Instant::now() - AVAILABILITY_TTL, vec![neoforge]));
+        assert!(cache.get("1.16.5").is_none());
+    }
+}
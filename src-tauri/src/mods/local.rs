@@ -0,0 +1,544 @@
+//! Metadata for mod jars added from disk
+//!
+//! Reads the loader metadata file inside a jar so a hand-picked file can be
+//! recorded like a downloaded one, and so the dependencies a mod declares can
+//! be checked against the rest of the instance.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use crate::mods::types::{DependencyType, ModError};
+
+/// What a mod jar says about itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalModMetadata {
+    pub mod_id: String,
+    pub name: String,
+    pub version: String,
+    pub loader: String,
+    pub description: String,
+    pub authors: Vec<String>,
+}
+
+/// Mod ids a jar answers to and the mods it declares dependencies on
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JarDependencies {
+    /// The mod's own ids, the ids it provides and those of mods nested inside it
+    pub mod_ids: Vec<String>,
+    /// Ids from the jar's own metadata, without provides or nested mods
+    pub own_ids: Vec<String>,
+    /// Ids the mod declares it provides in place of another mod
+    pub provides: Vec<String>,
+    /// Mixin config files the jar registers, such as `sodium.mixins.json`
+    pub mixin_configs: Vec<String>,
+    pub dependencies: Vec<DeclaredDependency>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclaredDependency {
+    pub mod_id: String,
+    /// Required, Optional or Incompatible
+    pub dependency_type: DependencyType,
+    /// Version requirement as written in the metadata
+    pub version: Option<String>,
+}
+
+/// fabric.mod.json, authors are either names or `{ "name": ... }` objects
+#[derive(Debug, Deserialize)]
+struct FabricModJson {
+    id: String,
+    version: String,
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    authors: Vec<serde_json::Value>,
+}
+
+/// Dependency fields of fabric.mod.json, versions are a string or a list of them
+#[derive(Debug, Deserialize)]
+struct FabricDependencyJson {
+    id: String,
+    #[serde(default)]
+    provides: Vec<String>,
+    #[serde(default)]
+    depends: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    recommends: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    suggests: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    breaks: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    conflicts: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    jars: Vec<FabricNestedJar>,
+    /// Config names or `{ "config": ... }` objects
+    #[serde(default)]
+    mixins: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FabricNestedJar {
+    file: String,
+}
+
+/// quilt.mod.json keeps the same fields under `quilt_loader`
+#[derive(Debug, Deserialize)]
+struct QuiltModJson {
+    quilt_loader: QuiltLoader,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuiltLoader {
+    id: String,
+    version: String,
+    #[serde(default)]
+    metadata: QuiltMetadata,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct QuiltMetadata {
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    contributors: HashMap<String, String>,
+}
+
+/// Dependency fields of quilt.mod.json, entries are an id or an object with one
+#[derive(Debug, Deserialize)]
+struct QuiltDependencyJson {
+    quilt_loader: QuiltDependencyLoader,
+    /// One config name or a list of them
+    #[serde(default)]
+    mixin: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuiltDependencyLoader {
+    id: String,
+    #[serde(default)]
+    provides: Vec<serde_json::Value>,
+    #[serde(default)]
+    depends: Vec<serde_json::Value>,
+    #[serde(default)]
+    breaks: Vec<serde_json::Value>,
+    #[serde(default)]
+    jars: Vec<String>,
+}
+
+/// META-INF/mods.toml and META-INF/neoforge.mods.toml
+#[derive(Debug, Deserialize)]
+struct ModsToml {
+    #[serde(default)]
+    mods: Vec<ModsTomlEntry>,
+    /// Keyed by the id of the mod that has the dependencies
+    #[serde(default)]
+    dependencies: HashMap<String, Vec<ModsTomlDependency>>,
+    /// NeoForge, Forge lists them in the manifest's `MixinConfigs`
+    #[serde(default)]
+    mixins: Vec<ModsTomlMixin>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModsTomlMixin {
+    config: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModsTomlDependency {
+    #[serde(rename = "modId")]
+    mod_id: String,
+    /// Forge
+    mandatory: Option<bool>,
+    /// NeoForge: required, optional, incompatible or discouraged
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(rename = "versionRange")]
+    version_range: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModsTomlEntry {
+    #[serde(rename = "modId")]
+    mod_id: String,
+    version: Option<String>,
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+    description: Option<String>,
+    authors: Option<String>,
+}
+
+/// Read the loader metadata from a mod jar
+///
+/// Tries Fabric, Quilt, NeoForge and Forge metadata in that order.
+pub fn read_jar_metadata(jar_path: &Path) -> Result<LocalModMetadata, ModError> {
+    let file = File::open(jar_path)?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| ModError::InvalidFile(format!("{} is not a valid jar: {}", jar_path.display(), e)))?;
+
+    if let Some(contents) = read_entry(&mut archive, "fabric.mod.json") {
+        return parse_fabric(&contents);
+    }
+    if let Some(contents) = read_entry(&mut archive, "quilt.mod.json") {
+        return parse_quilt(&contents);
+    }
+
+    // Forge fills ${file.jarVersion} from the manifest at load time
+    let jar_version = read_entry(&mut archive, "META-INF/MANIFEST.MF")
+        .and_then(|manifest| manifest_value(&manifest, "Implementation-Version"));
+    if let Some(contents) = read_entry(&mut archive, "META-INF/neoforge.mods.toml") {
+        return parse_mods_toml(&contents, "neoforge", jar_version.as_deref());
+    }
+    if let Some(contents) = read_entry(&mut archive, "META-INF/mods.toml") {
+        return parse_mods_toml(&contents, "forge", jar_version.as_deref());
+    }
+
+    Err(ModError::InvalidFile(format!(
+        "{} has no fabric.mod.json, quilt.mod.json or mods.toml",
+        jar_path.display()
+    )))
+}
+
+/// Read the ids and declared dependencies from a mod jar
+pub fn read_jar_dependencies(jar_path: &Path) -> Result<JarDependencies, ModError> {
+    let file = File::open(jar_path)?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| ModError::InvalidFile(format!("{} is not a valid jar: {}", jar_path.display(), e)))?;
+
+    if let Some(contents) = read_entry(&mut archive, "fabric.mod.json") {
+        let mut dependencies = parse_fabric_dependencies(&contents)?;
+        let nested: Vec<String> = serde_json::from_str::<FabricDependencyJson>(&contents)
+            .map(|fabric| fabric.jars.into_iter().map(|jar| jar.file).collect())
+            .unwrap_or_default();
+        dependencies.mod_ids.extend(nested_mod_ids(&mut archive, &nested));
+        return Ok(dependencies);
+    }
+    if let Some(contents) = read_entry(&mut archive, "quilt.mod.json") {
+        let mut dependencies = parse_quilt_dependencies(&contents)?;
+        let nested = serde_json::from_str::<QuiltDependencyJson>(&contents)
+            .map(|quilt| quilt.quilt_loader.jars)
+            .unwrap_or_default();
+        dependencies.mod_ids.extend(nested_mod_ids(&mut archive, &nested));
+        return Ok(dependencies);
+    }
+    for name in ["META-INF/neoforge.mods.toml", "META-INF/mods.toml"] {
+        if let Some(contents) = read_entry(&mut archive, name) {
+            let mut dependencies = parse_mods_toml_dependencies(&contents)?;
+            let manifest_mixins = read_entry(&mut archive, "META-INF/MANIFEST.MF")
+                .and_then(|manifest| manifest_value(&manifest, "MixinConfigs"));
+            for config in manifest_mixins.iter().flat_map(|configs| configs.split(',')) {
+                let config = config.trim().to_string();
+                if !config.is_empty() && !dependencies.mixin_configs.contains(&config) {
+                    dependencies.mixin_configs.push(config);
+                }
+            }
+            return Ok(dependencies);
+        }
+    }
+
+    Err(ModError::InvalidFile(format!(
+        "{} has no fabric.mod.json, quilt.mod.json or mods.toml",
+        jar_path.display()
+    )))
+}
+
+/// Ids of the Fabric or Quilt mods bundled as jar-in-jar, such as Fabric API's modules
+fn nested_mod_ids<R: Read + Seek>(archive: &mut ZipArchive<R>, paths: &[String]) -> Vec<String> {
+    let mut ids = Vec::new();
+    for path in paths {
+        let mut bytes = Vec::new();
+        let read = archive.by_name(path).ok().and_then(|mut entry| entry.read_to_end(&mut bytes).ok());
+        let Some(mut nested) = read.and_then(|_| ZipArchive::new(Cursor::new(bytes)).ok()) else {
+            continue;
+        };
+        if let Some(contents) = read_entry(&mut nested, "fabric.mod.json") {
+            if let Ok(fabric) = serde_json::from_str::<FabricDependencyJson>(&contents) {
+                ids.push(fabric.id);
+                ids.extend(fabric.provides);
+            }
+        } else if let Some(contents) = read_entry(&mut nested, "quilt.mod.json") {
+            if let Ok(quilt) = serde_json::from_str::<QuiltDependencyJson>(&contents) {
+                ids.push(quilt.quilt_loader.id);
+            }
+        }
+    }
+    ids
+}
+
+fn parse_fabric_dependencies(contents: &str) -> Result<JarDependencies, ModError> {
+    let fabric: FabricDependencyJson = serde_json::from_str(contents)
+        .map_err(|e| ModError::InvalidFile(format!("Invalid fabric.mod.json: {}", e)))?;
+
+    let mut dependencies = Vec::new();
+    for (entries, dependency_type) in [
+        (fabric.depends, DependencyType::Required),
+        (fabric.recommends, DependencyType::Optional),
+        (fabric.suggests, DependencyType::Optional),
+        (fabric.breaks, DependencyType::Incompatible),
+        (fabric.conflicts, DependencyType::Incompatible),
+    ] {
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        dependencies.extend(entries.into_iter().map(|(mod_id, version)| DeclaredDependency {
+            mod_id,
+            dependency_type: dependency_type.clone(),
+            version: version_requirement(&version),
+        }));
+    }
+
+    let own_ids = vec![fabric.id];
+    let mod_ids = own_ids.iter().chain(&fabric.provides).cloned().collect();
+    let mixin_configs = fabric.mixins.iter().flat_map(mixin_config_names).collect();
+    Ok(JarDependencies { mod_ids, own_ids, provides: fabric.provides, mixin_configs, dependencies })
+}
+
+fn parse_quilt_dependencies(contents: &str) -> Result<JarDependencies, ModError> {
+    let quilt: QuiltDependencyJson = serde_json::from_str(contents)
+        .map_err(|e| ModError::InvalidFile(format!("Invalid quilt.mod.json: {}", e)))?;
+    let loader = quilt.quilt_loader;
+    let mixin_configs = mixin_config_names(&quilt.mixin);
+
+    let entry = |value: &serde_json::Value, default_type: DependencyType| {
+        let (mod_id, version, optional) = match value {
+            serde_json::Value::String(id) => (id.clone(), None, false),
+            object => (
+                object["id"].as_str()?.to_string(),
+                version_requirement(&object["versions"]),
+                object["optional"].as_bool().unwrap_or(false),
+            ),
+        };
+        let dependency_type = match default_type {
+            DependencyType::Required if optional => DependencyType::Optional,
+            other => other,
+        };
+        Some(DeclaredDependency { mod_id, dependency_type, version })
+    };
+
+    let mut dependencies: Vec<DeclaredDependency> = loader.depends.iter()
+        .filter_map(|value| entry(value, DependencyType::Required))
+        .collect();
+    dependencies.extend(loader.breaks.iter().filter_map(|value| entry(value, DependencyType::Incompatible)));
+
+    let own_ids = vec![loader.id];
+    let provides: Vec<String> = loader.provides.iter()
+        .filter_map(|value| value.as_str().or_else(|| value["id"].as_str()).map(String::from))
+        .collect();
+    let mod_ids = own_ids.iter().chain(&provides).cloned().collect();
+    Ok(JarDependencies { mod_ids, own_ids, provides, mixin_configs, dependencies })
+}
+
+fn parse_mods_toml_dependencies(contents: &str) -> Result<JarDependencies, ModError> {
+    let toml: ModsToml = toml::from_str(contents)
+        .map_err(|e| ModError::InvalidFile(format!("Invalid mods.toml: {}", e)))?;
+    let mod_ids: Vec<String> = toml.mods.into_iter().map(|entry| entry.mod_id).collect();
+
+    let mut dependencies = Vec::new();
+    for mod_id in &mod_ids {
+        for dependency in toml.dependencies.get(mod_id).into_iter().flatten() {
+            let dependency_type = match (dependency.kind.as_deref().map(str::to_ascii_lowercase).as_deref(), dependency.mandatory) {
+                (Some("required"), _) => DependencyType::Required,
+                (Some("incompatible" | "discouraged"), _) => DependencyType::Incompatible,
+                (Some(_), _) | (None, Some(false)) => DependencyType::Optional,
+                (None, _) => DependencyType::Required,
+            };
+            dependencies.push(DeclaredDependency {
+                mod_id: dependency.mod_id.clone(),
+                dependency_type,
+                version: dependency.version_range.clone().filter(|range| !range.is_empty()),
+            });
+        }
+    }
+    Ok(JarDependencies {
+        own_ids: mod_ids.clone(),
+        mod_ids,
+        provides: Vec::new(),
+        mixin_configs: toml.mixins.into_iter().map(|mixin| mixin.config).collect(),
+        dependencies,
+    })
+}
+
+/// Mixin config names from a string, an object with `config`, or a list of either
+fn mixin_config_names(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::String(config) => vec![config.clone()],
+        serde_json::Value::Array(values) => values.iter().flat_map(mixin_config_names).collect(),
+        object => object["config"].as_str().map(String::from).into_iter().collect(),
+    }
+}
+
+/// `">=0.15"` or `[">=1.0", "<2"]` as one string, None for "any version"
+fn version_requirement(value: &serde_json::Value) -> Option<String> {
+    let requirement = match value {
+        serde_json::Value::String(version) => version.clone(),
+        serde_json::Value::Array(versions) => versions.iter()
+            .filter_map(|version| version.as_str())
+            .collect::<Vec<_>>()
+            .join(" || "),
+        _ => return None,
+    };
+    Some(requirement).filter(|requirement| !requirement.is_empty() && requirement != "*")
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents).ok()?;
+    Some(contents)
+}
+
+fn parse_fabric(contents: &str) -> Result<LocalModMetadata, ModError> {
+    let fabric: FabricModJson = serde_json::from_str(contents)
+        .map_err(|e| ModError::InvalidFile(format!("Invalid fabric.mod.json: {}", e)))?;
+
+    Ok(LocalModMetadata {
+        name: fabric.name.unwrap_or_else(|| fabric.id.clone()),
+        mod_id: fabric.id,
+        version: fabric.version,
+        loader: "fabric".to_string(),
+        description: fabric.description.unwrap_or_default(),
+        authors: fabric.authors.iter()
+            .filter_map(|author| author.as_str().or_else(|| author["name"].as_str()))
+            .map(String::from)
+            .collect(),
+    })
+}
+
+fn parse_quilt(contents: &str) -> Result<LocalModMetadata, ModError> {
+    let quilt: QuiltModJson = serde_json::from_str(contents)
+        .map_err(|e| ModError::InvalidFile(format!("Invalid quilt.mod.json: {}", e)))?;
+    let loader = quilt.quilt_loader;
+
+    let mut authors: Vec<String> = loader.metadata.contributors.into_keys().collect();
+    authors.sort();
+    Ok(LocalModMetadata {
+        name: loader.metadata.name.unwrap_or_else(|| loader.id.clone()),
+        mod_id: loader.id,
+        version: loader.version,
+        loader: "quilt".to_string(),
+        description: loader.metadata.description.unwrap_or_default(),
+        authors,
+    })
+}
+
+fn parse_mods_toml(contents: &str, loader: &str, jar_version: Option<&str>) -> Result<LocalModMetadata, ModError> {
+    let toml: ModsToml = toml::from_str(contents)
+        .map_err(|e| ModError::InvalidFile(format!("Invalid mods.toml: {}", e)))?;
+    let entry = toml.mods.into_iter().next()
+        .ok_or_else(|| ModError::InvalidFile("mods.toml lists no mods".to_string()))?;
+
+    let version = match entry.version.as_deref() {
+        Some("${file.jarVersion}") | None => jar_version.unwrap_or("unknown").to_string(),
+        Some(version) => version.to_string(),
+    };
+
+    Ok(LocalModMetadata {
+        name: entry.display_name.unwrap_or_else(|| entry.mod_id.clone()),
+        mod_id: entry.mod_id,
+        version,
+        loader: loader.to_string(),
+        description: entry.description.unwrap_or_default().trim().to_string(),
+        authors: entry.authors.map(|a| vec![a]).unwrap_or_default(),
+    })
+}
+
+fn manifest_value(manifest: &str, key: &str) -> Option<String> {
+    manifest.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fabric_with_object_authors() {
+        let json = r#"{"schemaVersion":1,"id":"sodium","version":"0.5.3","name":"Sodium",
+            "authors":["jellysquid3",{"name":"IMS"}]}"#;
+        let metadata = parse_fabric(json).unwrap();
+        assert_eq!(metadata.mod_id, "sodium");
+        assert_eq!(metadata.name, "Sodium");
+        assert_eq!(metadata.authors, vec!["jellysquid3", "IMS"]);
+    }
+
+    #[test]
+    fn parses_quilt_loader_block() {
+        let json = r#"{"schema_version":1,"quilt_loader":{"id":"qsl","version":"6.1.2",
+            "metadata":{"name":"Quilt Standard Libraries","contributors":{"Quilt":"Owner"}}}}"#;
+        let metadata = parse_quilt(json).unwrap();
+        assert_eq!(metadata.mod_id, "qsl");
+        assert_eq!(metadata.loader, "quilt");
+        assert_eq!(metadata.authors, vec!["Quilt"]);
+    }
+
+    #[test]
+    fn fabric_and_forge_dependencies_are_classified() {
+        let json = r#"{"schemaVersion":1,"id":"create","version":"0.5.1","provides":["create_fabric"],
+            "depends":{"fabricloader":">=0.14","fabric-api":["*"]},"recommends":{"jei":"*"},
+            "breaks":{"optifabric":"<1.13"},"mixins":["create.mixins.json",{"config":"create.client.mixins.json","environment":"client"}]}"#;
+        let fabric = parse_fabric_dependencies(json).unwrap();
+        assert_eq!(fabric.mod_ids, vec!["create", "create_fabric"]);
+        assert_eq!(fabric.own_ids, vec!["create"]);
+        assert_eq!(fabric.provides, vec!["create_fabric"]);
+        assert_eq!(fabric.mixin_configs, vec!["create.mixins.json", "create.client.mixins.json"]);
+        assert_eq!(fabric.dependencies, vec![
+            DeclaredDependency { mod_id: "fabric-api".to_string(), dependency_type: DependencyType::Required, version: None },
+            DeclaredDependency { mod_id: "fabricloader".to_string(), dependency_type: DependencyType::Required, version: Some(">=0.14".to_string()) },
+            DeclaredDependency { mod_id: "jei".to_string(), dependency_type: DependencyType::Optional, version: None },
+            DeclaredDependency { mod_id: "optifabric".to_string(), dependency_type: DependencyType::Incompatible, version: Some("<1.13".to_string()) },
+        ]);
+
+        let toml = r#"
+[[mods]]
+modId="farmersdelight"
+[[dependencies.farmersdelight]]
+modId="forge"
+mandatory=true
+versionRange="[47,)"
+[[dependencies.farmersdelight]]
+modId="jei"
+mandatory=false
+[[dependencies.farmersdelight]]
+modId="oldmod"
+type="incompatible"
+[[mixins]]
+config="farmersdelight.mixins.json"
+"#;
+        let forge = parse_mods_toml_dependencies(toml).unwrap();
+        assert_eq!(forge.mod_ids, vec!["farmersdelight"]);
+        assert_eq!(forge.mixin_configs, vec!["farmersdelight.mixins.json"]);
+        let types: Vec<(&str, DependencyType)> = forge.dependencies.iter()
+            .map(|dependency| (dependency.mod_id.as_str(), dependency.dependency_type.clone()))
+            .collect();
+        assert_eq!(types, vec![
+            ("forge", DependencyType::Required),
+            ("jei", DependencyType::Optional),
+            ("oldmod", DependencyType::Incompatible),
+        ]);
+    }
+
+    #[test]
+    fn mods_toml_uses_manifest_version_placeholder() {
+        let toml = r#"
+modLoader="javafml"
+loaderVersion="[47,)"
+[[mods]]
+modId="jei"
+version="${file.jarVersion}"
+displayName="Just Enough Items"
+"#;
+        let metadata = parse_mods_toml(toml, "forge", Some("15.2.0.27")).unwrap();
+        assert_eq!(metadata.mod_id, "jei");
+        assert_eq!(metadata.version, "15.2.0.27");
+        assert_eq!(metadata.name, "Just Enough Items");
+        assert_eq!(
+            manifest_value("Manifest-Version: 1.0\r\nImplementation-Version: 15.2.0.27\r\n", "Implementation-Version"),
+            Some("15.2.0.27".to_string())
+        );
+    }
+}
@@ -1,12 +1,28 @@
 use crate::mods::types::*;
-use crate::mods::api::{ModApi, ApiClient, ApiClientFactory};
+use crate::mods::api::{ModApi, ApiClient, ApiClientFactory, ModrinthApi};
+use crate::mods::api::common::ApiUtils;
+use crate::mods::conflicts::{self, PendingUpdate};
+use crate::mods::local::{self, LocalModMetadata};
 use crate::mods::loaders::ModLoaderManager;
-use std::path::PathBuf;
-use std::collections::HashMap;
+use crate::mods::trash;
+use crate::net::manager::{DownloadKind, DownloadPriority};
+use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
 use tokio::fs;
 use anyhow::{Result, Context};
 use serde_json;
 
+/// Dependency chains deeper than this are not followed when installing a mod
+const MAX_DEPENDENCY_DEPTH: usize = 8;
+
+/// Dependencies to install for a mod and the ones only reported
+#[derive(Default)]
+struct DependencyPlan {
+    install: Vec<(ModInfo, ModFile, ResolvedDependency)>,
+    optional: Vec<ResolvedDependency>,
+    warnings: Vec<String>,
+}
+
 /// Main mod management system for an instance
 pub struct ModManager {
     instance_path: PathBuf,
@@ -43,28 +59,34 @@ impl ModManager {
         Ok(manager)
     }
     
-    /// Install a mod by ID from any available API
-    pub async fn install_mod<F>(&mut self, mod_id: &str, version_id: Option<&str>, progress_callback: F) -> Result<InstalledMod, ModError>
+    /// Install a mod by ID from any available API, along with its required dependencies
+    ///
+    /// Optional dependencies are listed in the summary and only installed with
+    /// `include_optional`. `on_dependency` is called with the position and total
+    /// before each dependency is downloaded.
+    pub async fn install_mod<F, D>(
+        &mut self,
+        mod_id: &str,
+        version_id: Option<&str>,
+        include_optional: bool,
+        progress_callback: F,
+        on_dependency: D,
+    ) -> Result<ModInstallSummary, ModError>
     where
         F: Fn(u64, u64) + Send + Sync + Clone + 'static,
+        D: Fn(usize, usize, &ResolvedDependency) + Send + Sync,
     {
         // Try to find the mod across all API clients
-        let mut mod_info = None;
-        let mut selected_client = None;
-        
-        for client in &self.api_clients {
-            match client.get_mod_details(mod_id).await {
-                Ok(info) => {
-                    mod_info = Some(info);
-                    selected_client = Some(client);
-                    break;
-                }
-                Err(_) => continue,
+        let mut found = None;
+        for (index, client) in self.api_clients.iter().enumerate() {
+            if let Ok(info) = client.get_mod_details(mod_id).await {
+                found = Some((index, info));
+                break;
             }
         }
-        
-        let mod_info = mod_info.ok_or_else(|| ModError::NotFound(mod_id.to_string()))?;
-        let client = selected_client.unwrap();
+
+        let (client_index, mod_info) = found.ok_or_else(|| ModError::NotFound(mod_id.to_string()))?;
+        let client = &self.api_clients[client_index];
         
         // Get available files
         let files = client.get_mod_files(mod_id).await?;
@@ -81,48 +103,270 @@ impl ModManager {
                 .ok_or_else(|| ModError::NotFound(format!("No release files found for mod {}", mod_id)))?
         };
         
-        // Check dependencies
-        self.check_dependencies(&selected_file).await?;
-        
-        // Download the mod
-        let file_path = self.mods_path.join(&selected_file.filename);
-        let progress_box: Box<dyn Fn(u64, u64) + Send + Sync> = Box::new(progress_callback);
-        client.download_mod_file(&selected_file, &file_path, progress_box).await?;
+        // Resolve everything before downloading so a missing dependency installs nothing
+        let plan = self.resolve_dependencies(client, &mod_info, &selected_file, include_optional).await?;
+
+        let total = plan.install.len();
+        let mut dependencies_added = Vec::with_capacity(total);
+        for (index, (info, file, dependency)) in plan.install.into_iter().enumerate() {
+            on_dependency(index + 1, total, &dependency);
+            let key = info.id.clone();
+            self.download_and_record(client_index, &key, info, file, |_, _| {}).await?;
+            dependencies_added.push(dependency);
+        }
+
+        let installed_mod = self.download_and_record(client_index, mod_id, mod_info, selected_file, progress_callback).await?;
+
+        Ok(ModInstallSummary {
+            installed_mod,
+            dependencies_added,
+            optional_dependencies: plan.optional,
+            warnings: plan.warnings,
+            mod_conflicts: conflicts::instance_conflicts(self).await,
+        })
+    }
+
+    /// Download a file into the mods folder and record it in the manifest under `key`
+    async fn download_and_record<F>(
+        &mut self,
+        client_index: usize,
+        key: &str,
+        mod_info: ModInfo,
+        file: ModFile,
+        progress_callback: F,
+    ) -> Result<InstalledMod, ModError>
+    where
+        F: Fn(u64, u64) + Send + Sync + 'static,
+    {
+        let file_path = self.mods_path.join(&file.filename);
+        let trashed = match file.hashes.get("sha1") {
+            Some(sha1) if !file_path.exists() => trash::find_by_sha1(&self.mods_path, sha1).await,
+            _ => None,
+        };
+        match trashed {
+            Some((jar, _)) => {
+                // Same file as one uninstalled earlier, no need to download it again
+                println!("♻️  Restoring {} from the mod trash instead of downloading it", file.filename);
+                fs::rename(&jar, &file_path).await
+                    .context("Failed to restore mod from trash")?;
+                trash::remove_record(&jar).await;
+                progress_callback(file.size, file.size);
+            }
+            None => {
+                let progress_box: Box<dyn Fn(u64, u64) + Send + Sync> = Box::new(progress_callback);
+                self.api_clients[client_index].download_mod_file(&file, &file_path, progress_box).await?;
+            }
+        }
+        self.record_download(&file_path, &file).await;
         
-        // Create installed mod record
+        // Create installed mod record, a reinstall stays in its group
         let installed_mod = InstalledMod {
             mod_info,
-            installed_file: selected_file,
+            installed_file: file,
             install_path: file_path,
             enabled: true,
             install_date: chrono::Utc::now(),
             update_available: None,
+            group: self.installed_mods.get(key).and_then(|existing| existing.group.clone()),
         };
         
         // Save to installed mods
-        self.installed_mods.insert(mod_id.to_string(), installed_mod.clone());
+        self.installed_mods.insert(key.to_string(), installed_mod.clone());
         self.save_installed_mods().await?;
         
         Ok(installed_mod)
     }
     
-    /// Uninstall a mod
-    pub async fn uninstall_mod(&mut self, mod_id: &str) -> Result<(), ModError> {
-        let installed_mod = self.installed_mods.remove(mod_id)
-            .ok_or_else(|| ModError::NotFound(format!("Mod {} not installed", mod_id)))?;
+    /// Add a provenance entry for a mod file downloaded to `path`
+    async fn record_download(&self, path: &Path, file: &ModFile) {
+        crate::provenance::record_download(&self.instance_path, path, &file.download_url, file.hashes.get("sha1").map(String::as_str)).await;
+    }
+
+    /// Uninstall a mod, moving its jar to the mod trash
+    ///
+    /// Returns the trash entry id, None when the jar was already gone.
+    pub async fn uninstall_mod(&mut self, mod_id: &str) -> Result<Option<String>, ModError> {
+        let installed_mod = self.installed_mods.get(mod_id)
+            .ok_or_else(|| ModError::NotFound(format!("Mod {} not installed", mod_id)))?
+            .clone();
         
-        // Remove the mod file
+        let mut trash_entry = None;
         if installed_mod.install_path.exists() {
-            fs::remove_file(&installed_mod.install_path).await
-                .context("Failed to remove mod file")?;
+            let path = installed_mod.install_path.clone();
+            trash_entry = Some(trash::move_to_trash(&self.mods_path, &path, Some(mod_id.to_string()), Some(installed_mod)).await?);
+            crate::provenance::record_removed(&self.instance_path, &path).await;
         }
         
         // Save updated state
+        self.installed_mods.remove(mod_id);
         self.save_installed_mods().await?;
         
-        Ok(())
+        Ok(trash_entry)
+    }
+
+    /// Move a trashed jar back and put its manifest entry back
+    ///
+    /// Returns the restored entry, None for a jar that was never in the manifest.
+    pub async fn restore_deleted_mod(&mut self, trash_entry: &str) -> Result<Option<InstalledMod>, ModError> {
+        let (jar, record) = trash::read_entry(&self.mods_path, trash_entry).await?;
+        let dest = self.mods_path.join(&record.original_file);
+        if dest.exists() {
+            return Err(ModError::InvalidFile(format!("{} already exists in the mods folder", record.original_file)));
+        }
+        if let Some(key) = record.key.as_ref().filter(|key| self.installed_mods.contains_key(*key)) {
+            return Err(ModError::DependencyConflict(format!("{} has been installed again since it was deleted", key)));
+        }
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::rename(&jar, &dest).await
+            .context("Failed to restore mod from trash")?;
+        trash::remove_record(&jar).await;
+
+        match (record.key, record.installed_mod) {
+            (Some(key), Some(mut installed_mod)) => {
+                installed_mod.install_path = dest;
+                crate::provenance::record_download(
+                    &self.instance_path,
+                    &installed_mod.install_path,
+                    &installed_mod.installed_file.download_url,
+                    installed_mod.installed_file.hashes.get("sha1").map(String::as_str),
+                ).await;
+                self.installed_mods.insert(key, installed_mod.clone());
+                self.save_installed_mods().await?;
+                Ok(Some(installed_mod))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Mods in the trash, newest first
+    pub async fn list_trash(&self) -> Vec<trash::TrashEntry> {
+        trash::list(&self.mods_path).await.into_iter().map(|(entry, _)| entry).collect()
     }
     
+    /// Copy a mod jar from disk into the instance and record it
+    ///
+    /// The mod id, name and version come from the jar's loader metadata. When the
+    /// file's SHA1 is a known Modrinth version the entry is linked to that project
+    /// so update checks work, otherwise it is recorded as a local mod.
+    pub async fn import_local_mod(&mut self, file_path: &Path) -> Result<InstalledMod, ModError> {
+        let is_jar = file_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar"));
+        if !is_jar {
+            return Err(ModError::InvalidFile(format!("{} is not a .jar file", file_path.display())));
+        }
+        let filename = file_path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| ModError::InvalidFile(format!("Invalid path: {}", file_path.display())))?;
+
+        let jar_path = file_path.to_path_buf();
+        let metadata = tokio::task::spawn_blocking(move || local::read_jar_metadata(&jar_path)).await
+            .map_err(|e| ModError::InvalidFile(format!("Failed to read jar: {}", e)))??;
+        let sha1 = ApiUtils::file_sha1(file_path).await?;
+
+        let modrinth = ModrinthApi::new();
+        let matched = match modrinth.get_version_by_hash(&sha1).await {
+            Ok(Some(file)) => match modrinth.get_mod_details(&file.mod_id).await {
+                Ok(info) => Some((info, file)),
+                Err(e) => {
+                    println!("⚠️ Found Modrinth version for {} but not its project: {}", filename, e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                println!("⚠️ Modrinth lookup failed for {}: {}", filename, e);
+                None
+            }
+        };
+
+        let (key, mod_info, mut installed_file) = match matched {
+            Some((info, file)) => (info.id.clone(), info, file),
+            None => {
+                let size = fs::metadata(file_path).await?.len();
+                Self::local_mod_entry(&metadata, &filename, &sha1, size)
+            }
+        };
+
+        if let Some(existing) = self.installed_mods.get(&key)
+            .or_else(|| self.installed_mods.get(&metadata.mod_id))
+        {
+            return Err(ModError::AlreadyInstalled(format!(
+                "{} ({}) is already installed as {}",
+                metadata.name,
+                metadata.mod_id,
+                existing.installed_file.filename
+            )));
+        }
+
+        let install_path = self.mods_path.join(&filename);
+        if install_path.exists() {
+            return Err(ModError::AlreadyInstalled(format!("{} already exists in the mods folder", filename)));
+        }
+        fs::copy(file_path, &install_path).await
+            .context("Failed to copy mod into the mods folder")?;
+
+        // Keep the name the file was imported under, it may differ from Modrinth's
+        installed_file.filename = filename;
+        let installed_mod = InstalledMod {
+            mod_info,
+            installed_file,
+            install_path,
+            enabled: true,
+            install_date: chrono::Utc::now(),
+            update_available: None,
+            group: None,
+        };
+
+        self.installed_mods.insert(key, installed_mod.clone());
+        self.save_installed_mods().await?;
+
+        Ok(installed_mod)
+    }
+
+    /// Manifest entry for a jar that no API knows about
+    fn local_mod_entry(metadata: &LocalModMetadata, filename: &str, sha1: &str, size: u64) -> (String, ModInfo, ModFile) {
+        let now = chrono::Utc::now();
+        let mod_info = ModInfo {
+            id: metadata.mod_id.clone(),
+            name: metadata.name.clone(),
+            description: metadata.description.clone(),
+            author: metadata.authors.join(", "),
+            version: metadata.version.clone(),
+            game_versions: Vec::new(),
+            loaders: vec![metadata.loader.clone()],
+            downloads: 0,
+            icon_url: None,
+            website_url: None,
+            source_url: None,
+            license: None,
+            categories: Vec::new(),
+            side: ModSide::Unknown,
+            source: ModSource::Local,
+            featured: false,
+            date_created: now,
+            date_updated: now,
+        };
+        let file = ModFile {
+            id: sha1.to_string(),
+            mod_id: metadata.mod_id.clone(),
+            filename: filename.to_string(),
+            display_name: metadata.name.clone(),
+            version: metadata.version.clone(),
+            size,
+            download_url: String::new(),
+            hashes: HashMap::from([("sha1".to_string(), sha1.to_string())]),
+            dependencies: Vec::new(),
+            game_versions: Vec::new(),
+            loaders: vec![metadata.loader.clone()],
+            release_type: ReleaseType::Release,
+            date_published: now,
+            primary: true,
+        };
+        (metadata.mod_id.clone(), mod_info, file)
+    }
+
     /// Update a mod to the latest version
     pub async fn update_mod<F>(&mut self, mod_id: &str, progress_callback: F) -> Result<(), ModError>
     where
@@ -157,6 +401,7 @@ impl ModManager {
             Ok(_) => {
                 // Update was successful, remove backup
                 let _ = fs::remove_file(&backup_path).await;
+                self.record_download(&installed_mod.install_path, &update).await;
                 
                 // Update installed mod record
                 let mut updated_mod = installed_mod.clone();
@@ -193,6 +438,291 @@ impl ModManager {
         Ok(mods_with_updates)
     }
     
+    /// Update every installed mod whose newest file targets `game_version` and `mod_loader`
+    ///
+    /// Updates for another game version or loader are skipped and listed in the report.
+    /// Every update is downloaded before any is applied, and updates that would leave
+    /// jars conflicting with each other are skipped. With `dry_run` the report is the
+    /// plan and no files are touched. `on_progress` is called with the position, total,
+    /// mod name and a status for each mod.
+    pub async fn update_all_mods<P>(
+        &mut self,
+        game_version: &str,
+        mod_loader: &str,
+        dry_run: bool,
+        on_progress: P,
+    ) -> Result<ModUpdateReport, ModError>
+    where
+        P: Fn(usize, usize, &str, &str),
+    {
+        let mut report = ModUpdateReport { dry_run, ..Default::default() };
+
+        let mut mods: Vec<(String, InstalledMod)> = self.installed_mods.iter()
+            .map(|(id, m)| (id.clone(), m.clone()))
+            .collect();
+        mods.sort_by_key(|m| m.1.mod_info.name.to_lowercase());
+        let total = mods.len();
+        // Downloaded next to the jars they replace, applied once all are checked for conflicts
+        let mut staged: Vec<(usize, String, InstalledMod, ModFile, ModUpdateEntry, PathBuf)> = Vec::new();
+
+        for (index, (mod_id, installed_mod)) in mods.into_iter().enumerate() {
+            let current = index + 1;
+            let mod_name = installed_mod.mod_info.name.clone();
+            on_progress(current, total, &mod_name, "checking");
+
+            let mut found = None;
+            for (client_index, client) in self.api_clients.iter().enumerate() {
+                match client.check_updates(&installed_mod).await {
+                    Ok(Some(update)) => {
+                        found = Some((client_index, update));
+                        break;
+                    }
+                    Ok(None) => {}
+                    Err(e) => println!("⚠️ Failed to check updates for {}: {}", mod_name, e),
+                }
+            }
+
+            let Some((client_index, update)) = found else {
+                report.up_to_date += 1;
+                on_progress(current, total, &mod_name, "up_to_date");
+                continue;
+            };
+
+            if let Some(reason) = Self::update_mismatch(&update, game_version, mod_loader) {
+                report.skipped.push(ModUpdateSkip { mod_id, mod_name: mod_name.clone(), reason });
+                on_progress(current, total, &mod_name, "skipped");
+                continue;
+            }
+
+            let entry = ModUpdateEntry {
+                mod_id: mod_id.clone(),
+                mod_name: mod_name.clone(),
+                from_version: installed_mod.installed_file.version.clone(),
+                to_version: update.version.clone(),
+                from_file: installed_mod.installed_file.filename.clone(),
+                to_file: update.filename.clone(),
+            };
+
+            if dry_run {
+                report.updated.push(entry);
+                on_progress(current, total, &mod_name, "planned");
+                continue;
+            }
+
+            on_progress(current, total, &mod_name, "downloading");
+            match self.download_update(client_index, &update).await {
+                Ok(temp_path) => staged.push((current, mod_id, installed_mod, update, entry, temp_path)),
+                Err(e) => {
+                    report.failed.push(ModUpdateSkip { mod_id, mod_name: mod_name.clone(), reason: e.to_string() });
+                    on_progress(current, total, &mod_name, "failed");
+                }
+            }
+        }
+
+        if dry_run {
+            return Ok(report);
+        }
+
+        let pending: Vec<PendingUpdate> = staged.iter()
+            .map(|(_, mod_id, _, update, _, temp_path)| PendingUpdate { mod_id, temp_path, file: update })
+            .collect();
+        let blocked = conflicts::conflicting_updates(self, &pending).await;
+
+        for (current, mod_id, installed_mod, update, entry, temp_path) in staged {
+            let mod_name = installed_mod.mod_info.name.clone();
+            if let Some((_, conflict)) = blocked.iter().find(|(blocked_id, _)| *blocked_id == mod_id) {
+                let _ = fs::remove_file(&temp_path).await;
+                report.skipped.push(ModUpdateSkip { mod_id, mod_name: mod_name.clone(), reason: format!("Would conflict: {}", conflict.message) });
+                if !report.prevented_conflicts.contains(conflict) {
+                    report.prevented_conflicts.push(conflict.clone());
+                }
+                on_progress(current, total, &mod_name, "conflict");
+                continue;
+            }
+
+            on_progress(current, total, &mod_name, "updating");
+            match self.swap_mod_file(&mod_id, &installed_mod, update, &temp_path).await {
+                Ok(()) => {
+                    report.updated.push(entry);
+                    on_progress(current, total, &mod_name, "updated");
+                }
+                Err(e) => {
+                    let _ = fs::remove_file(&temp_path).await;
+                    report.failed.push(ModUpdateSkip { mod_id, mod_name: mod_name.clone(), reason: e.to_string() });
+                    on_progress(current, total, &mod_name, "failed");
+                }
+            }
+        }
+
+        report.mod_conflicts = conflicts::instance_conflicts(self).await;
+        Ok(report)
+    }
+
+    /// Why an update can't be applied to this game version and loader, if it can't
+    fn update_mismatch(update: &ModFile, game_version: &str, mod_loader: &str) -> Option<String> {
+        if !update.game_versions.iter().any(|v| v == game_version) {
+            return Some(format!(
+                "{} is for {} not {}",
+                update.version,
+                update.game_versions.join(", "),
+                game_version
+            ));
+        }
+
+        let loader = mod_loader.to_lowercase();
+        if !loader.is_empty() && loader != "vanilla"
+            && !update.loaders.is_empty()
+            && !update.loaders.iter().any(|l| l.to_lowercase() == loader)
+        {
+            return Some(format!(
+                "{} is for {} not {}",
+                update.version,
+                update.loaders.join(", "),
+                mod_loader
+            ));
+        }
+
+        None
+    }
+
+    /// Download `update` into the mods folder under a name the loader skips
+    async fn download_update(&self, client_index: usize, update: &ModFile) -> Result<PathBuf, ModError> {
+        let temp_path = self.mods_path.join(format!("{}.part", update.filename));
+        self.api_clients[client_index]
+            .download_mod_file(update, &temp_path, Box::new(|_, _| {}))
+            .await
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(&temp_path);
+            })?;
+        Ok(temp_path)
+    }
+
+    /// Replace the old jar with an update fetched by `download_update`
+    async fn swap_mod_file(
+        &mut self,
+        mod_id: &str,
+        installed_mod: &InstalledMod,
+        update: ModFile,
+        temp_path: &Path,
+    ) -> Result<(), ModError> {
+        // Keep disabled mods disabled
+        let mut new_path = self.mods_path.join(&update.filename);
+        if !installed_mod.enabled {
+            new_path = new_path.with_extension("jar.disabled");
+        }
+
+        if installed_mod.install_path.exists() {
+            fs::remove_file(&installed_mod.install_path).await
+                .context("Failed to remove old mod file")?;
+        }
+        fs::rename(temp_path, &new_path).await
+            .context("Failed to move updated mod file into place")?;
+        if installed_mod.install_path != new_path {
+            crate::provenance::record_removed(&self.instance_path, &installed_mod.install_path).await;
+        }
+        self.record_download(&new_path, &update).await;
+
+        let mut updated_mod = installed_mod.clone();
+        updated_mod.installed_file = update;
+        updated_mod.install_path = new_path;
+        updated_mod.update_available = None;
+        self.installed_mods.insert(mod_id.to_string(), updated_mod);
+        self.save_installed_mods().await?;
+
+        Ok(())
+    }
+
+    /// Install the newest file of an already installed mod that supports the given game version and loader
+    ///
+    /// Every client that knows the mod is asked in turn. Returns `None` when none of them has a compatible file.
+    pub async fn install_compatible_version<F>(
+        &mut self,
+        installed_mod: &InstalledMod,
+        mc_version: &str,
+        loader: &str,
+        progress_callback: F,
+    ) -> Result<Option<InstalledMod>, ModError>
+    where
+        F: Fn(u64, u64) + Send + Sync + Clone + 'static,
+    {
+        let mod_id = &installed_mod.mod_info.id;
+        let mut listed = false;
+
+        for client in &self.api_clients {
+            let files = match client.get_mod_files(mod_id).await {
+                Ok(files) => files,
+                Err(_) => continue,
+            };
+            listed = true;
+
+            let compatible = files.into_iter()
+                .filter(|f| f.game_versions.iter().any(|v| v == mc_version))
+                .filter(|f| f.loaders.iter().any(|l| l.eq_ignore_ascii_case(loader)))
+                .max_by_key(|f| (f.release_type == ReleaseType::Release, f.date_published));
+
+            let Some(file) = compatible else {
+                continue;
+            };
+
+            let file_path = self.mods_path.join(&file.filename);
+            let progress_box: Box<dyn Fn(u64, u64) + Send + Sync> = Box::new(progress_callback);
+            client.download_mod_file(&file, &file_path, progress_box).await?;
+
+            let new_mod = InstalledMod {
+                mod_info: installed_mod.mod_info.clone(),
+                installed_file: file,
+                install_path: file_path,
+                enabled: true,
+                install_date: chrono::Utc::now(),
+                update_available: None,
+                group: installed_mod.group.clone(),
+            };
+
+            self.installed_mods.insert(mod_id.clone(), new_mod.clone());
+            self.save_installed_mods().await?;
+
+            return Ok(Some(new_mod));
+        }
+
+        if listed {
+            Ok(None)
+        } else {
+            Err(ModError::NotFound(mod_id.clone()))
+        }
+    }
+
+    /// Download mods the manifest lists whose jar is gone, returning their names
+    ///
+    /// Mods without a download URL, such as imported local jars, are skipped.
+    pub async fn redownload_missing_mods(&mut self) -> Result<Vec<String>, ModError> {
+        let missing: Vec<InstalledMod> = self.installed_mods.values()
+            .filter(|installed_mod| !installed_mod.install_path.exists())
+            .cloned()
+            .collect();
+
+        let mut restored = Vec::new();
+        for installed_mod in missing {
+            let name = installed_mod.mod_info.name.clone();
+            let file = &installed_mod.installed_file;
+            if file.download_url.is_empty() {
+                println!("⚠️  {} can't be downloaded again, it was imported from a file", name);
+                continue;
+            }
+            let options = crate::net::DownloadOptions {
+                expected_size: Some(file.size).filter(|size| *size > 0),
+                sha1: file.hashes.get("sha1").cloned(),
+                ..Default::default()
+            };
+            crate::net::manager::shared()
+                .download(DownloadKind::Mod, DownloadPriority::Normal, &file.download_url, &installed_mod.install_path, options)
+                .await
+                .map_err(|e| ModError::DownloadFailed(format!("{}: {}", name, e)))?;
+            self.record_download(&installed_mod.install_path, file).await;
+            restored.push(name);
+        }
+        Ok(restored)
+    }
+
     /// Enable or disable a mod
     pub async fn set_mod_enabled(&mut self, mod_id: &str, enabled: bool) -> Result<(), ModError> {
         let installed_mod = self.installed_mods.get_mut(mod_id)
@@ -219,11 +749,108 @@ impl ModManager {
         Ok(())
     }
     
+    /// Put a mod in a named group, None or a blank name takes it out of its group
+    pub async fn set_mod_group(&mut self, mod_id: &str, group: Option<String>) -> Result<(), ModError> {
+        let installed_mod = self.installed_mods.get_mut(mod_id)
+            .ok_or_else(|| ModError::NotFound(format!("Mod {} not installed", mod_id)))?;
+        installed_mod.group = super::groups::normalize_group(group);
+        self.save_installed_mods().await
+    }
+
+    /// Move a jar into `mods/.extra` so the loader skips it, returning its new path
+    ///
+    /// Any manifest entry for the jar is dropped. A file already in `.extra` with the
+    /// same name is replaced.
+    pub async fn quarantine_mod_file(&mut self, path: &Path) -> Result<PathBuf, ModError> {
+        let file_name = path.file_name()
+            .ok_or_else(|| ModError::InvalidFile(format!("Invalid path: {}", path.display())))?;
+        let extra_dir = self.mods_path.join(super::sync::EXTRA_DIR);
+        fs::create_dir_all(&extra_dir).await
+            .context("Failed to create extra mods folder")?;
+
+        let new_path = extra_dir.join(file_name);
+        if new_path.exists() {
+            fs::remove_file(&new_path).await
+                .context("Failed to replace quarantined mod")?;
+        }
+        fs::rename(path, &new_path).await
+            .context("Failed to move mod out of the mods folder")?;
+        crate::provenance::record_removed(&self.instance_path, path).await;
+
+        let before = self.installed_mods.len();
+        self.installed_mods.retain(|_, installed_mod| installed_mod.install_path != path);
+        if self.installed_mods.len() != before {
+            self.save_installed_mods().await?;
+        }
+
+        Ok(new_path)
+    }
+
+    /// The instance folder this manager works on
+    pub fn instance_path(&self) -> &Path {
+        &self.instance_path
+    }
+
+    /// The instance's mods folder
+    pub fn mods_path(&self) -> &Path {
+        &self.mods_path
+    }
+
     /// Get all installed mods
     pub fn get_installed_mods(&self) -> &HashMap<String, InstalledMod> {
         &self.installed_mods
     }
     
+    /// Every mod in the instance for sharing, including jars that aren't in the manifest
+    pub async fn mod_list(&self) -> Result<Vec<ModListEntry>, ModError> {
+        let mut entries = Vec::new();
+
+        for installed_mod in self.installed_mods.values() {
+            let file_name = installed_mod.install_path.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| installed_mod.installed_file.filename.clone());
+            let sha1 = match installed_mod.installed_file.hashes.get("sha1") {
+                Some(hash) => Some(hash.clone()),
+                None => ApiUtils::file_sha1(&installed_mod.install_path).await.ok(),
+            };
+
+            entries.push(ModListEntry {
+                name: installed_mod.mod_info.name.clone(),
+                version: Some(installed_mod.installed_file.version.clone()),
+                file_name,
+                modrinth_project_id: matches!(installed_mod.mod_info.source, ModSource::Modrinth)
+                    .then(|| installed_mod.mod_info.id.clone()),
+                sha1,
+                enabled: installed_mod.enabled,
+            });
+        }
+
+        if let Ok(mut dir) = fs::read_dir(&self.mods_path).await {
+            while let Ok(Some(entry)) = dir.next_entry().await {
+                let path = entry.path();
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let enabled = file_name.ends_with(".jar");
+                if !(enabled || file_name.ends_with(".jar.disabled"))
+                    || self.installed_mods.values().any(|m| m.install_path == path)
+                {
+                    continue;
+                }
+
+                entries.push(ModListEntry {
+                    name: file_name.trim_end_matches(".disabled").trim_end_matches(".jar").to_string(),
+                    version: None,
+                    file_name,
+                    modrinth_project_id: None,
+                    sha1: ApiUtils::file_sha1(&path).await.ok(),
+                    enabled,
+                });
+            }
+        }
+
+        entries.sort_by_key(|e| e.name.to_lowercase());
+        Ok(entries)
+    }
+
     /// Search for mods across all APIs
     pub async fn search_mods(
         &self,
@@ -249,49 +876,118 @@ impl ModManager {
         Ok(all_results)
     }
     
-    /// Check dependencies for a mod file
-    pub async fn check_dependencies(&self, mod_file: &ModFile) -> Result<Vec<ModDependency>, ModError> {
-        let mut missing_deps = Vec::new();
-        
-        for dep in &mod_file.dependencies {
-            if dep.dependency_type == DependencyType::Required {
-                if !self.installed_mods.contains_key(&dep.mod_id) {
-                    missing_deps.push(dep.clone());
+    /// Walk the dependency tree of `root`, returning the files that still need installing
+    ///
+    /// Projects are visited once, so cycles end on their own, and chains deeper than
+    /// `MAX_DEPENDENCY_DEPTH` are reported as warnings instead of followed.
+    async fn resolve_dependencies(
+        &self,
+        client: &ApiClient,
+        root_info: &ModInfo,
+        root: &ModFile,
+        include_optional: bool,
+    ) -> Result<DependencyPlan, ModError> {
+        let mut plan = DependencyPlan::default();
+        let mut seen: HashSet<String> = HashSet::from([root.mod_id.clone(), root_info.id.clone()]);
+        let mut pending = vec![(root.clone(), root_info.name.clone(), 0)];
+
+        while let Some((parent, parent_name, depth)) = pending.pop() {
+            for dep in &parent.dependencies {
+                let required = match dep.dependency_type {
+                    DependencyType::Required => true,
+                    DependencyType::Optional => false,
+                    DependencyType::Incompatible | DependencyType::Embedded => continue,
+                };
+
+                let key = if dep.mod_id.is_empty() {
+                    dep.version_id.clone().unwrap_or_default()
+                } else {
+                    dep.mod_id.clone()
+                };
+                if !seen.insert(key.clone()) || self.is_installed(&dep.mod_id) {
+                    continue;
                 }
-            }
-        }
-        
-        if !missing_deps.is_empty() {
-            return Err(ModError::DependencyConflict(
-                format!("Missing required dependencies: {}", 
-                    missing_deps.iter()
-                        .map(|d| d.mod_id.clone())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )
-            ));
-        }
-        
-        Ok(missing_deps)
-    }
-    
-    /// Install missing dependencies for a mod
-    pub async fn install_dependencies<F>(&mut self, mod_file: &ModFile, progress_callback: F) -> Result<Vec<InstalledMod>, ModError>
-    where
-        F: Fn(u64, u64) + Send + Sync + Clone + 'static,
-    {
-        let mut installed_deps = Vec::new();
-        
-        for dep in &mod_file.dependencies {
-            if dep.dependency_type == DependencyType::Required && !self.installed_mods.contains_key(&dep.mod_id) {
-                match self.install_mod(&dep.mod_id, dep.version_id.as_deref(), progress_callback.clone()).await {
-                    Ok(installed) => installed_deps.push(installed),
-                    Err(e) => return Err(e),
+
+                if !required && !include_optional {
+                    let name = match dep.mod_id.is_empty() {
+                        true => None,
+                        false => client.get_mod_details(&dep.mod_id).await.ok().map(|info| info.name),
+                    };
+                    plan.optional.push(ResolvedDependency {
+                        mod_id: key,
+                        name,
+                        version: None,
+                        dependency_type: dep.dependency_type.clone(),
+                        required_by: parent_name.clone(),
+                    });
+                    continue;
+                }
+
+                if depth >= MAX_DEPENDENCY_DEPTH {
+                    plan.warnings.push(format!(
+                        "Dependencies of {} are nested more than {} levels deep, {} was not installed",
+                        parent_name, MAX_DEPENDENCY_DEPTH, key
+                    ));
+                    continue;
                 }
+
+                let (info, file) = match Self::resolve_dependency(client, dep, &parent).await {
+                    Ok(resolved) => resolved,
+                    Err(e) if required => {
+                        return Err(ModError::DependencyConflict(format!(
+                            "Required dependency {} of {} could not be resolved: {}", key, parent_name, e
+                        )));
+                    }
+                    Err(e) => {
+                        plan.warnings.push(format!("Optional dependency {} of {} was skipped: {}", key, parent_name, e));
+                        continue;
+                    }
+                };
+
+                // Dependencies given only by version are identified once resolved
+                seen.insert(info.id.clone());
+                if self.is_installed(&info.id) || self.mods_path.join(&file.filename).exists() {
+                    continue;
+                }
+
+                pending.push((file.clone(), info.name.clone(), depth + 1));
+                plan.install.push((info.clone(), file.clone(), ResolvedDependency {
+                    mod_id: info.id,
+                    name: Some(info.name),
+                    version: Some(file.version),
+                    dependency_type: dep.dependency_type.clone(),
+                    required_by: parent_name.clone(),
+                }));
             }
         }
-        
-        Ok(installed_deps)
+
+        // Dependencies found later belong to earlier ones, so install them first
+        plan.install.reverse();
+        Ok(plan)
+    }
+
+    /// Pick the file for a dependency, matching the game versions and loaders of the mod needing it
+    async fn resolve_dependency(client: &ApiClient, dep: &ModDependency, parent: &ModFile) -> Result<(ModInfo, ModFile), ModError> {
+        let file = match &dep.version_id {
+            Some(version_id) => client.get_mod_file(&dep.mod_id, version_id).await?,
+            None => client.get_mod_files(&dep.mod_id).await?
+                .into_iter()
+                .filter(|f| parent.game_versions.is_empty() || f.game_versions.iter().any(|v| parent.game_versions.contains(v)))
+                .filter(|f| parent.loaders.is_empty() || f.loaders.iter().any(|l| parent.loaders.iter().any(|p| p.eq_ignore_ascii_case(l))))
+                .max_by_key(|f| (f.release_type == ReleaseType::Release, f.date_published))
+                .ok_or_else(|| ModError::VersionIncompatible(format!(
+                    "no version for {} on {}", parent.game_versions.join(", "), parent.loaders.join(", ")
+                )))?,
+        };
+
+        let info = client.get_mod_details(&file.mod_id).await?;
+        Ok((info, file))
+    }
+
+    /// Whether a project is already in the manifest, under its own key or as an installed file
+    fn is_installed(&self, mod_id: &str) -> bool {
+        !mod_id.is_empty() && (self.installed_mods.contains_key(mod_id)
+            || self.installed_mods.values().any(|m| m.mod_info.id == mod_id || m.installed_file.mod_id == mod_id))
     }
     
     /// Refresh the list of installed mods by scanning the filesystem
@@ -329,15 +1025,25 @@ impl ModManager {
     /// Save installed mods metadata
     async fn save_installed_mods(&self) -> Result<(), ModError> {
         let metadata_path = self.instance_path.join("mods_metadata.json");
+        let tmp_path = metadata_path.with_extension("json.tmp");
         let json = serde_json::to_string_pretty(&self.installed_mods)?;
-        fs::write(metadata_path, json).await
+
+        // Write then rename so a crash never leaves a half-written manifest
+        fs::write(&tmp_path, json).await
+            .context("Failed to save mods metadata")?;
+        fs::rename(&tmp_path, &metadata_path).await
             .context("Failed to save mods metadata")?;
         Ok(())
     }
     
     /// Load installed mods metadata
     async fn load_installed_mods(&self) -> Result<HashMap<String, InstalledMod>, ModError> {
-        let metadata_path = self.instance_path.join("mods_metadata.json");
+        Self::read_installed_mods(&self.instance_path).await
+    }
+
+    /// Read installed mods metadata for an instance without creating a manager
+    pub async fn read_installed_mods(instance_path: &std::path::Path) -> Result<HashMap<String, InstalledMod>, ModError> {
+        let metadata_path = instance_path.join("mods_metadata.json");
         if !metadata_path.exists() {
             return Ok(HashMap::new());
         }
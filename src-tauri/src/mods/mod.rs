@@ -3,6 +3,13 @@ pub mod manager;
 pub mod loaders;
 pub mod types;
 pub mod commands;
+pub mod export;
+pub mod local;
+pub mod graph;
+pub mod conflicts;
+pub mod sync;
+pub mod trash;
+pub mod groups;
 
 pub use manager::*;
 pub use types::*;
\ No newline at end of file
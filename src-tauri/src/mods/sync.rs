@@ -0,0 +1,361 @@
+//! Matching an instance's mods folder to a server's mod manifest
+//!
+//! A manifest is a JSON list of `{ "filename", "sha1", "url"? }` entries, either
+//! bare or under a `mods` key. The URL is only needed for jars Modrinth doesn't host.
+
+use crate::mods::api::common::ApiUtils;
+use crate::mods::api::ModrinthApi;
+use crate::mods::{ModError, ModManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::fs;
+
+/// Folder in `mods` that extra jars are moved to, loaders don't look in subfolders
+pub const EXTRA_DIR: &str = ".extra";
+
+/// A mod the server requires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestMod {
+    #[serde(alias = "fileName", alias = "file_name")]
+    pub filename: String,
+    pub sha1: String,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ManifestFormat {
+    List(Vec<ManifestMod>),
+    Object { mods: Vec<ManifestMod> },
+}
+
+/// An enabled jar in the instance's mods folder
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LocalModFile {
+    pub filename: String,
+    pub sha1: String,
+}
+
+/// A local jar that looks like the same mod as a manifest entry but isn't the same file
+#[derive(Debug, Clone, Serialize)]
+pub struct ModVersionMismatch {
+    pub expected: ManifestMod,
+    pub local: LocalModFile,
+}
+
+/// How an instance's mods differ from a manifest
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ManifestComparison {
+    pub matching: usize,
+    pub missing: Vec<ManifestMod>,
+    pub extra: Vec<LocalModFile>,
+    pub mismatched: Vec<ModVersionMismatch>,
+}
+
+/// A manifest entry that couldn't be installed
+#[derive(Debug, Clone, Serialize)]
+pub struct ModSyncFailure {
+    pub filename: String,
+    pub reason: String,
+}
+
+/// What syncing to a manifest changed, file names throughout
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ManifestSyncReport {
+    pub installed: Vec<String>,
+    /// Moved to `mods/.extra`
+    pub quarantined: Vec<String>,
+    pub failed: Vec<ModSyncFailure>,
+}
+
+/// Parse and validate a manifest
+///
+/// File names come from the server, so anything that isn't a plain `.jar` name is rejected.
+pub fn parse_manifest(json: &str) -> Result<Vec<ManifestMod>, ModError> {
+    let mods = match serde_json::from_str::<ManifestFormat>(json)? {
+        ManifestFormat::List(mods) | ManifestFormat::Object { mods } => mods,
+    };
+
+    mods.into_iter()
+        .map(|mut entry| {
+            let plain_name = !entry.filename.contains(['/', '\\']) && !entry.filename.starts_with('.');
+            if !plain_name || !entry.filename.to_lowercase().ends_with(".jar") {
+                return Err(ModError::InvalidFile(format!("Manifest file name {:?} is not a jar name", entry.filename)));
+            }
+            if entry.sha1.len() != 40 || !entry.sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ModError::InvalidFile(format!("Manifest entry {} has an invalid SHA1", entry.filename)));
+            }
+            entry.sha1 = entry.sha1.to_lowercase();
+            Ok(entry)
+        })
+        .collect()
+}
+
+/// Read a manifest from an http(s) URL or a local file
+pub async fn load_manifest(source: &str) -> Result<Vec<ManifestMod>, ModError> {
+    let json = if source.starts_with("http://") || source.starts_with("https://") {
+        crate::http::client()
+            .get(source)
+            .header("User-Agent", crate::net::download::USER_AGENT)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?
+    } else {
+        fs::read_to_string(source).await?
+    };
+
+    parse_manifest(&json)
+}
+
+/// Hash every enabled jar in `mods_path`
+pub async fn scan_mods_folder(mods_path: &Path) -> Result<Vec<LocalModFile>, ModError> {
+    let mut files = Vec::new();
+    if !mods_path.exists() {
+        return Ok(files);
+    }
+
+    let mut entries = fs::read_dir(mods_path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_jar = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar"));
+        if !is_jar || !entry.file_type().await?.is_file() {
+            continue;
+        }
+
+        files.push(LocalModFile {
+            filename: entry.file_name().to_string_lossy().to_string(),
+            sha1: ApiUtils::file_sha1(&path).await?.to_lowercase(),
+        });
+    }
+
+    files.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(files)
+}
+
+/// Compare local jars to a manifest
+///
+/// Files match by SHA1. A manifest entry without a match is a version mismatch when
+/// a local jar has the same file name or the same name before its version number,
+/// otherwise it is missing. Local jars left over are extras.
+pub fn compare(manifest: &[ManifestMod], local: &[LocalModFile]) -> ManifestComparison {
+    let mut comparison = ManifestComparison::default();
+    let mut used = vec![false; local.len()];
+    let by_hash: HashMap<&str, usize> = local.iter().enumerate()
+        .map(|(index, file)| (file.sha1.as_str(), index))
+        .collect();
+
+    let mut unmatched = Vec::new();
+    for entry in manifest {
+        match by_hash.get(entry.sha1.as_str()) {
+            Some(&index) => {
+                used[index] = true;
+                comparison.matching += 1;
+            }
+            None => unmatched.push(entry),
+        }
+    }
+
+    for entry in unmatched {
+        let key = mod_key(&entry.filename);
+        let same_mod = (0..local.len()).find(|&index| {
+            !used[index] && (local[index].filename.eq_ignore_ascii_case(&entry.filename) || mod_key(&local[index].filename) == key)
+        });
+        match same_mod {
+            Some(index) => {
+                used[index] = true;
+                comparison.mismatched.push(ModVersionMismatch { expected: entry.clone(), local: local[index].clone() });
+            }
+            None => comparison.missing.push(entry.clone()),
+        }
+    }
+
+    comparison.extra = local.iter().zip(used)
+        .filter(|(_, used)| !used)
+        .map(|(file, _)| file.clone())
+        .collect();
+    comparison
+}
+
+/// Install missing mods and move mismatched ones out of the way
+///
+/// Missing files are looked up on Modrinth by SHA1, falling back to the manifest's
+/// URL, and every download is checked against the manifest's SHA1. A mismatched jar
+/// is always moved to `mods/.extra` since two versions of a mod can't load together,
+/// other extras only with `remove_extras`. `on_progress` is called with the position,
+/// total, file name and a status for each file installed.
+pub async fn sync_to_manifest<P>(
+    manager: &mut ModManager,
+    manifest: &[ManifestMod],
+    remove_extras: bool,
+    on_progress: P,
+) -> Result<ManifestSyncReport, ModError>
+where
+    P: Fn(usize, usize, &str, &str),
+{
+    let mut report = ManifestSyncReport::default();
+    let comparison = compare(manifest, &scan_mods_folder(manager.mods_path()).await?);
+
+    let mut to_quarantine: Vec<&LocalModFile> = comparison.mismatched.iter().map(|m| &m.local).collect();
+    if remove_extras {
+        to_quarantine.extend(&comparison.extra);
+    }
+    for file in to_quarantine {
+        let path = manager.mods_path().join(&file.filename);
+        match manager.quarantine_mod_file(&path).await {
+            Ok(_) => report.quarantined.push(file.filename.clone()),
+            Err(e) => report.failed.push(ModSyncFailure { filename: file.filename.clone(), reason: e.to_string() }),
+        }
+    }
+
+    let wanted: Vec<&ManifestMod> = comparison.missing.iter()
+        .chain(comparison.mismatched.iter().map(|m| &m.expected))
+        .collect();
+    if wanted.is_empty() {
+        return Ok(report);
+    }
+
+    let sha1s: Vec<String> = wanted.iter().map(|entry| entry.sha1.clone()).collect();
+    let versions = ModrinthApi::new().get_versions_by_hashes(&sha1s).await
+        .unwrap_or_else(|e| {
+            println!("⚠️ Modrinth lookup failed, using manifest URLs: {}", e);
+            HashMap::new()
+        });
+
+    let staging = std::env::temp_dir().join(format!("chai-mod-sync-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&staging).await?;
+
+    let total = wanted.len();
+    for (index, entry) in wanted.into_iter().enumerate() {
+        let current = index + 1;
+        on_progress(current, total, &entry.filename, "installing");
+
+        let url = versions.get(&entry.sha1)
+            .and_then(|version| hosted_url(version, &entry.sha1))
+            .or_else(|| entry.url.clone());
+        let Some(url) = url else {
+            report.failed.push(ModSyncFailure {
+                filename: entry.filename.clone(),
+                reason: "Not on Modrinth and the manifest has no download URL".to_string(),
+            });
+            on_progress(current, total, &entry.filename, "failed");
+            continue;
+        };
+
+        match install_file(manager, &staging, entry, &url).await {
+            Ok(()) => {
+                report.installed.push(entry.filename.clone());
+                on_progress(current, total, &entry.filename, "installed");
+            }
+            Err(e) => {
+                report.failed.push(ModSyncFailure { filename: entry.filename.clone(), reason: e.to_string() });
+                on_progress(current, total, &entry.filename, "failed");
+            }
+        }
+    }
+
+    let _ = fs::remove_dir_all(&staging).await;
+    Ok(report)
+}
+
+/// Download a manifest entry to `staging` and import it into the instance
+async fn install_file(manager: &mut ModManager, staging: &Path, entry: &ManifestMod, url: &str) -> Result<(), ModError> {
+    let staged = staging.join(&entry.filename);
+    crate::net::download_cached(url, &staged, crate::net::DownloadOptions {
+        sha1: Some(entry.sha1.clone()),
+        ..Default::default()
+    }).await
+    .map_err(|e| ModError::DownloadFailed(e.to_string()))?;
+
+    let installed = manager.import_local_mod(&staged).await;
+    let _ = fs::remove_file(&staged).await;
+    let installed = installed?;
+
+    crate::provenance::record_download(manager.instance_path(), &installed.install_path, url, Some(&entry.sha1)).await;
+    Ok(())
+}
+
+/// Download URL of the file in a Modrinth version with this SHA1
+fn hosted_url(version: &serde_json::Value, sha1: &str) -> Option<String> {
+    version["files"].as_array()?.iter()
+        .find(|file| file["hashes"]["sha1"].as_str().is_some_and(|hash| hash.eq_ignore_ascii_case(sha1)))?
+        ["url"].as_str()
+        .map(String::from)
+}
+
+/// A jar's name up to its version, `sodium-fabric-0.5.8+mc1.20.4.jar` is `sodium-fabric`
+fn mod_key(filename: &str) -> String {
+    let name = filename.to_lowercase();
+    let name = name.trim_end_matches(".jar");
+    let end = name.char_indices()
+        .find(|&(i, c)| c == '+' || (matches!(c, '-' | '_') && name[i + 1..].starts_with(|n: char| n.is_ascii_digit())))
+        .map_or(name.len(), |(i, _)| i);
+
+    match &name[..end] {
+        "" => name.to_string(),
+        key => key.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SODIUM: &str = "1111111111111111111111111111111111111111";
+    const SODIUM_OLD: &str = "2222222222222222222222222222222222222222";
+    const LITHIUM: &str = "3333333333333333333333333333333333333333";
+    const FABRIC_API: &str = "4444444444444444444444444444444444444444";
+
+    fn manifest_mod(filename: &str, sha1: &str) -> ManifestMod {
+        ManifestMod { filename: filename.to_string(), sha1: sha1.to_string(), url: None }
+    }
+
+    fn local(filename: &str, sha1: &str) -> LocalModFile {
+        LocalModFile { filename: filename.to_string(), sha1: sha1.to_string() }
+    }
+
+    #[test]
+    fn compare_finds_missing_extra_and_mismatched() {
+        let manifest = vec![
+            manifest_mod("sodium-fabric-0.5.8+mc1.20.4.jar", SODIUM),
+            manifest_mod("lithium-fabric-mc1.20.4-0.12.1.jar", LITHIUM),
+            manifest_mod("fabric-api-0.97.0+1.20.4.jar", FABRIC_API),
+        ];
+        let local = vec![
+            local("renamed.jar", FABRIC_API),
+            local("sodium-fabric-0.5.3+mc1.20.4.jar", SODIUM_OLD),
+            local("minimap.jar", "5555555555555555555555555555555555555555"),
+        ];
+
+        let comparison = compare(&manifest, &local);
+        assert_eq!(comparison.matching, 1);
+        assert_eq!(comparison.missing, vec![manifest[1].clone()]);
+        assert_eq!(comparison.mismatched.len(), 1);
+        assert_eq!(comparison.mismatched[0].expected, manifest[0]);
+        assert_eq!(comparison.mismatched[0].local, local[1]);
+        assert_eq!(comparison.extra, vec![local[2].clone()]);
+    }
+
+    #[test]
+    fn mod_key_stops_at_the_version() {
+        assert_eq!(mod_key("sodium-fabric-0.5.8+mc1.20.4.jar"), "sodium-fabric");
+        assert_eq!(mod_key("fabric-api-0.97.0+1.20.4.jar"), "fabric-api");
+        assert_eq!(mod_key("Xaeros_Minimap_24.0.3_Fabric_1.20.4.jar"), "xaeros_minimap");
+        assert_eq!(mod_key("1.2.3.jar"), "1.2.3");
+    }
+
+    #[test]
+    fn parse_manifest_accepts_both_shapes_and_rejects_paths() {
+        let list = format!(r#"[{{"filename":"a.jar","sha1":"{}"}}]"#, SODIUM.to_uppercase());
+        let object = format!(r#"{{"mods":[{{"fileName":"a.jar","sha1":"{}","url":"https://example.com/a.jar"}}]}}"#, SODIUM);
+        assert_eq!(parse_manifest(&list).unwrap()[0].sha1, SODIUM);
+        assert_eq!(parse_manifest(&object).unwrap()[0].url.as_deref(), Some("https://example.com/a.jar"));
+
+        let traversal = format!(r#"[{{"filename":"../options.jar","sha1":"{}"}}]"#, SODIUM);
+        assert!(parse_manifest(&traversal).is_err());
+        assert!(parse_manifest(r#"[{"filename":"a.jar","sha1":"abc"}]"#).is_err());
+    }
+}
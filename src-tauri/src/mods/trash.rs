@@ -0,0 +1,197 @@
+//! Uninstalled mods kept in `mods/.trash` so a misclick can be undone
+//!
+//! An uninstalled jar is moved to `.trash/<timestamp>-<filename>` with a
+//! `<entry>.json` record next to it holding the manifest entry it had. Restoring
+//! moves the jar back and puts the entry back in the manifest. Entries older than
+//! `mod_trash_retention_days` are purged after play sessions and at startup.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::mods::api::common::ApiUtils;
+use crate::mods::{InstalledMod, ModError};
+
+/// Folder in `mods` that uninstalled jars are moved to, loaders don't look in subfolders
+pub const TRASH_DIR: &str = ".trash";
+
+/// Days an uninstalled mod is kept when the settings can't be read
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// What is stored next to a trashed jar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashRecord {
+    /// Manifest key the mod was installed under, None for jars outside the manifest
+    pub key: Option<String>,
+    pub installed_mod: Option<InstalledMod>,
+    /// Path relative to the mods folder
+    pub original_file: String,
+    pub sha1: Option<String>,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// A trashed mod as shown to the player
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashEntry {
+    /// `<timestamp>-<filename>`, what `restore_deleted_mod` takes
+    pub id: String,
+    pub name: String,
+    pub original_file: String,
+    pub size: u64,
+    pub deleted_at: DateTime<Utc>,
+}
+
+pub fn trash_dir(mods_path: &Path) -> PathBuf {
+    mods_path.join(TRASH_DIR)
+}
+
+fn record_path(jar: &Path) -> PathBuf {
+    let mut name = jar.as_os_str().to_os_string();
+    name.push(".json");
+    PathBuf::from(name)
+}
+
+/// Move a jar into the trash with the manifest entry it had, returning the entry id
+pub async fn move_to_trash(
+    mods_path: &Path,
+    jar: &Path,
+    key: Option<String>,
+    installed_mod: Option<InstalledMod>,
+) -> Result<String, ModError> {
+    let file_name = jar.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| ModError::InvalidFile(format!("Invalid path: {}", jar.display())))?;
+    let dir = trash_dir(mods_path);
+    fs::create_dir_all(&dir).await?;
+
+    let deleted_at = Utc::now();
+    let id = format!("{}-{}", deleted_at.format("%Y%m%d%H%M%S%3f"), file_name);
+    let sha1 = match installed_mod.as_ref().and_then(|m| m.installed_file.hashes.get("sha1")) {
+        Some(sha1) => Some(sha1.clone()),
+        None => ApiUtils::file_sha1(jar).await.ok(),
+    };
+    let record = TrashRecord {
+        key,
+        installed_mod,
+        original_file: jar.strip_prefix(mods_path).unwrap_or(Path::new(&file_name)).to_string_lossy().replace('\\', "/"),
+        sha1,
+        deleted_at,
+    };
+
+    let dest = dir.join(&id);
+    fs::write(record_path(&dest), serde_json::to_vec_pretty(&record)?).await?;
+    if let Err(e) = fs::rename(jar, &dest).await {
+        let _ = fs::remove_file(record_path(&dest)).await;
+        return Err(e.into());
+    }
+    Ok(id)
+}
+
+/// The jar and record of a trash entry, rejecting ids that leave the trash folder
+pub async fn read_entry(mods_path: &Path, id: &str) -> Result<(PathBuf, TrashRecord), ModError> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return Err(ModError::InvalidFile(format!("Invalid trash entry: {}", id)));
+    }
+    let jar = trash_dir(mods_path).join(id);
+    if !jar.exists() {
+        return Err(ModError::NotFound(format!("Trash entry {}", id)));
+    }
+    let record = serde_json::from_str(&fs::read_to_string(record_path(&jar)).await?)?;
+    Ok((jar, record))
+}
+
+/// Forget a trash entry once its jar has been moved back
+pub async fn remove_record(jar: &Path) {
+    let _ = fs::remove_file(record_path(jar)).await;
+}
+
+/// Trashed mods, newest first
+pub async fn list(mods_path: &Path) -> Vec<(TrashEntry, TrashRecord)> {
+    let mut entries = Vec::new();
+    let Ok(mut dir) = fs::read_dir(trash_dir(mods_path)).await else {
+        return entries;
+    };
+    while let Ok(Some(item)) = dir.next_entry().await {
+        let id = item.file_name().to_string_lossy().to_string();
+        if id.ends_with(".json") {
+            continue;
+        }
+        let Ok((jar, record)) = read_entry(mods_path, &id).await else {
+            continue;
+        };
+        let name = record.installed_mod.as_ref()
+            .map(|m| m.mod_info.name.clone())
+            .unwrap_or_else(|| record.original_file.trim_end_matches(".disabled").trim_end_matches(".jar").to_string());
+        let size = fs::metadata(&jar).await.map(|m| m.len()).unwrap_or(0);
+        entries.push((TrashEntry { id, name, original_file: record.original_file.clone(), size, deleted_at: record.deleted_at }, record));
+    }
+    entries.sort_by_key(|e| std::cmp::Reverse(e.0.deleted_at));
+    entries
+}
+
+/// A trashed jar with this SHA1, newest first
+pub async fn find_by_sha1(mods_path: &Path, sha1: &str) -> Option<(PathBuf, TrashRecord)> {
+    list(mods_path).await.into_iter()
+        .find(|(_, record)| record.sha1.as_deref().is_some_and(|hash| hash.eq_ignore_ascii_case(sha1)))
+        .map(|(entry, record)| (trash_dir(mods_path).join(entry.id), record))
+}
+
+/// Delete trashed mods, all of them or only those older than `older_than_days`
+pub async fn purge(mods_path: &Path, older_than_days: Option<u32>) -> Result<u32, ModError> {
+    let cutoff = older_than_days.map(|days| Utc::now() - chrono::Duration::days(days as i64));
+    let mut removed = 0;
+    for (entry, record) in list(mods_path).await {
+        if cutoff.is_some_and(|cutoff| record.deleted_at > cutoff) {
+            continue;
+        }
+        let jar = trash_dir(mods_path).join(&entry.id);
+        fs::remove_file(&jar).await?;
+        remove_record(&jar).await;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Purge entries past the retention setting in an instance's mods folder
+pub async fn purge_expired(mods_path: &Path) {
+    let retention = match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().mod_trash_retention_days,
+        Err(_) => DEFAULT_RETENTION_DAYS,
+    };
+    match purge(mods_path, Some(retention)).await {
+        Ok(0) => {}
+        Ok(removed) => println!("🗑️  Purged {} mod(s) trashed more than {} days ago from {}", removed, retention, mods_path.display()),
+        Err(e) => println!("⚠️  Failed to purge mod trash in {}: {}", mods_path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn trashed_jars_can_be_found_and_purged_by_age() {
+        let mods_path = std::env::temp_dir().join(format!("chai-trash-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&mods_path).unwrap();
+        let jar = mods_path.join("sodium.jar");
+        std::fs::write(&jar, b"sodium").unwrap();
+        let sha1 = ApiUtils::file_sha1(&jar).await.unwrap();
+
+        let id = move_to_trash(&mods_path, &jar, None, None).await.unwrap();
+        assert!(!jar.exists());
+        assert!(id.ends_with("-sodium.jar"));
+
+        let entries = list(&mods_path).await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0.name, "sodium");
+        assert_eq!(entries[0].1.original_file, "sodium.jar");
+        assert!(find_by_sha1(&mods_path, &sha1.to_uppercase()).await.is_some());
+        assert!(read_entry(&mods_path, "../sodium.jar").await.is_err());
+
+        assert_eq!(purge(&mods_path, Some(1)).await.unwrap(), 0);
+        assert_eq!(purge(&mods_path, None).await.unwrap(), 1);
+        assert!(list(&mods_path).await.is_empty());
+        std::fs::remove_dir_all(mods_path).unwrap();
+    }
+}
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use chrono::{DateTime, Utc};
 
+use crate::mods::conflicts::ModConflict;
+
 /// Represents a mod from any API source
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModInfo {
@@ -63,10 +65,83 @@ pub struct InstalledMod {
     pub enabled: bool,
     pub install_date: DateTime<Utc>,
     pub update_available: Option<ModFile>,
+    /// Named group the mod is toggled with, see `mods::groups`
+    #[serde(default)]
+    pub group: Option<String>,
 }
 
-/// Supported mod loaders
+/// A dependency found while installing a mod
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedDependency {
+    pub mod_id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub dependency_type: DependencyType,
+    /// Name of the mod that depends on this one
+    pub required_by: String,
+}
+
+/// Result of installing a mod along with its dependencies
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModInstallSummary {
+    pub installed_mod: InstalledMod,
+    /// Dependencies downloaded by this install, dependencies of dependencies first
+    pub dependencies_added: Vec<ResolvedDependency>,
+    /// Optional dependencies that are missing and were not installed
+    pub optional_dependencies: Vec<ResolvedDependency>,
+    pub warnings: Vec<String>,
+    /// Conflicts between the instance's enabled jars after the install
+    pub mod_conflicts: Vec<ModConflict>,
+}
+
+/// A mod that has (or would get, in a dry run) a newer file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModUpdateEntry {
+    pub mod_id: String,
+    pub mod_name: String,
+    pub from_version: String,
+    pub to_version: String,
+    pub from_file: String,
+    pub to_file: String,
+}
+
+/// A mod left alone by a bulk update and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModUpdateSkip {
+    pub mod_id: String,
+    pub mod_name: String,
+    pub reason: String,
+}
+
+/// Outcome of updating every mod in an instance
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModUpdateReport {
+    pub dry_run: bool,
+    pub updated: Vec<ModUpdateEntry>,
+    /// Updates that target another game version or loader
+    pub skipped: Vec<ModUpdateSkip>,
+    pub failed: Vec<ModUpdateSkip>,
+    pub up_to_date: usize,
+    /// Conflicts updates would have added, those updates are in `skipped` instead
+    pub prevented_conflicts: Vec<ModConflict>,
+    /// Conflicts between the instance's enabled jars after the update
+    pub mod_conflicts: Vec<ModConflict>,
+}
+
+/// One mod in an exported mod list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModListEntry {
+    pub name: String,
+    pub version: Option<String>,
+    pub file_name: String,
+    /// Unset for CurseForge mods and jars added by hand
+    pub modrinth_project_id: Option<String>,
+    pub sha1: Option<String>,
+    pub enabled: bool,
+}
+
+/// Supported mod loaders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ModLoader {
     Forge(String),      // version
     Fabric(String),     // loader version
@@ -89,6 +164,9 @@ pub enum ModSide {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ModSource {
     Modrinth,
+    CurseForge,
+    /// Imported from a jar on disk that no API recognised
+    Local,
 }
 
 /// Type of mod dependency
@@ -131,6 +209,8 @@ pub enum ModError {
     DownloadFailed(String),
     #[error("Invalid mod file: {0}")]
     InvalidFile(String),
+    #[error("Mod already installed: {0}")]
+    AlreadyInstalled(String),
 }
 
 impl ModLoader {
@@ -243,6 +323,8 @@ impl ModSource {
     pub fn api_name(&self) -> &str {
         match self {
             ModSource::Modrinth => "modrinth",
+            ModSource::CurseForge => "curseforge",
+            ModSource::Local => "local",
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,295 @@
+//! Shared cache of downloaded files, addressed by SHA1
+//!
+//! The same mod jar often ends up in several instances. Downloads with a known SHA1
+//! are kept in `cache/downloads/<first two hex digits>/<sha1>` under the launcher
+//! directory and hardlinked, or copied where links aren't possible, into instances.
+//! Cached files are verified again before use, and the least recently used ones are
+//! evicted once the cache is larger than `download_cache_max_mb`.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+use tauri::command;
+use tokio::fs;
+
+use super::download::{self, DownloadError, DownloadOptions};
+
+/// Cache size used when the settings can't be read
+pub const DEFAULT_MAX_MB: u64 = 4096;
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Serializes adding files, eviction and clearing
+static WRITE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadCacheStats {
+    pub path: String,
+    pub files: u64,
+    pub total_bytes: u64,
+    /// 0 when the cache is turned off
+    pub max_bytes: u64,
+    /// Since the launcher started
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// A file `fetch_cached` put in place
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedDownload {
+    pub size: u64,
+    /// Linked or copied from the cache rather than downloaded
+    pub cache_hit: bool,
+}
+
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    last_used: SystemTime,
+}
+
+pub fn cache_dir() -> PathBuf {
+    crate::storage::get_launcher_dir().join("cache").join("downloads")
+}
+
+/// Like `download`, served from the cache when `options.sha1` is set and cached
+pub async fn download_cached(url: &str, dest: &Path, options: DownloadOptions) -> Result<u64, DownloadError> {
+    fetch_cached(url, dest, options).await.map(|fetched| fetched.size)
+}
+
+/// Like `download_cached`, also telling whether the file came from the cache
+pub async fn fetch_cached(url: &str, dest: &Path, options: DownloadOptions) -> Result<CachedDownload, DownloadError> {
+    let max_bytes = max_bytes().await;
+    let sha1 = options.sha1.as_deref().filter(|sha1| is_sha1(sha1)).map(str::to_lowercase);
+    let Some(sha1) = sha1.filter(|_| max_bytes > 0) else {
+        let size = download::download(url, dest, options).await?;
+        return Ok(CachedDownload { size, cache_hit: false });
+    };
+
+    let dir = cache_dir();
+    if let Some((cached, size)) = lookup(&dir, &sha1, options.expected_size).await {
+        link_or_copy(&cached, dest).await?;
+        HITS.fetch_add(1, Ordering::Relaxed);
+        if let Some(progress) = &options.progress {
+            progress(size, size);
+        }
+        return Ok(CachedDownload { size, cache_hit: true });
+    }
+
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    let size = download::download(url, dest, options).await?;
+    if let Err(e) = store(&dir, &sha1, dest, max_bytes).await {
+        println!("⚠️  Failed to cache {}: {}", dest.display(), e);
+    }
+    Ok(CachedDownload { size, cache_hit: false })
+}
+
+/// The cached file for `sha1` and its size, if it's there and intact
+///
+/// A file that fails verification is removed so it can't end up in an instance.
+async fn lookup(dir: &Path, sha1: &str, expected_size: Option<u64>) -> Option<(PathBuf, u64)> {
+    let cached = entry_path(dir, sha1);
+    let metadata = fs::metadata(&cached).await.ok().filter(|metadata| metadata.is_file())?;
+
+    if let Err(e) = download::verify(&cached, metadata.len(), expected_size, Some(sha1)).await {
+        println!("⚠️  Cached {} failed verification ({}), downloading it again", sha1, e);
+        let _ = fs::remove_file(&cached).await;
+        return None;
+    }
+
+    // The modification time doubles as the last use for eviction
+    let touched = std::fs::File::options().write(true).open(&cached)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(e) = touched {
+        println!("⚠️  Failed to mark {} as used: {}", cached.display(), e);
+    }
+    Some((cached, metadata.len()))
+}
+
+/// Add a verified download to the cache, then evict down to `max_bytes`
+async fn store(dir: &Path, sha1: &str, file: &Path, max_bytes: u64) -> std::io::Result<()> {
+    let _guard = WRITE_LOCK.lock().await;
+    link_or_copy(file, &entry_path(dir, sha1)).await?;
+    evict(dir, max_bytes).await?;
+    Ok(())
+}
+
+/// Remove the least recently used files until the cache fits in `max_bytes`
+async fn evict(dir: &Path, max_bytes: u64) -> std::io::Result<u64> {
+    let mut entries = entries(dir).await?;
+    let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+    entries.sort_by_key(|entry| entry.last_used);
+
+    let mut removed = 0;
+    for entry in entries {
+        if total <= max_bytes {
+            break;
+        }
+        fs::remove_file(&entry.path).await?;
+        total -= entry.size;
+        removed += entry.size;
+    }
+    Ok(removed)
+}
+
+async fn entries(dir: &Path) -> std::io::Result<Vec<CacheEntry>> {
+    let dir = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut entries = Vec::new();
+        for entry in walkdir::WalkDir::new(&dir).min_depth(2).max_depth(2) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if e.io_error().is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) => continue,
+                Err(e) => return Err(std::io::Error::other(e)),
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let metadata = entry.metadata().map_err(std::io::Error::other)?;
+            entries.push(CacheEntry {
+                path: entry.into_path(),
+                size: metadata.len(),
+                last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+        Ok(entries)
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// Put `src` at `dest` as a hardlink, or a copy across filesystems
+///
+/// Goes through a temporary name so `dest` is never seen half written.
+async fn link_or_copy(src: &Path, dest: &Path) -> std::io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+    let temp = dest.with_file_name(name);
+
+    if fs::hard_link(src, &temp).await.is_err() {
+        fs::copy(src, &temp).await?;
+    }
+    fs::rename(&temp, dest).await.inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+fn entry_path(dir: &Path, sha1: &str) -> PathBuf {
+    dir.join(&sha1[..2]).join(sha1)
+}
+
+pub(super) fn is_sha1(hash: &str) -> bool {
+    hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+async fn max_bytes() -> u64 {
+    let max_mb = match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().download_cache_max_mb,
+        Err(_) => DEFAULT_MAX_MB,
+    };
+    max_mb * 1024 * 1024
+}
+
+/// Size of the download cache and how often it was used
+#[command]
+pub async fn get_download_cache_stats() -> Result<DownloadCacheStats, String> {
+    let dir = cache_dir();
+    let entries = entries(&dir).await
+        .map_err(|e| format!("Failed to read download cache: {}", e))?;
+
+    Ok(DownloadCacheStats {
+        path: dir.to_string_lossy().to_string(),
+        files: entries.len() as u64,
+        total_bytes: entries.iter().map(|entry| entry.size).sum(),
+        max_bytes: max_bytes().await,
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    })
+}
+
+/// Delete every cached download, returning the bytes freed
+///
+/// Instances keep their files, hardlinked or not.
+#[command]
+pub async fn clear_download_cache() -> Result<u64, String> {
+    let _guard = WRITE_LOCK.lock().await;
+    let dir = cache_dir();
+    let freed = entries(&dir).await
+        .map_err(|e| format!("Failed to read download cache: {}", e))?
+        .iter()
+        .map(|entry| entry.size)
+        .sum();
+
+    if dir.exists() {
+        fs::remove_dir_all(&dir).await
+            .map_err(|e| format!("Failed to clear download cache: {}", e))?;
+    }
+    println!("🧹 Cleared download cache ({} bytes)", freed);
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::{Digest, Sha1};
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chai-cache-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_file(path: &Path, contents: &[u8]) -> String {
+        std::fs::write(path, contents).unwrap();
+        hex::encode(Sha1::digest(contents))
+    }
+
+    #[tokio::test]
+    async fn cached_files_are_verified_before_use() {
+        let dir = temp_dir();
+        let cache = dir.join("cache");
+        let download = dir.join("fabric-api.jar");
+        let sha1 = write_file(&download, b"fabric api");
+
+        store(&cache, &sha1, &download, u64::MAX).await.unwrap();
+        let (cached, size) = lookup(&cache, &sha1, Some(10)).await.unwrap();
+        assert_eq!(size, 10);
+
+        let instance_copy = dir.join("instance").join("mods").join("fabric-api.jar");
+        link_or_copy(&cached, &instance_copy).await.unwrap();
+        assert_eq!(std::fs::read(&instance_copy).unwrap(), b"fabric api");
+
+        assert!(lookup(&cache, &sha1, Some(11)).await.is_none());
+        assert!(!cached.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn eviction_drops_least_recently_used_first() {
+        let dir = temp_dir();
+        let cache = dir.join("cache");
+        let mut hashes = Vec::new();
+        for (index, name) in ["old", "mid", "new"].iter().enumerate() {
+            let file = dir.join(name);
+            let sha1 = write_file(&file, &[index as u8; 100]);
+            store(&cache, &sha1, &file, u64::MAX).await.unwrap();
+
+            let age = std::time::Duration::from_secs(3600 * (3 - index as u64));
+            std::fs::File::options().write(true).open(entry_path(&cache, &sha1)).unwrap()
+                .set_modified(SystemTime::now() - age).unwrap();
+            hashes.push(sha1);
+        }
+
+        assert_eq!(evict(&cache, 200).await.unwrap(), 100);
+        assert!(!entry_path(&cache, &hashes[0]).exists());
+        assert!(entry_path(&cache, &hashes[1]).exists());
+        assert!(entry_path(&cache, &hashes[2]).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
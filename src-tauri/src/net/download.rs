@@ -0,0 +1,448 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use sha1::{Digest, Sha1};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::watch;
+
+/// User agent sent with every download
+pub const USER_AGENT: &str = "ChaiLauncher/2.0.0";
+
+/// Wait before the first retry, doubled for every further one
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait between two attempts
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// Called with bytes written so far and the total size, 0 when unknown
+pub type ProgressCallback = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// How a download should be checked, retried and reported
+#[derive(Clone)]
+pub struct DownloadOptions {
+    /// Client to send the request with, the launcher's shared one when unset
+    pub client: Option<reqwest::Client>,
+    pub expected_size: Option<u64>,
+    /// Hex SHA1 the finished file must match
+    pub sha1: Option<String>,
+    /// Extra attempts after network errors, 5xx and 429 responses, with exponential backoff
+    pub retries: u32,
+    /// Continue from the `.part` file left by an earlier attempt
+    pub resume: bool,
+    pub progress: Option<ProgressCallback>,
+    /// Stops the download once it turns true
+    pub cancel: Option<watch::Receiver<bool>>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            client: None,
+            expected_size: None,
+            sha1: None,
+            retries: 3,
+            resume: true,
+            progress: None,
+            cancel: None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DownloadError {
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("HTTP {status} from {url}")]
+    Status { status: reqwest::StatusCode, url: String },
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Connection closed after {actual} of {expected} bytes")]
+    Truncated { expected: u64, actual: u64 },
+    #[error("Expected {expected} bytes but got {actual}")]
+    SizeMismatch { expected: u64, actual: u64 },
+    #[error("Hash mismatch (expected {expected}, got {actual})")]
+    HashMismatch { expected: String, actual: String },
+    #[error("Download cancelled")]
+    Cancelled,
+}
+
+impl DownloadError {
+    /// Whether another attempt could succeed
+    fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::Request(_) | DownloadError::Truncated { .. } => true,
+            DownloadError::Status { status, .. } => {
+                status.is_server_error() || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Download `url` to `dest`, returning the size of the finished file
+///
+/// Data goes to `<dest>.part` and is renamed over `dest` only after the size and
+/// hash checks pass, so an interrupted download never looks finished.
+pub async fn download(url: &str, dest: &Path, options: DownloadOptions) -> Result<u64, DownloadError> {
+    let DownloadOptions { client, expected_size, sha1, retries, resume, progress, mut cancel } = options;
+    let client = client.unwrap_or_else(crate::http::client);
+    let part = part_path(dest);
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    if !resume {
+        let _ = fs::remove_file(&part).await;
+    }
+
+    let mut attempt = 0;
+    let size = loop {
+        if is_cancelled(&cancel) {
+            return Err(DownloadError::Cancelled);
+        }
+
+        match fetch(&client, url, &part, resume, progress.as_ref(), &mut cancel).await {
+            Ok(size) => break size,
+            Err(e) if e.is_retryable() && attempt < retries => {
+                attempt += 1;
+                println!("⚠️  Download of {} failed ({}), retrying {}/{}", url, e, attempt, retries);
+                let backoff = tokio::time::sleep(retry_delay(attempt));
+                tokio::select! {
+                    _ = backoff => {}
+                    _ = wait_cancelled(&mut cancel) => return Err(DownloadError::Cancelled),
+                }
+            }
+            Err(e) => {
+                if !(resume && e.is_retryable()) {
+                    let _ = fs::remove_file(&part).await;
+                }
+                return Err(e);
+            }
+        }
+    };
+
+    if let Err(e) = verify(&part, size, expected_size, sha1.as_deref()).await {
+        let _ = fs::remove_file(&part).await;
+        return Err(e);
+    }
+
+    fs::rename(&part, dest).await?;
+    Ok(size)
+}
+
+/// How long to wait before retry number `attempt`, counting from 1
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY.saturating_mul(1 << attempt.saturating_sub(1).min(16)).min(RETRY_MAX_DELAY)
+}
+
+/// One request, appending to the part file when the server honours the range
+async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    resume: bool,
+    progress: Option<&ProgressCallback>,
+    cancel: &mut Option<watch::Receiver<bool>>,
+) -> Result<u64, DownloadError> {
+    let offset = match fs::metadata(part).await {
+        Ok(metadata) if resume => metadata.len(),
+        _ => 0,
+    };
+
+    let mut request = client.get(url).header(reqwest::header::USER_AGENT, USER_AGENT);
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let response = request.send().await?;
+    let status = response.status();
+
+    // The part file already holds everything
+    if offset > 0 && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(offset);
+    }
+    if !status.is_success() {
+        return Err(DownloadError::Status { status, url: url.to_string() });
+    }
+
+    // Servers that ignore Range send the whole file again
+    let resumed = offset > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut written = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|len| len + written).unwrap_or(0);
+
+    let mut file = if resumed {
+        fs::OpenOptions::new().append(true).open(part).await?
+    } else {
+        fs::File::create(part).await?
+    };
+
+    let mut stream = response.bytes_stream();
+    loop {
+        let chunk = tokio::select! {
+            chunk = stream.next() => chunk,
+            _ = wait_cancelled(cancel) => return Err(DownloadError::Cancelled),
+        };
+        let Some(chunk) = chunk else { break };
+        let chunk = chunk?;
+
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+        if let Some(progress) = progress {
+            progress(written, total);
+        }
+    }
+    file.flush().await?;
+
+    // A connection closed early can end the stream without an error
+    if total > 0 && written < total {
+        return Err(DownloadError::Truncated { expected: total, actual: written });
+    }
+
+    Ok(written)
+}
+
+/// Whether `dest` already holds the file with this SHA1, so downloading it again can be skipped
+pub async fn is_downloaded(dest: &Path, expected_size: Option<u64>, sha1: &str) -> bool {
+    if sha1.is_empty() {
+        return false;
+    }
+    match fs::metadata(dest).await {
+        Ok(metadata) if metadata.is_file() => verify(dest, metadata.len(), expected_size, Some(sha1)).await.is_ok(),
+        _ => false,
+    }
+}
+
+pub(crate) async fn verify(path: &Path, size: u64, expected_size: Option<u64>, sha1: Option<&str>) -> Result<(), DownloadError> {
+    if let Some(expected) = expected_size.filter(|s| *s > 0) {
+        if size != expected {
+            return Err(DownloadError::SizeMismatch { expected, actual: size });
+        }
+    }
+
+    if let Some(expected) = sha1.filter(|h| !h.is_empty()) {
+        let actual = file_sha1(path).await?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(DownloadError::HashMismatch { expected: expected.to_string(), actual });
+        }
+    }
+
+    Ok(())
+}
+
+/// Hex SHA1 of a file on disk
+pub(crate) async fn file_sha1(path: &Path) -> Result<String, std::io::Error> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+fn is_cancelled(cancel: &Option<watch::Receiver<bool>>) -> bool {
+    cancel.as_ref().is_some_and(|cancel| *cancel.borrow())
+}
+
+async fn wait_cancelled(cancel: &mut Option<watch::Receiver<bool>>) {
+    if let Some(cancel) = cancel {
+        if cancel.wait_for(|cancelled| *cancelled).await.is_ok() {
+            return;
+        }
+    }
+    // No receiver, or the sender is gone and can't cancel any more
+    std::future::pending().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn body() -> Vec<u8> {
+        (0..64 * 1024u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Requests seen by the mock server as (path, range start)
+    type RequestLog = Arc<Mutex<Vec<(String, Option<usize>)>>>;
+
+    /// Local HTTP server: `/file` supports Range, `/flaky` drops its first response
+    /// halfway through, everything else is a 404
+    async fn mock_server() -> (String, RequestLog) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let log = RequestLog::default();
+        let flaky_hits = Arc::new(AtomicUsize::new(0));
+
+        let server_log = log.clone();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(handle(socket, server_log.clone(), flaky_hits.clone()));
+            }
+        });
+
+        (base, log)
+    }
+
+    async fn handle(mut socket: TcpStream, log: RequestLog, flaky_hits: Arc<AtomicUsize>) {
+        let (reader, mut writer) = socket.split();
+        let mut reader = BufReader::new(reader);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await.unwrap();
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+        let mut range_start = None;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap() == 0 || line == "\r\n" {
+                break;
+            }
+            if let Some(range) = line.to_lowercase().strip_prefix("range: bytes=") {
+                range_start = range.trim().trim_end_matches('-').parse::<usize>().ok();
+            }
+        }
+        log.lock().unwrap().push((path.clone(), range_start));
+
+        if path != "/file" && path != "/flaky" {
+            let _ = writer.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            return;
+        }
+
+        let body = body();
+        let start = range_start.unwrap_or(0);
+        let status = if start > 0 { "206 Partial Content" } else { "200 OK" };
+        let head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len() - start);
+        writer.write_all(head.as_bytes()).await.unwrap();
+
+        if path == "/flaky" && flaky_hits.fetch_add(1, Ordering::SeqCst) == 0 {
+            writer.write_all(&body[start..body.len() / 2]).await.unwrap();
+            let _ = writer.shutdown().await;
+            return;
+        }
+        let _ = writer.write_all(&body[start..]).await;
+        let _ = writer.shutdown().await;
+    }
+
+    fn temp_dest() -> PathBuf {
+        std::env::temp_dir().join(format!("chai-download-{}", uuid::Uuid::new_v4())).join("file.bin")
+    }
+
+    #[tokio::test]
+    async fn downloads_and_verifies() {
+        let (base, _) = mock_server().await;
+        let dest = temp_dest();
+        let last_progress = Arc::new(Mutex::new((0, 0)));
+        let progress = last_progress.clone();
+
+        let options = DownloadOptions {
+            expected_size: Some(body().len() as u64),
+            sha1: Some(hex::encode(Sha1::digest(body()))),
+            progress: Some(Arc::new(move |done, total| *progress.lock().unwrap() = (done, total))),
+            ..Default::default()
+        };
+        let size = download(&format!("{}/file", base), &dest, options).await.unwrap();
+
+        assert_eq!(size, body().len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), body());
+        assert_eq!(*last_progress.lock().unwrap(), (size, size));
+        assert!(!part_path(&dest).exists());
+        std::fs::remove_dir_all(dest.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn resumes_after_disconnect() {
+        let (base, log) = mock_server().await;
+        let dest = temp_dest();
+
+        download(&format!("{}/flaky", base), &dest, DownloadOptions::default()).await.unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body());
+        let requests = log.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].1, None);
+        assert_eq!(requests[1].1, Some(body().len() / 2));
+        std::fs::remove_dir_all(dest.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn hash_mismatch_leaves_nothing_behind() {
+        let (base, _) = mock_server().await;
+        let dest = temp_dest();
+
+        let options = DownloadOptions { sha1: Some("0".repeat(40)), ..Default::default() };
+        let err = download(&format!("{}/file", base), &dest, options).await.unwrap_err();
+
+        assert!(matches!(err, DownloadError::HashMismatch { .. }));
+        assert!(!dest.exists());
+        assert!(!part_path(&dest).exists());
+        std::fs::remove_dir_all(dest.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_a_cap() {
+        assert_eq!(retry_delay(1), Duration::from_millis(500));
+        assert_eq!(retry_delay(2), Duration::from_secs(1));
+        assert_eq!(retry_delay(3), Duration::from_secs(2));
+        assert_eq!(retry_delay(10), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(u32::MAX), RETRY_MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn not_found_is_not_retried() {
+        let (base, log) = mock_server().await;
+        let dest = temp_dest();
+
+        let err = download(&format!("{}/missing", base), &dest, DownloadOptions::default()).await.unwrap_err();
+
+        assert!(matches!(err, DownloadError::Status { status, .. } if status == reqwest::StatusCode::NOT_FOUND));
+        assert_eq!(log.lock().unwrap().len(), 1);
+        assert!(!dest.exists());
+        std::fs::remove_dir_all(dest.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_before_start() {
+        let (base, log) = mock_server().await;
+        let dest = temp_dest();
+        let (_sender, cancel) = watch::channel(true);
+
+        let options = DownloadOptions { cancel: Some(cancel), ..Default::default() };
+        let err = download(&format!("{}/file", base), &dest, options).await.unwrap_err();
+
+        assert!(matches!(err, DownloadError::Cancelled));
+        assert!(log.lock().unwrap().is_empty());
+        std::fs::remove_dir_all(dest.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn existing_file_counts_only_with_matching_hash() {
+        let dest = temp_dest();
+        let sha1 = hex::encode(Sha1::digest(body()));
+        assert!(!is_downloaded(&dest, None, &sha1).await);
+
+        std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        std::fs::write(&dest, body()).unwrap();
+        assert!(is_downloaded(&dest, Some(body().len() as u64), &sha1).await);
+        assert!(!is_downloaded(&dest, Some(1), &sha1).await);
+        assert!(!is_downloaded(&dest, None, &"0".repeat(40)).await);
+        assert!(!is_downloaded(&dest, None, "").await);
+        std::fs::remove_dir_all(dest.parent().unwrap()).unwrap();
+    }
+}
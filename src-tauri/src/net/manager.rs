@@ -0,0 +1,504 @@
+//! One queue for the launcher's large downloads
+//!
+//! Java runtimes, modpack files and mods download through `DownloadManager`, so
+//! together they never run more than `max_concurrent_downloads` transfers at
+//! once. When a slot frees up the waiting download with the highest priority
+//! starts, the one queued first among equals. Every download reports on the
+//! `downloads_progress` event and can be listed and cancelled by its task id.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter, State};
+use tokio::sync::{oneshot, watch};
+
+use super::cache::{self, CachedDownload};
+use super::download::{self, DownloadError, DownloadOptions};
+
+pub type DownloadManagerState = Arc<DownloadManager>;
+
+/// Transfers at once when the settings can't be read, the settings default
+const DEFAULT_CONCURRENCY: u32 = 16;
+
+/// Shortest gap between two progress events of one download
+const EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+static MANAGER: OnceLock<Arc<DownloadManager>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadKind {
+    Java,
+    Modpack,
+    Mod,
+}
+
+/// Which waiting download starts first when a slot frees up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadPriority {
+    /// Bulk files like a modpack's mods
+    Low,
+    Normal,
+    /// Downloads a launch is waiting on
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadStatus {
+    Queued,
+    Running,
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+/// A download as sent with `downloads_progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub task_id: String,
+    pub kind: DownloadKind,
+    pub priority: DownloadPriority,
+    /// File name of the destination
+    pub file: String,
+    pub status: DownloadStatus,
+    pub bytes: u64,
+    /// 0 while the size is unknown
+    pub total: u64,
+    /// Bytes per second since the transfer started
+    pub speed: u64,
+}
+
+struct ActiveDownload {
+    progress: DownloadProgress,
+    cancel: watch::Sender<bool>,
+    queued_at: Instant,
+    started: Option<Instant>,
+    last_emit: Option<Instant>,
+}
+
+/// A download waiting for a slot
+struct Waiter {
+    priority: DownloadPriority,
+    order: Reverse<u64>,
+    start: oneshot::Sender<()>,
+}
+
+impl Waiter {
+    fn key(&self) -> (DownloadPriority, Reverse<u64>) {
+        (self.priority, self.order)
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[derive(Default)]
+struct Slots {
+    running: usize,
+    queued: u64,
+    waiting: BinaryHeap<Waiter>,
+}
+
+/// Held while a transfer runs, hands the slot on when dropped
+struct Slot {
+    manager: Arc<DownloadManager>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.manager.release();
+    }
+}
+
+/// Keeps a download listed while `run` goes on, unlists it however `run` ends
+struct Listing {
+    manager: Arc<DownloadManager>,
+    task_id: String,
+    forward: Option<tauri::async_runtime::JoinHandle<()>>,
+    /// What the download ended as, Cancelled when `run` is dropped
+    status: DownloadStatus,
+}
+
+impl Drop for Listing {
+    fn drop(&mut self) {
+        if let Some(forward) = self.forward.take() {
+            forward.abort();
+        }
+        let download = self.manager.active.lock().unwrap().remove(&self.task_id);
+        if let Some(mut download) = download {
+            download.progress.status = self.status;
+            self.manager.send(&download.progress);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DownloadManager {
+    slots: Mutex<Slots>,
+    active: Mutex<HashMap<String, ActiveDownload>>,
+    app_handle: OnceLock<AppHandle>,
+}
+
+/// The manager every download goes through
+pub fn shared() -> Arc<DownloadManager> {
+    MANAGER.get_or_init(Default::default).clone()
+}
+
+async fn concurrency_limit() -> usize {
+    let limit = match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().max_concurrent_downloads,
+        Err(_) => DEFAULT_CONCURRENCY,
+    };
+    limit.max(1) as usize
+}
+
+/// Resolves once `cancel` turns true, never when its sender is gone
+async fn cancelled(cancel: &mut watch::Receiver<bool>) {
+    if cancel.wait_for(|cancelled| *cancelled).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+impl DownloadManager {
+    /// Send progress events to the UI from now on
+    pub fn attach(&self, app_handle: AppHandle) {
+        let _ = self.app_handle.set(app_handle);
+    }
+
+    /// Download `url` to `dest` once a slot is free, see `download::download`
+    pub async fn download(
+        self: &Arc<Self>,
+        kind: DownloadKind,
+        priority: DownloadPriority,
+        url: &str,
+        dest: &Path,
+        options: DownloadOptions,
+    ) -> Result<u64, DownloadError> {
+        self.run(kind, priority, dest, options, |options| download::download(url, dest, options)).await
+    }
+
+    /// Like `download`, served from the download cache when it holds the file
+    pub async fn fetch_cached(
+        self: &Arc<Self>,
+        kind: DownloadKind,
+        priority: DownloadPriority,
+        url: &str,
+        dest: &Path,
+        options: DownloadOptions,
+    ) -> Result<CachedDownload, DownloadError> {
+        self.run(kind, priority, dest, options, |options| cache::fetch_cached(url, dest, options)).await
+    }
+
+    /// Downloads that are queued or running, oldest first
+    pub fn active(&self) -> Vec<DownloadProgress> {
+        let active = self.active.lock().unwrap();
+        let mut downloads: Vec<&ActiveDownload> = active.values().collect();
+        downloads.sort_by_key(|download| download.queued_at);
+        downloads.into_iter().map(|download| download.progress.clone()).collect()
+    }
+
+    /// Stop a queued or running download, it fails with `DownloadError::Cancelled`
+    pub fn cancel(&self, task_id: &str) -> Result<(), String> {
+        let active = self.active.lock().unwrap();
+        let download = active.get(task_id)
+            .ok_or_else(|| format!("Download '{}' is not running", task_id))?;
+        download.cancel.send_replace(true);
+        Ok(())
+    }
+
+    async fn run<T, F, Fut>(
+        self: &Arc<Self>,
+        kind: DownloadKind,
+        priority: DownloadPriority,
+        dest: &Path,
+        options: DownloadOptions,
+        transfer: F,
+    ) -> Result<T, DownloadError>
+    where
+        F: FnOnce(DownloadOptions) -> Fut,
+        Fut: Future<Output = Result<T, DownloadError>>,
+    {
+        let limit = concurrency_limit().await;
+        self.run_limited(limit, kind, priority, dest, options, transfer).await
+    }
+
+    /// `run` with at most `limit` transfers going at once
+    async fn run_limited<T, F, Fut>(
+        self: &Arc<Self>,
+        limit: usize,
+        kind: DownloadKind,
+        priority: DownloadPriority,
+        dest: &Path,
+        mut options: DownloadOptions,
+        transfer: F,
+    ) -> Result<T, DownloadError>
+    where
+        F: FnOnce(DownloadOptions) -> Fut,
+        Fut: Future<Output = Result<T, DownloadError>>,
+    {
+        let task_id = uuid::Uuid::new_v4().to_string();
+        let (cancel_sender, cancel) = watch::channel(false);
+        self.active.lock().unwrap().insert(task_id.clone(), ActiveDownload {
+            progress: DownloadProgress {
+                task_id: task_id.clone(),
+                kind,
+                priority,
+                file: dest.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                status: DownloadStatus::Queued,
+                bytes: 0,
+                total: options.expected_size.unwrap_or(0),
+                speed: 0,
+            },
+            cancel: cancel_sender,
+            queued_at: Instant::now(),
+            started: None,
+            last_emit: None,
+        });
+        let mut listing = Listing {
+            manager: self.clone(),
+            task_id: task_id.clone(),
+            forward: None,
+            status: DownloadStatus::Cancelled,
+        };
+        self.emit(&task_id);
+
+        // The caller's cancellation stops the download like cancel_download does
+        listing.forward = options.cancel.take().map(|mut outer| {
+            let manager = self.clone();
+            let task_id = task_id.clone();
+            tauri::async_runtime::spawn(async move {
+                cancelled(&mut outer).await;
+                let _ = manager.cancel(&task_id);
+            })
+        });
+
+        let result = match self.acquire(priority, limit, cancel.clone()).await {
+            Some(slot) => {
+                self.update(&task_id, |download| {
+                    download.progress.status = DownloadStatus::Running;
+                    download.started = Some(Instant::now());
+                });
+                self.emit(&task_id);
+
+                let caller = options.progress.take();
+                let manager = self.clone();
+                let id = task_id.clone();
+                options.progress = Some(Arc::new(move |bytes, total| {
+                    if let Some(caller) = &caller {
+                        caller(bytes, total);
+                    }
+                    manager.report(&id, bytes, total);
+                }));
+                options.cancel = Some(cancel);
+                let result = transfer(options).await;
+                drop(slot);
+                result
+            }
+            None => Err(DownloadError::Cancelled),
+        };
+
+        listing.status = match &result {
+            Ok(_) => DownloadStatus::Finished,
+            Err(DownloadError::Cancelled) => DownloadStatus::Cancelled,
+            Err(_) => DownloadStatus::Failed,
+        };
+        drop(listing);
+        result
+    }
+
+    /// Wait for one of `limit` slots, None when cancelled first
+    async fn acquire(self: &Arc<Self>, priority: DownloadPriority, limit: usize, mut cancel: watch::Receiver<bool>) -> Option<Slot> {
+        let mut start = {
+            let mut slots = self.slots.lock().unwrap();
+            if slots.running < limit {
+                slots.running += 1;
+                return Some(Slot { manager: self.clone() });
+            }
+            let (sender, start) = oneshot::channel();
+            slots.queued += 1;
+            let order = Reverse(slots.queued);
+            slots.waiting.push(Waiter { priority, order, start: sender });
+            start
+        };
+
+        tokio::select! {
+            // The finishing download hands its slot over, running stays counted
+            handed_over = &mut start => handed_over.ok().map(|_| Slot { manager: self.clone() }),
+            _ = cancelled(&mut cancel) => {
+                start.close();
+                // Handed over just as the cancellation came in
+                if start.try_recv().is_ok() {
+                    self.release();
+                }
+                None
+            }
+        }
+    }
+
+    fn release(&self) {
+        let mut slots = self.slots.lock().unwrap();
+        while let Some(waiter) = slots.waiting.pop() {
+            // Waiters that were cancelled have dropped their receiver
+            if waiter.start.send(()).is_ok() {
+                return;
+            }
+        }
+        slots.running = slots.running.saturating_sub(1);
+    }
+
+    fn report(&self, task_id: &str, bytes: u64, total: u64) {
+        let now = Instant::now();
+        let progress = self.update(task_id, |download| {
+            download.progress.bytes = bytes;
+            download.progress.total = total;
+            if let Some(started) = download.started {
+                let seconds = now.duration_since(started).as_secs_f64().max(0.001);
+                download.progress.speed = (bytes as f64 / seconds) as u64;
+            }
+            let due = download.last_emit.is_none_or(|last| now.duration_since(last) >= EMIT_INTERVAL);
+            if due || (total > 0 && bytes >= total) {
+                download.last_emit = Some(now);
+                Some(download.progress.clone())
+            } else {
+                None
+            }
+        });
+        if let Some(progress) = progress.flatten() {
+            self.send(&progress);
+        }
+    }
+
+    fn update<R>(&self, task_id: &str, f: impl FnOnce(&mut ActiveDownload) -> R) -> Option<R> {
+        self.active.lock().unwrap().get_mut(task_id).map(f)
+    }
+
+    fn emit(&self, task_id: &str) {
+        let progress = self.active.lock().unwrap().get(task_id).map(|download| download.progress.clone());
+        if let Some(progress) = progress {
+            self.send(&progress);
+        }
+    }
+
+    fn send(&self, progress: &DownloadProgress) {
+        if let Some(app_handle) = self.app_handle.get() {
+            let _ = app_handle.emit("downloads_progress", progress);
+        }
+    }
+}
+
+/// Downloads that are queued or running, oldest first
+#[command]
+pub async fn list_active_downloads(downloads: State<'_, DownloadManagerState>) -> Result<Vec<DownloadProgress>, String> {
+    Ok(downloads.active())
+}
+
+/// Stop a queued or running download
+#[command]
+pub async fn cancel_download(task_id: String, downloads: State<'_, DownloadManagerState>) -> Result<(), String> {
+    downloads.cancel(&task_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `future` completes without waiting
+    async fn ready<F: Future>(future: F) -> Option<F::Output> {
+        tokio::time::timeout(Duration::from_millis(20), future).await.ok()
+    }
+
+    fn never_cancelled() -> watch::Receiver<bool> {
+        watch::channel(false).1
+    }
+
+    #[tokio::test]
+    async fn freed_slots_go_to_the_highest_priority_first() {
+        let manager = Arc::new(DownloadManager::default());
+        let first = manager.acquire(DownloadPriority::Normal, 1, never_cancelled()).await.unwrap();
+
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let mut waiting = Vec::new();
+        for (name, priority) in [("low", DownloadPriority::Low), ("normal", DownloadPriority::Normal), ("high", DownloadPriority::High)] {
+            let manager = manager.clone();
+            let started = started.clone();
+            waiting.push(tokio::spawn(async move {
+                let _slot = manager.acquire(priority, 1, never_cancelled()).await.unwrap();
+                started.lock().unwrap().push(name);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }));
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        drop(first);
+        for task in waiting {
+            task.await.unwrap();
+        }
+        assert_eq!(*started.lock().unwrap(), ["high", "normal", "low"]);
+        assert_eq!(manager.slots.lock().unwrap().running, 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_waiters_give_up_their_place() {
+        let manager = Arc::new(DownloadManager::default());
+        let first = manager.acquire(DownloadPriority::Normal, 1, never_cancelled()).await.unwrap();
+
+        let (cancel, receiver) = watch::channel(false);
+        let waiting = tokio::spawn({
+            let manager = manager.clone();
+            async move { manager.acquire(DownloadPriority::High, 1, receiver).await.is_some() }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        cancel.send_replace(true);
+        assert!(!waiting.await.unwrap());
+
+        drop(first);
+        let next = ready(manager.acquire(DownloadPriority::Low, 1, never_cancelled())).await;
+        assert!(next.flatten().is_some(), "the slot of the cancelled waiter was lost");
+    }
+
+    #[tokio::test]
+    async fn dropped_downloads_leave_the_list() {
+        let manager = Arc::new(DownloadManager::default());
+        let _first = manager.acquire(DownloadPriority::Normal, 1, never_cancelled()).await.unwrap();
+
+        let (cancel, outer) = watch::channel(false);
+        let options = DownloadOptions { cancel: Some(outer), ..Default::default() };
+        let queued = manager.run_limited(1, DownloadKind::Mod, DownloadPriority::Normal, Path::new("queued.jar"), options, |_| async { Ok(()) });
+        assert!(ready(queued).await.is_none(), "the download started past the limit");
+
+        assert!(manager.active().is_empty());
+        assert!(ready(cancel.closed()).await.is_some(), "the cancellation forwarder kept running");
+    }
+
+    #[tokio::test]
+    async fn the_limit_caps_running_downloads() {
+        let manager = Arc::new(DownloadManager::default());
+        let _first = manager.acquire(DownloadPriority::Normal, 2, never_cancelled()).await.unwrap();
+        let _second = manager.acquire(DownloadPriority::Normal, 2, never_cancelled()).await.unwrap();
+        assert!(ready(manager.acquire(DownloadPriority::High, 2, never_cancelled())).await.is_none());
+        assert_eq!(manager.slots.lock().unwrap().running, 2);
+    }
+}
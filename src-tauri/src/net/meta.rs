@@ -0,0 +1,249 @@
+//! On-disk cache of launcher metadata in `cache/meta` under the launcher directory
+//!
+//! The version manifest is kept with the ETag and Last-Modified it was served
+//! with, so a stale copy is revalidated with a conditional request and the last
+//! copy is still listed when the network is down. Version JSONs are kept by id and
+//! SHA1, so a second instance of a version copies the JSON instead of fetching it.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::fs;
+
+use super::download::{self, DownloadError, DownloadOptions};
+use super::mirror::{self, DownloadMirror};
+
+/// How long a cached document is served without asking the server
+pub const MAX_AGE: Duration = Duration::from_secs(30 * 60);
+
+/// Validators and fetch time stored beside a cached document
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Validators {
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+    /// Unix seconds of the last time the server confirmed the copy
+    fetched_at: u64,
+}
+
+/// A document and the age of the copy it was read from
+#[derive(Debug, Clone)]
+pub struct Cached<T> {
+    pub value: T,
+    /// 0 when the server was just asked
+    pub age_secs: u64,
+}
+
+pub fn meta_dir() -> PathBuf {
+    crate::storage::get_launcher_dir().join("cache").join("meta")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Age of a copy fetched at `fetched_at`, None when the clock went back since
+fn age(fetched_at: u64, now: u64) -> Option<u64> {
+    now.checked_sub(fetched_at)
+}
+
+fn is_fresh(fetched_at: u64, now: u64, max_age: Duration) -> bool {
+    age(fetched_at, now).is_some_and(|age| age < max_age.as_secs())
+}
+
+fn validators_path(body: &Path) -> PathBuf {
+    let mut name = body.file_name().unwrap_or_default().to_os_string();
+    name.push(".meta");
+    body.with_file_name(name)
+}
+
+async fn read_cached<T: DeserializeOwned>(body: &Path) -> Option<(T, Validators)> {
+    let value = serde_json::from_str(&fs::read_to_string(body).await.ok()?).ok()?;
+    let validators = fs::read_to_string(validators_path(body)).await.ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    Some((value, validators))
+}
+
+/// Write through a temporary file so readers never see half a document
+async fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut temp = path.as_os_str().to_os_string();
+    temp.push(".tmp");
+    fs::write(&temp, contents).await?;
+    fs::rename(&temp, path).await
+}
+
+async fn write_validators(body: &Path, validators: &Validators) {
+    let json = serde_json::to_vec_pretty(validators).unwrap_or_default();
+    if let Err(e) = write_atomic(&validators_path(body), &json).await {
+        println!("⚠️  Failed to update {}: {}", validators_path(body).display(), e);
+    }
+}
+
+enum Revalidated<T> {
+    NotModified,
+    Modified { value: T, body: String, validators: Validators },
+}
+
+fn header(response: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from)
+}
+
+async fn revalidate<T: DeserializeOwned>(
+    url: &str,
+    mirror: &DownloadMirror,
+    cached: Option<&Validators>,
+) -> Result<Revalidated<T>, String> {
+    let client = crate::http::client();
+    let response = mirror::send(mirror, url, |url| {
+        let mut request = client.get(url);
+        if let Some(etag) = cached.and_then(|v| v.etag.as_deref()) {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = cached.and_then(|v| v.last_modified.as_deref()) {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+        }
+        request
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Revalidated::NotModified);
+    }
+    let response = response.error_for_status().map_err(|e| e.to_string())?;
+    let validators = Validators {
+        etag: header(&response, reqwest::header::ETAG),
+        last_modified: header(&response, reqwest::header::LAST_MODIFIED),
+        fetched_at: unix_now(),
+    };
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    let value = serde_json::from_str(&body)
+        .map_err(|e| format!("the response is not valid JSON ({}), the API may have changed", e))?;
+    Ok(Revalidated::Modified { value, body, validators })
+}
+
+/// The JSON document at `url`, cached in `file_name` under the meta directory
+///
+/// A copy younger than `MAX_AGE` is served as is unless `force_refresh` is set.
+/// Older copies are revalidated, and served with their age when the server can't
+/// be reached.
+pub async fn fetch_json<T: DeserializeOwned>(url: &str, file_name: &str, force_refresh: bool) -> Result<Cached<T>, String> {
+    let path = meta_dir().join(file_name);
+    let now = unix_now();
+    let cached = match read_cached::<T>(&path).await {
+        Some((value, validators)) if !force_refresh && is_fresh(validators.fetched_at, now, MAX_AGE) => {
+            return Ok(Cached { value, age_secs: now - validators.fetched_at });
+        }
+        cached => cached,
+    };
+
+    let mirror = mirror::current().await;
+    match (revalidate::<T>(url, &mirror, cached.as_ref().map(|(_, v)| v)).await, cached) {
+        (Ok(Revalidated::Modified { value, body, validators }), _) => {
+            if let Err(e) = write_atomic(&path, body.as_bytes()).await {
+                println!("⚠️  Failed to cache {}: {}", url, e);
+            } else {
+                write_validators(&path, &validators).await;
+            }
+            Ok(Cached { value, age_secs: 0 })
+        }
+        (Ok(Revalidated::NotModified), Some((value, validators))) => {
+            write_validators(&path, &Validators { fetched_at: now, ..validators }).await;
+            Ok(Cached { value, age_secs: 0 })
+        }
+        (Ok(Revalidated::NotModified), None) => Err(format!("{} answered 304 without a cached copy", url)),
+        (Err(e), Some((value, validators))) => {
+            let age_secs = age(validators.fetched_at, now).unwrap_or(0);
+            println!("⚠️  Failed to fetch {} ({}), using the copy from {} minutes ago", url, e, age_secs / 60);
+            Ok(Cached { value, age_secs })
+        }
+        (Err(e), None) => Err(format!("Failed to fetch {}: {}", url, e)),
+    }
+}
+
+/// Where the version JSON with `sha1` is cached, None for ids or hashes unfit for a file name
+pub fn version_json_path(version_id: &str, sha1: &str) -> Option<PathBuf> {
+    let safe_id = !version_id.is_empty()
+        && !version_id.contains(['/', '\\', ':'])
+        && !version_id.starts_with('.');
+    if !safe_id || !super::cache::is_sha1(sha1) {
+        return None;
+    }
+    Some(meta_dir().join("versions").join(format!("{}-{}.json", version_id, sha1.to_lowercase())))
+}
+
+/// Put the version JSON in `dest`, copied from the meta cache when it holds it
+pub async fn fetch_version_json(
+    mirror: &DownloadMirror,
+    version_id: &str,
+    url: &str,
+    sha1: Option<&str>,
+    dest: &Path,
+) -> Result<(), DownloadError> {
+    let options = DownloadOptions { sha1: sha1.map(String::from), ..Default::default() };
+    let Some(cached) = sha1.and_then(|sha1| version_json_path(version_id, sha1)) else {
+        return mirror::download(mirror, url, dest, options).await.map(|_| ());
+    };
+
+    if download::is_downloaded(&cached, None, sha1.unwrap_or_default()).await {
+        println!("📋 Using cached version JSON for {}", version_id);
+    } else {
+        mirror::download(mirror, url, &cached, options).await?;
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::copy(&cached, dest).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_are_fresh_for_thirty_minutes() {
+        let fetched_at = 1_700_000_000;
+        assert!(is_fresh(fetched_at, fetched_at, MAX_AGE));
+        assert!(is_fresh(fetched_at, fetched_at + 29 * 60, MAX_AGE));
+        assert!(!is_fresh(fetched_at, fetched_at + 30 * 60, MAX_AGE));
+        // A clock set back makes the age unknown, so the copy is revalidated
+        assert!(!is_fresh(fetched_at, fetched_at - 60, MAX_AGE));
+    }
+
+    #[test]
+    fn version_jsons_are_keyed_by_id_and_sha1() {
+        let sha1 = "A".repeat(40);
+        let path = version_json_path("1.20.4", &sha1).unwrap();
+        assert!(path.ends_with(format!("versions/1.20.4-{}.json", "a".repeat(40))));
+
+        assert!(version_json_path("1.20.4", "not-a-hash").is_none());
+        assert!(version_json_path("../1.20.4", &sha1).is_none());
+        assert!(version_json_path("..", &sha1).is_none());
+    }
+
+    #[tokio::test]
+    async fn validators_sit_beside_the_document() {
+        let dir = std::env::temp_dir().join(format!("chai-meta-{}", uuid::Uuid::new_v4()));
+        let body = dir.join("version_manifest_v2.json");
+        write_atomic(&body, br#"{"latest":{}}"#).await.unwrap();
+        write_validators(&body, &Validators { etag: Some("\"abc\"".to_string()), last_modified: None, fetched_at: 42 }).await;
+
+        let (value, validators) = read_cached::<serde_json::Value>(&body).await.unwrap();
+        assert_eq!(value, serde_json::json!({ "latest": {} }));
+        assert_eq!(validators.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(validators.fetched_at, 42);
+        assert!(dir.join("version_manifest_v2.json.meta").exists());
+
+        // A body that is no longer JSON is treated as not cached
+        std::fs::write(&body, "{").unwrap();
+        assert!(read_cached::<serde_json::Value>(&body).await.is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+//! Download mirrors for Mojang, Forge and Fabric endpoints
+//!
+//! Official hosts are slow or unreachable in some regions. With a mirror set, URLs
+//! on a known host are rewritten to the mirror's base URL for that service before
+//! downloading, and the official URL is tried when the mirror answers 404.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::download::{self, DownloadError, DownloadOptions};
+
+/// BMCLAPI, which mirrors every service under one host
+const BMCLAPI: &str = "https://bmclapi2.bangbang93.com";
+
+/// The groups of endpoints a mirror can replace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorService {
+    /// Version manifest, version JSONs and client jars
+    VersionMeta,
+    Libraries,
+    Assets,
+    Forge,
+    FabricMeta,
+    FabricMaven,
+}
+
+/// Official base URLs and the service each belongs to
+const OFFICIAL_BASES: &[(&str, MirrorService)] = &[
+    ("https://piston-meta.mojang.com", MirrorService::VersionMeta),
+    ("https://launchermeta.mojang.com", MirrorService::VersionMeta),
+    ("https://piston-data.mojang.com", MirrorService::VersionMeta),
+    ("https://launcher.mojang.com", MirrorService::VersionMeta),
+    ("https://libraries.minecraft.net", MirrorService::Libraries),
+    ("https://resources.download.minecraft.net", MirrorService::Assets),
+    ("https://maven.minecraftforge.net", MirrorService::Forge),
+    ("https://files.minecraftforge.net/maven", MirrorService::Forge),
+    ("https://meta.fabricmc.net", MirrorService::FabricMeta),
+    ("https://maven.fabricmc.net", MirrorService::FabricMaven),
+];
+
+/// Base URLs replacing the official ones, unset services stay official
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MirrorUrls {
+    #[serde(default)]
+    pub version_meta: Option<String>,
+    #[serde(default)]
+    pub libraries: Option<String>,
+    #[serde(default)]
+    pub assets: Option<String>,
+    #[serde(default)]
+    pub forge: Option<String>,
+    #[serde(default)]
+    pub fabric_meta: Option<String>,
+    #[serde(default)]
+    pub fabric_maven: Option<String>,
+}
+
+/// Where game files are downloaded from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DownloadMirror {
+    #[default]
+    None,
+    Bmclapi,
+    Custom(MirrorUrls),
+}
+
+impl DownloadMirror {
+    /// Base URL standing in for `service`, None to use the official one
+    fn base(&self, service: MirrorService) -> Option<String> {
+        match self {
+            DownloadMirror::None => None,
+            DownloadMirror::Bmclapi => Some(match service {
+                MirrorService::VersionMeta => BMCLAPI.to_string(),
+                MirrorService::Libraries | MirrorService::Forge | MirrorService::FabricMaven => format!("{}/maven", BMCLAPI),
+                MirrorService::Assets => format!("{}/assets", BMCLAPI),
+                MirrorService::FabricMeta => format!("{}/fabric-meta", BMCLAPI),
+            }),
+            DownloadMirror::Custom(urls) => match service {
+                MirrorService::VersionMeta => urls.version_meta.clone(),
+                MirrorService::Libraries => urls.libraries.clone(),
+                MirrorService::Assets => urls.assets.clone(),
+                MirrorService::Forge => urls.forge.clone(),
+                MirrorService::FabricMeta => urls.fabric_meta.clone(),
+                MirrorService::FabricMaven => urls.fabric_maven.clone(),
+            }
+            .filter(|base| !base.trim().is_empty()),
+        }
+    }
+}
+
+/// The mirror from the launcher settings, none when they can't be read
+pub async fn current() -> DownloadMirror {
+    match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().download_mirror.clone(),
+        Err(_) => DownloadMirror::None,
+    }
+}
+
+/// `url` on `mirror`, None when the mirror doesn't cover it
+pub fn rewrite(url: &str, mirror: &DownloadMirror) -> Option<String> {
+    OFFICIAL_BASES.iter().find_map(|(official, service)| {
+        let path = url.strip_prefix(official)?;
+        if !(path.is_empty() || path.starts_with('/') || path.starts_with('?')) {
+            return None;
+        }
+        let base = mirror.base(*service)?;
+        Some(format!("{}{}", base.trim().trim_end_matches('/'), path))
+    })
+}
+
+/// `download` from the mirror, falling back to `url` when the mirror doesn't have the file
+pub async fn download(mirror: &DownloadMirror, url: &str, dest: &Path, options: DownloadOptions) -> Result<u64, DownloadError> {
+    let Some(mirrored) = rewrite(url, mirror) else {
+        return download::download(url, dest, options).await;
+    };
+    match download::download(&mirrored, dest, options.clone()).await {
+        Err(DownloadError::Status { status: reqwest::StatusCode::NOT_FOUND, .. }) => {
+            println!("⚠️  {} is not on the mirror, downloading {}", mirrored, url);
+            download::download(url, dest, options).await
+        }
+        result => result,
+    }
+}
+
+/// Send the request `build` makes for the mirrored URL, or for `url` when there is none or it answers 404
+pub async fn send(
+    mirror: &DownloadMirror,
+    url: &str,
+    build: impl Fn(&str) -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response, reqwest::Error> {
+    if let Some(mirrored) = rewrite(url, mirror) {
+        let response = build(&mirrored).send().await?;
+        if response.status() != reqwest::StatusCode::NOT_FOUND {
+            return Ok(response);
+        }
+        println!("⚠️  {} is not on the mirror, requesting {}", mirrored, url);
+    }
+    build(url).send().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(urls: MirrorUrls) -> DownloadMirror {
+        DownloadMirror::Custom(urls)
+    }
+
+    #[test]
+    fn bmclapi_rewrites_every_endpoint_type() {
+        let mirror = DownloadMirror::Bmclapi;
+        let cases = [
+            ("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json", "https://bmclapi2.bangbang93.com/mc/game/version_manifest_v2.json"),
+            ("https://piston-meta.mojang.com/v1/packages/abc/1.20.4.json", "https://bmclapi2.bangbang93.com/v1/packages/abc/1.20.4.json"),
+            ("https://piston-data.mojang.com/v1/objects/fd19/client.jar", "https://bmclapi2.bangbang93.com/v1/objects/fd19/client.jar"),
+            ("https://launcher.mojang.com/v1/objects/37fd/client.jar", "https://bmclapi2.bangbang93.com/v1/objects/37fd/client.jar"),
+            ("https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar", "https://bmclapi2.bangbang93.com/maven/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar"),
+            ("https://resources.download.minecraft.net/ab/abcdef", "https://bmclapi2.bangbang93.com/assets/ab/abcdef"),
+            ("https://maven.minecraftforge.net/net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-installer.jar", "https://bmclapi2.bangbang93.com/maven/net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-installer.jar"),
+            ("https://files.minecraftforge.net/maven/net/minecraftforge/forge/x.jar", "https://bmclapi2.bangbang93.com/maven/net/minecraftforge/forge/x.jar"),
+            ("https://meta.fabricmc.net/v2/versions/loader/1.20.1", "https://bmclapi2.bangbang93.com/fabric-meta/v2/versions/loader/1.20.1"),
+            ("https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.15.0/fabric-loader-0.15.0.jar", "https://bmclapi2.bangbang93.com/maven/net/fabricmc/fabric-loader/0.15.0/fabric-loader-0.15.0.jar"),
+        ];
+        for (official, mirrored) in cases {
+            assert_eq!(rewrite(official, &mirror).as_deref(), Some(mirrored), "{}", official);
+        }
+    }
+
+    #[test]
+    fn leaves_other_urls_and_unset_services_alone() {
+        assert_eq!(rewrite("https://libraries.minecraft.net/a/b.jar", &DownloadMirror::None), None);
+        assert_eq!(rewrite("https://repo1.maven.org/maven2/a/b.jar", &DownloadMirror::Bmclapi), None);
+        // A host that merely starts like an official one
+        assert_eq!(rewrite("https://libraries.minecraft.network/a.jar", &DownloadMirror::Bmclapi), None);
+        // Forge's promotions are not under its maven
+        assert_eq!(rewrite("https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json", &DownloadMirror::Bmclapi), None);
+
+        let mirror = custom(MirrorUrls { assets: Some("https://assets.example.com/".to_string()), libraries: Some(" ".to_string()), ..Default::default() });
+        assert_eq!(rewrite("https://resources.download.minecraft.net/ab/abcdef", &mirror).as_deref(), Some("https://assets.example.com/ab/abcdef"));
+        assert_eq!(rewrite("https://libraries.minecraft.net/a/b.jar", &mirror), None);
+        assert_eq!(rewrite("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json", &mirror), None);
+    }
+
+    #[test]
+    fn custom_urls_apply_per_service() {
+        let mirror = custom(MirrorUrls {
+            version_meta: Some("https://meta.example.com".to_string()),
+            libraries: Some("https://maven.example.com/libs".to_string()),
+            forge: Some("https://maven.example.com/forge".to_string()),
+            fabric_meta: Some("https://fabric.example.com/meta".to_string()),
+            fabric_maven: Some("https://maven.example.com/fabric".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(rewrite("https://launchermeta.mojang.com/v1/packages/x/1.8.json", &mirror).as_deref(), Some("https://meta.example.com/v1/packages/x/1.8.json"));
+        assert_eq!(rewrite("https://libraries.minecraft.net/a/b.jar", &mirror).as_deref(), Some("https://maven.example.com/libs/a/b.jar"));
+        assert_eq!(rewrite("https://maven.minecraftforge.net/a/b.jar", &mirror).as_deref(), Some("https://maven.example.com/forge/a/b.jar"));
+        assert_eq!(rewrite("https://meta.fabricmc.net/v2/versions", &mirror).as_deref(), Some("https://fabric.example.com/meta/v2/versions"));
+        assert_eq!(rewrite("https://maven.fabricmc.net/a/b.jar", &mirror).as_deref(), Some("https://maven.example.com/fabric/a/b.jar"));
+    }
+
+    #[test]
+    fn settings_value_is_tagged_by_kind() {
+        assert_eq!(serde_json::to_value(DownloadMirror::Bmclapi).unwrap(), serde_json::json!({ "kind": "bmclapi" }));
+        let custom: DownloadMirror = serde_json::from_value(serde_json::json!({ "kind": "custom", "assets": "https://a.example.com" })).unwrap();
+        assert_eq!(custom, DownloadMirror::Custom(MirrorUrls { assets: Some("https://a.example.com".to_string()), ..Default::default() }));
+    }
+}
@@ -0,0 +1,19 @@
+//! Shared HTTP helpers
+//!
+//! Every file the launcher downloads goes through `download`, which handles
+//! retries, resuming, verification and cancellation in one place. Files with a
+//! known SHA1 can go through `download_cached` to share them across instances.
+//! Game files from Mojang, Forge and Fabric go through `mirror::download`, which
+//! applies the download mirror from the settings. Metadata such as the version
+//! manifest is cached on disk by `meta`. Java runtimes, modpack files and mods
+//! share the concurrency limit of `manager::DownloadManager`.
+
+pub mod cache;
+pub mod download;
+pub mod manager;
+pub mod meta;
+pub mod mirror;
+
+pub use cache::download_cached;
+pub use download::{download, is_downloaded, DownloadOptions};
+pub use mirror::DownloadMirror;
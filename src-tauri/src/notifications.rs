@@ -0,0 +1,207 @@
+//! OS notifications for work that finishes while the launcher is in the background
+//!
+//! Every notification is also emitted as `notification_shown`, so the frontend
+//! can open the notification's target when the window is focused again.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::storage::NotificationCategories;
+use crate::tasks::{TaskInfo, TaskStatus};
+
+/// Work that finishes faster than this is noticed without a notification
+pub const NOTIFY_AFTER: Duration = Duration::from_secs(20);
+
+const MAX_BODY_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    Installs,
+    Servers,
+    Updates,
+}
+
+impl NotificationCategory {
+    fn enabled_in(self, categories: &NotificationCategories) -> bool {
+        match self {
+            NotificationCategory::Installs => categories.installs,
+            NotificationCategory::Servers => categories.servers,
+            NotificationCategory::Updates => categories.updates,
+        }
+    }
+}
+
+/// What the frontend should open for a notification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotificationTarget {
+    Instance { id: String },
+    Server { id: String },
+    Task { id: String },
+    /// A top-level view such as `instances` or `settings`
+    View { view: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LauncherNotification {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub target: Option<NotificationTarget>,
+}
+
+impl LauncherNotification {
+    pub fn new(category: NotificationCategory, title: &str, body: &str, target: Option<NotificationTarget>) -> Self {
+        Self {
+            category,
+            title: title.to_string(),
+            body: body.to_string(),
+            target,
+        }
+    }
+}
+
+/// Show `notification` if notifications and its category are enabled
+pub async fn send(app_handle: &AppHandle, mut notification: LauncherNotification) {
+    let Ok(storage) = crate::storage::shared().await else {
+        return;
+    };
+    {
+        let storage = storage.read().await;
+        let settings = storage.get_settings();
+        if !settings.notifications_enabled || !notification.category.enabled_in(&settings.notification_categories) {
+            return;
+        }
+    }
+
+    notification.body = sanitize(&notification.body);
+    if let Err(e) = app_handle.notification()
+        .builder()
+        .title(&notification.title)
+        .body(&notification.body)
+        .show()
+    {
+        println!("⚠️  Failed to show notification '{}': {}", notification.title, e);
+        return;
+    }
+    let _ = app_handle.emit("notification_shown", notification);
+}
+
+/// Like `send`, skipped when the work that began at `started` was quick
+pub async fn send_if_slow(app_handle: &AppHandle, started: Instant, notification: LauncherNotification) {
+    if started.elapsed() >= NOTIFY_AFTER {
+        send(app_handle, notification).await;
+    }
+}
+
+/// Notification for a finished background task, None for kinds nobody waits on
+pub(crate) fn for_task(task: &TaskInfo) -> Option<LauncherNotification> {
+    let failed = match task.status {
+        TaskStatus::Completed => false,
+        TaskStatus::Failed => true,
+        _ => return None,
+    };
+
+    let (category, title) = match (task.kind.as_str(), failed) {
+        ("install_mod", false) => (NotificationCategory::Installs, "Mod installed"),
+        ("install_mod", true) => (NotificationCategory::Installs, "Mod install failed"),
+        ("docker_deploy", false) => (NotificationCategory::Servers, "Server ready"),
+        ("docker_deploy", true) => (NotificationCategory::Servers, "Server deployment failed"),
+        ("docker_restart", false) => (NotificationCategory::Servers, "Server restarted"),
+        ("docker_restart", true) => (NotificationCategory::Servers, "Server restart failed"),
+        _ => return None,
+    };
+
+    let subject = task.subject.as_deref().unwrap_or(title);
+    let body = match (&task.error, failed) {
+        (Some(error), true) => format!("{}: {}", subject, error),
+        _ => subject.to_string(),
+    };
+    let target = task.target.clone().or_else(|| Some(NotificationTarget::Task { id: task.id.clone() }));
+
+    Some(LauncherNotification::new(category, title, &body, target))
+}
+
+/// Strip anything that shouldn't show up on screen outside the launcher
+///
+/// Paths are shortened to their file name and token-like strings are replaced,
+/// since error messages can contain both.
+fn sanitize(text: &str) -> String {
+    let words: Vec<String> = text.split_whitespace().map(sanitize_word).collect();
+    let mut body = words.join(" ");
+    if body.chars().count() > MAX_BODY_CHARS {
+        body = body.chars().take(MAX_BODY_CHARS - 1).collect::<String>() + "…";
+    }
+    body
+}
+
+fn sanitize_word(word: &str) -> String {
+    let trimmed = word.trim_matches(|c: char| matches!(c, '\'' | '"' | '(' | ')' | ',' | ':' | ';'));
+    if trimmed.is_empty() {
+        return word.to_string();
+    }
+
+    let lower = trimmed.to_lowercase();
+    let secret = lower.contains("token=") || lower.contains("key=") || lower.starts_with("bearer")
+        || (trimmed.len() >= 32 && trimmed.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '=' | '+')));
+    let replacement = if secret {
+        "[redacted]".to_string()
+    } else if trimmed.contains('/') || trimmed.contains('\\') {
+        let name = trimmed.trim_end_matches(['/', '\\']).rsplit(['/', '\\']).next().unwrap_or_default();
+        name.split(['?', '#']).next().unwrap_or_default().to_string()
+    } else {
+        return word.to_string();
+    };
+    word.replacen(trimmed, &replacement, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_hides_paths_and_tokens() {
+        assert_eq!(
+            sanitize("Failed to write C:\\Users\\alex\\AppData\\ChaiLauncher\\instances\\Pack\\mods\\a.jar: denied"),
+            "Failed to write a.jar: denied"
+        );
+        assert_eq!(sanitize("HTTP 401 from https://api.example.com/v1/files?token=abc"), "HTTP 401 from [redacted]");
+        assert_eq!(sanitize("Bad access token eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.eyJzdWIiOiIx"), "Bad access token [redacted]");
+        assert_eq!(sanitize("'/home/alex/.chailauncher/java/java17' is missing"), "'java17' is missing");
+        assert_eq!(sanitize("Server ready"), "Server ready");
+        assert_eq!(sanitize(&"word ".repeat(100)).chars().count(), MAX_BODY_CHARS);
+    }
+
+    #[test]
+    fn only_finished_known_tasks_notify() {
+        let mut task = TaskInfo {
+            id: "task-1".to_string(),
+            kind: "docker_deploy".to_string(),
+            queue_key: "server:survival".to_string(),
+            status: TaskStatus::Failed,
+            progress: 40,
+            message: None,
+            result: None,
+            error: Some("Image pull failed".to_string()),
+            created_at: String::new(),
+            subject: Some("Survival".to_string()),
+            target: Some(NotificationTarget::Server { id: "srv".to_string() }),
+        };
+
+        let notification = for_task(&task).unwrap();
+        assert_eq!(notification.category, NotificationCategory::Servers);
+        assert_eq!(notification.title, "Server deployment failed");
+        assert_eq!(notification.body, "Survival: Image pull failed");
+        assert_eq!(notification.target, Some(NotificationTarget::Server { id: "srv".to_string() }));
+
+        task.status = TaskStatus::Cancelled;
+        assert!(for_task(&task).is_none());
+
+        task.status = TaskStatus::Completed;
+        task.kind = "docker_stop".to_string();
+        assert!(for_task(&task).is_none());
+    }
+}
@@ -0,0 +1,188 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// File beside the executable that turns on portable mode
+pub const PORTABLE_MARKER: &str = "portable.txt";
+
+/// Command line flag that turns on portable mode
+pub const PORTABLE_FLAG: &str = "--portable";
+
+/// Folder beside the executable that holds portable data
+pub const PORTABLE_DATA_DIR: &str = "ChaiLauncherData";
+
+static DATA_LOCATIONS: OnceLock<DataLocations> = OnceLock::new();
+
+/// Root directories used for all launcher data in this session
+#[derive(Debug, Clone, Serialize)]
+pub struct DataLocations {
+    pub portable: bool,
+    pub executable_dir: Option<PathBuf>,
+    pub launcher_dir: PathBuf,
+    pub config_path: PathBuf,
+    pub instances_dir: PathBuf,
+    pub java_dir: PathBuf,
+    pub downloads_dir: PathBuf,
+}
+
+/// Result of copying profile data next to the executable
+#[derive(Debug, Clone, Serialize)]
+pub struct PortableConversionResult {
+    pub data_dir: String,
+    pub marker_path: String,
+    pub files_copied: u64,
+    pub bytes_copied: u64,
+    /// Instances stored outside the launcher directory, which were not copied
+    pub skipped_instances: Vec<String>,
+    /// The executable has to be started from the destination for portable mode to apply
+    pub restart_required: bool,
+}
+
+/// Data locations for this session, resolved on first use and fixed afterwards
+pub fn data_locations() -> &'static DataLocations {
+    DATA_LOCATIONS.get_or_init(resolve)
+}
+
+/// Launcher directory inside the OS profile, used when not portable
+pub fn profile_launcher_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ChaiLauncher")
+}
+
+fn resolve() -> DataLocations {
+    let executable_dir = std::env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+    let flag = std::env::args().any(|arg| arg == PORTABLE_FLAG);
+    let marker = executable_dir.as_ref()
+        .is_some_and(|dir| dir.join(PORTABLE_MARKER).exists());
+
+    let portable_dir = (flag || marker)
+        .then(|| executable_dir.as_ref().map(|dir| dir.join(PORTABLE_DATA_DIR)))
+        .flatten();
+
+    let locations = match portable_dir {
+        Some(launcher_dir) => {
+            println!("💼 Portable mode enabled, using data in {}", launcher_dir.display());
+            DataLocations::new(true, executable_dir, launcher_dir)
+        }
+        None => DataLocations::new(false, executable_dir, profile_launcher_dir()),
+    };
+
+    if (flag || marker) && !locations.portable {
+        eprintln!("⚠️  Portable mode requested but the executable location is unknown, using profile data");
+    }
+
+    locations
+}
+
+impl DataLocations {
+    fn new(portable: bool, executable_dir: Option<PathBuf>, launcher_dir: PathBuf) -> Self {
+        Self {
+            portable,
+            executable_dir,
+            config_path: launcher_dir.join("config.json"),
+            instances_dir: launcher_dir.join("instances"),
+            java_dir: launcher_dir.join("java"),
+            downloads_dir: launcher_dir.join("downloads"),
+            launcher_dir,
+        }
+    }
+}
+
+/// Copy the current launcher data beside `dest` and mark it as a portable install
+///
+/// `dest` is the folder the executable will run from. Nothing in the current data
+/// directory is changed, so the profile install keeps working.
+pub async fn convert_to_portable(dest: &Path) -> Result<PortableConversionResult, String> {
+    let locations = data_locations();
+    let data_dir = dest.join(PORTABLE_DATA_DIR);
+
+    if data_dir == locations.launcher_dir {
+        return Err("This launcher is already running from that portable location".to_string());
+    }
+    if data_dir.starts_with(&locations.launcher_dir) {
+        return Err("The destination can't be inside the current data directory".to_string());
+    }
+
+    tokio::fs::create_dir_all(dest).await
+        .map_err(|e| format!("Failed to create destination: {}", e))?;
+    check_write_access(dest).await?;
+
+    if has_entries(&data_dir).await {
+        return Err(format!("Destination already contains launcher data: {}", data_dir.display()));
+    }
+
+    let skipped_instances = match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_all_instances().into_iter()
+            .filter(|instance| !instance.game_dir.starts_with(&locations.launcher_dir))
+            .map(|instance| instance.name.clone())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    println!("💼 Copying launcher data from {} to {}", locations.launcher_dir.display(), data_dir.display());
+
+    let source = locations.launcher_dir.clone();
+    let target = data_dir.clone();
+    let (files_copied, bytes_copied) = tokio::task::spawn_blocking(move || copy_tree(&source, &target))
+        .await
+        .map_err(|e| format!("Copy task failed: {}", e))??;
+
+    let marker_path = dest.join(PORTABLE_MARKER);
+    tokio::fs::write(&marker_path, "ChaiLauncher stores its data in the ChaiLauncherData folder next to this file while it exists.\n").await
+        .map_err(|e| format!("Failed to write {}: {}", PORTABLE_MARKER, e))?;
+
+    println!("✅ Portable data ready in {} ({} files, {} bytes)", data_dir.display(), files_copied, bytes_copied);
+
+    Ok(PortableConversionResult {
+        data_dir: data_dir.to_string_lossy().to_string(),
+        marker_path: marker_path.to_string_lossy().to_string(),
+        files_copied,
+        bytes_copied,
+        skipped_instances,
+        restart_required: true,
+    })
+}
+
+async fn check_write_access(dir: &Path) -> Result<(), String> {
+    let probe = dir.join(format!(".chai_write_test_{}", uuid::Uuid::new_v4()));
+    tokio::fs::write(&probe, b"ok").await
+        .map_err(|e| format!("Destination is not writable: {}", e))?;
+    tokio::fs::remove_file(&probe).await
+        .map_err(|e| format!("Destination is not writable: {}", e))
+}
+
+async fn has_entries(dir: &Path) -> bool {
+    match tokio::fs::read_dir(dir).await {
+        Ok(mut entries) => matches!(entries.next_entry().await, Ok(Some(_))),
+        Err(_) => false,
+    }
+}
+
+fn copy_tree(src: &Path, dst: &Path) -> Result<(u64, u64), String> {
+    let mut files = 0;
+    let mut bytes = 0;
+
+    std::fs::create_dir_all(dst)
+        .map_err(|e| format!("Failed to create {}: {}", dst.display(), e))?;
+
+    for entry in walkdir::WalkDir::new(src) {
+        let entry = entry.map_err(|e| format!("Failed to read launcher data: {}", e))?;
+        let relative = entry.path().strip_prefix(src)
+            .map_err(|e| format!("Failed to get relative path: {}", e))?;
+        let target = dst.join(relative);
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        } else if entry.file_type().is_file() {
+            bytes += std::fs::copy(entry.path(), &target)
+                .map_err(|e| format!("Failed to copy {}: {}", relative.display(), e))?;
+            files += 1;
+        }
+    }
+
+    Ok((files, bytes))
+}
@@ -0,0 +1,346 @@
+//! Where the files the launcher wrote into an instance came from
+//!
+//! Every download into an instance appends a line to `chailauncher.provenance.jsonl`
+//! in the instance folder. Replacing or removing a file appends a tombstone first,
+//! so the history of any path can be rebuilt from the log. Once the log grows past
+//! `MAX_LOG_BYTES` it is moved to `chailauncher.provenance.1.jsonl` and a new log is
+//! started with the entries of the files that still exist.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{command, State};
+
+use crate::storage::StorageState;
+
+pub const PROVENANCE_FILE: &str = "chailauncher.provenance.jsonl";
+/// The previous log, kept until the next rotation
+pub const PROVENANCE_ARCHIVE_FILE: &str = "chailauncher.provenance.1.jsonl";
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvenanceAction {
+    Written,
+    /// Tombstone for the previous file at the path, followed by a `Written` entry
+    Replaced,
+    Removed,
+}
+
+/// One line of the provenance log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvenanceEntry {
+    /// Path relative to the instance folder, always with `/` separators
+    pub path: String,
+    pub action: ProvenanceAction,
+    pub source_url: Option<String>,
+    /// SHA1 the source claimed the file has
+    pub declared_hash: Option<String>,
+    /// SHA1 of the file that was actually written
+    pub verified_hash: Option<String>,
+    pub timestamp: String,
+    /// Background task that wrote the file
+    pub task_id: Option<String>,
+}
+
+/// Latest `Written` entry of every live path, per instance folder
+///
+/// Loaded from the log the first time an instance is written to. The mutex also
+/// serializes appends so concurrent downloads can't interleave lines.
+static LIVE: OnceLock<Mutex<HashMap<PathBuf, HashMap<String, ProvenanceEntry>>>> = OnceLock::new();
+
+fn live() -> &'static Mutex<HashMap<PathBuf, HashMap<String, ProvenanceEntry>>> {
+    LIVE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Record that `file` was downloaded from `url` into the instance
+///
+/// Files outside `instance_dir` are ignored. The file's hash is only computed when
+/// the source did not declare one, since the download already verified it otherwise.
+/// Failures are logged and never fail the install.
+pub async fn record_download(instance_dir: &Path, file: &Path, url: &str, declared_sha1: Option<&str>) {
+    let Some(path) = relative_path(instance_dir, file) else {
+        return;
+    };
+
+    let declared_hash = declared_sha1.map(str::to_lowercase);
+    let verified_hash = match &declared_hash {
+        Some(hash) => Some(hash.clone()),
+        None => crate::net::download::file_sha1(file).await.ok(),
+    };
+
+    let entry = ProvenanceEntry {
+        path,
+        action: ProvenanceAction::Written,
+        source_url: Some(url.to_string()),
+        declared_hash,
+        verified_hash,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        task_id: crate::tasks::current_task_id(),
+    };
+    append_logged(instance_dir, entry).await;
+}
+
+/// Record that the launcher deleted `file` from the instance
+pub async fn record_removed(instance_dir: &Path, file: &Path) {
+    let Some(path) = relative_path(instance_dir, file) else {
+        return;
+    };
+
+    let entry = ProvenanceEntry {
+        path,
+        action: ProvenanceAction::Removed,
+        source_url: None,
+        declared_hash: None,
+        verified_hash: None,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        task_id: crate::tasks::current_task_id(),
+    };
+    append_logged(instance_dir, entry).await;
+}
+
+async fn append_logged(instance_dir: &Path, entry: ProvenanceEntry) {
+    let instance_dir = instance_dir.to_path_buf();
+    let path = entry.path.clone();
+    let result = tokio::task::spawn_blocking(move || append(&instance_dir, entry)).await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+    if let Err(e) = result {
+        println!("⚠️  Failed to record provenance of {}: {}", path, e);
+    }
+}
+
+/// Append `entry`, adding a tombstone for the file it replaces or removes
+fn append(instance_dir: &Path, entry: ProvenanceEntry) -> std::io::Result<()> {
+    let mut live = live().lock().unwrap();
+    if !live.contains_key(instance_dir) {
+        let entries = load_live(&instance_dir.join(PROVENANCE_FILE))?;
+        live.insert(instance_dir.to_path_buf(), entries);
+    }
+    let files = live.get_mut(instance_dir).unwrap();
+
+    let mut lines = Vec::with_capacity(2);
+    match entry.action {
+        ProvenanceAction::Written => {
+            if let Some(previous) = files.remove(&entry.path) {
+                lines.push(tombstone(previous, ProvenanceAction::Replaced, &entry));
+            }
+            files.insert(entry.path.clone(), entry.clone());
+            lines.push(entry);
+        }
+        _ => match files.remove(&entry.path) {
+            Some(previous) => lines.push(tombstone(previous, entry.action, &entry)),
+            None => lines.push(entry),
+        },
+    }
+
+    let log_path = instance_dir.join(PROVENANCE_FILE);
+    write_lines(&log_path, &lines, true)?;
+
+    if std::fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0) > MAX_LOG_BYTES {
+        rotate(instance_dir, files)?;
+    }
+    Ok(())
+}
+
+/// The entry being superseded, marked with who superseded it and when
+fn tombstone(previous: ProvenanceEntry, action: ProvenanceAction, by: &ProvenanceEntry) -> ProvenanceEntry {
+    ProvenanceEntry {
+        action,
+        timestamp: by.timestamp.clone(),
+        task_id: by.task_id.clone(),
+        ..previous
+    }
+}
+
+/// Archive the log and start a new one with the entries of live files
+fn rotate(instance_dir: &Path, files: &HashMap<String, ProvenanceEntry>) -> std::io::Result<()> {
+    let log_path = instance_dir.join(PROVENANCE_FILE);
+    std::fs::rename(&log_path, instance_dir.join(PROVENANCE_ARCHIVE_FILE))?;
+
+    let mut snapshot: Vec<ProvenanceEntry> = files.values().cloned().collect();
+    snapshot.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.path.cmp(&b.path)));
+    write_lines(&log_path, &snapshot, false)
+}
+
+fn write_lines(path: &Path, entries: &[ProvenanceEntry], append: bool) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    for entry in entries {
+        serde_json::to_writer(&mut buffer, entry)?;
+        buffer.push(b'\n');
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(append)
+        .write(true)
+        .truncate(!append)
+        .open(path)?;
+    file.write_all(&buffer)
+}
+
+/// Every entry of a log file, skipping lines that don't parse
+fn read_log(path: &Path) -> std::io::Result<Vec<ProvenanceEntry>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut entries = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+fn load_live(log_path: &Path) -> std::io::Result<HashMap<String, ProvenanceEntry>> {
+    let mut files = HashMap::new();
+    for entry in read_log(log_path)? {
+        match entry.action {
+            ProvenanceAction::Written => {
+                files.insert(entry.path.clone(), entry);
+            }
+            ProvenanceAction::Replaced | ProvenanceAction::Removed => {
+                files.remove(&entry.path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// History of one path, oldest first, across the archived and current log
+pub fn history(instance_dir: &Path, relative_path: &str) -> std::io::Result<Vec<ProvenanceEntry>> {
+    let path = normalize(relative_path);
+    let _guard = live().lock().unwrap();
+
+    let mut entries = read_log(&instance_dir.join(PROVENANCE_ARCHIVE_FILE))?;
+    entries.extend(read_log(&instance_dir.join(PROVENANCE_FILE))?);
+    entries.retain(|entry| entry.path == path);
+    // A rotation repeats the last entry of each live path
+    entries.dedup();
+    Ok(entries)
+}
+
+/// Entries of the files currently in the instance that live under `prefix`
+pub fn live_entries(instance_dir: &Path, prefix: &str) -> Vec<ProvenanceEntry> {
+    let _guard = live().lock().unwrap();
+    let mut entries: Vec<ProvenanceEntry> = load_live(&instance_dir.join(PROVENANCE_FILE))
+        .unwrap_or_default()
+        .into_values()
+        .filter(|entry| entry.path.starts_with(prefix))
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+/// `file` relative to `instance_dir` with `/` separators, None when outside it
+fn relative_path(instance_dir: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(instance_dir).ok()?;
+    let parts: Vec<String> = relative.components()
+        .map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn normalize(relative_path: &str) -> String {
+    relative_path.replace('\\', "/").trim_start_matches("./").trim_matches('/').to_string()
+}
+
+/// History of a file in an instance, oldest first
+#[command]
+pub async fn get_file_provenance(
+    instance_id: String,
+    relative_path: String,
+    storage: State<'_, StorageState>,
+) -> Result<Vec<ProvenanceEntry>, String> {
+    let game_dir = storage.read().await
+        .get_instance(&instance_id)
+        .map(|instance| instance.game_dir.clone())
+        .ok_or_else(|| format!("Instance '{}' not found", instance_id))?;
+
+    tokio::task::spawn_blocking(move || history(&game_dir, &relative_path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| format!("Failed to read provenance log: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(path: &str, url: &str, hash: &str) -> ProvenanceEntry {
+        ProvenanceEntry {
+            path: path.to_string(),
+            action: ProvenanceAction::Written,
+            source_url: Some(url.to_string()),
+            declared_hash: Some(hash.to_string()),
+            verified_hash: Some(hash.to_string()),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            task_id: None,
+        }
+    }
+
+    #[test]
+    fn replaced_and_removed_files_leave_tombstones() {
+        let dir = std::env::temp_dir().join(format!("chai-provenance-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        append(&dir, written("mods/sodium.jar", "https://cdn/a", "aaa")).unwrap();
+        append(&dir, written("mods/sodium.jar", "https://cdn/b", "bbb")).unwrap();
+        append(&dir, ProvenanceEntry {
+            action: ProvenanceAction::Removed,
+            source_url: None,
+            declared_hash: None,
+            verified_hash: None,
+            ..written("mods/sodium.jar", "", "")
+        }).unwrap();
+
+        let history = history(&dir, "./mods\\sodium.jar").unwrap();
+        let actions: Vec<_> = history.iter().map(|entry| (entry.action, entry.verified_hash.as_deref())).collect();
+        assert_eq!(actions, vec![
+            (ProvenanceAction::Written, Some("aaa")),
+            (ProvenanceAction::Replaced, Some("aaa")),
+            (ProvenanceAction::Written, Some("bbb")),
+            (ProvenanceAction::Removed, Some("bbb")),
+        ]);
+        assert!(live_entries(&dir, "mods/").is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotation_keeps_live_files_and_history() {
+        let dir = std::env::temp_dir().join(format!("chai-provenance-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        append(&dir, written("mods/kept.jar", "https://cdn/kept", "kkk")).unwrap();
+        let url = format!("https://cdn/{}", "x".repeat(4096));
+        for i in 0..(MAX_LOG_BYTES / 4096 + 1) {
+            append(&dir, written("mods/churn.jar", &url, &i.to_string())).unwrap();
+        }
+
+        assert!(dir.join(PROVENANCE_ARCHIVE_FILE).exists());
+        assert!(std::fs::metadata(dir.join(PROVENANCE_FILE)).unwrap().len() < MAX_LOG_BYTES);
+
+        let live = live_entries(&dir, "mods/");
+        assert_eq!(live.iter().map(|entry| entry.path.as_str()).collect::<Vec<_>>(), ["mods/churn.jar", "mods/kept.jar"]);
+        assert_eq!(history(&dir, "mods/kept.jar").unwrap().len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn paths_outside_the_instance_are_ignored() {
+        let dir = Path::new("/instances/pack");
+        assert_eq!(relative_path(dir, &dir.join("mods").join("a.jar")), Some("mods/a.jar".to_string()));
+        assert_eq!(relative_path(dir, Path::new("/tmp/pack.mrpack")), None);
+        assert_eq!(relative_path(dir, dir), None);
+    }
+}
@@ -0,0 +1,426 @@
+//! Local search over installed content
+//!
+//! A small inverted index over instance names, tags, descriptions, installed mods
+//! and world folders, so the global search bar finds "that backpack mod" without
+//! opening every instance. Each instance is indexed on its own together with a
+//! fingerprint of its metadata and the modification times of `mods_metadata.json`
+//! and `saves`; a refresh only re-reads instances whose fingerprint changed.
+//!
+//! The index lives in `search_index.json` in the launcher folder. Reading and
+//! indexing happen on the blocking pool. A file that can't be read is dropped and
+//! rebuilt, `rebuild_search_index` does the same on request.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::command;
+
+use crate::mods::InstalledMod;
+use crate::storage::InstanceMetadata;
+
+const INDEX_FILE: &str = "search_index.json";
+
+/// Bumped when the document layout changes, older files are rebuilt
+const INDEX_VERSION: u32 = 1;
+
+const DEFAULT_LIMIT: usize = 50;
+
+/// Words too common to say anything about a hit
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "does", "for", "from", "in", "is", "it", "mod", "of", "on", "one",
+    "that", "the", "this", "to", "what", "where", "which", "with",
+];
+
+/// Loaded on first use, only touched from the blocking pool
+static INDEX: Mutex<Option<SearchIndex>> = Mutex::new(None);
+
+/// What a hit points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Instance,
+    Tag,
+    /// The instance description
+    Note,
+    Mod,
+    World,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    /// Instance owning the hit
+    pub instance_id: String,
+    pub instance_name: String,
+    pub title: String,
+    pub detail: Option<String>,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchIndexStats {
+    pub instances: usize,
+    pub documents: usize,
+    /// Instances read from disk by this refresh
+    pub reindexed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SearchDocument {
+    kind: SearchKind,
+    title: String,
+    detail: Option<String>,
+}
+
+/// What decides whether an instance has to be indexed again
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Fingerprint {
+    /// sha256 of the indexed metadata fields
+    metadata: String,
+    mods_modified: Option<u128>,
+    saves_modified: Option<u128>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct IndexedInstance {
+    name: String,
+    fingerprint: Fingerprint,
+    documents: Vec<SearchDocument>,
+    /// Term to (document, weight)
+    terms: BTreeMap<String, Vec<(u32, f32)>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SearchIndex {
+    version: u32,
+    instances: HashMap<String, IndexedInstance>,
+}
+
+/// Lowercased words of `text`, without stop words
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+fn modified_millis(path: &Path) -> Option<u128> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_millis())
+}
+
+fn fingerprint(instance: &InstanceMetadata) -> Fingerprint {
+    let fields = serde_json::json!([instance.name, instance.version, instance.modpack, instance.description, instance.tags]);
+    Fingerprint {
+        metadata: hex::encode(Sha256::digest(fields.to_string())),
+        mods_modified: modified_millis(&instance.game_dir.join("mods_metadata.json")),
+        saves_modified: modified_millis(&instance.game_dir.join("saves")),
+    }
+}
+
+impl IndexedInstance {
+    /// Add a document, words of `title` count twice as much as those of `body`
+    fn add(&mut self, kind: SearchKind, title: &str, detail: Option<String>, body: &str) {
+        let document = self.documents.len() as u32;
+        let mut weights: HashMap<String, f32> = HashMap::new();
+        for word in tokenize(title) {
+            weights.insert(word, 2.0);
+        }
+        for word in tokenize(body) {
+            weights.entry(word).or_insert(1.0);
+        }
+        for (word, weight) in weights {
+            self.terms.entry(word).or_default().push((document, weight));
+        }
+        self.documents.push(SearchDocument { kind, title: title.to_string(), detail });
+    }
+
+    /// Read everything searchable about an instance
+    fn build(instance: &InstanceMetadata, fingerprint: Fingerprint) -> Self {
+        let mut indexed = IndexedInstance { name: instance.name.clone(), fingerprint, ..Default::default() };
+
+        let detail = match &instance.modpack {
+            Some(modpack) => format!("{} · {}", instance.version, modpack),
+            None => instance.version.clone(),
+        };
+        indexed.add(SearchKind::Instance, &instance.name, Some(detail), instance.modpack.as_deref().unwrap_or_default());
+        for tag in &instance.tags {
+            indexed.add(SearchKind::Tag, tag, None, "");
+        }
+        if let Some(description) = instance.description.as_deref().filter(|d| !d.trim().is_empty()) {
+            indexed.add(SearchKind::Note, &instance.name, Some(description.to_string()), description);
+        }
+
+        let mods: HashMap<String, InstalledMod> = std::fs::read_to_string(instance.game_dir.join("mods_metadata.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let mut mods: Vec<&InstalledMod> = mods.values().collect();
+        mods.sort_by(|a, b| a.mod_info.name.cmp(&b.mod_info.name));
+        for installed_mod in mods {
+            let info = &installed_mod.mod_info;
+            let body = format!("{} {} {} {}", info.id, info.author, info.description, info.categories.join(" "));
+            let detail = (!info.description.is_empty()).then(|| info.description.clone());
+            indexed.add(SearchKind::Mod, &info.name, detail, &body);
+        }
+
+        if let Ok(entries) = std::fs::read_dir(instance.game_dir.join("saves")) {
+            let mut worlds: Vec<String> = entries
+                .flatten()
+                .filter(|entry| entry.path().join("level.dat").is_file())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect();
+            worlds.sort();
+            for world in worlds {
+                indexed.add(SearchKind::World, &world, None, "");
+            }
+        }
+        indexed
+    }
+}
+
+impl SearchIndex {
+    fn load(path: &Path) -> Self {
+        let loaded = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<SearchIndex>(&json).map_err(|e| e.to_string()));
+        match loaded {
+            Ok(index) if index.version == INDEX_VERSION => index,
+            Ok(_) => SearchIndex { version: INDEX_VERSION, ..Default::default() },
+            Err(e) => {
+                if path.exists() {
+                    eprintln!("⚠️  Search index is unreadable, rebuilding it: {}", e);
+                }
+                SearchIndex { version: INDEX_VERSION, ..Default::default() }
+            }
+        }
+    }
+
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec(self)?)?;
+        std::fs::rename(&tmp_path, path)
+    }
+
+    /// Bring the index in line with `instances`
+    ///
+    /// Returns how many instances were re-read and how many were dropped.
+    fn update(&mut self, instances: &[InstanceMetadata]) -> (usize, usize) {
+        let live: HashSet<&str> = instances.iter().map(|instance| instance.id.as_str()).collect();
+        let before = self.instances.len();
+        self.instances.retain(|id, _| live.contains(id.as_str()));
+        let removed = before - self.instances.len();
+
+        let mut reindexed = 0;
+        for instance in instances {
+            let fingerprint = fingerprint(instance);
+            if self.instances.get(&instance.id).is_some_and(|indexed| indexed.fingerprint == fingerprint) {
+                continue;
+            }
+            self.instances.insert(instance.id.clone(), IndexedInstance::build(instance, fingerprint));
+            reindexed += 1;
+        }
+        if reindexed + removed > 0 {
+            println!("🔎 Search index updated, {} instances re-read and {} dropped", reindexed, removed);
+        }
+        (reindexed, removed)
+    }
+
+    fn stats(&self, reindexed: usize) -> SearchIndexStats {
+        SearchIndexStats {
+            instances: self.instances.len(),
+            documents: self.instances.values().map(|indexed| indexed.documents.len()).sum(),
+            reindexed,
+        }
+    }
+
+    /// Hits for `query`, best first
+    ///
+    /// Every query word matches terms it is a prefix of, whole words count more.
+    /// Words found in few documents weigh more than common ones, so a hit on
+    /// "backpacks" outranks one on "adds".
+    fn search(&self, query: &str, kinds: &[SearchKind], limit: usize) -> Vec<SearchHit> {
+        let words = tokenize(query);
+        let total: usize = self.instances.values().map(|indexed| indexed.documents.len()).sum();
+        let mut scores: HashMap<(&str, u32), f32> = HashMap::new();
+
+        for word in &words {
+            let mut matches: HashMap<(&str, u32), f32> = HashMap::new();
+            for (id, indexed) in &self.instances {
+                for (term, postings) in indexed.terms.range::<str, _>((Bound::Included(word.as_str()), Bound::Unbounded)) {
+                    if !term.starts_with(word.as_str()) {
+                        break;
+                    }
+                    let exact = if term == word { 1.0 } else { 0.5 };
+                    for (document, weight) in postings {
+                        let score = matches.entry((id.as_str(), *document)).or_default();
+                        *score = score.max(weight * exact);
+                    }
+                }
+            }
+
+            let idf = (1.0 + total as f32 / matches.len().max(1) as f32).ln();
+            for (key, score) in matches {
+                *scores.entry(key).or_default() += score * idf;
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = scores.into_iter()
+            .filter_map(|((id, document), score)| {
+                let indexed = &self.instances[id];
+                let document = &indexed.documents[document as usize];
+                (kinds.is_empty() || kinds.contains(&document.kind)).then(|| SearchHit {
+                    kind: document.kind,
+                    instance_id: id.to_string(),
+                    instance_name: indexed.name.clone(),
+                    title: document.title.clone(),
+                    detail: document.detail.clone(),
+                    score,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score)
+            .then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+            .then_with(|| a.instance_name.cmp(&b.instance_name)));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+fn index_path() -> PathBuf {
+    crate::storage::get_launcher_dir().join(INDEX_FILE)
+}
+
+async fn all_instances() -> Result<Vec<InstanceMetadata>, String> {
+    let storage = crate::storage::shared().await
+        .map_err(|e| format!("Failed to access storage: {}", e))?;
+    let storage = storage.read().await;
+    Ok(storage.get_all_instances().into_iter().cloned().collect())
+}
+
+/// Run `f` on the loaded index after an incremental update, on the blocking pool
+async fn with_index<T: Send + 'static>(
+    rebuild: bool,
+    f: impl FnOnce(&SearchIndex, usize) -> T + Send + 'static,
+) -> Result<T, String> {
+    let instances = all_instances().await?;
+    tokio::task::spawn_blocking(move || {
+        let path = index_path();
+        let mut guard = INDEX.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if rebuild {
+            *guard = Some(SearchIndex { version: INDEX_VERSION, ..Default::default() });
+        } else if guard.is_none() {
+            *guard = Some(SearchIndex::load(&path));
+        }
+        let index = guard.as_mut().expect("index loaded above");
+
+        let (reindexed, removed) = index.update(&instances);
+        if reindexed + removed > 0 || rebuild {
+            if let Err(e) = index.save(&path) {
+                eprintln!("⚠️  Failed to save the search index: {}", e);
+            }
+        }
+        f(index, reindexed)
+    })
+    .await
+    .map_err(|e| format!("Search index task failed: {}", e))
+}
+
+/// Pick up instance changes, called whenever `instances_updated` goes out
+pub async fn refresh() {
+    if let Err(e) = with_index(false, |_, _| ()).await {
+        eprintln!("⚠️  Failed to refresh the search index: {}", e);
+    }
+}
+
+/// Search installed content across all instances, limited to `kinds` when given
+#[command]
+pub async fn search_local(query: String, kinds: Option<Vec<SearchKind>>, limit: Option<usize>) -> Result<Vec<SearchHit>, String> {
+    let kinds = kinds.unwrap_or_default();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    with_index(false, move |index, _| index.search(&query, &kinds, limit)).await
+}
+
+/// Throw the index away and index every instance again
+#[command]
+pub async fn rebuild_search_index() -> Result<SearchIndexStats, String> {
+    let stats = with_index(true, |index, reindexed| index.stats(reindexed)).await?;
+    println!("🔎 Rebuilt the search index: {} documents from {} instances", stats.documents, stats.instances);
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(id: &str, name: &str, game_dir: PathBuf) -> InstanceMetadata {
+        InstanceMetadata {
+            name: name.to_string(),
+            is_modded: true,
+            description: Some("Skyblock run with friends".to_string()),
+            tags: vec!["Tech".to_string()],
+            ..crate::storage::test_instance(id, game_dir)
+        }
+    }
+
+    #[test]
+    fn ranks_rare_words_and_prefixes() {
+        let mut index = SearchIndex::default();
+        let mut survival = IndexedInstance { name: "Survival".to_string(), ..Default::default() };
+        survival.add(SearchKind::Mod, "Traveler's Backpack", None, "Adds backpacks with tanks and upgrades");
+        survival.add(SearchKind::Mod, "Create", None, "Adds gears, belts and contraptions");
+        let mut creative = IndexedInstance { name: "Creative".to_string(), ..Default::default() };
+        creative.add(SearchKind::Mod, "Sophisticated Backpacks", None, "Adds highly upgradeable backpacks");
+        creative.add(SearchKind::World, "Backup world", None, "");
+        index.instances.insert("survival".to_string(), survival);
+        index.instances.insert("creative".to_string(), creative);
+
+        let hits = index.search("where is that one mod that adds backpacks", &[], 10);
+        let titles: Vec<&str> = hits.iter().map(|hit| hit.title.as_str()).collect();
+        assert_eq!(&titles[..2], &["Sophisticated Backpacks", "Traveler's Backpack"]);
+        assert_eq!(hits[0].instance_id, "creative");
+        assert_eq!(titles[2], "Create");
+
+        let hits = index.search("back", &[SearchKind::World], 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Backup world");
+        assert!(index.search("the", &[], 10).is_empty());
+    }
+
+    #[test]
+    fn updates_only_changed_instances() {
+        let dir = std::env::temp_dir().join(format!("chai-search-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("a").join("saves").join("Island")).unwrap();
+        std::fs::write(dir.join("a").join("saves").join("Island").join("level.dat"), b"").unwrap();
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+
+        let mut instances = vec![instance("a", "Skies", dir.join("a")), instance("b", "Vanilla", dir.join("b"))];
+        let mut index = SearchIndex { version: INDEX_VERSION, ..Default::default() };
+        assert_eq!(index.update(&instances), (2, 0));
+        assert_eq!(index.update(&instances), (0, 0));
+
+        let hits = index.search("island", &[], 10);
+        assert_eq!((hits[0].kind, hits[0].instance_id.as_str()), (SearchKind::World, "a"));
+        assert_eq!(index.search("skyblock", &[SearchKind::Note], 10).len(), 2);
+
+        instances[1].tags.push("Speedrun".to_string());
+        instances.remove(0);
+        assert_eq!(index.update(&instances), (1, 1));
+        assert!(index.search("island", &[], 10).is_empty());
+        assert_eq!(index.search("speedrun", &[], 10)[0].kind, SearchKind::Tag);
+
+        // A corrupt file loads as an empty index
+        let path = dir.join(INDEX_FILE);
+        index.save(&path).unwrap();
+        assert_eq!(SearchIndex::load(&path).instances.len(), 1);
+        std::fs::write(&path, b"{ not json").unwrap();
+        assert!(SearchIndex::load(&path).instances.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
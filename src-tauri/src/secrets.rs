@@ -0,0 +1,310 @@
+//! Account tokens kept out of the plaintext config
+//!
+//! Tokens go to the platform credential store (Keychain, Windows Credential
+//! Manager or the Secret Service) under `KEYRING_SERVICE`. Linux setups without a
+//! Secret Service can switch `token_storage` to an encrypted file in the launcher
+//! folder instead. Its key is derived from the machine id and a random salt, so a
+//! copied launcher folder doesn't carry usable tokens, but it is no protection
+//! against other programs running as the same user. Reads fall back to the other
+//! store, so switching modes doesn't lose tokens saved before the switch.
+
+use anyhow::{Context, Result};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::storage::StorageState;
+
+const KEYRING_SERVICE: &str = "ChaiLauncher";
+const SECRETS_FILE: &str = "secrets.json";
+const NONCE_LEN: usize = 12;
+
+/// Secret name of the manual token from the settings page
+pub const AUTH_TOKEN_KEY: &str = "auth_token";
+
+/// Where account tokens are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenStorage {
+    /// The platform credential store
+    #[default]
+    Keyring,
+    /// `secrets.json` in the launcher folder, for Linux without a Secret Service
+    EncryptedFile,
+}
+
+/// Secret name of a Microsoft account's tokens
+pub fn account_key(uuid: &str) -> String {
+    format!("account:{}", uuid)
+}
+
+static FILE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+async fn configured_storage() -> TokenStorage {
+    match crate::storage::shared().await {
+        Ok(storage) => storage.read().await.get_settings().token_storage,
+        Err(_) => TokenStorage::default(),
+    }
+}
+
+fn secrets_path() -> PathBuf {
+    crate::storage::get_launcher_dir().join(SECRETS_FILE)
+}
+
+/// Read a secret, None when neither store has it
+pub async fn get(key: &str) -> Result<Option<String>> {
+    let (first, second) = match configured_storage().await {
+        TokenStorage::Keyring => (TokenStorage::Keyring, TokenStorage::EncryptedFile),
+        TokenStorage::EncryptedFile => (TokenStorage::EncryptedFile, TokenStorage::Keyring),
+    };
+
+    match read(first, key).await {
+        Ok(Some(value)) => return Ok(Some(value)),
+        Ok(None) => {}
+        Err(e) => eprintln!("⚠️  Failed to read {} from {:?}: {}", key, first, e),
+    }
+    read(second, key).await.or_else(|e| {
+        if first == TokenStorage::Keyring {
+            Err(e)
+        } else {
+            // The file was the configured store, a missing keyring is expected
+            Ok(None)
+        }
+    })
+}
+
+/// Save a secret in the configured store
+///
+/// When the credential store can't be reached the secret goes to the encrypted
+/// file rather than failing the sign-in.
+pub async fn set(key: &str, value: &str) -> Result<()> {
+    if configured_storage().await == TokenStorage::Keyring {
+        match keyring_set(key, value).await {
+            Ok(()) => {
+                let _ = file_delete(&secrets_path(), key).await;
+                return Ok(());
+            }
+            Err(e) => eprintln!("⚠️  Credential store unavailable, saving {} to the encrypted file: {}", key, e),
+        }
+    }
+    file_set(&secrets_path(), key, value).await
+}
+
+/// Remove a secret from both stores
+pub async fn delete(key: &str) -> Result<()> {
+    let keyring = keyring_delete(key).await;
+    file_delete(&secrets_path(), key).await?;
+    if configured_storage().await == TokenStorage::Keyring {
+        keyring?;
+    }
+    Ok(())
+}
+
+async fn read(storage: TokenStorage, key: &str) -> Result<Option<String>> {
+    match storage {
+        TokenStorage::Keyring => keyring_get(key).await,
+        TokenStorage::EncryptedFile => file_get(&secrets_path(), key).await,
+    }
+}
+
+/// Move a manual token left in the settings by older versions into the store
+pub async fn migrate_auth_token(storage: &StorageState) {
+    let Some(token) = storage.read().await.get_settings().auth_token.clone() else {
+        return;
+    };
+    if let Err(e) = set(AUTH_TOKEN_KEY, &token).await {
+        eprintln!("❌ Failed to move the auth token out of the config: {}", e);
+        return;
+    }
+
+    let mut storage = storage.write().await;
+    let mut settings = storage.get_settings().clone();
+    settings.auth_token = None;
+    match storage.update_settings(settings).await {
+        Ok(()) => println!("🔐 Moved the auth token out of the config"),
+        Err(e) => eprintln!("❌ Failed to remove the auth token from the config: {}", e),
+    }
+}
+
+async fn keyring_get(key: &str) -> Result<Option<String>> {
+    let key = key.to_string();
+    tokio::task::spawn_blocking(move || {
+        match keyring::Entry::new(KEYRING_SERVICE, &key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }).await?
+}
+
+async fn keyring_set(key: &str, value: &str) -> Result<()> {
+    let (key, value) = (key.to_string(), value.to_string());
+    tokio::task::spawn_blocking(move || {
+        keyring::Entry::new(KEYRING_SERVICE, &key)?.set_password(&value)?;
+        Ok(())
+    }).await?
+}
+
+async fn keyring_delete(key: &str) -> Result<()> {
+    let key = key.to_string();
+    tokio::task::spawn_blocking(move || {
+        match keyring::Entry::new(KEYRING_SERVICE, &key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }).await?
+}
+
+/// Encrypted secrets, hex encoded nonce followed by the ciphertext
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretsFile {
+    salt: String,
+    entries: BTreeMap<String, String>,
+}
+
+impl SecretsFile {
+    fn cipher(&self) -> Result<ChaCha20Poly1305> {
+        let salt = hex::decode(&self.salt).context("Corrupt secrets file salt")?;
+        let mut hasher = Sha256::new();
+        hasher.update(b"chailauncher-secrets");
+        hasher.update(machine_id());
+        hasher.update(&salt);
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&hasher.finalize())))
+    }
+}
+
+/// Identifies this machine, so the file can't be decrypted elsewhere
+fn machine_id() -> Vec<u8> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| std::fs::read(path).ok())
+        .or_else(|| dirs::home_dir().map(|home| home.to_string_lossy().as_bytes().to_vec()))
+        .unwrap_or_default()
+}
+
+async fn load_file(path: &Path) -> Result<SecretsFile> {
+    if !path.exists() {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        return Ok(SecretsFile { salt: hex::encode(salt), entries: BTreeMap::new() });
+    }
+    let content = tokio::fs::read_to_string(path).await?;
+    serde_json::from_str(&content).with_context(|| format!("Corrupt secrets file {}", path.display()))
+}
+
+async fn save_file(path: &Path, file: &SecretsFile) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let content = serde_json::to_string_pretty(file)?;
+
+    // Owner only from creation on, and a crash mid-write leaves the old file in place
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    // The mode only applies to new files, so don't reuse one left by a crash
+    match tokio::fs::remove_file(&temp_path).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut temp = options.open(&temp_path).await?;
+    temp.write_all(content.as_bytes()).await?;
+    temp.sync_all().await?;
+    drop(temp);
+    tokio::fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+async fn file_get(path: &Path, key: &str) -> Result<Option<String>> {
+    let _locked = FILE_LOCK.lock().await;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = load_file(path).await?;
+    let Some(entry) = file.entries.get(key) else {
+        return Ok(None);
+    };
+
+    let bytes = hex::decode(entry).context("Corrupt secrets file entry")?;
+    if bytes.len() < NONCE_LEN {
+        anyhow::bail!("Corrupt secrets file entry");
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = file.cipher()?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Can't decrypt {}, the secrets file was made on another machine", key))?;
+    Ok(Some(String::from_utf8(plaintext)?))
+}
+
+async fn file_set(path: &Path, key: &str, value: &str) -> Result<()> {
+    let _locked = FILE_LOCK.lock().await;
+    let mut file = load_file(path).await?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = file.cipher()?
+        .encrypt(&nonce, value.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt {}", key))?;
+
+    let mut entry = nonce.to_vec();
+    entry.extend(ciphertext);
+    file.entries.insert(key.to_string(), hex::encode(entry));
+    save_file(path, &file).await
+}
+
+async fn file_delete(path: &Path, key: &str) -> Result<()> {
+    let _locked = FILE_LOCK.lock().await;
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut file = load_file(path).await?;
+    if file.entries.remove(key).is_some() {
+        save_file(path, &file).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn encrypted_file_round_trips_without_plaintext() {
+        let dir = std::env::temp_dir().join(format!("chai-secrets-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(SECRETS_FILE);
+
+        assert!(file_get(&path, "account:abc").await.unwrap().is_none());
+        file_set(&path, "account:abc", "refresh-token-value").await.unwrap();
+        file_set(&path, AUTH_TOKEN_KEY, "manual").await.unwrap();
+
+        assert_eq!(file_get(&path, "account:abc").await.unwrap().as_deref(), Some("refresh-token-value"));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("refresh-token-value"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        assert!(!dir.join(format!("{}.tmp", SECRETS_FILE)).exists());
+
+        file_delete(&path, "account:abc").await.unwrap();
+        assert!(file_get(&path, "account:abc").await.unwrap().is_none());
+        assert_eq!(file_get(&path, AUTH_TOKEN_KEY).await.unwrap().as_deref(), Some("manual"));
+
+        // Another salt stands in for another machine
+        let mut file: SecretsFile = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        file.salt = hex::encode([7u8; 16]);
+        std::fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        assert!(file_get(&path, AUTH_TOKEN_KEY).await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
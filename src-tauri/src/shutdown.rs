@@ -0,0 +1,281 @@
+//! Graceful shutdown and reporting what the last session left unfinished
+//!
+//! `session.json` in the launcher directory is written with `clean_exit: false`
+//! at startup. On exit, running tasks and modpack installs are cancelled and
+//! given a moment to stop, then the file is rewritten with what was cut short
+//! and which games were still running. The next start reports a session that
+//! crashed or was interrupted, with a resume action where one is supported.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::minecraft::sessions::{PlaySessionsState, RunningInstance};
+use crate::modpack::{ModpackInstallRequest, ModpackInstallsState};
+use crate::tasks::{TaskInfo, TaskManagerState};
+
+pub type ShutdownState = Arc<Shutdown>;
+
+const SESSION_FILE: &str = "session.json";
+
+/// How long cancelled work gets to write its checkpoints before the launcher exits
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Whether the launcher is exiting, background loops stop once this is true
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+/// How work cut short by the exit can be picked up again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResumeAction {
+    /// Run `install_modpack` again, files already downloaded are kept
+    ModpackInstall { request: ModpackInstallRequest },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterruptedTask {
+    pub kind: String,
+    pub subject: Option<String>,
+    pub progress: u32,
+    pub message: Option<String>,
+    pub resume: Option<ResumeAction>,
+}
+
+impl From<TaskInfo> for InterruptedTask {
+    fn from(task: TaskInfo) -> Self {
+        Self {
+            kind: task.kind,
+            subject: task.subject,
+            progress: task.progress,
+            message: task.message,
+            resume: None,
+        }
+    }
+}
+
+/// Contents of `session.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    /// False when the launcher crashed or was killed
+    pub clean_exit: bool,
+    #[serde(default)]
+    pub interrupted: Vec<InterruptedTask>,
+    /// Games left running, for reattaching to them later
+    #[serde(default)]
+    pub running_games: Vec<RunningInstance>,
+}
+
+impl SessionRecord {
+    /// Whether the session ended in a way worth telling the player about
+    fn was_interrupted(&self) -> bool {
+        !self.clean_exit || !self.interrupted.is_empty()
+    }
+}
+
+/// What would be cut short by exiting now, for the exit prompt
+#[derive(Debug, Clone, Serialize)]
+pub struct BackgroundWork {
+    pub tasks: Vec<TaskInfo>,
+    pub modpack_installs: Vec<ModpackInstallRequest>,
+    pub running_games: Vec<RunningInstance>,
+}
+
+/// The current session and the report about the previous one
+pub struct Shutdown {
+    started_at: String,
+    previous: Mutex<Option<SessionRecord>>,
+}
+
+impl Shutdown {
+    /// Read what the last session left behind and mark this one as started
+    pub async fn begin() -> Self {
+        let path = session_path();
+        let previous = read_record(&path).await.filter(SessionRecord::was_interrupted);
+        if let Some(previous) = &previous {
+            println!("⚠️  The previous session {} ({} task(s) interrupted)",
+                if previous.clean_exit { "exited with work in progress" } else { "did not exit cleanly" },
+                previous.interrupted.len());
+        }
+
+        let started_at = chrono::Utc::now().to_rfc3339();
+        let record = SessionRecord {
+            started_at: started_at.clone(),
+            ended_at: None,
+            clean_exit: false,
+            interrupted: Vec::new(),
+            running_games: Vec::new(),
+        };
+        if let Err(e) = write_record(&path, &record).await {
+            println!("⚠️  Failed to write {}: {}", path.display(), e);
+        }
+
+        Self { started_at, previous: Mutex::new(previous) }
+    }
+
+    pub fn previous_session(&self) -> Option<SessionRecord> {
+        self.previous.lock().unwrap().clone()
+    }
+
+    /// Stop background work, record what was interrupted and save everything
+    pub async fn run(&self, app_handle: &AppHandle) {
+        SHUTTING_DOWN.store(true, Ordering::SeqCst);
+        let work = background_work(app_handle);
+
+        let mut interrupted: Vec<InterruptedTask> = work.tasks.into_iter().map(InterruptedTask::from).collect();
+        interrupted.extend(work.modpack_installs.into_iter().map(|request| InterruptedTask {
+            kind: "install_modpack".to_string(),
+            subject: Some(request.instance_name.clone()),
+            progress: 0,
+            message: None,
+            resume: Some(ResumeAction::ModpackInstall { request }),
+        }));
+
+        if !interrupted.is_empty() {
+            println!("🛑 Cancelling {} task(s) before exit", interrupted.len());
+            let tasks = app_handle.state::<TaskManagerState>();
+            let installs = app_handle.state::<ModpackInstallsState>();
+            tasks.cancel_all();
+            installs.cancel_all();
+
+            // Cancelled downloads keep their .part files, give them time to get there
+            let deadline = Instant::now() + SHUTDOWN_GRACE;
+            while Instant::now() < deadline && (!tasks.active_tasks().is_empty() || !installs.active().is_empty()) {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+
+        let path = session_path();
+        let record = SessionRecord {
+            started_at: self.started_at.clone(),
+            ended_at: Some(chrono::Utc::now().to_rfc3339()),
+            clean_exit: true,
+            interrupted,
+            running_games: work.running_games,
+        };
+        if let Err(e) = write_record(&path, &record).await {
+            println!("⚠️  Failed to write {}: {}", path.display(), e);
+        }
+
+        // ssh processes would outlive the launcher
+        if let Some(docker) = app_handle.try_state::<crate::docker::commands::DockerManagerState>() {
+            match tokio::time::timeout(SHUTDOWN_GRACE, docker.lock()).await {
+                Ok(mut docker) => docker.shutdown().await,
+                Err(_) => eprintln!("⚠️  Docker is busy, SSH tunnels are left to be killed on exit"),
+            }
+        }
+
+        let storage = app_handle.state::<crate::storage::StorageState>().inner().clone();
+        let flushed = storage.read().await.flush().await;
+        if let Err(e) = flushed {
+            eprintln!("⚠️  Failed to save launcher config on exit: {}", e);
+        }
+    }
+}
+
+fn background_work(app_handle: &AppHandle) -> BackgroundWork {
+    BackgroundWork {
+        tasks: app_handle.state::<TaskManagerState>().active_tasks(),
+        modpack_installs: app_handle.state::<ModpackInstallsState>().active(),
+        running_games: app_handle.state::<PlaySessionsState>().running_instances(),
+    }
+}
+
+fn session_path() -> PathBuf {
+    crate::storage::get_launcher_dir().join(SESSION_FILE)
+}
+
+async fn read_record(path: &Path) -> Option<SessionRecord> {
+    let text = tokio::fs::read_to_string(path).await.ok()?;
+    match serde_json::from_str(&text) {
+        Ok(record) => Some(record),
+        Err(e) => {
+            println!("⚠️  Ignoring unreadable {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+async fn write_record(path: &Path, record: &SessionRecord) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&temp_path, serde_json::to_vec_pretty(record)?).await?;
+    tokio::fs::rename(&temp_path, path).await
+}
+
+/// How the previous session ended, None if it exited cleanly with nothing in progress
+#[command]
+pub async fn get_previous_session_report(shutdown: State<'_, ShutdownState>) -> Result<Option<SessionRecord>, String> {
+    Ok(shutdown.previous_session())
+}
+
+/// Stop reporting the previous session until the next interrupted one
+#[command]
+pub async fn dismiss_previous_session_report(shutdown: State<'_, ShutdownState>) -> Result<(), String> {
+    shutdown.previous.lock().unwrap().take();
+    Ok(())
+}
+
+/// Tasks, installs and games that exiting now would affect
+#[command]
+pub async fn get_background_work(app_handle: AppHandle) -> Result<BackgroundWork, String> {
+    Ok(background_work(&app_handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn session_records_round_trip_and_flag_interruptions() {
+        let dir = std::env::temp_dir().join(format!("chai-shutdown-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(SESSION_FILE);
+        assert_eq!(read_record(&path).await, None);
+
+        let mut record = SessionRecord {
+            started_at: "2024-05-01T10:00:00+00:00".to_string(),
+            ended_at: None,
+            clean_exit: false,
+            interrupted: Vec::new(),
+            running_games: Vec::new(),
+        };
+        write_record(&path, &record).await.unwrap();
+        assert_eq!(read_record(&path).await.as_ref(), Some(&record));
+        assert!(record.was_interrupted());
+
+        record.clean_exit = true;
+        assert!(!record.was_interrupted());
+
+        record.interrupted.push(InterruptedTask {
+            kind: "install_modpack".to_string(),
+            subject: Some("Pack".to_string()),
+            progress: 0,
+            message: None,
+            resume: Some(ResumeAction::ModpackInstall {
+                request: ModpackInstallRequest {
+                    project_id: "abc".to_string(),
+                    version_id: "def".to_string(),
+                    instance_name: "Pack".to_string(),
+                    instance_dir: "/instances".to_string(),
+                    platform: "modrinth".to_string(),
+                },
+            }),
+        });
+        write_record(&path, &record).await.unwrap();
+        let read = read_record(&path).await.unwrap();
+        assert!(read.was_interrupted());
+        assert_eq!(read, record);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
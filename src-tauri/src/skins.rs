@@ -0,0 +1,384 @@
+//! Player skins and head avatars for the accounts UI
+//!
+//! Skins come from the session server's profile textures. The skin PNG is kept
+//! by texture hash under `skins/textures` in the launcher folder, with the 8x8
+//! face and its hat layer composited into `skins/heads`. What a player wears is
+//! remembered for `CACHE_TTL_HOURS`. Players the session server can't tell us
+//! about get the default Steve or Alex head that ships with the launcher. A skin
+//! changed from the launcher replaces the cached one straight away, since the
+//! session server keeps serving the old skin for a while.
+
+use base64::Engine;
+use image::{ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+const PROFILE_URL: &str = "https://sessionserver.mojang.com/session/minecraft/profile";
+const CACHE_TTL_HOURS: i64 = 24;
+const STEVE_HEAD: &[u8] = include_bytes!("../resources/skins/steve_head.png");
+const ALEX_HEAD: &[u8] = include_bytes!("../resources/skins/alex_head.png");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkinModel {
+    /// Steve's 4 pixel wide arms
+    Classic,
+    /// Alex's 3 pixel wide arms
+    Slim,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSkin {
+    pub uuid: String,
+    /// Full skin texture, unset for the default skins
+    pub skin_path: Option<String>,
+    /// 8x8 face with the hat layer on top
+    pub head_path: String,
+    pub model: SkinModel,
+    /// The player has no skin of their own, or it couldn't be fetched
+    pub is_default: bool,
+    /// RFC 3339, unset for defaults that weren't looked up
+    pub fetched_at: Option<String>,
+}
+
+/// Session server profile, only the fields skins need
+#[derive(Debug, Deserialize)]
+struct ProfileResponse {
+    #[serde(default)]
+    properties: Vec<ProfileProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileProperty {
+    name: String,
+    value: String,
+}
+
+/// Base64 decoded `textures` property
+#[derive(Debug, Deserialize)]
+struct TexturesPayload {
+    textures: Textures,
+}
+
+#[derive(Debug, Deserialize)]
+struct Textures {
+    #[serde(rename = "SKIN")]
+    skin: Option<SkinTexture>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SkinTexture {
+    url: String,
+    metadata: Option<SkinMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SkinMetadata {
+    model: Option<String>,
+}
+
+fn skins_dir() -> PathBuf {
+    crate::storage::get_launcher_dir().join("skins")
+}
+
+/// Skin and head of the player with `uuid`, never failing
+///
+/// A cached skin younger than a day is used unless `refresh` is set. When the
+/// session server can't be reached an older cached skin is still returned.
+pub async fn player_skin(uuid: &str, refresh: bool) -> PlayerSkin {
+    let Ok(parsed) = uuid::Uuid::parse_str(uuid.trim()) else {
+        println!("⚠️  Not a player UUID: {}", uuid);
+        return default_skin(uuid, SkinModel::Classic, None).await;
+    };
+    let id = parsed.simple().to_string();
+    let dir = skins_dir();
+    let cached = read_cached(&dir, &id).await;
+
+    if let Some(skin) = cached.as_ref().filter(|skin| !refresh && is_fresh(skin)) {
+        return skin.clone();
+    }
+
+    match fetch_skin(&dir, &id, parsed).await {
+        Ok(skin) => {
+            if let Err(e) = write_cached(&dir, &skin).await {
+                println!("⚠️  Failed to cache skin of {}: {}", id, e);
+            }
+            skin
+        }
+        Err(e) => {
+            println!("⚠️  Couldn't fetch skin of {}: {}", id, e);
+            match cached {
+                Some(skin) => skin,
+                None => default_skin(&id, default_model(parsed), None).await,
+            }
+        }
+    }
+}
+
+async fn fetch_skin(dir: &Path, id: &str, uuid: uuid::Uuid) -> Result<PlayerSkin, String> {
+    let client = crate::http::client();
+    let response = client.get(format!("{}/{}", PROFILE_URL, id)).send().await
+        .map_err(|e| format!("Failed to reach the session server: {}", e))?;
+    // 204 means there's no such player
+    if response.status() != reqwest::StatusCode::OK {
+        return Err(format!("Session server answered {}", response.status()));
+    }
+    let profile: ProfileResponse = response.json().await
+        .map_err(|e| format!("Invalid profile: {}", e))?;
+    let fetched_at = Some(chrono::Utc::now().to_rfc3339());
+
+    let Some((url, model)) = skin_texture(&profile)? else {
+        return Ok(default_skin(id, default_model(uuid), fetched_at).await);
+    };
+    skin_from_url(dir, &client, id, &url, model).await
+}
+
+/// Remember that the player with `uuid` now wears the skin at `url`
+///
+/// Used after changing a skin, falls back to forgetting the cached one.
+pub async fn cache_active_skin(uuid: &str, url: &str, model: SkinModel) {
+    let Ok(parsed) = uuid::Uuid::parse_str(uuid.trim()) else {
+        return;
+    };
+    let id = parsed.simple().to_string();
+    let dir = skins_dir();
+    let cached = skin_from_url(&dir, &crate::http::client(), &id, url, model).await;
+    let result = match cached {
+        Ok(skin) => write_cached(&dir, &skin).await,
+        Err(e) => {
+            println!("⚠️  Couldn't cache new skin of {}: {}", id, e);
+            forget(&dir, &id).await
+        }
+    };
+    if let Err(e) = result {
+        println!("⚠️  Failed to update cached skin of {}: {}", id, e);
+    }
+}
+
+/// Drop the cached skin of `uuid` so the next lookup asks the session server
+pub async fn forget_skin(uuid: &str) {
+    let Ok(parsed) = uuid::Uuid::parse_str(uuid.trim()) else {
+        return;
+    };
+    if let Err(e) = forget(&skins_dir(), &parsed.simple().to_string()).await {
+        println!("⚠️  Failed to forget cached skin of {}: {}", uuid, e);
+    }
+}
+
+async fn forget(dir: &Path, id: &str) -> std::io::Result<()> {
+    match tokio::fs::remove_file(dir.join("players").join(format!("{}.json", id))).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Download the skin at `url` and render its head, reusing files of the same texture
+async fn skin_from_url(dir: &Path, client: &reqwest::Client, id: &str, url: &str, model: SkinModel) -> Result<PlayerSkin, String> {
+    // The last path segment is the texture's hash, so a skin is only downloaded once
+    let hash = url.rsplit('/').next().filter(|hash| hash.chars().all(|c| c.is_ascii_alphanumeric()) && !hash.is_empty())
+        .ok_or_else(|| format!("Unexpected skin URL {}", url))?;
+    let skin_path = dir.join("textures").join(format!("{}.png", hash));
+    let head_path = dir.join("heads").join(format!("{}.png", hash));
+
+    if !skin_path.exists() {
+        // Texture URLs are handed out as plain HTTP, the same URL works over HTTPS
+        let url = match url.strip_prefix("http://") {
+            Some(rest) => format!("https://{}", rest),
+            None => url.to_string(),
+        };
+        crate::net::download(&url, &skin_path, crate::net::DownloadOptions { client: Some(client.clone()), ..Default::default() }).await
+            .map_err(|e| format!("Failed to download skin: {}", e))?;
+    }
+    if !head_path.exists() {
+        let bytes = tokio::fs::read(&skin_path).await.map_err(|e| e.to_string())?;
+        let head = tokio::task::spawn_blocking(move || render_head(&bytes)).await
+            .map_err(|e| e.to_string())??;
+        write_file(&head_path, &head).await.map_err(|e| e.to_string())?;
+    }
+
+    Ok(PlayerSkin {
+        uuid: id.to_string(),
+        skin_path: Some(skin_path.to_string_lossy().to_string()),
+        head_path: head_path.to_string_lossy().to_string(),
+        model,
+        is_default: false,
+        fetched_at: Some(chrono::Utc::now().to_rfc3339()),
+    })
+}
+
+/// Skin URL and model from a profile, None when the player wears a default skin
+fn skin_texture(profile: &ProfileResponse) -> Result<Option<(String, SkinModel)>, String> {
+    let Some(property) = profile.properties.iter().find(|property| property.name == "textures") else {
+        return Ok(None);
+    };
+    let decoded = base64::engine::general_purpose::STANDARD.decode(&property.value)
+        .map_err(|e| format!("Invalid textures property: {}", e))?;
+    let payload: TexturesPayload = serde_json::from_slice(&decoded)
+        .map_err(|e| format!("Invalid textures property: {}", e))?;
+
+    Ok(payload.textures.skin.map(|skin| {
+        let slim = skin.metadata.and_then(|metadata| metadata.model).as_deref() == Some("slim");
+        (skin.url, if slim { SkinModel::Slim } else { SkinModel::Classic })
+    }))
+}
+
+/// Check a file is a skin Minecraft accepts: a 64x64 or 64x32 PNG
+pub fn validate_skin(png: &[u8]) -> Result<(), String> {
+    decode_skin(png).map(|_| ())
+}
+
+fn decode_skin(png: &[u8]) -> Result<RgbaImage, String> {
+    let skin = image::load_from_memory_with_format(png, ImageFormat::Png)
+        .map_err(|e| format!("Not a PNG image: {}", e))?
+        .to_rgba8();
+    if skin.width() != 64 || !matches!(skin.height(), 32 | 64) {
+        return Err(format!("Skins are 64x64 or 64x32 pixels, this image is {}x{}", skin.width(), skin.height()));
+    }
+    Ok(skin)
+}
+
+/// The face of a skin with its hat layer on top, as an 8x8 PNG
+fn render_head(skin_png: &[u8]) -> Result<Vec<u8>, String> {
+    let skin = decode_skin(skin_png)?;
+
+    let mut head: RgbaImage = image::imageops::crop_imm(&skin, 8, 8, 8, 8).to_image();
+    let hat = image::imageops::crop_imm(&skin, 40, 8, 8, 8).to_image();
+    // Old 64x32 skins often fill the hat with a solid colour, the game hides it then
+    let solid_legacy_hat = skin.height() == 32 && hat.pixels().all(|pixel| pixel[3] == 255);
+    if !solid_legacy_hat {
+        image::imageops::overlay(&mut head, &hat, 0, 0);
+    }
+
+    let mut png = Vec::new();
+    head.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode head: {}", e))?;
+    Ok(png)
+}
+
+/// Alex or Steve, picked from the UUID the way the game does
+fn default_model(uuid: uuid::Uuid) -> SkinModel {
+    // Java's UUID.hashCode
+    let bits = uuid.as_u128();
+    let hilo = (bits >> 64) as u64 ^ bits as u64;
+    let hash = (hilo >> 32) as u32 ^ hilo as u32;
+    if hash & 1 == 1 { SkinModel::Slim } else { SkinModel::Classic }
+}
+
+/// The bundled head for `model`, written out to the skins folder on first use
+async fn default_skin(uuid: &str, model: SkinModel, fetched_at: Option<String>) -> PlayerSkin {
+    let (name, bytes) = match model {
+        SkinModel::Classic => ("steve_head.png", STEVE_HEAD),
+        SkinModel::Slim => ("alex_head.png", ALEX_HEAD),
+    };
+    let path = skins_dir().join("default").join(name);
+    if !path.exists() {
+        if let Err(e) = write_file(&path, bytes).await {
+            println!("⚠️  Failed to write default skin {}: {}", path.display(), e);
+        }
+    }
+
+    PlayerSkin {
+        uuid: uuid.to_string(),
+        skin_path: None,
+        head_path: path.to_string_lossy().to_string(),
+        model,
+        is_default: true,
+        fetched_at,
+    }
+}
+
+fn is_fresh(skin: &PlayerSkin) -> bool {
+    let fetched_at = skin.fetched_at.as_deref()
+        .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok());
+    let files_exist = Path::new(&skin.head_path).exists()
+        && skin.skin_path.as_deref().is_none_or(|path| Path::new(path).exists());
+    files_exist && fetched_at.is_some_and(|time| chrono::Utc::now() - time.to_utc() < chrono::Duration::hours(CACHE_TTL_HOURS))
+}
+
+async fn read_cached(dir: &Path, id: &str) -> Option<PlayerSkin> {
+    let json = tokio::fs::read_to_string(dir.join("players").join(format!("{}.json", id))).await.ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+async fn write_cached(dir: &Path, skin: &PlayerSkin) -> std::io::Result<()> {
+    let json = serde_json::to_vec_pretty(skin)?;
+    write_file(&dir.join("players").join(format!("{}.json", skin.uuid)), &json).await
+}
+
+/// Write through a temporary file so a reader never sees half a file
+async fn write_file(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    tokio::fs::write(&tmp_path, bytes).await?;
+    tokio::fs::rename(&tmp_path, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(image: &RgbaImage) -> Vec<u8> {
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).unwrap();
+        png
+    }
+
+    #[test]
+    fn textures_property_gives_the_skin_url_and_model() {
+        let payload = r#"{"textures":{"SKIN":{"url":"http://textures.minecraft.net/texture/abc123","metadata":{"model":"slim"}}}}"#;
+        let profile = ProfileResponse {
+            properties: vec![ProfileProperty {
+                name: "textures".to_string(),
+                value: base64::engine::general_purpose::STANDARD.encode(payload),
+            }],
+        };
+        assert_eq!(
+            skin_texture(&profile).unwrap(),
+            Some(("http://textures.minecraft.net/texture/abc123".to_string(), SkinModel::Slim))
+        );
+
+        let no_skin = ProfileResponse { properties: Vec::new() };
+        assert_eq!(skin_texture(&no_skin).unwrap(), None);
+    }
+
+    #[test]
+    fn heads_put_the_hat_over_the_face() {
+        let face = image::Rgba([200, 150, 100, 255]);
+        let hat = image::Rgba([10, 20, 30, 255]);
+        let mut skin = RgbaImage::new(64, 64);
+        for (x, y, pixel) in skin.enumerate_pixels_mut() {
+            if (8..16).contains(&x) && (8..16).contains(&y) {
+                *pixel = face;
+            } else if (40..48).contains(&x) && y == 8 {
+                *pixel = hat;
+            }
+        }
+
+        let head = image::load_from_memory(&render_head(&encode(&skin)).unwrap()).unwrap().to_rgba8();
+        assert_eq!(head.dimensions(), (8, 8));
+        assert_eq!(*head.get_pixel(3, 0), hat);
+        assert_eq!(*head.get_pixel(3, 1), face);
+
+        // A legacy skin with a solid hat layer shows the bare face
+        let mut legacy = RgbaImage::from_pixel(64, 32, hat);
+        for y in 8..16 {
+            for x in 8..16 {
+                legacy.put_pixel(x, y, face);
+            }
+        }
+        let head = image::load_from_memory(&render_head(&encode(&legacy)).unwrap()).unwrap().to_rgba8();
+        assert_eq!(*head.get_pixel(0, 0), face);
+
+        assert!(render_head(&encode(&RgbaImage::new(32, 32))).is_err());
+    }
+
+    #[test]
+    fn default_model_follows_the_uuid_hash() {
+        // Notch's UUID hashes to an even number, jeb_'s to an odd one
+        assert_eq!(default_model(uuid::Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()), SkinModel::Classic);
+        assert_eq!(default_model(uuid::Uuid::parse_str("853c80ef-3c37-49fd-aa49-938b674adae6").unwrap()), SkinModel::Slim);
+    }
+}
@@ -1,10 +1,22 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{OnceCell, RwLock};
 use anyhow::{Result, Context};
-use crate::minecraft::MinecraftInstance;
+use crate::minecraft::{MinecraftInstance, timestamps};
+use crate::minecraft::crashes::{CrashSignature, InstanceStability, SessionOutcome};
+use crate::minecraft::servers::{self, RecentServer, ServerAddress};
+use crate::minecraft::integrity::InstanceHealth;
+use crate::minecraft::jvm::JvmArgsPreset;
+use crate::net::DownloadMirror;
+use crate::http::ProxySettings;
 use crate::docker::types::{DockerConnection, ServerInstance};
+use crate::secrets::TokenStorage;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InstanceMetadata {
@@ -17,6 +29,7 @@ pub struct InstanceMetadata {
     pub java_path: Option<String>,
     pub jvm_args: Option<Vec<String>>,
     pub last_played: Option<String>,
+    /// Seconds spent in game
     pub total_play_time: u64,
     pub icon: Option<String>,
     pub is_modded: bool,
@@ -27,15 +40,58 @@ pub struct InstanceMetadata {
     pub tags: Vec<String>,
     pub resolved_java_version: Option<u32>,
     pub java_analysis_date: Option<String>,
+    /// Per-instance override of the launcher-wide log cleanup policy
+    #[serde(default)]
+    pub log_cleanup: Option<LogCleanupPolicy>,
+    /// Extra game arguments appended after the generated ones at launch
+    #[serde(default)]
+    pub game_args: Option<Vec<String>>,
+    /// Shell command run in the instance folder before launching, a failure aborts the launch
+    #[serde(default)]
+    pub pre_launch_command: Option<String>,
+    /// Shell command run in the instance folder after the game exits
+    #[serde(default)]
+    pub post_exit_command: Option<String>,
+    /// Environment variables set for the game on top of the launcher's own
+    #[serde(default)]
+    pub env_vars: Option<HashMap<String, String>>,
+    /// Start the game with only `env_vars`, none of the launcher's environment
+    #[serde(default)]
+    pub clear_inherited_env: bool,
+    /// Window size override, None uses the launcher setting and 0 lets the game decide
+    #[serde(default)]
+    pub window_width: Option<u32>,
+    #[serde(default)]
+    pub window_height: Option<u32>,
+    /// Fullscreen override, None uses the launcher setting
+    #[serde(default)]
+    pub fullscreen: Option<bool>,
+    /// JVM argument preset override, None uses the launcher setting
+    #[serde(default)]
+    pub jvm_args_preset: Option<JvmArgsPreset>,
+    /// How past sessions ended, tracked by the launcher
+    #[serde(default)]
+    pub stability: InstanceStability,
+    /// Servers launched into, most recent first, tracked by the launcher
+    #[serde(default)]
+    pub recent_servers: Vec<RecentServer>,
+    /// Last integrity check, tracked by the launcher
+    #[serde(default)]
+    pub health: Option<InstanceHealth>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LauncherConfig {
     pub instances: HashMap<String, InstanceMetadata>,
     pub docker_connections: HashMap<String, DockerConnection>,
     pub servers: HashMap<String, ServerInstance>,
     pub settings: LauncherSettings,
     pub version: String,
+    /// Schema version, upgraded by `crate::migrations` on load
+    pub config_version: u32,
+    /// Launcher directory the paths in this config were written for
+    #[serde(default)]
+    pub data_root: Option<PathBuf>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -52,6 +108,222 @@ pub struct LauncherSettings {
     pub secondary_base_color: Option<String>,
     pub auto_update: bool,
     pub auth_token: Option<String>,
+    #[serde(default)]
+    pub log_cleanup: LogCleanupPolicy,
+    /// Use the in-memory mock Docker backend instead of a real daemon
+    #[serde(default)]
+    pub docker_mock: bool,
+    /// Key for the CurseForge API, which refuses requests without one
+    #[serde(default)]
+    pub curseforge_api_key: Option<String>,
+    /// Number of asset objects downloaded at once when installing a version
+    #[serde(default = "default_max_concurrent_downloads")]
+    pub max_concurrent_downloads: u32,
+    #[serde(default)]
+    pub docker_timeouts: DockerTimeouts,
+    /// Only use Java runtimes the launcher installed, never system installs
+    #[serde(default)]
+    pub java_bundled_only: bool,
+    /// Seconds the Microsoft sign-in waits for the browser before giving up
+    #[serde(default = "default_oauth_timeout_secs")]
+    pub oauth_timeout_secs: u64,
+    /// Number of modpack files downloaded at once when installing a pack
+    #[serde(default = "default_modpack_download_concurrency")]
+    pub modpack_download_concurrency: u32,
+    /// Show OS notifications when long-running work finishes
+    #[serde(default = "default_notifications_enabled")]
+    pub notifications_enabled: bool,
+    #[serde(default)]
+    pub notification_categories: NotificationCategories,
+    /// Size limit of the shared download cache, 0 turns it off
+    #[serde(default = "default_download_cache_max_mb")]
+    pub download_cache_max_mb: u64,
+    /// Allow launching an instance that is already running
+    #[serde(default)]
+    pub allow_multiple_instances: bool,
+    /// Ask before closing while tasks, installs or games are running
+    #[serde(default)]
+    pub confirm_exit_with_background_work: bool,
+    /// Seconds a pre-launch or post-exit hook may run before it is killed
+    #[serde(default = "default_hook_timeout_secs")]
+    pub hook_timeout_secs: u64,
+    /// List recently played instances in the taskbar or dock menu
+    #[serde(default = "default_jump_list_enabled")]
+    pub jump_list_enabled: bool,
+    /// Game window size for instances without their own, 0 lets the game decide
+    #[serde(default)]
+    pub window_width: u32,
+    #[serde(default)]
+    pub window_height: u32,
+    /// Start games fullscreen unless the instance says otherwise
+    #[serde(default)]
+    pub fullscreen: bool,
+    /// Days uninstalled mods stay in an instance's mod trash
+    #[serde(default = "default_mod_trash_retention_days")]
+    pub mod_trash_retention_days: u32,
+    /// Microsoft account launches use, the first stored one when unset or removed
+    #[serde(default)]
+    pub active_account_uuid: Option<String>,
+    /// Check instances in the background while the launcher is idle
+    #[serde(default)]
+    pub integrity_sweep_enabled: bool,
+    /// Pause between files the sweep checks, higher is gentler on the disk
+    #[serde(default = "default_integrity_sweep_pace_ms")]
+    pub integrity_sweep_pace_ms: u32,
+    /// Launch without a Microsoft account as `offline_username`, for LAN play and testing
+    #[serde(default)]
+    pub allow_offline_launch: bool,
+    #[serde(default)]
+    pub offline_username: Option<String>,
+    /// Ask before a hook runs a command string for the first time
+    #[serde(default)]
+    pub confirm_external_commands: bool,
+    /// Check the OpenGL driver before launching and warn about software rendering
+    #[serde(default = "default_probe_graphics_before_launch")]
+    pub probe_graphics_before_launch: bool,
+    /// Extra folders searched for instances whose stored path no longer exists
+    #[serde(default)]
+    pub instance_search_roots: Vec<PathBuf>,
+    /// Where account tokens are kept, the encrypted file is for Linux without a Secret Service
+    #[serde(default)]
+    pub token_storage: TokenStorage,
+    /// Host ports servers deployed with `auto_assign_port` are given from
+    #[serde(default)]
+    pub server_port_range: ServerPortRange,
+    /// JVM flags games launch with unless the instance picks its own
+    #[serde(default)]
+    pub jvm_args_preset: JvmArgsPreset,
+    /// Mirror game files, loader metadata and libraries are downloaded from
+    #[serde(default)]
+    pub download_mirror: DownloadMirror,
+    /// Proxy every HTTP request goes through
+    #[serde(default)]
+    pub proxy: ProxySettings,
+}
+
+fn default_integrity_sweep_pace_ms() -> u32 {
+    crate::minecraft::integrity::DEFAULT_PACE_MS
+}
+
+fn default_max_concurrent_downloads() -> u32 {
+    crate::minecraft::commands::versions::DEFAULT_ASSET_CONCURRENCY
+}
+
+fn default_modpack_download_concurrency() -> u32 {
+    crate::modpack::types::DEFAULT_DOWNLOAD_CONCURRENCY
+}
+
+fn default_download_cache_max_mb() -> u64 {
+    crate::net::cache::DEFAULT_MAX_MB
+}
+
+fn default_hook_timeout_secs() -> u64 {
+    crate::minecraft::hooks::DEFAULT_TIMEOUT_SECS
+}
+
+pub(crate) fn default_oauth_timeout_secs() -> u64 {
+    300
+}
+
+fn default_notifications_enabled() -> bool {
+    true
+}
+
+fn default_jump_list_enabled() -> bool {
+    true
+}
+
+fn default_probe_graphics_before_launch() -> bool {
+    true
+}
+
+fn default_mod_trash_retention_days() -> u32 {
+    crate::mods::trash::DEFAULT_RETENTION_DAYS
+}
+
+/// Which kinds of notifications are shown while notifications are enabled
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationCategories {
+    /// Modpacks, mods and Java runtimes
+    pub installs: bool,
+    pub servers: bool,
+    pub updates: bool,
+}
+
+impl Default for NotificationCategories {
+    fn default() -> Self {
+        Self {
+            installs: true,
+            servers: true,
+            updates: true,
+        }
+    }
+}
+
+/// Limits applied when cleaning up rotated logs and crash reports
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogCleanupPolicy {
+    /// Maximum combined size of logs/ and crash-reports/ in megabytes
+    pub max_total_size_mb: Option<u64>,
+    /// Maximum age of rotated logs and crash reports in days
+    pub max_age_days: Option<u32>,
+}
+
+/// Time limits for Docker API calls, in seconds
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DockerTimeouts {
+    /// Reaching the daemon, used for pings
+    pub connect_secs: u64,
+    /// Ordinary API requests like starting or inspecting a container
+    pub request_secs: u64,
+    /// Whole image pulls, which also fail after `request_secs` without progress
+    pub pull_secs: u64,
+}
+
+impl Default for DockerTimeouts {
+    fn default() -> Self {
+        Self {
+            connect_secs: 5,
+            request_secs: 30,
+            pull_secs: 30 * 60,
+        }
+    }
+}
+
+impl DockerTimeouts {
+    pub fn connect(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.connect_secs.max(1))
+    }
+
+    pub fn request(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.request_secs.max(1))
+    }
+
+    pub fn pull(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.pull_secs.max(1))
+    }
+}
+
+/// Inclusive range of host ports automatically given to servers
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ServerPortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Default for ServerPortRange {
+    fn default() -> Self {
+        Self { start: 25565, end: 25665 }
+    }
+}
+
+impl Default for LogCleanupPolicy {
+    fn default() -> Self {
+        Self {
+            max_total_size_mb: Some(500),
+            max_age_days: Some(30),
+        }
+    }
 }
 
 impl Default for LauncherSettings {
@@ -77,6 +349,38 @@ impl Default for LauncherSettings {
             secondary_base_color: Some("#d97706".to_string()),
             auto_update: true,
             auth_token: None,
+            log_cleanup: LogCleanupPolicy::default(),
+            docker_mock: false,
+            curseforge_api_key: None,
+            max_concurrent_downloads: default_max_concurrent_downloads(),
+            docker_timeouts: DockerTimeouts::default(),
+            java_bundled_only: false,
+            oauth_timeout_secs: default_oauth_timeout_secs(),
+            modpack_download_concurrency: default_modpack_download_concurrency(),
+            notifications_enabled: default_notifications_enabled(),
+            notification_categories: NotificationCategories::default(),
+            download_cache_max_mb: default_download_cache_max_mb(),
+            allow_multiple_instances: false,
+            confirm_exit_with_background_work: false,
+            hook_timeout_secs: default_hook_timeout_secs(),
+            jump_list_enabled: default_jump_list_enabled(),
+            window_width: 0,
+            window_height: 0,
+            fullscreen: false,
+            mod_trash_retention_days: default_mod_trash_retention_days(),
+            active_account_uuid: None,
+            integrity_sweep_enabled: false,
+            integrity_sweep_pace_ms: default_integrity_sweep_pace_ms(),
+            allow_offline_launch: false,
+            offline_username: None,
+            confirm_external_commands: false,
+            probe_graphics_before_launch: true,
+            instance_search_roots: Vec::new(),
+            token_storage: TokenStorage::Keyring,
+            server_port_range: ServerPortRange::default(),
+            jvm_args_preset: JvmArgsPreset::default(),
+            download_mirror: DownloadMirror::default(),
+            proxy: ProxySettings::default(),
         }
     }
 }
@@ -89,13 +393,142 @@ impl Default for LauncherConfig {
             servers: HashMap::new(),
             settings: LauncherSettings::default(),
             version: "2.1.0".to_string(),
+            config_version: crate::migrations::CURRENT_CONFIG_VERSION,
+            data_root: Some(get_launcher_dir()),
         }
     }
 }
 
+impl LauncherConfig {
+    /// Move every stored path under `from` to the same place under `to`
+    fn rebase_paths(&mut self, from: &Path, to: &Path) {
+        if from != to {
+            println!("💼 Moving stored paths from {} to {}", from.display(), to.display());
+        }
+
+        let rebase = |path: &mut PathBuf| {
+            if let Ok(relative) = path.strip_prefix(from) {
+                *path = to.join(relative);
+            }
+        };
+        let rebase_str = |path: &mut Option<String>| {
+            if let Some(value) = path.as_mut() {
+                if let Ok(relative) = Path::new(value.as_str()).strip_prefix(from) {
+                    *value = to.join(relative).to_string_lossy().to_string();
+                }
+            }
+        };
+
+        rebase(&mut self.settings.instances_dir);
+        rebase(&mut self.settings.downloads_dir);
+        rebase_str(&mut self.settings.default_java_path);
+        for instance in self.instances.values_mut() {
+            rebase(&mut instance.game_dir);
+            rebase_str(&mut instance.java_path);
+        }
+
+        self.data_root = Some(to.to_path_buf());
+    }
+}
+
+/// Launcher storage shared by every command, managed as Tauri state
+pub type StorageState = Arc<RwLock<StorageManager>>;
+
+/// Another instance already uses the game directory being saved
+#[derive(Debug, thiserror::Error)]
+#[error("Instance '{existing_name}' ({existing_id}) already uses {}", game_dir.display())]
+pub struct DuplicateGameDir {
+    pub game_dir: PathBuf,
+    pub existing_id: String,
+    pub existing_name: String,
+}
+
+/// Instances found pointing at the same game directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateInstances {
+    pub game_dir: PathBuf,
+    pub instance_ids: Vec<String>,
+}
+
+static SHARED_STORAGE: OnceCell<StorageState> = OnceCell::const_new();
+
+/// The storage for this session, loaded from disk on first use
+///
+/// Commands get the same instance through `State<'_, StorageState>`, code without
+/// an `AppHandle` uses this directly.
+pub async fn shared() -> Result<StorageState> {
+    SHARED_STORAGE.get_or_try_init(|| async {
+        let storage = StorageManager::new().await?;
+        Ok::<_, anyhow::Error>(Arc::new(RwLock::new(storage)))
+    })
+    .await
+    .cloned()
+}
+
+/// How long mutations are collected before they are written in one save
+pub const SAVE_COALESCE_WINDOW: Duration = Duration::from_millis(250);
+
+/// Batches config saves so a burst of mutations becomes a single write
+///
+/// Each save replaces the pending snapshot; the first one in a window schedules
+/// a flush. Writes go through a temporary file, so a crash leaves either the old
+/// or the new config on disk, never a mix.
+struct ConfigWriter {
+    path: PathBuf,
+    pending: std::sync::Mutex<Option<LauncherConfig>>,
+    scheduled: AtomicBool,
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl ConfigWriter {
+    fn new(path: PathBuf) -> Arc<Self> {
+        Arc::new(Self {
+            path,
+            pending: std::sync::Mutex::new(None),
+            scheduled: AtomicBool::new(false),
+            write_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Queue a snapshot and schedule a flush unless one is already waiting
+    fn queue(self: &Arc<Self>, config: LauncherConfig) {
+        *self.pending.lock().unwrap() = Some(config);
+        if self.scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let writer = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(SAVE_COALESCE_WINDOW).await;
+            writer.scheduled.store(false, Ordering::SeqCst);
+            if let Err(e) = writer.flush().await {
+                eprintln!("⚠️  Failed to save launcher config: {:#}", e);
+            }
+        });
+    }
+
+    /// Write the latest pending snapshot, if any
+    ///
+    /// A failed write puts the snapshot back so the next flush retries it.
+    async fn flush(&self) -> Result<()> {
+        // Holding the write lock while taking the snapshot keeps an older one
+        // from landing on disk after a newer one
+        let _guard = self.write_lock.lock().await;
+        let Some(config) = self.pending.lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        let result = StorageManager::save_config(&self.path, &config).await;
+        if result.is_err() {
+            self.pending.lock().unwrap().get_or_insert(config);
+        }
+        result
+    }
+}
+
 pub struct StorageManager {
-    config_path: PathBuf,
     config: LauncherConfig,
+    writer: Arc<ConfigWriter>,
 }
 
 impl StorageManager {
@@ -109,88 +542,71 @@ impl StorageManager {
         }
 
         let config = if config_path.exists() {
-            Self::load_config(&config_path).await?
+            let mut config = Self::load_config(&config_path).await?;
+
+            // Configs copied between profile and portable data point at the old root
+            let data_root = config.data_root.clone()
+                .unwrap_or_else(crate::portable::profile_launcher_dir);
+            let launcher_dir = get_launcher_dir();
+            if data_root != launcher_dir || config.data_root.is_none() {
+                config.rebase_paths(&data_root, &launcher_dir);
+                Self::save_config(&config_path, &config).await?;
+            }
+            config
         } else {
             let default_config = LauncherConfig::default();
             Self::save_config(&config_path, &default_config).await?;
             default_config
         };
 
-        // If we just migrated (version is 2.1.0 and we loaded from existing file), save the migrated config
-        if config_path.exists() && config.version == "2.1.0" {
-            // Check if this is a fresh migration by looking for the docker_connections field in the raw file
-            let raw_content = fs::read_to_string(&config_path).await?;
-            if !raw_content.contains("docker_connections") {
-                Self::save_config(&config_path, &config).await?;
-                println!("💾 Saved migrated config to disk");
-            }
-        }
-
         // Ensure essential directories exist
         fs::create_dir_all(&config.settings.instances_dir).await
             .context("Failed to create instances directory")?;
         fs::create_dir_all(&config.settings.downloads_dir).await
             .context("Failed to create downloads directory")?;
 
-        Ok(Self { config_path, config })
+        Ok(Self { config, writer: ConfigWriter::new(config_path) })
     }
 
     async fn load_config(path: &PathBuf) -> Result<LauncherConfig> {
+        // Bring older configs up to the current schema before parsing
+        crate::migrations::migrate_config_file(path, &get_migration_log_path()).await?;
+
         let content = fs::read_to_string(path).await
             .context("Failed to read config file")?;
-        
-        // First, try to parse as the new format
-        match serde_json::from_str::<LauncherConfig>(&content) {
-            Ok(config) => Ok(config),
-            Err(_) => {
-                // If that fails, try to parse as the old format and migrate
-                let old_config: serde_json::Value = serde_json::from_str(&content)
-                    .context("Failed to parse config file as JSON")?;
-                
-                Self::migrate_config(old_config)
-            }
-        }
-    }
-
-    fn migrate_config(old_config: serde_json::Value) -> Result<LauncherConfig> {
-        // Create a new config with the old data + new fields
-        let mut new_config = LauncherConfig::default();
-        
-        // Migrate instances if they exist
-        if let Some(instances) = old_config.get("instances") {
-            if let Ok(instances_map) = serde_json::from_value::<HashMap<String, InstanceMetadata>>(instances.clone()) {
-                println!("📦 Migrated {} instances", instances_map.len());
-                new_config.instances = instances_map;
-            }
-        }
-        
-        // Migrate settings if they exist
-        if let Some(settings) = old_config.get("settings") {
-            if let Ok(settings_struct) = serde_json::from_value::<LauncherSettings>(settings.clone()) {
-                new_config.settings = settings_struct;
-                println!("⚙️  Migrated launcher settings");
-            }
-        }
-        
-        // Version is now "2.1.0" to indicate migration
-        new_config.version = "2.1.0".to_string();
-        
-        println!("✅ Successfully migrated config from v2.0.0 to v2.1.0");
-        println!("   Added support for Docker connections and server management");
-        
-        Ok(new_config)
+        serde_json::from_str::<LauncherConfig>(&content)
+            .context("Failed to parse config file")
     }
 
+    /// Write the config to a temporary file and rename it over the old one
     async fn save_config(path: &PathBuf, config: &LauncherConfig) -> Result<()> {
         let content = serde_json::to_string_pretty(config)
             .context("Failed to serialize config")?;
-        fs::write(path, content).await
+        let temp_path = path.with_extension("json.tmp");
+        let mut file = fs::File::create(&temp_path).await
+            .context("Failed to write config file")?;
+        file.write_all(content.as_bytes()).await
             .context("Failed to write config file")?;
+        // The data must be on disk before the rename makes it the live config
+        file.sync_all().await
+            .context("Failed to write config file")?;
+        drop(file);
+        fs::rename(&temp_path, path).await
+            .context("Failed to replace config file")?;
         Ok(())
     }
 
+    /// Queue the current config to be written with the next batch
+    ///
+    /// Use `flush` when the change has to be on disk before returning.
     pub async fn save(&self) -> Result<()> {
-        Self::save_config(&self.config_path, &self.config).await
+        self.writer.queue(self.config.clone());
+        Ok(())
+    }
+
+    /// Write any queued changes now
+    pub async fn flush(&self) -> Result<()> {
+        self.writer.flush().await
     }
 
     pub async fn add_instance(&mut self, instance: InstanceMetadata) -> Result<()> {
@@ -198,6 +614,7 @@ impl StorageManager {
         if instance.game_dir.as_os_str().is_empty() {
             return Err(anyhow::anyhow!("Instance game directory cannot be empty"));
         }
+        self.check_game_dir_unique(&instance)?;
         
         // Ensure instance directory exists
         fs::create_dir_all(&instance.game_dir).await
@@ -233,7 +650,20 @@ impl StorageManager {
             return Err(anyhow::anyhow!("Instance game directory cannot be empty"));
         }
         
-        if self.config.instances.contains_key(&instance.id) {
+        if let Some(current) = self.config.instances.get(&instance.id) {
+            self.check_game_dir_unique(&instance)?;
+            // Only the launcher records session outcomes, joined servers and health
+            let mut instance = InstanceMetadata {
+                stability: current.stability.clone(),
+                recent_servers: current.recent_servers.clone(),
+                health: current.health.clone(),
+                ..instance
+            };
+            // The cached Java version belongs to the version JSON it was read from
+            if instance.version != current.version {
+                instance.resolved_java_version = None;
+                instance.java_analysis_date = None;
+            }
             self.config.instances.insert(instance.id.clone(), instance);
             self.save().await
         } else {
@@ -241,6 +671,153 @@ impl StorageManager {
         }
     }
 
+    /// Reject a game directory that another instance already uses
+    ///
+    /// Instances that kept their directory are let through so duplicates from older
+    /// configs can still be saved until they're resolved.
+    fn check_game_dir_unique(&self, instance: &InstanceMetadata) -> Result<()> {
+        let game_dir = normalize_game_dir(&instance.game_dir);
+        if let Some(current) = self.config.instances.get(&instance.id) {
+            if normalize_game_dir(&current.game_dir) == game_dir {
+                return Ok(());
+            }
+        }
+
+        let existing = self.config.instances.values()
+            .find(|other| other.id != instance.id && normalize_game_dir(&other.game_dir) == game_dir);
+        match existing {
+            Some(existing) => Err(DuplicateGameDir {
+                game_dir: instance.game_dir.clone(),
+                existing_id: existing.id.clone(),
+                existing_name: existing.name.clone(),
+            }.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Groups of instances that share a game directory
+    pub fn find_duplicate_game_dirs(&self) -> Vec<DuplicateInstances> {
+        let mut by_dir: HashMap<PathBuf, Vec<&InstanceMetadata>> = HashMap::new();
+        for instance in self.config.instances.values() {
+            if instance.game_dir.as_os_str().is_empty() {
+                continue;
+            }
+            by_dir.entry(normalize_game_dir(&instance.game_dir)).or_default().push(instance);
+        }
+
+        let mut duplicates: Vec<DuplicateInstances> = by_dir.into_values()
+            .filter(|instances| instances.len() > 1)
+            .map(|mut instances| {
+                instances.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+                DuplicateInstances {
+                    game_dir: instances[0].game_dir.clone(),
+                    instance_ids: instances.iter().map(|i| i.id.clone()).collect(),
+                }
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.game_dir.cmp(&b.game_dir));
+        duplicates
+    }
+
+    /// Fold `remove_id` into `keep_id` when both use the same game directory
+    ///
+    /// Play time, last played and tags are merged and servers move over to the kept
+    /// instance. The shared directory is left alone. To keep both instances instead,
+    /// point one of them at another directory with `update_instance`.
+    pub async fn merge_duplicate_instance(&mut self, keep_id: &str, remove_id: &str) -> Result<InstanceMetadata> {
+        if keep_id == remove_id {
+            return Err(anyhow::anyhow!("Cannot merge an instance into itself"));
+        }
+        let removed = self.config.instances.get(remove_id).cloned()
+            .ok_or_else(|| anyhow::anyhow!("Instance not found: {}", remove_id))?;
+        let kept = self.config.instances.get_mut(keep_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance not found: {}", keep_id))?;
+
+        if normalize_game_dir(&kept.game_dir) != normalize_game_dir(&removed.game_dir) {
+            return Err(anyhow::anyhow!(
+                "Instances '{}' and '{}' use different game directories",
+                kept.name, removed.name
+            ));
+        }
+
+        kept.total_play_time += removed.total_play_time;
+        if removed.last_played > kept.last_played {
+            kept.last_played = removed.last_played.clone();
+        }
+        for tag in removed.tags {
+            if !kept.tags.contains(&tag) {
+                kept.tags.push(tag);
+            }
+        }
+        let kept = kept.clone();
+
+        for server in self.config.servers.values_mut() {
+            if server.minecraft_instance_id == remove_id {
+                server.minecraft_instance_id = keep_id.to_string();
+            }
+        }
+
+        self.config.instances.remove(remove_id);
+        self.save().await?;
+        Ok(kept)
+    }
+
+    /// Add a finished play session to an instance's play time and last played date
+    pub async fn record_play_session(&mut self, instance_id: &str, seconds: u64, ended_at: chrono::DateTime<chrono::Utc>) -> Result<InstanceMetadata> {
+        let instance = self.config.instances.get_mut(instance_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance not found: {}", instance_id))?;
+        instance.total_play_time += seconds;
+        instance.last_played = Some(ended_at.to_rfc3339());
+        let instance = instance.clone();
+
+        self.save().await?;
+        Ok(instance)
+    }
+
+    /// Count how a finished session of an instance ended
+    pub async fn record_session_outcome(
+        &mut self,
+        instance_id: &str,
+        outcome: SessionOutcome,
+        signature: Option<CrashSignature>,
+        ended_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let instance = self.config.instances.get_mut(instance_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance not found: {}", instance_id))?;
+        instance.stability.record(outcome, signature, &ended_at.to_rfc3339());
+        self.save().await
+    }
+
+    /// Put a server an instance launched into at the front of its recent servers
+    pub async fn record_server_join(&mut self, instance_id: &str, address: &ServerAddress, joined_at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        let instance = self.config.instances.get_mut(instance_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance not found: {}", instance_id))?;
+        servers::remember(&mut instance.recent_servers, address, &joined_at.to_rfc3339());
+        self.save().await
+    }
+
+    /// Cache the Java version read from an instance's version JSON
+    ///
+    /// Skipped when the instance moved to another version in the meantime.
+    pub async fn record_java_version(&mut self, instance_id: &str, version: &str, major: u32) -> Result<()> {
+        let instance = self.config.instances.get_mut(instance_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance not found: {}", instance_id))?;
+        if instance.version != version {
+            return Ok(());
+        }
+        instance.resolved_java_version = Some(major);
+        instance.java_analysis_date = Some(chrono::Utc::now().to_rfc3339());
+        self.save().await
+    }
+
+    /// Store the result of an integrity check of an instance
+    pub async fn record_instance_health(&mut self, instance_id: &str, health: InstanceHealth) -> Result<()> {
+        let instance = self.config.instances.get_mut(instance_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance not found: {}", instance_id))?;
+        instance.health = Some(health);
+        self.save().await
+    }
+
     pub fn get_instance(&self, instance_id: &str) -> Option<&InstanceMetadata> {
         self.config.instances.get(instance_id)
     }
@@ -270,7 +847,9 @@ impl StorageManager {
             .context("Failed to create new downloads directory")?;
 
         self.config.settings = settings;
-        self.save().await
+        // Settings apply to the next launch, which may come right after this
+        self.save().await?;
+        self.flush().await
     }
 
     pub async fn refresh_instance_sizes(&mut self) -> Result<()> {
@@ -324,6 +903,7 @@ impl StorageManager {
 
     pub async fn remove_docker_connection(&mut self, connection_id: &str) -> Result<()> {
         if self.config.docker_connections.remove(connection_id).is_some() {
+            crate::docker::tls::remove_files(connection_id).await;
             // Also remove any servers using this connection
             self.config.servers.retain(|_, server| server.docker_connection_id != connection_id);
             self.save().await
@@ -380,16 +960,43 @@ impl StorageManager {
 
 // Utility functions
 
+/// Launcher data directory for this session, portable or in the OS profile
 pub fn get_launcher_dir() -> PathBuf {
-    dirs::data_local_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("ChaiLauncher")
+    crate::portable::data_locations().launcher_dir.clone()
 }
 
 pub fn get_config_path() -> PathBuf {
     get_launcher_dir().join("config.json")
 }
 
+pub fn get_migration_log_path() -> PathBuf {
+    get_launcher_dir().join("migration_log.json")
+}
+
+/// Resolve a game directory for comparison, lexically when it doesn't exist yet
+fn normalize_game_dir(path: &Path) -> PathBuf {
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        return canonical;
+    }
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        normalized = PathBuf::from(normalized.to_string_lossy().to_lowercase());
+    }
+    normalized
+}
+
 async fn calculate_directory_size(path: &PathBuf) -> Result<u64> {
     let mut total_size = 0u64;
     let mut stack = vec![path.clone()];
@@ -481,17 +1088,267 @@ impl From<MinecraftInstance> for InstanceMetadata {
             game_dir: instance.game_dir,
             java_path: instance.java_path,
             jvm_args: instance.jvm_args,
-            last_played: instance.last_played,
+            last_played: instance.last_played.map(timestamps::to_rfc3339),
             total_play_time: instance.total_play_time,
             icon: instance.icon,
             is_modded: instance.is_modded,
             mods_count: instance.mods_count,
-            created_at: chrono::Utc::now().to_rfc3339(),
-            size_mb: None,
+            created_at: instance.created_at
+                .map(timestamps::to_rfc3339)
+                .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+            size_mb: instance.size_mb,
             description: None,
             tags: Vec::new(),
             resolved_java_version: instance.resolved_java_version,
-            java_analysis_date: instance.java_analysis_date,
+            java_analysis_date: instance.java_analysis_date.map(timestamps::to_rfc3339),
+            log_cleanup: instance.log_cleanup,
+            game_args: instance.game_args,
+            pre_launch_command: instance.pre_launch_command,
+            post_exit_command: instance.post_exit_command,
+            env_vars: instance.env_vars,
+            clear_inherited_env: instance.clear_inherited_env,
+            window_width: instance.window_width,
+            window_height: instance.window_height,
+            fullscreen: instance.fullscreen,
+            jvm_args_preset: instance.jvm_args_preset,
+            stability: InstanceStability::default(),
+            recent_servers: Vec::new(),
+            health: None,
+        }
+    }
+}
+
+/// Bare instance for tests; override fields with struct-update syntax
+#[cfg(test)]
+pub(crate) fn test_instance(id: &str, game_dir: PathBuf) -> InstanceMetadata {
+    InstanceMetadata {
+        id: id.to_string(),
+        name: id.to_string(),
+        version: "1.20.1".to_string(),
+        modpack: None,
+        modpack_version: None,
+        game_dir,
+        java_path: None,
+        jvm_args: None,
+        last_played: None,
+        total_play_time: 0,
+        icon: None,
+        is_modded: false,
+        mods_count: 0,
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+        size_mb: None,
+        description: None,
+        tags: Vec::new(),
+        resolved_java_version: None,
+        java_analysis_date: None,
+        log_cleanup: None,
+        game_args: None,
+        pre_launch_command: None,
+        post_exit_command: None,
+        env_vars: None,
+        clear_inherited_env: false,
+        window_width: None,
+        window_height: None,
+        fullscreen: None,
+        jvm_args_preset: None,
+        stability: Default::default(),
+        recent_servers: Vec::new(),
+        health: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn concurrent_add_instance_keeps_both() {
+        let dir = std::env::temp_dir().join(format!("chai-storage-{}", uuid::Uuid::new_v4()));
+        let storage: StorageState = Arc::new(RwLock::new(temp_storage(&dir)));
+
+        let adds = ["first", "second"].map(|id| {
+            let storage = storage.clone();
+            let instance = test_instance(id, dir.join(id));
+            tokio::spawn(async move { storage.write().await.add_instance(instance).await })
+        });
+        for add in adds {
+            add.await.unwrap().unwrap();
+        }
+        storage.read().await.flush().await.unwrap();
+
+        let saved = read_saved(&dir);
+        assert!(saved.instances.contains_key("first"));
+        assert!(saved.instances.contains_key("second"));
+        assert!(!dir.join("config.json.tmp").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn temp_storage(dir: &Path) -> StorageManager {
+        std::fs::create_dir_all(dir).unwrap();
+        StorageManager {
+            config: LauncherConfig::default(),
+            writer: ConfigWriter::new(dir.join("config.json")),
         }
     }
-}
\ No newline at end of file
+
+    fn read_saved(dir: &Path) -> LauncherConfig {
+        let content = std::fs::read_to_string(dir.join("config.json")).unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+
+    #[tokio::test]
+    async fn burst_of_saves_is_written_once_after_the_window() {
+        let dir = std::env::temp_dir().join(format!("chai-storage-{}", uuid::Uuid::new_v4()));
+        let mut storage = temp_storage(&dir);
+
+        for id in ["first", "second", "third"] {
+            storage.add_instance(test_instance(id, dir.join(id))).await.unwrap();
+        }
+        assert!(!dir.join("config.json").exists());
+
+        tokio::time::sleep(SAVE_COALESCE_WINDOW * 4).await;
+        let saved = read_saved(&dir);
+        assert_eq!(saved.instances.len(), 3);
+        assert!(!dir.join("config.json.tmp").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn flush_writes_latest_snapshot_and_survives_stale_temp_file() {
+        let dir = std::env::temp_dir().join(format!("chai-storage-{}", uuid::Uuid::new_v4()));
+        let mut storage = temp_storage(&dir);
+
+        storage.add_instance(test_instance("first", dir.join("first"))).await.unwrap();
+        storage.flush().await.unwrap();
+
+        // A crash during a write leaves a partial temp file next to the old config
+        std::fs::write(dir.join("config.json.tmp"), "{\"instances\": {").unwrap();
+        assert!(read_saved(&dir).instances.contains_key("first"));
+
+        storage.remove_instance("first").await.unwrap();
+        storage.add_instance(test_instance("second", dir.join("second"))).await.unwrap();
+        storage.flush().await.unwrap();
+
+        let saved = read_saved(&dir);
+        assert!(!saved.instances.contains_key("first"));
+        assert!(saved.instances.contains_key("second"));
+        assert!(!dir.join("config.json.tmp").exists());
+
+        // Nothing queued, nothing written
+        std::fs::remove_file(dir.join("config.json")).unwrap();
+        storage.flush().await.unwrap();
+        assert!(!dir.join("config.json").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_flush_keeps_changes_for_retry() {
+        let dir = std::env::temp_dir().join(format!("chai-storage-{}", uuid::Uuid::new_v4()));
+        let mut storage = StorageManager {
+            config: LauncherConfig::default(),
+            writer: ConfigWriter::new(dir.join("missing").join("config.json")),
+        };
+        std::fs::create_dir_all(&dir).unwrap();
+
+        storage.add_instance(test_instance("first", dir.join("first"))).await.unwrap();
+        assert!(storage.flush().await.is_err());
+
+        std::fs::create_dir_all(dir.join("missing")).unwrap();
+        storage.flush().await.unwrap();
+        assert!(read_saved(&dir.join("missing")).instances.contains_key("first"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn add_instance_rejects_shared_game_dir() {
+        let dir = std::env::temp_dir().join(format!("chai-storage-{}", uuid::Uuid::new_v4()));
+        let mut storage = temp_storage(&dir);
+
+        storage.add_instance(test_instance("first", dir.join("game"))).await.unwrap();
+        let err = storage.add_instance(test_instance("second", dir.join("./game/"))).await.unwrap_err();
+        let duplicate = err.downcast_ref::<DuplicateGameDir>().expect("typed duplicate error");
+        assert_eq!(duplicate.existing_id, "first");
+
+        // Saving the same instance again is not a conflict
+        storage.add_instance(test_instance("first", dir.join("game"))).await.unwrap();
+        assert!(storage.update_instance(test_instance("first", dir.join("game"))).await.is_ok());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn version_change_drops_cached_java_version() {
+        let dir = std::env::temp_dir().join(format!("chai-storage-{}", uuid::Uuid::new_v4()));
+        let mut storage = temp_storage(&dir);
+
+        storage.add_instance(test_instance("first", dir.join("first"))).await.unwrap();
+        storage.record_java_version("first", "1.20.1", 17).await.unwrap();
+        // Resolved for a version the instance no longer runs
+        storage.record_java_version("first", "1.19.4", 8).await.unwrap();
+        let cached = storage.get_instance("first").unwrap().clone();
+        assert_eq!(cached.resolved_java_version, Some(17));
+        assert!(cached.java_analysis_date.is_some());
+
+        storage.update_instance(InstanceMetadata { name: "renamed".to_string(), ..cached.clone() }).await.unwrap();
+        assert_eq!(storage.get_instance("first").unwrap().resolved_java_version, Some(17));
+
+        storage.update_instance(InstanceMetadata { version: "1.20.6".to_string(), ..cached }).await.unwrap();
+        let upgraded = storage.get_instance("first").unwrap();
+        assert_eq!(upgraded.resolved_java_version, None);
+        assert_eq!(upgraded.java_analysis_date, None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn duplicates_are_found_and_merged() {
+        let dir = std::env::temp_dir().join(format!("chai-storage-{}", uuid::Uuid::new_v4()));
+        let mut storage = temp_storage(&dir);
+        std::fs::create_dir_all(dir.join("game")).unwrap();
+
+        // Older configs could already contain duplicates
+        let mut first = test_instance("first", dir.join("game"));
+        first.total_play_time = 60;
+        let mut second = test_instance("second", dir.join("game"));
+        second.total_play_time = 30;
+        second.tags = vec!["imported".to_string()];
+        storage.config.instances.insert(first.id.clone(), first);
+        storage.config.instances.insert(second.id.clone(), second);
+        storage.config.instances.insert("other".to_string(), test_instance("other", dir.join("other")));
+
+        let duplicates = storage.find_duplicate_game_dirs();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].instance_ids.len(), 2);
+
+        let kept = storage.merge_duplicate_instance("first", "second").await.unwrap();
+        assert_eq!(kept.total_play_time, 90);
+        assert_eq!(kept.tags, vec!["imported".to_string()]);
+        assert!(storage.get_instance("second").is_none());
+        assert!(dir.join("game").exists());
+        assert!(storage.find_duplicate_game_dirs().is_empty());
+        assert!(storage.merge_duplicate_instance("first", "other").await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn play_sessions_add_up() {
+        let dir = std::env::temp_dir().join(format!("chai-storage-{}", uuid::Uuid::new_v4()));
+        let mut storage = temp_storage(&dir);
+        storage.config.instances.insert("first".to_string(), test_instance("first", dir.join("game")));
+
+        let first_exit = chrono::Utc::now();
+        storage.record_play_session("first", 90, first_exit).await.unwrap();
+        let instance = storage.record_play_session("first", 30, first_exit + chrono::Duration::hours(1)).await.unwrap();
+
+        assert_eq!(instance.total_play_time, 120);
+        assert_eq!(instance.last_played, Some((first_exit + chrono::Duration::hours(1)).to_rfc3339()));
+        assert!(storage.record_play_session("missing", 10, first_exit).await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, State};
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::watch;
+
+use crate::notifications::{self, NotificationTarget};
+
+pub type TaskManagerState = Arc<TaskManager>;
+
+tokio::task_local! {
+    static CURRENT_TASK: String;
+}
+
+/// Id of the background task the caller runs in, if any
+pub fn current_task_id() -> Option<String> {
+    CURRENT_TASK.try_with(|id| id.clone()).ok()
+}
+
+/// Lifecycle of a background task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Snapshot of a background task, sent to the UI with `task_updated`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub id: String,
+    pub kind: String,
+    /// Tasks with the same queue key run one after another
+    pub queue_key: String,
+    pub status: TaskStatus,
+    pub progress: u32,
+    pub message: Option<String>,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_at: String,
+    /// What the task works on, e.g. an instance or server name
+    pub subject: Option<String>,
+    /// Where a notification about the task should lead
+    pub target: Option<NotificationTarget>,
+}
+
+/// Tracks background tasks and runs tasks sharing a queue key sequentially
+#[derive(Default)]
+pub struct TaskManager {
+    tasks: Mutex<HashMap<String, TaskInfo>>,
+    queues: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// Cancellation senders for tasks that have not finished
+    cancellations: Mutex<HashMap<String, watch::Sender<bool>>>,
+    /// Woken for every spawned task, so idle work can step aside
+    started: tokio::sync::Notify,
+}
+
+/// Handle to a spawned task
+pub struct TaskHandle<T, E = String> {
+    pub id: String,
+    join: JoinHandle<Result<T, E>>,
+}
+
+impl<T, E: From<String>> TaskHandle<T, E> {
+    /// Wait for the task to finish
+    pub async fn wait(self) -> Result<T, E> {
+        self.join.await
+            .map_err(|e| E::from(format!("Task {} did not complete: {}", self.id, e)))?
+    }
+}
+
+/// Passed to a running task to report progress
+#[derive(Clone)]
+pub struct TaskContext {
+    pub id: String,
+    manager: Arc<TaskManager>,
+    app_handle: AppHandle,
+    cancel: watch::Receiver<bool>,
+}
+
+impl TaskContext {
+    /// Resolves once cancellation is requested, for use in `tokio::select!`
+    pub async fn cancelled(&self) {
+        let mut cancel = self.cancel.clone();
+        if cancel.wait_for(|cancelled| *cancelled).await.is_err() {
+            // The task finished and dropped its sender, so it can no longer be cancelled
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Update progress, emitting `task_updated` only when the percentage changes
+    pub fn set_progress(&self, progress: u32, message: Option<String>) {
+        let progress = progress.min(100);
+        let changed = self.manager.update(&self.id, |task| {
+            let changed = task.progress != progress;
+            task.progress = progress;
+            if message.is_some() {
+                task.message = message;
+            }
+            changed
+        });
+
+        if changed == Some(true) {
+            self.manager.emit(&self.app_handle, &self.id);
+        }
+    }
+
+    /// Name what the task works on, used in its completion notification
+    pub fn set_subject(&self, subject: &str, target: Option<NotificationTarget>) {
+        self.manager.update(&self.id, |task| {
+            task.subject = Some(subject.to_string());
+            task.target = target;
+        });
+        self.manager.emit(&self.app_handle, &self.id);
+    }
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a task behind any other task with the same key and return its handle immediately
+    ///
+    /// A task cancelled while queued never runs, a running task has to watch
+    /// `TaskContext::cancelled` itself.
+    pub fn spawn<T, E, F, Fut>(
+        self: &Arc<Self>,
+        app_handle: AppHandle,
+        kind: &str,
+        queue_key: &str,
+        task: F,
+    ) -> TaskHandle<T, E>
+    where
+        T: Serialize + Send + 'static,
+        E: Display + From<String> + Send + 'static,
+        F: FnOnce(TaskContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.tasks.lock().unwrap().insert(id.clone(), TaskInfo {
+            id: id.clone(),
+            kind: kind.to_string(),
+            queue_key: queue_key.to_string(),
+            status: TaskStatus::Queued,
+            progress: 0,
+            message: None,
+            result: None,
+            error: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            subject: None,
+            target: None,
+        });
+        self.emit(&app_handle, &id);
+        self.started.notify_waiters();
+
+        let queue = self.queues.lock().unwrap()
+            .entry(queue_key.to_string())
+            .or_default()
+            .clone();
+
+        let (cancel_sender, cancel) = watch::channel(false);
+        self.cancellations.lock().unwrap().insert(id.clone(), cancel_sender);
+
+        let context = TaskContext {
+            id: id.clone(),
+            manager: self.clone(),
+            app_handle,
+            cancel,
+        };
+
+        let join = tauri::async_runtime::spawn(async move {
+            let manager = context.manager.clone();
+            let app_handle = context.app_handle.clone();
+            let id = context.id.clone();
+
+            let mut started = None;
+            let queued = tokio::select! {
+                guard = queue.lock() => Some(guard),
+                _ = context.cancelled() => None,
+            };
+
+            let result = match queued {
+                Some(_guard) => {
+                    manager.update(&id, |task| task.status = TaskStatus::Running);
+                    manager.emit(&app_handle, &id);
+                    started = Some(Instant::now());
+
+                    let cancel = context.cancel.clone();
+                    let result = CURRENT_TASK.scope(id.clone(), task(context)).await;
+                    result.map_err(|e| (e, *cancel.borrow()))
+                }
+                None => Err((E::from(format!("Task {} was cancelled before it started", id)), true)),
+            };
+
+            manager.cancellations.lock().unwrap().remove(&id);
+            manager.update(&id, |task| match &result {
+                Ok(value) => {
+                    task.status = TaskStatus::Completed;
+                    task.progress = 100;
+                    task.result = serde_json::to_value(value).ok();
+                }
+                Err((e, cancelled)) => {
+                    task.status = if *cancelled { TaskStatus::Cancelled } else { TaskStatus::Failed };
+                    task.error = Some(e.to_string());
+                }
+            });
+            manager.emit(&app_handle, &id);
+
+            if let (Some(started), Some(task)) = (started, manager.get_task(&id)) {
+                if let Some(notification) = notifications::for_task(&task) {
+                    notifications::send_if_slow(&app_handle, started, notification).await;
+                }
+            }
+
+            result.map_err(|(e, _)| e)
+        });
+
+        TaskHandle { id, join }
+    }
+
+    pub fn get_task(&self, id: &str) -> Option<TaskInfo> {
+        self.tasks.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn get_tasks(&self) -> Vec<TaskInfo> {
+        let mut tasks: Vec<TaskInfo> = self.tasks.lock().unwrap().values().cloned().collect();
+        tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        tasks
+    }
+
+    /// Request cancellation of a queued or running task
+    pub fn cancel(&self, id: &str) -> Result<(), String> {
+        let cancellations = self.cancellations.lock().unwrap();
+        let sender = cancellations.get(id)
+            .ok_or_else(|| format!("Task '{}' is not running", id))?;
+        sender.send_replace(true);
+        Ok(())
+    }
+
+    /// Tasks that are queued or running
+    pub fn active_tasks(&self) -> Vec<TaskInfo> {
+        self.get_tasks().into_iter()
+            .filter(|task| matches!(task.status, TaskStatus::Queued | TaskStatus::Running))
+            .collect()
+    }
+
+    /// Resolves when the next task is spawned
+    pub async fn task_started(&self) {
+        self.started.notified().await
+    }
+
+    /// Request cancellation of every unfinished task
+    pub fn cancel_all(&self) {
+        for sender in self.cancellations.lock().unwrap().values() {
+            sender.send_replace(true);
+        }
+    }
+
+    /// Drop finished tasks from the list
+    pub fn clear_finished(&self) {
+        self.tasks.lock().unwrap()
+            .retain(|_, task| matches!(task.status, TaskStatus::Queued | TaskStatus::Running));
+    }
+
+    fn update<R>(&self, id: &str, f: impl FnOnce(&mut TaskInfo) -> R) -> Option<R> {
+        self.tasks.lock().unwrap().get_mut(id).map(f)
+    }
+
+    fn emit(&self, app_handle: &AppHandle, id: &str) {
+        if let Some(task) = self.get_task(id) {
+            let _ = app_handle.emit("task_updated", task);
+        }
+    }
+}
+
+/// Get a single background task
+#[command]
+pub async fn get_task(task_id: String, tasks: State<'_, TaskManagerState>) -> Result<TaskInfo, String> {
+    tasks.get_task(&task_id)
+        .ok_or_else(|| format!("Task '{}' not found", task_id))
+}
+
+/// List background tasks, oldest first
+#[command]
+pub async fn get_tasks(tasks: State<'_, TaskManagerState>) -> Result<Vec<TaskInfo>, String> {
+    Ok(tasks.get_tasks())
+}
+
+/// Ask a queued or running task to stop
+#[command]
+pub async fn cancel_task(task_id: String, tasks: State<'_, TaskManagerState>) -> Result<(), String> {
+    tasks.cancel(&task_id)
+}
+
+/// Remove completed and failed tasks from the list
+#[command]
+pub async fn clear_finished_tasks(tasks: State<'_, TaskManagerState>) -> Result<(), String> {
+    tasks.clear_finished();
+    Ok(())
+}
@@ -1,4 +1,4 @@
-import { useState, useEffect, useCallback } from 'react';
+import { useState, useEffect, useCallback, useRef } from 'react';
 import { convertFileSrc } from '@tauri-apps/api/core';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import LauncherSidebar from './components/LauncherSidebar';
@@ -10,10 +10,10 @@ import SettingsView from './components/SettingsView';
 import AccountsView from './components/AccountsView';
 import JavaInstallModal from './components/JavaInstallModal';
 import InstanceSettingsModal from './components/InstanceSettingsModal';
-import { CreateInstanceModal, ExitConfirmationModal } from './components/modals';
-import { MinecraftInstance, ModpackInfo } from './types/minecraft';
-import { useInstances, useSettings, useMinecraftVersions } from './hooks';
-import { JavaService } from './services';
+import { CreateInstanceModal, ExitConfirmationModal, PreviousSessionModal } from './components/modals';
+import { BackgroundWork, InterruptedTask, MinecraftInstance, ModpackInfo, NotificationTarget, SessionRecord } from './types/minecraft';
+import { useInstances, useSettings, useMinecraftVersions, useNotificationRouting, useExecConfirmation, useInstancePathRepair } from './hooks';
+import { JavaService, SessionService } from './services';
 import heroImage from './assets/hero.png';
 import type { CSSProperties } from 'react';
 import './index.css';
@@ -23,6 +23,10 @@ function App() {
   const [showCreateModal, setShowCreateModal] = useState(false);
   const [showJavaInstallModal, setShowJavaInstallModal] = useState(false);
   const [showExitConfirmationModal, setShowExitConfirmationModal] = useState(false);
+  const [backgroundWork, setBackgroundWork] = useState<BackgroundWork | null>(null);
+  const [previousSession, setPreviousSession] = useState<SessionRecord | null>(null);
+  const exitConfirmedRef = useRef(false);
+  const confirmBackgroundExitRef = useRef(false);
   const [editingInstance, setEditingInstance] = useState<MinecraftInstance | null>(null);
   const [pendingInstanceLaunch, setPendingInstanceLaunch] = useState<MinecraftInstance | null>(null);
   const [requiredJavaVersion, setRequiredJavaVersion] = useState<number>(17);
@@ -35,6 +39,9 @@ function App() {
     createInstance, 
     launchInstance, 
     deleteInstance, 
+    duplicateInstance,
+    stopInstance,
+    updateInstance,
     openInstanceFolder,
     getInstallingInstances 
   } = useInstances(launcherSettings);
@@ -53,6 +60,8 @@ function App() {
 
   // Handle confirmed exit
   const handleConfirmedExit = useCallback(async () => {
+    // close() asks again through onCloseRequested, which must let it through now
+    exitConfirmedRef.current = true;
     try {
       const currentWindow = getCurrentWindow();
       await currentWindow.close();
@@ -67,14 +76,30 @@ function App() {
       try {
         const currentWindow = getCurrentWindow();
         const unlistenCloseRequested = await currentWindow.onCloseRequested(async (event) => {
+          if (exitConfirmedRef.current) return;
+
           // Check current instances using the ref to get latest state
           const installing = instancesRef.current.filter(instance => instance.status === 'installing');
           if (installing.length > 0) {
             // Prevent close and show modal
             event.preventDefault();
             setShowExitConfirmationModal(true);
+            return;
+          }
+
+          if (confirmBackgroundExitRef.current) {
+            try {
+              const work = await SessionService.getBackgroundWork();
+              if (work.tasks.length > 0 || work.modpack_installs.length > 0 || work.running_games.length > 0) {
+                event.preventDefault();
+                setBackgroundWork(work);
+                setShowExitConfirmationModal(true);
+              }
+            } catch (error) {
+              console.error('Failed to check background work:', error);
+            }
           }
-          // If no installations, allow the close to proceed normally
+          // If nothing is running, allow the close to proceed normally
         });
         
         return unlistenCloseRequested;
@@ -86,6 +111,68 @@ function App() {
     setupCloseListener();
   }, [instancesRef]);
 
+  useEffect(() => {
+    confirmBackgroundExitRef.current = launcherSettings?.confirm_exit_with_background_work ?? false;
+  }, [launcherSettings]);
+
+  // Tell the player about work the last session left unfinished
+  useEffect(() => {
+    SessionService.getPreviousSessionReport()
+      .then(setPreviousSession)
+      .catch(error => console.error('Failed to load previous session report:', error));
+  }, []);
+
+  const dismissPreviousSession = useCallback(() => {
+    setPreviousSession(null);
+    SessionService.dismissPreviousSessionReport()
+      .catch(error => console.error('Failed to dismiss previous session report:', error));
+  }, []);
+
+  const resumeInterruptedTask = useCallback((task: InterruptedTask) => {
+    if (!task.resume) return;
+    setPreviousSession(prev => prev && { ...prev, interrupted: prev.interrupted.filter(t => t !== task) });
+    setActiveView('instances');
+    SessionService.resume(task.resume)
+      .catch(error => console.error(`Failed to resume ${task.kind}:`, error));
+  }, []);
+
+  // Launch the instance picked from the taskbar menu once instances have loaded
+  const pendingLaunchCheckedRef = useRef(false);
+  useEffect(() => {
+    if (pendingLaunchCheckedRef.current || instances.length === 0) return;
+    pendingLaunchCheckedRef.current = true;
+    SessionService.takePendingLaunch()
+      .then(instanceId => {
+        const instance = instances.find(i => i.id === instanceId);
+        if (instance) handlePlayInstance(instance);
+      })
+      .catch(error => console.error('Failed to check for a requested launch:', error));
+  }, [instances]);
+
+  useExecConfirmation();
+  useInstancePathRepair();
+
+  // Open whatever a clicked notification was about
+  useNotificationRouting(useCallback((target: NotificationTarget) => {
+    switch (target.kind) {
+      case 'instance': {
+        setActiveView('instances');
+        const instance = instancesRef.current.find(i => i.id === target.id);
+        if (instance) setEditingInstance(instance);
+        break;
+      }
+      case 'server':
+        setActiveView('servers');
+        break;
+      case 'view':
+        setActiveView(target.view);
+        break;
+      case 'task':
+        setActiveView('home');
+        break;
+    }
+  }, [instancesRef]));
+
   // Ensure Java is available on startup
   useEffect(() => {
     JavaService.ensureJavaAvailable();
@@ -178,6 +265,8 @@ function App() {
             onPlayInstance={handlePlayInstance}
             onEditInstance={setEditingInstance}
             onDeleteInstance={deleteInstance}
+            onDuplicateInstance={duplicateInstance}
+            onStopInstance={stopInstance}
             onOpenFolder={openInstanceFolder}
           />
         );
@@ -189,6 +278,8 @@ function App() {
             onPlayInstance={handlePlayInstance}
             onEditInstance={setEditingInstance}
             onDeleteInstance={deleteInstance}
+            onDuplicateInstance={duplicateInstance}
+            onStopInstance={stopInstance}
             onOpenFolder={openInstanceFolder}
           />
         );
@@ -362,6 +453,7 @@ function App() {
           onClose={handleJavaInstallCancel}
           onInstallComplete={handleJavaInstallComplete}
           requiredJavaVersion={requiredJavaVersion}
+          autoInstall
         />
 
         {editingInstance && (
@@ -369,14 +461,28 @@ function App() {
             isOpen={!!editingInstance}
             onClose={() => setEditingInstance(null)}
             instance={editingInstance}
+            onUpdateInstance={(updated) => {
+              updateInstance(updated);
+              setEditingInstance(updated);
+            }}
           />
         )}
 
         <ExitConfirmationModal
           isOpen={showExitConfirmationModal}
-          onClose={() => setShowExitConfirmationModal(false)}
+          onClose={() => {
+            setShowExitConfirmationModal(false);
+            setBackgroundWork(null);
+          }}
           onConfirmExit={handleConfirmedExit}
           installingInstances={getInstallingInstances()}
+          backgroundWork={backgroundWork}
+        />
+
+        <PreviousSessionModal
+          report={previousSession}
+          onClose={dismissPreviousSession}
+          onResume={resumeInterruptedTask}
         />
       </div>
     </div>
@@ -18,9 +18,12 @@ const AccountsView: React.FC<AccountsViewProps> = ({
     activeAccountId,
     currentAuthToken,
     isAuthenticating,
+    oauthState,
+    deviceAuth,
     saveAuthToken,
     clearAuthToken,
     loginWithMicrosoft,
+    loginWithDeviceCode,
     removeAccount,
     setActiveAccount,
     addOfflineAccount
@@ -30,9 +33,13 @@ const AccountsView: React.FC<AccountsViewProps> = ({
   const [showAuthModal, setShowAuthModal] = useState(false);
 
 
-  const handleSetActiveAccount = (accountId: string) => {
-    setActiveAccount(accountId);
-    onSetActiveAccount?.(accountId);
+  const handleSetActiveAccount = async (accountId: string) => {
+    try {
+      await setActiveAccount(accountId);
+      onSetActiveAccount?.(accountId);
+    } catch (error) {
+      alert(`Failed to switch account: ${error}`);
+    }
   };
 
   const handleMicrosoftLogin = async () => {
@@ -40,6 +47,11 @@ const AccountsView: React.FC<AccountsViewProps> = ({
     alert(`Successfully added Microsoft account: ${account.username}`);
   };
 
+  const handleDeviceCodeLogin = async () => {
+    const account = await loginWithDeviceCode();
+    alert(`Successfully added Microsoft account: ${account.username}`);
+  };
+
   const handleRemoveAccount = async (accountId: string) => {
     try {
       await removeAccount(accountId);
@@ -117,8 +129,11 @@ const AccountsView: React.FC<AccountsViewProps> = ({
         isOpen={showAddModal}
         onClose={() => setShowAddModal(false)}
         onMicrosoftLogin={handleMicrosoftLogin}
+        onDeviceCodeLogin={handleDeviceCodeLogin}
         onOfflineAccount={addOfflineAccount}
         isAuthenticating={isAuthenticating}
+        oauthState={oauthState}
+        deviceAuth={deviceAuth}
       />
 
       <AuthTokenModal
@@ -31,6 +31,7 @@ const DeployServerModal: React.FC<DeployServerModalProps> = ({
     showAdvanced,
     setShowAdvanced,
     selectedInstanceData,
+    templates,
     resetForm,
     addEnvironmentVariable,
     removeEnvironmentVariable,
@@ -86,6 +87,7 @@ const DeployServerModal: React.FC<DeployServerModalProps> = ({
             dockerConnections={dockerConnections}
             selectedInstanceData={selectedInstanceData}
             selectedInstance={selectedInstance}
+            templates={templates}
           />
 
           <AdvancedServerSettings
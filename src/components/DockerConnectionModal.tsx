@@ -84,12 +84,12 @@ const DockerConnectionModal: React.FC<DockerConnectionModalProps> = ({ isOpen, o
             <select
               value={formData.connection_type}
               onChange={(e) => {
-                const type = e.target.value as 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm';
+                const type = e.target.value as 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm' | 'ssh';
                 setFormData(prev => ({ 
                   ...prev, 
                   connection_type: type,
                   host: ['local', 'windows_named_pipe', 'unix_socket'].includes(type) ? 'localhost' : prev.host,
-                  port: ['local', 'windows_named_pipe', 'unix_socket'].includes(type) ? undefined : (prev.port || 2376)
+                  port: ['local', 'windows_named_pipe', 'unix_socket'].includes(type) ? undefined : type === 'ssh' ? 22 : (prev.port === 22 || !prev.port ? 2376 : prev.port)
                 }));
               }}
               className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-blue-500 focus:outline-none"
@@ -99,6 +99,7 @@ const DockerConnectionModal: React.FC<DockerConnectionModalProps> = ({ isOpen, o
               <option value="unix_socket">Unix Socket</option>
               <option value="remote">Remote Docker</option>
               <option value="swarm">Docker Swarm</option>
+              <option value="ssh">SSH Tunnel</option>
             </select>
           </div>
 
@@ -129,11 +130,93 @@ const DockerConnectionModal: React.FC<DockerConnectionModalProps> = ({ isOpen, o
                 value={formData.port || ''}
                 onChange={(e) => setFormData(prev => ({ ...prev, port: e.target.value ? parseInt(e.target.value) : undefined }))}
                 className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-blue-500 focus:outline-none"
-                placeholder="2376"
+                placeholder={formData.connection_type === 'ssh' ? '22' : '2376'}
               />
             </div>
           )}
 
+          {formData.connection_type === 'ssh' && (
+            <div className="space-y-3">
+              <div>
+                <label className="block text-sm font-medium text-primary-300 mb-2">
+                  SSH User
+                </label>
+                <input
+                  type="text"
+                  value={formData.ssh.user}
+                  onChange={(e) => setFormData(prev => ({ ...prev, ssh: { ...prev.ssh, user: e.target.value } }))}
+                  className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-blue-500 focus:outline-none"
+                  placeholder="deploy"
+                  required
+                />
+              </div>
+              <div>
+                <label className="block text-sm font-medium text-primary-300 mb-2">
+                  Private Key
+                </label>
+                <input
+                  type="text"
+                  value={formData.ssh.key_path || ''}
+                  onChange={(e) => setFormData(prev => ({ ...prev, ssh: { ...prev.ssh, key_path: e.target.value } }))}
+                  className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-blue-500 focus:outline-none"
+                  placeholder="~/.ssh/id_ed25519 (leave empty to use the SSH agent)"
+                />
+              </div>
+              <div>
+                <label className="block text-sm font-medium text-primary-300 mb-2">
+                  Remote Docker Socket
+                </label>
+                <input
+                  type="text"
+                  value={formData.ssh.remote_socket || ''}
+                  onChange={(e) => setFormData(prev => ({ ...prev, ssh: { ...prev.ssh, remote_socket: e.target.value } }))}
+                  className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-blue-500 focus:outline-none"
+                  placeholder="/var/run/docker.sock"
+                />
+              </div>
+            </div>
+          )}
+
+          {['remote', 'swarm'].includes(formData.connection_type) && (
+            <div className="space-y-3">
+              <label className="flex items-center gap-2 text-sm font-medium text-primary-300">
+                <input
+                  type="checkbox"
+                  checked={formData.use_tls}
+                  onChange={(e) => setFormData(prev => ({ ...prev, use_tls: e.target.checked }))}
+                />
+                Use TLS
+              </label>
+              {formData.use_tls && ([
+                ['ca', 'ca_path', 'ca_pem', 'CA certificate'],
+                ['cert', 'cert_path', 'cert_pem', 'Client certificate'],
+                ['key', 'key_path', 'key_pem', 'Client key'],
+              ] as const).map(([field, pathKey, pemKey, label]) => (
+                <div key={field}>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    {label}
+                  </label>
+                  <input
+                    type="text"
+                    value={formData.tls[pathKey] || ''}
+                    onChange={(e) => setFormData(prev => ({ ...prev, tls: { ...prev.tls, [pathKey]: e.target.value } }))}
+                    className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-blue-500 focus:outline-none"
+                    placeholder={`/path/to/${field}.pem`}
+                  />
+                  {!formData.tls[pathKey] && (
+                    <textarea
+                      value={formData.tls[pemKey] || ''}
+                      onChange={(e) => setFormData(prev => ({ ...prev, tls: { ...prev.tls, [pemKey]: e.target.value } }))}
+                      className="mt-2 w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-blue-500 focus:outline-none font-mono text-xs"
+                      placeholder="...or paste the PEM text, it is stored encrypted"
+                      rows={3}
+                    />
+                  )}
+                </div>
+              ))}
+            </div>
+          )}
+
           {/* Connection Info */}
           <div className="bg-primary-700/30 rounded-lg p-4 text-sm">
             <h4 className="font-medium text-primary-200 mb-2">Connection Information:</h4>
@@ -153,10 +236,16 @@ const DockerConnectionModal: React.FC<DockerConnectionModalProps> = ({ isOpen, o
               {formData.connection_type === 'swarm' && (
                 <li>• Connects to a Docker Swarm manager node via TCP</li>
               )}
+              {formData.connection_type === 'ssh' && (
+                <li>• Forwards the remote Docker socket through the ssh client, the user needs access to Docker</li>
+              )}
               <li>• Make sure Docker is running and accessible</li>
-              {!['local', 'windows_named_pipe', 'unix_socket'].includes(formData.connection_type) && (
+              {['remote', 'swarm'].includes(formData.connection_type) && (
                 <li>• Ensure the Docker daemon accepts TCP connections</li>
               )}
+              {['remote', 'swarm', 'ssh'].includes(formData.connection_type) && (
+                <li>• Server data is kept in a Docker volume on the remote host</li>
+              )}
             </ul>
           </div>
 
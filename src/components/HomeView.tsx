@@ -13,6 +13,8 @@ interface HomeViewProps {
   onPlayInstance: (instance: MinecraftInstance) => void;
   onEditInstance: (instance: MinecraftInstance) => void;
   onDeleteInstance: (instance: MinecraftInstance) => void;
+  onDuplicateInstance?: (instance: MinecraftInstance) => void;
+  onStopInstance?: (instance: MinecraftInstance) => void;
   onOpenFolder?: (instance: MinecraftInstance) => void;
 }
 
@@ -22,6 +24,8 @@ const HomeView: React.FC<HomeViewProps> = ({
   onPlayInstance,
   onEditInstance,
   onDeleteInstance,
+  onDuplicateInstance,
+  onStopInstance,
   onOpenFolder,
 }) => {
   const { news, loading, hasMore, error, loadMore, refresh } = useInfiniteNews();
@@ -113,7 +117,7 @@ const HomeView: React.FC<HomeViewProps> = ({
               <span className="text-white">
                 {Math.floor(
                   recentInstances.reduce((acc, i) => acc + i.totalPlayTime, 0) /
-                  60
+                  3600
                 )}
                 h
               </span>
@@ -135,6 +139,8 @@ const HomeView: React.FC<HomeViewProps> = ({
                 onPlay={onPlayInstance}
                 onEdit={onEditInstance}
                 onDelete={onDeleteInstance}
+                onDuplicate={onDuplicateInstance}
+                onStop={onStopInstance}
                 onOpenFolder={onOpenFolder}
               />
             ))}
@@ -1,8 +1,9 @@
 import React, { useState, useEffect } from 'react';
 import { motion, AnimatePresence } from 'framer-motion';
 import { X, Settings, Package, Folder, Trash2, Download, Star, Search, Filter, Grid, List, RefreshCw, Loader, AlertCircle, ExternalLink, User, Calendar, Image, Monitor, Cpu, HardDrive, Camera, FolderOpen, Plus, Minus, MemoryStick } from 'lucide-react';
-import { MinecraftInstance } from '../types/minecraft';
-import { ModInfo } from '../types/mods';
+import { InstanceRenameError, JvmArgsPreset, JvmPresetInfo, LaunchEnvironment, LaunchValidation, MinecraftInstance } from '../types/minecraft';
+import { InstanceService } from '../services';
+import { ModInfo, ModUninstallError } from '../types/mods';
 import { invoke } from '@tauri-apps/api/core';
 
 interface InstanceSettingsModalProps {
@@ -34,6 +35,11 @@ export default function InstanceSettingsModal({
   const [installedMods, setInstalledMods] = useState<ModInfo[]>([]);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [lastUninstalled, setLastUninstalled] = useState<{ name: string; trashEntry: string } | null>(null);
+  const [newName, setNewName] = useState(instance.name);
+  const [renameFolder, setRenameFolder] = useState(false);
+  const [renaming, setRenaming] = useState(false);
+  const [renameError, setRenameError] = useState<string | null>(null);
   const [viewMode, setViewMode] = useState<'grid' | 'list'>('grid');
   const [hasMoreResults, setHasMoreResults] = useState(false);
   const [currentOffset, setCurrentOffset] = useState(0);
@@ -49,6 +55,30 @@ export default function InstanceSettingsModal({
     useCustomArgs: (instance.jvmArgs && instance.jvmArgs.length > 0) || false
   });
 
+  const [hooks, setHooks] = useState({
+    preLaunchCommand: instance.preLaunchCommand || '',
+    postExitCommand: instance.postExitCommand || ''
+  });
+
+  // One KEY=VALUE per line
+  const [envText, setEnvText] = useState(
+    Object.entries(instance.envVars || {}).map(([name, value]) => `${name}=${value}`).join('\n')
+  );
+  const [clearInheritedEnv, setClearInheritedEnv] = useState(instance.clearInheritedEnv || false);
+  const [envPreview, setEnvPreview] = useState<LaunchEnvironment | null>(null);
+
+  // Empty size fields and 'default' fall back to the launcher settings
+  const [windowOptions, setWindowOptions] = useState({
+    width: instance.windowWidth?.toString() ?? '',
+    height: instance.windowHeight?.toString() ?? '',
+    fullscreen: instance.fullscreen === undefined ? 'default' : instance.fullscreen ? 'on' : 'off'
+  });
+
+  // 'default' follows the launcher setting
+  const [jvmPreset, setJvmPreset] = useState<JvmArgsPreset | 'default'>(instance.jvmArgsPreset ?? 'default');
+  const [jvmPresets, setJvmPresets] = useState<JvmPresetInfo[]>([]);
+  const [memoryCheck, setMemoryCheck] = useState<LaunchValidation | null>(null);
+
   // Screenshots state
   const [screenshots, setScreenshots] = useState<ScreenshotInfo[]>([]);
   const [selectedScreenshot, setSelectedScreenshot] = useState<ScreenshotInfo | null>(null);
@@ -71,10 +101,23 @@ export default function InstanceSettingsModal({
         case 'resourcepacks':
           loadResourcePacks();
           break;
+        case 'jvm':
+          InstanceService.getJvmPresets(jvmSettings.maxMemory)
+            .then(setJvmPresets)
+            .catch(err => console.error('Failed to load JVM presets:', err));
+          break;
       }
     }
   }, [isOpen, activeTab, instance.id]);
 
+  // Check the memory against this computer's while it is being edited
+  useEffect(() => {
+    if (!isOpen || activeTab !== 'jvm') return;
+    InstanceService.validateLaunchConfig(instance.id, jvmSettings.maxMemory)
+      .then(setMemoryCheck)
+      .catch(err => console.error('Failed to validate memory:', err));
+  }, [isOpen, activeTab, instance.id, jvmSettings.maxMemory]);
+
   const loadInstalledMods = async () => {
     try {
       const mods = await invoke<ModInfo[]>('get_installed_mods', {
@@ -141,6 +184,96 @@ export default function InstanceSettingsModal({
     }
   };
 
+  const saveHooks = async () => {
+    try {
+      await InstanceService.updateInstanceHooks(instance.id, hooks.preLaunchCommand, hooks.postExitCommand);
+      if (onUpdateInstance) {
+        onUpdateInstance({
+          ...instance,
+          preLaunchCommand: hooks.preLaunchCommand.trim() || undefined,
+          postExitCommand: hooks.postExitCommand.trim() || undefined
+        });
+      }
+    } catch (err) {
+      console.error('Failed to save launch hooks:', err);
+      setError('Failed to save launch hooks');
+    }
+  };
+
+  const parseEnvText = (text: string): Record<string, string> => {
+    const envVars: Record<string, string> = {};
+    for (const line of text.split('\n')) {
+      const separator = line.indexOf('=');
+      if (separator > 0) {
+        envVars[line.slice(0, separator).trim()] = line.slice(separator + 1);
+      }
+    }
+    return envVars;
+  };
+
+  const saveEnvironment = async () => {
+    const envVars = parseEnvText(envText);
+    try {
+      await InstanceService.updateInstanceEnvironment(instance.id, envVars, clearInheritedEnv);
+      const diagnostics = await InstanceService.diagnoseLaunch(instance.id);
+      setEnvPreview(diagnostics.environment);
+      if (onUpdateInstance) {
+        onUpdateInstance({ ...instance, envVars, clearInheritedEnv });
+      }
+    } catch (err) {
+      console.error('Failed to save environment variables:', err);
+      setError(typeof err === 'string' ? err : 'Failed to save environment variables');
+    }
+  };
+
+  const saveWindowOptions = async () => {
+    const size = (value: string) => value.trim() === '' ? null : Math.max(0, parseInt(value) || 0);
+    const width = size(windowOptions.width);
+    const height = size(windowOptions.height);
+    const fullscreen = windowOptions.fullscreen === 'default' ? null : windowOptions.fullscreen === 'on';
+    try {
+      await InstanceService.updateInstanceWindow(instance.id, width, height, fullscreen);
+      if (onUpdateInstance) {
+        onUpdateInstance({
+          ...instance,
+          windowWidth: width ?? undefined,
+          windowHeight: height ?? undefined,
+          fullscreen: fullscreen ?? undefined
+        });
+      }
+    } catch (err) {
+      console.error('Failed to save window options:', err);
+      setError(typeof err === 'string' ? err : 'Failed to save window options');
+    }
+  };
+
+  const saveJvmPreset = async () => {
+    const preset = jvmPreset === 'default' ? null : jvmPreset;
+    try {
+      await InstanceService.updateInstanceJvmPreset(instance.id, preset);
+      if (onUpdateInstance) {
+        onUpdateInstance({ ...instance, jvmArgsPreset: preset ?? undefined });
+      }
+    } catch (err) {
+      console.error('Failed to save JVM preset:', err);
+      setError(typeof err === 'string' ? err : 'Failed to save JVM preset');
+    }
+  };
+
+  const renameInstance = async () => {
+    setRenaming(true);
+    setRenameError(null);
+    try {
+      const renamed = await InstanceService.renameInstance(instance.id, newName, renameFolder);
+      onUpdateInstance?.({ ...instance, ...renamed });
+    } catch (err) {
+      const renameErr = err as InstanceRenameError;
+      setRenameError(renameErr.message ?? String(err));
+    } finally {
+      setRenaming(false);
+    }
+  };
+
   const deleteScreenshot = async (screenshot: ScreenshotInfo) => {
     try {
       // Try to delete the file using a generic file deletion command
@@ -283,7 +416,8 @@ export default function InstanceSettingsModal({
       await invoke('install_mod', {
         instanceId: instance.id,
         modId: mod.id,
-        versionId: null
+        versionId: null,
+        wait: true
       });
       // Reload installed mods
       await loadInstalledMods();
@@ -295,20 +429,44 @@ export default function InstanceSettingsModal({
     }
   };
 
-  const uninstallMod = async (mod: ModInfo) => {
+  const uninstallMod = async (mod: ModInfo, force = false) => {
     try {
-      await invoke('uninstall_mod', {
+      const trashEntry = await invoke('uninstall_mod', {
         instanceId: instance.id,
-        modId: mod.id
-      });
+        modId: mod.id,
+        force
+      }) as string | null;
+      setLastUninstalled(trashEntry ? { name: mod.name, trashEntry } : null);
       // Reload installed mods
       await loadInstalledMods();
     } catch (err) {
+      const uninstallErr = err as ModUninstallError;
+      if (uninstallErr?.kind === 'required_by') {
+        if (confirm(`${uninstallErr.message}. Uninstall ${mod.name} anyway?`)) {
+          await uninstallMod(mod, true);
+        }
+        return;
+      }
       console.error('Failed to uninstall mod:', err);
       setError(`Failed to uninstall ${mod.name}`);
     }
   };
 
+  const undoUninstall = async () => {
+    if (!lastUninstalled) return;
+    try {
+      await invoke('restore_deleted_mod', {
+        instanceId: instance.id,
+        trashEntry: lastUninstalled.trashEntry
+      });
+      setLastUninstalled(null);
+      await loadInstalledMods();
+    } catch (err) {
+      console.error('Failed to restore mod:', err);
+      setError(`Failed to restore ${lastUninstalled.name}: ${err}`);
+    }
+  };
+
   const handleSearch = (e: React.FormEvent) => {
     e.preventDefault();
     searchMods(true);
@@ -517,12 +675,32 @@ export default function InstanceSettingsModal({
           <label className="block text-sm font-medium text-primary-300 mb-2">
             Instance Name
           </label>
-          <input
-            type="text"
-            value={instance.name}
-            className="w-full px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
-            readOnly
-          />
+          <div className="flex gap-2">
+            <input
+              type="text"
+              value={newName}
+              onChange={(e) => setNewName(e.target.value)}
+              className="flex-1 px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
+            />
+            <button
+              onClick={renameInstance}
+              disabled={renaming || !newName.trim() || newName.trim() === instance.name}
+              className="px-3 py-2 bg-secondary-600/60 hover:bg-secondary-700/80 disabled:opacity-50 text-white rounded-lg transition-colors backdrop-blur-sm border border-secondary-500/30"
+            >
+              {renaming ? <Loader size={16} className="animate-spin" /> : 'Rename'}
+            </button>
+          </div>
+          <label className="flex items-center gap-2 mt-2 text-sm text-primary-300">
+            <input
+              type="checkbox"
+              checked={renameFolder}
+              onChange={(e) => setRenameFolder(e.target.checked)}
+            />
+            Also rename the instance folder
+          </label>
+          {renameError && (
+            <p className="mt-2 text-sm text-red-400">{renameError}</p>
+          )}
         </div>
         
         <div>
@@ -693,6 +871,23 @@ export default function InstanceSettingsModal({
             </div>
           </div>
 
+          {memoryCheck && (memoryCheck.errors.length > 0 || memoryCheck.warnings.length > 0) && (
+            <div className="space-y-1">
+              {memoryCheck.errors.map(message => (
+                <p key={message} className="text-sm text-red-400 flex items-start gap-1">
+                  <AlertCircle size={14} className="mt-0.5 shrink-0" />
+                  {message}
+                </p>
+              ))}
+              {memoryCheck.warnings.map(message => (
+                <p key={message} className="text-sm text-yellow-400 flex items-start gap-1">
+                  <AlertCircle size={14} className="mt-0.5 shrink-0" />
+                  {message}
+                </p>
+              ))}
+            </div>
+          )}
+
           <div>
             <label className="flex items-center gap-2 mb-3">
               <input
@@ -751,6 +946,164 @@ export default function InstanceSettingsModal({
               Save JVM Settings
             </button>
           </div>
+
+          <div className="space-y-3 pt-4 border-t border-primary-700/30">
+            <div>
+              <h4 className="text-sm font-medium text-primary-300">JVM Flags Preset</h4>
+              <p className="text-xs text-primary-400 mt-1">
+                Added before your own arguments, which win when they set the same option.
+              </p>
+            </div>
+            <select
+              value={jvmPreset}
+              onChange={(e) => setJvmPreset(e.target.value as JvmArgsPreset | 'default')}
+              className="w-full px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
+            >
+              <option value="default">Launcher default</option>
+              {jvmPresets.map(preset => (
+                <option key={preset.id} value={preset.id}>{preset.name}</option>
+              ))}
+            </select>
+            {jvmPreset !== 'default' && jvmPresets.find(p => p.id === jvmPreset) && (
+              <div className="space-y-1">
+                <p className="text-xs text-primary-400">{jvmPresets.find(p => p.id === jvmPreset)!.description}</p>
+                <code className="block text-xs text-primary-300 bg-primary-900/30 rounded-lg p-2 break-all">
+                  {jvmPresets.find(p => p.id === jvmPreset)!.args.join(' ')}
+                </code>
+              </div>
+            )}
+            <div className="flex justify-end">
+              <button
+                onClick={saveJvmPreset}
+                className="px-6 py-2 bg-secondary-600/60 hover:bg-secondary-700/80 text-white rounded-lg transition-colors backdrop-blur-sm border border-secondary-500/30"
+              >
+                Save Preset
+              </button>
+            </div>
+          </div>
+
+          <div className="space-y-3 pt-4 border-t border-primary-700/30">
+            <div>
+              <h4 className="text-sm font-medium text-primary-300">Game Window</h4>
+              <p className="text-xs text-primary-400 mt-1">
+                Leave the size empty to use the launcher setting, or enter 0 to let the game decide.
+              </p>
+            </div>
+            <div className="flex items-center gap-2">
+              <input
+                type="number"
+                value={windowOptions.width}
+                onChange={(e) => setWindowOptions(prev => ({ ...prev, width: e.target.value }))}
+                placeholder="Width"
+                min="0"
+                max="16384"
+                className="w-full px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
+              />
+              <span className="text-primary-400">×</span>
+              <input
+                type="number"
+                value={windowOptions.height}
+                onChange={(e) => setWindowOptions(prev => ({ ...prev, height: e.target.value }))}
+                placeholder="Height"
+                min="0"
+                max="16384"
+                className="w-full px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
+              />
+              <select
+                value={windowOptions.fullscreen}
+                onChange={(e) => setWindowOptions(prev => ({ ...prev, fullscreen: e.target.value }))}
+                className="px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
+              >
+                <option value="default">Launcher default</option>
+                <option value="on">Fullscreen</option>
+                <option value="off">Windowed</option>
+              </select>
+            </div>
+            <div className="flex justify-end">
+              <button
+                onClick={saveWindowOptions}
+                className="px-6 py-2 bg-secondary-600/60 hover:bg-secondary-700/80 text-white rounded-lg transition-colors backdrop-blur-sm border border-secondary-500/30"
+              >
+                Save Window Options
+              </button>
+            </div>
+          </div>
+
+          <div className="space-y-3 pt-4 border-t border-primary-700/30">
+            <div>
+              <h4 className="text-sm font-medium text-primary-300">Launch Hooks</h4>
+              <p className="text-xs text-primary-400 mt-1">
+                Shell commands run in the instance folder with CHAI_INSTANCE_ID, CHAI_INSTANCE_DIR and CHAI_MC_VERSION set.
+                A failing pre-launch command stops the launch.
+              </p>
+            </div>
+            <input
+              type="text"
+              value={hooks.preLaunchCommand}
+              onChange={(e) => setHooks(prev => ({ ...prev, preLaunchCommand: e.target.value }))}
+              placeholder="Before launch, e.g. ./sync-saves.sh pull"
+              className="w-full px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
+            />
+            <input
+              type="text"
+              value={hooks.postExitCommand}
+              onChange={(e) => setHooks(prev => ({ ...prev, postExitCommand: e.target.value }))}
+              placeholder="After exit, e.g. ./sync-saves.sh push"
+              className="w-full px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
+            />
+            <div className="flex justify-end">
+              <button
+                onClick={saveHooks}
+                className="px-6 py-2 bg-secondary-600/60 hover:bg-secondary-700/80 text-white rounded-lg transition-colors backdrop-blur-sm border border-secondary-500/30"
+              >
+                Save Hooks
+              </button>
+            </div>
+          </div>
+
+          <div className="space-y-3 pt-4 border-t border-primary-700/30">
+            <div>
+              <h4 className="text-sm font-medium text-primary-300">Environment Variables</h4>
+              <p className="text-xs text-primary-400 mt-1">
+                One KEY=VALUE per line, set on top of the launcher's environment when the game starts.
+              </p>
+            </div>
+            <textarea
+              value={envText}
+              onChange={(e) => setEnvText(e.target.value)}
+              placeholder={'__GL_THREADED_OPTIMIZATIONS=0\nMESA_GL_VERSION_OVERRIDE=4.5'}
+              rows={4}
+              className="w-full px-3 py-2 bg-primary-900/30 border border-primary-700/30 rounded-lg text-white font-mono text-sm focus:outline-none focus:ring-2 focus:ring-secondary-500/50 backdrop-blur-sm"
+            />
+            <label className="flex items-center gap-2 text-sm text-primary-300">
+              <input
+                type="checkbox"
+                checked={clearInheritedEnv}
+                onChange={(e) => setClearInheritedEnv(e.target.checked)}
+              />
+              Don't pass the launcher's environment to the game
+            </label>
+            <div className="flex justify-end">
+              <button
+                onClick={saveEnvironment}
+                className="px-6 py-2 bg-secondary-600/60 hover:bg-secondary-700/80 text-white rounded-lg transition-colors backdrop-blur-sm border border-secondary-500/30"
+              >
+                Save and Preview
+              </button>
+            </div>
+            {envPreview && (
+              <div className="max-h-48 overflow-y-auto p-3 bg-primary-900/40 border border-primary-700/30 rounded-lg font-mono text-xs">
+                {Object.entries(envPreview.variables).map(([name, value]) => (
+                  <div
+                    key={name}
+                    className={envPreview.overridden.includes(name) ? 'text-secondary-300' : 'text-primary-400'}
+                  >
+                    {name}={value}
+                  </div>
+                ))}
+              </div>
+            )}
+          </div>
         </div>
       </div>
     </div>
@@ -894,6 +1247,27 @@ export default function InstanceSettingsModal({
         </div>
       )}
 
+      {lastUninstalled && (
+        <div className="p-3 bg-black/30 border border-primary-700/50 rounded-lg flex items-center justify-between gap-2 text-primary-200 text-sm backdrop-blur-sm">
+          <span>Uninstalled {lastUninstalled.name}. It stays in the mod trash for a while.</span>
+          <div className="flex gap-2">
+            <button
+              onClick={undoUninstall}
+              className="px-3 py-1 bg-primary-600/80 hover:bg-primary-700/90 text-white rounded transition-colors"
+            >
+              Undo
+            </button>
+            <button
+              onClick={() => setLastUninstalled(null)}
+              className="p-1 text-primary-400 hover:text-white transition-colors"
+              title="Dismiss"
+            >
+              <X size={14} />
+            </button>
+          </div>
+        </div>
+      )}
+
       {/* Content */}
       <div className="max-h-96 overflow-y-auto">
         {searchResults.length > 0 ? (
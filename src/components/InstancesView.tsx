@@ -12,6 +12,8 @@ interface InstancesViewProps {
   onPlayInstance: (instance: MinecraftInstance) => void;
   onEditInstance: (instance: MinecraftInstance) => void;
   onDeleteInstance: (instance: MinecraftInstance) => void;
+  onDuplicateInstance?: (instance: MinecraftInstance) => void;
+  onStopInstance?: (instance: MinecraftInstance) => void;
   onOpenFolder?: (instance: MinecraftInstance) => void;
 }
 
@@ -21,6 +23,8 @@ const InstancesView: React.FC<InstancesViewProps> = ({
   onPlayInstance,
   onEditInstance,
   onDeleteInstance,
+  onDuplicateInstance,
+  onStopInstance,
   onOpenFolder,
 }) => {
   // Debug logging for instances prop changes
@@ -160,6 +164,8 @@ const InstancesView: React.FC<InstancesViewProps> = ({
         onPlay={onPlayInstance}
         onEdit={handleEditInstance}
         onDelete={onDeleteInstance}
+        onDuplicate={onDuplicateInstance}
+        onStop={onStopInstance}
         onOpenFolder={onOpenFolder}
         viewMode={viewMode}
       />
@@ -12,9 +12,11 @@ interface JavaInstallModalProps {
   onClose: () => void;
   onInstallComplete: (javaPath: string) => void;
   requiredJavaVersion?: number;
+  /** Start installing as soon as the modal opens */
+  autoInstall?: boolean;
 }
 
-const JavaInstallModal: React.FC<JavaInstallModalProps> = ({ isOpen, onClose, onInstallComplete, requiredJavaVersion = 17 }) => {
+const JavaInstallModal: React.FC<JavaInstallModalProps> = ({ isOpen, onClose, onInstallComplete, requiredJavaVersion = 17, autoInstall = false }) => {
   const [installProgress, setInstallProgress] = useState<JavaInstallEvent>({
     stage: 'Preparing...',
     progress: 0
@@ -78,6 +80,12 @@ const JavaInstallModal: React.FC<JavaInstallModalProps> = ({ isOpen, onClose, on
     }
   };
 
+  useEffect(() => {
+    if (isOpen && autoInstall) {
+      handleInstall();
+    }
+  }, [isOpen, autoInstall]); // eslint-disable-line react-hooks/exhaustive-deps
+
   const handleClose = () => {
     if (!isInstalling) {
       onClose();
@@ -20,9 +20,10 @@ import {
   Tag,
   FileDown,
   Upload,
-  FolderOpen
+  FolderOpen,
+  X
 } from 'lucide-react';
-import { ModrinthPack, ModrinthVersion, ModpackInstallProgress, LauncherSettings } from '../types';
+import { ModrinthPack, ModrinthVersion, ModpackInstallProgress, ModpackInstallError, LauncherSettings } from '../types';
 import { useInfiniteModpacks } from '../hooks/useInfiniteModpacks';
 import { useInfiniteScroll } from '../hooks/useInfiniteScroll';
 
@@ -67,7 +68,11 @@ export default function ModpackBrowser({ onCreateInstance, launcherSettings }: M
         const unlisten = await listen<ModpackInstallProgress>('modpack_install_progress', (event) => {
           setInstallProgress(prev => {
             const newMap = new Map(prev);
-            newMap.set(event.payload.instance_dir, event.payload);
+            if (event.payload.stage === 'cancelled') {
+              newMap.delete(event.payload.instance_dir);
+            } else {
+              newMap.set(event.payload.instance_dir, event.payload);
+            }
             return newMap;
           });
         });
@@ -136,8 +141,20 @@ export default function ModpackBrowser({ onCreateInstance, launcherSettings }: M
 
       setShowVersionModal(false);
     } catch (error) {
+      const installError = error as ModpackInstallError;
+      if (installError?.kind === 'cancelled') {
+        return;
+      }
       console.error('Failed to install modpack:', error);
-      setInstallError(`Failed to install ${pack.name}: ${error}`);
+      setInstallError(`Failed to install ${pack.name}: ${installError?.message ?? error}`);
+    }
+  };
+
+  const cancelInstall = async (instanceDir: string) => {
+    try {
+      await invoke('cancel_modpack_install', { instanceDir });
+    } catch (error) {
+      console.error('Failed to cancel modpack installation:', error);
     }
   };
 
@@ -218,6 +235,13 @@ export default function ModpackBrowser({ onCreateInstance, launcherSettings }: M
                   />
                 </div>
                 <span className="text-xs text-secondary-400">{Math.round(progress.progress)}%</span>
+                <button
+                  onClick={() => cancelInstall(progress.instance_dir)}
+                  className="p-1 text-gray-400 hover:text-white transition-colors"
+                  title="Cancel installation"
+                >
+                  <X className="w-3 h-3" />
+                </button>
               </div>
             ) : (
               <button
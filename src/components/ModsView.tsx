@@ -72,7 +72,8 @@ export default function ModsView({ selectedInstance }: ModsViewProps) {
       await invoke('install_mod', {
         instanceId: selectedInstance.id,
         modId: mod.id,
-        versionId: null
+        versionId: null,
+        wait: true
       });
       // Success feedback would come from event listeners
     } catch (err) {
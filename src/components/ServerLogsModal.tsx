@@ -1,7 +1,8 @@
 import React, { useState, useEffect, useRef } from 'react';
 import { X, Terminal, Download, Trash2, Send } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
-import { ServerInstance, LogEntry } from '../types/servers';
+import { ServerInstance, LogEntry, dockerErrorMessage } from '../types/servers';
+import { useServerLogStream } from '../hooks/useServerLogStream';
 
 interface ServerLogsModalProps {
   isOpen: boolean;
@@ -10,23 +11,20 @@ interface ServerLogsModalProps {
 }
 
 const ServerLogsModal: React.FC<ServerLogsModalProps> = ({ isOpen, onClose, server }) => {
-  const [logs, setLogs] = useState<LogEntry[]>([]);
-  const [loading, setLoading] = useState(false);
-  const [lastLogTimestamp, setLastLogTimestamp] = useState<string | null>(null);
+  const { logs, loading, streaming, dropped, error, restart, addLocal, clear } = useServerLogStream(isOpen ? server.id : null);
   const [command, setCommand] = useState('');
   const [autoScroll, setAutoScroll] = useState(true);
   const logsEndRef = useRef<HTMLDivElement>(null);
   const logsContainerRef = useRef<HTMLDivElement>(null);
+  const previousStatus = useRef(server.status);
 
+  // The stream ends with the container, follow the log again once the server is back up
   useEffect(() => {
-    if (isOpen && server) {
-      // Initial load - get all logs
-      loadInitialLogs();
-      // Set up periodic log following - only get new logs
-      const interval = setInterval(followLogs, 3000);
-      return () => clearInterval(interval);
+    if (server.status === 'running' && previousStatus.current !== 'running' && !streaming) {
+      restart();
     }
-  }, [isOpen, server]);
+    previousStatus.current = server.status;
+  }, [server.status, streaming, restart]);
 
   useEffect(() => {
     if (autoScroll && logsEndRef.current) {
@@ -34,77 +32,6 @@ const ServerLogsModal: React.FC<ServerLogsModalProps> = ({ isOpen, onClose, serv
     }
   }, [logs, autoScroll]);
 
-  // Initial load - get all recent logs
-  const loadInitialLogs = async () => {
-    if (!server) return;
-    
-    try {
-      setLoading(true);
-      const serverLogs = await invoke<LogEntry[]>('get_server_logs', { 
-        serverId: server.id,
-        lines: 100 
-      });
-      setLogs(serverLogs);
-      
-      // Set the timestamp of the last log for following
-      if (serverLogs.length > 0) {
-        setLastLogTimestamp(serverLogs[serverLogs.length - 1].timestamp);
-      }
-    } catch (error) {
-      console.error('Failed to load server logs:', error);
-      // Mock logs for fallback
-      const mockLogs = [
-        {
-          timestamp: new Date(Date.now() - 120000).toISOString(),
-          level: 'info' as const,
-          message: 'Starting minecraft server version ' + (server.minecraft_instance_id.includes('1.20') ? '1.20.4' : '1.19.4')
-        },
-        {
-          timestamp: new Date(Date.now() - 60000).toISOString(),
-          level: 'info' as const,
-          message: 'Loading libraries, please wait...'
-        },
-        {
-          timestamp: new Date().toISOString(),
-          level: 'info' as const,
-          message: `Server ${server.name} started successfully`
-        }
-      ];
-      setLogs(mockLogs);
-      setLastLogTimestamp(mockLogs[mockLogs.length - 1].timestamp);
-    } finally {
-      setLoading(false);
-    }
-  };
-
-  // Follow logs - only get new logs since last timestamp
-  const followLogs = async () => {
-    if (!server || !lastLogTimestamp) return;
-    
-    try {
-      // Get recent logs (small number since we're following)
-      const serverLogs = await invoke<LogEntry[]>('get_server_logs', { 
-        serverId: server.id,
-        lines: 20 
-      });
-      
-      // Filter to only new logs after our last timestamp
-      const newLogs = serverLogs.filter(log => 
-        new Date(log.timestamp) > new Date(lastLogTimestamp)
-      );
-      
-      if (newLogs.length > 0) {
-        // Append new logs to existing logs
-        setLogs(prev => [...prev, ...newLogs]);
-        // Update last timestamp
-        setLastLogTimestamp(newLogs[newLogs.length - 1].timestamp);
-      }
-    } catch (error) {
-      console.error('Failed to follow server logs:', error);
-      // Don't do anything on error - just keep existing logs
-    }
-  };
-
   const handleSendCommand = async () => {
     if (!command.trim()) return;
 
@@ -127,25 +54,22 @@ const ServerLogsModal: React.FC<ServerLogsModalProps> = ({ isOpen, onClose, serv
         message: response
       };
 
-      setLogs(prev => [...prev, commandLog, responseLog]);
-      setLastLogTimestamp(responseLog.timestamp); // Update timestamp to include command logs
+      addLocal([commandLog, responseLog]);
       setCommand('');
     } catch (error) {
       console.error('Failed to send command:', error);
       const errorLog: LogEntry = {
         timestamp: new Date().toISOString(),
         level: 'error',
-        message: `Failed to send command: ${error}`
+        message: `Failed to send command: ${dockerErrorMessage(error)}`
       };
-      setLogs(prev => [...prev, errorLog]);
-      setLastLogTimestamp(errorLog.timestamp); // Update timestamp to include error logs
+      addLocal([errorLog]);
     }
   };
 
   const handleClearLogs = () => {
     if (confirm('Are you sure you want to clear the logs display? This won\'t affect server logs.')) {
-      setLogs([]);
-      setLastLogTimestamp(null); // Reset timestamp so next follow gets all recent logs
+      clear();
     }
   };
 
@@ -234,6 +158,14 @@ const ServerLogsModal: React.FC<ServerLogsModalProps> = ({ isOpen, onClose, serv
             </div>
             <span className="text-primary-300">Port: {server.port}</span>
             <span className="text-primary-300">Max Players: {server.max_players}</span>
+            <span className={streaming ? 'text-green-400' : 'text-primary-400'}>
+              {streaming ? 'Live' : 'Not following'}
+            </span>
+            {dropped > 0 && (
+              <span className="text-yellow-400" title="The view fell behind the server's output">
+                {dropped} lines skipped
+              </span>
+            )}
           </div>
           <div className="flex items-center gap-2">
             <span className="text-primary-400">Auto-scroll:</span>
@@ -255,6 +187,9 @@ const ServerLogsModal: React.FC<ServerLogsModalProps> = ({ isOpen, onClose, serv
           className="flex-1 bg-black/20 rounded-lg p-4 font-mono text-sm overflow-y-auto"
           style={{ scrollbarWidth: 'thin' }}
         >
+          {error && (
+            <div className="text-red-400 mb-2">{error}</div>
+          )}
           {loading && logs.length === 0 ? (
             <div className="text-center text-primary-400 py-8">
               <div className="animate-spin w-6 h-6 border-2 border-current border-t-transparent rounded-full mx-auto mb-2" />
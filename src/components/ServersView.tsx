@@ -13,14 +13,17 @@ import {
   Users,
   HardDrive,
   Activity,
-  Terminal
+  Terminal,
+  LogIn
 } from 'lucide-react';
 import { MinecraftInstance } from '../types/minecraft';
+import { InstanceService } from '../services';
 import { 
   ServerInstance, 
   DockerConnection, 
   ServerDeploymentRequest,
-  ServerStatus 
+  ServerStatus,
+  dockerErrorMessage
 } from '../types/servers';
 import DeployServerModal from './DeployServerModal';
 import DockerConnectionModal from './DockerConnectionModal';
@@ -123,7 +126,26 @@ const ServersView: React.FC<ServersViewProps> = ({ instances }) => {
       setTimeout(() => refreshServerStatuses(), 2000);
     } catch (error) {
       console.error('Failed to start server:', error);
-      alert(`Failed to start server: ${error}`);
+      alert(`Failed to start server: ${dockerErrorMessage(error)}`);
+    }
+  };
+
+  /** Where players reach a server, localhost unless the Docker host is remote */
+  const joinAddress = (server: ServerInstance) => {
+    const connection = dockerConnections.find(c => c.id === server.docker_connection_id);
+    if (!connection || !['remote', 'swarm', 'ssh'].includes(connection.connection_type)) {
+      return `localhost:${server.port}`;
+    }
+    const host = connection.host.replace(/^[a-z]+:\/\//i, '').replace(/\/.*$/, '').replace(/:\d+$/, '');
+    return `${host || 'localhost'}:${server.port}`;
+  };
+
+  const handleJoinServer = async (server: ServerInstance) => {
+    try {
+      await InstanceService.joinServer(server.minecraft_instance_id, joinAddress(server));
+    } catch (error) {
+      console.error('Failed to join server:', error);
+      alert(`Failed to join server: ${error}`);
     }
   };
 
@@ -137,7 +159,7 @@ const ServersView: React.FC<ServersViewProps> = ({ instances }) => {
       setTimeout(() => refreshServerStatuses(), 2000);
     } catch (error) {
       console.error('Failed to stop server:', error);
-      alert(`Failed to stop server: ${error}`);
+      alert(`Failed to stop server: ${dockerErrorMessage(error)}`);
     }
   };
 
@@ -181,7 +203,7 @@ const ServersView: React.FC<ServersViewProps> = ({ instances }) => {
       alert(`Server "${request.name}" deployed successfully!`);
     } catch (error) {
       console.error('Failed to deploy server:', error);
-      alert(`Failed to deploy server: ${error}`);
+      alert(`Failed to deploy server: ${dockerErrorMessage(error)}`);
     }
   };
 
@@ -297,6 +319,14 @@ const ServersView: React.FC<ServersViewProps> = ({ instances }) => {
                     <span className={`${connection.is_connected ? 'text-secondary-400' : 'text-secondary-500/70'}`}>
                       {connection.is_connected ? 'Connected' : 'Disconnected'}
                     </span>
+                    {connection.tunnel && (
+                      <span
+                        className={`ml-2 ${connection.tunnel.state === 'connected' ? 'text-secondary-400' : connection.tunnel.state === 'failed' ? 'text-red-400' : 'text-yellow-400'}`}
+                        title={connection.tunnel.error}
+                      >
+                        Tunnel {connection.tunnel.state}
+                      </span>
+                    )}
                   </div>
                 </div>
               ))}
@@ -351,6 +381,15 @@ const ServersView: React.FC<ServersViewProps> = ({ instances }) => {
                           <h3 className="font-semibold">{server.name}</h3>
                         </div>
                         <div className="flex items-center gap-1">
+                          {server.status === 'running' && (
+                            <button
+                              onClick={() => handleJoinServer(server)}
+                              className="p-2 text-secondary-400 hover:text-secondary-300 hover:bg-secondary-600/20 rounded transition-colors"
+                              title={`Join ${joinAddress(server)}`}
+                            >
+                              <LogIn className="w-4 h-4" />
+                            </button>
+                          )}
                           {server.status === 'running' && (
                             <button
                               onClick={() => {
@@ -1,7 +1,9 @@
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
 import { Save, Folder, HardDrive, Coffee, Palette, Shield, RotateCcw } from 'lucide-react';
 import { motion } from 'framer-motion';
-import { LauncherSettings } from '../types/minecraft';
+import { BundledJavaRuntime, DownloadCacheStats, LauncherSettings, MirrorUrls, NotificationCategories, ResolvedJava } from '../types/minecraft';
+import JavaService from '../services/javaService';
+import SettingsService from '../services/settingsService';
 import { getCurrentPrimaryColor, getCurrentSecondaryColor, DEFAULT_PRIMARY_COLOR, DEFAULT_SECONDARY_COLOR } from '../utils/colors';
 
 interface SettingsViewProps {
@@ -12,10 +14,87 @@ interface SettingsViewProps {
 
 type SettingsTab = 'general' | 'java' | 'appearance' | 'advanced';
 
+const DEFAULT_NOTIFICATION_CATEGORIES: NotificationCategories = { installs: true, servers: true, updates: true };
+
+const NOTIFICATION_CATEGORIES: { key: keyof NotificationCategories; label: string }[] = [
+  { key: 'installs', label: 'Installs' },
+  { key: 'servers', label: 'Servers' },
+  { key: 'updates', label: 'Updates' },
+];
+
+const MIRROR_SERVICES: { key: keyof MirrorUrls; label: string; placeholder: string }[] = [
+  { key: 'version_meta', label: 'Versions and client jars', placeholder: 'https://piston-meta.mojang.com' },
+  { key: 'libraries', label: 'Libraries', placeholder: 'https://libraries.minecraft.net' },
+  { key: 'assets', label: 'Assets', placeholder: 'https://resources.download.minecraft.net' },
+  { key: 'forge', label: 'Forge maven', placeholder: 'https://maven.minecraftforge.net' },
+  { key: 'fabric_meta', label: 'Fabric metadata', placeholder: 'https://meta.fabricmc.net' },
+  { key: 'fabric_maven', label: 'Fabric maven', placeholder: 'https://maven.fabricmc.net' },
+];
+
+const formatMegabytes = (bytes: number) => `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+
 const SettingsView: React.FC<SettingsViewProps> = ({ settings, onUpdateSettings, onOpenFolder }) => {
   const [activeTab, setActiveTab] = useState<SettingsTab>('general');
   const [localSettings, setLocalSettings] = useState<LauncherSettings>(settings);
   const [hasChanges, setHasChanges] = useState(false);
+  const [resolvedJava, setResolvedJava] = useState<Record<number, ResolvedJava | string>>({});
+  const [javaRuntimes, setJavaRuntimes] = useState<BundledJavaRuntime[]>([]);
+  const [removingJava, setRemovingJava] = useState<number | null>(null);
+  const [cacheStats, setCacheStats] = useState<DownloadCacheStats | null>(null);
+  const [clearingCache, setClearingCache] = useState(false);
+
+  const loadCacheStats = () => {
+    SettingsService.getDownloadCacheStats()
+      .then(setCacheStats)
+      .catch((error) => console.error('Failed to load download cache stats:', error));
+  };
+
+  useEffect(() => {
+    if (activeTab === 'advanced') loadCacheStats();
+  }, [activeTab]);
+
+  const handleClearCache = async () => {
+    setClearingCache(true);
+    try {
+      await SettingsService.clearDownloadCache();
+    } catch (error) {
+      console.error('Failed to clear download cache:', error);
+    } finally {
+      setClearingCache(false);
+      loadCacheStats();
+    }
+  };
+
+  const loadJavaRuntimes = () => {
+    JavaService.listRuntimes()
+      .then(setJavaRuntimes)
+      .catch((error) => console.error('Failed to list Java runtimes:', error));
+  };
+
+  const handleRemoveJava = async (major: number) => {
+    if (!confirm(`Remove the launcher's Java ${major}? It is downloaded again when a game needs it.`)) return;
+    setRemovingJava(major);
+    try {
+      await JavaService.removeRuntime(major);
+    } catch (error) {
+      alert(`Failed to remove Java ${major}: ${error}`);
+    } finally {
+      setRemovingJava(null);
+      loadJavaRuntimes();
+    }
+  };
+
+  useEffect(() => {
+    if (activeTab !== 'java') return;
+    loadJavaRuntimes();
+    Promise.all([8, 17, 21].map(async (major) => {
+      try {
+        return [major, await JavaService.resolveJava(major)] as const;
+      } catch (error) {
+        return [major, String(error).split('\n')[0]] as const;
+      }
+    })).then((entries) => setResolvedJava(Object.fromEntries(entries)));
+  }, [activeTab, settings.java_bundled_only]);
 
   const handleSettingChange = (key: keyof LauncherSettings, value: any) => {
     const newSettings = { ...localSettings, [key]: value };
@@ -218,6 +297,94 @@ const SettingsView: React.FC<SettingsViewProps> = ({ settings, onUpdateSettings,
                     className="rounded border-primary-600 bg-primary-700"
                   />
                 </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Allow multiple launches
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      Launch an instance again while it is already running
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.allow_multiple_instances ?? false}
+                    onChange={(e) => handleSettingChange('allow_multiple_instances', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Confirm exit while busy
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      Ask before closing while downloads, server tasks or games are running
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.confirm_exit_with_background_work ?? false}
+                    onChange={(e) => handleSettingChange('confirm_exit_with_background_work', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Recent instances in the taskbar
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      List the five most recently played instances in the launcher's taskbar or dock menu
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.jump_list_enabled ?? true}
+                    onChange={(e) => handleSettingChange('jump_list_enabled', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Desktop notifications
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      Notify when long installs, server tasks or update checks finish
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.notifications_enabled ?? true}
+                    onChange={(e) => handleSettingChange('notifications_enabled', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                {(localSettings.notifications_enabled ?? true) && (
+                  <div className="flex gap-6 pl-4">
+                    {NOTIFICATION_CATEGORIES.map(({ key, label }) => (
+                      <label key={key} className="flex items-center gap-2 text-sm text-primary-300">
+                        <input
+                          type="checkbox"
+                          checked={localSettings.notification_categories?.[key] ?? true}
+                          onChange={(e) => handleSettingChange('notification_categories', {
+                            ...DEFAULT_NOTIFICATION_CATEGORIES,
+                            ...localSettings.notification_categories,
+                            [key]: e.target.checked,
+                          })}
+                          className="rounded border-primary-600 bg-primary-700"
+                        />
+                        {label}
+                      </label>
+                    ))}
+                  </div>
+                )}
               </motion.div>
             )}
 
@@ -275,6 +442,131 @@ const SettingsView: React.FC<SettingsViewProps> = ({ settings, onUpdateSettings,
                     Default JVM arguments for new instances
                   </p>
                 </div>
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    JVM Flags Preset
+                  </label>
+                  <select
+                    value={localSettings.jvm_args_preset ?? 'default'}
+                    onChange={(e) => handleSettingChange('jvm_args_preset', e.target.value)}
+                    className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                  >
+                    <option value="default">Default</option>
+                    <option value="aikar">Aikar's flags</option>
+                    <option value="g1gc_balanced">G1 balanced</option>
+                    <option value="zgc_large_heap">ZGC large heap</option>
+                  </select>
+                  <p className="text-sm text-primary-400 mt-1">
+                    Tuned garbage collector flags for instances without their own preset
+                  </p>
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Only use launcher-installed Java
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      Never fall back to Java installed on this system
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.java_bundled_only ?? false}
+                    onChange={(e) => handleSettingChange('java_bundled_only', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Game Window Size
+                  </label>
+                  <div className="flex items-center gap-2">
+                    <input
+                      type="number"
+                      value={localSettings.window_width ?? 0}
+                      onChange={(e) => handleSettingChange('window_width', Math.max(0, parseInt(e.target.value) || 0))}
+                      min="0"
+                      max="16384"
+                      className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                    />
+                    <span className="text-primary-400">×</span>
+                    <input
+                      type="number"
+                      value={localSettings.window_height ?? 0}
+                      onChange={(e) => handleSettingChange('window_height', Math.max(0, parseInt(e.target.value) || 0))}
+                      min="0"
+                      max="16384"
+                      className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                    />
+                  </div>
+                  <p className="text-sm text-primary-400 mt-1">
+                    Between 320 and 16384, or 0 to let the game decide. Instances can override this.
+                  </p>
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Start games fullscreen
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      Used by instances that don't set their own window mode
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.fullscreen ?? false}
+                    onChange={(e) => handleSettingChange('fullscreen', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Detected Java
+                  </label>
+                  <div className="space-y-1">
+                    {Object.entries(resolvedJava).map(([major, java]) => (
+                      <p key={major} className="text-sm text-primary-400">
+                        <span className="text-white">Java {major}:</span>{' '}
+                        {typeof java === 'string'
+                          ? java
+                          : `${java.version} (${java.source === 'bundled' ? 'launcher' : 'system'}) — ${java.path}`}
+                      </p>
+                    ))}
+                  </div>
+                </div>
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Installed by the launcher
+                  </label>
+                  {javaRuntimes.length === 0 ? (
+                    <p className="text-sm text-primary-400">No Java runtimes downloaded yet</p>
+                  ) : (
+                    <div className="space-y-2">
+                      {javaRuntimes.map((runtime) => (
+                        <div key={runtime.major_version} className="flex items-center justify-between gap-3">
+                          <p className="text-sm text-primary-400 min-w-0 truncate" title={runtime.directory}>
+                            <span className="text-white">Java {runtime.major_version}:</span>{' '}
+                            {runtime.healthy ? runtime.version : <span className="text-red-400">broken</span>}
+                            {' — '}{formatMegabytes(runtime.size_bytes)}
+                          </p>
+                          <button
+                            onClick={() => handleRemoveJava(runtime.major_version)}
+                            disabled={removingJava !== null}
+                            className="px-3 py-1 text-sm bg-primary-700 hover:bg-primary-600 disabled:opacity-50 text-white rounded-lg transition-colors"
+                          >
+                            {removingJava === runtime.major_version ? 'Removing...' : 'Remove'}
+                          </button>
+                        </div>
+                      ))}
+                    </div>
+                  )}
+                </div>
               </motion.div>
             )}
 
@@ -439,6 +731,280 @@ const SettingsView: React.FC<SettingsViewProps> = ({ settings, onUpdateSettings,
                   </p>
                 </div>
 
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Download Cache Limit (MB)
+                  </label>
+                  <input
+                    type="number"
+                    value={localSettings.download_cache_max_mb ?? 4096}
+                    onChange={(e) => handleSettingChange('download_cache_max_mb', Math.max(0, parseInt(e.target.value) || 0))}
+                    min="0"
+                    className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                  />
+                  <p className="text-primary-400 text-xs mt-1">
+                    Mod and modpack files are shared between instances through this cache. Set to 0 to turn it off.
+                  </p>
+                  {cacheStats && (
+                    <p className="text-primary-300 text-sm mt-2">
+                      {cacheStats.files} files, {formatMegabytes(cacheStats.total_bytes)} used
+                      {' '}({cacheStats.hits} hits, {cacheStats.misses} misses this session)
+                    </p>
+                  )}
+                  <button
+                    onClick={handleClearCache}
+                    disabled={clearingCache}
+                    className="mt-2 bg-primary-700 hover:bg-primary-600 disabled:opacity-50 text-white px-3 py-2 rounded-lg transition-colors"
+                  >
+                    {clearingCache ? 'Clearing...' : 'Clear Download Cache'}
+                  </button>
+                </div>
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Download Mirror
+                  </label>
+                  <select
+                    value={localSettings.download_mirror?.kind ?? 'none'}
+                    onChange={(e) => handleSettingChange('download_mirror', { kind: e.target.value })}
+                    className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                  >
+                    <option value="none">None (official servers)</option>
+                    <option value="bmclapi">BMCLAPI</option>
+                    <option value="custom">Custom</option>
+                  </select>
+                  <p className="text-primary-400 text-xs mt-1">
+                    Game files, libraries and Forge or Fabric metadata come from the mirror, files it lacks from the official servers.
+                  </p>
+                  {localSettings.download_mirror?.kind === 'custom' && (
+                    <div className="space-y-2 mt-3">
+                      {MIRROR_SERVICES.map(({ key, label, placeholder }) => (
+                        <div key={key}>
+                          <label className="block text-xs text-primary-400 mb-1">{label}</label>
+                          <input
+                            type="url"
+                            value={(localSettings.download_mirror as MirrorUrls)[key] ?? ''}
+                            onChange={(e) => handleSettingChange('download_mirror', {
+                              ...localSettings.download_mirror,
+                              [key]: e.target.value.trim() || null
+                            })}
+                            placeholder={placeholder}
+                            className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white placeholder-primary-500 focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                          />
+                        </div>
+                      ))}
+                    </div>
+                  )}
+                </div>
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Proxy
+                  </label>
+                  <select
+                    value={localSettings.proxy?.mode ?? 'system'}
+                    onChange={(e) => handleSettingChange('proxy', { ...localSettings.proxy, mode: e.target.value })}
+                    className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                  >
+                    <option value="system">System proxy</option>
+                    <option value="manual">Manual</option>
+                    <option value="none">No proxy</option>
+                  </select>
+                  <p className="text-primary-400 text-xs mt-1">
+                    Used for every download, sign-in and API request. Addresses on this computer are always reached directly.
+                  </p>
+                  {localSettings.proxy?.mode === 'manual' && (
+                    <div className="space-y-2 mt-3">
+                      <input
+                        type="text"
+                        value={localSettings.proxy.url ?? ''}
+                        onChange={(e) => handleSettingChange('proxy', { ...localSettings.proxy, url: e.target.value.trim() || null })}
+                        placeholder="http://proxy.example.com:3128"
+                        className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white placeholder-primary-500 focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                      />
+                      <div className="grid grid-cols-2 gap-2">
+                        <input
+                          type="text"
+                          value={localSettings.proxy.username ?? ''}
+                          onChange={(e) => handleSettingChange('proxy', { ...localSettings.proxy, username: e.target.value.trim() || null })}
+                          placeholder="Username (optional)"
+                          className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white placeholder-primary-500 focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                        />
+                        <input
+                          type="password"
+                          value={localSettings.proxy.password ?? ''}
+                          onChange={(e) => handleSettingChange('proxy', { ...localSettings.proxy, password: e.target.value })}
+                          placeholder={localSettings.proxy.username ? 'Password (saved)' : 'Password'}
+                          className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white placeholder-primary-500 focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                        />
+                      </div>
+                      <input
+                        type="text"
+                        value={localSettings.proxy.no_proxy ?? ''}
+                        onChange={(e) => handleSettingChange('proxy', { ...localSettings.proxy, no_proxy: e.target.value.trim() || null })}
+                        placeholder="Bypass for: .corp.example.com, 10.0.0.0/8"
+                        className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white placeholder-primary-500 focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                      />
+                    </div>
+                  )}
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Allow offline launches
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      Play LAN or singleplayer without a Microsoft account. Online servers reject offline players.
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.allow_offline_launch ?? false}
+                    onChange={(e) => handleSettingChange('allow_offline_launch', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                {localSettings.allow_offline_launch && (
+                  <div>
+                    <label className="block text-sm font-medium text-primary-300 mb-2">
+                      Offline Username
+                    </label>
+                    <input
+                      type="text"
+                      value={localSettings.offline_username ?? ''}
+                      onChange={(e) => handleSettingChange('offline_username', e.target.value)}
+                      maxLength={16}
+                      placeholder="Steve"
+                      className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white placeholder-primary-400 focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                    />
+                    {localSettings.offline_username && !/^[A-Za-z0-9_]{3,16}$/.test(localSettings.offline_username) && (
+                      <p className="text-red-400 text-xs mt-1">
+                        Use 3 to 16 letters, digits or underscores.
+                      </p>
+                    )}
+                  </div>
+                )}
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Launch Hook Timeout (seconds)
+                  </label>
+                  <input
+                    type="number"
+                    value={localSettings.hook_timeout_secs ?? 60}
+                    onChange={(e) => handleSettingChange('hook_timeout_secs', Math.max(1, parseInt(e.target.value) || 60))}
+                    min="1"
+                    className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                  />
+                  <p className="text-primary-400 text-xs mt-1">
+                    Pre-launch and post-exit commands still running after this long are stopped.
+                  </p>
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Confirm new hook commands
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      Ask before a pre-launch or post-exit command runs for the first time. Denied commands stay blocked until edited.
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.confirm_external_commands ?? false}
+                    onChange={(e) => handleSettingChange('confirm_external_commands', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Check graphics driver before launching
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      Warn when OpenGL is rendered in software or too old for the version. Turn off if the check misbehaves with your driver.
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.probe_graphics_before_launch ?? true}
+                    onChange={(e) => handleSettingChange('probe_graphics_before_launch', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Account Token Storage
+                  </label>
+                  <select
+                    value={localSettings.token_storage ?? 'keyring'}
+                    onChange={(e) => handleSettingChange('token_storage', e.target.value)}
+                    className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                  >
+                    <option value="keyring">System keyring</option>
+                    <option value="encrypted_file">Encrypted file</option>
+                  </select>
+                  <p className="text-primary-400 text-xs mt-1">
+                    Use the encrypted file on Linux systems without a Secret Service such as GNOME Keyring or KWallet.
+                  </p>
+                </div>
+
+                <div>
+                  <label className="block text-sm font-medium text-primary-300 mb-2">
+                    Keep Uninstalled Mods (days)
+                  </label>
+                  <input
+                    type="number"
+                    value={localSettings.mod_trash_retention_days ?? 30}
+                    onChange={(e) => handleSettingChange('mod_trash_retention_days', Math.max(0, parseInt(e.target.value) || 0))}
+                    min="0"
+                    className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                  />
+                  <p className="text-primary-400 text-xs mt-1">
+                    Uninstalled mods can be restored from the mod trash until they are this old.
+                  </p>
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <div>
+                    <label className="text-sm font-medium text-primary-300">
+                      Background integrity checks
+                    </label>
+                    <p className="text-sm text-primary-400">
+                      While the launcher is idle, check one instance at a time for missing files and mismatched mods
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={localSettings.integrity_sweep_enabled ?? false}
+                    onChange={(e) => handleSettingChange('integrity_sweep_enabled', e.target.checked)}
+                    className="rounded border-primary-600 bg-primary-700"
+                  />
+                </div>
+
+                {localSettings.integrity_sweep_enabled && (
+                  <div>
+                    <label className="block text-sm font-medium text-primary-300 mb-2">
+                      Integrity Check Pace (ms per file)
+                    </label>
+                    <input
+                      type="number"
+                      value={localSettings.integrity_sweep_pace_ms ?? 20}
+                      onChange={(e) => handleSettingChange('integrity_sweep_pace_ms', Math.max(0, parseInt(e.target.value) || 0))}
+                      min="0"
+                      className="w-full px-3 py-2 bg-primary-700 border border-primary-600 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-secondary-500"
+                    />
+                    <p className="text-primary-400 text-xs mt-1">
+                      Higher values are gentler on the disk. The check stops whenever a download, install or game starts.
+                    </p>
+                  </div>
+                )}
+
                 <div className="space-y-4">
                   <button className="w-full bg-secondary-600 hover:bg-secondary-700 text-white py-2 px-4 rounded-lg transition-colors">
                     Clear All Instance Data
@@ -1,7 +1,9 @@
-import React from 'react';
+import React, { useEffect, useState } from 'react';
+import { convertFileSrc } from '@tauri-apps/api/core';
 import { User, LogOut } from 'lucide-react';
 import { motion } from 'framer-motion';
 import { MinecraftAccount } from '../../types/minecraft';
+import { AccountService } from '../../services/accountService';
 
 interface AccountCardProps {
   account: MinecraftAccount;
@@ -16,6 +18,14 @@ export const AccountCard: React.FC<AccountCardProps> = ({
   onSetActive,
   onRemove,
 }) => {
+  const [headPath, setHeadPath] = useState<string | null>(null);
+
+  useEffect(() => {
+    AccountService.getPlayerSkin(account.uuid)
+      .then(skin => setHeadPath(skin.head_path))
+      .catch(error => console.error('Failed to load player skin:', error));
+  }, [account.uuid]);
+
   const getAccountTypeIcon = (type: string) => {
     switch (type) {
       case 'microsoft':
@@ -39,11 +49,12 @@ export const AccountCard: React.FC<AccountCardProps> = ({
     >
       <div className="flex items-center gap-4">
         <div className="w-16 h-16 bg-primary-700 rounded-lg flex items-center justify-center">
-          {account.skin_url ? (
-            <img 
-              src={account.skin_url} 
-              alt={account.username} 
+          {headPath ? (
+            <img
+              src={convertFileSrc(headPath)}
+              alt={account.username}
               className="w-full h-full rounded-lg object-cover"
+              style={{ imageRendering: 'pixelated' }}
             />
           ) : (
             <User size={32} className="text-primary-400" />
@@ -59,6 +70,11 @@ export const AccountCard: React.FC<AccountCardProps> = ({
                 Active
               </span>
             )}
+            {account.needs_relogin && (
+              <span className="bg-red-600 text-white text-xs px-2 py-1 rounded-full">
+                Sign in again
+              </span>
+            )}
           </div>
           <p className="text-primary-400 text-sm">{account.uuid}</p>
           <p className="text-primary-500 text-xs">Microsoft Account</p>
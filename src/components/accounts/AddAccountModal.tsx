@@ -1,21 +1,37 @@
 import React, { useState } from 'react';
-import { Shield, EyeOff } from 'lucide-react';
+import { Shield, EyeOff, Smartphone, Loader2 } from 'lucide-react';
 import { motion } from 'framer-motion';
+import { AuthProgressEvent, OAuthFlowState } from '../../types/minecraft';
+
+const OAUTH_STATE_LABELS: Record<OAuthFlowState, string> = {
+  waiting_for_browser: 'Waiting for browser...',
+  code_received: 'Signed in, verifying...',
+  exchanging: 'Getting Minecraft profile...',
+  done: 'Done',
+  failed: 'Sign-in failed',
+};
 
 interface AddAccountModalProps {
   isOpen: boolean;
   onClose: () => void;
   onMicrosoftLogin: () => Promise<void>;
+  /** Sign in by entering a code on another device */
+  onDeviceCodeLogin: () => Promise<void>;
   onOfflineAccount: (username: string) => void;
   isAuthenticating: boolean;
+  oauthState?: OAuthFlowState | null;
+  deviceAuth?: AuthProgressEvent | null;
 }
 
 export const AddAccountModal: React.FC<AddAccountModalProps> = ({
   isOpen,
   onClose,
   onMicrosoftLogin,
+  onDeviceCodeLogin,
   onOfflineAccount,
   isAuthenticating,
+  oauthState,
+  deviceAuth,
 }) => {
   const [showOfflineForm, setShowOfflineForm] = useState(false);
   const [offlineUsername, setOfflineUsername] = useState('');
@@ -27,7 +43,16 @@ export const AddAccountModal: React.FC<AddAccountModalProps> = ({
       await onMicrosoftLogin();
       onClose();
     } catch (error) {
-      alert(`Microsoft login failed: ${error}`);
+      alert(`Microsoft login failed: ${error instanceof Error ? error.message : error}`);
+    }
+  };
+
+  const handleDeviceCodeLogin = async () => {
+    try {
+      await onDeviceCodeLogin();
+      onClose();
+    } catch (error) {
+      alert(`Microsoft login failed: ${error instanceof Error ? error.message : error}`);
     }
   };
 
@@ -55,7 +80,24 @@ export const AddAccountModal: React.FC<AddAccountModalProps> = ({
       >
         <h3 className="text-lg font-semibold text-white mb-4">Add Account</h3>
         
-        {!showOfflineForm ? (
+        {deviceAuth ? (
+          <div className="space-y-4 text-center">
+            <p className="text-primary-300 text-sm">
+              Go to{' '}
+              <a href={deviceAuth.verification_uri} target="_blank" rel="noreferrer" className="text-secondary-400 underline">
+                {deviceAuth.verification_uri}
+              </a>{' '}
+              on any device and enter
+            </p>
+            <div className="text-3xl font-mono font-bold tracking-widest text-white select-all">
+              {deviceAuth.user_code}
+            </div>
+            <div className="flex items-center justify-center gap-2 text-primary-400 text-sm">
+              <Loader2 size={16} className="animate-spin" />
+              {deviceAuth.state === 'authenticating' ? 'Getting Minecraft profile...' : 'Waiting for the code to be entered...'}
+            </div>
+          </div>
+        ) : !showOfflineForm ? (
           <div className="space-y-3">
             <button
               onClick={handleMicrosoftLogin}
@@ -65,11 +107,25 @@ export const AddAccountModal: React.FC<AddAccountModalProps> = ({
               <Shield size={20} />
               <div className="text-left">
                 <div className="font-semibold">
-                  {isAuthenticating ? 'Authenticating...' : 'Microsoft Account'}
+                  {isAuthenticating
+                    ? (oauthState ? OAUTH_STATE_LABELS[oauthState] : 'Authenticating...')
+                    : 'Microsoft Account'}
                 </div>
                 <div className="text-sm opacity-90">Recommended for most users</div>
               </div>
             </button>
+
+            <button
+              onClick={handleDeviceCodeLogin}
+              disabled={isAuthenticating}
+              className="w-full bg-primary-600 hover:bg-primary-700 text-white p-3 rounded-lg flex items-center gap-3 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+            >
+              <Smartphone size={20} />
+              <div className="text-left">
+                <div className="font-semibold">Sign in with a code</div>
+                <div className="text-sm opacity-90">For when the browser sign-in can't reach the launcher</div>
+              </div>
+            </button>
             
             <button
               onClick={() => setShowOfflineForm(true)}
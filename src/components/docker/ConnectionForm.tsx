@@ -1,17 +1,24 @@
 import React from 'react';
+import { DockerSshConfig, DockerTlsConfig } from '../../types/servers';
 
 interface ConnectionFormProps {
   formData: {
     name: string;
     host: string;
     port: number | undefined;
-    connection_type: 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm';
+    connection_type: 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm' | 'ssh';
+    use_tls: boolean;
+    tls: DockerTlsConfig;
+    ssh: DockerSshConfig;
   };
   setFormData: React.Dispatch<React.SetStateAction<{
     name: string;
     host: string;
     port: number | undefined;
-    connection_type: 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm';
+    connection_type: 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm' | 'ssh';
+    use_tls: boolean;
+    tls: DockerTlsConfig;
+    ssh: DockerSshConfig;
   }>>;
   getConnectionTypeLabel: (type: string) => string;
 }
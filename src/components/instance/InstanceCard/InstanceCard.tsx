@@ -1,5 +1,5 @@
 import React from 'react';
-import { Play, Settings, Trash2, Clock, Package, Folder, Download, AlertTriangle, X } from 'lucide-react';
+import { Play, Settings, Trash2, Clock, Package, Folder, Download, AlertTriangle, X, Copy, Square } from 'lucide-react';
 import { motion } from 'framer-motion';
 import { MinecraftInstance } from '../../../types/minecraft';
 import { ContextMenu, ContextMenuItem, ProgressBar } from '../../ui';
@@ -9,6 +9,9 @@ interface InstanceCardProps {
   onPlay: (instance: MinecraftInstance) => void;
   onEdit: (instance: MinecraftInstance) => void;
   onDelete: (instance: MinecraftInstance) => void;
+  onDuplicate?: (instance: MinecraftInstance) => void;
+  /** Force-stop the running game */
+  onStop?: (instance: MinecraftInstance) => void;
   onOpenFolder?: (instance: MinecraftInstance) => void;
 }
 
@@ -17,6 +20,8 @@ const InstanceCard: React.FC<InstanceCardProps> = React.memo(({
   onPlay, 
   onEdit, 
   onDelete, 
+  onDuplicate,
+  onStop,
   onOpenFolder 
 }) => {
   const [showMenu, setShowMenu] = React.useState(false);
@@ -65,11 +70,22 @@ const InstanceCard: React.FC<InstanceCardProps> = React.memo(({
       onClick: () => onPlay(instance),
       disabled: isDisabled
     },
+    ...(onStop && instance.running ? [{
+      label: 'Force Stop',
+      icon: Square,
+      onClick: () => onStop(instance)
+    }] : []),
     {
       label: 'Settings',
       icon: Settings,
       onClick: () => onEdit(instance)
     },
+    ...(onDuplicate && !instance.isExternal ? [{
+      label: 'Duplicate',
+      icon: Copy,
+      onClick: () => onDuplicate(instance),
+      disabled: instance.status === 'installing'
+    }] : []),
     ...(onOpenFolder ? [{
       label: 'Open Folder',
       icon: Folder,
@@ -227,6 +243,43 @@ const InstanceCard: React.FC<InstanceCardProps> = React.memo(({
                 )}
               </p>
             </div>
+            {instance.running && (
+              <span className="text-xs bg-green-900/50 text-green-400 border border-green-700/50 px-2 py-1 rounded-full ml-2 flex-shrink-0 flex items-center gap-1">
+                <span className="w-1.5 h-1.5 rounded-full bg-green-400 animate-pulse" />
+                Running
+              </span>
+            )}
+            {instance.unstable && (
+              <span
+                className="text-xs bg-red-900/20 text-red-400 border border-red-700/50 px-2 py-1 rounded-full ml-2 flex-shrink-0 flex items-center gap-1"
+                title="Many recent sessions of this instance crashed"
+              >
+                <AlertTriangle size={12} />
+                Unstable
+              </span>
+            )}
+            {instance.health === 'issues' && !instance.running && (
+              <span
+                className="text-xs bg-amber-900/20 text-amber-400 border border-amber-700/50 px-2 py-1 rounded-full ml-2 flex-shrink-0 flex items-center gap-1"
+                title="The background integrity check found missing or mismatched files"
+              >
+                <AlertTriangle size={12} />
+                Needs repair
+              </span>
+            )}
+            {instance.lastCrash && !instance.running && (
+              <span
+                className="text-xs bg-red-900/20 text-red-400 border border-red-700/50 px-2 py-1 rounded-full ml-2 flex-shrink-0 flex items-center gap-1"
+                title={[
+                  instance.lastCrash.hint ?? instance.lastCrash.description,
+                  instance.lastCrash.exception,
+                  instance.lastCrash.suspected_mods.length > 0 && `Suspected: ${instance.lastCrash.suspected_mods.join(', ')}`,
+                ].filter(Boolean).join('\n')}
+              >
+                <AlertTriangle size={12} />
+                Crashed
+              </span>
+            )}
             {instance.isExternal && (
               <span className="text-xs bg-primary-700 text-primary-300 px-2 py-1 rounded-full ml-2 flex-shrink-0">
                 {instance.externalLauncher}
@@ -8,6 +8,8 @@ interface InstanceListProps {
   onPlay: (instance: MinecraftInstance) => void;
   onEdit: (instance: MinecraftInstance) => void;
   onDelete: (instance: MinecraftInstance) => void;
+  onDuplicate?: (instance: MinecraftInstance) => void;
+  onStop?: (instance: MinecraftInstance) => void;
   onOpenFolder?: (instance: MinecraftInstance) => void;
   viewMode?: 'grid' | 'list';
   loading?: boolean;
@@ -18,6 +20,8 @@ const InstanceList: React.FC<InstanceListProps> = ({
   onPlay,
   onEdit,
   onDelete,
+  onDuplicate,
+  onStop,
   onOpenFolder,
   viewMode = 'grid',
   loading = false
@@ -74,6 +78,8 @@ const InstanceList: React.FC<InstanceListProps> = ({
             onPlay={onPlay}
             onEdit={onEdit}
             onDelete={onDelete}
+            onDuplicate={onDuplicate}
+            onStop={onStop}
             onOpenFolder={onOpenFolder}
           />
         ))}
@@ -245,7 +245,12 @@ const CreateInstanceModal: React.FC<CreateInstanceModalProps> = ({
                   }`}
                 >
                   <div className="flex items-center justify-between">
-                    <span className="text-white">{version.id}</span>
+                    <span className="text-white">
+                      {version.id}
+                      {(version.isLatestRelease || version.isLatestSnapshot) && (
+                        <span className="ml-2 text-xs text-secondary-400">latest</span>
+                      )}
+                    </span>
                     <span className={`px-2 py-1 rounded text-xs ${
                       version.type === 'release'
                         ? 'bg-green-900/30 text-green-400'
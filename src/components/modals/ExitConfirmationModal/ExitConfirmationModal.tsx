@@ -1,6 +1,7 @@
 import React from 'react';
 import { AlertTriangle } from 'lucide-react';
 import { Modal, Button } from '../../ui';
+import { BackgroundWork } from '../../../types/minecraft';
 
 interface InstallingInstance {
   name: string;
@@ -12,14 +13,21 @@ interface ExitConfirmationModalProps {
   onClose: () => void;
   onConfirmExit: () => void;
   installingInstances: InstallingInstance[];
+  /** Tasks and games still running, when the exit prompt for background work is on */
+  backgroundWork?: BackgroundWork | null;
 }
 
 const ExitConfirmationModal: React.FC<ExitConfirmationModalProps> = ({
   isOpen,
   onClose,
   onConfirmExit,
-  installingInstances
+  installingInstances,
+  backgroundWork
 }) => {
+  const tasks = backgroundWork?.tasks ?? [];
+  const modpackInstalls = backgroundWork?.modpack_installs ?? [];
+  const runningGames = backgroundWork?.running_games ?? [];
+
   return (
     <Modal 
       isOpen={isOpen} 
@@ -32,12 +40,14 @@ const ExitConfirmationModal: React.FC<ExitConfirmationModalProps> = ({
         <div className="flex items-center gap-3 mb-4">
           <AlertTriangle className="text-yellow-400" size={24} />
           <h3 className="text-lg font-medium text-white">
-            Installations in Progress
+            {installingInstances.length > 0 ? 'Installations in Progress' : 'Work in Progress'}
           </h3>
         </div>
 
         <p className="text-primary-300 mb-4">
-          The following instances are still being installed. Closing the launcher now will cancel these installations:
+          {installingInstances.length > 0
+            ? 'The following instances are still being installed. Closing the launcher now will cancel these installations:'
+            : 'The launcher is still busy. Closing it now cancels this work, interrupted installs can be resumed on the next start:'}
         </p>
 
         <div className="space-y-2 mb-6">
@@ -52,8 +62,34 @@ const ExitConfirmationModal: React.FC<ExitConfirmationModalProps> = ({
               </span>
             </div>
           ))}
+          {modpackInstalls.map((install) => (
+            <div
+              key={install.instance_dir}
+              className="p-3 bg-primary-800 rounded-lg flex items-center justify-between"
+            >
+              <span className="text-white font-medium">{install.instance_name}</span>
+              <span className="text-secondary-400 text-sm">modpack install</span>
+            </div>
+          ))}
+          {tasks.map((task) => (
+            <div
+              key={task.id}
+              className="p-3 bg-primary-800 rounded-lg flex items-center justify-between"
+            >
+              <span className="text-white font-medium">{task.subject ?? task.kind.replace(/_/g, ' ')}</span>
+              <span className="text-secondary-400 text-sm">
+                {task.status === 'queued' ? 'queued' : `${task.progress}%`}
+              </span>
+            </div>
+          ))}
         </div>
 
+        {runningGames.length > 0 && (
+          <p className="text-primary-400 text-sm mb-6">
+            {runningGames.length} game(s) will keep running, but their play time won't be recorded.
+          </p>
+        )}
+
         <div className="flex gap-3 justify-end">
           <Button variant="ghost" onClick={onClose}>
             Keep Running
@@ -0,0 +1,75 @@
+import React from 'react';
+import { AlertTriangle, RotateCcw } from 'lucide-react';
+import { Modal, Button } from '../../ui';
+import { InterruptedTask, SessionRecord } from '../../../types/minecraft';
+
+interface PreviousSessionModalProps {
+  report: SessionRecord | null;
+  onClose: () => void;
+  onResume: (task: InterruptedTask) => void;
+}
+
+const PreviousSessionModal: React.FC<PreviousSessionModalProps> = ({
+  report,
+  onClose,
+  onResume
+}) => {
+  if (!report) return null;
+
+  return (
+    <Modal
+      isOpen={true}
+      onClose={onClose}
+      title="Previous Session Interrupted"
+      size="md"
+    >
+      <div className="p-6">
+        <div className="flex items-center gap-3 mb-4">
+          <AlertTriangle className="text-yellow-400" size={24} />
+          <p className="text-primary-300">
+            {report.clean_exit
+              ? 'ChaiLauncher was closed while work was still in progress.'
+              : 'ChaiLauncher did not shut down properly last time.'}
+          </p>
+        </div>
+
+        {report.interrupted.length > 0 && (
+          <div className="space-y-2 mb-4">
+            {report.interrupted.map((task, index) => (
+              <div
+                key={index}
+                className="p-3 bg-primary-800 rounded-lg flex items-center justify-between"
+              >
+                <div>
+                  <span className="text-white font-medium">{task.subject ?? task.kind}</span>
+                  <span className="text-primary-400 text-sm ml-2">
+                    {task.kind.replace(/_/g, ' ')}{task.progress > 0 ? ` · ${task.progress}%` : ''}
+                  </span>
+                </div>
+                {task.resume && (
+                  <Button variant="secondary" size="sm" icon={RotateCcw} onClick={() => onResume(task)}>
+                    Resume
+                  </Button>
+                )}
+              </div>
+            ))}
+          </div>
+        )}
+
+        {report.running_games.length > 0 && (
+          <p className="text-primary-400 text-sm mb-4">
+            {report.running_games.length} game(s) were still running when the launcher closed, their play time for that session was not recorded.
+          </p>
+        )}
+
+        <div className="flex justify-end">
+          <Button variant="ghost" onClick={onClose}>
+            Dismiss
+          </Button>
+        </div>
+      </div>
+    </Modal>
+  );
+};
+
+export default PreviousSessionModal;
@@ -0,0 +1 @@
+export { default } from './PreviousSessionModal';
\ No newline at end of file
@@ -1,2 +1,3 @@
 export { default as CreateInstanceModal } from './CreateInstanceModal';
-export { default as ExitConfirmationModal } from './ExitConfirmationModal';
\ No newline at end of file
+export { default as ExitConfirmationModal } from './ExitConfirmationModal';
+export { default as PreviousSessionModal } from './PreviousSessionModal';
\ No newline at end of file
@@ -1,6 +1,6 @@
 import React from 'react';
 import { MinecraftInstance } from '../../types/minecraft';
-import { DockerConnection } from '../../types/servers';
+import { DockerConnection, ServerResourceLimits, ServerTemplate } from '../../types/servers';
 
 interface BasicServerSettingsProps {
   formData: {
@@ -8,15 +8,19 @@ interface BasicServerSettingsProps {
     minecraft_instance_id: string;
     docker_connection_id: string;
     port: number;
+    auto_assign_port: boolean;
     max_players: number;
     memory_limit: number;
     auto_start: boolean;
+    template_id: string;
+    resources: ServerResourceLimits;
   };
   setFormData: React.Dispatch<React.SetStateAction<any>>;
   instances: MinecraftInstance[];
   dockerConnections: DockerConnection[];
   selectedInstanceData?: MinecraftInstance;
   selectedInstance?: MinecraftInstance | null;
+  templates: ServerTemplate[];
 }
 
 export const BasicServerSettings: React.FC<BasicServerSettingsProps> = ({
@@ -25,8 +29,11 @@ export const BasicServerSettings: React.FC<BasicServerSettingsProps> = ({
   instances,
   dockerConnections,
   selectedInstanceData,
-  selectedInstance
+  selectedInstance,
+  templates
 }) => {
+  const selectedTemplate = templates.find(t => t.id === formData.template_id);
+
   return (
     <div className="space-y-4">
       <h3 className="text-lg font-semibold text-white mb-3">Basic Settings</h3>
@@ -70,6 +77,36 @@ export const BasicServerSettings: React.FC<BasicServerSettingsProps> = ({
         )}
       </div>
 
+      <div>
+        <label className="block text-sm font-medium text-primary-300 mb-2">
+          Server Type
+        </label>
+        <select
+          value={formData.template_id}
+          onChange={(e) => {
+            const template = templates.find(t => t.id === e.target.value);
+            setFormData((prev: any) => ({
+              ...prev,
+              template_id: e.target.value,
+              memory_limit: template?.recommended_memory_mb ?? prev.memory_limit
+            }));
+          }}
+          className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-secondary-500 focus:outline-none"
+        >
+          {templates.length === 0 && <option value="vanilla">Vanilla</option>}
+          {templates.map((template) => (
+            <option key={template.id} value={template.id}>
+              {template.name}
+            </option>
+          ))}
+        </select>
+        {selectedTemplate && (
+          <p className="text-xs text-primary-400 mt-1">
+            {selectedTemplate.description}
+          </p>
+        )}
+      </div>
+
       <div>
         <label className="block text-sm font-medium text-primary-300 mb-2">
           Docker Connection
@@ -107,6 +144,18 @@ export const BasicServerSettings: React.FC<BasicServerSettingsProps> = ({
             onChange={(e) => setFormData((prev: any) => ({ ...prev, port: parseInt(e.target.value) || 25565 }))}
             className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-secondary-500 focus:outline-none"
           />
+          <div className="flex items-center mt-2">
+            <input
+              type="checkbox"
+              id="auto_assign_port"
+              checked={formData.auto_assign_port}
+              onChange={(e) => setFormData((prev: any) => ({ ...prev, auto_assign_port: e.target.checked }))}
+              className="mr-2"
+            />
+            <label htmlFor="auto_assign_port" className="text-xs text-primary-400">
+              Use the next free port if this one is taken
+            </label>
+          </div>
         </div>
         
         <div>
@@ -126,7 +175,7 @@ export const BasicServerSettings: React.FC<BasicServerSettingsProps> = ({
 
       <div>
         <label className="block text-sm font-medium text-primary-300 mb-2">
-          Memory Limit (MB)
+          Java Heap (MB)
         </label>
         <input
           type="number"
@@ -142,6 +191,37 @@ export const BasicServerSettings: React.FC<BasicServerSettingsProps> = ({
         </p>
       </div>
 
+      <div className="grid grid-cols-3 gap-3">
+        {([
+          ['memory_limit_mb', 'Container Memory (MB)', '512', 'Unlimited'],
+          ['memory_reservation_mb', 'Reserved Memory (MB)', '512', 'None'],
+          ['cpus', 'CPU Cores', '0.5', 'Unlimited'],
+        ] as const).map(([field, label, step, placeholder]) => (
+          <div key={field}>
+            <label className="block text-sm font-medium text-primary-300 mb-2">
+              {label}
+            </label>
+            <input
+              type="number"
+              min="0"
+              step={step}
+              value={formData.resources[field] ?? ''}
+              onChange={(e) => setFormData((prev: any) => ({
+                ...prev,
+                resources: { ...prev.resources, [field]: e.target.value ? Number(e.target.value) : undefined }
+              }))}
+              className="w-full px-3 py-2 bg-primary-700 text-white rounded-lg border border-primary-600 focus:border-secondary-500 focus:outline-none"
+              placeholder={placeholder}
+            />
+          </div>
+        ))}
+      </div>
+      {formData.resources.memory_limit_mb !== undefined && formData.resources.memory_limit_mb < formData.memory_limit && (
+        <p className="text-xs text-yellow-400">
+          The container memory is below the Java heap, the server will be killed once the heap fills up
+        </p>
+      )}
+
       <div className="flex items-center">
         <input
           type="checkbox"
@@ -6,4 +6,13 @@ export { useInfiniteMods } from './useInfiniteMods';
 export { useInfiniteModpacks } from './useInfiniteModpacks';
 export { useInfiniteScroll } from './useInfiniteScroll';
 export { useKeyboardShortcuts } from './useKeyboardShortcuts';
-export { useLocalStorage } from './useLocalStorage';
\ No newline at end of file
+export { useLocalStorage } from './useLocalStorage';export { useNotificationRouting } from './useNotificationRouting';
+export { useExecConfirmation } from './useExecConfirmation';
+export { useInstancePathRepair } from './useInstancePathRepair';
+export { useServerProperties } from './useServerProperties';
+export { useServerResources } from './useServerResources';
+export { useWorldUpload } from './useWorldUpload';
+export { useServerLogStream } from './useServerLogStream';
+export { useServerPlayers } from './useServerPlayers';
+export { useServerUpgrade } from './useServerUpgrade';
+export { useServerComposeExport } from './useServerComposeExport';
@@ -1,26 +1,65 @@
 import { useState, useEffect } from 'react';
-import { MinecraftAccount } from '../types/minecraft';
+import { listen } from '@tauri-apps/api/event';
+import { AuthProgressEvent, DeviceCode, MinecraftAccount, OAuthError, OAuthFlowEvent, OAuthFlowState } from '../types/minecraft';
 
 export const useAccounts = () => {
   const [accounts, setAccounts] = useState<MinecraftAccount[]>([]);
   const [activeAccountId, setActiveAccountId] = useState<string | null>(null);
   const [currentAuthToken, setCurrentAuthToken] = useState<string | null>(null);
   const [isAuthenticating, setIsAuthenticating] = useState(false);
+  const [oauthState, setOAuthState] = useState<OAuthFlowState | null>(null);
+  const [deviceAuth, setDeviceAuth] = useState<AuthProgressEvent | null>(null);
 
   useEffect(() => {
     loadAuthToken();
     loadAccounts();
   }, []);
 
+  useEffect(() => {
+    const unlisten = listen<OAuthFlowEvent>('oauth_flow_state', (event) => {
+      setOAuthState(event.payload.state);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<AuthProgressEvent>('auth_progress', (event) => {
+      setDeviceAuth(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Switching or removing the active account elsewhere (another window, the backend fallback)
+  useEffect(() => {
+    const unlisten = listen('accounts_changed', () => {
+      loadAccounts();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // The background refresh moved an expiry date
+  useEffect(() => {
+    const unlisten = listen('account_token_refreshed', () => {
+      loadAccounts();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const loadAccounts = async () => {
     try {
       const { invoke } = await import('@tauri-apps/api/core');
       const storedAccounts = await invoke('get_stored_accounts') as MinecraftAccount[];
+      const active = await invoke('get_active_account') as MinecraftAccount | null;
       setAccounts(storedAccounts);
-      
-      if (storedAccounts.length > 0 && !activeAccountId) {
-        setActiveAccountId(storedAccounts[0].id);
-      }
+      setActiveAccountId(active?.id ?? null);
     } catch (error) {
       console.error('Failed to load accounts:', error);
     }
@@ -69,28 +108,53 @@ export const useAccounts = () => {
       return account;
     } catch (error) {
       console.error('Microsoft login failed:', error);
-      throw error;
+      throw new Error((error as OAuthError)?.message ?? String(error));
+    } finally {
+      setIsAuthenticating(false);
+      setOAuthState(null);
+    }
+  };
+
+  const loginWithDeviceCode = async () => {
+    try {
+      setIsAuthenticating(true);
+      const { invoke } = await import('@tauri-apps/api/core');
+
+      const code = await invoke('start_device_code_auth') as DeviceCode;
+      setDeviceAuth({ state: 'waiting_for_user', user_code: code.user_code, verification_uri: code.verification_uri });
+      const account = await invoke('poll_device_code_auth') as MinecraftAccount;
+      await loadAccounts();
+
+      return account;
+    } catch (error) {
+      console.error('Device code login failed:', error);
+      throw new Error((error as OAuthError)?.message ?? String(error));
     } finally {
       setIsAuthenticating(false);
+      setDeviceAuth(null);
     }
   };
 
   const removeAccount = async (accountId: string) => {
     try {
       const { invoke } = await import('@tauri-apps/api/core');
+      // The backend picks the next account when the active one is removed
       await invoke('remove_minecraft_account', { accountId });
       await loadAccounts();
-      
-      if (activeAccountId === accountId) {
-        setActiveAccountId(accounts.length > 1 ? accounts.find(a => a.id !== accountId)?.id || null : null);
-      }
     } catch (error) {
       console.error('Failed to remove account:', error);
       throw error;
     }
   };
 
-  const setActiveAccount = (accountId: string) => {
+  const setActiveAccount = async (accountId: string) => {
+    const account = accounts.find(a => a.id === accountId);
+    if (!account || account.type === 'offline') {
+      setActiveAccountId(accountId);
+      return;
+    }
+    const { invoke } = await import('@tauri-apps/api/core');
+    await invoke('set_active_account', { uuid: account.uuid });
     setActiveAccountId(accountId);
   };
 
@@ -119,10 +183,13 @@ export const useAccounts = () => {
     activeAccountId,
     currentAuthToken,
     isAuthenticating,
+    oauthState,
+    deviceAuth,
     loadAccounts,
     saveAuthToken,
     clearAuthToken,
     loginWithMicrosoft,
+    loginWithDeviceCode,
     removeAccount,
     setActiveAccount,
     addOfflineAccount
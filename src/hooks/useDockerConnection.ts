@@ -1,5 +1,5 @@
 import { useState } from 'react';
-import { DockerConnection } from '../types/servers';
+import { DockerConnection, DockerError, DockerSshConfig, DockerTlsConfig, dockerErrorMessage } from '../types/servers';
 import { invoke } from '@tauri-apps/api/core';
 
 export const useDockerConnection = () => {
@@ -7,7 +7,10 @@ export const useDockerConnection = () => {
     name: '',
     host: 'localhost',
     port: undefined as number | undefined,
-    connection_type: 'local' as 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm'
+    connection_type: 'local' as 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm' | 'ssh',
+    use_tls: false,
+    tls: {} as DockerTlsConfig,
+    ssh: { user: '' } as DockerSshConfig
   });
 
   const [testing, setTesting] = useState(false);
@@ -18,11 +21,34 @@ export const useDockerConnection = () => {
       name: '',
       host: 'localhost',
       port: undefined,
-      connection_type: 'local'
+      connection_type: 'local',
+      use_tls: false,
+      tls: {},
+      ssh: { user: '' }
     });
     setTestResult(null);
   };
 
+  // TLS only applies to TCP connections, empty fields are left out
+  const tlsConfig = (): DockerTlsConfig | undefined => {
+    if (!formData.use_tls || !['remote', 'swarm'].includes(formData.connection_type)) {
+      return undefined;
+    }
+    const entries = Object.entries(formData.tls).filter(([, value]) => typeof value === 'string' && value.trim());
+    return Object.fromEntries(entries.map(([key, value]) => [key, (value as string).trim()]));
+  };
+
+  const sshConfig = (): DockerSshConfig | undefined => {
+    if (formData.connection_type !== 'ssh') {
+      return undefined;
+    }
+    return {
+      user: formData.ssh.user.trim(),
+      key_path: formData.ssh.key_path?.trim() || undefined,
+      remote_socket: formData.ssh.remote_socket?.trim() || undefined
+    };
+  };
+
   const testConnection = async () => {
     setTesting(true);
     setTestResult(null);
@@ -35,7 +61,9 @@ export const useDockerConnection = () => {
         host: formData.host,
         port: formData.port,
         connection_type: formData.connection_type,
-        is_connected: false
+        is_connected: false,
+        tls: tlsConfig(),
+        ssh: sshConfig()
       };
 
       await invoke('test_docker_connection', { connection: tempConnection });
@@ -46,9 +74,15 @@ export const useDockerConnection = () => {
       });
     } catch (error) {
       console.error('Docker connection test failed:', error);
+      const kind = (error as DockerError | undefined)?.kind;
+      const prefix = kind === 'certificate'
+        ? 'Certificate problem, check the CA, client certificate and key'
+        : kind === 'network'
+          ? 'Host not reachable, check the address, port and firewall'
+          : 'Connection failed';
       setTestResult({
         success: false,
-        message: `Connection failed: ${error}`
+        message: `${prefix}: ${dockerErrorMessage(error)}`
       });
     } finally {
       setTesting(false);
@@ -59,6 +93,9 @@ export const useDockerConnection = () => {
     if (!formData.name.trim()) {
       return 'Connection name is required';
     }
+    if (formData.connection_type === 'ssh' && !formData.ssh.user.trim()) {
+      return 'SSH user is required';
+    }
 
     return {
       id: `docker-${Date.now()}-${Math.random().toString(36).slice(2, 9)}`,
@@ -66,7 +103,9 @@ export const useDockerConnection = () => {
       host: formData.host,
       port: formData.port,
       connection_type: formData.connection_type,
-      is_connected: false // Will be set to true after successful test
+      is_connected: false, // Will be set to true after successful test
+      tls: tlsConfig(),
+      ssh: sshConfig()
     };
   };
 
@@ -82,6 +121,8 @@ export const useDockerConnection = () => {
         return 'Remote Docker Host';
       case 'swarm':
         return 'Docker Swarm';
+      case 'ssh':
+        return 'SSH Tunnel';
       default:
         return type;
     }
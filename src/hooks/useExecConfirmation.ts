@@ -0,0 +1,30 @@
+import { useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { ExecConfirmation } from '../types/minecraft';
+import { SettingsService } from '../services/settingsService';
+
+const FEATURE_LABELS: Record<ExecConfirmation['feature'], string> = {
+  pre_launch_hook: 'pre-launch hook',
+  post_exit_hook: 'post-exit hook',
+};
+
+/**
+ * Ask the user before a command runs for the first time, when
+ * `confirm_external_commands` is on. The answer is remembered per command.
+ */
+export const useExecConfirmation = () => {
+  useEffect(() => {
+    const unlisten = listen<ExecConfirmation>('exec_confirmation_required', (event) => {
+      const request = event.payload;
+      const allow = confirm(
+        `A ${FEATURE_LABELS[request.feature]} wants to run:\n\n${request.command}\n\nin ${request.cwd}\n\nAllow this command? Your answer is remembered until the command changes.`
+      );
+      SettingsService.respondExecConfirmation(request.request_id, allow)
+        .catch(error => console.error('Failed to answer command confirmation:', error));
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+};
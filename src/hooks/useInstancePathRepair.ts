@@ -0,0 +1,31 @@
+import { useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { PathRepairPlan } from '../types/minecraft';
+import { InstanceService } from '../services/instanceService';
+
+/**
+ * Offer to repair instances whose folder moved, e.g. after the OS username or a
+ * drive letter changed. The proposed mapping is shown before anything is saved.
+ */
+export const useInstancePathRepair = () => {
+  useEffect(() => {
+    const unlisten = listen<PathRepairPlan>('instance_paths_broken', (event) => {
+      const { repairs } = event.payload;
+      const mapping = repairs
+        .map(repair => `${repair.name}\n  ${repair.old_path}\n  → ${repair.new_path}`)
+        .join('\n\n');
+      const apply = confirm(
+        `${repairs.length} instance folder(s) could not be found at their saved location, but were found elsewhere:\n\n${mapping}\n\nUpdate the saved paths?`
+      );
+      if (!apply) {
+        return;
+      }
+      InstanceService.repairInstancePaths(false, repairs.map(repair => repair.instance_id))
+        .catch(error => console.error('Failed to repair instance paths:', error));
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+};
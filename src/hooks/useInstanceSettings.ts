@@ -1,6 +1,6 @@
 import { useState, useEffect } from 'react';
 import { MinecraftInstance } from '../types/minecraft';
-import { ModInfo } from '../types/mods';
+import { ModInfo, ModUninstallError } from '../types/mods';
 import { invoke } from '@tauri-apps/api/core';
 
 interface ScreenshotInfo {
@@ -144,19 +144,37 @@ export const useInstanceSettings = (instance: MinecraftInstance, isOpen: boolean
     await searchMods(searchQuery, newOffset);
   };
 
-  const deleteMod = async (modId: string) => {
+  /** Resolves to the trash entry that restoreMod takes, null if nothing was moved */
+  const deleteMod = async (modId: string, force = false): Promise<string | null> => {
     try {
-      await invoke('uninstall_mod', {
+      const trashEntry = await invoke('uninstall_mod', {
         instanceId: instance.id,
-        modId
-      });
+        modId,
+        force
+      }) as string | null;
       await loadInstalledMods();
+      return trashEntry;
     } catch (err) {
+      const uninstallErr = err as ModUninstallError;
+      if (uninstallErr?.kind === 'required_by') {
+        if (confirm(`${uninstallErr.message}. Delete it anyway?`)) {
+          return await deleteMod(modId, true);
+        }
+        return null;
+      }
       console.error('Failed to delete mod:', err);
       throw err;
     }
   };
 
+  const restoreMod = async (trashEntry: string): Promise<void> => {
+    await invoke('restore_deleted_mod', {
+      instanceId: instance.id,
+      trashEntry
+    });
+    await loadInstalledMods();
+  };
+
   const deleteScreenshot = async (screenshotId: string) => {
     try {
       await invoke('delete_screenshot', {
@@ -210,6 +228,7 @@ export const useInstanceSettings = (instance: MinecraftInstance, isOpen: boolean
     searchMods,
     loadMoreMods,
     deleteMod,
+    restoreMod,
     deleteScreenshot,
     saveSettings,
     loadInstalledMods,
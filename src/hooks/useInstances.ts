@@ -1,11 +1,12 @@
-import { useState, useEffect, useCallback, useRef } from 'react';
+import { useState, useEffect, useCallback, useMemo, useRef } from 'react';
 import { listen } from '@tauri-apps/api/event';
-import { MinecraftInstance, InstallProgressEvent, InstallCompleteEvent, LauncherSettings } from '../types/minecraft';
+import { MinecraftInstance, InstallProgressEvent, InstallCompleteEvent, LauncherSettings, GameExited, CrashDetected, GraphicsWarning } from '../types/minecraft';
 import { InstanceService, JavaService } from '../services';
 
 export const useInstances = (launcherSettings: LauncherSettings | null) => {
   const [instances, setInstances] = useState<MinecraftInstance[]>([]);
   const [installProgress, setInstallProgress] = useState<Map<string, InstallProgressEvent>>(new Map());
+  const [runningIds, setRunningIds] = useState<Set<string>>(new Set());
   const instancesRef = useRef<MinecraftInstance[]>([]);
 
   // Update ref whenever instances change
@@ -105,16 +106,64 @@ export const useInstances = (launcherSettings: LauncherSettings | null) => {
           handleInstallComplete(event.payload);
         });
 
+        // Clear the Running badge once the game is closed
+        const unlistenExited = await listen<GameExited>('game_exited', (event) => {
+          const { instance_id, exit_code, crashed } = event.payload;
+          if (crashed) {
+            console.warn(`Instance ${instance_id} crashed (exit code ${exit_code ?? 'unknown'})`);
+          }
+          // Any session can change whether the instance counts as unstable
+          InstanceService.getInstanceStability(instance_id)
+            .then(stability => setInstances(prev =>
+              prev.map(inst => inst.id === instance_id ? { ...inst, unstable: stability.unstable } : inst)
+            ))
+            .catch(error => console.error('Failed to load instance stability:', error));
+          setRunningIds(prev => {
+            const next = new Set(prev);
+            next.delete(instance_id);
+            return next;
+          });
+        });
+
+        // Keep the crash report around so the card can say what went wrong
+        const unlistenCrash = await listen<CrashDetected>('crash_detected', (event) => {
+          const { instance_id, report } = event.payload;
+          console.warn(`Instance ${instance_id} wrote crash report ${report.file_name}`, report);
+          setInstances(prev =>
+            prev.map(inst => inst.id === instance_id ? { ...inst, lastCrash: report } : inst)
+          );
+        });
+
+        // Software rendering or a too old OpenGL, the launch goes ahead anyway
+        const unlistenGraphics = await listen<GraphicsWarning>('graphics_warning', (event) => {
+          const { instance_id, warnings, probe } = event.payload;
+          warnings.forEach(warning => console.warn(`Instance ${instance_id}: ${warning}`, probe));
+        });
+
         return () => {
           unlistenProgress();
           unlistenComplete();
+          unlistenExited();
+          unlistenCrash();
+          unlistenGraphics();
         };
       } catch (error) {
         console.error('Failed to set up event listeners:', error);
       }
     };
 
+    // Games may still be running from before the window was reloaded
+    const loadRunning = async () => {
+      try {
+        const running = await InstanceService.getRunningInstances();
+        setRunningIds(new Set(running.map(r => r.instance_id)));
+      } catch (error) {
+        console.error('Failed to load running instances:', error);
+      }
+    };
+
     loadInstances();
+    loadRunning();
     setupEventListeners();
   }, [handleProgressUpdate, handleInstallComplete]);
 
@@ -235,6 +284,8 @@ export const useInstances = (launcherSettings: LauncherSettings | null) => {
       jvmArgs: settings.jvmArgs || []
     });
     
+    setRunningIds(prev => new Set(prev).add(instance.id));
+    setInstances(prev => prev.map(inst => inst.id === instance.id ? { ...inst, lastCrash: undefined } : inst));
     console.log('Successfully launched instance:', instance.name);
   }, []);
 
@@ -251,6 +302,31 @@ export const useInstances = (launcherSettings: LauncherSettings | null) => {
     }
   }, []);
 
+  const duplicateInstance = useCallback(async (instance: MinecraftInstance) => {
+    try {
+      await InstanceService.duplicateInstance(instance.id, `${instance.name} (copy)`);
+
+      // External instances are not stored, keep the ones already detected
+      const storedInstances = await InstanceService.loadInstances();
+      setInstances(prev => [...storedInstances, ...prev.filter(inst => inst.isExternal)]);
+    } catch (error) {
+      console.error('Failed to duplicate instance:', error);
+    }
+  }, []);
+
+  // Running ids are cleared by the game_exited event once the process is gone
+  const stopInstance = useCallback(async (instance: MinecraftInstance) => {
+    try {
+      await InstanceService.killInstance(instance.id);
+    } catch (error) {
+      console.error('Failed to stop instance:', error);
+    }
+  }, []);
+
+  const updateInstance = useCallback((updated: MinecraftInstance) => {
+    setInstances(prev => prev.map(inst => inst.id === updated.id ? updated : inst));
+  }, []);
+
   const openInstanceFolder = useCallback(async (instance: MinecraftInstance) => {
     if (!instance.gameDir) {
       console.error('Cannot open folder: gameDir is undefined or empty for instance:', instance.name);
@@ -273,12 +349,20 @@ export const useInstances = (launcherSettings: LauncherSettings | null) => {
       }));
   }, [instances]);
 
+  const instancesWithRunning = useMemo(
+    () => instances.map(inst => (runningIds.has(inst.id) ? { ...inst, running: true } : inst)),
+    [instances, runningIds]
+  );
+
   return {
-    instances,
+    instances: instancesWithRunning,
     instancesRef,
     createInstance,
     launchInstance,
     deleteInstance,
+    duplicateInstance,
+    stopInstance,
+    updateInstance,
     openInstanceFolder,
     getInstallingInstances
   };
@@ -1,4 +1,4 @@
-import { useState, useEffect } from 'react';
+import { useState, useEffect, useCallback } from 'react';
 import { MinecraftVersion } from '../types/minecraft';
 import { MinecraftService } from '../services';
 
@@ -7,33 +7,41 @@ export const useMinecraftVersions = () => {
   const [versionsLoading, setVersionsLoading] = useState(true);
   const [versionsError, setVersionsError] = useState<string | null>(null);
 
-  useEffect(() => {
-    const loadVersions = async () => {
-      try {
-        setVersionsLoading(true);
-        setVersionsError(null);
-        
-        const result = await MinecraftService.loadVersions();
-        setMinecraftVersions(result.versions);
-        
-        if (result.error) {
-          setVersionsError(result.error);
-        }
-      } catch (error) {
-        console.error('Unexpected error loading versions:', error);
-        setVersionsError('Unexpected error occurred while loading versions');
-      } finally {
-        setVersionsLoading(false);
+  // When the listed manifest was fetched from Mojang, null until loaded
+  const [versionsUpdatedAt, setVersionsUpdatedAt] = useState<Date | null>(null);
+
+  const loadVersions = useCallback(async (forceRefresh = false) => {
+    try {
+      setVersionsLoading(true);
+      setVersionsError(null);
+      
+      const result = await MinecraftService.loadVersions(forceRefresh);
+      setMinecraftVersions(result.versions);
+      setVersionsUpdatedAt(result.cacheAgeSecs !== undefined ? new Date(Date.now() - result.cacheAgeSecs * 1000) : null);
+      
+      if (result.error) {
+        setVersionsError(result.error);
       }
-    };
+    } catch (error) {
+      console.error('Unexpected error loading versions:', error);
+      setVersionsError('Unexpected error occurred while loading versions');
+    } finally {
+      setVersionsLoading(false);
+    }
+  }, []);
 
+  useEffect(() => {
     loadVersions();
-  }, []);
+  }, [loadVersions]);
+
+  const refreshVersions = useCallback(() => loadVersions(true), [loadVersions]);
 
   return {
     minecraftVersions,
     versionsLoading,
-    versionsError
+    versionsError,
+    versionsUpdatedAt,
+    refreshVersions
   };
 };
 
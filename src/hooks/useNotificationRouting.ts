@@ -0,0 +1,39 @@
+import { useEffect, useRef } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { LauncherNotification, NotificationTarget } from '../types/minecraft';
+
+/** Clicking a notification focuses the launcher, so a focus soon after one counts as a click */
+const CLICK_WINDOW_MS = 2 * 60 * 1000;
+
+/**
+ * Call `onOpen` with the target of the last notification when the launcher
+ * window regains focus shortly after it was shown.
+ */
+export const useNotificationRouting = (onOpen: (target: NotificationTarget) => void) => {
+  const pending = useRef<{ target: NotificationTarget; shownAt: number } | null>(null);
+  const onOpenRef = useRef(onOpen);
+  onOpenRef.current = onOpen;
+
+  useEffect(() => {
+    const unlistenShown = listen<LauncherNotification>('notification_shown', (event) => {
+      if (event.payload.target) {
+        pending.current = { target: event.payload.target, shownAt: Date.now() };
+      }
+    });
+
+    const unlistenFocus = getCurrentWindow().onFocusChanged(({ payload: focused }) => {
+      const notification = pending.current;
+      if (!focused || !notification) return;
+      pending.current = null;
+      if (Date.now() - notification.shownAt <= CLICK_WINDOW_MS) {
+        onOpenRef.current(notification.target);
+      }
+    });
+
+    return () => {
+      unlistenShown.then((unlisten) => unlisten());
+      unlistenFocus.then((unlisten) => unlisten());
+    };
+  }, []);
+};
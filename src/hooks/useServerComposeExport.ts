@@ -0,0 +1,30 @@
+import { useCallback, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { dockerErrorMessage } from '../types/servers';
+
+/**
+ * Export a server as a docker-compose.yml so it can run without the launcher.
+ * Passwords are written to the `.env` file next to it, not into the YAML.
+ */
+export const useServerComposeExport = (serverId: string | null) => {
+  const [exporting, setExporting] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const exportCompose = useCallback(async (outputPath: string) => {
+    if (!serverId) {
+      throw new Error('No server selected');
+    }
+    setExporting(true);
+    setError(null);
+    try {
+      return await invoke<string>('export_server_compose', { serverId, outputPath });
+    } catch (err) {
+      setError(dockerErrorMessage(err));
+      throw err;
+    } finally {
+      setExporting(false);
+    }
+  }, [serverId]);
+
+  return { exportCompose, exporting, error };
+};
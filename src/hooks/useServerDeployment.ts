@@ -1,6 +1,7 @@
 import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { MinecraftInstance } from '../types/minecraft';
-import { DockerConnection, ServerDeploymentRequest } from '../types/servers';
+import { DockerConnection, ServerDeploymentRequest, ServerResourceLimits, ServerTemplate } from '../types/servers';
 
 export const useServerDeployment = (
   instances: MinecraftInstance[],
@@ -12,14 +13,24 @@ export const useServerDeployment = (
     minecraft_instance_id: selectedInstance?.id || '',
     docker_connection_id: '',
     port: 25565,
+    auto_assign_port: true,
     max_players: 20,
     memory_limit: 2048,
     auto_start: true,
-    environment_vars: {} as Record<string, string>
+    template_id: 'vanilla',
+    environment_vars: {} as Record<string, string>,
+    resources: {} as ServerResourceLimits
   });
 
   const [envVarInput, setEnvVarInput] = useState({ key: '', value: '' });
   const [showAdvanced, setShowAdvanced] = useState(false);
+  const [templates, setTemplates] = useState<ServerTemplate[]>([]);
+
+  useEffect(() => {
+    invoke<ServerTemplate[]>('get_server_templates')
+      .then(setTemplates)
+      .catch(error => console.error('Failed to load server templates:', error));
+  }, []);
 
   useEffect(() => {
     if (selectedInstance) {
@@ -37,10 +48,13 @@ export const useServerDeployment = (
       minecraft_instance_id: selectedInstance?.id || '',
       docker_connection_id: '',
       port: 25565,
+      auto_assign_port: true,
       max_players: 20,
       memory_limit: 2048,
       auto_start: true,
-      environment_vars: {}
+      template_id: 'vanilla',
+      environment_vars: {},
+      resources: {}
     });
     setEnvVarInput({ key: '', value: '' });
     setShowAdvanced(false);
@@ -83,6 +97,14 @@ export const useServerDeployment = (
       return 'Please select a Docker connection';
     }
 
+    const { memory_limit_mb, memory_reservation_mb, cpus } = formData.resources;
+    if (memory_limit_mb && memory_reservation_mb && memory_reservation_mb > memory_limit_mb) {
+      return 'The memory reservation can\'t be above the memory limit';
+    }
+    if (cpus !== undefined && cpus <= 0) {
+      return 'The CPU limit must be above 0';
+    }
+
     return null;
   };
 
@@ -92,10 +114,13 @@ export const useServerDeployment = (
       minecraft_instance_id: formData.minecraft_instance_id,
       docker_connection_id: formData.docker_connection_id,
       port: formData.port,
+      auto_assign_port: formData.auto_assign_port,
       max_players: formData.max_players,
       memory_limit: formData.memory_limit,
       environment_vars: formData.environment_vars,
-      auto_start: formData.auto_start
+      auto_start: formData.auto_start,
+      template_id: formData.template_id,
+      resources: formData.resources
     };
   };
 
@@ -109,6 +134,7 @@ export const useServerDeployment = (
     showAdvanced,
     setShowAdvanced,
     selectedInstanceData,
+    templates,
     resetForm,
     addEnvironmentVariable,
     removeEnvironmentVariable,
@@ -0,0 +1,104 @@
+import { useCallback, useEffect, useRef, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { LogEntry, ServerLogLine, ServerLogStreamEnded, dockerErrorMessage } from '../types/servers';
+
+/** Lines kept in view, older ones are discarded */
+const MAX_LINES = 5000;
+
+/**
+ * Console output of a server as it is printed. Loads the recent log first, then
+ * follows the container until the server stops; `restart` follows it again, picking
+ * up after the last line received.
+ */
+export const useServerLogStream = (serverId: string | null, lines = 100) => {
+  const [logs, setLogs] = useState<LogEntry[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [streaming, setStreaming] = useState(false);
+  const [dropped, setDropped] = useState(0);
+  const [error, setError] = useState<string | null>(null);
+  const lastTimestamp = useRef<number>(0);
+
+  const append = useCallback((entries: LogEntry[]) => {
+    if (entries.length > 0) {
+      setLogs(prev => [...prev, ...entries].slice(-MAX_LINES));
+    }
+  }, []);
+
+  const start = useCallback(async () => {
+    if (!serverId) return;
+    setError(null);
+    try {
+      await invoke('start_server_log_stream', { serverId });
+      setStreaming(true);
+    } catch (err) {
+      setError(dockerErrorMessage(err));
+      setStreaming(false);
+    }
+  }, [serverId]);
+
+  useEffect(() => {
+    if (!serverId) {
+      setLogs([]);
+      return;
+    }
+    let cancelled = false;
+    lastTimestamp.current = 0;
+    setDropped(0);
+
+    const unlistenLine = listen<ServerLogLine>('server_log', (event) => {
+      const line = event.payload;
+      if (line.server_id !== serverId) return;
+      // Lines already shown by the initial load come around again when the stream starts
+      const time = Date.parse(line.timestamp);
+      if (time <= lastTimestamp.current) return;
+      lastTimestamp.current = time;
+      if (line.dropped > 0) {
+        setDropped(prev => prev + line.dropped);
+      }
+      append([{ timestamp: line.timestamp, level: line.level, message: line.line }]);
+    });
+    const unlistenEnded = listen<ServerLogStreamEnded>('server_log_stream_ended', (event) => {
+      if (event.payload.server_id !== serverId) return;
+      setStreaming(false);
+      if (event.payload.error) {
+        setError(event.payload.error);
+      }
+    });
+
+    const load = async () => {
+      setLoading(true);
+      try {
+        const recent = await invoke<LogEntry[]>('get_server_logs', { serverId, lines });
+        if (cancelled) return;
+        setLogs(recent);
+        if (recent.length > 0) {
+          lastTimestamp.current = Date.parse(recent[recent.length - 1].timestamp);
+        }
+      } catch (err) {
+        if (!cancelled) setError(dockerErrorMessage(err));
+      } finally {
+        if (!cancelled) setLoading(false);
+      }
+      if (!cancelled) await start();
+    };
+    load();
+
+    return () => {
+      cancelled = true;
+      unlistenLine.then(stop => stop());
+      unlistenEnded.then(stop => stop());
+      invoke('stop_server_log_stream', { serverId }).catch(() => {});
+    };
+  }, [serverId, lines, start, append]);
+
+  /** Add lines that didn't come from the server, such as sent commands */
+  const addLocal = useCallback((entries: LogEntry[]) => append(entries), [append]);
+
+  const clear = useCallback(() => {
+    setLogs([]);
+    setDropped(0);
+  }, []);
+
+  return { logs, loading, streaming, dropped, error, restart: start, addLocal, clear };
+};
@@ -0,0 +1,44 @@
+import { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { OpRef, PlayerRef, ServerPlayersConfig, ServerPlayersUpdate, dockerErrorMessage } from '../types/servers';
+
+/**
+ * Whitelist and operators of a server. Players added by name are looked up with
+ * the Mojang API when saving; a running server gets the changes over RCON.
+ */
+export const useServerPlayers = (serverId: string | null) => {
+  const [config, setConfig] = useState<ServerPlayersConfig | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const reload = useCallback(async () => {
+    if (!serverId) {
+      setConfig(null);
+      return;
+    }
+    setLoading(true);
+    setError(null);
+    try {
+      setConfig(await invoke<ServerPlayersConfig>('get_server_players_config', { serverId }));
+    } catch (err) {
+      setError(dockerErrorMessage(err));
+    } finally {
+      setLoading(false);
+    }
+  }, [serverId]);
+
+  useEffect(() => {
+    reload();
+  }, [reload]);
+
+  const save = useCallback(async (whitelist: PlayerRef[], ops: OpRef[]) => {
+    if (!serverId) {
+      throw new Error('No server selected');
+    }
+    const update = await invoke<ServerPlayersUpdate>('update_server_players_config', { serverId, whitelist, ops });
+    setConfig(update.config);
+    return update;
+  }, [serverId]);
+
+  return { config, loading, error, reload, save };
+};
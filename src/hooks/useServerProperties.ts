@@ -0,0 +1,47 @@
+import { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { ServerPropertiesUpdate } from '../types/servers';
+
+/**
+ * Read and edit server.properties of a deployed server. The server has to be
+ * running, the file is read and written through the container.
+ */
+export const useServerProperties = (serverId: string | null) => {
+  const [properties, setProperties] = useState<Record<string, string>>({});
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const reload = useCallback(async () => {
+    if (!serverId) {
+      setProperties({});
+      return;
+    }
+    setLoading(true);
+    setError(null);
+    try {
+      setProperties(await invoke<Record<string, string>>('get_server_properties', { serverId }));
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  }, [serverId]);
+
+  useEffect(() => {
+    reload();
+  }, [reload]);
+
+  const save = useCallback(async (changes: Record<string, string>) => {
+    if (!serverId) {
+      throw new Error('No server selected');
+    }
+    const update = await invoke<ServerPropertiesUpdate>('update_server_properties', {
+      serverId,
+      properties: changes,
+    });
+    setProperties(update.properties);
+    return update;
+  }, [serverId]);
+
+  return { properties, loading, error, reload, save };
+};
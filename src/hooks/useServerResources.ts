@@ -0,0 +1,44 @@
+import { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { ServerResourceLimits, ServerResourcesUpdate, ServerStats } from '../types/servers';
+
+/**
+ * Live usage and container limits of a deployed server. Raising a limit applies
+ * right away, removing one recreates the container.
+ */
+export const useServerResources = (serverId: string | null) => {
+  const [stats, setStats] = useState<ServerStats | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const reload = useCallback(async () => {
+    if (!serverId) {
+      setStats(null);
+      return;
+    }
+    setLoading(true);
+    setError(null);
+    try {
+      setStats(await invoke<ServerStats>('get_server_stats', { serverId }));
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  }, [serverId]);
+
+  useEffect(() => {
+    reload();
+  }, [reload]);
+
+  const save = useCallback(async (limits: ServerResourceLimits) => {
+    if (!serverId) {
+      throw new Error('No server selected');
+    }
+    const update = await invoke<ServerResourcesUpdate>('update_server_resources', { serverId, limits });
+    await reload();
+    return update;
+  }, [serverId, reload]);
+
+  return { stats, loading, error, reload, save };
+};
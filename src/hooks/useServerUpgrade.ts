@@ -0,0 +1,48 @@
+import { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { dockerErrorMessage, ServerUpgradeProgress, ServerUpgradeProgressEvent, ServerUpgradeResult } from '../types/servers';
+
+/**
+ * Move a server to another Minecraft version. Its data is backed up first, and
+ * when the upgraded server fails to start the backup and old container are put
+ * back. Older versions are refused unless `force` is set.
+ */
+export const useServerUpgrade = (serverId: string | null) => {
+  const [progress, setProgress] = useState<ServerUpgradeProgress | null>(null);
+  const [upgrading, setUpgrading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!serverId) {
+      return;
+    }
+    const unlisten = listen<ServerUpgradeProgressEvent>('server_upgrade_progress', (event) => {
+      if (event.payload.server_id === serverId) {
+        setProgress(event.payload.progress);
+      }
+    });
+    return () => {
+      unlisten.then(stop => stop());
+    };
+  }, [serverId]);
+
+  const upgrade = useCallback(async (newVersion: string, force = false) => {
+    if (!serverId) {
+      throw new Error('No server selected');
+    }
+    setUpgrading(true);
+    setError(null);
+    setProgress(null);
+    try {
+      return await invoke<ServerUpgradeResult>('upgrade_server', { serverId, newVersion, force });
+    } catch (err) {
+      setError(dockerErrorMessage(err));
+      throw err;
+    } finally {
+      setUpgrading(false);
+    }
+  }, [serverId]);
+
+  return { upgrade, upgrading, progress, error };
+};
@@ -0,0 +1,48 @@
+import { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { dockerErrorMessage, WorldUploadProgress, WorldUploadProgressEvent, WorldUploadResult } from '../types/servers';
+
+/**
+ * Upload a world from an instance's saves to a server. The server is stopped for
+ * the swap and started again if it was running; its old world is backed up unless
+ * `backup` is false.
+ */
+export const useWorldUpload = (serverId: string | null) => {
+  const [progress, setProgress] = useState<WorldUploadProgress | null>(null);
+  const [uploading, setUploading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!serverId) {
+      return;
+    }
+    const unlisten = listen<WorldUploadProgressEvent>('world_upload_progress', (event) => {
+      if (event.payload.server_id === serverId) {
+        setProgress(event.payload.progress);
+      }
+    });
+    return () => {
+      unlisten.then(stop => stop());
+    };
+  }, [serverId]);
+
+  const upload = useCallback(async (instanceId: string, worldName: string, backup = true) => {
+    if (!serverId) {
+      throw new Error('No server selected');
+    }
+    setUploading(true);
+    setError(null);
+    setProgress(null);
+    try {
+      return await invoke<WorldUploadResult>('upload_world', { serverId, instanceId, worldName, backup });
+    } catch (err) {
+      setError(dockerErrorMessage(err));
+      throw err;
+    } finally {
+      setUploading(false);
+    }
+  }, [serverId]);
+
+  return { upload, uploading, progress, error };
+};
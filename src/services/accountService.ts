@@ -1,5 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
 import { MinecraftAccount } from '../types/accounts';
+import { DeviceCode, PlayerSkin } from '../types/minecraft';
 
 export class AccountService {
   static async getStoredAccounts(): Promise<MinecraftAccount[]> {
@@ -32,10 +33,44 @@ export class AccountService {
     return await invoke('start_oauth_with_server') as MinecraftAccount;
   }
 
+  /** Get a code to sign in with on any device, for when the browser sign-in can't reach the launcher */
+  static async startDeviceCodeAuth(): Promise<DeviceCode> {
+    return await invoke('start_device_code_auth') as DeviceCode;
+  }
+
+  /** Wait for the device code to be entered, resolves with the stored account */
+  static async pollDeviceCodeAuth(): Promise<MinecraftAccount> {
+    return await invoke('poll_device_code_auth') as MinecraftAccount;
+  }
+
   static async removeMinecraftAccount(accountId: string): Promise<void> {
     await invoke('remove_minecraft_account', { accountId });
   }
 
+  /** The account launches use, null when none is signed in */
+  static async getActiveAccount(): Promise<MinecraftAccount | null> {
+    return await invoke('get_active_account') as MinecraftAccount | null;
+  }
+
+  static async setActiveAccount(uuid: string): Promise<void> {
+    await invoke('set_active_account', { uuid });
+  }
+
+  /** Cached skin and head of a player, the default Steve or Alex when unavailable */
+  static async getPlayerSkin(uuid: string, refresh = false): Promise<PlayerSkin> {
+    return await invoke('get_player_skin', { uuid, refresh }) as PlayerSkin;
+  }
+
+  /** Change an account's skin to a 64x64 or 64x32 PNG on disk */
+  static async uploadSkin(accountUuid: string, filePath: string, variant: PlayerSkin['model']): Promise<PlayerSkin> {
+    return await invoke('upload_skin', { accountUuid, filePath, variant }) as PlayerSkin;
+  }
+
+  /** Put an account back on its default skin */
+  static async resetSkin(accountUuid: string): Promise<PlayerSkin> {
+    return await invoke('reset_skin', { accountUuid }) as PlayerSkin;
+  }
+
   static getAccountTypeIcon(type: string): string {
     switch (type) {
       case 'microsoft':
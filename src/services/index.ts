@@ -1,4 +1,6 @@
 export { InstanceService } from './instanceService';
 export { JavaService } from './javaService';
 export { SettingsService } from './settingsService';
-export { MinecraftService } from './minecraftService';
\ No newline at end of file
+export { MinecraftService } from './minecraftService';
+export { SessionService } from './sessionService';
+export { SearchService } from './searchService';
\ No newline at end of file
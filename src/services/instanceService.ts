@@ -1,15 +1,19 @@
 import { invoke } from '@tauri-apps/api/core';
-import { MinecraftInstance } from '../types/minecraft';
+import { CrashReport, FolderImportResult, GameLogLine, InstanceWorlds, LaunchDiagnostics, MinecraftInstance, ModpackUpdateReport, PlaytimeStats, ProvenanceEntry, RecentServer, RunningInstance, InstanceHealth, RepairAction, StabilitySummary, SettingsPreset, PresetApplyResult, PathRepairPlan, JvmArgsPreset, JvmPresetInfo, LaunchValidation } from '../types/minecraft';
+import { GroupToggleResult, ModConflict, ModDependencyGraph, ModGroupSummary } from '../types/mods';
 
 export class InstanceService {
   static async loadInstances(): Promise<MinecraftInstance[]> {
     try {
-      const storedInstances = await invoke('load_instances') as MinecraftInstance[];
+      const storedInstances = await invoke('load_instances', { sortBy: 'last_played' }) as MinecraftInstance[];
       return storedInstances.filter(instance => {
         const gameDir = (instance as any).gameDir || (instance as any).game_dir;
         return !!gameDir;
       }).map(instance => {
         const rawInstance = instance as any;
+        // Dates arrive as epoch milliseconds
+        const lastPlayed = rawInstance.lastPlayed ?? rawInstance.last_played;
+        const createdAt = rawInstance.createdAt ?? rawInstance.created_at;
         return {
           id: rawInstance.id,
           name: rawInstance.name,
@@ -19,14 +23,26 @@ export class InstanceService {
           gameDir: rawInstance.game_dir || rawInstance.gameDir,
           javaPath: rawInstance.java_path,
           jvmArgs: rawInstance.jvm_args,
-          lastPlayed: rawInstance.last_played ? new Date(rawInstance.last_played) : undefined,
-          totalPlayTime: rawInstance.total_play_time || 0,
+          lastPlayed: lastPlayed ? new Date(lastPlayed) : undefined,
+          createdAt: createdAt ? new Date(createdAt) : undefined,
+          sizeMb: rawInstance.sizeMb ?? rawInstance.size_mb,
+          totalPlayTime: rawInstance.totalPlayTime || rawInstance.total_play_time || 0,
           icon: rawInstance.icon,
           isModded: rawInstance.is_modded || false,
           modsCount: rawInstance.mods_count || 0,
           isExternal: rawInstance.is_external,
           externalLauncher: rawInstance.external_launcher,
-          status: rawInstance.status || 'ready' as const
+          status: rawInstance.status || 'ready' as const,
+          unstable: rawInstance.unstable || false,
+          health: rawInstance.health ?? undefined,
+          preLaunchCommand: rawInstance.preLaunchCommand,
+          postExitCommand: rawInstance.postExitCommand,
+          envVars: rawInstance.envVars,
+          clearInheritedEnv: rawInstance.clearInheritedEnv || false,
+          windowWidth: rawInstance.windowWidth ?? undefined,
+          windowHeight: rawInstance.windowHeight ?? undefined,
+          fullscreen: rawInstance.fullscreen ?? undefined,
+          jvmArgsPreset: rawInstance.jvmArgsPreset ?? undefined
         } as MinecraftInstance;
       });
     } catch (error) {
@@ -87,6 +103,11 @@ export class InstanceService {
     await invoke('install_minecraft_version', params);
   }
 
+  /** Download whatever the instance is missing so it can launch */
+  static async prepareInstance(instanceId: string): Promise<MinecraftInstance> {
+    return await invoke('prepare_instance', { instanceId }) as MinecraftInstance;
+  }
+
   static async launchInstance(params: {
     instanceId: string;
     instancePath: string;
@@ -94,6 +115,10 @@ export class InstanceService {
     javaPath: string;
     memory: number;
     jvmArgs: string[];
+    /** host[:port] to join once the game has started */
+    serverAddress?: string;
+    /** Launch as this stored account instead of the active one */
+    accountUuid?: string;
   }): Promise<void> {
     await invoke('launch_instance', params);
   }
@@ -114,6 +139,188 @@ export class InstanceService {
       return [];
     }
   }
+
+  static async importMinecraftFolderZip(zipPath: string, instanceName: string, stripJunk = false): Promise<FolderImportResult> {
+    return await invoke('import_minecraft_folder_zip', { zipPath, instanceName, stripJunk }) as FolderImportResult;
+  }
+
+  /** Copy an instance into a sibling folder, returning the new instance id */
+  static async duplicateInstance(instanceId: string, newName: string): Promise<string> {
+    return await invoke('duplicate_instance', { instanceId, newName }) as string;
+  }
+
+  /** Rename an instance and optionally its folder, rejecting with an InstanceRenameError */
+  static async renameInstance(instanceId: string, newName: string, renameDirectory: boolean): Promise<{ name: string; gameDir: string }> {
+    const renamed = await invoke('rename_instance', { instanceId, newName, renameDirectory }) as MinecraftInstance;
+    return { name: renamed.name, gameDir: renamed.gameDir };
+  }
+
+  /** Set the commands run before launching and after the game exits, empty clears them */
+  static async updateInstanceHooks(instanceId: string, preLaunchCommand: string, postExitCommand: string): Promise<void> {
+    await invoke('update_instance_hooks', { instanceId, preLaunchCommand, postExitCommand });
+  }
+
+  /** Update a Modrinth pack instance to another version, progress arrives as modpack_install_progress */
+  static async updateModpackInstance(instanceId: string, projectId: string, versionId: string): Promise<ModpackUpdateReport> {
+    return await invoke('update_modpack_instance', { instanceId, projectId, versionId }) as ModpackUpdateReport;
+  }
+
+  /** Set the environment variables an instance launches with */
+  static async updateInstanceEnvironment(instanceId: string, envVars: Record<string, string>, clearInheritedEnv: boolean): Promise<void> {
+    await invoke('update_instance_environment', { instanceId, envVars, clearInheritedEnv });
+  }
+
+  /** Set the window size and fullscreen overrides, null falls back to the launcher settings */
+  static async updateInstanceWindow(instanceId: string, windowWidth: number | null, windowHeight: number | null, fullscreen: boolean | null): Promise<void> {
+    await invoke('update_instance_window', { instanceId, windowWidth, windowHeight, fullscreen });
+  }
+
+  /** Set the JVM argument preset, null follows the launcher setting */
+  static async updateInstanceJvmPreset(instanceId: string, preset: JvmArgsPreset | null): Promise<void> {
+    await invoke('update_instance_jvm_preset', { instanceId, preset });
+  }
+
+  /** Presets with the flags they expand to, for the default memory when none is given */
+  static async getJvmPresets(memory?: number): Promise<JvmPresetInfo[]> {
+    return await invoke('get_jvm_presets', { memory: memory ?? null }) as JvmPresetInfo[];
+  }
+
+  /** Check a memory setting and the instance's JVM arguments against this computer's memory */
+  static async validateLaunchConfig(instanceId: string, memory: number): Promise<LaunchValidation> {
+    return await invoke('validate_launch_config', { instanceId, memory }) as LaunchValidation;
+  }
+
+  /** Resolve what a launch would use, including its environment, without starting the game */
+  static async diagnoseLaunch(instanceId: string, worldFolder?: string, serverAddress?: string): Promise<LaunchDiagnostics> {
+    return await invoke('diagnose_launch', { instanceId, worldFolder, serverAddress }) as LaunchDiagnostics;
+  }
+
+  /** Save an instance's keybinds and the picked config files or folders as a named preset */
+  static async saveSettingsPreset(name: string, instanceId: string, includePaths: string[]): Promise<SettingsPreset> {
+    return await invoke('save_settings_preset', { name, instanceId, includePaths }) as SettingsPreset;
+  }
+
+  static async listSettingsPresets(): Promise<SettingsPreset[]> {
+    return await invoke('list_settings_presets') as SettingsPreset[];
+  }
+
+  /** Merge a preset into an instance, conflicting keybinds and files are only replaced with `overwrite` */
+  static async applySettingsPreset(instanceId: string, name: string, overwrite: boolean): Promise<PresetApplyResult> {
+    return await invoke('apply_settings_preset', { instanceId, name, overwrite }) as PresetApplyResult;
+  }
+
+  static async deleteSettingsPreset(name: string): Promise<void> {
+    await invoke('delete_settings_preset', { name });
+  }
+
+  /** Download history of a file in an instance, oldest first */
+  static async getFileProvenance(instanceId: string, relativePath: string): Promise<ProvenanceEntry[]> {
+    return await invoke('get_file_provenance', { instanceId, relativePath }) as ProvenanceEntry[];
+  }
+
+  /** Merge `removeId` into `keepId` when both point at the same game directory */
+  static async resolveDuplicateInstances(keepId: string, removeId: string): Promise<void> {
+    await invoke('resolve_duplicate_instances', { keepId, removeId });
+  }
+
+  /** Find new folders for instances whose game directory is missing, `dryRun` only previews the mapping */
+  static async repairInstancePaths(dryRun: boolean, instanceIds?: string[]): Promise<PathRepairPlan> {
+    return await invoke('repair_instance_paths', { dryRun, instanceIds: instanceIds ?? null }) as PathRepairPlan;
+  }
+
+  /** Play time per instance and in total, in seconds */
+  static async getPlaytimeStats(): Promise<PlaytimeStats> {
+    return await invoke('get_playtime_stats') as PlaytimeStats;
+  }
+
+  /** Crash ratios and the most frequent crash of an instance */
+  static async getInstanceStability(instanceId: string): Promise<StabilitySummary> {
+    return await invoke('get_instance_stability', { instanceId }) as StabilitySummary;
+  }
+
+  /** Result of the last integrity check, null until the sweep reached the instance */
+  static async getInstanceHealth(instanceId: string): Promise<InstanceHealth | null> {
+    return await invoke('get_instance_health', { instanceId }) as InstanceHealth | null;
+  }
+
+  /** Download mods the instance's manifest lists but its mods folder lacks, returns their names */
+  static async repairInstanceMods(instanceId: string): Promise<string[]> {
+    return await invoke('repair_instance_mods', { instanceId }) as string[];
+  }
+
+  /** Run the repair a health issue links to */
+  static async runRepair(instanceId: string, action: RepairAction): Promise<void> {
+    switch (action) {
+      case 'prepare_instance':
+        await InstanceService.prepareInstance(instanceId);
+        break;
+      case 'repair_instance_mods':
+        await InstanceService.repairInstanceMods(instanceId);
+        break;
+    }
+  }
+
+  /** Newest crash report of an instance, null if it never crashed */
+  static async getLatestCrashReport(instanceId: string): Promise<CrashReport | null> {
+    return await invoke('get_latest_crash_report', { instanceId }) as CrashReport | null;
+  }
+
+  /** Installed mods and the dependencies between them, for a graph view */
+  static async getModDependencyGraph(instanceId: string): Promise<ModDependencyGraph> {
+    return await invoke('get_mod_dependency_graph', { instanceId }) as ModDependencyGraph;
+  }
+
+  /** Enabled jars that can't be loaded together, with the older copies to disable */
+  static async getModConflicts(instanceId: string): Promise<ModConflict[]> {
+    return await invoke('get_mod_conflicts', { instanceId }) as ModConflict[];
+  }
+
+  /** Put a mod in a named group, null removes it from its group */
+  static async setModGroup(instanceId: string, modId: string, group: string | null): Promise<void> {
+    await invoke('set_mod_group', { instanceId, modId, group });
+  }
+
+  /** Groups in an instance with how many of their mods are enabled */
+  static async listModGroups(instanceId: string): Promise<ModGroupSummary[]> {
+    return await invoke('list_mod_groups', { instanceId }) as ModGroupSummary[];
+  }
+
+  /** Enable or disable a whole group, dependencies of mods outside it stay enabled */
+  static async setGroupEnabled(instanceId: string, group: string, enabled: boolean): Promise<GroupToggleResult> {
+    return await invoke('set_group_enabled', { instanceId, group, enabled }) as GroupToggleResult;
+  }
+
+  /** Game output logged since the launch, for a console opened afterwards */
+  static async getRecentGameLog(instanceId: string, maxLines?: number): Promise<GameLogLine[]> {
+    return await invoke('get_recent_game_log', { instanceId, maxLines }) as GameLogLine[];
+  }
+
+  /** Force-stop the games running from an instance, returns how many were stopped */
+  static async killInstance(instanceId: string): Promise<number> {
+    return await invoke('kill_instance', { instanceId }) as number;
+  }
+
+  /** Games started by the launcher that haven't exited yet */
+  static async getRunningInstances(): Promise<RunningInstance[]> {
+    return await invoke('get_running_instances') as RunningInstance[];
+  }
+
+  static async listWorlds(instanceId: string): Promise<InstanceWorlds> {
+    return await invoke('list_instance_worlds', { instanceId }) as InstanceWorlds;
+  }
+
+  static async launchIntoWorld(instanceId: string, worldFolder: string, memory: number): Promise<void> {
+    await invoke('launch_instance_into_world', { instanceId, worldFolder, memory });
+  }
+
+  /** Launch and join a server, memory defaults to the launcher setting */
+  static async joinServer(instanceId: string, address: string, memory?: number): Promise<void> {
+    await invoke('join_server', { instanceId, address, memory });
+  }
+
+  static async getRecentServers(instanceId: string): Promise<RecentServer[]> {
+    return await invoke('get_recent_servers', { instanceId }) as RecentServer[];
+  }
 }
 
 export default InstanceService;
\ No newline at end of file
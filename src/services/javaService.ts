@@ -1,10 +1,29 @@
 import { invoke } from '@tauri-apps/api/core';
+import { BundledJavaRuntime, JavaRuntime, ResolvedJava } from '../types/minecraft';
 
 export class JavaService {
   static async getBundledJavaPath(): Promise<string> {
     return await invoke('get_bundled_java_path') as string;
   }
 
+  static async resolveJava(majorVersion: number): Promise<ResolvedJava> {
+    return await invoke('get_bundled_java_path_for_version', { majorVersion }) as ResolvedJava;
+  }
+
+  /** Every runtime that runs, the launcher's own first */
+  static async getJavaRuntimes(): Promise<JavaRuntime[]> {
+    return await invoke('get_java_runtimes') as JavaRuntime[];
+  }
+
+  static async listRuntimes(): Promise<BundledJavaRuntime[]> {
+    return await invoke('list_java_runtimes') as BundledJavaRuntime[];
+  }
+
+  /** Resolves to the number of bytes freed */
+  static async removeRuntime(majorVersion: number): Promise<number> {
+    return await invoke('remove_java_installation', { majorVersion }) as number;
+  }
+
   static async getJavaForMinecraftVersion(minecraftVersion: string): Promise<string> {
     return await invoke('get_java_for_minecraft_version', { minecraftVersion }) as string;
   }
@@ -31,13 +50,9 @@ export class JavaService {
       const javaPath = await this.getJavaForMinecraftVersion(version);
       return { javaPath, requiresInstall: false };
     } catch {
-      try {
-        const javaPath = await this.getBundledJavaPath();
-        return { javaPath, requiresInstall: false };
-      } catch {
-        const requiredVersion = await this.getRequiredJavaVersion(version).catch(() => 17);
-        return { javaPath: '', requiresInstall: true, requiredVersion };
-      }
+      // Another major version can't run the game, e.g. 1.20.5+ fails on Java 17
+      const requiredVersion = await this.getRequiredJavaVersion(version).catch(() => 17);
+      return { javaPath: '', requiresInstall: true, requiredVersion };
     }
   }
 }
@@ -1,11 +1,12 @@
 import { invoke } from '@tauri-apps/api/core';
-import { MinecraftVersion } from '../types/minecraft';
+import { LoaderAvailability, MinecraftVersion, VersionListOptions } from '../types/minecraft';
 
 export class MinecraftService {
-  static async loadVersions(): Promise<{ versions: MinecraftVersion[]; error?: string }> {
+  /** Versions from the cached manifest, `forceRefresh` asks Mojang even when the cache is recent */
+  static async loadVersions(forceRefresh = false, options: VersionListOptions = {}): Promise<{ versions: MinecraftVersion[]; cacheAgeSecs?: number; error?: string }> {
     try {
-      const versionManifest = await invoke('get_minecraft_versions') as { versions: MinecraftVersion[] };
-      return { versions: versionManifest.versions };
+      const versionManifest = await invoke('get_minecraft_versions', { forceRefresh, ...options }) as { versions: MinecraftVersion[]; cacheAgeSecs: number };
+      return { versions: versionManifest.versions, cacheAgeSecs: versionManifest.cacheAgeSecs };
     } catch (error) {
       console.error('Failed to load Minecraft versions:', error);
       
@@ -30,6 +31,11 @@ export class MinecraftService {
       };
     }
   }
+
+  /** Which mod loaders have builds for a Minecraft version */
+  static async getLoaderAvailability(mcVersion: string): Promise<LoaderAvailability[]> {
+    return await invoke('get_loader_availability', { mcVersion }) as LoaderAvailability[];
+  }
 }
 
 export default MinecraftService;
\ No newline at end of file
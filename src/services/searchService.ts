@@ -0,0 +1,14 @@
+import { invoke } from '@tauri-apps/api/core';
+import { SearchHit, SearchIndexStats, SearchKind } from '../types/minecraft';
+
+export class SearchService {
+  /** Search instances, tags, descriptions, mods and worlds, every kind when `kinds` is left out */
+  static async searchLocal(query: string, kinds?: SearchKind[], limit?: number): Promise<SearchHit[]> {
+    return await invoke('search_local', { query, kinds, limit }) as SearchHit[];
+  }
+
+  /** Index every instance again, e.g. when results look stale */
+  static async rebuildSearchIndex(): Promise<SearchIndexStats> {
+    return await invoke('rebuild_search_index') as SearchIndexStats;
+  }
+}
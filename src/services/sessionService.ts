@@ -0,0 +1,41 @@
+import { invoke } from '@tauri-apps/api/core';
+import { BackgroundWork, ResumeAction, SessionRecord } from '../types/minecraft';
+
+export class SessionService {
+  /** How the previous session ended, null if it exited cleanly with nothing in progress */
+  static async getPreviousSessionReport(): Promise<SessionRecord | null> {
+    return await invoke('get_previous_session_report') as SessionRecord | null;
+  }
+
+  static async dismissPreviousSessionReport(): Promise<void> {
+    await invoke('dismiss_previous_session_report');
+  }
+
+  /** Tasks, installs and games that exiting now would affect */
+  static async getBackgroundWork(): Promise<BackgroundWork> {
+    return await invoke('get_background_work') as BackgroundWork;
+  }
+
+  /** Instance the launcher was started to launch from the taskbar menu, only returned once */
+  static async takePendingLaunch(): Promise<string | null> {
+    return await invoke('take_pending_launch') as string | null;
+  }
+
+  static async resume(action: ResumeAction): Promise<void> {
+    switch (action.kind) {
+      case 'modpack_install': {
+        const { project_id, version_id, instance_name, instance_dir, platform } = action.request;
+        await invoke('install_modpack', {
+          projectId: project_id,
+          versionId: version_id,
+          instanceName: instance_name,
+          instanceDir: instance_dir,
+          platform,
+        });
+        break;
+      }
+    }
+  }
+}
+
+export default SessionService;
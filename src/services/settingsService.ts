@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { LauncherSettings } from '../types/minecraft';
+import { DownloadCacheStats, DownloadProgress, ExecRecord, GraphicsProbe, LauncherSettings } from '../types/minecraft';
 import { applyColorScheme } from '../utils/colors';
 
 export class SettingsService {
@@ -37,6 +37,37 @@ export class SettingsService {
     };
   }
 
+  /** Programs hooks ran, newest first */
+  static async getExecutionAudit(limit?: number): Promise<ExecRecord[]> {
+    return await invoke('get_execution_audit', { limit }) as ExecRecord[];
+  }
+
+  static async respondExecConfirmation(requestId: string, allow: boolean): Promise<void> {
+    await invoke('respond_exec_confirmation', { requestId, allow });
+  }
+
+  /** OpenGL version and renderer of the graphics driver, cached for a day */
+  static async probeGraphics(refresh = false): Promise<GraphicsProbe> {
+    return await invoke('probe_graphics', { refresh }) as GraphicsProbe;
+  }
+
+  static async getDownloadCacheStats(): Promise<DownloadCacheStats> {
+    return await invoke('get_download_cache_stats') as DownloadCacheStats;
+  }
+
+  /** Returns the number of bytes freed */
+  static async clearDownloadCache(): Promise<number> {
+    return await invoke('clear_download_cache') as number;
+  }
+
+  static async listActiveDownloads(): Promise<DownloadProgress[]> {
+    return await invoke('list_active_downloads') as DownloadProgress[];
+  }
+
+  static async cancelDownload(taskId: string): Promise<void> {
+    await invoke('cancel_download', { taskId });
+  }
+
   static async openFolder(path: string): Promise<void> {
     await invoke('open_folder', { path });
   }
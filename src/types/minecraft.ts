@@ -7,11 +7,57 @@ export interface MinecraftAccount {
   expires_at: number;
   skin_url?: string;
   cape_url?: string;
+  /** The token couldn't be refreshed, signing in again clears it */
+  needs_relogin?: boolean;
   type?: 'microsoft' | 'offline';
   isActive?: boolean;
   lastUsed?: Date;
 }
 
+/** Progress of the browser sign-in, from the `oauth_flow_state` event */
+export type OAuthFlowState = 'waiting_for_browser' | 'code_received' | 'exchanging' | 'done' | 'failed';
+
+export interface OAuthFlowEvent {
+  state: OAuthFlowState;
+  message?: string;
+}
+
+/** Code from start_device_code_auth, entered by the player at verification_uri */
+export interface DeviceCode {
+  user_code: string;
+  verification_uri: string;
+  expires_in: number;
+}
+
+/** From get_player_skin, paths are on disk */
+export interface PlayerSkin {
+  uuid: string;
+  /** Unset for the default skins */
+  skin_path?: string;
+  /** 8x8 face with the hat layer on top */
+  head_path: string;
+  model: 'classic' | 'slim';
+  is_default: boolean;
+  fetched_at?: string;
+}
+
+export type DeviceAuthState = 'waiting_for_user' | 'authenticating' | 'done' | 'failed';
+
+/** Payload of auth_progress while poll_device_code_auth runs */
+export interface AuthProgressEvent {
+  state: DeviceAuthState;
+  user_code: string;
+  verification_uri: string;
+  message?: string;
+}
+
+/** Error from start_oauth_with_server and poll_device_code_auth */
+export interface OAuthError {
+  kind: 'timeout' | 'ports_in_use' | 'failed';
+  message: string;
+  seconds?: number;
+}
+
 export interface MinecraftInstance {
   id: string;
   name: string;
@@ -22,26 +68,72 @@ export interface MinecraftInstance {
   javaPath?: string;
   jvmArgs?: string[];
   lastPlayed?: Date;
+  /** Seconds */
   totalPlayTime: number;
   icon?: string;
   isModded: boolean;
   modsCount: number;
   isExternal?: boolean;
   externalLauncher?: 'gdlauncher' | 'multimc' | 'prism' | 'modrinth';
-  createdAt?: string;
+  createdAt?: Date;
   sizeMb?: number;
   description?: string;
   tags?: string[];
   status?: 'ready' | 'installing' | 'failed' | 'invalid';
   installProgress?: number;
   errorMessage?: string;
+  /** Whether the game files needed to launch are present */
+  ready?: boolean;
+  notReadyReason?: string;
+  /** Whether a game launched from this instance is still running */
+  running?: boolean;
+  /** Whether enough recent sessions crashed to call the setup flaky */
+  unstable?: boolean;
+  /** Outcome of the last background integrity check */
+  health?: HealthStatus;
+  /** Crash report of the last session, cleared on the next launch */
+  lastCrash?: CrashReport;
+  /** Shell command run in the instance folder before launching */
+  preLaunchCommand?: string;
+  /** Shell command run in the instance folder after the game exits */
+  postExitCommand?: string;
+  /** Environment variables set for the game on top of the launcher's own */
+  envVars?: Record<string, string>;
+  /** Start the game with only envVars, none of the launcher's environment */
+  clearInheritedEnv?: boolean;
+  /** Window size override, unset uses the launcher setting and 0 lets the game decide */
+  windowWidth?: number;
+  windowHeight?: number;
+  /** Fullscreen override, unset uses the launcher setting */
+  fullscreen?: boolean;
+  /** JVM argument preset override, unset uses the launcher setting */
+  jvmArgsPreset?: JvmArgsPreset;
+}
+
+export type JvmArgsPreset = 'default' | 'aikar' | 'g1gc_balanced' | 'zgc_large_heap';
+
+/** A JVM argument preset and the flags it expands to */
+export interface JvmPresetInfo {
+  id: JvmArgsPreset;
+  name: string;
+  description: string;
+  args: string[];
 }
 
 export interface MinecraftVersion {
   id: string;
-  type: 'release' | 'snapshot' | 'beta' | 'alpha';
+  type: 'release' | 'snapshot' | 'old_beta' | 'old_alpha';
   releaseTime: string;
   url: string;
+  isLatestRelease?: boolean;
+  isLatestSnapshot?: boolean;
+}
+
+/** Version types get_minecraft_versions lists, each left out counts as true */
+export interface VersionListOptions {
+  includeSnapshots?: boolean;
+  includeOldBeta?: boolean;
+  includeOldAlpha?: boolean;
 }
 
 export interface ModpackInfo {
@@ -112,10 +204,276 @@ export interface ModpackInstallProgress {
   stage: string;
 }
 
+/** A CurseForge file whose author blocks launcher downloads */
+export interface ManualDownload {
+  project_id: number;
+  file_id: number;
+  file_name: string;
+  url: string;
+}
+
+/** Error from install_modpack, `cancelled` when stopped through cancel_modpack_install */
+/** Arguments of an install_modpack call, kept to resume it */
+export interface ModpackInstallRequest {
+  project_id: string;
+  version_id: string;
+  instance_name: string;
+  instance_dir: string;
+  platform: string;
+}
+
+/** Background task tracked by the backend, sent with `task_updated` */
+export interface TaskInfo {
+  id: string;
+  kind: string;
+  queue_key: string;
+  status: 'queued' | 'running' | 'completed' | 'failed' | 'cancelled';
+  progress: number;
+  message?: string;
+  error?: string;
+  created_at: string;
+  subject?: string;
+}
+
+export type ResumeAction = { kind: 'modpack_install'; request: ModpackInstallRequest };
+
+export interface InterruptedTask {
+  kind: string;
+  subject?: string;
+  progress: number;
+  message?: string;
+  /** Missing when the work can't be resumed automatically */
+  resume?: ResumeAction;
+}
+
+/** How the previous launcher session ended, from `previous_session_interrupted` */
+export interface SessionRecord {
+  started_at: string;
+  ended_at?: string;
+  /** False when the launcher crashed or was killed */
+  clean_exit: boolean;
+  interrupted: InterruptedTask[];
+  running_games: RunningInstance[];
+}
+
+/** What exiting now would cut short */
+export interface BackgroundWork {
+  tasks: TaskInfo[];
+  modpack_installs: ModpackInstallRequest[];
+  running_games: RunningInstance[];
+}
+
+export interface ModpackInstallError {
+  kind: 'cancelled' | 'failed';
+  message: string;
+}
+
+/** Error from rename_instance, `in_use` when the game or another program holds the folder */
+export interface InstanceRenameError {
+  kind: 'in_use' | 'already_exists' | 'failed';
+  message: string;
+}
+
+export interface ProvenanceEntry {
+  path: string;
+  action: 'written' | 'replaced' | 'removed';
+  source_url?: string;
+  declared_hash?: string;
+  verified_hash?: string;
+  timestamp: string;
+  task_id?: string;
+}
+
+export type ExecFeature = 'pre_launch_hook' | 'post_exit_hook';
+
+/** A program the launcher ran for the user, from get_execution_audit */
+export interface ExecRecord {
+  feature: ExecFeature;
+  /** The command as the user wrote it */
+  command: string;
+  program: string;
+  args: string[];
+  cwd: string;
+  instance_id?: string;
+  outcome: 'exited' | 'timed_out' | 'failed_to_start' | 'denied';
+  exit_code?: number;
+  duration_ms: number;
+  timestamp: string;
+}
+
+/** Payload of exec_confirmation_required */
+export interface ExecConfirmation {
+  request_id: string;
+  feature: ExecFeature;
+  command: string;
+  cwd: string;
+  instance_id?: string;
+}
+
+/** Play time of one instance, in seconds */
+export interface InstancePlaytime {
+  instance_id: string;
+  name: string;
+  total_play_time: number;
+  last_played?: string;
+  running: boolean;
+}
+
+export interface PlaytimeStats {
+  /** Most played first */
+  instances: InstancePlaytime[];
+  total_play_time: number;
+}
+
+/** A game the launcher started that is still running */
+export interface RunningInstance {
+  instance_id: string;
+  pid: number;
+  started_at: string;
+  runtime_seconds: number;
+}
+
+/** Payload of `game_exited` */
+export interface GameExited {
+  instance_id: string;
+  /** Missing when the process was killed by a signal */
+  exit_code?: number;
+  runtime_seconds: number;
+  crashed: boolean;
+  /** Stopped with `kill_instance` */
+  killed: boolean;
+}
+
+/** The interesting parts of a Minecraft crash report */
+export interface CrashReport {
+  file_name: string;
+  path: string;
+  time?: string;
+  description?: string;
+  /** Exception class */
+  exception?: string;
+  exception_message?: string;
+  suspected_mods: string[];
+  minecraft_version?: string;
+  loader?: string;
+  loader_version?: string;
+  /** What the crash usually means, for common failures */
+  hint?: string;
+}
+
+/** Payload of `crash_detected` */
+export interface CrashDetected {
+  instance_id: string;
+  report: CrashReport;
+}
+
+/** OpenGL details of the graphics driver from probe_graphics */
+export interface GraphicsProbe {
+  gl_version?: string;
+  renderer?: string;
+  vendor?: string;
+  /** A CPU rasterizer such as llvmpipe draws instead of the GPU */
+  software: boolean;
+  /** Why no OpenGL context could be created */
+  error?: string;
+  /** The probe process died, usually a driver crash */
+  crashed: boolean;
+  probed_at: string;
+}
+
+/** Payload of `graphics_warning`, sent before a launch */
+export interface GraphicsWarning {
+  instance_id: string;
+  warnings: string[];
+  probe: GraphicsProbe;
+}
+
+/** One line of game output, `game_log` events carry a list of these */
+export interface GameLogLine {
+  instance_id: string;
+  stream: 'stdout' | 'stderr';
+  line: string;
+}
+
+export interface CrashSignatureCount {
+  /** Exception class, or the signal for JVM crashes */
+  exception: string;
+  suspected_mod?: string;
+  count: number;
+  last_seen: string;
+}
+
+/** How past sessions of an instance ended */
+export interface StabilitySummary {
+  sessions: number;
+  clean_exits: number;
+  crashes: number;
+  jvm_crashes: number;
+  clean_rate: number;
+  crash_rate: number;
+  jvm_crash_rate: number;
+  most_frequent_crash?: CrashSignatureCount;
+  last_clean_session?: string;
+  last_crash?: string;
+  unstable: boolean;
+}
+
+export type HealthStatus = 'ok' | 'issues';
+
+/** Command that fixes a health issue */
+export type RepairAction = 'prepare_instance' | 'repair_instance_mods';
+
+export interface HealthIssue {
+  kind: 'version_files' | 'libraries' | 'asset_index' | 'mods' | 'loader';
+  message: string;
+  /** Null when the player has to decide how to fix it */
+  repair?: RepairAction | null;
+}
+
+/** Result of the last integrity check of an instance */
+export interface InstanceHealth {
+  status: HealthStatus;
+  issues: HealthIssue[];
+  checked_at: string;
+}
+
+export interface ModpackInstallReport {
+  instance_dir: string;
+  warnings: string[];
+  manual_downloads: ManualDownload[];
+}
+
+/** Result of updating a pack instance, only changed files are downloaded */
+export interface ModpackUpdateReport {
+  instance_dir: string;
+  previous_version_id?: string;
+  version_id: string;
+  added: number;
+  updated: number;
+  removed: number;
+  unchanged: number;
+  cached: number;
+  overrides_written: number;
+  overrides_unchanged: number;
+  downloaded_bytes: number;
+  /** What downloading the whole pack again would have cost */
+  full_download_bytes: number;
+  warnings: string[];
+}
+
 export interface ModpackCreationRequest {
   instanceId: string;
   instancePath: string;
   metadata: ModpackMetadata;
+  format?: ModpackFormat;
+}
+
+export type ModpackFormat = 'zip' | 'mrpack';
+
+export interface ModpackCreationResult {
+  outputPath: string;
+  /** Mod jars bundled under overrides/ because Modrinth doesn't host them */
+  overrideMods: string[];
 }
 
 export interface ModpackMetadata {
@@ -158,6 +516,223 @@ export interface LauncherSettings {
   jvmArgs?: string[];
   gameDir?: string;
   auth_token?: string;
+  curseforge_api_key?: string;
+  max_concurrent_downloads?: number;
+  /** Never fall back to a system Java install */
+  java_bundled_only?: boolean;
+  /** Seconds the Microsoft sign-in waits for the browser */
+  oauth_timeout_secs?: number;
+  /** Launch as offline_username when no Microsoft account can be used */
+  allow_offline_launch?: boolean;
+  /** 3 to 16 letters, digits or underscores */
+  offline_username?: string | null;
+  /** Modpack files downloaded at once */
+  modpack_download_concurrency?: number;
+  /** Size limit of the shared download cache, 0 turns it off */
+  download_cache_max_mb?: number;
+  /** Allow launching an instance that is already running */
+  allow_multiple_instances?: boolean;
+  /** Ask before closing while tasks, installs or games are running */
+  confirm_exit_with_background_work?: boolean;
+  /** Seconds a pre-launch or post-exit hook may run before it is stopped */
+  hook_timeout_secs?: number;
+  /** Ask before a hook runs a command for the first time */
+  confirm_external_commands?: boolean;
+  /** Check the OpenGL driver before launching */
+  probe_graphics_before_launch?: boolean;
+  /** Extra folders searched for instances whose folder has moved */
+  instance_search_roots?: string[];
+  /** Where account tokens are kept */
+  token_storage?: 'keyring' | 'encrypted_file';
+  /** Host ports servers are automatically given from, both ends included */
+  server_port_range?: { start: number; end: number };
+  /** JVM flags games launch with unless the instance picks its own */
+  jvm_args_preset?: JvmArgsPreset;
+  /** Mirror game files, loader metadata and libraries are downloaded from */
+  download_mirror?: DownloadMirror;
+  /** Proxy every HTTP request goes through */
+  proxy?: ProxySettings;
+  /** List recently played instances in the taskbar or dock menu */
+  jump_list_enabled?: boolean;
+  /** Game window size for instances without their own, 0 lets the game decide */
+  window_width?: number;
+  window_height?: number;
+  fullscreen?: boolean;
+  /** Days uninstalled mods can still be restored */
+  mod_trash_retention_days?: number;
+  /** Microsoft account launches use, changed with setActiveAccount */
+  active_account_uuid?: string | null;
+  /** Check instances in the background while the launcher is idle */
+  integrity_sweep_enabled?: boolean;
+  /** Pause between files the sweep checks, in milliseconds */
+  integrity_sweep_pace_ms?: number;
+  notifications_enabled?: boolean;
+  notification_categories?: NotificationCategories;
+}
+
+/** Base URLs replacing the official ones, unset services stay official */
+export interface MirrorUrls {
+  version_meta?: string | null;
+  libraries?: string | null;
+  assets?: string | null;
+  forge?: string | null;
+  fabric_meta?: string | null;
+  fabric_maven?: string | null;
+}
+
+export type DownloadMirror =
+  | { kind: 'none' }
+  | { kind: 'bmclapi' }
+  | ({ kind: 'custom' } & MirrorUrls);
+
+export interface ProxySettings {
+  mode: 'system' | 'manual' | 'none';
+  /** Manual proxy, `http://` is assumed without a scheme */
+  url?: string | null;
+  username?: string | null;
+  /** Only sent when saving, an empty one removes the saved password */
+  password?: string;
+  /** Comma separated hosts, `.domain` suffixes and CIDR ranges to reach directly */
+  no_proxy?: string | null;
+}
+
+export interface DownloadCacheStats {
+  path: string;
+  files: number;
+  total_bytes: number;
+  /** 0 when the cache is turned off */
+  max_bytes: number;
+  /** Since the launcher started */
+  hits: number;
+  misses: number;
+}
+
+/** A queued or running download, as sent with the `downloads_progress` event */
+export interface DownloadProgress {
+  task_id: string;
+  kind: 'java' | 'modpack' | 'mod';
+  priority: 'low' | 'normal' | 'high';
+  file: string;
+  status: 'queued' | 'running' | 'finished' | 'failed' | 'cancelled';
+  bytes: number;
+  /** 0 while the size is unknown */
+  total: number;
+  /** Bytes per second */
+  speed: number;
+}
+
+export interface NotificationCategories {
+  installs: boolean;
+  servers: boolean;
+  updates: boolean;
+}
+
+/** Where clicking a notification should lead */
+export type NotificationTarget =
+  | { kind: 'instance'; id: string }
+  | { kind: 'server'; id: string }
+  | { kind: 'task'; id: string }
+  | { kind: 'view'; view: string };
+
+/** Payload of the `notification_shown` event */
+export interface LauncherNotification {
+  category: 'installs' | 'servers' | 'updates';
+  title: string;
+  body: string;
+  target?: NotificationTarget;
+}
+
+/** Whether a mod loader can be installed for a Minecraft version */
+export interface LoaderAvailability {
+  loader: 'forge' | 'fabric' | 'quilt' | 'neoforge';
+  available: boolean;
+  recommended_version?: string;
+  latest_version?: string;
+  /** Newest builds first */
+  versions: string[];
+  min_java_version: number;
+  /** Set when the builds could not be listed, e.g. offline */
+  error?: string;
+}
+
+export type JavaSource = 'bundled' | 'system' | 'custom';
+
+/** The Java runtime picked for a major version and where it came from */
+export interface ResolvedJava {
+  major_version: number;
+  path: string;
+  source: JavaSource;
+  version: string;
+}
+
+/** A Java runtime found on this machine, described by running it */
+export interface JavaRuntime {
+  path: string;
+  major_version: number;
+  /** e.g. `17.0.9` or `1.8.0_381` */
+  full_version: string;
+  vendor?: string;
+  arch?: string;
+  is_bundled: boolean;
+  source: JavaSource;
+}
+
+/** A Java runtime in the launcher's own java directory */
+export interface BundledJavaRuntime {
+  major_version: number;
+  directory: string;
+  /** Missing when extraction never finished */
+  path?: string;
+  /** Missing when the runtime doesn't run */
+  version?: string;
+  healthy: boolean;
+  size_bytes: number;
+}
+
+/** The environment a launch would start the game with */
+export interface LaunchEnvironment {
+  variables: Record<string, string>;
+  /** Variables set by the instance */
+  overridden: string[];
+  cleared_inherited: boolean;
+}
+
+/** Launch dry run from diagnose_launch, the access token is redacted */
+/** A version JSON merged with the versions it inherits from */
+export interface LaunchProfile {
+  id: string;
+  main_class: string;
+  game_args: string[];
+  /** Maven coordinates in classpath order */
+  libraries: string[];
+  client_jar: string;
+  legacy_arguments: boolean;
+}
+
+/** Memory of a launch checked against this computer's */
+export interface LaunchValidation {
+  /** Memory setting the game launches with, raised to the minimum if it was below */
+  memory_mb: number;
+  /** Heap the JVM is given, a custom -Xmx wins over the memory setting */
+  heap_mb: number;
+  system?: { total_mb: number; available_mb: number };
+  /** Why the launch would be refused */
+  errors: string[];
+  warnings: string[];
+}
+
+export interface LaunchDiagnostics {
+  game_args: string[];
+  rejected_game_args: string[];
+  java?: ResolvedJava;
+  java_error?: string;
+  environment: LaunchEnvironment;
+  /** Missing until the instance's version JSON is installed */
+  profile?: LaunchProfile;
+  classpath: string[];
+  /** Missing when probing is turned off in the settings */
+  graphics?: GraphicsProbe;
+  graphics_warnings: string[];
 }
 
 export interface NewsItem {
@@ -195,3 +770,110 @@ export interface InstallCompleteEvent {
   error?: string;
 }
 
+export interface FolderImportResult {
+  instance_id: string;
+  name: string;
+  game_dir: string;
+  minecraft_version?: string;
+  loader?: string;
+  mods_count: number;
+  /** The archive had no installed versions, run the installer before launching */
+  needs_install: boolean;
+  stripped: string[];
+  warnings: string[];
+}
+
+/** Payload of `duplicate_instances_detected`: instances sharing one game directory */
+export interface DuplicateInstances {
+  game_dir: string;
+  instance_ids: string[];
+}
+
+export interface PathRepair {
+  instance_id: string;
+  name: string;
+  old_path: string;
+  new_path: string;
+  matched_by: 'instance_id' | 'folder_name' | 'drive_letter';
+}
+
+export interface MissingInstance {
+  instance_id: string;
+  name: string;
+  game_dir: string;
+}
+
+/** Payload of `instance_paths_broken` and result of `repair_instance_paths` */
+export interface PathRepairPlan {
+  repairs: PathRepair[];
+  /** Missing instances no new folder was found for */
+  unresolved: MissingInstance[];
+}
+
+export interface WorldSummary {
+  /** Folder name under saves, pass it back to launch into the world */
+  folder: string;
+  /** Epoch milliseconds */
+  last_played?: number;
+  has_icon: boolean;
+}
+
+/** A server an instance was launched into */
+export interface RecentServer {
+  /** host:port */
+  address: string;
+  /** RFC 3339 */
+  last_joined: string;
+}
+
+/** Keybinds and config files saved under a name, see save_settings_preset */
+export interface SettingsPreset {
+  name: string;
+  id: string;
+  created_at: string;
+  source_instance?: string;
+  keybinds: { key: string; value: string }[];
+  /** Relative to the instance folder */
+  files: string[];
+}
+
+export interface KeybindConflict {
+  key: string;
+  instance_value: string;
+  preset_value: string;
+}
+
+/** Conflicts were replaced by the preset when `overwrite` was set */
+export interface PresetApplyResult {
+  overwrite: boolean;
+  keybinds_added: string[];
+  keybind_conflicts: KeybindConflict[];
+  files_copied: string[];
+  file_conflicts: string[];
+  skipped: { path: string; reason: string }[];
+  backup_dir?: string;
+}
+
+export interface InstanceWorlds {
+  /** False before 1.20, hide the play world button */
+  quick_play_supported: boolean;
+  worlds: WorldSummary[];
+}
+
+export type SearchKind = 'instance' | 'tag' | 'note' | 'mod' | 'world';
+
+export interface SearchHit {
+  kind: SearchKind;
+  /** Instance owning the hit */
+  instance_id: string;
+  instance_name: string;
+  title: string;
+  detail?: string;
+  score: number;
+}
+
+export interface SearchIndexStats {
+  instances: number;
+  documents: number;
+  reindexed: number;
+}
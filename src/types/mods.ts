@@ -52,6 +52,8 @@ export interface InstalledMod {
   enabled: boolean;
   install_date: string;
   update_available?: ModFile;
+  /** Named group the mod is toggled with */
+  group?: string | null;
 }
 
 export type ModLoader = 
@@ -87,6 +89,120 @@ export interface ModUpdateProgress {
   total: number;
 }
 
+// Server mod manifests
+export interface ManifestMod {
+  filename: string;
+  sha1: string;
+  url?: string;
+}
+
+export interface LocalModFile {
+  filename: string;
+  sha1: string;
+}
+
+export interface ManifestComparison {
+  matching: number;
+  missing: ManifestMod[];
+  extra: LocalModFile[];
+  mismatched: { expected: ManifestMod; local: LocalModFile }[];
+}
+
+export interface ManifestSyncReport {
+  installed: string[];
+  /** Moved to mods/.extra */
+  quarantined: string[];
+  failed: { filename: string; reason: string }[];
+}
+
+// Dependency graph
+export interface ModGraphNode {
+  /** Installed mod id, or the dependency's id when nothing installed provides it */
+  id: string;
+  name: string;
+  version?: string;
+  /** Loader ids from the jar */
+  mod_ids: string[];
+  enabled: boolean;
+  installed: boolean;
+}
+
+export interface ModGraphEdge {
+  from: string;
+  to: string;
+  kind: 'required' | 'optional' | 'incompatible';
+  /** 'jar' for loader metadata, otherwise the platform */
+  source: string;
+  version?: string;
+}
+
+export interface ModDependencyGraph {
+  nodes: ModGraphNode[];
+  edges: ModGraphEdge[];
+}
+
+// Conflicts between enabled jars
+export interface ConflictingMod {
+  /** Manifest key, unset for jars added by hand */
+  mod_id?: string;
+  name: string;
+  version?: string;
+  file_name: string;
+}
+
+export interface ModGroupSummary {
+  name: string;
+  mod_count: number;
+  enabled_count: number;
+  /** 0 to 1 */
+  enabled_ratio: number;
+}
+
+export interface GroupToggleResult {
+  group: string;
+  enabled: boolean;
+  /** Ids of the mods that were switched */
+  changed: string[];
+  /** Members left enabled because a mod outside the group requires them */
+  kept_enabled: string[];
+  warnings: string[];
+}
+
+export interface ModConflict {
+  kind: 'duplicate_id' | 'overlapping_provides' | 'shared_mixin_config';
+  /** The mod id or mixin config the jars share */
+  id: string;
+  /** Newest first */
+  mods: ConflictingMod[];
+  message: string;
+  /** The older copies, disabling them resolves the conflict */
+  disable: ConflictingMod[];
+  suggestion: string;
+}
+
+/** Error from uninstall_mod, `required_by` until retried with `force` */
+export type ModUninstallError =
+  | { kind: 'required_by'; message: string; dependents: string[] }
+  | { kind: 'failed'; message: string };
+
+/** An uninstalled mod that restore_deleted_mod can put back */
+export interface TrashEntry {
+  id: string;
+  name: string;
+  original_file: string;
+  size: number;
+  deleted_at: string;
+}
+
+export interface ModsSyncProgress {
+  task_id: string;
+  instance_id: string;
+  current: number;
+  total: number;
+  filename: string;
+  status: 'installing' | 'installed' | 'failed';
+}
+
 // Events
 export interface ModInstalledEvent {
   instance_id: string;
@@ -3,8 +3,35 @@ export interface DockerConnection {
   name: string;
   host: string;
   port?: number;
-  connection_type: 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm';
+  connection_type: 'local' | 'windows_named_pipe' | 'unix_socket' | 'remote' | 'swarm' | 'ssh';
   is_connected: boolean;
+  tls?: DockerTlsConfig;
+  ssh?: DockerSshConfig;
+  /** State of the tunnel of an SSH connection */
+  tunnel?: TunnelStatus;
+}
+
+/** Key based login for a Docker host reached through SSH, host and port are the SSH server's */
+export interface DockerSshConfig {
+  user: string;
+  key_path?: string;
+  remote_socket?: string;
+}
+
+export interface TunnelStatus {
+  state: 'connected' | 'reconnecting' | 'failed';
+  error?: string;
+}
+
+/** Certificates of a TLS protected remote host, PEM text is moved to the secret store on save */
+export interface DockerTlsConfig {
+  ca_path?: string;
+  cert_path?: string;
+  key_path?: string;
+  ca_pem?: string;
+  cert_pem?: string;
+  key_pem?: string;
+  pem_stored?: boolean;
 }
 
 export interface ServerInstance {
@@ -14,12 +41,43 @@ export interface ServerInstance {
   docker_connection_id: string;
   container_id?: string;
   status: ServerStatus;
+  /** Host port players join on, moved off the requested one when it was taken */
   port: number;
   max_players: number;
-  memory_limit: number; // in MB
+  /** Java heap in MB */
+  memory_limit: number;
   created_at: string;
   last_started?: string;
   environment_vars: Record<string, string>;
+  /** Host port RCON is published on, game port + 10 when missing */
+  rcon_port?: number;
+  resources?: ServerResourceLimits;
+  /** The most recent version upgrade, kept for rolling it back */
+  last_upgrade?: ServerUpgradeRecord;
+}
+
+export interface ServerUpgradeRecord {
+  previous_version: string;
+  previous_image: string;
+  minecraft_version: string;
+  /** Backup of the data folder taken before the upgrade, relative to it */
+  backup: string;
+  upgraded_at: string;
+}
+
+/** Container limits of a server, missing ones are unlimited */
+export interface ServerResourceLimits {
+  memory_limit_mb?: number;
+  memory_reservation_mb?: number;
+  /** Cores the container may use, e.g. 1.5 */
+  cpus?: number;
+}
+
+export interface ServerResourcesUpdate {
+  resources: ServerResourceLimits;
+  /** Removing a limit replaces the container, a running server is restarted */
+  recreated: boolean;
+  warnings: string[];
 }
 
 export type ServerStatus = 
@@ -35,19 +93,56 @@ export interface ServerDeploymentRequest {
   minecraft_instance_id: string;
   docker_connection_id: string;
   port: number;
+  /** Take the next free port of the configured range when `port` is taken */
+  auto_assign_port?: boolean;
   max_players: number;
   memory_limit: number;
   environment_vars: Record<string, string>;
   auto_start: boolean;
+  /** Id from `get_server_templates`, vanilla when left out */
+  template_id?: string;
+  /** Overrides the instance's Minecraft version */
+  minecraft_version?: string;
+  resources?: ServerResourceLimits;
+}
+
+export type ServerType = 'vanilla' | 'paper' | 'purpur' | 'fabric' | 'forge';
+
+export interface ServerTemplate {
+  id: string;
+  name: string;
+  description: string;
+  server_type: ServerType;
+  /** Image repository, the tag follows the Minecraft version */
+  image: string;
+  recommended_memory_mb: number;
+  env: Record<string, string>;
+}
+
+/** Result of `update_server_properties` */
+export interface ServerPropertiesUpdate {
+  properties: Record<string, string>;
+  changed: string[];
+  /** A changed key only takes effect after a restart, e.g. server-port or level-name */
+  needs_restart: boolean;
+  warnings: string[];
 }
 
 export interface ServerStats {
+  /** Percent of one core, above 100 when several are busy */
   cpu_usage: number;
   memory_usage: number;
+  /** Bytes, the host's memory when the container has no limit */
   memory_limit: number;
   network_in: number;
   network_out: number;
   uptime: number;
+  online_players?: string[];
+  resources?: ServerResourceLimits;
+  /** Cores the container may use */
+  cpu_cores?: number;
+  heap_mb?: number;
+  warnings?: string[];
 }
 
 export interface LogEntry {
@@ -56,10 +151,135 @@ export interface LogEntry {
   message: string;
 }
 
+/** Payload of `server_log`, one console line of a followed server */
+export interface ServerLogLine {
+  server_id: string;
+  line: string;
+  timestamp: string;
+  stream: 'stdout' | 'stderr';
+  level: LogEntry['level'];
+  /** Lines dropped since the previous event because the view fell behind */
+  dropped: number;
+}
+
+export interface ServerLogStreamEnded {
+  server_id: string;
+  /** Set when the connection dropped rather than the server stopping */
+  error?: string | null;
+}
+
 export interface DockerImage {
   id: string;
   repository: string;
   tag: string;
   size: number;
   created: string;
-}
\ No newline at end of file
+}
+
+export interface DockerError {
+  kind: 'host_unreachable' | 'timeout' | 'cancelled' | 'certificate' | 'network' | 'other';
+  message: string;
+  operation?: string;
+  seconds?: number;
+}
+
+export interface PullProgress {
+  image: string;
+  layer: string;
+  status: string;
+  current?: number;
+  total?: number;
+  layers_done: number;
+  layers_total: number;
+  percent: number;
+}
+
+export type WorldUploadStage = 'compressing' | 'transferring' | 'extracting';
+
+export interface WorldUploadProgress {
+  stage: WorldUploadStage;
+  /** Bytes compressed or sent so far */
+  current: number;
+  total: number;
+  /** Progress of the whole upload, 0 to 100 */
+  percent: number;
+}
+
+export interface WorldUploadProgressEvent {
+  task_id: string;
+  server_id: string;
+  progress: WorldUploadProgress;
+}
+
+export interface WorldUploadResult {
+  /** Folder the world now lives in on the server */
+  level_name: string;
+  /** Archive of the replaced world, relative to the server's data folder */
+  backup?: string;
+  restarted: boolean;
+}
+
+export type ServerUpgradeStage = 'backup' | 'pull' | 'recreate' | 'verify' | 'roll_back';
+
+export interface ServerUpgradeProgress {
+  stage: ServerUpgradeStage;
+  /** Progress of the whole upgrade, 0 to 100 */
+  percent: number;
+  /** Image download while the stage is `pull` */
+  pull?: PullProgress;
+}
+
+export interface ServerUpgradeProgressEvent {
+  task_id: string;
+  server_id: string;
+  progress: ServerUpgradeProgress;
+}
+
+export interface ServerUpgradeResult {
+  previous_version: string;
+  minecraft_version: string;
+  image: string;
+  /** Backup of the data folder, relative to the server's data folder */
+  backup: string;
+}
+
+/** A player to list, the UUID is looked up from the name when missing */
+export interface PlayerRef {
+  name: string;
+  uuid?: string;
+}
+
+export interface OpRef extends PlayerRef {
+  /** 1 to 4, 4 when missing */
+  level?: number;
+  bypasses_player_limit?: boolean;
+}
+
+export interface WhitelistEntry {
+  uuid: string;
+  name: string;
+}
+
+export interface OpEntry extends WhitelistEntry {
+  level: number;
+  bypasses_player_limit: boolean;
+}
+
+export interface ServerPlayersConfig {
+  whitelist: WhitelistEntry[];
+  ops: OpEntry[];
+}
+
+export interface ServerPlayersUpdate {
+  config: ServerPlayersConfig;
+  /** The running server took the changes over RCON */
+  applied_live: boolean;
+  warnings: string[];
+}
+
+export const dockerErrorMessage = (error: unknown): string => {
+  if (error && typeof error === 'object' && 'message' in error) {
+    return String((error as DockerError).message);
+  }
+  return String(error);
+};